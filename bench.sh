@@ -6,29 +6,164 @@ set -u
 COUNT=${1:-3}
 FILTER=${2:-''}
 
-echo "Benchmarking with:" >&2
-echo "- COUNT=$COUNT"     >&2
-echo "- FILTER='$FILTER'" >&2
-echo                      >&2
+# Results are written as CSV to OUTPUT. If BASELINE names the OUTPUT of a previous run, the results
+# are compared against it: unique state counts must match, and throughput (best states/sec across
+# runs) and peak memory must not regress by more than TOLERANCE. Runs shorter than MIN_SEC are
+# dominated by process startup and the checker's 1-second reporting interval, so their throughput
+# is recorded but not compared.
+OUTPUT=${OUTPUT:-bench_output.csv}
+BASELINE=${BASELINE:-''}
+TOLERANCE=${TOLERANCE:-0.1}
+MIN_SEC=${MIN_SEC:-3}
+
+REV=$(git rev-parse --short HEAD 2>/dev/null || echo unknown)
+if [[ -n $(git status --porcelain --untracked-files=no 2>/dev/null) ]]; then
+    REV="$REV-dirty"
+fi
+VERSION=$(grep -m1 '^version' Cargo.toml | cut -d'"' -f2)
+
+echo "Benchmarking with:"              >&2
+echo "- COUNT=$COUNT"                  >&2
+echo "- FILTER='$FILTER'"              >&2
+echo "- OUTPUT='$OUTPUT'"              >&2
+echo "- BASELINE='$BASELINE'"          >&2
+echo "- REV=$REV (version $VERSION)"   >&2
+echo                                   >&2
+
+cargo build -q --release --examples
+
+# Peak memory is measured with `time`, which reports kilobytes on Linux and bytes on macOS. It is
+# left blank if neither variant is available.
+TIME=()
+if /usr/bin/time -f 'peak_kb=%M' true >/dev/null 2>&1; then
+    TIME=(/usr/bin/time -f 'peak_kb=%M')
+elif /usr/bin/time -l true >/dev/null 2>&1; then
+    TIME=(/usr/bin/time -l)
+fi
+
+now() {
+    if [[ -n ${EPOCHREALTIME:-} ]]; then
+        echo "${EPOCHREALTIME/,/.}"
+    else
+        date +%s
+    fi
+}
+
+TMP=$(mktemp -d)
+trap 'rm -rf "$TMP"' EXIT
+
+echo "rev,version,example,args,run,states,unique,depth,sec,states_per_sec,peak_kb" > "$OUTPUT"
 
 # USAGE: bench EXAMPLE ARGS...
 # EXAMPLE: bench 2pc check 9
-# 
+#
 # No-op if EXAMPLE does not match FILTER.
 bench() {
     if [[ $1 == *"$FILTER"* ]]; then
-        echo "== $@ =="
+        echo "== $@ ==" >&2
+        local example=$1
+        shift
         for i in $(seq $COUNT); do
-            cargo -q run --release --example "$@" |grep "sec="
+            local start=$(now)
+            ${TIME[@]+"${TIME[@]}"} "target/release/examples/$example" "$@" \
+                > "$TMP/stdout" 2> "$TMP/stderr"
+            local end=$(now)
+            local done_line=$(grep "^Done\." "$TMP/stdout")
+            local peak_kb=$(awk '
+                /^peak_kb=/ { split($0, a, "="); print a[2] }
+                /maximum resident set size/ { print int($1 / 1024) }' "$TMP/stderr")
+            echo "$done_line" | awk -F'[=, ]+' \
+                -v rev="$REV" -v version="$VERSION" -v example="$example" -v args="$*" \
+                -v run="$i" -v start="$start" -v end="$end" -v peak_kb="$peak_kb" '{
+                    sec = end - start
+                    printf "%s,%s,%s,%s,%d,%d,%d,%d,%.3f,%d,%s\n",
+                        rev, version, example, args, run, $3, $5, $7, sec, $3 / sec, peak_kb
+                }' | tee -a "$OUTPUT" >&2
         done
     fi
 }
 
+bench 2pc check 8
 bench 2pc check 10
+bench 2pc check-sym 10
+bench 2pl check --wound-wait
+bench bakery check 2
+bench bakery check 3
+bench broadcast check urb 3
+bench broadcast check urb 4
+bench chain-replication check 1
+bench chain-replication check 2
+bench crdt check counter 3
+bench crdt check counter 4
+bench crdt check set
+bench dining-philosophers check 5
+bench dining-philosophers check 9 --ordered
+bench hierarchical-consensus check 3
+bench increment check 6
+bench increment_lock check 6
+bench lease-lock check
+bench linearizable-register check 1
+bench linearizable-register check 2
+bench mvcc check --validate-reads
+bench paxos check 3
 bench paxos check 6
+bench pbft check 1
+bench raft check 1 1
+bench ring-election check 4
+bench ring-election check 8
+bench sharded-kv check 3
+bench sharded-kv check 4
+bench single-copy-register check 3
 bench single-copy-register check 4
-bench linearizable-register check 2
+bench write-once-register check 2
 if [[ "$FILTER" == '' ]]; then
-    # This test takes particularly long so it's only run when the filter is empty.
+    # These tests take particularly long so they're only run when the filter is empty.
     bench linearizable-register check 3 ordered
+    bench multipaxos-kv check 1
+fi
+
+echo >&2
+echo "Results written to '$OUTPUT'." >&2
+
+if [[ -n "$BASELINE" ]]; then
+    echo "Comparing against '$BASELINE':" >&2
+    awk -F, -v tolerance="$TOLERANCE" -v min_sec="$MIN_SEC" '
+        FNR == 1 { next }
+        {
+            key = $3 " " $4
+            if ($9 >= min_sec && $10 > sps[FILENAME, key]) sps[FILENAME, key] = $10
+            if ($11 != "" && $11 > peak[FILENAME, key]) peak[FILENAME, key] = $11
+            unique[FILENAME, key] = $7
+        }
+        NR == FNR { baseline = FILENAME; next }
+        !(key in seen) { seen[key] = 1; keys[++count] = key; current = FILENAME }
+        END {
+            status = 0
+            for (i = 1; i <= count; i++) {
+                key = keys[i]
+                if (!((baseline, key) in unique)) {
+                    printf "  NEW        %s\n", key
+                    continue
+                }
+                if (unique[current, key] != unique[baseline, key]) {
+                    printf "  CHANGED    %s: unique states %d -> %d\n",
+                        key, unique[baseline, key], unique[current, key]
+                    status = 1
+                }
+                before = sps[baseline, key]; after = sps[current, key]
+                if (before > 0 && after > 0 && after < (1 - tolerance) * before) {
+                    printf "  SLOWER     %s: states/sec %d -> %d (%.1f%%)\n",
+                        key, before, after, 100 * (after - before) / before
+                    status = 1
+                }
+                before = peak[baseline, key]; after = peak[current, key]
+                if (before > 0 && after > 0 && after > (1 + tolerance) * before) {
+                    printf "  LARGER     %s: peak KB %d -> %d (+%.1f%%)\n",
+                        key, before, after, 100 * (after - before) / before
+                    status = 1
+                }
+            }
+            if (status == 0) print "  No regressions."
+            exit status
+        }' "$BASELINE" "$OUTPUT" >&2
 fi
@@ -2,6 +2,7 @@
 //! ["Consensus on Transaction Commit"](https://www.microsoft.com/en-us/research/wp-content/uploads/2016/02/tr-2003-96.pdf)
 //! by Jim Gray and Leslie Lamport.
 
+use stateright::cli::{self, System};
 use stateright::report::WriteReporter;
 use stateright::{Checker, Model, Property, Representative, Rewrite, RewritePlan};
 use std::collections::BTreeSet;
@@ -146,6 +147,35 @@ impl Model for TwoPhaseSys {
     }
 }
 
+// Implementing this trait enables symmetry reduction to speed up model checking (optional).
+impl Representative for TwoPhaseState {
+    fn representative(&self) -> Self {
+        let plan = RewritePlan::from_values_to_sort(&self.rm_state);
+        Self {
+            rm_state: plan.reindex(&self.rm_state),
+            tm_state: self.tm_state.clone(),
+            tm_prepared: plan.reindex(&self.tm_prepared),
+            msgs: self
+                .msgs
+                .iter()
+                .map(|m| match m {
+                    Message::Prepared { rm } => Message::Prepared {
+                        rm: plan.rewrite(rm),
+                    },
+                    Message::Commit => Message::Commit,
+                    Message::Abort => Message::Abort,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<T> Rewrite<T> for RmState {
+    fn rewrite<S>(&self, _: &RewritePlan<T, S>) -> Self {
+        self.clone()
+    }
+}
+
 #[cfg(test)]
 #[test]
 fn can_model_2pc() {
@@ -169,85 +199,43 @@ fn can_model_2pc() {
     checker.assert_properties();
 }
 
+impl System for TwoPhaseSys {
+    type Model = Self;
+    type Actor = ();
+
+    fn description(&self) -> String {
+        format!("two phase commit with {} resource managers", self.rms.len())
+    }
+
+    fn model(&self) -> Self::Model {
+        self.clone()
+    }
+}
+
 fn main() -> Result<(), pico_args::Error> {
     env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
 
+    // Symmetry reduction requires `Representative` states, so it is not offered by `cli`.
     let mut args = pico_args::Arguments::from_env();
-    match args.subcommand()?.as_deref() {
-        Some("check") => {
-            let rm_count = args.opt_free_from_str()?.unwrap_or(2);
-            println!(
-                "Checking two phase commit with {} resource managers.",
-                rm_count
-            );
-            TwoPhaseSys { rms: 0..rm_count }
-                .checker()
-                .threads(num_cpus::get())
-                .spawn_dfs()
-                .report(&mut WriteReporter::new(&mut std::io::stdout()));
-        }
-        Some("check-sym") => {
-            let rm_count = args.opt_free_from_str()?.unwrap_or(2);
-            println!(
-                "Checking two phase commit with {} resource managers using symmetry reduction.",
-                rm_count
-            );
-            TwoPhaseSys { rms: 0..rm_count }
-                .checker()
-                .threads(num_cpus::get())
-                .symmetry()
-                .spawn_dfs()
-                .report(&mut WriteReporter::new(&mut std::io::stdout()));
-
-            // Implementing this trait enables symmetry reduction to speed up model checking (optional).
-            impl Representative for TwoPhaseState {
-                fn representative(&self) -> Self {
-                    let plan = RewritePlan::from_values_to_sort(&self.rm_state);
-                    Self {
-                        rm_state: plan.reindex(&self.rm_state),
-                        tm_state: self.tm_state.clone(),
-                        tm_prepared: plan.reindex(&self.tm_prepared),
-                        msgs: self
-                            .msgs
-                            .iter()
-                            .map(|m| match m {
-                                Message::Prepared { rm } => Message::Prepared {
-                                    rm: plan.rewrite(rm),
-                                },
-                                Message::Commit => Message::Commit,
-                                Message::Abort => Message::Abort,
-                            })
-                            .collect(),
-                    }
-                }
-            }
-            impl<T> Rewrite<T> for RmState {
-                fn rewrite<S>(&self, _: &RewritePlan<T, S>) -> Self {
-                    self.clone()
-                }
-            }
-        }
-        Some("explore") => {
-            let rm_count = args.opt_free_from_str()?.unwrap_or(2);
-            let address = args
-                .opt_free_from_str()?
-                .unwrap_or("localhost:3000".to_string());
-            println!(
-                "Exploring state space for two phase commit with {} resource managers on {}.",
-                rm_count, address
-            );
-            TwoPhaseSys { rms: 0..rm_count }
-                .checker()
-                .threads(num_cpus::get())
-                .serve(address);
-        }
-        _ => {
-            println!("USAGE:");
-            println!("  ./2pc check [RESOURCE_MANAGER_COUNT]");
-            println!("  ./2pc check-sym [RESOURCE_MANAGER_COUNT]");
-            println!("  ./2pc explore [RESOURCE_MANAGER_COUNT] [ADDRESS]");
-        }
+    if args.subcommand()?.as_deref() == Some("check-sym") {
+        let rm_count = args.opt_free_from_str()?.unwrap_or(2);
+        println!(
+            "Checking two phase commit with {} resource managers using symmetry reduction.",
+            rm_count
+        );
+        TwoPhaseSys { rms: 0..rm_count }
+            .checker()
+            .threads(num_cpus::get())
+            .symmetry()
+            .spawn_dfs()
+            .report(&mut WriteReporter::new(&mut std::io::stdout()));
+        return Ok(());
     }
 
+    cli::run(|params| {
+        Ok(TwoPhaseSys {
+            rms: 0..params.next_or("RESOURCE_MANAGER_COUNT", 2)?,
+        })
+    });
     Ok(())
 }
@@ -0,0 +1,397 @@
+//! This is an implementation of distributed transactions that use [two-phase
+//! locking](https://en.wikipedia.org/wiki/Two-phase_locking) (2PL) for concurrency control and
+//! [two-phase commit](https://en.wikipedia.org/wiki/Two-phase_commit_protocol) (2PC) for atomic
+//! commitment.
+//!
+//! # The Algorithm
+//!
+//! Each key is managed by a resource manager, which grants an exclusive lock on its key to one
+//! transaction at a time, queueing other requests. A transaction acquires the lock on each key
+//! that it accesses, reading the committed value along the way. Once it holds every lock, the
+//! transaction acts as the 2PC coordinator: it asks each resource manager to prepare its write, and
+//! if all vote to commit, it tells each to commit, at which point the resource managers apply the
+//! prepared writes and release the locks.
+//!
+//! # Deadlock
+//!
+//! Transactions that acquire locks in different orders can each hold a lock that the other is
+//! waiting for, in which case neither can make progress. The model reaches a terminal state that
+//! violates a [`Expectation::Terminal`] property.
+//!
+//! A resource manager can optionally employ the wound-wait policy: when an older transaction (with
+//! a smaller [`Id`]) requests a lock held by a younger one that has not yet prepared, the resource
+//! manager "wounds" the younger transaction, which aborts and releases its locks. A younger
+//! transaction waits for an older one, so there are no cycles of waiting transactions.
+
+use stateright::actor::{Actor, ActorModel, Envelope, Id, Network, Out};
+use stateright::report::WriteReporter;
+use stateright::semantics::transactional_kv::{TransactionalKv, TxnOp, TxnRet};
+use stateright::semantics::{ConsistencyTester, SerializabilityTester};
+use stateright::{Checker, Expectation, Model};
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+type Key = char;
+type Value = u8;
+type Ops = Vec<TxnOp<Key, Value>>;
+type Rets = Vec<TxnRet<Value>>;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum TxnMsg {
+    Acquire,
+    Granted(Value),
+    /// Includes the value to write upon commit.
+    Prepare(Value),
+    Vote(bool),
+    /// Includes the transaction's operations to record them in the history.
+    Commit(Ops, Rets),
+    Abort,
+    Wound,
+}
+use TxnMsg::*;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum TxnPhase {
+    Acquiring { next: usize },
+    Preparing { votes: usize },
+    Committed,
+    Aborted,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum TplState {
+    ResourceManager {
+        value: Value,
+        holder: Option<Id>,
+        /// The holder's prepared write.
+        pending: Option<Value>,
+        prepared: bool,
+        waiting: Vec<Id>,
+    },
+    Txn {
+        phase: TxnPhase,
+        ops: Ops,
+        rets: Rets,
+    },
+}
+
+impl TplState {
+    fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            TplState::ResourceManager { .. }
+                | TplState::Txn {
+                    phase: TxnPhase::Committed | TxnPhase::Aborted,
+                    ..
+                }
+        )
+    }
+}
+
+#[derive(Clone)]
+enum TplActor {
+    ResourceManager {
+        wound_wait: bool,
+    },
+    Txn {
+        /// The keys to access, in order, along with their resource managers.
+        plan: Vec<(Key, Id)>,
+    },
+}
+
+impl TplActor {
+    fn abort(plan: &[(Key, Id)], o: &mut Out<Self>) -> TxnPhase {
+        for (_, rm_id) in plan {
+            o.send(*rm_id, Abort);
+        }
+        TxnPhase::Aborted
+    }
+}
+
+impl Actor for TplActor {
+    type Msg = TxnMsg;
+    type State = TplState;
+    type Timer = ();
+
+    fn name(&self) -> String {
+        match self {
+            TplActor::ResourceManager { .. } => "Resource Manager".to_owned(),
+            TplActor::Txn { .. } => "Transaction".to_owned(),
+        }
+    }
+
+    fn on_start(&self, _id: Id, o: &mut Out<Self>) -> Self::State {
+        match self {
+            TplActor::ResourceManager { .. } => TplState::ResourceManager {
+                value: 0,
+                holder: None,
+                pending: None,
+                prepared: false,
+                waiting: Vec::new(),
+            },
+            TplActor::Txn { plan } => {
+                o.send(plan[0].1, Acquire);
+                TplState::Txn {
+                    phase: TxnPhase::Acquiring { next: 0 },
+                    ops: Vec::new(),
+                    rets: Vec::new(),
+                }
+            }
+        }
+    }
+
+    fn on_msg(
+        &self,
+        _id: Id,
+        state: &mut Cow<Self::State>,
+        src: Id,
+        msg: Self::Msg,
+        o: &mut Out<Self>,
+    ) {
+        match (self, &**state) {
+            (
+                TplActor::ResourceManager { wound_wait },
+                TplState::ResourceManager {
+                    value,
+                    holder,
+                    prepared,
+                    waiting,
+                    ..
+                },
+            ) => {
+                let is_holder = *holder == Some(src);
+                match msg {
+                    Acquire if holder.is_none() => {
+                        o.send(src, Granted(*value));
+                        if let TplState::ResourceManager { holder, .. } = state.to_mut() {
+                            *holder = Some(src);
+                        }
+                    }
+                    Acquire => {
+                        if *wound_wait && src < holder.unwrap() && !prepared {
+                            o.send(holder.unwrap(), Wound);
+                        }
+                        if let TplState::ResourceManager { waiting, .. } = state.to_mut() {
+                            waiting.push(src);
+                        }
+                    }
+                    Prepare(value) => {
+                        o.send(src, Vote(is_holder));
+                        if is_holder {
+                            if let TplState::ResourceManager {
+                                pending, prepared, ..
+                            } = state.to_mut()
+                            {
+                                *pending = Some(value);
+                                *prepared = true;
+                            }
+                        }
+                    }
+                    Commit(..) | Abort if is_holder => {
+                        let is_commit = matches!(msg, Commit(..));
+                        if let TplState::ResourceManager {
+                            value,
+                            holder,
+                            pending,
+                            prepared,
+                            waiting,
+                        } = state.to_mut()
+                        {
+                            if is_commit {
+                                *value = pending.unwrap_or(*value);
+                            }
+                            *pending = None;
+                            *prepared = false;
+                            *holder = None;
+                            if !waiting.is_empty() {
+                                let next = waiting.remove(0);
+                                o.send(next, Granted(*value));
+                                *holder = Some(next);
+                            }
+                        }
+                    }
+                    Abort if waiting.contains(&src) => {
+                        if let TplState::ResourceManager { waiting, .. } = state.to_mut() {
+                            waiting.retain(|id| *id != src);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            (TplActor::Txn { plan }, TplState::Txn { phase, ops, rets }) => {
+                let (mut ops, mut rets) = (ops.clone(), rets.clone());
+                let phase = match (phase, msg) {
+                    (&TxnPhase::Acquiring { next }, Granted(value)) if src == plan[next].1 => {
+                        // Increments the value of each key.
+                        let key = plan[next].0;
+                        ops.extend([TxnOp::Read(key), TxnOp::Write(key, value + 1)]);
+                        rets.extend([TxnRet::ReadOk(Some(value)), TxnRet::WriteOk]);
+                        if next + 1 < plan.len() {
+                            o.send(plan[next + 1].1, Acquire);
+                            TxnPhase::Acquiring { next: next + 1 }
+                        } else {
+                            let writes = ops.iter().filter_map(|op| match op {
+                                TxnOp::Write(_, value) => Some(*value),
+                                TxnOp::Read(_) => None,
+                            });
+                            for ((_, rm_id), value) in plan.iter().zip(writes) {
+                                o.send(*rm_id, Prepare(value));
+                            }
+                            TxnPhase::Preparing { votes: 0 }
+                        }
+                    }
+                    (&TxnPhase::Preparing { votes }, Vote(true)) => {
+                        if votes + 1 < plan.len() {
+                            TxnPhase::Preparing { votes: votes + 1 }
+                        } else {
+                            for (_, rm_id) in plan {
+                                o.send(*rm_id, Commit(ops.clone(), rets.clone()));
+                            }
+                            TxnPhase::Committed
+                        }
+                    }
+                    (
+                        TxnPhase::Acquiring { .. } | TxnPhase::Preparing { .. },
+                        Vote(false) | Wound,
+                    ) => Self::abort(plan, o),
+                    _ => return,
+                };
+                *state.to_mut() = TplState::Txn { phase, ops, rets };
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct TxnHistory {
+    serializability: SerializabilityTester<Id, TransactionalKv<Key, Value>>,
+    committed: BTreeSet<Id>,
+}
+
+/// Records a transaction upon its first `Commit` message, which is sent once the outcome is
+/// decided.
+fn record_commits(
+    _cfg: &TplModelCfg,
+    history: &TxnHistory,
+    env: Envelope<&TxnMsg>,
+) -> Option<TxnHistory> {
+    let Commit(ops, rets) = env.msg else {
+        return None;
+    };
+    if history.committed.contains(&env.src) {
+        return None;
+    }
+    let mut history = history.clone();
+    let _ = history.serializability.on_invoke(env.src, ops.clone());
+    let _ = history.serializability.on_return(env.src, rets.clone());
+    history.committed.insert(env.src);
+    Some(history)
+}
+
+#[derive(Clone)]
+struct TplModelCfg {
+    wound_wait: bool,
+}
+
+impl TplModelCfg {
+    fn into_model(self) -> ActorModel<TplActor, Self, TxnHistory> {
+        let keys = ['x', 'y'];
+        let rm_ids = [Id::from(0), Id::from(1)];
+        let init = keys.iter().map(|k| (*k, 0)).collect();
+        ActorModel::new(
+            self.clone(),
+            TxnHistory {
+                serializability: SerializabilityTester::new(TransactionalKv(init)),
+                committed: Default::default(),
+            },
+        )
+        .actors(rm_ids.iter().map(|_| TplActor::ResourceManager {
+            wound_wait: self.wound_wait,
+        }))
+        // The transactions access the keys in opposite orders.
+        .actor(TplActor::Txn {
+            plan: vec![(keys[0], rm_ids[0]), (keys[1], rm_ids[1])],
+        })
+        .actor(TplActor::Txn {
+            plan: vec![(keys[1], rm_ids[1]), (keys[0], rm_ids[0])],
+        })
+        .init_network(Network::new_unordered_nonduplicating([]))
+        .property(Expectation::Always, "serializable", |_, state| {
+            state.history.serializability.serialized_history().is_some()
+        })
+        .property(Expectation::Terminal, "deadlock free", |_, state| {
+            state.actor_states.iter().all(|s| s.is_finished())
+        })
+        .property(Expectation::Sometimes, "all committed", |_, state| {
+            state.history.committed.len() == 2
+        })
+        .record_msg_out(record_commits)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn can_model_2pl() {
+    // Transactions that acquire locks in different orders can deadlock.
+    let checker = TplModelCfg { wound_wait: false }
+        .into_model()
+        .checker()
+        .spawn_bfs()
+        .join();
+    checker.assert_no_discovery("serializable");
+    checker.assert_any_discovery("deadlock free");
+    assert_eq!(checker.unique_state_count(), 98);
+
+    // Wound-wait prevents deadlock.
+    let checker = TplModelCfg { wound_wait: true }
+        .into_model()
+        .checker()
+        .spawn_bfs()
+        .join();
+    checker.assert_properties();
+    assert_eq!(checker.unique_state_count(), 651);
+}
+
+fn main() -> Result<(), pico_args::Error> {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    let mut args = pico_args::Arguments::from_env();
+    let wound_wait = args.contains("--wound-wait");
+    match args.subcommand()?.as_deref() {
+        Some("check") => {
+            println!(
+                "Model checking two-phase locking{}.",
+                if wound_wait { " with wound-wait" } else { "" }
+            );
+            TplModelCfg { wound_wait }
+                .into_model()
+                .checker()
+                .threads(num_cpus::get())
+                .spawn_dfs()
+                .report(&mut WriteReporter::new(&mut std::io::stdout()));
+        }
+        Some("explore") => {
+            let address = args
+                .opt_free_from_str()?
+                .unwrap_or("localhost:3000".to_string());
+            println!(
+                "Exploring state space for two-phase locking{} on {}.",
+                if wound_wait { " with wound-wait" } else { "" },
+                address
+            );
+            TplModelCfg { wound_wait }
+                .into_model()
+                .checker()
+                .threads(num_cpus::get())
+                .serve(address);
+        }
+        _ => {
+            println!("USAGE:");
+            println!("  ./2pl check [--wound-wait]");
+            println!("  ./2pl explore [ADDRESS] [--wound-wait]");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,238 @@
+//! This is an implementation of [Lamport's bakery
+//! algorithm](https://en.wikipedia.org/wiki/Lamport%27s_bakery_algorithm), which provides mutual
+//! exclusion for threads that communicate via shared memory using only reads and writes.
+//!
+//! # The Algorithm
+//!
+//! A thread that wants to enter its critical section takes a ticket numbered one higher than the
+//! highest ticket it observes, reading the other threads' tickets one at a time. It then waits
+//! until every other thread either has no ticket or has a larger one (with ties broken by thread
+//! index), at which point it enters its critical section. Upon exiting the critical section, the
+//! thread discards its ticket.
+//!
+//! Two threads can take the same ticket, so each thread also raises a `choosing` flag while it
+//! takes a ticket, and other threads wait until the flag is lowered before comparing tickets.
+//! Without the flag, a thread with a smaller index can take its ticket after a thread with a
+//! larger index has already compared tickets, and both enter the critical section.
+//!
+//! # Bounded Tickets
+//!
+//! Ticket numbers can grow without bound, so the model only explores states in which tickets are
+//! at most a configurable maximum.
+
+use stateright::{report::WriteReporter, *};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// A specified thread executes its next atomic instruction.
+    Step(usize),
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq)]
+enum Pc {
+    /// Raises the `choosing` flag.
+    Enter,
+    /// Reads the ticket of thread `j`, tracking the maximum so far.
+    Scan { j: usize, max: u8 },
+    /// Writes one more than the maximum observed ticket.
+    TakeTicket { max: u8 },
+    /// Lowers the `choosing` flag.
+    Chosen,
+    /// Waits until thread `j` is not choosing a ticket.
+    AwaitChoosing { j: usize },
+    /// Waits until thread `j` has no ticket or a larger one.
+    AwaitTicket { j: usize },
+    /// Executes the critical section and then discards the ticket.
+    Critical,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq)]
+pub struct State {
+    choosing: Vec<bool>,
+    tickets: Vec<u8>,
+    pcs: Vec<Pc>,
+}
+
+#[derive(Clone)]
+pub struct Bakery {
+    thread_count: usize,
+    max_ticket: u8,
+    /// Whether threads raise a flag while choosing a ticket.
+    choosing: bool,
+}
+
+impl Bakery {
+    /// Indicates whether a thread can execute its next instruction, as opposed to waiting.
+    fn is_enabled(&self, state: &State, i: usize) -> bool {
+        match state.pcs[i] {
+            Pc::AwaitChoosing { j } => !state.choosing[j],
+            Pc::AwaitTicket { j } => {
+                state.tickets[j] == 0 || (state.tickets[i], i) <= (state.tickets[j], j)
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns the instruction after checking thread `j` while waiting.
+    fn after_await(&self, j: usize) -> Pc {
+        if j + 1 < self.thread_count {
+            Pc::AwaitChoosing { j: j + 1 }
+        } else {
+            Pc::Critical
+        }
+    }
+}
+
+impl Model for Bakery {
+    type State = State;
+    type Action = Action;
+
+    fn init_states(&self) -> Vec<Self::State> {
+        vec![State {
+            choosing: vec![false; self.thread_count],
+            tickets: vec![0; self.thread_count],
+            pcs: vec![Pc::Enter; self.thread_count],
+        }]
+    }
+
+    fn actions(&self, state: &Self::State, actions: &mut Vec<Self::Action>) {
+        for i in 0..self.thread_count {
+            if self.is_enabled(state, i) {
+                actions.push(Action::Step(i));
+            }
+        }
+    }
+
+    fn next_state(&self, last_state: &Self::State, action: Self::Action) -> Option<Self::State> {
+        let Action::Step(i) = action;
+        let mut state = last_state.clone();
+        state.pcs[i] = match last_state.pcs[i] {
+            Pc::Enter => {
+                state.choosing[i] = self.choosing;
+                Pc::Scan { j: 0, max: 0 }
+            }
+            Pc::Scan { j, max } => {
+                let max = max.max(last_state.tickets[j]);
+                if j + 1 < self.thread_count {
+                    Pc::Scan { j: j + 1, max }
+                } else {
+                    Pc::TakeTicket { max }
+                }
+            }
+            Pc::TakeTicket { max } => {
+                state.tickets[i] = max + 1;
+                Pc::Chosen
+            }
+            Pc::Chosen => {
+                state.choosing[i] = false;
+                Pc::AwaitChoosing { j: 0 }
+            }
+            Pc::AwaitChoosing { j } => Pc::AwaitTicket { j },
+            Pc::AwaitTicket { j } => self.after_await(j),
+            Pc::Critical => {
+                state.tickets[i] = 0;
+                Pc::Enter
+            }
+        };
+        Some(state)
+    }
+
+    fn properties(&self) -> Vec<Property<Self>> {
+        vec![
+            Property::<Self>::always("mutual exclusion", |_, state| {
+                state.pcs.iter().filter(|pc| **pc == Pc::Critical).count() <= 1
+            }),
+            Property::<Self>::sometimes("critical section reached", |_, state| {
+                state.pcs.contains(&Pc::Critical)
+            }),
+        ]
+    }
+
+    fn within_boundary(&self, state: &Self::State) -> bool {
+        state
+            .tickets
+            .iter()
+            .all(|ticket| *ticket <= self.max_ticket)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn can_model_bakery() {
+    let checker = Bakery {
+        thread_count: 2,
+        max_ticket: 3,
+        choosing: true,
+    }
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_properties();
+    assert_eq!(checker.unique_state_count(), 393);
+
+    // Without the `choosing` flag, threads can take the same ticket unnoticed.
+    let checker = Bakery {
+        thread_count: 2,
+        max_ticket: 3,
+        choosing: false,
+    }
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_any_discovery("mutual exclusion");
+}
+
+fn main() -> Result<(), pico_args::Error> {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    let mut args = pico_args::Arguments::from_env();
+    let choosing = !args.contains("--no-choosing");
+    match args.subcommand()?.as_deref() {
+        Some("check") => {
+            let thread_count = args.opt_free_from_str()?.unwrap_or(3);
+            println!(
+                "Model checking the bakery algorithm with {} threads{}.",
+                thread_count,
+                if choosing {
+                    ""
+                } else {
+                    " and no choosing flag"
+                }
+            );
+            Bakery {
+                thread_count,
+                max_ticket: 3,
+                choosing,
+            }
+            .checker()
+            .threads(num_cpus::get())
+            .spawn_dfs()
+            .report(&mut WriteReporter::new(&mut std::io::stdout()));
+        }
+        Some("explore") => {
+            let thread_count = args.opt_free_from_str()?.unwrap_or(3);
+            let address = args
+                .opt_free_from_str()?
+                .unwrap_or("localhost:3000".to_string());
+            println!(
+                "Exploring the state space of the bakery algorithm with {} threads on {}.",
+                thread_count, address
+            );
+            Bakery {
+                thread_count,
+                max_ticket: 3,
+                choosing,
+            }
+            .checker()
+            .threads(num_cpus::get())
+            .serve(address);
+        }
+        _ => {
+            println!("USAGE:");
+            println!("  ./bakery check [THREAD_COUNT] [--no-choosing]");
+            println!("  ./bakery explore [THREAD_COUNT] [ADDRESS] [--no-choosing]");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,331 @@
+//! This is an implementation of the broadcast abstractions described in "[Introduction to Reliable
+//! and Secure Distributed Programming](https://link.springer.com/book/10.1007/978-3-642-15260-3)"
+//! by Cachin, Guerraoui, and Rodrigues, each layered on the previous one.
+//!
+//! # Best-Effort Broadcast
+//!
+//! A process broadcasts a message by sending it to every process over a perfect link. Every
+//! correct process delivers the messages that a correct process broadcasts (_validity_), but if the
+//! broadcaster crashes partway through, some processes may deliver the message while others never
+//! do.
+//!
+//! # (Eager) Reliable Broadcast
+//!
+//! A process that delivers a message for the first time also relays it via best-effort broadcast,
+//! so if any correct process delivers a message, every correct process does (_agreement_). A
+//! process can still deliver a message and then crash before anyone else receives it.
+//!
+//! # (Majority-Ack) Uniform Reliable Broadcast
+//!
+//! A process relays every message it receives, but only delivers a message once it has received it
+//! from a majority of processes. If any process delivers a message, even one that subsequently
+//! crashes, then a correct process relayed it, so every correct process delivers it (_uniform
+//! agreement_). This requires a majority of processes to be correct.
+//!
+//! # Crashes
+//!
+//! Sending a message to each process is a separate step, so a process can crash partway through a
+//! broadcast. The liveness properties are checked at terminal states, once every correct process
+//! has finished sending.
+
+use stateright::actor::{model_timeout, Actor, ActorModel, Id, Network, Out};
+use stateright::report::WriteReporter;
+use stateright::{Checker, Expectation, Model};
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+/// A broadcast message, which is identified by its original broadcaster and content.
+type Payload = (Id, char);
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Protocol {
+    BestEffort,
+    Reliable,
+    UniformReliable,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct BroadcastState {
+    /// Best-effort broadcast: messages awaiting sending.
+    outbox: Vec<(Id, Payload)>,
+    /// Uniform reliable broadcast: the messages relayed and the processes they were received from.
+    pending: BTreeSet<Payload>,
+    acks: BTreeSet<(Payload, Id)>,
+    delivered: Vec<Payload>,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum BroadcastTimer {
+    /// Sends the next message in the outbox.
+    Send,
+}
+
+#[derive(Clone)]
+struct BroadcastActor {
+    protocol: Protocol,
+    process_ids: Vec<Id>,
+    /// The message to broadcast, if any.
+    message: Option<char>,
+}
+
+impl BroadcastActor {
+    /// Best-effort broadcast to the other processes, delivering the message locally right away.
+    /// Local deliveries are not relayed again.
+    fn beb_broadcast(
+        &self,
+        id: Id,
+        state: &mut BroadcastState,
+        payload: Payload,
+        o: &mut Out<Self>,
+    ) {
+        if state.outbox.is_empty() {
+            o.set_timer(BroadcastTimer::Send, model_timeout());
+        }
+        for dst in &self.process_ids {
+            if *dst != id {
+                state.outbox.push((*dst, payload));
+            }
+        }
+        self.beb_deliver(id, state, id, payload, o);
+    }
+
+    fn beb_deliver(
+        &self,
+        id: Id,
+        state: &mut BroadcastState,
+        src: Id,
+        payload: Payload,
+        o: &mut Out<Self>,
+    ) {
+        match self.protocol {
+            Protocol::BestEffort => state.delivered.push(payload),
+            Protocol::Reliable => {
+                if !state.delivered.contains(&payload) {
+                    state.delivered.push(payload);
+                    if src != id {
+                        self.beb_broadcast(id, state, payload, o);
+                    }
+                }
+            }
+            Protocol::UniformReliable => {
+                state.acks.insert((payload, src));
+                if state.pending.insert(payload) && src != id {
+                    self.beb_broadcast(id, state, payload, o);
+                }
+                let ack_count = state.acks.iter().filter(|(p, _)| *p == payload).count();
+                if 2 * ack_count > self.process_ids.len() && !state.delivered.contains(&payload) {
+                    state.delivered.push(payload);
+                }
+            }
+        }
+    }
+}
+
+impl Actor for BroadcastActor {
+    type Msg = Payload;
+    type State = BroadcastState;
+    type Timer = BroadcastTimer;
+
+    fn on_start(&self, id: Id, o: &mut Out<Self>) -> Self::State {
+        let mut state = BroadcastState {
+            outbox: Vec::new(),
+            pending: Default::default(),
+            acks: Default::default(),
+            delivered: Vec::new(),
+        };
+        if let Some(message) = self.message {
+            self.beb_broadcast(id, &mut state, (id, message), o);
+        }
+        state
+    }
+
+    fn on_msg(
+        &self,
+        id: Id,
+        state: &mut Cow<Self::State>,
+        src: Id,
+        msg: Self::Msg,
+        o: &mut Out<Self>,
+    ) {
+        self.beb_deliver(id, state.to_mut(), src, msg, o);
+    }
+
+    fn on_timeout(
+        &self,
+        _id: Id,
+        state: &mut Cow<Self::State>,
+        _timer: &Self::Timer,
+        o: &mut Out<Self>,
+    ) {
+        let state = state.to_mut();
+        let (dst, payload) = state.outbox.remove(0);
+        o.send(dst, payload);
+        if !state.outbox.is_empty() {
+            o.set_timer(BroadcastTimer::Send, model_timeout());
+        }
+    }
+}
+
+#[derive(Clone)]
+struct BroadcastModelCfg {
+    protocol: Protocol,
+    process_count: usize,
+    max_crashes: usize,
+}
+
+impl BroadcastModelCfg {
+    fn into_model(self) -> ActorModel<BroadcastActor, Self> {
+        let process_ids: Vec<_> = (0..self.process_count).map(Id::from).collect();
+        ActorModel::new(self.clone(), ())
+            .actors((0..self.process_count).map(|i| BroadcastActor {
+                protocol: self.protocol,
+                process_ids: process_ids.clone(),
+                // Only the first process broadcasts, which keeps the state space small.
+                message: (i == 0).then_some('A'),
+            }))
+            .init_network(Network::new_unordered_nonduplicating([]))
+            .max_crashes(self.max_crashes)
+            .property(Expectation::Always, "no duplication", |_, state| {
+                state.actor_states.iter().all(|s| {
+                    let unique: BTreeSet<_> = s.delivered.iter().collect();
+                    unique.len() == s.delivered.len()
+                })
+            })
+            .property(Expectation::Always, "no creation", |model, state| {
+                state.actor_states.iter().all(|s| {
+                    s.delivered.iter().all(|(origin, message)| {
+                        model.actors[usize::from(*origin)].message == Some(*message)
+                    })
+                })
+            })
+            .property(Expectation::Terminal, "validity", |model, state| {
+                let correct = || (0..state.actor_states.len()).filter(|i| !state.crashed[*i]);
+                correct().all(|i| match model.actors[i].message {
+                    Some(message) => correct().all(|j| {
+                        state.actor_states[j]
+                            .delivered
+                            .contains(&(Id::from(i), message))
+                    }),
+                    None => true,
+                })
+            })
+            .property(Expectation::Terminal, "agreement", |_, state| {
+                let correct = || (0..state.actor_states.len()).filter(|i| !state.crashed[*i]);
+                correct().all(|i| {
+                    state.actor_states[i].delivered.iter().all(|payload| {
+                        correct().all(|j| state.actor_states[j].delivered.contains(payload))
+                    })
+                })
+            })
+            .property(Expectation::Terminal, "uniform agreement", |_, state| {
+                let correct = || (0..state.actor_states.len()).filter(|i| !state.crashed[*i]);
+                state.actor_states.iter().all(|s| {
+                    s.delivered.iter().all(|payload| {
+                        correct().all(|j| state.actor_states[j].delivered.contains(payload))
+                    })
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn can_model_broadcast() {
+    let cfg = |protocol| BroadcastModelCfg {
+        protocol,
+        process_count: 3,
+        max_crashes: 1,
+    };
+
+    // Best-effort broadcast only guarantees delivery if the broadcaster is correct.
+    let checker = cfg(Protocol::BestEffort)
+        .into_model()
+        .checker()
+        .spawn_bfs()
+        .join();
+    checker.assert_no_discovery("no duplication");
+    checker.assert_no_discovery("no creation");
+    checker.assert_no_discovery("validity");
+    checker.assert_any_discovery("agreement");
+    checker.assert_any_discovery("uniform agreement");
+    assert_eq!(checker.unique_state_count(), 28);
+
+    // Reliable broadcast guarantees agreement among correct processes.
+    let checker = cfg(Protocol::Reliable)
+        .into_model()
+        .checker()
+        .spawn_bfs()
+        .join();
+    checker.assert_no_discovery("no duplication");
+    checker.assert_no_discovery("no creation");
+    checker.assert_no_discovery("validity");
+    checker.assert_no_discovery("agreement");
+    checker.assert_any_discovery("uniform agreement");
+    assert_eq!(checker.unique_state_count(), 436);
+
+    // Uniform reliable broadcast also accounts for processes that crash after delivering.
+    let checker = cfg(Protocol::UniformReliable)
+        .into_model()
+        .checker()
+        .spawn_bfs()
+        .join();
+    checker.assert_properties();
+    assert_eq!(checker.unique_state_count(), 436);
+}
+
+fn main() -> Result<(), pico_args::Error> {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    let mut args = pico_args::Arguments::from_env();
+    let protocol = |name: Option<String>| match name.as_deref() {
+        Some("beb") => Some(Protocol::BestEffort),
+        Some("rb") => Some(Protocol::Reliable),
+        Some("urb") => Some(Protocol::UniformReliable),
+        _ => None,
+    };
+    match (args.subcommand()?.as_deref(), protocol(args.subcommand()?)) {
+        (Some("check"), Some(protocol)) => {
+            let process_count = args.opt_free_from_str()?.unwrap_or(3);
+            println!(
+                "Model checking {:?} broadcast with {} processes.",
+                protocol, process_count
+            );
+            BroadcastModelCfg {
+                protocol,
+                process_count,
+                max_crashes: (process_count - 1) / 2,
+            }
+            .into_model()
+            .checker()
+            .threads(num_cpus::get())
+            .spawn_dfs()
+            .report(&mut WriteReporter::new(&mut std::io::stdout()));
+        }
+        (Some("explore"), Some(protocol)) => {
+            let process_count = args.opt_free_from_str()?.unwrap_or(3);
+            let address = args
+                .opt_free_from_str()?
+                .unwrap_or("localhost:3000".to_string());
+            println!(
+                "Exploring state space for {:?} broadcast with {} processes on {}.",
+                protocol, process_count, address
+            );
+            BroadcastModelCfg {
+                protocol,
+                process_count,
+                max_crashes: (process_count - 1) / 2,
+            }
+            .into_model()
+            .checker()
+            .threads(num_cpus::get())
+            .serve(address);
+        }
+        _ => {
+            println!("USAGE:");
+            println!("  ./broadcast check beb|rb|urb [PROCESS_COUNT]");
+            println!("  ./broadcast explore beb|rb|urb [PROCESS_COUNT] [ADDRESS]");
+        }
+    }
+
+    Ok(())
+}
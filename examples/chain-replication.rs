@@ -0,0 +1,599 @@
+//! This is an implementation of [Chain Replication](https://www.cs.cornell.edu/home/rvr/papers/OSDI04.pdf)
+//! by Robbert van Renesse and Fred B. Schneider, driving a register.
+//!
+//! # The Algorithm
+//!
+//! Replicas are arranged in a chain, and each replica's role is determined by its position in the
+//! chain's configuration:
+//!
+//! - The **head** orders writes, applies them, and forwards them to its successor.
+//! - Each **middle** replica applies writes from its predecessor and forwards them.
+//! - The **tail** applies writes and replies to the client, then acknowledges each write back up
+//!   the chain so that the replicas can discard it. The tail also serves reads, which are
+//!   linearizable because every write the tail has applied is stored by every replica.
+//!
+//! Replicas forward requests that their role does not handle, so clients can contact any server.
+//!
+//! ## Failures
+//!
+//! Chain replication assumes fail-stop replicas: a replica halts upon failure, and a master that
+//! detects the halt removes the replica from the chain, broadcasting the new configuration under
+//! a new epoch. The crash action of an [`ActorModel`] halts an actor without notifying others, so
+//! this implementation instead halts a replica via a timer that also notifies the master, modeling
+//! the master's failure detector.
+//!
+//! Upon reconfiguration, a replica whose successor changes resends the writes that have not been
+//! acknowledged, and a replica that becomes the tail replies for those writes. The failed tail
+//! may have already replied, so clients ignore replies to requests they are no longer awaiting.
+
+use serde::{Deserialize, Serialize};
+use stateright::actor::properties::monotonic_sequence_numbers;
+use stateright::actor::register::{RegisterActor, RegisterActorState, RegisterMsg, RegisterMsg::*};
+use stateright::actor::{model_timeout, Actor, ActorModel, ActorModelState, Id, Network, Out};
+use stateright::report::WriteReporter;
+use stateright::semantics::register::Register;
+use stateright::semantics::LinearizabilityTester;
+use stateright::{Checker, Expectation, Model};
+use std::borrow::Cow;
+
+type Epoch = u32;
+type Seq = u32;
+type RequestId = u64;
+type Value = char;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+struct Update {
+    seq: Seq,
+    value: Value,
+    request_id: RequestId,
+    requester: Id,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+enum ChainMsg {
+    /// Sent by the master when the chain changes.
+    NewConfig {
+        epoch: Epoch,
+        chain: Vec<Id>,
+    },
+    /// Sent by a replica to the master upon failure.
+    Failed,
+
+    Update(Update),
+    Ack {
+        seq: Seq,
+    },
+
+    ForwardPut {
+        request_id: RequestId,
+        requester: Id,
+        value: Value,
+    },
+    ForwardGet {
+        request_id: RequestId,
+        requester: Id,
+    },
+}
+use ChainMsg::*;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+enum ChainTimer {
+    Fail,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct Config {
+    epoch: Epoch,
+    chain: Vec<Id>,
+}
+
+impl Config {
+    fn head(&self) -> Id {
+        self.chain[0]
+    }
+
+    fn tail(&self) -> Id {
+        self.chain[self.chain.len() - 1]
+    }
+
+    fn position(&self, id: Id) -> Option<usize> {
+        self.chain.iter().position(|&r| r == id)
+    }
+
+    fn predecessor(&self, id: Id) -> Option<Id> {
+        let position = self.position(id)?;
+        position.checked_sub(1).map(|p| self.chain[p])
+    }
+
+    fn successor(&self, id: Id) -> Option<Id> {
+        let position = self.position(id)?;
+        self.chain.get(position + 1).copied()
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct ReplicaState {
+    config: Config,
+    value: Value,
+    applied: Seq,
+    /// Writes forwarded to the successor that the tail has not acknowledged.
+    pending: Vec<Update>,
+    failed: bool,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum ChainState {
+    Master(Config),
+    Replica(ReplicaState),
+}
+
+impl ChainState {
+    fn config(&self) -> &Config {
+        match self {
+            ChainState::Master(config) => config,
+            ChainState::Replica(replica) => &replica.config,
+        }
+    }
+}
+
+#[derive(Clone)]
+enum ChainActor {
+    Master {
+        replica_ids: Vec<Id>,
+    },
+    Replica {
+        master_id: Id,
+        replica_ids: Vec<Id>,
+        can_fail: bool,
+    },
+}
+
+impl ChainActor {
+    fn write(&self, id: Id, state: &mut ReplicaState, update: Update, o: &mut Out<Self>) {
+        state.applied = update.seq;
+        state.value = update.value;
+        match state.config.successor(id) {
+            None => {
+                o.send(update.requester, PutOk(update.request_id));
+                if let Some(predecessor) = state.config.predecessor(id) {
+                    o.send(predecessor, Internal(Ack { seq: update.seq }));
+                }
+            }
+            Some(successor) => {
+                o.send(successor, Internal(Update(update.clone())));
+                state.pending.push(update);
+            }
+        }
+    }
+
+    /// Forwards a request to the replica responsible for it, if not handled locally.
+    fn forward(&self, config: &Config, msg: ChainMsg, o: &mut Out<Self>) {
+        let dst = match msg {
+            ForwardPut { .. } => config.head(),
+            _ => config.tail(),
+        };
+        o.send(dst, Internal(msg));
+    }
+
+    fn on_replica_msg(
+        &self,
+        id: Id,
+        replica: &mut Cow<ReplicaState>,
+        src: Id,
+        msg: ChainMsg,
+        o: &mut Out<Self>,
+    ) {
+        if replica.failed {
+            return;
+        }
+        match msg {
+            ForwardPut {
+                request_id,
+                requester,
+                value,
+            } if replica.config.head() == id => {
+                let replica = replica.to_mut();
+                let update = Update {
+                    seq: replica.applied + 1,
+                    value,
+                    request_id,
+                    requester,
+                };
+                self.write(id, replica, update, o);
+            }
+            ForwardGet {
+                request_id,
+                requester,
+            } if replica.config.tail() == id => {
+                o.send(requester, GetOk(request_id, replica.value));
+            }
+            msg @ (ForwardPut { .. } | ForwardGet { .. }) => self.forward(&replica.config, msg, o),
+            Update(update)
+                if Some(src) == replica.config.predecessor(id)
+                    && update.seq == replica.applied + 1 =>
+            {
+                let replica = replica.to_mut();
+                self.write(id, replica, update, o);
+            }
+            Ack { seq } => {
+                let replica = replica.to_mut();
+                replica.pending.retain(|update| update.seq > seq);
+                if let Some(predecessor) = replica.config.predecessor(id) {
+                    o.send(predecessor, Internal(Ack { seq }));
+                }
+            }
+            NewConfig { epoch, chain } if epoch > replica.config.epoch => {
+                let replica = replica.to_mut();
+                let prev_successor = replica.config.successor(id);
+                replica.config = Config { epoch, chain };
+                match replica.config.successor(id) {
+                    None => {
+                        for update in replica.pending.drain(..) {
+                            o.send(update.requester, PutOk(update.request_id));
+                        }
+                        if let Some(predecessor) = replica.config.predecessor(id) {
+                            o.send(
+                                predecessor,
+                                Internal(Ack {
+                                    seq: replica.applied,
+                                }),
+                            );
+                        }
+                    }
+                    Some(successor) if Some(successor) != prev_successor => {
+                        for update in &replica.pending {
+                            o.send(successor, Internal(Update(update.clone())));
+                        }
+                    }
+                    Some(_) => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Actor for ChainActor {
+    type Msg = RegisterMsg<RequestId, Value, ChainMsg>;
+    type State = ChainState;
+    type Timer = ChainTimer;
+
+    fn name(&self) -> String {
+        match self {
+            ChainActor::Master { .. } => "Master".to_owned(),
+            ChainActor::Replica { .. } => "Replica".to_owned(),
+        }
+    }
+
+    fn on_start(&self, _id: Id, o: &mut Out<Self>) -> Self::State {
+        match self {
+            ChainActor::Master { replica_ids } => ChainState::Master(Config {
+                epoch: 0,
+                chain: replica_ids.clone(),
+            }),
+            ChainActor::Replica {
+                replica_ids,
+                can_fail,
+                ..
+            } => {
+                if *can_fail {
+                    o.set_timer(ChainTimer::Fail, model_timeout());
+                }
+                ChainState::Replica(ReplicaState {
+                    config: Config {
+                        epoch: 0,
+                        chain: replica_ids.clone(),
+                    },
+                    value: Value::default(),
+                    applied: 0,
+                    pending: Vec::new(),
+                    failed: false,
+                })
+            }
+        }
+    }
+
+    fn on_msg(
+        &self,
+        id: Id,
+        state: &mut Cow<Self::State>,
+        src: Id,
+        msg: Self::Msg,
+        o: &mut Out<Self>,
+    ) {
+        let msg = match msg {
+            Put(request_id, value) => ForwardPut {
+                request_id,
+                requester: src,
+                value,
+            },
+            Get(request_id) => ForwardGet {
+                request_id,
+                requester: src,
+            },
+            Internal(msg) => msg,
+            PutOk(_) | GetOk(..) => return,
+        };
+        match (self, msg) {
+            (ChainActor::Master { .. }, Failed) => {
+                let ChainState::Master(config) = state.to_mut() else {
+                    return;
+                };
+                config.chain.retain(|&r| r != src);
+                config.epoch += 1;
+                o.broadcast(
+                    &config.chain,
+                    &Internal(NewConfig {
+                        epoch: config.epoch,
+                        chain: config.chain.clone(),
+                    }),
+                );
+            }
+            (ChainActor::Master { .. }, msg @ (ForwardPut { .. } | ForwardGet { .. })) => {
+                self.forward(state.config(), msg, o);
+            }
+            (ChainActor::Master { .. }, _) => {}
+            (ChainActor::Replica { .. }, msg) => {
+                let ChainState::Replica(replica) = &**state else {
+                    return;
+                };
+                let mut replica = Cow::Borrowed(replica);
+                self.on_replica_msg(id, &mut replica, src, msg, o);
+                if let Cow::Owned(replica) = replica {
+                    *state = Cow::Owned(ChainState::Replica(replica));
+                }
+            }
+        }
+    }
+
+    fn on_timeout(
+        &self,
+        _id: Id,
+        state: &mut Cow<Self::State>,
+        timer: &Self::Timer,
+        o: &mut Out<Self>,
+    ) {
+        match (self, timer) {
+            (ChainActor::Replica { master_id, .. }, ChainTimer::Fail) => {
+                if let ChainState::Replica(replica) = state.to_mut() {
+                    replica.failed = true;
+                    o.send(*master_id, Internal(Failed));
+                }
+            }
+            (ChainActor::Master { .. }, _) => {}
+        }
+    }
+}
+
+#[derive(Clone)]
+struct ChainModelCfg {
+    client_count: usize,
+    replica_count: usize,
+    max_failures: usize,
+    network: Network<<ChainActor as Actor>::Msg>,
+}
+
+type ChainModelState =
+    ActorModelState<RegisterActor<ChainActor>, LinearizabilityTester<Id, Register<Value>>>;
+
+/// The states of the master and replicas, which precede the clients.
+fn servers(state: &ChainModelState) -> impl Iterator<Item = &ChainState> {
+    state.actor_states.iter().filter_map(|s| match &**s {
+        RegisterActorState::Server(s) => Some(s),
+        RegisterActorState::Client { .. } => None,
+    })
+}
+
+fn replica(state: &ChainModelState, id: Id) -> &ReplicaState {
+    match &*state.actor_states[usize::from(id)] {
+        RegisterActorState::Server(ChainState::Replica(replica)) => replica,
+        _ => unreachable!("not a replica"),
+    }
+}
+
+impl ChainModelCfg {
+    fn into_model(
+        self,
+    ) -> ActorModel<RegisterActor<ChainActor>, Self, LinearizabilityTester<Id, Register<Value>>>
+    {
+        let master_id = Id::from(0);
+        let replica_ids: Vec<_> = (1..=self.replica_count).map(Id::from).collect();
+        let mut model = ActorModel::new(
+            self.clone(),
+            LinearizabilityTester::new(Register(Value::default())),
+        )
+        .actor(RegisterActor::Server(ChainActor::Master {
+            replica_ids: replica_ids.clone(),
+        }))
+        .actors(replica_ids.iter().map(|_| {
+            RegisterActor::Server(ChainActor::Replica {
+                master_id,
+                replica_ids: replica_ids.clone(),
+                can_fail: self.max_failures > 0,
+            })
+        }))
+        .actors((0..self.client_count).map(|_| RegisterActor::Client {
+            put_count: 1,
+            server_count: self.replica_count + 1,
+        }))
+        .init_network(self.network)
+        .property(Expectation::Always, "linearizable", |_, state| {
+            state.history.serialized_history().is_some()
+        })
+        .property(Expectation::Always, "update propagation", |_, state| {
+            // Each live replica has applied every write applied by its successors.
+            let ChainState::Master(config) = servers(state).next().unwrap() else {
+                unreachable!("the master is the first actor");
+            };
+            let applied: Vec<_> = config
+                .chain
+                .iter()
+                .map(|&id| replica(state, id))
+                .filter(|replica| !replica.failed)
+                .map(|replica| replica.applied)
+                .collect();
+            applied.windows(2).all(|w| w[0] >= w[1])
+        })
+        .property(Expectation::Sometimes, "value chosen", |_, state| {
+            for env in state.network.iter_deliverable() {
+                if let RegisterMsg::GetOk(_req_id, value) = env.msg {
+                    if *value != Value::default() {
+                        return true;
+                    }
+                }
+            }
+            false
+        })
+        .record_msg_in(RegisterMsg::record_returns)
+        .record_msg_out(RegisterMsg::record_invocations)
+        .within_boundary(|cfg, state| {
+            let failures = servers(state)
+                .filter(|s| matches!(s, ChainState::Replica(r) if r.failed))
+                .count();
+            failures <= cfg.max_failures
+        });
+        model.properties.push(monotonic_sequence_numbers(
+            "monotonic epochs",
+            |_, state: &RegisterActorState<ChainState, _>| match state {
+                RegisterActorState::Server(s) => s.config().epoch,
+                RegisterActorState::Client { .. } => 0,
+            },
+        ));
+        model
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn can_model_chain_replication() {
+    let checker = ChainModelCfg {
+        client_count: 2,
+        replica_count: 3,
+        max_failures: 1,
+        network: Network::new_unordered_nonduplicating([]),
+    }
+    .into_model()
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_properties();
+    assert_eq!(checker.unique_state_count(), 71_421);
+}
+
+fn main() -> Result<(), pico_args::Error> {
+    use stateright::actor::spawn;
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    let mut args = pico_args::Arguments::from_env();
+    match args.subcommand()?.as_deref() {
+        Some("check") => {
+            let client_count = args.opt_free_from_str()?.unwrap_or(2);
+            let max_failures = args.opt_free_from_str()?.unwrap_or(1);
+            let network = args
+                .opt_free_from_str()?
+                .unwrap_or(Network::new_unordered_nonduplicating([]));
+            println!(
+                "Model checking chain replication with {} clients and up to {} failures.",
+                client_count, max_failures
+            );
+            ChainModelCfg {
+                client_count,
+                replica_count: 3,
+                max_failures,
+                network,
+            }
+            .into_model()
+            .checker()
+            .threads(num_cpus::get())
+            .spawn_dfs()
+            .report(&mut WriteReporter::new(&mut std::io::stdout()));
+        }
+        Some("explore") => {
+            let client_count = args.opt_free_from_str()?.unwrap_or(2);
+            let max_failures = args.opt_free_from_str()?.unwrap_or(1);
+            let address = args
+                .opt_free_from_str()?
+                .unwrap_or("localhost:3000".to_string());
+            let network = args
+                .opt_free_from_str()?
+                .unwrap_or(Network::new_unordered_nonduplicating([]));
+            println!(
+                "Exploring state space for chain replication with {} clients and up to {} failures on {}.",
+                client_count, max_failures, address
+            );
+            ChainModelCfg {
+                client_count,
+                replica_count: 3,
+                max_failures,
+                network,
+            }
+            .into_model()
+            .checker()
+            .threads(num_cpus::get())
+            .serve(address);
+        }
+        Some("spawn") => {
+            let port = 3000;
+
+            println!("  A master and replicas that implement chain replication.");
+            println!("  You can monitor and interact using tcpdump and netcat.");
+            println!("  Use `tcpdump -D` if you see error `lo0: No such device exists`.");
+            println!("Examples:");
+            println!("$ sudo tcpdump -i lo0 -s 0 -nnX");
+            println!("$ nc -u localhost {}", port);
+            println!(
+                "{}",
+                serde_json::to_string(&RegisterMsg::Put::<RequestId, Value, ()>(1, 'X')).unwrap()
+            );
+            println!(
+                "{}",
+                serde_json::to_string(&RegisterMsg::Get::<RequestId, Value, ()>(2)).unwrap()
+            );
+            println!();
+
+            // WARNING: Omits `ordered_reliable_link` to keep the message
+            //          protocol simple for `nc`.
+            let id = |i: u16| Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port + i));
+            let master_id = id(0);
+            let replica_ids: Vec<_> = (1..=3).map(id).collect();
+            let mut actors = vec![(
+                master_id,
+                ChainActor::Master {
+                    replica_ids: replica_ids.clone(),
+                },
+            )];
+            actors.extend(replica_ids.iter().map(|&replica_id| {
+                (
+                    replica_id,
+                    ChainActor::Replica {
+                        master_id,
+                        replica_ids: replica_ids.clone(),
+                        can_fail: false,
+                    },
+                )
+            }));
+            spawn(
+                serde_json::to_vec,
+                |bytes| serde_json::from_slice(bytes),
+                actors,
+            )
+            .unwrap();
+        }
+        _ => {
+            println!("USAGE:");
+            println!("  ./chain-replication check [CLIENT_COUNT] [MAX_FAILURES] [NETWORK]");
+            println!(
+                "  ./chain-replication explore [CLIENT_COUNT] [MAX_FAILURES] [ADDRESS] [NETWORK]"
+            );
+            println!("  ./chain-replication spawn");
+            println!(
+                "NETWORK: {}",
+                Network::<<ChainActor as Actor>::Msg>::names().join(" | ")
+            );
+        }
+    }
+
+    Ok(())
+}
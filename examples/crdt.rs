@@ -0,0 +1,474 @@
+//! This is an implementation of two [conflict-free replicated data
+//! types](https://en.wikipedia.org/wiki/Conflict-free_replicated_data_type) (CRDTs), a grow-only
+//! counter and an observed-remove set, along with naive counterparts that converge to the wrong
+//! result.
+//!
+//! # The Algorithm
+//!
+//! Each replica applies a sequence of local operations without coordinating with the other
+//! replicas, and gossips its state to its peers after every operation. A replica that receives a
+//! peer's state merges it into its own. Because merging is commutative, associative, and
+//! idempotent, replicas that have received the same updates converge regardless of the order in
+//! which gossip is delivered.
+//!
+//! A G-counter tracks the number of increments performed by each replica separately, merging by
+//! taking the maximum of each entry, while a naive counter that tracks only the total loses
+//! concurrent increments.
+//!
+//! An OR-set tags every addition with a unique tag, and a removal only removes the tags that the
+//! replica has observed, so an element can be removed and then added again. A two-phase set
+//! instead tracks removed elements, so a removed element can never return.
+//!
+//! # Convergence
+//!
+//! Liveness properties such as convergence only hold if every gossip message is eventually
+//! delivered, so the network is neither lossy nor duplicating, and every behavior terminates once
+//! all operations are performed and all gossip is delivered.
+
+use stateright::actor::{model_timeout, Actor, ActorModel, Id, Network, Out};
+use stateright::report::WriteReporter;
+use stateright::{Checker, Expectation, Model};
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+type Element = char;
+
+/// A state-based CRDT.
+trait Crdt: Clone + Debug + Eq + Hash + Send + Sync + 'static {
+    type Op: Clone + Debug + Send + Sync + 'static;
+
+    /// Applies an operation performed by the replica at the specified index.
+    fn apply(&mut self, replica: usize, op: &Self::Op);
+
+    /// Merges another replica's state into this one.
+    fn merge(&mut self, other: &Self);
+}
+
+trait Counter: Crdt<Op = ()> {
+    fn value(&self) -> u8;
+}
+
+trait Set: Crdt<Op = SetOp> {
+    fn contains(&self, element: Element) -> bool;
+}
+
+/// Tracks increments per replica.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct GCounter(Vec<u8>);
+
+impl Crdt for GCounter {
+    type Op = ();
+
+    fn apply(&mut self, replica: usize, _op: &()) {
+        self.0[replica] += 1;
+    }
+
+    fn merge(&mut self, other: &Self) {
+        for (count, other) in self.0.iter_mut().zip(&other.0) {
+            *count = (*count).max(*other);
+        }
+    }
+}
+
+impl Counter for GCounter {
+    fn value(&self) -> u8 {
+        self.0.iter().sum()
+    }
+}
+
+/// Tracks only the total, so concurrent increments are lost.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct MaxCounter(u8);
+
+impl Crdt for MaxCounter {
+    type Op = ();
+
+    fn apply(&mut self, _replica: usize, _op: &()) {
+        self.0 += 1;
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.0 = self.0.max(other.0);
+    }
+}
+
+impl Counter for MaxCounter {
+    fn value(&self) -> u8 {
+        self.0
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum SetOp {
+    Add(Element),
+    Remove(Element),
+}
+
+/// A unique tag for an addition, comprising the replica index and a per-replica sequence number.
+type Tag = (usize, u8);
+
+/// Removes only the observed additions of an element.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+struct OrSet {
+    adds: BTreeSet<(Element, Tag)>,
+    removes: BTreeSet<(Element, Tag)>,
+}
+
+impl Crdt for OrSet {
+    type Op = SetOp;
+
+    fn apply(&mut self, replica: usize, op: &SetOp) {
+        match *op {
+            SetOp::Add(element) => {
+                let seq = self.adds.iter().filter(|(_, tag)| tag.0 == replica).count() as u8;
+                self.adds.insert((element, (replica, seq)));
+            }
+            SetOp::Remove(element) => {
+                let observed: Vec<_> = self
+                    .adds
+                    .iter()
+                    .filter(|(e, _)| *e == element)
+                    .cloned()
+                    .collect();
+                self.removes.extend(observed);
+            }
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.adds.extend(other.adds.iter().cloned());
+        self.removes.extend(other.removes.iter().cloned());
+    }
+}
+
+impl Set for OrSet {
+    fn contains(&self, element: Element) -> bool {
+        self.adds
+            .iter()
+            .any(|add| add.0 == element && !self.removes.contains(add))
+    }
+}
+
+/// Removes an element permanently, so it cannot be added again.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+struct TwoPhaseSet {
+    adds: BTreeSet<Element>,
+    removes: BTreeSet<Element>,
+}
+
+impl Crdt for TwoPhaseSet {
+    type Op = SetOp;
+
+    fn apply(&mut self, _replica: usize, op: &SetOp) {
+        match *op {
+            SetOp::Add(element) => self.adds.insert(element),
+            SetOp::Remove(element) => self.removes.insert(element),
+        };
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.adds.extend(other.adds.iter().cloned());
+        self.removes.extend(other.removes.iter().cloned());
+    }
+}
+
+impl Set for TwoPhaseSet {
+    fn contains(&self, element: Element) -> bool {
+        self.adds.contains(&element) && !self.removes.contains(&element)
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct ReplicaState<C> {
+    crdt: C,
+    /// The number of local operations performed so far.
+    performed: usize,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum ReplicaTimer {
+    /// Performs the next local operation.
+    Perform,
+}
+
+#[derive(Clone)]
+struct ReplicaActor<C: Crdt> {
+    init: C,
+    ops: Vec<C::Op>,
+    peer_ids: Vec<Id>,
+}
+
+impl<C: Crdt> Actor for ReplicaActor<C> {
+    /// Gossip carries the sender's entire state.
+    type Msg = C;
+    type State = ReplicaState<C>;
+    type Timer = ReplicaTimer;
+
+    fn on_start(&self, _id: Id, o: &mut Out<Self>) -> Self::State {
+        if !self.ops.is_empty() {
+            o.set_timer(ReplicaTimer::Perform, model_timeout());
+        }
+        ReplicaState {
+            crdt: self.init.clone(),
+            performed: 0,
+        }
+    }
+
+    fn on_msg(
+        &self,
+        _id: Id,
+        state: &mut Cow<Self::State>,
+        _src: Id,
+        msg: Self::Msg,
+        _o: &mut Out<Self>,
+    ) {
+        let mut merged = state.crdt.clone();
+        merged.merge(&msg);
+        if merged != state.crdt {
+            state.to_mut().crdt = merged;
+        }
+    }
+
+    fn on_timeout(
+        &self,
+        id: Id,
+        state: &mut Cow<Self::State>,
+        _timer: &Self::Timer,
+        o: &mut Out<Self>,
+    ) {
+        let state = state.to_mut();
+        state
+            .crdt
+            .apply(usize::from(id), &self.ops[state.performed]);
+        state.performed += 1;
+        o.broadcast(&self.peer_ids, &state.crdt);
+        if state.performed < self.ops.len() {
+            o.set_timer(ReplicaTimer::Perform, model_timeout());
+        }
+    }
+}
+
+#[derive(Clone)]
+struct CrdtModelCfg<C: Crdt> {
+    init: C,
+    ops: Vec<Vec<C::Op>>,
+}
+
+impl<C: Crdt> CrdtModelCfg<C> {
+    fn into_model(self) -> ActorModel<ReplicaActor<C>, Self> {
+        let ids: Vec<Id> = (0..self.ops.len()).map(Id::from).collect();
+        ActorModel::new(self.clone(), ())
+            .actors(self.ops.iter().enumerate().map(|(i, ops)| {
+                ReplicaActor {
+                    init: self.init.clone(),
+                    ops: ops.clone(),
+                    peer_ids: ids
+                        .iter()
+                        .copied()
+                        .filter(|id| usize::from(*id) != i)
+                        .collect(),
+                }
+            }))
+            .init_network(Network::new_unordered_nonduplicating([]))
+            .property(Expectation::Eventually, "converged", |model, state| {
+                let is_done = state
+                    .actor_states
+                    .iter()
+                    .zip(&model.cfg.ops)
+                    .all(|(s, ops)| s.performed == ops.len());
+                is_done
+                    && state
+                        .actor_states
+                        .windows(2)
+                        .all(|pair| pair[0].crdt == pair[1].crdt)
+            })
+    }
+}
+
+impl<C: Counter> CrdtModelCfg<C> {
+    fn into_counter_model(self) -> ActorModel<ReplicaActor<C>, Self> {
+        self.into_model().property(
+            Expectation::Eventually,
+            "no lost increments",
+            |model, state| {
+                let total: usize = model.cfg.ops.iter().map(|ops| ops.len()).sum();
+                state
+                    .actor_states
+                    .iter()
+                    .all(|s| s.crdt.value() as usize == total)
+            },
+        )
+    }
+}
+
+impl<C: Set> CrdtModelCfg<C> {
+    fn into_set_model(self) -> ActorModel<ReplicaActor<C>, Self> {
+        self.into_model()
+            .property(Expectation::Always, "read your writes", |model, state| {
+                // A replica's most recent local operation on an element is always visible to it.
+                state
+                    .actor_states
+                    .iter()
+                    .zip(&model.cfg.ops)
+                    .all(|(s, ops)| match ops[..s.performed].last() {
+                        Some(SetOp::Add(element)) => s.crdt.contains(*element),
+                        Some(SetOp::Remove(element)) => !s.crdt.contains(*element),
+                        None => true,
+                    })
+            })
+            .property(Expectation::Sometimes, "re-added", |_, state| {
+                // See `set_cfg`, whose first replica adds, removes, and then re-adds an element.
+                state
+                    .actor_states
+                    .iter()
+                    .any(|s| s.performed == 3 && s.crdt.contains('a'))
+            })
+    }
+}
+
+fn counter_cfg<C: Counter>(init: C, replica_count: usize) -> CrdtModelCfg<C> {
+    CrdtModelCfg {
+        init,
+        ops: vec![vec![()]; replica_count],
+    }
+}
+
+fn set_cfg<C: Set + Default>() -> CrdtModelCfg<C> {
+    use SetOp::*;
+    CrdtModelCfg {
+        init: C::default(),
+        ops: vec![vec![Add('a'), Remove('a'), Add('a')], vec![Add('b')]],
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn can_model_g_counter() {
+    let checker = counter_cfg(GCounter(vec![0; 3]), 3)
+        .into_counter_model()
+        .checker()
+        .spawn_bfs()
+        .join();
+    checker.assert_properties();
+    assert_eq!(checker.unique_state_count(), 467);
+
+    // Concurrent increments are lost when replicas only track the total.
+    let checker = counter_cfg(MaxCounter(0), 3)
+        .into_counter_model()
+        .checker()
+        .spawn_bfs()
+        .join();
+    checker.assert_no_discovery("converged");
+    checker.assert_any_discovery("no lost increments");
+}
+
+#[cfg(test)]
+#[test]
+fn can_model_or_set() {
+    let checker = set_cfg::<OrSet>()
+        .into_set_model()
+        .checker()
+        .spawn_bfs()
+        .join();
+    checker.assert_properties();
+    assert_eq!(checker.unique_state_count(), 79);
+
+    // A two-phase set cannot add an element again once removed.
+    let checker = set_cfg::<TwoPhaseSet>()
+        .into_set_model()
+        .checker()
+        .spawn_bfs()
+        .join();
+    checker.assert_no_discovery("converged");
+    checker.assert_any_discovery("read your writes");
+}
+
+fn main() -> Result<(), pico_args::Error> {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    let mut args = pico_args::Arguments::from_env();
+    let naive = args.contains("--naive");
+    match (args.subcommand()?.as_deref(), args.subcommand()?.as_deref()) {
+        (Some("check"), Some("counter")) => {
+            let replica_count = args.opt_free_from_str()?.unwrap_or(3);
+            println!(
+                "Model checking a {} with {} replicas.",
+                if naive { "max counter" } else { "G-counter" },
+                replica_count
+            );
+            let mut stdout = std::io::stdout();
+            let mut reporter = WriteReporter::new(&mut stdout);
+            if naive {
+                counter_cfg(MaxCounter(0), replica_count)
+                    .into_counter_model()
+                    .checker()
+                    .threads(num_cpus::get())
+                    .spawn_dfs()
+                    .report(&mut reporter);
+            } else {
+                counter_cfg(GCounter(vec![0; replica_count]), replica_count)
+                    .into_counter_model()
+                    .checker()
+                    .threads(num_cpus::get())
+                    .spawn_dfs()
+                    .report(&mut reporter);
+            }
+        }
+        (Some("check"), Some("set")) => {
+            println!(
+                "Model checking a {}.",
+                if naive { "two-phase set" } else { "OR-set" }
+            );
+            let mut stdout = std::io::stdout();
+            let mut reporter = WriteReporter::new(&mut stdout);
+            if naive {
+                set_cfg::<TwoPhaseSet>()
+                    .into_set_model()
+                    .checker()
+                    .threads(num_cpus::get())
+                    .spawn_dfs()
+                    .report(&mut reporter);
+            } else {
+                set_cfg::<OrSet>()
+                    .into_set_model()
+                    .checker()
+                    .threads(num_cpus::get())
+                    .spawn_dfs()
+                    .report(&mut reporter);
+            }
+        }
+        (Some("explore"), Some("counter")) => {
+            let address = args
+                .opt_free_from_str()?
+                .unwrap_or("localhost:3000".to_string());
+            println!("Exploring state space for a G-counter on {}.", address);
+            counter_cfg(GCounter(vec![0; 3]), 3)
+                .into_counter_model()
+                .checker()
+                .threads(num_cpus::get())
+                .serve(address);
+        }
+        (Some("explore"), Some("set")) => {
+            let address = args
+                .opt_free_from_str()?
+                .unwrap_or("localhost:3000".to_string());
+            println!("Exploring state space for an OR-set on {}.", address);
+            set_cfg::<OrSet>()
+                .into_set_model()
+                .checker()
+                .threads(num_cpus::get())
+                .serve(address);
+        }
+        _ => {
+            println!("USAGE:");
+            println!("  ./crdt check counter [--naive] [REPLICA_COUNT]");
+            println!("  ./crdt check set [--naive]");
+            println!("  ./crdt explore counter [ADDRESS]");
+            println!("  ./crdt explore set [ADDRESS]");
+        }
+    }
+
+    Ok(())
+}
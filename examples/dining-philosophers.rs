@@ -0,0 +1,201 @@
+//! This is an implementation of Dijkstra's [dining philosophers
+//! problem](https://en.wikipedia.org/wiki/Dining_philosophers_problem), illustrating how the
+//! checker detects deadlock.
+//!
+//! # The Algorithm
+//!
+//! Philosophers sit around a table with a fork between each pair of neighbors. A hungry philosopher
+//! picks up one adjacent fork and then the other, eats, and then puts down both forks, repeating
+//! forever. Picking up a fork is an atomic step that is only possible if the fork is on the table.
+//!
+//! If every philosopher picks up the fork to their left first, then every philosopher can hold one
+//! fork while waiting for the other, and nobody can make progress. Ordering the forks and having
+//! each philosopher pick up the lower-numbered fork first breaks the cycle and avoids deadlock.
+//!
+//! # Deadlock Detection
+//!
+//! Philosophers otherwise dine forever, so every terminal state (a state without successors) is a
+//! deadlock, which a [`Property::terminal`] property reports.
+
+use stateright::{report::WriteReporter, *};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// A specified philosopher picks up their next fork.
+    PickUp(usize),
+    /// A specified philosopher finishes eating and puts down both forks.
+    PutDown(usize),
+}
+
+#[derive(Debug, Clone, Hash, PartialEq)]
+pub struct State {
+    /// The philosopher holding each fork, if any.
+    forks: Vec<Option<usize>>,
+    /// The number of forks held by each philosopher.
+    held: Vec<u8>,
+}
+
+impl State {
+    fn is_eating(&self, philosopher: usize) -> bool {
+        self.held[philosopher] == 2
+    }
+}
+
+#[derive(Clone)]
+pub struct Table {
+    philosopher_count: usize,
+    /// Whether philosophers pick up the lower-numbered fork first, rather than the left fork.
+    ordered: bool,
+}
+
+impl Table {
+    /// Returns the forks of a philosopher in the order in which they are picked up.
+    fn forks(&self, philosopher: usize) -> [usize; 2] {
+        let left = philosopher;
+        let right = (philosopher + 1) % self.philosopher_count;
+        if self.ordered && right < left {
+            [right, left]
+        } else {
+            [left, right]
+        }
+    }
+}
+
+impl Model for Table {
+    type State = State;
+    type Action = Action;
+
+    fn init_states(&self) -> Vec<Self::State> {
+        vec![State {
+            forks: vec![None; self.philosopher_count],
+            held: vec![0; self.philosopher_count],
+        }]
+    }
+
+    fn actions(&self, state: &Self::State, actions: &mut Vec<Self::Action>) {
+        for philosopher in 0..self.philosopher_count {
+            match state.held[philosopher] {
+                2 => actions.push(Action::PutDown(philosopher)),
+                held => {
+                    let fork = self.forks(philosopher)[held as usize];
+                    if state.forks[fork].is_none() {
+                        actions.push(Action::PickUp(philosopher));
+                    }
+                }
+            }
+        }
+    }
+
+    fn next_state(&self, last_state: &Self::State, action: Self::Action) -> Option<Self::State> {
+        let mut state = last_state.clone();
+        match action {
+            Action::PickUp(philosopher) => {
+                let fork = self.forks(philosopher)[state.held[philosopher] as usize];
+                state.forks[fork] = Some(philosopher);
+                state.held[philosopher] += 1;
+            }
+            Action::PutDown(philosopher) => {
+                for fork in self.forks(philosopher) {
+                    state.forks[fork] = None;
+                }
+                state.held[philosopher] = 0;
+            }
+        }
+        Some(state)
+    }
+
+    fn properties(&self) -> Vec<Property<Self>> {
+        vec![
+            Property::<Self>::always("neighbors never eat together", |model, state| {
+                (0..model.philosopher_count).all(|p| {
+                    !state.is_eating(p) || !state.is_eating((p + 1) % model.philosopher_count)
+                })
+            }),
+            Property::<Self>::sometimes("non-neighbors eat together", |model, state| {
+                // As many philosophers as possible.
+                (0..model.philosopher_count)
+                    .filter(|p| state.is_eating(*p))
+                    .count()
+                    == model.philosopher_count / 2
+            }),
+            Property::<Self>::terminal("deadlock free", |_, _| false),
+        ]
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn can_model_dining_philosophers() {
+    // Philosophers who pick up the left fork first can deadlock.
+    let checker = Table {
+        philosopher_count: 3,
+        ordered: false,
+    }
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_discovery(
+        "deadlock free",
+        vec![Action::PickUp(0), Action::PickUp(1), Action::PickUp(2)],
+    );
+
+    // Ordering the forks avoids deadlock.
+    let checker = Table {
+        philosopher_count: 5,
+        ordered: true,
+    }
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_properties();
+    assert_eq!(checker.unique_state_count(), 70);
+}
+
+fn main() -> Result<(), pico_args::Error> {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    let mut args = pico_args::Arguments::from_env();
+    let ordered = args.contains("--ordered");
+    match args.subcommand()?.as_deref() {
+        Some("check") => {
+            let philosopher_count = args.opt_free_from_str()?.unwrap_or(5);
+            println!(
+                "Model checking dining philosophers with {} philosophers{}.",
+                philosopher_count,
+                if ordered { " and ordered forks" } else { "" }
+            );
+            Table {
+                philosopher_count,
+                ordered,
+            }
+            .checker()
+            .threads(num_cpus::get())
+            .spawn_dfs()
+            .report(&mut WriteReporter::new(&mut std::io::stdout()));
+        }
+        Some("explore") => {
+            let philosopher_count = args.opt_free_from_str()?.unwrap_or(5);
+            let address = args
+                .opt_free_from_str()?
+                .unwrap_or("localhost:3000".to_string());
+            println!(
+                "Exploring the state space of dining philosophers with {} philosophers on {}.",
+                philosopher_count, address
+            );
+            Table {
+                philosopher_count,
+                ordered,
+            }
+            .checker()
+            .threads(num_cpus::get())
+            .serve(address);
+        }
+        _ => {
+            println!("USAGE:");
+            println!("  ./dining-philosophers check [PHILOSOPHER_COUNT] [--ordered]");
+            println!("  ./dining-philosophers explore [PHILOSOPHER_COUNT] [ADDRESS] [--ordered]");
+        }
+    }
+
+    Ok(())
+}
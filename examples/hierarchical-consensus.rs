@@ -0,0 +1,260 @@
+//! This is an implementation of the "hierarchical consensus" algorithm described in "[Introduction
+//! to Reliable and Secure Distributed
+//! Programming](https://link.springer.com/book/10.1007/978-3-642-15260-3)" by Cachin, Guerraoui,
+//! and Rodrigues, which relies on a perfect failure detector.
+//!
+//! # The Algorithm
+//!
+//! Processes are ranked by their [`Id`], and each round is led by the process with that rank. The
+//! leader of a round decides its current proposal and broadcasts it. A process adopts a proposal
+//! from a leader that ranks ahead of it, and moves to the next round once it has received the
+//! leader's proposal or suspects that the leader has crashed. By the time a process leads a round,
+//! it has therefore adopted the proposal of the last leader that did not crash before
+//! broadcasting, so correct processes decide the same value. Agreement is not uniform: a leader
+//! can decide and then crash before its proposal is received, in which case the remaining
+//! processes can decide a different value.
+//!
+//! # Failure Detection
+//!
+//! Processes detect crashes using the heartbeat-based detector in
+//! [`stateright::actor::failure_detector`]. The algorithm is only correct if the detector never
+//! suspects a correct process, so allowing even one false suspicion lets a process skip a correct
+//! leader and decide a different value.
+
+use stateright::actor::failure_detector::{is_eventually_accurate, ActorWrapper, DetectorActor};
+use stateright::actor::{Actor, ActorModel, Id, Network, Out};
+use stateright::report::WriteReporter;
+use stateright::{Checker, Expectation, Model};
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+type Value = char;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct Decided(Value);
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct ConsensusState {
+    round: usize,
+    proposal: Value,
+    /// The rank of the leader whose proposal was adopted, if any.
+    proposer: Option<usize>,
+    /// Ranks of leaders that are suspected or whose proposals have been received.
+    finished_rounds: BTreeSet<usize>,
+    decided: Option<Value>,
+}
+
+#[derive(Clone)]
+struct ConsensusActor {
+    peer_ids: Vec<Id>,
+    proposal: Value,
+}
+
+impl ConsensusActor {
+    /// Leads the current round if it is this process's turn, and advances through finished
+    /// rounds.
+    fn advance(&self, id: Id, state: &mut ConsensusState, o: &mut Out<Self>) {
+        let rank = usize::from(id);
+        loop {
+            if state.round == rank && state.decided.is_none() {
+                state.decided = Some(state.proposal);
+                o.broadcast(&self.peer_ids, &Decided(state.proposal));
+            }
+            if !state.finished_rounds.contains(&state.round) {
+                break;
+            }
+            state.round += 1;
+        }
+    }
+}
+
+impl Actor for ConsensusActor {
+    type Msg = Decided;
+    type State = ConsensusState;
+    type Timer = ();
+
+    fn on_start(&self, id: Id, o: &mut Out<Self>) -> Self::State {
+        let mut state = ConsensusState {
+            round: 0,
+            proposal: self.proposal,
+            proposer: None,
+            finished_rounds: Default::default(),
+            decided: None,
+        };
+        self.advance(id, &mut state, o);
+        state
+    }
+
+    fn on_msg(
+        &self,
+        id: Id,
+        state: &mut Cow<Self::State>,
+        src: Id,
+        msg: Self::Msg,
+        o: &mut Out<Self>,
+    ) {
+        let Decided(value) = msg;
+        let src_rank = usize::from(src);
+        let mut next_state = (**state).clone();
+        if src_rank < usize::from(id) && next_state.proposer.is_none_or(|r| src_rank > r) {
+            next_state.proposal = value;
+            next_state.proposer = Some(src_rank);
+        }
+        next_state.finished_rounds.insert(src_rank);
+        self.advance(id, &mut next_state, o);
+        if **state != next_state {
+            *state.to_mut() = next_state;
+        }
+    }
+}
+
+impl DetectorActor for ConsensusActor {
+    fn on_suspect(&self, id: Id, state: &mut Cow<Self::State>, peer: Id, o: &mut Out<Self>) {
+        let mut next_state = (**state).clone();
+        next_state.finished_rounds.insert(usize::from(peer));
+        self.advance(id, &mut next_state, o);
+        if **state != next_state {
+            *state.to_mut() = next_state;
+        }
+    }
+}
+
+#[derive(Clone)]
+struct ConsensusModelCfg {
+    process_count: usize,
+    /// The number of false suspicions each failure detector can make. See
+    /// [`is_eventually_accurate`].
+    max_false_suspicions: usize,
+}
+
+impl ConsensusModelCfg {
+    fn into_model(self) -> ActorModel<ActorWrapper<ConsensusActor>, Self> {
+        let process_ids: Vec<_> = (0..self.process_count).map(Id::from).collect();
+        ActorModel::new(self.clone(), ())
+            .actors((0..self.process_count).map(|i| {
+                let peer_ids: Vec<_> = process_ids
+                    .iter()
+                    .copied()
+                    .filter(|id| usize::from(*id) != i)
+                    .collect();
+                ActorWrapper::with_default_timeout(
+                    ConsensusActor {
+                        peer_ids: peer_ids.clone(),
+                        proposal: (b'A' + i as u8) as char,
+                    },
+                    peer_ids,
+                )
+            }))
+            .init_network(Network::new_unordered_nonduplicating([]))
+            .max_crashes(1)
+            .property(Expectation::Always, "agreement", |_, state| {
+                let decided: BTreeSet<_> = state
+                    .actor_states
+                    .iter()
+                    .zip(&state.crashed)
+                    .filter(|(_, crashed)| !**crashed)
+                    .filter_map(|(s, _)| s.wrapped_state.decided)
+                    .collect();
+                decided.len() <= 1
+            })
+            .property(Expectation::Always, "validity", |model, state| {
+                state.actor_states.iter().all(|s| {
+                    s.wrapped_state.decided.is_none_or(|value| {
+                        model
+                            .actors
+                            .iter()
+                            .any(|a| a.wrapped_actor.proposal == value)
+                    })
+                })
+            })
+            .property(Expectation::Sometimes, "all correct decided", |_, state| {
+                state
+                    .actor_states
+                    .iter()
+                    .zip(&state.crashed)
+                    .all(|(s, crashed)| *crashed || s.wrapped_state.decided.is_some())
+            })
+            .within_boundary(|cfg, state| is_eventually_accurate(cfg.max_false_suspicions, state))
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn can_model_hierarchical_consensus() {
+    // Correct with a perfect failure detector.
+    let checker = ConsensusModelCfg {
+        process_count: 3,
+        max_false_suspicions: 0,
+    }
+    .into_model()
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_properties();
+    assert_eq!(checker.unique_state_count(), 306_666);
+
+    // But not if the failure detector can suspect a correct process.
+    let checker = ConsensusModelCfg {
+        process_count: 2,
+        max_false_suspicions: 1,
+    }
+    .into_model()
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_no_discovery("validity");
+    checker.assert_any_discovery("agreement");
+}
+
+fn main() -> Result<(), pico_args::Error> {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    let mut args = pico_args::Arguments::from_env();
+    match args.subcommand()?.as_deref() {
+        Some("check") => {
+            let process_count = args.opt_free_from_str()?.unwrap_or(3);
+            let max_false_suspicions = args.opt_free_from_str()?.unwrap_or(0);
+            println!(
+                "Model checking hierarchical consensus with {} processes and up to {} false suspicions per process.",
+                process_count, max_false_suspicions
+            );
+            ConsensusModelCfg {
+                process_count,
+                max_false_suspicions,
+            }
+            .into_model()
+            .checker()
+            .threads(num_cpus::get())
+            .spawn_dfs()
+            .report(&mut WriteReporter::new(&mut std::io::stdout()));
+        }
+        Some("explore") => {
+            let process_count = args.opt_free_from_str()?.unwrap_or(3);
+            let max_false_suspicions = args.opt_free_from_str()?.unwrap_or(0);
+            let address = args
+                .opt_free_from_str()?
+                .unwrap_or("localhost:3000".to_string());
+            println!(
+                "Exploring state space for hierarchical consensus with {} processes on {}.",
+                process_count, address
+            );
+            ConsensusModelCfg {
+                process_count,
+                max_false_suspicions,
+            }
+            .into_model()
+            .checker()
+            .threads(num_cpus::get())
+            .serve(address);
+        }
+        _ => {
+            println!("USAGE:");
+            println!("  ./hierarchical-consensus check [PROCESS_COUNT] [MAX_FALSE_SUSPICIONS]");
+            println!(
+                "  ./hierarchical-consensus explore [PROCESS_COUNT] [MAX_FALSE_SUSPICIONS] [ADDRESS]"
+            );
+        }
+    }
+
+    Ok(())
+}
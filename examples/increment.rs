@@ -104,7 +104,11 @@
 //! State { i: 1, s: [{t: 0, pc: 3}, {t: 0, pc: 3}]}
 //! ```
 
-use stateright::{report::WriteReporter, *};
+use stateright::{
+    cli::{self, System},
+    report::WriteReporter,
+    *,
+};
 
 #[derive(Debug, Clone)]
 pub enum Action {
@@ -196,59 +200,39 @@ impl Model for State {
     }
 }
 
+impl System for State {
+    type Model = Self;
+    type Actor = ();
+
+    fn description(&self) -> String {
+        format!("increment with {} threads", self.s.len())
+    }
+
+    fn model(&self) -> Self::Model {
+        self.clone()
+    }
+}
+
 fn main() -> Result<(), pico_args::Error> {
     env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
 
+    // Symmetry reduction requires `Representative` states, so it is not offered by `cli`.
     let mut args = pico_args::Arguments::from_env();
-    match args.subcommand()?.as_deref() {
-        Some("check") => {
-            let thread_count = args.opt_free_from_str()?.unwrap_or(3);
-            println!(
-                "Model checking increment_lock with {} threads.",
-                thread_count
-            );
-
-            State::new(thread_count)
-                .checker()
-                .threads(num_cpus::get())
-                .spawn_dfs()
-                .report(&mut WriteReporter::new(&mut std::io::stdout()));
-        }
-        Some("check-sym") => {
-            let thread_count = args.opt_free_from_str()?.unwrap_or(3);
-            println!(
-                "Model checking increment_lock with {} threads using symmetry reduction.",
-                thread_count
-            );
-
-            State::new(thread_count)
-                .checker()
-                .threads(num_cpus::get())
-                .symmetry()
-                .spawn_dfs()
-                .report(&mut WriteReporter::new(&mut std::io::stdout()));
-        }
-        Some("explore") => {
-            let thread_count = args.opt_free_from_str()?.unwrap_or(3);
-            let address = args
-                .opt_free_from_str()?
-                .unwrap_or("localhost:3000".to_string());
-            println!(
-                "Exploring the state space of increment_lock with {} threads on {}.",
-                thread_count, address
-            );
-            State::new(thread_count)
-                .checker()
-                .threads(num_cpus::get())
-                .serve(address);
-        }
-        _ => {
-            println!("USAGE:");
-            println!("  ./increment check [THREAD_COUNT]");
-            println!("  ./increment check-sym [THREAD_COUNT] [full|sorted]");
-            println!("  ./increment explore [THREAD_COUNT] [ADDRESS]");
-        }
+    if args.subcommand()?.as_deref() == Some("check-sym") {
+        let thread_count = args.opt_free_from_str()?.unwrap_or(3);
+        println!(
+            "Model checking increment with {} threads using symmetry reduction.",
+            thread_count
+        );
+        State::new(thread_count)
+            .checker()
+            .threads(num_cpus::get())
+            .symmetry()
+            .spawn_dfs()
+            .report(&mut WriteReporter::new(&mut std::io::stdout()));
+        return Ok(());
     }
 
+    cli::run(|params| Ok(State::new(params.next_or("THREAD_COUNT", 3)?)));
     Ok(())
 }
@@ -0,0 +1,407 @@
+//! This is an implementation of a lease-based distributed lock that guards writes to a register,
+//! illustrating the classic expired-lease bug along with the fencing tokens that mitigate it.
+//!
+//! # The Algorithm
+//!
+//! A lock server grants the lock to one client at a time, along with a lease that expires after a
+//! fixed duration of the server's clock, at which point the server can grant the lock to another
+//! client. Each grant includes a fencing token that increases with every grant.
+//!
+//! A client that is granted the lock considers itself the holder until its lease expires, as
+//! measured by its own clock from the time it requested the lock, minus a safety margin. While
+//! holding the lock it writes to a register via [`RegisterMsg::Put`], whose request ID carries the
+//! fencing token. A client whose lease expires before the grant arrives requests the lock again.
+//!
+//! ## Clock Skew
+//!
+//! Every actor has its own clock, which advances independently of the others, but the model only
+//! explores states where the clocks differ by at most a configurable skew. If the safety margin is
+//! smaller than twice the skew, a client can believe it still holds the lock after the server has
+//! granted it to another client, violating mutual exclusion.
+//!
+//! ## Fencing
+//!
+//! Even with sufficient margin, a write can be delayed in the network until after the lease
+//! expires, so the register can receive writes out of order. A register that enforces fencing
+//! rejects writes whose tokens are older than the newest token it has accepted.
+
+use serde::{Deserialize, Serialize};
+use stateright::actor::properties::monotonic_sequence_numbers;
+use stateright::actor::register::RegisterMsg::{self, *};
+use stateright::actor::{model_timeout, Actor, ActorModel, Id, Network, Out};
+use stateright::report::WriteReporter;
+use stateright::{Checker, Expectation, Model};
+use std::borrow::Cow;
+
+type Time = u8;
+type Token = u64;
+type Value = char;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+enum LockMsg {
+    Acquire,
+    Grant { token: Token },
+}
+use LockMsg::*;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+enum LockTimer {
+    /// Advances the actor's clock.
+    Tick,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum ClientPhase {
+    Requested { at: Time },
+    Holding { token: Token, until: Time },
+    Done,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum LockState {
+    Server {
+        clock: Time,
+        holder: Option<(Id, Time)>,
+        last_token: Token,
+    },
+    Register {
+        last_token: Token,
+        value: Value,
+    },
+    Client {
+        clock: Time,
+        phase: ClientPhase,
+    },
+}
+
+impl LockState {
+    fn clock(&self) -> Option<Time> {
+        match self {
+            LockState::Server { clock, .. } | LockState::Client { clock, .. } => Some(*clock),
+            LockState::Register { .. } => None,
+        }
+    }
+
+    /// Whether a client considers itself the holder of the lock.
+    fn is_holding(&self) -> bool {
+        matches!(self,
+            LockState::Client { clock, phase: ClientPhase::Holding { until, .. } }
+                if clock < until)
+    }
+}
+
+#[derive(Clone)]
+enum LockActor {
+    Server {
+        lease_duration: Time,
+        max_time: Time,
+    },
+    Register {
+        fencing: bool,
+    },
+    Client {
+        server_id: Id,
+        register_id: Id,
+        lease_duration: Time,
+        safety_margin: Time,
+        max_time: Time,
+    },
+}
+
+impl LockActor {
+    fn max_time(&self) -> Time {
+        match self {
+            LockActor::Server { max_time, .. } | LockActor::Client { max_time, .. } => *max_time,
+            LockActor::Register { .. } => 0,
+        }
+    }
+}
+
+impl Actor for LockActor {
+    type Msg = RegisterMsg<Token, Value, LockMsg>;
+    type State = LockState;
+    type Timer = LockTimer;
+
+    fn name(&self) -> String {
+        match self {
+            LockActor::Server { .. } => "Lock Server".to_owned(),
+            LockActor::Register { .. } => "Register".to_owned(),
+            LockActor::Client { .. } => "Client".to_owned(),
+        }
+    }
+
+    fn on_start(&self, _id: Id, o: &mut Out<Self>) -> Self::State {
+        match self {
+            LockActor::Server { .. } => {
+                o.set_timer(LockTimer::Tick, model_timeout());
+                LockState::Server {
+                    clock: 0,
+                    holder: None,
+                    last_token: 0,
+                }
+            }
+            LockActor::Register { .. } => LockState::Register {
+                last_token: 0,
+                value: Value::default(),
+            },
+            LockActor::Client { server_id, .. } => {
+                o.set_timer(LockTimer::Tick, model_timeout());
+                o.send(*server_id, Internal(Acquire));
+                LockState::Client {
+                    clock: 0,
+                    phase: ClientPhase::Requested { at: 0 },
+                }
+            }
+        }
+    }
+
+    fn on_msg(
+        &self,
+        id: Id,
+        state: &mut Cow<Self::State>,
+        src: Id,
+        msg: Self::Msg,
+        o: &mut Out<Self>,
+    ) {
+        match (self, &**state, msg) {
+            (
+                LockActor::Server { lease_duration, .. },
+                &LockState::Server {
+                    clock,
+                    holder,
+                    last_token,
+                },
+                Internal(Acquire),
+            )
+                // Requests are left pending until the current lease expires, unless the lease is
+                // being renewed.
+                if holder
+                    .is_none_or(|(holder, expires_at)| holder == src || clock >= expires_at) =>
+            {
+                let token = last_token + 1;
+                *state.to_mut() = LockState::Server {
+                    clock,
+                    holder: Some((src, clock + lease_duration)),
+                    last_token: token,
+                };
+                o.send(src, Internal(Grant { token }));
+            }
+            (
+                LockActor::Register { fencing },
+                &LockState::Register { last_token, .. },
+                Put(token, value),
+            ) if !fencing || token >= last_token => {
+                *state.to_mut() = LockState::Register {
+                    last_token: token,
+                    value,
+                };
+                o.send(src, PutOk(token));
+            }
+            (
+                LockActor::Client {
+                    server_id,
+                    register_id,
+                    lease_duration,
+                    safety_margin,
+                    ..
+                },
+                &LockState::Client {
+                    clock,
+                    phase: ClientPhase::Requested { at },
+                },
+                Internal(Grant { token }),
+            ) => {
+                let until = (at + lease_duration).saturating_sub(*safety_margin);
+                let phase = if clock < until {
+                    let value = (b'A' + usize::from(id) as u8 - 2) as char;
+                    o.send(*register_id, Put(token, value));
+                    ClientPhase::Holding { token, until }
+                } else {
+                    // The lease expired in transit, so try again.
+                    o.send(*server_id, Internal(Acquire));
+                    ClientPhase::Requested { at: clock }
+                };
+                *state.to_mut() = LockState::Client { clock, phase };
+            }
+            (
+                LockActor::Client { .. },
+                &LockState::Client {
+                    clock,
+                    phase: ClientPhase::Holding { token, .. },
+                },
+                PutOk(acked),
+            ) if token == acked => {
+                *state.to_mut() = LockState::Client {
+                    clock,
+                    phase: ClientPhase::Done,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn on_timeout(
+        &self,
+        _id: Id,
+        state: &mut Cow<Self::State>,
+        timer: &Self::Timer,
+        o: &mut Out<Self>,
+    ) {
+        let LockTimer::Tick = timer;
+        match state.to_mut() {
+            LockState::Server { clock, .. } | LockState::Client { clock, .. } => *clock += 1,
+            LockState::Register { .. } => {}
+        }
+        if state.clock() < Some(self.max_time()) {
+            o.set_timer(LockTimer::Tick, model_timeout());
+        }
+    }
+}
+
+#[derive(Clone)]
+struct LockModelCfg {
+    client_count: usize,
+    lease_duration: Time,
+    safety_margin: Time,
+    max_skew: Time,
+    max_time: Time,
+    fencing: bool,
+}
+
+impl LockModelCfg {
+    fn into_model(self) -> ActorModel<LockActor, Self> {
+        let server_id = Id::from(0);
+        let register_id = Id::from(1);
+        let mut model = ActorModel::new(self.clone(), ())
+            .actor(LockActor::Server {
+                lease_duration: self.lease_duration,
+                max_time: self.max_time,
+            })
+            .actor(LockActor::Register {
+                fencing: self.fencing,
+            })
+            .actors((0..self.client_count).map(|_| LockActor::Client {
+                server_id,
+                register_id,
+                lease_duration: self.lease_duration,
+                safety_margin: self.safety_margin,
+                max_time: self.max_time,
+            }))
+            .init_network(Network::new_unordered_nonduplicating([]))
+            .property(Expectation::Always, "mutual exclusion", |_, state| {
+                state.actor_states.iter().filter(|s| s.is_holding()).count() <= 1
+            })
+            .property(Expectation::Sometimes, "lock handed over", |_, state| {
+                matches!(*state.actor_states[0], LockState::Server { last_token, .. } if last_token > 1)
+            })
+            .within_boundary(|cfg, state| {
+                let clocks = state.actor_states.iter().filter_map(|s| s.clock());
+                let min = clocks.clone().min().unwrap_or(0);
+                let max = clocks.max().unwrap_or(0);
+                max - min <= cfg.max_skew
+            });
+        model.properties.push(monotonic_sequence_numbers(
+            "monotonic fencing tokens",
+            |_, state: &LockState| match state {
+                LockState::Register { last_token, .. } => *last_token,
+                _ => 0,
+            },
+        ));
+        model
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn can_model_lease_lock() {
+    let cfg = LockModelCfg {
+        client_count: 2,
+        lease_duration: 3,
+        safety_margin: 2,
+        max_skew: 1,
+        max_time: 5,
+        fencing: true,
+    };
+
+    // A sufficient safety margin and fencing ensure safety.
+    let checker = cfg.clone().into_model().checker().spawn_bfs().join();
+    checker.assert_properties();
+    assert_eq!(checker.unique_state_count(), 32_838);
+
+    // Without a safety margin, clock skew leads a client to act upon an expired lease.
+    let checker = LockModelCfg {
+        safety_margin: 0,
+        ..cfg.clone()
+    }
+    .into_model()
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_any_discovery("mutual exclusion");
+    checker.assert_no_discovery("monotonic fencing tokens");
+
+    // Without fencing, a delayed write overwrites a newer one.
+    let checker = LockModelCfg {
+        fencing: false,
+        ..cfg
+    }
+    .into_model()
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_no_discovery("mutual exclusion");
+    checker.assert_any_discovery("monotonic fencing tokens");
+}
+
+fn main() -> Result<(), pico_args::Error> {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    let mut args = pico_args::Arguments::from_env();
+    let cfg = |args: &mut pico_args::Arguments| -> Result<LockModelCfg, pico_args::Error> {
+        Ok(LockModelCfg {
+            client_count: 2,
+            lease_duration: 3,
+            safety_margin: args.opt_value_from_str("--margin")?.unwrap_or(2),
+            max_skew: args.opt_value_from_str("--skew")?.unwrap_or(1),
+            max_time: 5,
+            fencing: !args.contains("--no-fencing"),
+        })
+    };
+    match args.subcommand()?.as_deref() {
+        Some("check") => {
+            let cfg = cfg(&mut args)?;
+            println!(
+                "Model checking a lease-based lock with safety margin {} and clock skew {}{}.",
+                cfg.safety_margin,
+                cfg.max_skew,
+                if cfg.fencing { "" } else { " without fencing" },
+            );
+            cfg.into_model()
+                .checker()
+                .threads(num_cpus::get())
+                .spawn_dfs()
+                .report(&mut WriteReporter::new(&mut std::io::stdout()));
+        }
+        Some("explore") => {
+            let cfg = cfg(&mut args)?;
+            let address = args
+                .opt_free_from_str()?
+                .unwrap_or("localhost:3000".to_string());
+            println!(
+                "Exploring state space for a lease-based lock with safety margin {} and clock skew {} on {}.",
+                cfg.safety_margin, cfg.max_skew, address
+            );
+            cfg.into_model()
+                .checker()
+                .threads(num_cpus::get())
+                .serve(address);
+        }
+        _ => {
+            println!("USAGE:");
+            println!("  ./lease-lock check [--margin N] [--skew N] [--no-fencing]");
+            println!("  ./lease-lock explore [--margin N] [--skew N] [--no-fencing] [ADDRESS]");
+        }
+    }
+
+    Ok(())
+}
@@ -10,11 +10,11 @@
 use serde::{Deserialize, Serialize};
 use stateright::actor::register::{RegisterActor, RegisterMsg, RegisterMsg::*};
 use stateright::actor::{majority, model_peers, Actor, ActorModel, Id, Network, Out};
-use stateright::report::WriteReporter;
+use stateright::cli::{self, System};
 use stateright::semantics::register::Register;
 use stateright::semantics::LinearizabilityTester;
 use stateright::util::{HashableHashMap, HashableHashSet};
-use stateright::{Checker, Expectation, Model};
+use stateright::Expectation;
 use std::borrow::Cow;
 use std::fmt::Debug;
 use std::hash::Hash;
@@ -257,6 +257,7 @@ impl AbdModelCfg {
 #[test]
 fn can_model_linearizable_register() {
     use stateright::actor::ActorModelAction::Deliver;
+    use stateright::{Checker, Model};
 
     // BFS
     let checker = AbdModelCfg {
@@ -313,115 +314,38 @@ fn can_model_linearizable_register() {
     assert_eq!(checker.unique_state_count(), 544);
 }
 
-fn main() -> Result<(), pico_args::Error> {
-    use stateright::actor::spawn;
-    use std::net::{Ipv4Addr, SocketAddrV4};
+impl System for AbdModelCfg {
+    type Model =
+        ActorModel<RegisterActor<AbdActor>, Self, LinearizabilityTester<Id, Register<Value>>>;
+    type Actor = AbdActor;
 
-    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
-
-    let mut args = pico_args::Arguments::from_env();
-    match args.subcommand()?.as_deref() {
-        Some("check") => {
-            let client_count = args.opt_free_from_str()?.unwrap_or(2);
-            let network = args
-                .opt_free_from_str()?
-                .unwrap_or(Network::new_unordered_nonduplicating([]));
-            println!(
-                "Model checking a linearizable register with {} clients.",
-                client_count
-            );
-            AbdModelCfg {
-                client_count,
-                server_count: 3,
-                network,
-            }
-            .into_model()
-            .checker()
-            .threads(num_cpus::get())
-            .spawn_dfs()
-            .report(&mut WriteReporter::new(&mut std::io::stdout()));
-        }
-        Some("explore") => {
-            let client_count = args.opt_free_from_str()?.unwrap_or(2);
-            let address = args
-                .opt_free_from_str()?
-                .unwrap_or("localhost:3000".to_string());
-            let network = args
-                .opt_free_from_str()?
-                .unwrap_or(Network::new_unordered_nonduplicating([]));
-            println!(
-                "Exploring state space for linearizable register with {} clients on {}.",
-                client_count, address
-            );
-            AbdModelCfg {
-                client_count,
-                server_count: 3,
-                network,
-            }
-            .into_model()
-            .checker()
-            .threads(num_cpus::get())
-            .serve(address);
-        }
-        Some("spawn") => {
-            let port = 3000;
+    fn description(&self) -> String {
+        format!("a linearizable register with {} clients", self.client_count)
+    }
 
-            println!("  A server that implements a linearizable register.");
-            println!("  You can monitor and interact using tcpdump and netcat.");
-            println!("  Use `tcpdump -D` if you see error `lo0: No such device exists`.");
-            println!("Examples:");
-            println!("$ sudo tcpdump -i lo0 -s 0 -nnX");
-            println!("$ nc -u localhost {}", port);
-            println!(
-                "{}",
-                serde_json::to_string(&RegisterMsg::Put::<RequestId, Value, ()>(1, 'X')).unwrap()
-            );
-            println!(
-                "{}",
-                serde_json::to_string(&RegisterMsg::Get::<RequestId, Value, ()>(2)).unwrap()
-            );
-            println!();
+    fn model(&self) -> Self::Model {
+        self.clone().into_model()
+    }
 
-            let id0 = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port));
-            let id1 = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port + 1));
-            let id2 = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port + 2));
-            spawn(
-                serde_json::to_vec,
-                |bytes| serde_json::from_slice(bytes),
-                vec![
-                    (
-                        id0,
-                        AbdActor {
-                            peers: vec![id1, id2],
-                        },
-                    ),
-                    (
-                        id1,
-                        AbdActor {
-                            peers: vec![id0, id2],
-                        },
-                    ),
-                    (
-                        id2,
-                        AbdActor {
-                            peers: vec![id0, id1],
-                        },
-                    ),
-                ],
-            )
-            .unwrap();
-        }
-        _ => {
-            println!("USAGE:");
-            println!("  ./linearizable-register check [CLIENT_COUNT] [NETWORK]");
-            println!("  ./linearizable-register explore [CLIENT_COUNT] [ADDRESS] [NETWORK]");
-            println!("  ./linearizable-register spawn");
-            println!(
-                "NETWORK: {}",
-                Network::<<AbdActor as Actor>::Msg>::names().join(" | ")
-            );
-        }
+    // WARNING: Omits `ordered_reliable_link` to keep the message
+    //          protocol simple for `nc`.
+    fn actors(&self, id: &dyn Fn(usize) -> Id) -> Vec<Self::Actor> {
+        (0..self.server_count)
+            .map(|i| AbdActor {
+                peers: (0..self.server_count).filter(|&j| j != i).map(id).collect(),
+            })
+            .collect()
     }
+}
 
-    Ok(())
+fn main() {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    cli::run(|params| {
+        Ok(AbdModelCfg {
+            client_count: params.next_or("CLIENT_COUNT", 2)?,
+            server_count: 3,
+            network: params.next_or("NETWORK", Network::new_unordered_nonduplicating([]))?,
+        })
+    });
 }
@@ -0,0 +1,625 @@
+//! This is an implementation of Multipaxos, which extends Single Decree Paxos (see `paxos.rs`) to
+//! agree upon a sequence of values, here a log of commands driving a key-value store.
+//!
+//! # The Algorithm
+//!
+//! The log is divided into slots, and each slot is decided by its own instance of Paxos. The
+//! instances share leadership terms (ballots), so a leader only needs to complete Phase 1 once
+//! for all slots, after which each command only costs a single Phase 2 round trip.
+//!
+//! ## Leadership Terms
+//!
+//! A server that receives a client request without leading starts a new term by broadcasting a
+//! `Prepare`. Each reply includes every proposal the replying server has accepted, so once a
+//! quorum replies, the new leader re-proposes the most recently accepted proposal for each slot
+//! that it has not seen decided, filling any gaps with no-ops. Only then does it propose new
+//! commands, starting with the requests it queued while becoming leader.
+//!
+//! ## Log Replication
+//!
+//! Once a quorum accepts the proposal for a slot, the leader broadcasts the decision. Every server
+//! applies decided commands to its copy of the key-value store in slot order, and the server that
+//! originally proposed a command replies to the requesting client, so each request receives
+//! exactly one reply.
+//!
+//! The clients record their requests and replies in a history that is checked for
+//! linearizability against a sequential key-value store.
+
+use serde::{Deserialize, Serialize};
+use stateright::actor::properties::monotonic_sequence_numbers;
+use stateright::actor::{
+    majority, model_peers, Actor, ActorModel, ActorModelState, Envelope, Id, Network, Out,
+};
+use stateright::report::WriteReporter;
+use stateright::semantics::transactional_kv::{TransactionalKv, TxnOp, TxnRet};
+use stateright::semantics::{ConsistencyTester, LinearizabilityTester};
+use stateright::{Checker, Expectation, Model};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+
+type Round = u32;
+type Ballot = (Round, Id);
+type Slot = usize;
+type RequestId = u64;
+type Key = char;
+type Value = char;
+
+/// The proposals a server has accepted, indexed by slot, where a `None` proposal is a no-op.
+type AcceptedProposals = BTreeMap<Slot, (Ballot, Option<Command>)>;
+
+type History = LinearizabilityTester<Id, TransactionalKv<Key, Value>>;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+enum KvMsg {
+    /// A message specific to the Multipaxos protocol.
+    Internal(MultipaxosMsg),
+
+    Put(RequestId, Key, Value),
+    Get(RequestId, Key),
+
+    PutOk(RequestId),
+    GetOk(RequestId, Option<Value>),
+}
+use KvMsg::*;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+enum KvOp {
+    Put(Key, Value),
+    Get(Key),
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+struct Command {
+    request_id: RequestId,
+    requester: Id,
+    /// The server responsible for replying once the command is applied.
+    proposer: Id,
+    op: KvOp,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+enum MultipaxosMsg {
+    Prepare {
+        ballot: Ballot,
+    },
+    Prepared {
+        ballot: Ballot,
+        accepted: AcceptedProposals,
+    },
+
+    Accept {
+        ballot: Ballot,
+        slot: Slot,
+        command: Option<Command>,
+    },
+    Accepted {
+        ballot: Ballot,
+        slot: Slot,
+    },
+
+    Decided {
+        slot: Slot,
+        command: Option<Command>,
+    },
+}
+use MultipaxosMsg::*;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum Role {
+    Follower,
+    Candidate {
+        prepares: BTreeMap<Id, AcceptedProposals>,
+    },
+    Leader {
+        next_slot: Slot,
+        proposals: BTreeMap<Slot, (Option<Command>, BTreeSet<Id>)>,
+    },
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct ServerState {
+    // acceptor state
+    ballot: Ballot,
+    accepted: AcceptedProposals,
+
+    // leader state
+    role: Role,
+    pending: Vec<Command>,
+
+    // learner state
+    decided: BTreeMap<Slot, Option<Command>>,
+    applied: Slot,
+    kv: BTreeMap<Key, Value>,
+}
+
+impl ServerState {
+    /// Adopts a newer ballot as a follower.
+    fn observe_ballot(&mut self, ballot: Ballot) {
+        if ballot > self.ballot {
+            self.ballot = ballot;
+            self.role = Role::Follower;
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum KvState {
+    Client { awaiting: Option<RequestId> },
+    Server(ServerState),
+}
+
+#[derive(Clone)]
+enum KvActor {
+    /// Writes a value and then reads it back from a different server.
+    Client {
+        server_count: usize,
+    },
+    Server {
+        peer_ids: Vec<Id>,
+    },
+}
+
+impl KvActor {
+    fn peer_ids(&self) -> &[Id] {
+        match self {
+            KvActor::Client { .. } => &[],
+            KvActor::Server { peer_ids } => peer_ids,
+        }
+    }
+
+    fn on_request(&self, id: Id, state: &mut ServerState, command: Command, o: &mut Out<Self>) {
+        match &mut state.role {
+            Role::Leader { next_slot, .. } => {
+                let slot = *next_slot;
+                *next_slot += 1;
+                self.propose(id, state, slot, Some(command), o);
+            }
+            Role::Candidate { .. } => state.pending.push(command),
+            Role::Follower => {
+                state.pending.push(command);
+                state.ballot = (state.ballot.0 + 1, id);
+                state.role = Role::Candidate {
+                    prepares: BTreeMap::from([(id, state.accepted.clone())]),
+                };
+                o.broadcast(
+                    self.peer_ids(),
+                    &Internal(Prepare {
+                        ballot: state.ballot,
+                    }),
+                );
+            }
+        }
+    }
+
+    /// Re-proposes the latest accepted proposal for each undecided slot reported by a quorum, and
+    /// then proposes the queued requests.
+    fn lead(&self, id: Id, state: &mut ServerState, o: &mut Out<Self>) {
+        let prepares = match &mut state.role {
+            Role::Candidate { prepares } => std::mem::take(prepares),
+            _ => return,
+        };
+        let mut latest = AcceptedProposals::new();
+        for (slot, (ballot, command)) in prepares.into_values().flatten() {
+            if latest.get(&slot).is_none_or(|(b, _)| ballot > *b) {
+                latest.insert(slot, (ballot, command));
+            }
+        }
+        let next_slot = 1 + latest
+            .keys()
+            .chain(state.decided.keys())
+            .max()
+            .copied()
+            .unwrap_or(0);
+        state.role = Role::Leader {
+            next_slot,
+            proposals: Default::default(),
+        };
+        for slot in 1..next_slot {
+            if !state.decided.contains_key(&slot) {
+                let command = latest.remove(&slot).and_then(|(_, command)| command);
+                self.propose(id, state, slot, command, o);
+            }
+        }
+        for command in std::mem::take(&mut state.pending) {
+            self.on_request(id, state, command, o);
+        }
+    }
+
+    fn propose(
+        &self,
+        id: Id,
+        state: &mut ServerState,
+        slot: Slot,
+        command: Option<Command>,
+        o: &mut Out<Self>,
+    ) {
+        state.accepted.insert(slot, (state.ballot, command.clone()));
+        if let Role::Leader { proposals, .. } = &mut state.role {
+            proposals.insert(slot, (command.clone(), BTreeSet::from([id])));
+        }
+        o.broadcast(
+            self.peer_ids(),
+            &Internal(Accept {
+                ballot: state.ballot,
+                slot,
+                command,
+            }),
+        );
+    }
+
+    /// Records a decision and applies every decided command that is next in the log.
+    fn learn(
+        &self,
+        id: Id,
+        state: &mut ServerState,
+        slot: Slot,
+        command: Option<Command>,
+        o: &mut Out<Self>,
+    ) {
+        state.decided.insert(slot, command);
+        while let Some(command) = state.decided.get(&(state.applied + 1)) {
+            state.applied += 1;
+            let Some(command) = command else { continue };
+            let reply = match command.op {
+                KvOp::Put(key, value) => {
+                    state.kv.insert(key, value);
+                    PutOk(command.request_id)
+                }
+                KvOp::Get(key) => GetOk(command.request_id, state.kv.get(&key).copied()),
+            };
+            if command.proposer == id {
+                o.send(command.requester, reply);
+            }
+        }
+    }
+}
+
+impl Actor for KvActor {
+    type Msg = KvMsg;
+    type State = KvState;
+    type Timer = ();
+
+    fn name(&self) -> String {
+        match self {
+            KvActor::Client { .. } => "Client".to_owned(),
+            KvActor::Server { .. } => "Multipaxos Server".to_owned(),
+        }
+    }
+
+    fn on_start(&self, id: Id, o: &mut Out<Self>) -> Self::State {
+        match self {
+            KvActor::Client { server_count } => {
+                let index = usize::from(id);
+                let value = (b'A' + (index - server_count) as u8) as char;
+                o.send(
+                    Id::from(index % server_count),
+                    Put(index as RequestId, 'x', value),
+                );
+                KvState::Client {
+                    awaiting: Some(index as RequestId),
+                }
+            }
+            KvActor::Server { .. } => KvState::Server(ServerState {
+                ballot: (0, Id::from(0)),
+                accepted: Default::default(),
+
+                role: Role::Follower,
+                pending: Vec::new(),
+
+                decided: Default::default(),
+                applied: 0,
+                kv: Default::default(),
+            }),
+        }
+    }
+
+    fn on_msg(
+        &self,
+        id: Id,
+        state: &mut Cow<Self::State>,
+        src: Id,
+        msg: Self::Msg,
+        o: &mut Out<Self>,
+    ) {
+        if let (KvActor::Client { server_count }, KvState::Client { awaiting }) = (self, &**state) {
+            match msg {
+                PutOk(request_id) if Some(request_id) == *awaiting => {
+                    let index = usize::from(id);
+                    let request_id = 2 * index as RequestId;
+                    o.send(Id::from((index + 1) % server_count), Get(request_id, 'x'));
+                    *state.to_mut() = KvState::Client {
+                        awaiting: Some(request_id),
+                    };
+                }
+                GetOk(request_id, _) if Some(request_id) == *awaiting => {
+                    *state.to_mut() = KvState::Client { awaiting: None };
+                }
+                _ => {}
+            }
+            return;
+        }
+        let KvState::Server(state) = state.to_mut() else {
+            return;
+        };
+        let request = |request_id, op| Command {
+            request_id,
+            requester: src,
+            proposer: id,
+            op,
+        };
+        match msg {
+            Put(request_id, key, value) => {
+                self.on_request(id, state, request(request_id, KvOp::Put(key, value)), o);
+            }
+            Get(request_id, key) => {
+                self.on_request(id, state, request(request_id, KvOp::Get(key)), o);
+            }
+            Internal(Prepare { ballot }) => {
+                state.observe_ballot(ballot);
+                if ballot == state.ballot {
+                    o.send(
+                        src,
+                        Internal(Prepared {
+                            ballot,
+                            accepted: state.accepted.clone(),
+                        }),
+                    );
+                }
+            }
+            Internal(Prepared { ballot, accepted }) if ballot == state.ballot => {
+                if let Role::Candidate { prepares } = &mut state.role {
+                    prepares.insert(src, accepted);
+                    if prepares.len() == majority(self.peer_ids().len() + 1) {
+                        self.lead(id, state, o);
+                    }
+                }
+            }
+            Internal(Accept {
+                ballot,
+                slot,
+                command,
+            }) => {
+                state.observe_ballot(ballot);
+                if ballot == state.ballot {
+                    state.accepted.insert(slot, (ballot, command));
+                    o.send(src, Internal(Accepted { ballot, slot }));
+                }
+            }
+            Internal(Accepted { ballot, slot }) if ballot == state.ballot => {
+                let Role::Leader { proposals, .. } = &mut state.role else {
+                    return;
+                };
+                let Some((_, accepts)) = proposals.get_mut(&slot) else {
+                    return;
+                };
+                accepts.insert(src);
+                if accepts.len() == majority(self.peer_ids().len() + 1) {
+                    let (command, _) = proposals.remove(&slot).unwrap();
+                    o.broadcast(
+                        self.peer_ids(),
+                        &Internal(Decided {
+                            slot,
+                            command: command.clone(),
+                        }),
+                    );
+                    self.learn(id, state, slot, command, o);
+                }
+            }
+            Internal(Decided { slot, command }) if !state.decided.contains_key(&slot) => {
+                self.learn(id, state, slot, command, o);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Records a single-operation transaction upon each client request.
+fn record_invocations(
+    _cfg: &KvModelCfg,
+    history: &History,
+    env: Envelope<&KvMsg>,
+) -> Option<History> {
+    let op = match env.msg {
+        Put(_, key, value) => TxnOp::Write(*key, *value),
+        Get(_, key) => TxnOp::Read(*key),
+        _ => return None,
+    };
+    let mut history = history.clone();
+    let _ = history.on_invoke(env.src, vec![op]);
+    Some(history)
+}
+
+/// Records the result of a single-operation transaction upon each reply to a client.
+fn record_returns(_cfg: &KvModelCfg, history: &History, env: Envelope<&KvMsg>) -> Option<History> {
+    let ret = match env.msg {
+        PutOk(_) => TxnRet::WriteOk,
+        GetOk(_, value) => TxnRet::ReadOk(*value),
+        _ => return None,
+    };
+    let mut history = history.clone();
+    let _ = history.on_return(env.dst, vec![ret]);
+    Some(history)
+}
+
+/// The decided log of each server.
+fn decided_logs(
+    state: &ActorModelState<KvActor, History>,
+) -> impl Iterator<Item = &BTreeMap<Slot, Option<Command>>> {
+    state.actor_states.iter().filter_map(|s| match &**s {
+        KvState::Server(s) => Some(&s.decided),
+        KvState::Client { .. } => None,
+    })
+}
+
+#[derive(Clone)]
+struct KvModelCfg {
+    client_count: usize,
+    server_count: usize,
+    network: Network<<KvActor as Actor>::Msg>,
+}
+
+impl KvModelCfg {
+    fn into_model(self) -> ActorModel<KvActor, Self, History> {
+        let mut model = ActorModel::new(self.clone(), History::default())
+            .actors((0..self.server_count).map(|i| KvActor::Server {
+                peer_ids: model_peers(i, self.server_count),
+            }))
+            .actors((0..self.client_count).map(|_| KvActor::Client {
+                server_count: self.server_count,
+            }))
+            .init_network(self.network)
+            .property(Expectation::Always, "linearizable", |_, state| {
+                state.history.serialized_history().is_some()
+            })
+            .property(Expectation::Always, "log agreement", |_, state| {
+                decided_logs(state).all(|a| {
+                    decided_logs(state).all(|b| {
+                        a.iter()
+                            .all(|(slot, command)| b.get(slot).is_none_or(|c| c == command))
+                    })
+                })
+            })
+            .property(Expectation::Sometimes, "value chosen", |_, state| {
+                state
+                    .network
+                    .iter_deliverable()
+                    .any(|env| matches!(env.msg, GetOk(_, Some(_))))
+            })
+            .record_msg_in(record_returns)
+            .record_msg_out(record_invocations);
+        model.properties.push(monotonic_sequence_numbers(
+            "monotonic ballots",
+            |_, state: &KvState| match state {
+                KvState::Server(s) => Some(s.ballot),
+                KvState::Client { .. } => None,
+            },
+        ));
+        model
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn can_model_multipaxos_kv() {
+    let checker = KvModelCfg {
+        client_count: 1,
+        server_count: 3,
+        network: Network::new_ordered([]),
+    }
+    .into_model()
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_properties();
+    assert_eq!(checker.unique_state_count(), 6_037);
+
+    // The state space is too large to exhaust with concurrent clients, so the depth is bounded.
+    let checker = KvModelCfg {
+        client_count: 2,
+        server_count: 3,
+        network: Network::new_ordered([]),
+    }
+    .into_model()
+    .checker()
+    .target_max_depth(16)
+    .spawn_dfs()
+    .join();
+    checker.assert_no_discovery("linearizable");
+    checker.assert_no_discovery("log agreement");
+    checker.assert_no_discovery("monotonic ballots");
+}
+
+fn main() -> Result<(), pico_args::Error> {
+    use stateright::actor::spawn;
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    let mut args = pico_args::Arguments::from_env();
+    match args.subcommand()?.as_deref() {
+        Some("check") => {
+            let client_count = args.opt_free_from_str()?.unwrap_or(2);
+            let network = args
+                .opt_free_from_str()?
+                .unwrap_or(Network::new_unordered_nonduplicating([]));
+            println!(
+                "Model checking a Multipaxos key-value store with {} clients.",
+                client_count
+            );
+            KvModelCfg {
+                client_count,
+                server_count: 3,
+                network,
+            }
+            .into_model()
+            .checker()
+            .threads(num_cpus::get())
+            .spawn_dfs()
+            .report(&mut WriteReporter::new(&mut std::io::stdout()));
+        }
+        Some("explore") => {
+            let client_count = args.opt_free_from_str()?.unwrap_or(2);
+            let address = args
+                .opt_free_from_str()?
+                .unwrap_or("localhost:3000".to_string());
+            let network = args
+                .opt_free_from_str()?
+                .unwrap_or(Network::new_unordered_nonduplicating([]));
+            println!(
+                "Exploring state space for a Multipaxos key-value store with {} clients on {}.",
+                client_count, address
+            );
+            KvModelCfg {
+                client_count,
+                server_count: 3,
+                network,
+            }
+            .into_model()
+            .checker()
+            .threads(num_cpus::get())
+            .serve(address);
+        }
+        Some("spawn") => {
+            let port = 3000;
+
+            println!("  A set of servers that implement a Multipaxos key-value store.");
+            println!("  You can monitor and interact using tcpdump and netcat.");
+            println!("  Use `tcpdump -D` if you see error `lo0: No such device exists`.");
+            println!("Examples:");
+            println!("$ sudo tcpdump -i lo0 -s 0 -nnX");
+            println!("$ nc -u localhost {}", port);
+            println!("{}", serde_json::to_string(&Put(1, 'x', 'X')).unwrap());
+            println!("{}", serde_json::to_string(&Get(2, 'x')).unwrap());
+            println!();
+
+            // WARNING: Omits `ordered_reliable_link` to keep the message
+            //          protocol simple for `nc`.
+            let ids: Vec<_> = (0..3)
+                .map(|i| Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port + i)))
+                .collect();
+            spawn(
+                serde_json::to_vec,
+                |bytes| serde_json::from_slice(bytes),
+                ids.iter()
+                    .map(|&id| {
+                        let peer_ids = ids.iter().copied().filter(|&p| p != id).collect();
+                        (id, KvActor::Server { peer_ids })
+                    })
+                    .collect(),
+            )
+            .unwrap();
+        }
+        _ => {
+            println!("USAGE:");
+            println!("  ./multipaxos-kv check [CLIENT_COUNT] [NETWORK]");
+            println!("  ./multipaxos-kv explore [CLIENT_COUNT] [ADDRESS] [NETWORK]");
+            println!("  ./multipaxos-kv spawn");
+            println!(
+                "NETWORK: {}",
+                Network::<<KvActor as Actor>::Msg>::names().join(" | ")
+            );
+        }
+    }
+
+    Ok(())
+}
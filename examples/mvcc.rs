@@ -0,0 +1,466 @@
+//! This is an implementation of a multi-version key-value store providing
+//! [snapshot isolation](https://en.wikipedia.org/wiki/Snapshot_isolation), illustrating the write
+//! skew anomaly that distinguishes snapshot isolation from serializability.
+//!
+//! # The Algorithm
+//!
+//! The store retains every committed version of each key, tagged with a commit timestamp. A
+//! transaction reads from a snapshot as of the last commit when it began (along with its own
+//! writes), and its writes are buffered until it commits. Upon commit, the store aborts the
+//! transaction if another transaction has committed a newer version of any key that it wrote
+//! since it began ("first committer wins"), and otherwise installs its writes under a new
+//! timestamp.
+//!
+//! ## Write Skew
+//!
+//! Two doctors are on call (`x` and `y`), and each may go off call as long as the other remains on
+//! call. Each doctor runs a transaction that reads both keys and then writes its own key if both
+//! doctors are on call. The transactions write different keys, so snapshot isolation allows both
+//! to commit, leaving nobody on call, which no serial execution allows.
+//!
+//! Optionally validating that the keys a transaction read are also unchanged since it began, as
+//! with optimistic concurrency control, makes the store serializable.
+
+use stateright::actor::{Actor, ActorModel, Id, Network, Out};
+use stateright::report::WriteReporter;
+use stateright::semantics::transactional_kv::{TransactionalKv, TxnOp, TxnRet};
+use stateright::semantics::{ConsistencyTester, SequentialSpec, SerializabilityTester};
+use stateright::{Checker, Expectation, Model};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+type Timestamp = u8;
+type Key = char;
+type Value = u8;
+type Kv = TransactionalKv<Key, Value>;
+type Ops = Vec<TxnOp<Key, Value>>;
+type Rets = Vec<TxnRet<Value>>;
+
+const ON_CALL: Value = 1;
+const OFF_CALL: Value = 0;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum MvccMsg {
+    Begin,
+    Read(Key),
+    Write(Key, Value),
+    /// Includes the transaction's operations to record them in the history.
+    Commit(Ops),
+
+    ReadOk(Key, Option<Value>),
+    CommitOk(Rets),
+    Aborted,
+}
+use MvccMsg::*;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct Txn {
+    start: Timestamp,
+    writes: BTreeMap<Key, Value>,
+    /// The results of the transaction's operations, in order.
+    rets: Rets,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct StoreState {
+    versions: BTreeMap<Key, Vec<(Timestamp, Value)>>,
+    last_commit: Timestamp,
+    txns: BTreeMap<Id, Txn>,
+}
+
+impl StoreState {
+    fn latest(&self, key: Key) -> Option<&(Timestamp, Value)> {
+        self.versions.get(&key).and_then(|versions| versions.last())
+    }
+
+    fn read(&self, key: Key, at: Timestamp) -> Option<Value> {
+        self.versions
+            .get(&key)?
+            .iter()
+            .rev()
+            .find(|(ts, _)| *ts <= at)
+            .map(|(_, value)| *value)
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum DoctorState {
+    Reading { ops: Ops, on_call: usize },
+    Committing,
+    Done,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum MvccState {
+    Store(StoreState),
+    Doctor(DoctorState),
+}
+
+#[derive(Clone)]
+enum MvccActor {
+    Store {
+        init: BTreeMap<Key, Value>,
+        validate_reads: bool,
+    },
+    Doctor {
+        store_id: Id,
+        keys: Vec<Key>,
+        own_key: Key,
+    },
+}
+
+impl Actor for MvccActor {
+    type Msg = MvccMsg;
+    type State = MvccState;
+    type Timer = ();
+
+    fn name(&self) -> String {
+        match self {
+            MvccActor::Store { .. } => "Store".to_owned(),
+            MvccActor::Doctor { .. } => "Doctor".to_owned(),
+        }
+    }
+
+    fn on_start(&self, _id: Id, o: &mut Out<Self>) -> Self::State {
+        match self {
+            MvccActor::Store { init, .. } => MvccState::Store(StoreState {
+                versions: init.iter().map(|(&k, &v)| (k, vec![(0, v)])).collect(),
+                last_commit: 0,
+                txns: Default::default(),
+            }),
+            MvccActor::Doctor { store_id, keys, .. } => {
+                o.send(*store_id, Begin);
+                o.send(*store_id, Read(keys[0]));
+                MvccState::Doctor(DoctorState::Reading {
+                    ops: Vec::new(),
+                    on_call: 0,
+                })
+            }
+        }
+    }
+
+    fn on_msg(
+        &self,
+        _id: Id,
+        state: &mut Cow<Self::State>,
+        src: Id,
+        msg: Self::Msg,
+        o: &mut Out<Self>,
+    ) {
+        match (self, state.to_mut()) {
+            (MvccActor::Store { validate_reads, .. }, MvccState::Store(store)) => match msg {
+                Begin => {
+                    let txn = Txn {
+                        start: store.last_commit,
+                        writes: Default::default(),
+                        rets: Vec::new(),
+                    };
+                    store.txns.insert(src, txn);
+                }
+                Read(key) => {
+                    let Some(txn) = store.txns.get(&src) else {
+                        return;
+                    };
+                    let value = match txn.writes.get(&key) {
+                        Some(value) => Some(*value),
+                        None => store.read(key, txn.start),
+                    };
+                    store
+                        .txns
+                        .get_mut(&src)
+                        .unwrap()
+                        .rets
+                        .push(TxnRet::ReadOk(value));
+                    o.send(src, ReadOk(key, value));
+                }
+                Write(key, value) => {
+                    let Some(txn) = store.txns.get_mut(&src) else {
+                        return;
+                    };
+                    txn.writes.insert(key, value);
+                    txn.rets.push(TxnRet::WriteOk);
+                }
+                Commit(ops) => {
+                    let Some(txn) = store.txns.remove(&src) else {
+                        return;
+                    };
+                    let is_stale =
+                        |key: &Key| store.latest(*key).is_some_and(|(ts, _)| *ts > txn.start);
+                    let read_keys = ops.iter().filter_map(|op| match op {
+                        TxnOp::Read(key) => Some(key),
+                        TxnOp::Write(..) => None,
+                    });
+                    if txn.writes.keys().any(is_stale)
+                        || (*validate_reads && read_keys.clone().any(is_stale))
+                    {
+                        o.send(src, Aborted);
+                        return;
+                    }
+                    store.last_commit += 1;
+                    for (key, value) in txn.writes {
+                        store
+                            .versions
+                            .entry(key)
+                            .or_default()
+                            .push((store.last_commit, value));
+                    }
+                    o.send(src, CommitOk(txn.rets));
+                }
+                ReadOk(..) | CommitOk(_) | Aborted => {}
+            },
+            (
+                MvccActor::Doctor {
+                    store_id,
+                    keys,
+                    own_key,
+                },
+                MvccState::Doctor(doctor),
+            ) => match (msg, &mut *doctor) {
+                (ReadOk(key, value), DoctorState::Reading { ops, on_call }) => {
+                    ops.push(TxnOp::Read(key));
+                    if value == Some(ON_CALL) {
+                        *on_call += 1;
+                    }
+                    if let Some(&next_key) = keys.get(ops.len()) {
+                        o.send(*store_id, Read(next_key));
+                        return;
+                    }
+                    let mut ops = std::mem::take(ops);
+                    if *on_call == keys.len() {
+                        o.send(*store_id, Write(*own_key, OFF_CALL));
+                        ops.push(TxnOp::Write(*own_key, OFF_CALL));
+                    }
+                    o.send(*store_id, Commit(ops));
+                    *doctor = DoctorState::Committing;
+                }
+                (CommitOk(_) | Aborted, DoctorState::Committing) => {
+                    *doctor = DoctorState::Done;
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+/// A history of transactions, which can be checked for serializability and snapshot isolation.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct TxnHistory {
+    init: Kv,
+    serializability: SerializabilityTester<Id, Kv>,
+    completed: Vec<(Ops, Rets)>,
+    in_flight: BTreeMap<Id, Ops>,
+}
+
+impl TxnHistory {
+    fn new(init: Kv) -> Self {
+        TxnHistory {
+            serializability: SerializabilityTester::new(init.clone()),
+            init,
+            completed: Vec::new(),
+            in_flight: Default::default(),
+        }
+    }
+
+    /// Indicates whether the transactions can be totally ordered such that each reads from a
+    /// snapshot comprising a prefix of that order, and each observes every earlier transaction
+    /// that wrote any key it wrote. Transactions that never returned may or may not have
+    /// committed.
+    fn is_snapshot_isolated(&self) -> bool {
+        let remaining: Vec<_> = self
+            .completed
+            .iter()
+            .map(|(ops, rets)| (ops, Some(rets)))
+            .chain(self.in_flight.values().map(|ops| (ops, None)))
+            .collect();
+        Self::order(&mut vec![self.init.clone()], &mut Vec::new(), &remaining)
+    }
+
+    fn order(
+        snapshots: &mut Vec<Kv>,
+        write_sets: &mut Vec<Vec<Key>>,
+        remaining: &[(&Ops, Option<&Rets>)],
+    ) -> bool {
+        if remaining.iter().all(|(_, rets)| rets.is_none()) {
+            return true;
+        }
+        for (i, &(ops, rets)) in remaining.iter().enumerate() {
+            let writes: Vec<_> = ops
+                .iter()
+                .filter_map(|op| match op {
+                    TxnOp::Write(key, _) => Some(*key),
+                    TxnOp::Read(_) => None,
+                })
+                .collect();
+            // The snapshot must include every earlier transaction with an overlapping write.
+            let earliest_snapshot = write_sets
+                .iter()
+                .rposition(|earlier| earlier.iter().any(|key| writes.contains(key)))
+                .map_or(0, |position| position + 1);
+            let is_valid = match rets {
+                None => true,
+                Some(rets) => (earliest_snapshot..snapshots.len())
+                    .any(|s| snapshots[s].clone().is_valid_step(ops, rets)),
+            };
+            if !is_valid {
+                continue;
+            }
+            let mut next = snapshots.last().unwrap().clone();
+            for op in ops {
+                if let TxnOp::Write(..) = op {
+                    next.invoke(&vec![op.clone()]);
+                }
+            }
+            let mut remaining = remaining.to_vec();
+            remaining.remove(i);
+            snapshots.push(next);
+            write_sets.push(writes);
+            let is_ordered = Self::order(snapshots, write_sets, &remaining);
+            snapshots.pop();
+            write_sets.pop();
+            if is_ordered {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+fn record_invocations(
+    _cfg: &MvccModelCfg,
+    history: &TxnHistory,
+    env: stateright::actor::Envelope<&MvccMsg>,
+) -> Option<TxnHistory> {
+    let Commit(ops) = env.msg else {
+        return None;
+    };
+    let mut history = history.clone();
+    let _ = history.serializability.on_invoke(env.src, ops.clone());
+    history.in_flight.insert(env.src, ops.clone());
+    Some(history)
+}
+
+fn record_returns(
+    _cfg: &MvccModelCfg,
+    history: &TxnHistory,
+    env: stateright::actor::Envelope<&MvccMsg>,
+) -> Option<TxnHistory> {
+    let CommitOk(rets) = env.msg else {
+        return None;
+    };
+    let mut history = history.clone();
+    let _ = history.serializability.on_return(env.dst, rets.clone());
+    let ops = history.in_flight.remove(&env.dst)?;
+    history.completed.push((ops, rets.clone()));
+    Some(history)
+}
+
+#[derive(Clone)]
+struct MvccModelCfg {
+    validate_reads: bool,
+}
+
+impl MvccModelCfg {
+    fn into_model(self) -> ActorModel<MvccActor, Self, TxnHistory> {
+        let keys = vec!['x', 'y'];
+        let init: BTreeMap<_, _> = keys.iter().map(|&k| (k, ON_CALL)).collect();
+        let store_id = Id::from(0);
+        ActorModel::new(self.clone(), TxnHistory::new(TransactionalKv(init.clone())))
+            .actor(MvccActor::Store {
+                init,
+                validate_reads: self.validate_reads,
+            })
+            .actors(keys.iter().map(|&own_key| MvccActor::Doctor {
+                store_id,
+                keys: keys.clone(),
+                own_key,
+            }))
+            .init_network(Network::new_ordered([]))
+            .property(Expectation::Always, "snapshot isolation", |_, state| {
+                state.history.is_snapshot_isolated()
+            })
+            .property(Expectation::Always, "serializable", |_, state| {
+                state.history.serializability.serialized_history().is_some()
+            })
+            .property(Expectation::Sometimes, "doctor off call", |_, state| {
+                state
+                    .history
+                    .completed
+                    .iter()
+                    .any(|(ops, _)| ops.iter().any(|op| matches!(op, TxnOp::Write(_, OFF_CALL))))
+            })
+            .record_msg_in(record_returns)
+            .record_msg_out(record_invocations)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn can_model_mvcc() {
+    // Snapshot isolation permits write skew, which is not serializable.
+    let checker = MvccModelCfg {
+        validate_reads: false,
+    }
+    .into_model()
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_no_discovery("snapshot isolation");
+    checker.assert_any_discovery("serializable");
+    checker.assert_any_discovery("doctor off call");
+    assert_eq!(checker.unique_state_count(), 117);
+
+    // Validating reads upon commit prevents write skew.
+    let checker = MvccModelCfg {
+        validate_reads: true,
+    }
+    .into_model()
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_properties();
+    assert_eq!(checker.unique_state_count(), 115);
+}
+
+fn main() -> Result<(), pico_args::Error> {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    let mut args = pico_args::Arguments::from_env();
+    let validate_reads = args.contains("--validate-reads");
+    match args.subcommand()?.as_deref() {
+        Some("check") => {
+            println!(
+                "Model checking an MVCC store{}.",
+                if validate_reads {
+                    " that validates reads"
+                } else {
+                    ""
+                }
+            );
+            MvccModelCfg { validate_reads }
+                .into_model()
+                .checker()
+                .threads(num_cpus::get())
+                .spawn_dfs()
+                .report(&mut WriteReporter::new(&mut std::io::stdout()));
+        }
+        Some("explore") => {
+            let address = args
+                .opt_free_from_str()?
+                .unwrap_or("localhost:3000".to_string());
+            println!("Exploring state space for an MVCC store on {}.", address);
+            MvccModelCfg { validate_reads }
+                .into_model()
+                .checker()
+                .threads(num_cpus::get())
+                .serve(address);
+        }
+        _ => {
+            println!("USAGE:");
+            println!("  ./mvcc check [--validate-reads]");
+            println!("  ./mvcc explore [--validate-reads] [ADDRESS]");
+        }
+    }
+
+    Ok(())
+}
@@ -49,11 +49,11 @@
 use serde::{Deserialize, Serialize};
 use stateright::actor::register::{RegisterActor, RegisterMsg, RegisterMsg::*};
 use stateright::actor::{majority, model_peers, Actor, ActorModel, Id, Network, Out};
-use stateright::report::WriteReporter;
+use stateright::cli::{self, System};
 use stateright::semantics::register::Register;
 use stateright::semantics::LinearizabilityTester;
 use stateright::util::{HashableHashMap, HashableHashSet};
-use stateright::{Checker, Expectation, Model};
+use stateright::{Expectation, Model};
 use std::borrow::Cow;
 
 type Round = u32;
@@ -87,7 +87,7 @@ enum PaxosMsg {
 }
 use PaxosMsg::*;
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize)]
 struct PaxosState {
     // shared state
     ballot: Ballot,
@@ -299,6 +299,7 @@ impl PaxosModelCfg {
 #[test]
 fn can_model_paxos() {
     use stateright::actor::ActorModelAction::Deliver;
+    use stateright::Checker;
 
     // BFS
     let checker = PaxosModelCfg {
@@ -349,117 +350,61 @@ fn can_model_paxos() {
     assert_eq!(checker.unique_state_count(), 16_668);
 }
 
-fn main() -> Result<(), pico_args::Error> {
-    use stateright::actor::spawn;
-    use std::net::{Ipv4Addr, SocketAddrV4};
+impl System for PaxosModelCfg {
+    type Model =
+        ActorModel<RegisterActor<PaxosActor>, Self, LinearizabilityTester<Id, Register<Value>>>;
+    type Actor = PaxosActor;
+
+    fn description(&self) -> String {
+        format!("Single Decree Paxos with {} clients", self.client_count)
+    }
+
+    fn model(&self) -> Self::Model {
+        self.clone().into_model()
+    }
+
+    // WARNING: Omits `ordered_reliable_link` to keep the message
+    //          protocol simple for `nc`.
+    fn actors(&self, id: &dyn Fn(usize) -> Id) -> Vec<Self::Actor> {
+        (0..self.server_count)
+            .map(|i| PaxosActor {
+                peer_ids: (0..self.server_count).filter(|&j| j != i).map(id).collect(),
+            })
+            .collect()
+    }
+}
 
+fn main() -> Result<(), pico_args::Error> {
     env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
 
     let mut args = pico_args::Arguments::from_env();
-    match args.subcommand()?.as_deref() {
-        Some("check") => {
-            let client_count = args.opt_free_from_str()?.unwrap_or(2);
-            let network = args
-                .opt_free_from_str()?
-                .unwrap_or(Network::new_unordered_nonduplicating([]));
-            println!(
-                "Model checking Single Decree Paxos with {} clients.",
-                client_count
-            );
-            PaxosModelCfg {
-                client_count,
-                server_count: 3,
-                network,
-            }
-            .into_model()
-            .checker()
-            .threads(num_cpus::get())
-            .spawn_dfs()
-            .report(&mut WriteReporter::new(&mut std::io::stdout()));
-        }
-        Some("explore") => {
-            let client_count = args.opt_free_from_str()?.unwrap_or(2);
-            let address = args
-                .opt_free_from_str()?
-                .unwrap_or("localhost:3000".to_string());
-            let network = args
-                .opt_free_from_str()?
-                .unwrap_or(Network::new_unordered_nonduplicating([]));
-            println!(
-                "Exploring state space for Single Decree Paxos with {} clients on {}.",
-                client_count, address
-            );
-            PaxosModelCfg {
-                client_count,
-                server_count: 3,
-                network,
-            }
-            .into_model()
-            .checker()
-            .threads(num_cpus::get())
-            .serve(address);
-        }
-        Some("spawn") => {
-            let port = 3000;
-
-            println!("  A set of servers that implement Single Decree Paxos.");
-            println!("  You can monitor and interact using tcpdump and netcat.");
-            println!("  Use `tcpdump -D` if you see error `lo0: No such device exists`.");
-            println!("Examples:");
-            println!("$ sudo tcpdump -i lo0 -s 0 -nnX");
-            println!("$ nc -u localhost {}", port);
-            println!(
-                "{}",
-                serde_json::to_string(&RegisterMsg::Put::<RequestId, Value, ()>(1, 'X')).unwrap()
-            );
-            println!(
-                "{}",
-                serde_json::to_string(&RegisterMsg::Get::<RequestId, Value, ()>(2)).unwrap()
-            );
-            println!();
-
-            // WARNING: Omits `ordered_reliable_link` to keep the message
-            //          protocol simple for `nc`.
-            let id0 = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port));
-            let id1 = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port + 1));
-            let id2 = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port + 2));
-            spawn(
-                serde_json::to_vec,
-                |bytes| serde_json::from_slice(bytes),
-                vec![
-                    (
-                        id0,
-                        PaxosActor {
-                            peer_ids: vec![id1, id2],
-                        },
-                    ),
-                    (
-                        id1,
-                        PaxosActor {
-                            peer_ids: vec![id0, id2],
-                        },
-                    ),
-                    (
-                        id2,
-                        PaxosActor {
-                            peer_ids: vec![id0, id1],
-                        },
-                    ),
-                ],
-            )
-            .unwrap();
-        }
-        _ => {
-            println!("USAGE:");
-            println!("  ./paxos check [CLIENT_COUNT] [NETWORK]");
-            println!("  ./paxos explore [CLIENT_COUNT] [ADDRESS] [NETWORK]");
-            println!("  ./paxos spawn");
-            println!(
-                "NETWORK: {}",
-                Network::<<PaxosActor as Actor>::Msg>::names().join(" | ")
-            );
+    if args.subcommand()?.as_deref() == Some("profile") {
+        let client_count = args.opt_free_from_str()?.unwrap_or(2);
+        let network = args
+            .opt_free_from_str()?
+            .unwrap_or(Network::new_unordered_nonduplicating([]));
+        println!(
+            "Profiling the state space of Single Decree Paxos with {} clients.",
+            client_count
+        );
+        let profile = PaxosModelCfg {
+            client_count,
+            server_count: 3,
+            network,
         }
+        .into_model()
+        .checker()
+        .profile();
+        print!("{}", profile);
+        return Ok(());
     }
 
+    cli::run(|params| {
+        Ok(PaxosModelCfg {
+            client_count: params.next_or("CLIENT_COUNT", 2)?,
+            server_count: 3,
+            network: params.next_or("NETWORK", Network::new_unordered_nonduplicating([]))?,
+        })
+    });
     Ok(())
 }
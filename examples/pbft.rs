@@ -0,0 +1,332 @@
+//! This is an implementation of a simplified, single-instance variant of [Practical Byzantine
+//! Fault Tolerance](https://pmg.csail.mit.edu/papers/osdi99.pdf) (PBFT), which tolerates `f`
+//! arbitrarily faulty replicas out of `3f + 1`.
+//!
+//! # The Algorithm
+//!
+//! The primary proposes a value by sending a `PrePrepare` to the other replicas. A replica that
+//! accepts the proposal sends a `Prepare` for it to every replica, and once it has a quorum of
+//! `2f + 1` matching `Prepare`s (counting its own), it sends a `Commit` to every replica. A replica
+//! decides a value once it has a quorum of `2f + 1` matching `Commit`s. Any two quorums intersect in
+//! at least `f + 1` replicas, at least one of which is correct and only prepares one value, so
+//! correct replicas never decide different values.
+//!
+//! This variant omits view changes, so a faulty primary can prevent progress, but not safety.
+//!
+//! # Byzantine Replicas
+//!
+//! Faulty replicas equivocate: a faulty primary proposes different values to different replicas,
+//! and every faulty replica sends `Prepare`s and `Commit`s for every value. Channels are
+//! authenticated, which the model reflects by counting votes by the sender's [`Id`], which actors
+//! cannot forge. With one more faulty replica than the protocol tolerates, correct replicas can
+//! decide different values.
+
+use stateright::actor::{Actor, ActorModel, Id, Network, Out};
+use stateright::report::WriteReporter;
+use stateright::{Checker, Expectation, Model};
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+type Value = char;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum BftMsg {
+    PrePrepare(Value),
+    Prepare(Value),
+    Commit(Value),
+}
+use BftMsg::*;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum BftState {
+    Correct {
+        accepted: Option<Value>,
+        prepares: BTreeSet<(Value, Id)>,
+        commits: BTreeSet<(Value, Id)>,
+        decided: Option<Value>,
+    },
+    Faulty,
+}
+
+impl BftState {
+    fn decided(&self) -> Option<Value> {
+        match self {
+            BftState::Correct { decided, .. } => *decided,
+            BftState::Faulty => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+enum BftActor {
+    Correct {
+        primary_id: Id,
+        peer_ids: Vec<Id>,
+        /// The value to propose, if this replica is the primary.
+        proposal: Option<Value>,
+        quorum: usize,
+    },
+    /// Sends conflicting messages, but cannot impersonate other replicas.
+    Faulty {
+        peer_ids: Vec<Id>,
+        /// The value to propose to each peer, if this replica is the primary.
+        proposals: Option<Vec<Value>>,
+        values: Vec<Value>,
+    },
+}
+
+impl Actor for BftActor {
+    type Msg = BftMsg;
+    type State = BftState;
+    type Timer = ();
+
+    fn name(&self) -> String {
+        match self {
+            BftActor::Correct { .. } => "Correct".to_owned(),
+            BftActor::Faulty { .. } => "Faulty".to_owned(),
+        }
+    }
+
+    fn on_start(&self, id: Id, o: &mut Out<Self>) -> Self::State {
+        match self {
+            BftActor::Correct {
+                peer_ids, proposal, ..
+            } => {
+                let mut state = BftState::Correct {
+                    accepted: None,
+                    prepares: Default::default(),
+                    commits: Default::default(),
+                    decided: None,
+                };
+                if let (
+                    Some(value),
+                    BftState::Correct {
+                        accepted, prepares, ..
+                    },
+                ) = (proposal, &mut state)
+                {
+                    o.broadcast(peer_ids, &PrePrepare(*value));
+                    o.broadcast(peer_ids, &Prepare(*value));
+                    *accepted = Some(*value);
+                    prepares.insert((*value, id));
+                }
+                state
+            }
+            BftActor::Faulty {
+                peer_ids,
+                proposals,
+                values,
+            } => {
+                if let Some(proposals) = proposals {
+                    for (dst, value) in peer_ids.iter().zip(proposals) {
+                        o.send(*dst, PrePrepare(*value));
+                    }
+                }
+                for value in values {
+                    o.broadcast(peer_ids, &Prepare(*value));
+                    o.broadcast(peer_ids, &Commit(*value));
+                }
+                BftState::Faulty
+            }
+        }
+    }
+
+    fn on_msg(
+        &self,
+        id: Id,
+        state: &mut Cow<Self::State>,
+        src: Id,
+        msg: Self::Msg,
+        o: &mut Out<Self>,
+    ) {
+        let BftActor::Correct {
+            primary_id,
+            peer_ids,
+            quorum,
+            ..
+        } = self
+        else {
+            return;
+        };
+        let BftState::Correct {
+            accepted,
+            prepares,
+            commits,
+            decided: None,
+        } = &**state
+        else {
+            // Messages after the decision are irrelevant.
+            return;
+        };
+        let (mut accepted, mut prepares, mut commits) =
+            (*accepted, prepares.clone(), commits.clone());
+        match msg {
+            PrePrepare(value) if src == *primary_id && accepted.is_none() => {
+                accepted = Some(value);
+                prepares.insert((value, id));
+                o.broadcast(peer_ids, &Prepare(value));
+            }
+            Prepare(value) => {
+                prepares.insert((value, src));
+            }
+            Commit(value) => {
+                commits.insert((value, src));
+            }
+            _ => {}
+        }
+        // Commits the accepted value once prepared.
+        if let Some(value) = accepted {
+            if count(&prepares, value) >= *quorum && commits.insert((value, id)) {
+                o.broadcast(peer_ids, &Commit(value));
+            }
+        }
+        let decided = commits
+            .iter()
+            .map(|(value, _)| *value)
+            .find(|value| count(&commits, *value) >= *quorum);
+        let next_state = BftState::Correct {
+            accepted,
+            prepares,
+            commits,
+            decided,
+        };
+        if **state != next_state {
+            *state.to_mut() = next_state;
+        }
+    }
+}
+
+/// Counts the votes for a value.
+fn count(votes: &BTreeSet<(Value, Id)>, value: Value) -> usize {
+    votes.iter().filter(|(v, _)| *v == value).count()
+}
+
+#[derive(Clone)]
+struct BftModelCfg {
+    /// The number of faulty replicas tolerated by the protocol.
+    f: usize,
+    /// The number of faulty replicas, including the primary if nonzero.
+    faulty_count: usize,
+}
+
+impl BftModelCfg {
+    fn into_model(self) -> ActorModel<BftActor, Self> {
+        let replica_count = 3 * self.f + 1;
+        let values = vec!['A', 'B'];
+        let primary_id = Id::from(0);
+        // Faulty replicas ignore their inputs, so messages to them are omitted.
+        let correct_ids: Vec<_> = (self.faulty_count..replica_count).map(Id::from).collect();
+        let peer_ids = |i: usize| {
+            correct_ids
+                .iter()
+                .copied()
+                .filter(|id| usize::from(*id) != i)
+                .collect::<Vec<_>>()
+        };
+        ActorModel::new(self.clone(), ())
+            .actors((0..replica_count).map(|i| {
+                if i < self.faulty_count {
+                    BftActor::Faulty {
+                        // Alternates the proposed values.
+                        proposals: (i == 0).then(|| {
+                            correct_ids
+                                .iter()
+                                .map(|id| values[usize::from(*id) % values.len()])
+                                .collect()
+                        }),
+                        peer_ids: peer_ids(i),
+                        values: values.clone(),
+                    }
+                } else {
+                    BftActor::Correct {
+                        primary_id,
+                        peer_ids: peer_ids(i),
+                        proposal: (i == 0).then_some(values[0]),
+                        quorum: 2 * self.f + 1,
+                    }
+                }
+            }))
+            .init_network(Network::new_ordered([]))
+            .property(Expectation::Always, "agreement", |_, state| {
+                let decided: BTreeSet<_> = state
+                    .actor_states
+                    .iter()
+                    .filter_map(|s| s.decided())
+                    .collect();
+                decided.len() <= 1
+            })
+            .property(Expectation::Sometimes, "value decided", |_, state| {
+                state.actor_states.iter().any(|s| s.decided().is_some())
+            })
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn can_model_pbft() {
+    // Tolerates a faulty primary.
+    let checker = BftModelCfg {
+        f: 1,
+        faulty_count: 1,
+    }
+    .into_model()
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_properties();
+    assert_eq!(checker.unique_state_count(), 15_570);
+
+    // But not an additional faulty replica.
+    let checker = BftModelCfg {
+        f: 1,
+        faulty_count: 2,
+    }
+    .into_model()
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_any_discovery("agreement");
+}
+
+fn main() -> Result<(), pico_args::Error> {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    let mut args = pico_args::Arguments::from_env();
+    match args.subcommand()?.as_deref() {
+        Some("check") => {
+            let faulty_count = args.opt_free_from_str()?.unwrap_or(1);
+            println!(
+                "Model checking PBFT with 4 replicas, {} of which are faulty.",
+                faulty_count
+            );
+            BftModelCfg { f: 1, faulty_count }
+                .into_model()
+                .checker()
+                .threads(num_cpus::get())
+                .spawn_dfs()
+                .report(&mut WriteReporter::new(&mut std::io::stdout()));
+        }
+        Some("explore") => {
+            let faulty_count = args.opt_free_from_str()?.unwrap_or(1);
+            let address = args
+                .opt_free_from_str()?
+                .unwrap_or("localhost:3000".to_string());
+            println!(
+                "Exploring state space for PBFT with 4 replicas, {} of which are faulty, on {}.",
+                faulty_count, address
+            );
+            BftModelCfg { f: 1, faulty_count }
+                .into_model()
+                .checker()
+                .threads(num_cpus::get())
+                .serve(address);
+        }
+        _ => {
+            println!("USAGE:");
+            println!("  ./pbft check [FAULTY_COUNT]");
+            println!("  ./pbft explore [FAULTY_COUNT] [ADDRESS]");
+        }
+    }
+
+    Ok(())
+}
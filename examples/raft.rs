@@ -0,0 +1,700 @@
+//! This is an implementation of [Raft](https://raft.github.io/), an algorithm that replicates a
+//! log of commands across a cluster of servers, here driving a register.
+//!
+//! # The Algorithm
+//!
+//! Raft decomposes consensus into two mostly independent subproblems.
+//!
+//! ## Leader Election
+//!
+//! Time is divided into numbered terms, each of which has at most one leader. A server that does
+//! not hear from a leader before its election timer fires starts a new term as a candidate and
+//! requests votes from its peers. A server grants at most one vote per term, and only to a
+//! candidate whose log is at least as up-to-date as its own, so a candidate that wins a majority
+//! holds every committed entry.
+//!
+//! ## Log Replication
+//!
+//! The leader appends client requests to its log and replicates them via `AppendEntries`
+//! messages, which also carry the index and term of the preceding entry so that followers only
+//! accept entries that extend a matching prefix (removing any conflicting suffix). Once a
+//! majority stores an entry from the leader's current term, that entry and all preceding entries
+//! are committed and can be applied to the state machine.
+//!
+//! ## Faults
+//!
+//! The election timers model arbitrary message delays and leader failures. Servers can also crash
+//! and recover, losing their volatile state (role, commit index, applied state, and replication
+//! progress) while retaining their persistent state (current term, vote, and log).
+
+use serde::{Deserialize, Serialize};
+use stateright::actor::properties::monotonic_sequence_numbers;
+use stateright::actor::register::{RegisterActor, RegisterActorState, RegisterMsg, RegisterMsg::*};
+use stateright::actor::{
+    majority, model_peers, model_timeout, Actor, ActorModel, ActorModelState, Id, Network, Out,
+};
+use stateright::report::WriteReporter;
+use stateright::semantics::register::Register;
+use stateright::semantics::LinearizabilityTester;
+use stateright::{Checker, Expectation, Model};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+
+type Term = u32;
+type LogIndex = usize;
+type RequestId = u64;
+type Value = char;
+
+/// A client request, where a `None` value indicates a read.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    term: Term,
+    request_id: RequestId,
+    requester: Id,
+    value: Option<Value>,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+enum RaftMsg {
+    RequestVote {
+        term: Term,
+        last_log_index: LogIndex,
+        last_log_term: Term,
+    },
+    Vote {
+        term: Term,
+        granted: bool,
+    },
+    AppendEntries {
+        term: Term,
+        prev_log_index: LogIndex,
+        prev_log_term: Term,
+        entries: Vec<Entry>,
+        leader_commit: LogIndex,
+    },
+    AppendEntriesOk {
+        term: Term,
+        match_index: LogIndex,
+    },
+    AppendEntriesFail {
+        term: Term,
+    },
+    /// A client request that a follower forwards to the leader it knows of.
+    Forward {
+        request_id: RequestId,
+        requester: Id,
+        value: Option<Value>,
+    },
+}
+use RaftMsg::*;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+enum RaftTimer {
+    Election,
+    Restart,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum Role {
+    Follower,
+    Candidate { votes: BTreeSet<Id> },
+    Leader { next_index: BTreeMap<Id, LogIndex> },
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct RaftState {
+    // persistent state
+    term: Term,
+    voted_for: Option<Id>,
+    log: Vec<Entry>,
+
+    // volatile state
+    role: Role,
+    leader: Option<Id>,
+    commit_index: LogIndex,
+    match_index: BTreeMap<Id, LogIndex>,
+    last_applied: LogIndex,
+    value: Value,
+}
+
+impl RaftState {
+    fn last_log_term(&self) -> Term {
+        self.log.last().map(|e| e.term).unwrap_or(0)
+    }
+
+    fn term_at(&self, index: LogIndex) -> Term {
+        if index == 0 {
+            0
+        } else {
+            self.log[index - 1].term
+        }
+    }
+
+    fn is_leader(&self) -> bool {
+        matches!(self.role, Role::Leader { .. })
+    }
+
+    /// Adopts a newer term as a follower.
+    fn observe_term(&mut self, term: Term, o: &mut Out<RaftActor>) {
+        if term > self.term {
+            if self.is_leader() {
+                o.set_timer(RaftTimer::Election, model_timeout());
+            }
+            self.term = term;
+            self.voted_for = None;
+            self.role = Role::Follower;
+            self.leader = None;
+        }
+    }
+}
+
+#[derive(Clone)]
+struct RaftActor {
+    peer_ids: Vec<Id>,
+    can_restart: bool,
+}
+
+impl RaftActor {
+    fn cluster_size(&self) -> usize {
+        self.peer_ids.len() + 1
+    }
+
+    fn replicate(&self, state: &RaftState, dst: Id, next_index: LogIndex, o: &mut Out<Self>) {
+        let prev_log_index = next_index - 1;
+        o.send(
+            dst,
+            Internal(AppendEntries {
+                term: state.term,
+                prev_log_index,
+                prev_log_term: state.term_at(prev_log_index),
+                entries: state.log[prev_log_index..].to_vec(),
+                leader_commit: state.commit_index,
+            }),
+        );
+    }
+
+    /// Commits entries from the current term once a majority stores them, then applies committed
+    /// entries, replying to the requester of each entry that this leader appended.
+    fn advance_commit_index(&self, state: &mut RaftState, o: &mut Out<Self>) {
+        if state.is_leader() {
+            for index in (state.commit_index + 1)..=state.log.len() {
+                let replicas = 1 + state
+                    .match_index
+                    .values()
+                    .filter(|&&match_index| match_index >= index)
+                    .count();
+                if replicas >= majority(self.cluster_size()) && state.term_at(index) == state.term {
+                    state.commit_index = index;
+                }
+            }
+        }
+        while state.last_applied < state.commit_index {
+            let entry = &state.log[state.last_applied];
+            state.last_applied += 1;
+            if let Some(value) = entry.value {
+                state.value = value;
+            }
+            if state.is_leader() && entry.term == state.term {
+                o.send(
+                    entry.requester,
+                    match entry.value {
+                        Some(_) => PutOk(entry.request_id),
+                        None => GetOk(entry.request_id, state.value),
+                    },
+                );
+            }
+        }
+    }
+}
+
+impl Actor for RaftActor {
+    type Msg = RegisterMsg<RequestId, Value, RaftMsg>;
+    type State = RaftState;
+    type Timer = RaftTimer;
+
+    fn name(&self) -> String {
+        "Raft Server".to_owned()
+    }
+
+    fn on_start(&self, _id: Id, o: &mut Out<Self>) -> Self::State {
+        o.set_timer(RaftTimer::Election, model_timeout());
+        if self.can_restart {
+            o.set_timer(RaftTimer::Restart, model_timeout());
+        }
+        RaftState {
+            term: 0,
+            voted_for: None,
+            log: Vec::new(),
+
+            role: Role::Follower,
+            leader: None,
+            commit_index: 0,
+            match_index: Default::default(),
+            last_applied: 0,
+            value: Value::default(),
+        }
+    }
+
+    fn on_msg(
+        &self,
+        _id: Id,
+        state: &mut Cow<Self::State>,
+        src: Id,
+        msg: Self::Msg,
+        o: &mut Out<Self>,
+    ) {
+        match msg {
+            Put(request_id, value) => {
+                self.on_request(state, request_id, src, Some(value), o);
+            }
+            Get(request_id) => {
+                self.on_request(state, request_id, src, None, o);
+            }
+            Internal(Forward {
+                request_id,
+                requester,
+                value,
+            }) if state.is_leader() => {
+                self.append(state.to_mut(), request_id, requester, value, o);
+            }
+            Internal(RequestVote {
+                term,
+                last_log_index,
+                last_log_term,
+            }) => {
+                let state = state.to_mut();
+                state.observe_term(term, o);
+                let is_up_to_date =
+                    (last_log_term, last_log_index) >= (state.last_log_term(), state.log.len());
+                let granted = term == state.term
+                    && state.voted_for.is_none_or(|voted_for| voted_for == src)
+                    && is_up_to_date;
+                if granted {
+                    state.voted_for = Some(src);
+                }
+                o.send(
+                    src,
+                    Internal(Vote {
+                        term: state.term,
+                        granted,
+                    }),
+                );
+            }
+            Internal(Vote { term, granted }) => {
+                let state = state.to_mut();
+                state.observe_term(term, o);
+                if term != state.term || !granted {
+                    return;
+                }
+                if let Role::Candidate { votes } = &mut state.role {
+                    votes.insert(src);
+                    if votes.len() == majority(self.cluster_size()) {
+                        let next_index = state.log.len() + 1;
+                        state.role = Role::Leader {
+                            next_index: self.peer_ids.iter().map(|&p| (p, next_index)).collect(),
+                        };
+                        state.match_index = self.peer_ids.iter().map(|&p| (p, 0)).collect();
+                        o.cancel_timer(RaftTimer::Election);
+                        for &peer_id in &self.peer_ids {
+                            self.replicate(state, peer_id, next_index, o);
+                        }
+                    }
+                }
+            }
+            Internal(AppendEntries {
+                term,
+                prev_log_index,
+                prev_log_term,
+                entries,
+                leader_commit,
+            }) => {
+                let state = state.to_mut();
+                state.observe_term(term, o);
+                if term < state.term {
+                    o.send(src, Internal(AppendEntriesFail { term: state.term }));
+                    return;
+                }
+                state.role = Role::Follower;
+                state.leader = Some(src);
+                if state.log.len() < prev_log_index
+                    || state.term_at(prev_log_index) != prev_log_term
+                {
+                    o.send(src, Internal(AppendEntriesFail { term: state.term }));
+                    return;
+                }
+                // Only remove entries that conflict, as the message may be stale.
+                for (offset, entry) in entries.iter().enumerate() {
+                    let index = prev_log_index + offset + 1;
+                    if state.log.len() >= index && state.log[index - 1].term != entry.term {
+                        state.log.truncate(index - 1);
+                    }
+                    if state.log.len() < index {
+                        state.log.push(entry.clone());
+                    }
+                }
+                let match_index = prev_log_index + entries.len();
+                if leader_commit > state.commit_index {
+                    state.commit_index = leader_commit.min(match_index);
+                }
+                self.advance_commit_index(state, o);
+                o.send(
+                    src,
+                    Internal(AppendEntriesOk {
+                        term: state.term,
+                        match_index,
+                    }),
+                );
+            }
+            Internal(AppendEntriesOk { term, match_index }) => {
+                let state = state.to_mut();
+                state.observe_term(term, o);
+                if term != state.term {
+                    return;
+                }
+                if let Role::Leader { next_index } = &mut state.role {
+                    if match_index + 1 > next_index[&src] {
+                        next_index.insert(src, match_index + 1);
+                    }
+                    if match_index > state.match_index[&src] {
+                        state.match_index.insert(src, match_index);
+                    }
+                    self.advance_commit_index(state, o);
+                }
+            }
+            Internal(AppendEntriesFail { term }) => {
+                let state = state.to_mut();
+                state.observe_term(term, o);
+                if term != state.term {
+                    return;
+                }
+                if let Role::Leader { next_index } = &mut state.role {
+                    let next = next_index[&src].saturating_sub(1).max(1);
+                    next_index.insert(src, next);
+                    self.replicate(state, src, next, o);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_timeout(
+        &self,
+        id: Id,
+        state: &mut Cow<Self::State>,
+        timer: &Self::Timer,
+        o: &mut Out<Self>,
+    ) {
+        match timer {
+            RaftTimer::Election => {
+                if state.is_leader() {
+                    return;
+                }
+                let state = state.to_mut();
+                state.term += 1;
+                state.voted_for = Some(id);
+                state.role = Role::Candidate {
+                    votes: BTreeSet::from([id]),
+                };
+                state.leader = None;
+                o.set_timer(RaftTimer::Election, model_timeout());
+                o.broadcast(
+                    &self.peer_ids,
+                    &Internal(RequestVote {
+                        term: state.term,
+                        last_log_index: state.log.len(),
+                        last_log_term: state.last_log_term(),
+                    }),
+                );
+            }
+            RaftTimer::Restart => {
+                // Volatile state is lost, and the restarted server rejoins as a follower.
+                let state = state.to_mut();
+                state.role = Role::Follower;
+                state.leader = None;
+                state.commit_index = 0;
+                state.match_index = Default::default();
+                state.last_applied = 0;
+                state.value = Value::default();
+                o.set_timer(RaftTimer::Election, model_timeout());
+            }
+        }
+    }
+}
+
+impl RaftActor {
+    /// Appends a client request if leading, or forwards it to the leader if one is known.
+    fn on_request(
+        &self,
+        state: &mut Cow<RaftState>,
+        request_id: RequestId,
+        requester: Id,
+        value: Option<Value>,
+        o: &mut Out<Self>,
+    ) {
+        if state.is_leader() {
+            self.append(state.to_mut(), request_id, requester, value, o);
+        } else if let Some(leader) = state.leader {
+            o.send(
+                leader,
+                Internal(Forward {
+                    request_id,
+                    requester,
+                    value,
+                }),
+            );
+        }
+    }
+
+    fn append(
+        &self,
+        state: &mut RaftState,
+        request_id: RequestId,
+        requester: Id,
+        value: Option<Value>,
+        o: &mut Out<Self>,
+    ) {
+        state.log.push(Entry {
+            term: state.term,
+            request_id,
+            requester,
+            value,
+        });
+        if let Role::Leader { next_index } = &state.role {
+            for (&peer_id, &next_index) in next_index {
+                self.replicate(state, peer_id, next_index, o);
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+struct RaftModelCfg {
+    client_count: usize,
+    server_count: usize,
+    max_term: Term,
+    restart: bool,
+    network: Network<<RaftActor as Actor>::Msg>,
+}
+
+type RaftModelState =
+    ActorModelState<RegisterActor<RaftActor>, LinearizabilityTester<Id, Register<Value>>>;
+
+/// The states of the servers, which precede the clients.
+fn servers(state: &RaftModelState) -> impl Iterator<Item = &RaftState> {
+    state.actor_states.iter().filter_map(|s| match &**s {
+        RegisterActorState::Server(s) => Some(s),
+        RegisterActorState::Client { .. } => None,
+    })
+}
+
+impl RaftModelCfg {
+    fn into_model(
+        self,
+    ) -> ActorModel<RegisterActor<RaftActor>, Self, LinearizabilityTester<Id, Register<Value>>>
+    {
+        let mut model = ActorModel::new(
+            self.clone(),
+            LinearizabilityTester::new(Register(Value::default())),
+        )
+        .actors((0..self.server_count).map(|i| {
+            RegisterActor::Server(RaftActor {
+                peer_ids: model_peers(i, self.server_count),
+                // One server may crash and recover.
+                can_restart: self.restart && i == 0,
+            })
+        }))
+        .actors((0..self.client_count).map(|_| RegisterActor::Client {
+            put_count: 1,
+            server_count: self.server_count,
+        }))
+        .init_network(self.network)
+        .property(Expectation::Always, "linearizable", |_, state| {
+            state.history.serialized_history().is_some()
+        })
+        .property(Expectation::Always, "election safety", |_, state| {
+            let leader_terms: Vec<_> = servers(state)
+                .filter(|s| s.is_leader())
+                .map(|s| s.term)
+                .collect();
+            let distinct: BTreeSet<_> = leader_terms.iter().collect();
+            distinct.len() == leader_terms.len()
+        })
+        .property(Expectation::Always, "state machine safety", |_, state| {
+            servers(state).all(|a| {
+                servers(state).all(|b| {
+                    let committed = a.commit_index.min(b.commit_index);
+                    a.log[..committed] == b.log[..committed]
+                })
+            })
+        })
+        .property(Expectation::Sometimes, "value chosen", |_, state| {
+            for env in state.network.iter_deliverable() {
+                if let RegisterMsg::GetOk(_req_id, value) = env.msg {
+                    if *value != Value::default() {
+                        return true;
+                    }
+                }
+            }
+            false
+        })
+        .record_msg_in(RegisterMsg::record_returns)
+        .record_msg_out(RegisterMsg::record_invocations)
+        .within_boundary(|cfg, state| servers(state).all(|s| s.term <= cfg.max_term));
+        model.properties.push(monotonic_sequence_numbers(
+            "monotonic terms",
+            |_, state: &RegisterActorState<RaftState, _>| match state {
+                RegisterActorState::Server(s) => s.term,
+                RegisterActorState::Client { .. } => 0,
+            },
+        ));
+        model
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn can_model_raft() {
+    let checker = RaftModelCfg {
+        client_count: 1,
+        server_count: 3,
+        max_term: 1,
+        restart: false,
+        network: Network::new_ordered([]),
+    }
+    .into_model()
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_properties();
+
+    let checker = RaftModelCfg {
+        client_count: 1,
+        server_count: 3,
+        max_term: 2,
+        restart: true,
+        network: Network::new_ordered([]),
+    }
+    .into_model()
+    .checker()
+    .target_max_depth(10)
+    .spawn_dfs()
+    .join();
+    checker.assert_no_discovery("linearizable");
+    checker.assert_no_discovery("election safety");
+    checker.assert_no_discovery("state machine safety");
+    checker.assert_no_discovery("monotonic terms");
+}
+
+fn main() -> Result<(), pico_args::Error> {
+    use stateright::actor::spawn;
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    let mut args = pico_args::Arguments::from_env();
+    match args.subcommand()?.as_deref() {
+        Some("check") => {
+            let client_count = args.opt_free_from_str()?.unwrap_or(1);
+            let max_term = args.opt_free_from_str()?.unwrap_or(2);
+            let network = args
+                .opt_free_from_str()?
+                .unwrap_or(Network::new_unordered_nonduplicating([]));
+            println!(
+                "Model checking Raft with {} clients through term {}.",
+                client_count, max_term
+            );
+            RaftModelCfg {
+                client_count,
+                server_count: 3,
+                max_term,
+                restart: true,
+                network,
+            }
+            .into_model()
+            .checker()
+            .threads(num_cpus::get())
+            .spawn_dfs()
+            .report(&mut WriteReporter::new(&mut std::io::stdout()));
+        }
+        Some("explore") => {
+            let client_count = args.opt_free_from_str()?.unwrap_or(1);
+            let max_term = args.opt_free_from_str()?.unwrap_or(2);
+            let address = args
+                .opt_free_from_str()?
+                .unwrap_or("localhost:3000".to_string());
+            let network = args
+                .opt_free_from_str()?
+                .unwrap_or(Network::new_unordered_nonduplicating([]));
+            println!(
+                "Exploring state space for Raft with {} clients through term {} on {}.",
+                client_count, max_term, address
+            );
+            RaftModelCfg {
+                client_count,
+                server_count: 3,
+                max_term,
+                restart: true,
+                network,
+            }
+            .into_model()
+            .checker()
+            .threads(num_cpus::get())
+            .serve(address);
+        }
+        Some("spawn") => {
+            let port = 3000;
+
+            println!("  A set of servers that implement Raft.");
+            println!("  You can monitor and interact using tcpdump and netcat.");
+            println!("  Use `tcpdump -D` if you see error `lo0: No such device exists`.");
+            println!("  Followers forward requests to the leader once one is elected.");
+            println!("Examples:");
+            println!("$ sudo tcpdump -i lo0 -s 0 -nnX");
+            println!("$ nc -u localhost {}", port);
+            println!(
+                "{}",
+                serde_json::to_string(&RegisterMsg::Put::<RequestId, Value, ()>(1, 'X')).unwrap()
+            );
+            println!(
+                "{}",
+                serde_json::to_string(&RegisterMsg::Get::<RequestId, Value, ()>(2)).unwrap()
+            );
+            println!();
+
+            // WARNING: Omits `ordered_reliable_link` to keep the message
+            //          protocol simple for `nc`.
+            let ids: Vec<_> = (0..3)
+                .map(|i| Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port + i)))
+                .collect();
+            spawn(
+                serde_json::to_vec,
+                |bytes| serde_json::from_slice(bytes),
+                ids.iter()
+                    .map(|&id| {
+                        let peer_ids = ids.iter().copied().filter(|&p| p != id).collect();
+                        (
+                            id,
+                            RaftActor {
+                                peer_ids,
+                                can_restart: false,
+                            },
+                        )
+                    })
+                    .collect(),
+            )
+            .unwrap();
+        }
+        _ => {
+            println!("USAGE:");
+            println!("  ./raft check [CLIENT_COUNT] [MAX_TERM] [NETWORK]");
+            println!("  ./raft explore [CLIENT_COUNT] [MAX_TERM] [ADDRESS] [NETWORK]");
+            println!("  ./raft spawn");
+            println!(
+                "NETWORK: {}",
+                Network::<<RaftActor as Actor>::Msg>::names().join(" | ")
+            );
+        }
+    }
+
+    Ok(())
+}
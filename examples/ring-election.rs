@@ -0,0 +1,235 @@
+//! This is an implementation of the [Chang and Roberts
+//! algorithm](https://en.wikipedia.org/wiki/Chang_and_Roberts_algorithm) for electing a leader in
+//! a unidirectional ring.
+//!
+//! # The Algorithm
+//!
+//! Each node has a unique identifier and starts by sending its identifier to its successor in the
+//! ring. A node forwards identifiers larger than its own and discards smaller ones, so only the
+//! largest identifier travels all the way around the ring. A node that receives its own identifier
+//! is therefore the leader, and it announces itself to the other nodes by sending an `Elected`
+//! message around the ring.
+//!
+//! # Liveness
+//!
+//! Election is a liveness property, which only holds if messages are eventually delivered. The
+//! model is acyclic, so the checker can verify that every behavior eventually elects a leader,
+//! and it finds a counterexample if the network can drop messages.
+
+use serde::{Deserialize, Serialize};
+use stateright::actor::{Actor, ActorModel, Id, LossyNetwork, Network, Out};
+use stateright::report::WriteReporter;
+use stateright::{Checker, Expectation, Model};
+use std::borrow::Cow;
+use std::net::{Ipv4Addr, SocketAddrV4};
+
+type Uid = u8;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+enum RingMsg {
+    Candidate(Uid),
+    Elected(Uid),
+}
+use RingMsg::*;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct RingState {
+    leader: Option<Uid>,
+}
+
+#[derive(Clone)]
+struct RingActor {
+    uid: Uid,
+    successor: Id,
+}
+
+impl Actor for RingActor {
+    type Msg = RingMsg;
+    type State = RingState;
+    type Timer = ();
+
+    fn on_start(&self, _id: Id, o: &mut Out<Self>) -> Self::State {
+        o.send(self.successor, Candidate(self.uid));
+        RingState { leader: None }
+    }
+
+    fn on_msg(
+        &self,
+        _id: Id,
+        state: &mut Cow<Self::State>,
+        _src: Id,
+        msg: Self::Msg,
+        o: &mut Out<Self>,
+    ) {
+        match msg {
+            Candidate(uid) if uid > self.uid => {
+                o.send(self.successor, Candidate(uid));
+            }
+            Candidate(uid) if uid == self.uid => {
+                state.to_mut().leader = Some(self.uid);
+                o.send(self.successor, Elected(self.uid));
+            }
+            Elected(uid) if uid != self.uid => {
+                state.to_mut().leader = Some(uid);
+                o.send(self.successor, Elected(uid));
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Clone)]
+struct RingModelCfg {
+    uids: Vec<Uid>,
+    lossy: bool,
+}
+
+impl RingModelCfg {
+    fn into_model(self) -> ActorModel<RingActor, Self> {
+        let node_count = self.uids.len();
+        ActorModel::new(self.clone(), ())
+            .actors(self.uids.iter().enumerate().map(|(i, &uid)| RingActor {
+                uid,
+                successor: Id::from((i + 1) % node_count),
+            }))
+            .init_network(Network::new_unordered_nonduplicating([]))
+            .lossy_network(if self.lossy {
+                LossyNetwork::Yes
+            } else {
+                LossyNetwork::No
+            })
+            .property(Expectation::Always, "unique leader", |model, state| {
+                let leaders = state
+                    .actor_states
+                    .iter()
+                    .zip(&model.actors)
+                    .filter(|(s, actor)| s.leader == Some(actor.uid));
+                leaders.count() <= 1
+            })
+            .property(Expectation::Eventually, "leader elected", |model, state| {
+                let max = model.cfg.uids.iter().max().copied();
+                state.actor_states.iter().all(|s| s.leader == max)
+            })
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn can_model_ring_election() {
+    let checker = RingModelCfg {
+        uids: vec![2, 4, 1, 3],
+        lossy: false,
+    }
+    .into_model()
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_properties();
+    assert_eq!(checker.unique_state_count(), 16);
+
+    // Election can stall if the network drops messages.
+    let checker = RingModelCfg {
+        uids: vec![2, 4, 1, 3],
+        lossy: true,
+    }
+    .into_model()
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_no_discovery("unique leader");
+    checker.assert_any_discovery("leader elected");
+}
+
+fn main() -> Result<(), pico_args::Error> {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    let mut args = pico_args::Arguments::from_env();
+    let lossy = args.contains("--lossy");
+    // Interleaves small and large identifiers so that some candidates are discarded early.
+    let uids = |node_count: usize| {
+        (0..node_count)
+            .map(|i| {
+                if i % 2 == 0 {
+                    i / 2
+                } else {
+                    node_count - 1 - i / 2
+                }
+            } as Uid)
+            .collect::<Vec<_>>()
+    };
+    match args.subcommand()?.as_deref() {
+        Some("check") => {
+            let node_count = args.opt_free_from_str()?.unwrap_or(4);
+            println!(
+                "Model checking ring election with {} nodes{}.",
+                node_count,
+                if lossy { " on a lossy network" } else { "" }
+            );
+            RingModelCfg {
+                uids: uids(node_count),
+                lossy,
+            }
+            .into_model()
+            .checker()
+            .threads(num_cpus::get())
+            .spawn_dfs()
+            .report(&mut WriteReporter::new(&mut std::io::stdout()));
+        }
+        Some("explore") => {
+            let node_count = args.opt_free_from_str()?.unwrap_or(4);
+            let address = args
+                .opt_free_from_str()?
+                .unwrap_or("localhost:3000".to_string());
+            println!(
+                "Exploring state space for ring election with {} nodes on {}.",
+                node_count, address
+            );
+            RingModelCfg {
+                uids: uids(node_count),
+                lossy,
+            }
+            .into_model()
+            .checker()
+            .threads(num_cpus::get())
+            .serve(address);
+        }
+        Some("spawn") => {
+            let port = 3000;
+            let uids = uids(3);
+
+            println!("  A ring of servers that elect a leader upon starting.");
+            println!("  You can monitor the election using tcpdump.");
+            println!("  Use `tcpdump -D` if you see error `lo0: No such device exists`.");
+            println!("Example:");
+            println!("$ sudo tcpdump -i lo0 -s 0 -nnX");
+            println!();
+
+            let id = |i: usize| Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port + i as u16));
+            stateright::actor::spawn(
+                serde_json::to_vec,
+                |bytes| serde_json::from_slice(bytes),
+                uids.iter()
+                    .enumerate()
+                    .map(|(i, &uid)| {
+                        (
+                            id(i),
+                            RingActor {
+                                uid,
+                                successor: id((i + 1) % uids.len()),
+                            },
+                        )
+                    })
+                    .collect(),
+            )
+            .unwrap();
+        }
+        _ => {
+            println!("USAGE:");
+            println!("  ./ring-election check [NODE_COUNT] [--lossy]");
+            println!("  ./ring-election explore [NODE_COUNT] [ADDRESS] [--lossy]");
+            println!("  ./ring-election spawn");
+        }
+    }
+
+    Ok(())
+}
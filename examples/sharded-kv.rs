@@ -0,0 +1,374 @@
+//! This is an implementation of a sharded key-value store that supports transactions spanning
+//! multiple shards. Each shard is replicated, and transactions use [two-phase
+//! commit](https://en.wikipedia.org/wiki/Two-phase_commit_protocol) (2PC) across shards.
+//!
+//! # The Algorithm
+//!
+//! Each key is stored by a shard consisting of a primary and a backup. A transaction acts as the
+//! 2PC coordinator: it asks the primary of each shard that it accesses to prepare, and each
+//! primary locks its key and votes with the key's current value. If a key is already locked, the
+//! primary votes to abort instead of waiting ("no-wait" locking), so transactions cannot deadlock.
+//! Once every primary votes to commit, the transaction tells each primary to commit its write.
+//!
+//! Within a shard, the primary applies a committed write and forwards it to the backup, only
+//! releasing the lock once the backup acknowledges the write. Both replicas of a shard therefore
+//! end up with the same value.
+//!
+//! # Composition
+//!
+//! The replication layer and the transaction layer are independent: a primary does not know
+//! whether the transaction that locked its key also accesses other shards, and the coordinator
+//! does not know that the shards are replicated. The model composes them into one system and
+//! checks properties of both layers, including serializability of the transactions.
+//!
+//! Without locking, two transactions can read the same value and overwrite each other's writes,
+//! which the model detects as a serializability violation.
+
+use stateright::actor::{Actor, ActorModel, Envelope, Id, Network, Out};
+use stateright::report::WriteReporter;
+use stateright::semantics::transactional_kv::{TransactionalKv, TxnOp, TxnRet};
+use stateright::semantics::{ConsistencyTester, SerializabilityTester};
+use stateright::{Checker, Expectation, Model};
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+type Key = char;
+type Value = u8;
+type Ops = Vec<TxnOp<Key, Value>>;
+type Rets = Vec<TxnRet<Value>>;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum ShardMsg {
+    // Transaction layer.
+    Prepare,
+    /// Includes the key's value if the primary votes to commit.
+    Vote(Option<Value>),
+    /// Includes the value to write and the transaction's operations to record them in the
+    /// history.
+    Commit(Value, Ops, Rets),
+    Abort,
+
+    // Replication layer.
+    Replicate(Value),
+    Replicated,
+}
+use ShardMsg::*;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum ShardState {
+    Primary {
+        value: Value,
+        locked_by: Option<Id>,
+    },
+    Backup {
+        value: Value,
+    },
+    Txn {
+        /// The values read by the primaries that have voted to commit, in the order of the
+        /// transaction's keys.
+        reads: Vec<Option<Value>>,
+        outcome: Option<bool>,
+    },
+}
+
+impl ShardState {
+    fn value(&self) -> Option<Value> {
+        match self {
+            ShardState::Primary { value, .. } | ShardState::Backup { value } => Some(*value),
+            ShardState::Txn { .. } => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+enum ShardActor {
+    Primary {
+        backup_id: Id,
+        locking: bool,
+    },
+    Backup,
+    Txn {
+        /// The keys to increment along with the primaries that store them.
+        keys: Vec<(Key, Id)>,
+    },
+}
+
+impl Actor for ShardActor {
+    type Msg = ShardMsg;
+    type State = ShardState;
+    type Timer = ();
+
+    fn name(&self) -> String {
+        match self {
+            ShardActor::Primary { .. } => "Primary".to_owned(),
+            ShardActor::Backup => "Backup".to_owned(),
+            ShardActor::Txn { .. } => "Transaction".to_owned(),
+        }
+    }
+
+    fn on_start(&self, _id: Id, o: &mut Out<Self>) -> Self::State {
+        match self {
+            ShardActor::Primary { .. } => ShardState::Primary {
+                value: 0,
+                locked_by: None,
+            },
+            ShardActor::Backup => ShardState::Backup { value: 0 },
+            ShardActor::Txn { keys } => {
+                for (_, primary_id) in keys {
+                    o.send(*primary_id, Prepare);
+                }
+                ShardState::Txn {
+                    reads: vec![None; keys.len()],
+                    outcome: None,
+                }
+            }
+        }
+    }
+
+    fn on_msg(
+        &self,
+        _id: Id,
+        state: &mut Cow<Self::State>,
+        src: Id,
+        msg: Self::Msg,
+        o: &mut Out<Self>,
+    ) {
+        match (self, &**state) {
+            (
+                ShardActor::Primary { backup_id, locking },
+                ShardState::Primary { value, locked_by },
+            ) => {
+                let (value, locked_by) = (*value, *locked_by);
+                let next_state = match msg {
+                    Prepare if locked_by.is_some() => {
+                        o.send(src, Vote(None));
+                        return;
+                    }
+                    Prepare => {
+                        o.send(src, Vote(Some(value)));
+                        ShardState::Primary {
+                            value,
+                            locked_by: locking.then_some(src),
+                        }
+                    }
+                    Commit(value, ..) => {
+                        o.send(*backup_id, Replicate(value));
+                        ShardState::Primary { value, locked_by }
+                    }
+                    Abort if locked_by == Some(src) => ShardState::Primary {
+                        value,
+                        locked_by: None,
+                    },
+                    Replicated => ShardState::Primary {
+                        value,
+                        locked_by: None,
+                    },
+                    _ => return,
+                };
+                if **state != next_state {
+                    *state.to_mut() = next_state;
+                }
+            }
+            (ShardActor::Backup, ShardState::Backup { .. }) => {
+                if let Replicate(value) = msg {
+                    *state.to_mut() = ShardState::Backup { value };
+                    o.send(src, Replicated);
+                }
+            }
+            (
+                ShardActor::Txn { keys },
+                ShardState::Txn {
+                    reads,
+                    outcome: None,
+                },
+            ) => {
+                let Vote(vote) = msg else {
+                    return;
+                };
+                let mut reads = reads.clone();
+                let index = keys.iter().position(|(_, id)| *id == src).unwrap();
+                reads[index] = vote;
+                let outcome = if vote.is_none() {
+                    for (_, primary_id) in keys {
+                        o.send(*primary_id, Abort);
+                    }
+                    Some(false)
+                } else if reads.iter().all(Option::is_some) {
+                    // Increments the value of each key.
+                    let mut ops = Vec::new();
+                    let mut rets = Vec::new();
+                    for ((key, _), read) in keys.iter().zip(&reads) {
+                        let read = read.unwrap();
+                        ops.extend([TxnOp::Read(*key), TxnOp::Write(*key, read + 1)]);
+                        rets.extend([TxnRet::ReadOk(Some(read)), TxnRet::WriteOk]);
+                    }
+                    for ((_, primary_id), read) in keys.iter().zip(&reads) {
+                        o.send(
+                            *primary_id,
+                            Commit(read.unwrap() + 1, ops.clone(), rets.clone()),
+                        );
+                    }
+                    Some(true)
+                } else {
+                    None
+                };
+                *state.to_mut() = ShardState::Txn { reads, outcome };
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct TxnHistory {
+    serializability: SerializabilityTester<Id, TransactionalKv<Key, Value>>,
+    committed: BTreeSet<Id>,
+}
+
+/// Records a transaction upon its first `Commit` message, which is sent once the outcome is
+/// decided.
+fn record_commits(
+    _cfg: &ShardedKvModelCfg,
+    history: &TxnHistory,
+    env: Envelope<&ShardMsg>,
+) -> Option<TxnHistory> {
+    let Commit(_, ops, rets) = env.msg else {
+        return None;
+    };
+    if history.committed.contains(&env.src) {
+        return None;
+    }
+    let mut history = history.clone();
+    let _ = history.serializability.on_invoke(env.src, ops.clone());
+    let _ = history.serializability.on_return(env.src, rets.clone());
+    history.committed.insert(env.src);
+    Some(history)
+}
+
+#[derive(Clone)]
+struct ShardedKvModelCfg {
+    txn_count: usize,
+    locking: bool,
+}
+
+impl ShardedKvModelCfg {
+    fn into_model(self) -> ActorModel<ShardActor, Self, TxnHistory> {
+        // Each shard stores one key, and its primary and backup are adjacent.
+        let keys = ['x', 'y'];
+        let primary_id = |shard: usize| Id::from(2 * shard);
+        let init = keys.iter().map(|k| (*k, 0)).collect();
+        ActorModel::new(
+            self.clone(),
+            TxnHistory {
+                serializability: SerializabilityTester::new(TransactionalKv(init)),
+                committed: Default::default(),
+            },
+        )
+        .actors((0..keys.len()).flat_map(|shard| {
+            [
+                ShardActor::Primary {
+                    backup_id: Id::from(2 * shard + 1),
+                    locking: self.locking,
+                },
+                ShardActor::Backup,
+            ]
+        }))
+        .actors((0..self.txn_count).map(|_| {
+            ShardActor::Txn {
+                keys: keys
+                    .iter()
+                    .enumerate()
+                    .map(|(shard, key)| (*key, primary_id(shard)))
+                    .collect(),
+            }
+        }))
+        .init_network(Network::new_unordered_nonduplicating([]))
+        .property(Expectation::Always, "serializable", |_, state| {
+            state.history.serializability.serialized_history().is_some()
+        })
+        .property(Expectation::Terminal, "replicas agree", |_, state| {
+            state
+                .actor_states
+                .chunks(2)
+                .take(2)
+                .all(|shard| shard[0].value() == shard[1].value())
+        })
+        .property(Expectation::Sometimes, "all committed", |model, state| {
+            state.history.committed.len() == model.cfg.txn_count
+        })
+        .record_msg_out(record_commits)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn can_model_sharded_kv() {
+    let checker = ShardedKvModelCfg {
+        txn_count: 3,
+        locking: true,
+    }
+    .into_model()
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_properties();
+    assert_eq!(checker.unique_state_count(), 20_719);
+
+    // Without locking, concurrent transactions can lose updates.
+    let checker = ShardedKvModelCfg {
+        txn_count: 2,
+        locking: false,
+    }
+    .into_model()
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_any_discovery("serializable");
+}
+
+fn main() -> Result<(), pico_args::Error> {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    let mut args = pico_args::Arguments::from_env();
+    let locking = !args.contains("--no-locking");
+    match args.subcommand()?.as_deref() {
+        Some("check") => {
+            let txn_count = args.opt_free_from_str()?.unwrap_or(2);
+            println!(
+                "Model checking a sharded key-value store with {} transactions{}.",
+                txn_count,
+                if locking { "" } else { " and no locking" }
+            );
+            ShardedKvModelCfg { txn_count, locking }
+                .into_model()
+                .checker()
+                .threads(num_cpus::get())
+                .spawn_dfs()
+                .report(&mut WriteReporter::new(&mut std::io::stdout()));
+        }
+        Some("explore") => {
+            let txn_count = args.opt_free_from_str()?.unwrap_or(2);
+            let address = args
+                .opt_free_from_str()?
+                .unwrap_or("localhost:3000".to_string());
+            println!(
+                "Exploring state space for a sharded key-value store with {} transactions{} on {}.",
+                txn_count,
+                if locking { "" } else { " and no locking" },
+                address
+            );
+            ShardedKvModelCfg { txn_count, locking }
+                .into_model()
+                .checker()
+                .threads(num_cpus::get())
+                .serve(address);
+        }
+        _ => {
+            println!("USAGE:");
+            println!("  ./sharded-kv check [TXN_COUNT] [--no-locking]");
+            println!("  ./sharded-kv explore [TXN_COUNT] [ADDRESS] [--no-locking]");
+        }
+    }
+
+    Ok(())
+}
@@ -3,10 +3,10 @@
 
 use stateright::actor::register::{RegisterActor, RegisterMsg, RegisterMsg::*};
 use stateright::actor::{Actor, ActorModel, Id, Network, Out};
-use stateright::report::WriteReporter;
+use stateright::cli::{self, System};
 use stateright::semantics::register::Register;
 use stateright::semantics::LinearizabilityTester;
-use stateright::{Checker, Expectation, Model};
+use stateright::Expectation;
 use std::borrow::Cow;
 
 type RequestId = u64;
@@ -89,6 +89,7 @@ impl SingleCopyModelCfg {
 #[test]
 fn can_model_single_copy_register() {
     use stateright::actor::ActorModelAction::Deliver;
+    use stateright::{Checker, Model};
 
     // Linearizable if only one server. DFS for this one.
     let checker = SingleCopyModelCfg {
@@ -136,98 +137,37 @@ fn can_model_single_copy_register() {
     assert_eq!(checker.unique_state_count(), 20);
 }
 
-fn main() -> Result<(), pico_args::Error> {
-    use stateright::actor::spawn;
-    use std::net::{Ipv4Addr, SocketAddrV4};
+impl System for SingleCopyModelCfg {
+    type Model = ActorModel<
+        RegisterActor<SingleCopyActor>,
+        Self,
+        LinearizabilityTester<Id, Register<Value>>,
+    >;
+    type Actor = SingleCopyActor;
 
-    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+    fn description(&self) -> String {
+        format!("a single-copy register with {} clients", self.client_count)
+    }
 
-    let mut args = pico_args::Arguments::from_env();
-    match args.subcommand()?.as_deref() {
-        Some("check") => {
-            let client_count = args.opt_free_from_str()?.unwrap_or(2);
-            let network = args
-                .opt_free_from_str()?
-                .unwrap_or(Network::new_unordered_nonduplicating([]));
-            println!(
-                "Model checking a single-copy register with {} clients.",
-                client_count
-            );
-            SingleCopyModelCfg {
-                client_count,
-                server_count: 1,
-                network,
-            }
-            .into_model()
-            .checker()
-            .threads(num_cpus::get())
-            .spawn_dfs()
-            .report(&mut WriteReporter::new(&mut std::io::stdout()));
-        }
-        Some("explore") => {
-            let client_count = args.opt_free_from_str()?.unwrap_or(2);
-            let address = args
-                .opt_free_from_str()?
-                .unwrap_or("localhost:3000".to_string());
-            let network = args
-                .opt_free_from_str()?
-                .unwrap_or(Network::new_unordered_nonduplicating([]));
-            println!(
-                "Exploring state space for single-copy register with {} clients on {}.",
-                client_count, address
-            );
-            SingleCopyModelCfg {
-                client_count,
-                server_count: 1,
-                network,
-            }
-            .into_model()
-            .checker()
-            .threads(num_cpus::get())
-            .serve(address);
-        }
-        Some("spawn") => {
-            let port = 3000;
-
-            println!("  A server that implements a single-copy register.");
-            println!("  You can monitor and interact using tcpdump and netcat.");
-            println!("  Use `tcpdump -D` if you see error `lo0: No such device exists`.");
-            println!("Examples:");
-            println!("$ sudo tcpdump -i lo0 -s 0 -nnX");
-            println!("$ nc -u localhost {}", port);
-            println!(
-                "{}",
-                serde_json::to_string(&RegisterMsg::Put::<RequestId, Value, ()>(1, 'X')).unwrap()
-            );
-            println!(
-                "{}",
-                serde_json::to_string(&RegisterMsg::Get::<RequestId, Value, ()>(2)).unwrap()
-            );
-            println!();
-
-            // WARNING: Omits `ordered_reliable_link` to keep the message
-            //          protocol simple for `nc`.
-            spawn(
-                serde_json::to_vec,
-                |bytes| serde_json::from_slice(bytes),
-                vec![(
-                    SocketAddrV4::new(Ipv4Addr::LOCALHOST, port),
-                    SingleCopyActor,
-                )],
-            )
-            .unwrap();
-        }
-        _ => {
-            println!("USAGE:");
-            println!("  ./single-copy-register check [CLIENT_COUNT]");
-            println!("  ./single-copy-register explore [CLIENT_COUNT] [ADDRESS] [NETWORK]");
-            println!("  ./single-copy-register spawn");
-            println!(
-                "NETWORK: {}",
-                Network::<<SingleCopyActor as Actor>::Msg>::names().join(" | ")
-            );
-        }
+    fn model(&self) -> Self::Model {
+        self.clone().into_model()
+    }
+
+    // WARNING: Omits `ordered_reliable_link` to keep the message
+    //          protocol simple for `nc`.
+    fn actors(&self, _id: &dyn Fn(usize) -> Id) -> Vec<Self::Actor> {
+        vec![SingleCopyActor]
     }
+}
 
-    Ok(())
+fn main() {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    cli::run(|params| {
+        Ok(SingleCopyModelCfg {
+            client_count: params.next_or("CLIENT_COUNT", 2)?,
+            server_count: 1,
+            network: params.next_or("NETWORK", Network::new_unordered_nonduplicating([]))?,
+        })
+    });
 }
@@ -1,8 +1,7 @@
 use serde::{Deserialize, Serialize};
 use stateright::actor::model_timeout;
 use stateright::actor::{model_peers, Actor, ActorModel, Id, Network, Out};
-use stateright::report::WriteReporter;
-use stateright::{Checker, Model};
+use stateright::cli::{self, System};
 use std::borrow::Cow;
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
@@ -112,53 +111,26 @@ impl PingerModelCfg {
     }
 }
 
-fn main() -> Result<(), pico_args::Error> {
-    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+impl System for PingerModelCfg {
+    type Model = ActorModel<PingerActor, Self, ()>;
+    type Actor = ();
 
-    let mut args = pico_args::Arguments::from_env();
-    match args.subcommand()?.as_deref() {
-        Some("check") => {
-            let network = args
-                .opt_free_from_str()?
-                .unwrap_or(Network::new_unordered_nonduplicating([]));
-            println!("Model checking Pingers");
-            PingerModelCfg {
-                server_count: 3,
-                network,
-            }
-            .into_model()
-            .checker()
-            .threads(num_cpus::get())
-            .spawn_dfs()
-            .report(&mut WriteReporter::new(&mut std::io::stdout()));
-        }
-        Some("explore") => {
-            let address = args
-                .opt_free_from_str()?
-                .unwrap_or("localhost:3000".to_string());
-            let network = args
-                .opt_free_from_str()?
-                .unwrap_or(Network::new_unordered_nonduplicating([]));
-            println!("Exploring state space for Pingers on {}.", address);
-            PingerModelCfg {
-                server_count: 3,
-                network,
-            }
-            .into_model()
-            .checker()
-            .threads(num_cpus::get())
-            .serve(address);
-        }
-        _ => {
-            println!("USAGE:");
-            println!("  ./timers check [CLIENT_COUNT] [NETWORK]");
-            println!("  ./timers explore [CLIENT_COUNT] [ADDRESS] [NETWORK]");
-            println!(
-                "NETWORK: {}",
-                Network::<<PingerActor as Actor>::Msg>::names().join(" | ")
-            );
-        }
+    fn description(&self) -> String {
+        format!("{} pingers", self.server_count)
     }
 
-    Ok(())
+    fn model(&self) -> Self::Model {
+        self.clone().into_model()
+    }
+}
+
+fn main() {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    cli::run(|params| {
+        Ok(PingerModelCfg {
+            server_count: 3,
+            network: params.next_or("NETWORK", Network::new_unordered_nonduplicating([]))?,
+        })
+    });
 }
@@ -0,0 +1,408 @@
+//! This is an implementation of a write-once register, the core of consensus: the first value
+//! written is chosen, and every later write fails unless it writes the chosen value. It sits
+//! between the [ABD register](https://dl.acm.org/doi/10.1145/200836.200869) (see the
+//! `linearizable-register` example) and full Single Decree Paxos (see the `paxos` example), and
+//! shows exactly what Paxos adds to ABD to solve consensus.
+//!
+//! # The Algorithm
+//!
+//! A server that receives a `Put` acts as a proposer and runs two quorum phases, much like an ABD
+//! write:
+//!
+//! 1. **Read.** The proposer picks a ballot higher than any it has seen and asks a majority of
+//!    servers for the value they have accepted. If any server has accepted a value, the proposer
+//!    adopts the one with the highest ballot instead of its client's value.
+//! 2. **Write.** The proposer asks the servers to accept the value with its ballot. Once a majority
+//!    has accepted, the value is chosen, and the proposer tells its client whether its own value
+//!    was the one chosen (`PutOk`) or not (`PutFail`).
+//!
+//! # Where ABD Fails
+//!
+//! ABD orders writes by timestamp: a server accepts any write whose timestamp is at least as high
+//! as the one it last accepted. That is enough for a register, where a later write is allowed to
+//! overwrite an earlier one, but not for consensus. Two proposers can both complete the read phase
+//! before either writes, both find nothing accepted, and both write their own value. Each write
+//! reaches a majority, so both clients are told that their value was chosen, and the model checker
+//! finds the resulting history is not linearizable with respect to a write-once register.
+//!
+//! # What Paxos Adds
+//!
+//! The fix is for the read phase to also be a *promise*: a server that replies to a read with
+//! ballot `b` refuses every later write with a lower ballot. Any two majorities intersect, so once
+//! a value is chosen by a majority of accepts, every higher ballot's read phase must either see
+//! that value (and adopt it) or have been promised by a server that then refused the earlier write.
+//! Either way, at most one value is chosen.
+
+use serde::{Deserialize, Serialize};
+use stateright::actor::write_once_register::{WORegisterActor, WORegisterMsg, WORegisterMsg::*};
+use stateright::actor::{majority, model_peers, Actor, ActorModel, Id, Network, Out};
+use stateright::report::WriteReporter;
+use stateright::semantics::write_once_register::WORegister;
+use stateright::semantics::LinearizabilityTester;
+use stateright::util::{HashableHashMap, HashableHashSet};
+use stateright::{Checker, Expectation, Model};
+use std::borrow::Cow;
+
+type Round = u32;
+type Ballot = (Round, Id);
+type RequestId = u64;
+type Value = char;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+enum WORegisterInternalMsg {
+    Read {
+        ballot: Ballot,
+    },
+    ReadOk {
+        ballot: Ballot,
+        last_accepted: Option<(Ballot, Value)>,
+    },
+
+    Write {
+        ballot: Ballot,
+        value: Value,
+    },
+    WriteOk {
+        ballot: Ballot,
+    },
+
+    Chosen {
+        ballot: Ballot,
+        value: Value,
+    },
+}
+use WORegisterInternalMsg::*;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct WORegisterState {
+    // proposer state
+    ballot: Ballot,
+    request: Option<(RequestId, Id, Value)>,
+    reads: HashableHashMap<Id, Option<(Ballot, Value)>>,
+    writes: HashableHashSet<Id>,
+
+    // acceptor state
+    /// The lowest ballot this server will accept. Only raised by reads if the server promises.
+    promised: Ballot,
+    accepted: Option<(Ballot, Value)>,
+    chosen: Option<Value>,
+}
+
+#[derive(Clone)]
+struct WORegisterServer {
+    peer_ids: Vec<Id>,
+    /// Whether the read phase promises to refuse lower ballots (Paxos) or not (ABD).
+    promises: bool,
+}
+
+impl Actor for WORegisterServer {
+    type Msg = WORegisterMsg<RequestId, Value, WORegisterInternalMsg>;
+    type State = WORegisterState;
+    type Timer = ();
+
+    fn name(&self) -> String {
+        if self.promises {
+            "Paxos Server".to_owned()
+        } else {
+            "ABD Server".to_owned()
+        }
+    }
+
+    fn on_start(&self, _id: Id, _o: &mut Out<Self>) -> Self::State {
+        WORegisterState {
+            ballot: (0, Id::from(0)),
+            request: None,
+            reads: Default::default(),
+            writes: Default::default(),
+
+            promised: (0, Id::from(0)),
+            accepted: None,
+            chosen: None,
+        }
+    }
+
+    fn on_msg(
+        &self,
+        id: Id,
+        state: &mut Cow<Self::State>,
+        src: Id,
+        msg: Self::Msg,
+        o: &mut Out<Self>,
+    ) {
+        if let Some(chosen) = state.chosen {
+            match msg {
+                Put(request_id, value) if value == chosen => o.send(src, PutOk(request_id)),
+                Put(request_id, _) => o.send(src, PutFail(request_id)),
+                Get(request_id) => o.send(src, GetOk(request_id, chosen)),
+                _ => {}
+            }
+            return;
+        }
+
+        match msg {
+            Put(request_id, value) if state.request.is_none() => {
+                let state = state.to_mut();
+                state.request = Some((request_id, src, value));
+                state.ballot = (state.ballot.0.max(state.promised.0) + 1, id);
+                state.reads = Default::default();
+                state.writes = Default::default();
+
+                // Simulate `Read` self-send.
+                if self.promises {
+                    state.promised = state.ballot;
+                }
+                // Simulate `ReadOk` self-send.
+                state.reads.insert(id, state.accepted);
+
+                o.broadcast(
+                    &self.peer_ids,
+                    &Internal(Read {
+                        ballot: state.ballot,
+                    }),
+                );
+            }
+            Internal(Read { ballot }) if !self.promises => {
+                // ABD: a read is only a read.
+                o.send(
+                    src,
+                    Internal(ReadOk {
+                        ballot,
+                        last_accepted: state.accepted,
+                    }),
+                );
+            }
+            Internal(Read { ballot }) if state.promised < ballot => {
+                // Paxos: a read is also a promise to refuse writes with lower ballots.
+                state.to_mut().promised = ballot;
+                o.send(
+                    src,
+                    Internal(ReadOk {
+                        ballot,
+                        last_accepted: state.accepted,
+                    }),
+                );
+            }
+            Internal(ReadOk {
+                ballot,
+                last_accepted,
+            }) if ballot == state.ballot => {
+                let state = state.to_mut();
+                state.reads.insert(src, last_accepted);
+                if state.reads.len() == majority(self.peer_ids.len() + 1) {
+                    // Adopt the most recently accepted value, if any, as it may have been chosen.
+                    let (_, _, requested) = state.request.expect("request expected");
+                    let value = state
+                        .reads
+                        .values()
+                        .max()
+                        .unwrap()
+                        .map(|(_b, v)| v)
+                        .unwrap_or(requested);
+
+                    // Simulate `Write` self-send.
+                    if state.promised <= ballot {
+                        state.promised = ballot;
+                        state.accepted = Some((ballot, value));
+                        // Simulate `WriteOk` self-send.
+                        state.writes.insert(id);
+                    }
+
+                    o.broadcast(&self.peer_ids, &Internal(Write { ballot, value }));
+                }
+            }
+            Internal(Write { ballot, value }) if state.promised <= ballot => {
+                let state = state.to_mut();
+                state.promised = ballot;
+                state.accepted = Some((ballot, value));
+                o.send(src, Internal(WriteOk { ballot }));
+            }
+            Internal(WriteOk { ballot }) if ballot == state.ballot => {
+                let state = state.to_mut();
+                state.writes.insert(src);
+                if state.writes.len() == majority(self.peer_ids.len() + 1) {
+                    let (_, value) = state.accepted.expect("accepted value expected");
+                    state.chosen = Some(value);
+                    o.broadcast(&self.peer_ids, &Internal(Chosen { ballot, value }));
+                    let (request_id, requester_id, requested) =
+                        state.request.expect("request expected");
+                    if value == requested {
+                        o.send(requester_id, PutOk(request_id));
+                    } else {
+                        o.send(requester_id, PutFail(request_id));
+                    }
+                }
+            }
+            Internal(Chosen { ballot, value }) => {
+                let state = state.to_mut();
+                state.accepted = Some((ballot, value));
+                state.chosen = Some(value);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Clone)]
+struct WORegisterModelCfg {
+    client_count: usize,
+    server_count: usize,
+    promises: bool,
+}
+
+impl WORegisterModelCfg {
+    fn into_model(
+        self,
+    ) -> ActorModel<
+        WORegisterActor<WORegisterServer>,
+        Self,
+        LinearizabilityTester<Id, WORegister<Value>>,
+    > {
+        ActorModel::new(self.clone(), LinearizabilityTester::new(WORegister(None)))
+            .actors((0..self.server_count).map(|i| {
+                WORegisterActor::Server(WORegisterServer {
+                    peer_ids: model_peers(i, self.server_count),
+                    promises: self.promises,
+                })
+            }))
+            .actors((0..self.client_count).map(|_| WORegisterActor::Client {
+                put_count: 1,
+                server_count: self.server_count,
+            }))
+            .init_network(Network::new_unordered_nonduplicating([]))
+            .property(Expectation::Always, "linearizable", |_, state| {
+                state.history.serialized_history().is_some()
+            })
+            .property(Expectation::Sometimes, "value chosen", |_, state| {
+                state
+                    .network
+                    .iter_deliverable()
+                    .any(|env| matches!(env.msg, GetOk(..)))
+            })
+            .record_msg_in(WORegisterMsg::record_returns)
+            .record_msg_out(WORegisterMsg::record_invocations)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn can_model_write_once_register() {
+    // Promises make the register linearizable.
+    let checker = WORegisterModelCfg {
+        client_count: 2,
+        server_count: 3,
+        promises: true,
+    }
+    .into_model()
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_properties();
+    assert_eq!(checker.unique_state_count(), 90_233);
+
+    // Without them, two clients can both be told that their value was chosen.
+    let checker = WORegisterModelCfg {
+        client_count: 2,
+        server_count: 3,
+        promises: false,
+    }
+    .into_model()
+    .checker()
+    .spawn_bfs()
+    .join();
+    checker.assert_any_discovery("linearizable");
+    checker.assert_any_discovery("value chosen");
+}
+
+fn main() -> Result<(), pico_args::Error> {
+    use stateright::actor::spawn;
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    let mut args = pico_args::Arguments::from_env();
+    let promises = !args.contains("--abd");
+    let protocol = if promises { "Paxos" } else { "ABD" };
+    match args.subcommand()?.as_deref() {
+        Some("check") => {
+            let client_count = args.opt_free_from_str()?.unwrap_or(2);
+            println!(
+                "Model checking a write-once register ({}) with {} clients.",
+                protocol, client_count
+            );
+            WORegisterModelCfg {
+                client_count,
+                server_count: 3,
+                promises,
+            }
+            .into_model()
+            .checker()
+            .threads(num_cpus::get())
+            .spawn_dfs()
+            .report(&mut WriteReporter::new(&mut std::io::stdout()));
+        }
+        Some("explore") => {
+            let client_count = args.opt_free_from_str()?.unwrap_or(2);
+            let address = args
+                .opt_free_from_str()?
+                .unwrap_or("localhost:3000".to_string());
+            println!(
+                "Exploring state space for a write-once register ({}) with {} clients on {}.",
+                protocol, client_count, address
+            );
+            WORegisterModelCfg {
+                client_count,
+                server_count: 3,
+                promises,
+            }
+            .into_model()
+            .checker()
+            .threads(num_cpus::get())
+            .serve(address);
+        }
+        Some("spawn") => {
+            let port = 3000;
+
+            println!(
+                "  A set of servers that implement a write-once register ({}).",
+                protocol
+            );
+            println!("  You can monitor and interact using tcpdump and netcat.");
+            println!("  Use `tcpdump -D` if you see error `lo0: No such device exists`.");
+            println!("Examples:");
+            println!("$ sudo tcpdump -i lo0 -s 0 -nnX");
+            println!("$ nc -u localhost {}", port);
+            println!(
+                "{}",
+                serde_json::to_string(&WORegisterMsg::Put::<RequestId, Value, ()>(1, 'X')).unwrap()
+            );
+            println!(
+                "{}",
+                serde_json::to_string(&WORegisterMsg::Get::<RequestId, Value, ()>(2)).unwrap()
+            );
+            println!();
+
+            let ids: Vec<_> = (0..3)
+                .map(|i| Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port + i)))
+                .collect();
+            spawn(
+                serde_json::to_vec,
+                |bytes| serde_json::from_slice(bytes),
+                ids.iter()
+                    .map(|id| {
+                        let peer_ids = ids.iter().copied().filter(|p| p != id).collect();
+                        (*id, WORegisterServer { peer_ids, promises })
+                    })
+                    .collect(),
+            )
+            .unwrap();
+        }
+        _ => {
+            println!("USAGE:");
+            println!("  ./write-once-register check [CLIENT_COUNT] [--abd]");
+            println!("  ./write-once-register explore [CLIENT_COUNT] [ADDRESS] [--abd]");
+            println!("  ./write-once-register spawn [--abd]");
+        }
+    }
+
+    Ok(())
+}
@@ -81,12 +81,23 @@
 //! [Additional examples](https://github.com/stateright/stateright/tree/master/examples)
 //! are available in the repository.
 
+use buffer_pool::BufferPool;
 use choice::{Choice, Never};
+use smallvec::SmallVec;
+mod buffer_pool;
+mod metrics;
 mod model;
 mod model_state;
 mod network;
+#[cfg(feature = "spawn")]
+mod persistence;
+mod promela;
+#[cfg(feature = "spawn")]
+mod rate_limit;
+#[cfg(feature = "spawn")]
 mod spawn;
 mod timers;
+mod topology;
 use std::borrow::Cow;
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hash;
@@ -96,13 +107,21 @@ use std::time::Duration;
 
 #[cfg(test)]
 pub mod actor_test_util;
+pub use metrics::*;
 pub use model::*;
 pub use model_state::*;
 pub use network::*;
 pub use timers::*;
+pub mod failure_detector;
+pub mod golden;
+#[cfg(feature = "spawn")]
+pub mod harness;
+pub mod monitor;
 pub mod ordered_reliable_link;
+pub mod properties;
 pub mod register;
 pub mod write_once_register;
+#[cfg(feature = "spawn")]
 pub use spawn::*;
 
 /// Uniquely identifies an [`Actor`]. Encodes the socket address for spawned
@@ -110,6 +129,7 @@ pub use spawn::*;
 #[derive(
     Clone, Copy, Default, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Serialize, serde::Deserialize,
 )]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Id(u64);
 
 impl Debug for Id {
@@ -155,6 +175,42 @@ impl From<usize> for Id {
     }
 }
 
+impl From<Id> for SocketAddrV4 {
+    fn from(id: Id) -> Self {
+        let bytes = id.0.to_be_bytes();
+        let ip = std::net::Ipv4Addr::from([bytes[2], bytes[3], bytes[4], bytes[5]]);
+        let port = u16::from_be_bytes([bytes[6], bytes[7]]);
+        SocketAddrV4::new(ip, port)
+    }
+}
+
+impl From<SocketAddrV4> for Id {
+    fn from(addr: SocketAddrV4) -> Self {
+        let octets = addr.ip().octets();
+        let port_bytes = addr.port().to_be_bytes();
+        let mut result: [u8; 8] = [0; 8];
+        result[0] = 0;
+        result[1] = 0;
+        result[2] = octets[0];
+        result[3] = octets[1];
+        result[4] = octets[2];
+        result[5] = octets[3];
+        result[6] = port_bytes[0];
+        result[7] = port_bytes[1];
+        Id(u64::from_be_bytes(result))
+    }
+}
+
+impl crate::util::CompactState for Id {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.0.encode(buf);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        u64::decode(buf).map(Id)
+    }
+}
+
 /// Commands with which an actor can respond.
 #[derive(Debug, serde::Serialize)]
 pub enum Command<Msg, Timer> {
@@ -166,8 +222,17 @@ pub enum Command<Msg, Timer> {
     Send(Id, Msg),
 }
 
-/// Holds [`Command`]s output by an actor.
-pub struct Out<A: Actor>(Vec<Command<A::Msg, A::Timer>>);
+/// Holds [`Command`]s output by an actor, along with the labels of any failed assertions (see
+/// [`Out::assert`]).
+///
+/// Most steps output only a few commands, so these are stored inline, and a heap allocation is
+/// only needed for steps that output more than four commands.
+pub struct Out<A: Actor>(
+    SmallVec<[Command<A::Msg, A::Timer>; OUT_INLINE_CAPACITY]>,
+    Vec<&'static str>,
+);
+
+const OUT_INLINE_CAPACITY: usize = 4;
 
 impl<A: Actor> Default for Out<A> {
     fn default() -> Self {
@@ -178,15 +243,40 @@ impl<A: Actor> Default for Out<A> {
 impl<A: Actor> Out<A> {
     /// Constructs an empty `Out`.
     pub fn new() -> Self {
-        Self(Vec::new())
+        Self(SmallVec::new(), Vec::new())
     }
 
-    /// Moves all [`Command`]s of `other` into `Self`, leaving `other` empty.
+    /// Constructs an empty `Out` that pushes onto a reused buffer, which is only retained if it
+    /// exceeds the inline capacity.
+    pub(crate) fn with_buffer(buffer: Vec<Command<A::Msg, A::Timer>>) -> Self {
+        debug_assert!(buffer.is_empty());
+        Self(SmallVec::from_vec(buffer), Vec::new())
+    }
+
+    /// Removes the [`Command`]s in the order that they were output.
+    pub(crate) fn drain(&mut self) -> impl Iterator<Item = Command<A::Msg, A::Timer>> + '_ {
+        self.0.drain(..)
+    }
+
+    /// Removes the labels of failed assertions in the order that they were output.
+    pub(crate) fn drain_failed_assertions(&mut self) -> impl Iterator<Item = &'static str> + '_ {
+        self.1.drain(..)
+    }
+
+    /// Returns the buffer to the pool if the [`Command`]s did not fit inline.
+    pub(crate) fn release(self, pool: &BufferPool<Command<A::Msg, A::Timer>>) {
+        if self.0.spilled() {
+            pool.release(self.0.into_vec());
+        }
+    }
+
+    /// Moves all [`Command`]s and failed assertions of `other` into `Self`, leaving `other` empty.
     pub fn append<B>(&mut self, other: &mut Out<B>)
     where
         B: Actor<Msg = A::Msg, Timer = A::Timer>,
     {
-        self.0.append(&mut other.0)
+        self.0.append(&mut other.0);
+        self.1.append(&mut other.1);
     }
 
     /// Records the need to set the timer. See [`Actor::on_timeout`].
@@ -204,6 +294,16 @@ impl<A: Actor> Out<A> {
         self.0.push(Command::Send(recipient, msg));
     }
 
+    /// Asserts a local invariant, recording a failure under the specified label if `condition`
+    /// is false. Once [`ActorModel::check_assertions`] is called, an [`ActorModel`] records the
+    /// failure in its state and surfaces it as a property violation with the offending trace,
+    /// whereas the runtime logs or panics per its [`AssertionPolicy`].
+    pub fn assert(&mut self, condition: bool, label: &'static str) {
+        if !condition {
+            self.1.push(label);
+        }
+    }
+
     /// Records the need to send a message to multiple recipients. See [`Actor::on_msg`].
     pub fn broadcast<'a>(&mut self, recipients: impl IntoIterator<Item = &'a Id>, msg: &A::Msg)
     where
@@ -230,13 +330,13 @@ impl<A: Actor> std::ops::Deref for Out<A> {
 
 impl<A: Actor> std::iter::FromIterator<Command<A::Msg, A::Timer>> for Out<A> {
     fn from_iter<I: IntoIterator<Item = Command<A::Msg, A::Timer>>>(iter: I) -> Self {
-        Out(Vec::from_iter(iter))
+        Out(SmallVec::from_iter(iter), Vec::new())
     }
 }
 
 impl<A: Actor> IntoIterator for Out<A> {
     type Item = Command<A::Msg, A::Timer>;
-    type IntoIter = std::vec::IntoIter<Command<A::Msg, A::Timer>>;
+    type IntoIter = smallvec::IntoIter<[Command<A::Msg, A::Timer>; OUT_INLINE_CAPACITY]>;
     fn into_iter(self) -> Self::IntoIter {
         self.0.into_iter()
     }
@@ -245,7 +345,7 @@ impl<A: Actor> IntoIterator for Out<A> {
 /// If true, then the actor did not update its state or output commands.
 #[allow(clippy::ptr_arg)] // `&Cow` needed for `matches!`
 pub fn is_no_op<A: Actor>(state: &Cow<A::State>, out: &Out<A>) -> bool {
-    matches!(state, Cow::Borrowed(_)) && out.0.is_empty()
+    matches!(state, Cow::Borrowed(_)) && out.0.is_empty() && out.1.is_empty()
 }
 
 /// If true, then the actor did not update its state or output commands, besides renewing the same
@@ -259,7 +359,7 @@ pub fn is_no_op_with_timer<A: Actor>(
     let keep_timer = out
         .iter()
         .any(|c| matches!(c, Command::SetTimer(t, _) if t == timer));
-    let unmodified_out = out.0.len() == 1 && keep_timer;
+    let unmodified_out = out.0.len() == 1 && out.1.is_empty() && keep_timer;
     matches!(state, Cow::Borrowed(_)) && unmodified_out
 }
 
@@ -335,9 +435,39 @@ pub trait Actor: Sized {
         let _ = o;
     }
 
+    /// Indicates the commands to output when a spawned actor resumes from a persisted state (see
+    /// `SpawnBuilder::persist_states`) instead of calling [`Actor::on_start`]. Timers do not
+    /// survive a restart, so by default this sets the timers that [`Actor::on_start`] would set.
+    /// An [`ActorModel`] checks recovery once [`ActorModel::max_recoveries`] is set.
+    fn on_recover(&self, id: Id, state: &Self::State, o: &mut Out<Self>) {
+        let _ = state;
+        let mut start = Out::new();
+        self.on_start(id, &mut start);
+        o.0.extend(
+            start
+                .0
+                .into_iter()
+                .filter(|c| matches!(c, Command::SetTimer(..))),
+        );
+    }
+
     fn name(&self) -> String {
         String::new()
     }
+
+    /// Converts a state of this actor to a more intuitive representation (e.g. for Explorer).
+    /// [`None`] indicates that the [`Debug`] representation should be used.
+    fn format_state(&self, state: &Self::State) -> Option<String> {
+        let _ = state;
+        None
+    }
+
+    /// Converts a message sent to this actor to a more intuitive representation (e.g. for
+    /// Explorer). [`None`] indicates that the [`Debug`] representation should be used.
+    fn format_msg(&self, msg: &Self::Msg) -> Option<String> {
+        let _ = msg;
+        None
+    }
 }
 
 impl<A> Actor for Choice<A, Never>
@@ -397,6 +527,14 @@ where
     fn name(&self) -> String {
         self.get().name()
     }
+
+    fn format_state(&self, state: &Self::State) -> Option<String> {
+        self.get().format_state(state.get())
+    }
+
+    fn format_msg(&self, msg: &Self::Msg) -> Option<String> {
+        self.get().format_msg(msg)
+    }
 }
 
 impl<Msg, Timer, A1, A2> Actor for Choice<A1, A2>
@@ -494,6 +632,21 @@ where
             Choice::R(a) => a.name(),
         }
     }
+
+    fn format_state(&self, state: &Self::State) -> Option<String> {
+        match (self, state) {
+            (Choice::L(a), Choice::L(s)) => a.format_state(s),
+            (Choice::R(a), Choice::R(s)) => a.format_state(s),
+            _ => None,
+        }
+    }
+
+    fn format_msg(&self, msg: &Self::Msg) -> Option<String> {
+        match self {
+            Choice::L(a) => a.format_msg(msg),
+            Choice::R(a) => a.format_msg(msg),
+        }
+    }
 }
 
 /// Implemented only for rustdoc tests. Do not take a dependency on this. It will likely be removed
@@ -583,6 +736,32 @@ mod test {
         );
     }
 
+    #[test]
+    fn out_stores_few_commands_inline() {
+        let pool = BufferPool::new();
+        let mut out = Out::<Vec<(Id, usize)>>::new();
+        for i in 0..OUT_INLINE_CAPACITY {
+            out.send(Id::from(i), i);
+        }
+        assert!(!out.0.spilled());
+
+        out.send(Id::from(0), OUT_INLINE_CAPACITY);
+        assert!(out.0.spilled());
+        assert_eq!(
+            out.drain()
+                .map(|c| match c {
+                    Command::Send(_, msg) => msg,
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>(),
+            (0..=OUT_INLINE_CAPACITY).collect::<Vec<_>>()
+        );
+        out.release(&pool);
+        let buffer = pool.take();
+        assert!(buffer.capacity() > OUT_INLINE_CAPACITY);
+        assert!(Out::<Vec<(Id, usize)>>::with_buffer(buffer).0.spilled());
+    }
+
     #[test]
     fn vec_can_serve_as_actor() {
         use crate::StateRecorder;
@@ -0,0 +1,105 @@
+//! Private module for reusing the buffers that back [`Out`](crate::actor::Out).
+
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The pool is split into shards so that checker threads rarely contend for the same lock.
+const SHARD_COUNT: usize = 16;
+
+/// Bounds the number of idle buffers retained by each shard.
+const MAX_BUFFERS_PER_SHARD: usize = 64;
+
+/// Buffers that grew beyond this capacity are released rather than retained, so that an
+/// occasional large step does not pin memory for the rest of the run.
+const MAX_BUFFER_CAPACITY: usize = 256;
+
+static NEXT_SHARD: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static SHARD: usize = NEXT_SHARD.fetch_add(1, Ordering::Relaxed) % SHARD_COUNT;
+}
+
+/// A pool of empty vectors whose allocations are reused across successor generation. Each thread
+/// consistently uses the same shard, so a buffer is typically reused by the thread that released
+/// it.
+pub(crate) struct BufferPool<T> {
+    shards: Box<[Mutex<Vec<Vec<T>>>]>,
+}
+
+impl<T> BufferPool<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            shards: (0..SHARD_COUNT).map(|_| Mutex::new(Vec::new())).collect(),
+        }
+    }
+
+    /// Returns an empty buffer, reusing a previously released allocation if one is available.
+    pub(crate) fn take(&self) -> Vec<T> {
+        self.shard().lock().pop().unwrap_or_default()
+    }
+
+    /// Clears the buffer and makes its allocation available to a subsequent [`BufferPool::take`].
+    pub(crate) fn release(&self, mut buffer: Vec<T>) {
+        if buffer.capacity() == 0 || buffer.capacity() > MAX_BUFFER_CAPACITY {
+            return;
+        }
+        buffer.clear();
+        let mut shard = self.shard().lock();
+        if shard.len() < MAX_BUFFERS_PER_SHARD {
+            shard.push(buffer);
+        }
+    }
+
+    fn shard(&self) -> &Mutex<Vec<Vec<T>>> {
+        &self.shards[SHARD.with(|shard| *shard)]
+    }
+}
+
+/// Clones start with an empty pool, as the buffers hold no data.
+impl<T> Clone for BufferPool<T> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Default for BufferPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reuses_released_allocations() {
+        let pool = BufferPool::new();
+        let mut buffer = pool.take();
+        buffer.extend([1, 2, 3]);
+        let ptr = buffer.as_ptr();
+        pool.release(buffer);
+
+        let buffer = pool.take();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.as_ptr(), ptr);
+        assert_eq!(pool.take().capacity(), 0);
+    }
+
+    #[test]
+    fn drops_oversized_and_unallocated_buffers() {
+        let pool = BufferPool::<u8>::new();
+        pool.release(Vec::new());
+        pool.release(Vec::with_capacity(MAX_BUFFER_CAPACITY + 1));
+        assert_eq!(pool.take().capacity(), 0);
+    }
+
+    #[test]
+    fn bounds_idle_buffers() {
+        let pool = BufferPool::<u8>::new();
+        for _ in 0..2 * MAX_BUFFERS_PER_SHARD {
+            pool.release(Vec::with_capacity(1));
+        }
+        assert_eq!(pool.shard().lock().len(), MAX_BUFFERS_PER_SHARD);
+    }
+}
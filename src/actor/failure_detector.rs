@@ -0,0 +1,320 @@
+//! An eventually perfect failure detector (◇P) based loosely on the "increasing timeout" algorithm
+//! described in "[Introduction to Reliable and Secure Distributed
+//! Programming](https://link.springer.com/book/10.1007/978-3-642-15260-3)" by Cachin, Guerraoui,
+//! and Rodrigues.
+//!
+//! Each actor periodically exchanges heartbeats with its peers and suspects a peer that does not
+//! reply before the next check. A suspected peer whose reply arrives later is restored. The
+//! wrapped actor is notified of both events via [`DetectorActor::on_suspect`] and
+//! [`DetectorActor::on_restore`].
+//!
+//! # Timing Assumptions
+//!
+//! The model checker can fire a timer at any point, so in a model every timeout can be premature,
+//! whereas the detector is only eventually perfect if timeouts eventually exceed message delays.
+//! [`is_eventually_accurate`] expresses that assumption as a [boundary](ActorModel::within_boundary):
+//! each detector can make a bounded number of false suspicions, after which it only suspects
+//! crashed peers. A bound of zero yields a perfect failure detector (P).
+//!
+//! At most one heartbeat is outstanding per peer, which keeps the network bounded. Replies are not
+//! matched to heartbeats, so the detector assumes a network that does not duplicate messages, such
+//! as [`Network::new_unordered_nonduplicating`]; otherwise a redelivered reply can restore a crashed
+//! peer.
+
+use crate::actor::*;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::Range;
+use std::time::Duration;
+
+/// An actor that is notified when the failure detector suspects or restores a peer.
+pub trait DetectorActor: Actor {
+    /// Indicates the next state and commands when a peer is suspected to have crashed.
+    fn on_suspect(&self, id: Id, state: &mut Cow<Self::State>, peer: Id, o: &mut Out<Self>) {
+        let _ = (id, state, peer, o);
+    }
+
+    /// Indicates the next state and commands when a suspected peer is found to be alive.
+    fn on_restore(&self, id: Id, state: &mut Cow<Self::State>, peer: Id, o: &mut Out<Self>) {
+        let _ = (id, state, peer, o);
+    }
+}
+
+/// Wraps an actor with a failure detector that monitors its peers.
+#[derive(Clone)]
+pub struct ActorWrapper<A: Actor> {
+    pub check_interval: Range<Duration>,
+    pub peer_ids: Vec<Id>,
+    pub wrapped_actor: A,
+}
+
+/// An envelope for failure detector messages.
+#[derive(
+    Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Serialize, serde::Deserialize,
+)]
+pub enum MsgWrapper<Msg> {
+    Deliver(Msg),
+    Heartbeat,
+    HeartbeatReply,
+}
+
+/// Maintains state for the failure detector.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct StateWrapper<State> {
+    /// Peers whose heartbeat replies are outstanding.
+    awaiting: BTreeSet<Id>,
+    /// Peers that are suspected to have crashed.
+    pub suspected: BTreeSet<Id>,
+    /// The number of times a suspected peer was restored.
+    pub false_suspicions: usize,
+    pub wrapped_state: State,
+}
+
+/// Wrapper for timers.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, serde::Serialize)]
+pub enum TimerWrapper<Timer> {
+    Check,
+    User(Timer),
+}
+
+impl<A: Actor> ActorWrapper<A> {
+    pub fn with_default_timeout(wrapped_actor: A, peer_ids: Vec<Id>) -> Self {
+        Self {
+            check_interval: Duration::from_secs(1)..Duration::from_secs(2),
+            peer_ids,
+            wrapped_actor,
+        }
+    }
+}
+
+impl<A: DetectorActor> Actor for ActorWrapper<A> {
+    type Msg = MsgWrapper<A::Msg>;
+    type State = StateWrapper<A::State>;
+    type Timer = TimerWrapper<A::Timer>;
+
+    fn on_start(&self, id: Id, o: &mut Out<Self>) -> Self::State {
+        o.set_timer(TimerWrapper::Check, self.check_interval.clone());
+        o.broadcast(&self.peer_ids, &MsgWrapper::Heartbeat);
+
+        let mut wrapped_out = Out::new();
+        let state = StateWrapper {
+            awaiting: self.peer_ids.iter().copied().collect(),
+            suspected: Default::default(),
+            false_suspicions: 0,
+            wrapped_state: self.wrapped_actor.on_start(id, &mut wrapped_out),
+        };
+        process_output(wrapped_out, o);
+        state
+    }
+
+    fn on_msg(
+        &self,
+        id: Id,
+        state: &mut Cow<Self::State>,
+        src: Id,
+        msg: Self::Msg,
+        o: &mut Out<Self>,
+    ) {
+        match msg {
+            MsgWrapper::Deliver(wrapped_msg) => {
+                self.update_wrapped(state, o, |wrapped_state, wrapped_out| {
+                    self.wrapped_actor
+                        .on_msg(id, wrapped_state, src, wrapped_msg, wrapped_out)
+                });
+            }
+            MsgWrapper::Heartbeat => {
+                o.send(src, MsgWrapper::HeartbeatReply);
+            }
+            MsgWrapper::HeartbeatReply => {
+                if !state.awaiting.contains(&src) {
+                    return;
+                }
+                let state_mut = state.to_mut();
+                state_mut.awaiting.remove(&src);
+                if state_mut.suspected.remove(&src) {
+                    state_mut.false_suspicions += 1;
+                    self.update_wrapped(state, o, |wrapped_state, wrapped_out| {
+                        self.wrapped_actor
+                            .on_restore(id, wrapped_state, src, wrapped_out)
+                    });
+                }
+            }
+        }
+    }
+
+    fn on_timeout(
+        &self,
+        id: Id,
+        state: &mut Cow<Self::State>,
+        timer: &Self::Timer,
+        o: &mut Out<Self>,
+    ) {
+        match timer {
+            TimerWrapper::Check => {
+                o.set_timer(TimerWrapper::Check, self.check_interval.clone());
+                for peer in &self.peer_ids {
+                    if !state.awaiting.contains(peer) {
+                        // Replied since the last check, so send another heartbeat.
+                        o.send(*peer, MsgWrapper::Heartbeat);
+                        state.to_mut().awaiting.insert(*peer);
+                    } else if !state.suspected.contains(peer) {
+                        state.to_mut().suspected.insert(*peer);
+                        self.update_wrapped(state, o, |wrapped_state, wrapped_out| {
+                            self.wrapped_actor
+                                .on_suspect(id, wrapped_state, *peer, wrapped_out)
+                        });
+                    }
+                }
+            }
+            TimerWrapper::User(timer) => {
+                self.update_wrapped(state, o, |wrapped_state, wrapped_out| {
+                    self.wrapped_actor
+                        .on_timeout(id, wrapped_state, timer, wrapped_out)
+                });
+            }
+        }
+    }
+
+    fn name(&self) -> String {
+        self.wrapped_actor.name()
+    }
+}
+
+impl<A: DetectorActor> ActorWrapper<A> {
+    /// Invokes a handler of the wrapped actor, only modifying the wrapper state if the wrapped
+    /// actor's state changes.
+    fn update_wrapped(
+        &self,
+        state: &mut Cow<StateWrapper<A::State>>,
+        o: &mut Out<Self>,
+        handler: impl FnOnce(&mut Cow<A::State>, &mut Out<A>),
+    ) {
+        let mut wrapped_state = Cow::Borrowed(&state.wrapped_state);
+        let mut wrapped_out = Out::new();
+        handler(&mut wrapped_state, &mut wrapped_out);
+        if let Cow::Owned(wrapped_state) = wrapped_state {
+            state.to_mut().wrapped_state = wrapped_state;
+        }
+        process_output(wrapped_out, o);
+    }
+}
+
+fn process_output<A: DetectorActor>(mut wrapped_out: Out<A>, o: &mut Out<ActorWrapper<A>>) {
+    for label in wrapped_out.drain_failed_assertions() {
+        o.assert(false, label);
+    }
+    for command in wrapped_out {
+        match command {
+            Command::CancelTimer(timer) => {
+                o.cancel_timer(TimerWrapper::User(timer));
+            }
+            Command::SetTimer(timer, duration) => {
+                o.set_timer(TimerWrapper::User(timer), duration);
+            }
+            Command::Send(dst, inner_msg) => {
+                o.send(dst, MsgWrapper::Deliver(inner_msg));
+            }
+        }
+    }
+}
+
+/// Indicates whether every failure detector is within its timing assumptions: a detector that has
+/// made `max_false_suspicions` false suspicions only suspects crashed peers. Intended for use with
+/// [`ActorModel::within_boundary`].
+pub fn is_eventually_accurate<A: DetectorActor, H>(
+    max_false_suspicions: usize,
+    state: &ActorModelState<ActorWrapper<A>, H>,
+) -> bool {
+    state.actor_states.iter().all(|s| {
+        s.false_suspicions < max_false_suspicions
+            || s.suspected.iter().all(|id| state.crashed[usize::from(*id)])
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::actor::failure_detector::{is_eventually_accurate, ActorWrapper, DetectorActor};
+    use crate::actor::{Actor, ActorModel, Id, Network, Out};
+    use crate::{Checker, Expectation, Model};
+    use std::borrow::Cow;
+
+    /// Records the peers that are currently suspected.
+    pub struct TestActor;
+    #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+    pub struct Suspected(Vec<Id>);
+
+    impl Actor for TestActor {
+        type Msg = ();
+        type State = Suspected;
+        type Timer = ();
+
+        fn on_start(&self, _id: Id, _o: &mut Out<Self>) -> Self::State {
+            Suspected(Vec::new())
+        }
+    }
+
+    impl DetectorActor for TestActor {
+        fn on_suspect(&self, _id: Id, state: &mut Cow<Self::State>, peer: Id, _o: &mut Out<Self>) {
+            state.to_mut().0.push(peer);
+        }
+
+        fn on_restore(&self, _id: Id, state: &mut Cow<Self::State>, peer: Id, _o: &mut Out<Self>) {
+            state.to_mut().0.retain(|id| *id != peer);
+        }
+    }
+
+    fn model(max_false_suspicions: usize) -> ActorModel<ActorWrapper<TestActor>, usize> {
+        ActorModel::new(max_false_suspicions, ())
+            .actor(ActorWrapper::with_default_timeout(
+                TestActor,
+                vec![1.into()],
+            ))
+            .actor(ActorWrapper::with_default_timeout(
+                TestActor,
+                vec![0.into()],
+            ))
+            .init_network(Network::new_unordered_nonduplicating([]))
+            .max_crashes(1)
+            .property(Expectation::Always, "accurate", |_, state| {
+                state.actor_states.iter().all(|s| {
+                    s.wrapped_state
+                        .0
+                        .iter()
+                        .all(|id| state.crashed[usize::from(*id)])
+                })
+            })
+            .property(Expectation::Sometimes, "crash suspected", |_, state| {
+                (0..2).any(|i| {
+                    state.crashed[i]
+                        && state.actor_states[1 - i].wrapped_state.0 == vec![Id::from(i)]
+                })
+            })
+            .property(Expectation::Sometimes, "restored", |_, state| {
+                state.actor_states.iter().any(|s| s.false_suspicions > 0)
+            })
+            .within_boundary(|max_false_suspicions, state| {
+                is_eventually_accurate(*max_false_suspicions, state)
+            })
+    }
+
+    #[test]
+    fn crashed_peers_are_suspected() {
+        model(0)
+            .checker()
+            .spawn_bfs()
+            .join()
+            .assert_any_discovery("crash suspected");
+    }
+
+    #[test]
+    fn correct_peers_are_suspected_within_timing_assumptions() {
+        let checker = model(0).checker().spawn_bfs().join();
+        checker.assert_no_discovery("accurate");
+
+        let checker = model(1).checker().spawn_bfs().join();
+        checker.assert_any_discovery("accurate");
+        checker.assert_any_discovery("restored");
+    }
+}
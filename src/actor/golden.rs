@@ -0,0 +1,246 @@
+//! Golden files that pin down the wire format of [spawned](crate::actor::spawn()) actors.
+//!
+//! A model checker verifies the protocol, but not the bytes that carry it. Renaming a message
+//! variant or reordering fields can silently break compatibility between nodes running different
+//! revisions. [`WireFormat`] guards against this by serializing a representative sample of each
+//! message variant to files that are checked into version control, and later asserting that the
+//! current serialization still matches those files byte-for-byte and that the files still
+//! deserialize to the original messages.
+//!
+//! # Example
+//!
+//! ```
+//! use stateright::actor::golden::WireFormat;
+//! use stateright::actor::register::RegisterMsg;
+//!
+//! # let dir = std::env::temp_dir().join(format!("stateright-golden-doc-{}", std::process::id()));
+//! let wire_format = WireFormat::new(
+//!     &dir, // e.g. "tests/golden"
+//!     |msg: &RegisterMsg<u64, char, ()>| serde_json::to_vec(msg),
+//!     |bytes| serde_json::from_slice(bytes),
+//! )
+//! .sample("put", RegisterMsg::Put(1, 'A'))
+//! .sample("put_ok", RegisterMsg::PutOk(1))
+//! .sample("get", RegisterMsg::Get(2))
+//! .sample("get_ok", RegisterMsg::GetOk(2, 'A'));
+//!
+//! // Run once, and again whenever the wire format is intentionally changed.
+//! wire_format.write().unwrap();
+//!
+//! // Then assert in a unit test.
+//! wire_format.assert_unchanged();
+//! # std::fs::remove_dir_all(&dir).unwrap();
+//! ```
+
+use std::fmt::Debug;
+use std::fs;
+use std::path::PathBuf;
+
+/// The extension used for golden files.
+const EXTENSION: &str = "golden";
+
+/// A set of sample messages and the directory holding their golden files. See the
+/// [module documentation](self) for an example.
+pub struct WireFormat<Msg, E> {
+    dir: PathBuf,
+    serialize: fn(&Msg) -> Result<Vec<u8>, E>,
+    deserialize: fn(&[u8]) -> Result<Msg, E>,
+    samples: Vec<(String, Msg)>,
+}
+
+impl<Msg, E> WireFormat<Msg, E>
+where
+    Msg: Debug + PartialEq,
+    E: Debug,
+{
+    /// Instantiates a golden file set that will use the specified functions to convert messages
+    /// to and from bytes.
+    pub fn new(
+        dir: impl Into<PathBuf>,
+        serialize: fn(&Msg) -> Result<Vec<u8>, E>,
+        deserialize: fn(&[u8]) -> Result<Msg, E>,
+    ) -> Self {
+        Self {
+            dir: dir.into(),
+            serialize,
+            deserialize,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Adds a representative message, which will be stored in a file named after `name`.
+    /// Typically there is at least one sample per message variant.
+    pub fn sample(mut self, name: impl Into<String>, msg: Msg) -> Self {
+        let name = name.into();
+        assert!(
+            !self.samples.iter().any(|(n, _)| n == &name),
+            "duplicate sample name: {}",
+            name
+        );
+        self.samples.push((name, msg));
+        self
+    }
+
+    /// The path of the golden file for a sample.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", name, EXTENSION))
+    }
+
+    /// Serializes every sample to its golden file, replacing any existing file and removing
+    /// golden files for samples that no longer exist.
+    pub fn write(&self) -> std::io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        for name in self.stale_files()? {
+            fs::remove_file(self.path(&name))?;
+        }
+        for (name, msg) in &self.samples {
+            let bytes = (self.serialize)(msg).map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("unable to serialize {}: {:?}", name, e),
+                )
+            })?;
+            fs::write(self.path(name), bytes)?;
+        }
+        Ok(())
+    }
+
+    /// Returns a description of each way in which the current wire format differs from the
+    /// golden files. An empty result indicates that the wire format is unchanged.
+    pub fn check(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (name, msg) in &self.samples {
+            let path = self.path(name);
+            let golden = match fs::read(&path) {
+                Ok(golden) => golden,
+                Err(e) => {
+                    problems.push(format!("{}: unable to read {:?}: {}", name, path, e));
+                    continue;
+                }
+            };
+            match (self.serialize)(msg) {
+                Ok(actual) if actual != golden => problems.push(format!(
+                    "{}: serialization changed. golden={}, actual={}",
+                    name,
+                    display_bytes(&golden),
+                    display_bytes(&actual)
+                )),
+                Ok(_) => {}
+                Err(e) => problems.push(format!("{}: unable to serialize: {:?}", name, e)),
+            }
+            match (self.deserialize)(&golden) {
+                Ok(decoded) if &decoded != msg => problems.push(format!(
+                    "{}: deserialization changed. expected={:?}, actual={:?}",
+                    name, msg, decoded
+                )),
+                Ok(_) => {}
+                Err(e) => problems.push(format!("{}: unable to deserialize: {:?}", name, e)),
+            }
+        }
+        match self.stale_files() {
+            Ok(stale) => {
+                for name in stale {
+                    problems.push(format!("{}: golden file has no sample", name));
+                }
+            }
+            Err(e) => problems.push(format!("unable to list {:?}: {}", self.dir, e)),
+        }
+        problems
+    }
+
+    /// Panics if the current wire format differs from the golden files. See
+    /// [`WireFormat::check`].
+    pub fn assert_unchanged(&self) {
+        let problems = self.check();
+        if !problems.is_empty() {
+            panic!(
+                "Wire format differs from golden files in {:?}. Call `WireFormat::write` if the change is intentional.\n{}",
+                self.dir,
+                problems.join("\n")
+            );
+        }
+    }
+
+    /// Names of golden files without a corresponding sample, in sorted order.
+    fn stale_files(&self) -> std::io::Result<Vec<String>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut stale = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(EXTENSION) {
+                continue;
+            }
+            if let Some(name) = path.file_stem().and_then(|n| n.to_str()) {
+                if !self.samples.iter().any(|(n, _)| n == name) {
+                    stale.push(name.to_string());
+                }
+            }
+        }
+        stale.sort();
+        Ok(stale)
+    }
+}
+
+/// Renders bytes as text if possible, since most wire formats in practice are textual.
+fn display_bytes(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => format!("{:?}", s),
+        Err(_) => format!("{:?}", bytes),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::actor::register::RegisterMsg;
+    use std::path::Path;
+
+    type Msg = RegisterMsg<u64, char, ()>;
+
+    fn wire_format(dir: &Path) -> WireFormat<Msg, serde_json::Error> {
+        WireFormat::new(
+            dir,
+            |m: &Msg| serde_json::to_vec(m),
+            |b| serde_json::from_slice(b),
+        )
+        .sample("put", RegisterMsg::Put(1, 'A'))
+        .sample("get", RegisterMsg::Get(2))
+    }
+
+    #[test]
+    fn detects_wire_format_changes() {
+        let dir = std::env::temp_dir().join(format!("stateright-golden-{}", std::process::id()));
+        let golden = wire_format(&dir);
+        assert_eq!(golden.check().len(), 2); // files do not exist yet
+        golden.write().unwrap();
+        assert_eq!(golden.check(), Vec::<String>::new());
+        assert_eq!(fs::read(golden.path("get")).unwrap(), br#"{"Get":2}"#);
+
+        // A changed encoding is reported from both directions.
+        fs::write(golden.path("get"), br#"{"Get":3}"#).unwrap();
+        assert_eq!(
+            golden.check(),
+            vec![
+                r#"get: serialization changed. golden="{\"Get\":3}", actual="{\"Get\":2}""#,
+                "get: deserialization changed. expected=Get(2), actual=Get(3)",
+            ]
+        );
+
+        // So are files for samples that were removed.
+        fs::write(dir.join("get_ok.golden"), br#"{"GetOk":[2,"A"]}"#).unwrap();
+        golden.write().unwrap();
+        assert!(!dir.join("get_ok.golden").exists());
+        fs::write(dir.join("get_ok.golden"), br#"{"GetOk":[2,"A"]}"#).unwrap();
+        assert_eq!(golden.check(), vec!["get_ok: golden file has no sample"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Wire format differs from golden files")]
+    fn panics_when_golden_files_missing() {
+        wire_format(Path::new("/nonexistent")).assert_unchanged();
+    }
+}
@@ -0,0 +1,500 @@
+//! A black-box test driver for [spawned](crate::actor::spawn()) actor systems, in the spirit of
+//! [Jepsen](https://jepsen.io/).
+//!
+//! A [`Harness`] runs a cluster of actors over UDP alongside a [`Nemesis`] that injects faults
+//! (network partitions, pauses, and crashes) on a schedule. Workload generators are simply
+//! additional actors (typically clients) that issue requests to the cluster. As messages flow, the
+//! harness records a history using the same `record_msg_in`/`record_msg_out` functions that an
+//! [`ActorModel`] uses, so the history can be validated by the built-in
+//! [consistency testers](crate::semantics::ConsistencyTester) after the run.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use stateright::actor::harness::{Fault, Harness, Nemesis};
+//! use stateright::actor::register::RegisterMsg;
+//! use stateright::actor::Id;
+//! use stateright::semantics::register::Register;
+//! use stateright::semantics::{ConsistencyTester, LinearizabilityTester};
+//! use std::net::{Ipv4Addr, SocketAddrV4};
+//! use std::time::Duration;
+//! # use stateright::actor::{Actor, Out};
+//! # struct Node;
+//! # impl Actor for Node {
+//! #     type Msg = RegisterMsg<u64, char, ()>;
+//! #     type State = ();
+//! #     type Timer = ();
+//! #     fn on_start(&self, _: Id, _: &mut Out<Self>) {}
+//! # }
+//! # let (server1, server2, client) = (Node, Node, Node);
+//!
+//! let id1 = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 3001));
+//! let id2 = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 3002));
+//! let id3 = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 3003));
+//! let history = Harness::new((), LinearizabilityTester::new(Register('?')))
+//!     .actor(id1, server1)
+//!     .actor(id2, server2)
+//!     .actor(id3, client)
+//!     .record_msg_in(RegisterMsg::record_returns)
+//!     .record_msg_out(RegisterMsg::record_invocations)
+//!     .nemesis(Nemesis::new()
+//!         .at(Duration::from_millis(100), Fault::Partition(vec![id1], vec![id2, id3]))
+//!         .at(Duration::from_millis(300), Fault::Heal))
+//!     .run(serde_json::to_vec, |bytes| serde_json::from_slice(bytes), Duration::from_secs(1))
+//!     .unwrap();
+//! assert!(history.is_consistent());
+//! ```
+//!
+//! [`ActorModel`]: crate::actor::ActorModel
+
+use crate::actor::spawn::{run_actor, AssertionPolicy, RuntimeHooks, RuntimeStatus};
+use crate::actor::{Actor, Envelope, Id, RuntimeMetrics};
+use crossbeam_utils::thread;
+use parking_lot::{Mutex, RwLock};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Debug;
+use std::time::{Duration, Instant};
+
+/// A fault that a [`Nemesis`] can inject into a running cluster.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Fault {
+    /// Drops messages flowing between the two groups of actors (in either direction). Messages
+    /// within a group continue to flow.
+    Partition(Vec<Id>, Vec<Id>),
+    /// Removes all partitions.
+    Heal,
+    /// Stops an actor from processing messages and timeouts until it is resumed. Messages sent to
+    /// the actor queue up in the meantime, similar to sending a process `SIGSTOP`.
+    Pause(Id),
+    /// Resumes a paused actor. Ignored unless the actor is paused.
+    Resume(Id),
+    /// Discards an actor's state and timers. Messages sent to the actor are lost until it
+    /// restarts.
+    Crash(Id),
+    /// Restarts a crashed actor via [`Actor::on_start`] (or [`Actor::on_recover`] if the actor
+    /// persists its state). Ignored unless the actor is crashed.
+    Restart(Id),
+}
+
+/// A schedule of [`Fault`]s to inject into a running cluster, indicated by offsets from the start
+/// of a [`Harness::run`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Nemesis {
+    schedule: Vec<(Duration, Fault)>,
+}
+
+impl Nemesis {
+    /// Constructs a [`Nemesis`] that does not inject any faults.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Schedules a [`Fault`] to be injected once the specified time has elapsed.
+    pub fn at(mut self, offset: Duration, fault: Fault) -> Self {
+        self.schedule.push((offset, fault));
+        self.schedule.sort_by_key(|(offset, _)| *offset);
+        self
+    }
+
+    /// Returns the scheduled faults in the order that they will be injected.
+    pub fn schedule(&self) -> &[(Duration, Fault)] {
+        &self.schedule
+    }
+}
+
+/// Runs a cluster of actors over UDP while injecting faults and recording a history. `H` indicates
+/// the type of history to record, such as a [`LinearizabilityTester`]. See the
+/// [module documentation](self) for an example.
+///
+/// [`LinearizabilityTester`]: crate::semantics::LinearizabilityTester
+pub struct Harness<A, C = (), H = ()>
+where
+    A: Actor,
+{
+    actors: Vec<(Id, A)>,
+    cfg: C,
+    init_history: H,
+    nemesis: Nemesis,
+    record_msg_in: fn(cfg: &C, history: &H, envelope: Envelope<&A::Msg>) -> Option<H>,
+    record_msg_out: fn(cfg: &C, history: &H, envelope: Envelope<&A::Msg>) -> Option<H>,
+}
+
+impl<A, C, H> Harness<A, C, H>
+where
+    A: Actor,
+{
+    /// Initializes a [`Harness`] with a specified configuration and history.
+    pub fn new(cfg: C, init_history: H) -> Self {
+        Harness {
+            actors: Vec::new(),
+            cfg,
+            init_history,
+            nemesis: Nemesis::new(),
+            record_msg_in: |_, _, _| None,
+            record_msg_out: |_, _, _| None,
+        }
+    }
+
+    /// Adds another [`Actor`] to the cluster. Workload generators (e.g. clients) are added the
+    /// same way.
+    pub fn actor(mut self, id: impl Into<Id>, actor: A) -> Self {
+        self.actors.push((id.into(), actor));
+        self
+    }
+
+    /// Adds multiple [`Actor`]s to the cluster.
+    pub fn actors(mut self, actors: impl IntoIterator<Item = (impl Into<Id>, A)>) -> Self {
+        for (id, actor) in actors {
+            self.actors.push((id.into(), actor));
+        }
+        self
+    }
+
+    /// Defines the faults to inject while the cluster runs.
+    pub fn nemesis(mut self, nemesis: Nemesis) -> Self {
+        self.nemesis = nemesis;
+        self
+    }
+
+    /// Defines whether/how an incoming message contributes to relevant history. Returning
+    /// `Some(new_history)` updates the relevant history, while `None` does not. Same as
+    /// [`ActorModel::record_msg_in`](crate::actor::ActorModel::record_msg_in).
+    pub fn record_msg_in(
+        mut self,
+        record_msg_in: fn(cfg: &C, history: &H, Envelope<&A::Msg>) -> Option<H>,
+    ) -> Self {
+        self.record_msg_in = record_msg_in;
+        self
+    }
+
+    /// Defines whether/how an outgoing message contributes to relevant history. Returning
+    /// `Some(new_history)` updates the relevant history, while `None` does not. Same as
+    /// [`ActorModel::record_msg_out`](crate::actor::ActorModel::record_msg_out).
+    pub fn record_msg_out(
+        mut self,
+        record_msg_out: fn(cfg: &C, history: &H, Envelope<&A::Msg>) -> Option<H>,
+    ) -> Self {
+        self.record_msg_out = record_msg_out;
+        self
+    }
+
+    /// Runs the cluster for the specified duration, injecting scheduled faults along the way.
+    /// Blocks the current thread. Returns the recorded history, or the panic payload of an actor
+    /// thread that panicked.
+    pub fn run<E: Debug>(
+        self,
+        serialize: fn(&A::Msg) -> Result<Vec<u8>, E>,
+        deserialize: fn(&[u8]) -> Result<A::Msg, E>,
+        duration: Duration,
+    ) -> Result<H, Box<dyn std::any::Any + Send + 'static>>
+    where
+        A: Send,
+        A::Msg: Debug,
+        A::State: Debug,
+        C: Sync,
+        H: Send,
+    {
+        let hooks = HarnessHooks {
+            cfg: self.cfg,
+            history: Mutex::new(self.init_history),
+            faults: RwLock::new(FaultState::default()),
+            record_msg_in: self.record_msg_in,
+            record_msg_out: self.record_msg_out,
+        };
+        let nemesis = self.nemesis;
+        let actors = self.actors;
+
+        let metrics = RuntimeMetrics::default();
+        thread::scope(|s| {
+            for (id, actor) in actors {
+                let hooks = &hooks;
+                let metrics = metrics.register(id);
+                s.spawn(move |_| {
+                    run_actor(
+                        id,
+                        actor,
+                        serialize,
+                        deserialize,
+                        hooks,
+                        &metrics,
+                        None,
+                        None,
+                        AssertionPolicy::default(),
+                    )
+                });
+            }
+
+            // The nemesis runs on the current thread.
+            let start = Instant::now();
+            for (offset, fault) in nemesis.schedule {
+                if let Some(delay) = (start + offset).checked_duration_since(Instant::now()) {
+                    std::thread::sleep(delay);
+                }
+                log::info!("Injecting fault. fault={:?}", fault);
+                hooks.faults.write().apply(fault);
+            }
+            if let Some(delay) = (start + duration).checked_duration_since(Instant::now()) {
+                std::thread::sleep(delay);
+            }
+            hooks.faults.write().is_stopped = true;
+        })?;
+
+        Ok(hooks.history.into_inner())
+    }
+}
+
+/// The faults in effect for a running cluster.
+#[derive(Debug, Default)]
+struct FaultState {
+    partitions: Vec<(BTreeSet<Id>, BTreeSet<Id>)>,
+    statuses: HashMap<Id, RuntimeStatus>,
+    is_stopped: bool,
+}
+
+impl FaultState {
+    fn apply(&mut self, fault: Fault) {
+        match fault {
+            Fault::Partition(group1, group2) => {
+                self.partitions
+                    .push((group1.into_iter().collect(), group2.into_iter().collect()));
+            }
+            Fault::Heal => {
+                self.partitions.clear();
+            }
+            Fault::Pause(id) => {
+                self.statuses.insert(id, RuntimeStatus::Paused);
+            }
+            Fault::Crash(id) => {
+                self.statuses.insert(id, RuntimeStatus::Crashed);
+            }
+            Fault::Resume(id) => {
+                if self.statuses.get(&id) == Some(&RuntimeStatus::Paused) {
+                    self.statuses.remove(&id);
+                } else {
+                    log::warn!("Ignoring resume of an actor that is not paused. id={}", id);
+                }
+            }
+            Fault::Restart(id) => {
+                if self.statuses.get(&id) == Some(&RuntimeStatus::Crashed) {
+                    self.statuses.remove(&id);
+                } else {
+                    log::warn!(
+                        "Ignoring restart of an actor that is not crashed. id={}",
+                        id
+                    );
+                }
+            }
+        }
+    }
+}
+
+struct HarnessHooks<C, H, Msg> {
+    cfg: C,
+    history: Mutex<H>,
+    faults: RwLock<FaultState>,
+    record_msg_in: fn(cfg: &C, history: &H, envelope: Envelope<&Msg>) -> Option<H>,
+    record_msg_out: fn(cfg: &C, history: &H, envelope: Envelope<&Msg>) -> Option<H>,
+}
+
+impl<C, H, Msg> RuntimeHooks<Msg> for HarnessHooks<C, H, Msg>
+where
+    C: Sync,
+    H: Send,
+{
+    fn poll_interval(&self) -> Option<Duration> {
+        Some(Duration::from_millis(10))
+    }
+
+    fn status(&self, id: Id) -> RuntimeStatus {
+        let faults = self.faults.read();
+        if faults.is_stopped {
+            return RuntimeStatus::Stopped;
+        }
+        faults
+            .statuses
+            .get(&id)
+            .copied()
+            .unwrap_or(RuntimeStatus::Running)
+    }
+
+    fn is_linked(&self, src: Id, dst: Id) -> bool {
+        !self.faults.read().partitions.iter().any(|(g1, g2)| {
+            (g1.contains(&src) && g2.contains(&dst)) || (g2.contains(&src) && g1.contains(&dst))
+        })
+    }
+
+    fn on_send(&self, envelope: Envelope<&Msg>) {
+        let mut history = self.history.lock();
+        if let Some(next) = (self.record_msg_out)(&self.cfg, &history, envelope) {
+            *history = next;
+        }
+    }
+
+    fn on_deliver(&self, envelope: Envelope<&Msg>) {
+        let mut history = self.history.lock();
+        if let Some(next) = (self.record_msg_in)(&self.cfg, &history, envelope) {
+            *history = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::actor::Out;
+    use std::borrow::Cow;
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    #[derive(Clone, Debug, Eq, Hash, PartialEq, serde::Serialize, serde::Deserialize)]
+    enum Msg {
+        Ping(u32),
+        Pong(u32),
+    }
+
+    /// Pings the peer periodically. Replies to each ping with a pong.
+    struct Pinger {
+        peer: Id,
+    }
+
+    #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+    struct Tick;
+
+    impl Actor for Pinger {
+        type Msg = Msg;
+        type State = u32;
+        type Timer = Tick;
+
+        fn on_start(&self, _id: Id, o: &mut Out<Self>) -> Self::State {
+            o.set_timer(Tick, Duration::from_millis(5)..Duration::from_millis(5));
+            0
+        }
+
+        fn on_msg(
+            &self,
+            _id: Id,
+            _state: &mut Cow<Self::State>,
+            src: Id,
+            msg: Self::Msg,
+            o: &mut Out<Self>,
+        ) {
+            if let Msg::Ping(n) = msg {
+                o.send(src, Msg::Pong(n));
+            }
+        }
+
+        fn on_timeout(
+            &self,
+            _id: Id,
+            state: &mut Cow<Self::State>,
+            _timer: &Self::Timer,
+            o: &mut Out<Self>,
+        ) {
+            o.send(self.peer, Msg::Ping(**state));
+            *state.to_mut() += 1;
+            o.set_timer(Tick, Duration::from_millis(5)..Duration::from_millis(5));
+        }
+    }
+
+    /// Counts pongs received.
+    type History = usize;
+
+    fn record_pongs(_: &(), history: &History, env: Envelope<&Msg>) -> Option<History> {
+        matches!(env.msg, Msg::Pong(_)).then_some(history + 1)
+    }
+
+    fn run_pingers(port: u16, nemesis: Nemesis) -> History {
+        let id1 = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port));
+        let id2 = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port + 1));
+        Harness::new((), 0)
+            .actor(id1, Pinger { peer: id2 })
+            .actor(id2, Pinger { peer: id1 })
+            .record_msg_in(record_pongs)
+            .nemesis(nemesis)
+            .run(
+                serde_json::to_vec,
+                |bytes| serde_json::from_slice(bytes),
+                Duration::from_millis(200),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn records_history() {
+        assert!(run_pingers(31_001, Nemesis::new()) > 0);
+    }
+
+    #[test]
+    fn partition_blocks_messages() {
+        let id1 = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 31_011));
+        let id2 = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 31_012));
+        let nemesis = Nemesis::new().at(Duration::ZERO, Fault::Partition(vec![id1], vec![id2]));
+        assert_eq!(run_pingers(31_011, nemesis), 0);
+    }
+
+    #[test]
+    fn applies_faults() {
+        let id1 = Id::from(1);
+        let id2 = Id::from(2);
+        let id3 = Id::from(3);
+        let mut faults = FaultState::default();
+
+        faults.apply(Fault::Partition(vec![id1], vec![id2, id3]));
+        faults.apply(Fault::Pause(id2));
+        faults.apply(Fault::Crash(id3));
+        assert_eq!(faults.statuses.get(&id2), Some(&RuntimeStatus::Paused));
+        assert_eq!(faults.statuses.get(&id3), Some(&RuntimeStatus::Crashed));
+        assert_eq!(faults.partitions.len(), 1);
+
+        faults.apply(Fault::Resume(id2));
+        faults.apply(Fault::Restart(id3));
+        faults.apply(Fault::Heal);
+        assert!(faults.statuses.is_empty());
+        assert!(faults.partitions.is_empty());
+    }
+
+    #[test]
+    fn resume_requires_paused_actor() {
+        let (id1, id2) = (Id::from(1), Id::from(2));
+        let mut faults = FaultState::default();
+        faults.apply(Fault::Crash(id1));
+        faults.apply(Fault::Resume(id1));
+        assert_eq!(faults.statuses.get(&id1), Some(&RuntimeStatus::Crashed));
+
+        faults.apply(Fault::Resume(id2));
+        assert_eq!(faults.statuses.get(&id2), None);
+
+        faults.apply(Fault::Pause(id2));
+        faults.apply(Fault::Resume(id2));
+        assert_eq!(faults.statuses.get(&id2), None);
+    }
+
+    #[test]
+    fn restart_requires_crashed_actor() {
+        let (id1, id2) = (Id::from(1), Id::from(2));
+        let mut faults = FaultState::default();
+        faults.apply(Fault::Pause(id1));
+        faults.apply(Fault::Restart(id1));
+        assert_eq!(faults.statuses.get(&id1), Some(&RuntimeStatus::Paused));
+
+        faults.apply(Fault::Restart(id2));
+        assert_eq!(faults.statuses.get(&id2), None);
+
+        faults.apply(Fault::Crash(id2));
+        faults.apply(Fault::Restart(id2));
+        assert_eq!(faults.statuses.get(&id2), None);
+    }
+
+    #[test]
+    fn nemesis_sorts_schedule() {
+        let nemesis = Nemesis::new()
+            .at(Duration::from_millis(2), Fault::Heal)
+            .at(Duration::from_millis(1), Fault::Pause(Id::from(0)));
+        assert_eq!(
+            nemesis.schedule(),
+            &[
+                (Duration::from_millis(1), Fault::Pause(Id::from(0))),
+                (Duration::from_millis(2), Fault::Heal),
+            ]
+        );
+    }
+}
@@ -0,0 +1,311 @@
+//! Private module for selective re-export.
+
+use crate::actor::Id;
+use parking_lot::RwLock;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::net::SocketAddrV4;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Counters maintained by the runtime for a single [spawned](crate::actor::spawn()) actor.
+#[derive(Debug, Default)]
+pub struct ActorMetrics {
+    messages_sent: AtomicU64,
+    messages_received: AtomicU64,
+    messages_dropped: [AtomicU64; DropReason::COUNT],
+    timeouts: AtomicU64,
+    restarts: AtomicU64,
+    state: RwLock<Option<String>>,
+    /// Whether [`Self::state`] is maintained. Formatting is skipped otherwise.
+    records_state: AtomicBool,
+    /// Whether [`Self::state`] reflects the actor's latest state.
+    is_state_current: AtomicBool,
+}
+
+/// Indicates why the runtime dropped a message rather than sending or delivering it.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum DropReason {
+    /// The message could not be serialized.
+    Serialize,
+    /// The message could not be deserialized.
+    Deserialize,
+    /// The socket rejected the message.
+    Send,
+    /// The message was sent across a network partition.
+    Partition,
+    /// The message exceeded the [rate limit](crate::actor::SpawnBuilder::rate_limit) for its
+    /// destination.
+    RateLimit,
+}
+
+impl DropReason {
+    const COUNT: usize = 5;
+    const ALL: [DropReason; DropReason::COUNT] = [
+        DropReason::Serialize,
+        DropReason::Deserialize,
+        DropReason::Send,
+        DropReason::Partition,
+        DropReason::RateLimit,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            DropReason::Serialize => "serialize",
+            DropReason::Deserialize => "deserialize",
+            DropReason::Send => "send",
+            DropReason::Partition => "partition",
+            DropReason::RateLimit => "rate_limit",
+        }
+    }
+}
+
+impl ActorMetrics {
+    /// The number of messages the actor has sent.
+    pub fn messages_sent(&self) -> u64 {
+        self.messages_sent.load(Ordering::Relaxed)
+    }
+
+    /// The number of messages delivered to the actor.
+    pub fn messages_received(&self) -> u64 {
+        self.messages_received.load(Ordering::Relaxed)
+    }
+
+    /// The number of messages to or from the actor that were dropped for a particular reason.
+    pub fn messages_dropped(&self, reason: DropReason) -> u64 {
+        self.messages_dropped[reason as usize].load(Ordering::Relaxed)
+    }
+
+    /// The number of timeouts the actor has handled.
+    pub fn timeouts(&self) -> u64 {
+        self.timeouts.load(Ordering::Relaxed)
+    }
+
+    /// The number of times the actor has restarted after crashing.
+    pub fn restarts(&self) -> u64 {
+        self.restarts.load(Ordering::Relaxed)
+    }
+
+    /// The actor's most recent state, formatted via [`Actor::format_state`] if available and
+    /// otherwise via [`Debug`](std::fmt::Debug). [`None`] if the actor has not started or has
+    /// crashed, or if states are not being recorded (see [`RuntimeMetrics::record_states`]).
+    /// Enables live inspection of a running cluster, for example via the
+    /// [Explorer](crate::checker::ExplorerBuilder::cluster).
+    ///
+    /// [`Actor::format_state`]: crate::actor::Actor::format_state
+    pub fn state(&self) -> Option<String> {
+        self.state.read().clone()
+    }
+
+    #[cfg_attr(not(feature = "spawn"), allow(dead_code))]
+    pub(crate) fn on_send(&self) {
+        self.messages_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg_attr(not(feature = "spawn"), allow(dead_code))]
+    pub(crate) fn on_receive(&self) {
+        self.messages_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg_attr(not(feature = "spawn"), allow(dead_code))]
+    pub(crate) fn on_drop(&self, reason: DropReason) {
+        self.messages_dropped[reason as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg_attr(not(feature = "spawn"), allow(dead_code))]
+    pub(crate) fn on_timeout(&self) {
+        self.timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg_attr(not(feature = "spawn"), allow(dead_code))]
+    pub(crate) fn on_restart(&self) {
+        self.restarts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Indicates that the actor's state changed, without paying to format it.
+    #[cfg_attr(not(feature = "spawn"), allow(dead_code))]
+    pub(crate) fn on_state_change(&self) {
+        self.is_state_current.store(false, Ordering::Relaxed);
+    }
+
+    /// Indicates that the actor crashed and therefore has no state.
+    #[cfg_attr(not(feature = "spawn"), allow(dead_code))]
+    pub(crate) fn on_crash(&self) {
+        *self.state.write() = None;
+        self.is_state_current.store(true, Ordering::Relaxed);
+    }
+
+    /// Formats the actor's state if states are being recorded and it changed since it was last
+    /// formatted.
+    #[cfg_attr(not(feature = "spawn"), allow(dead_code))]
+    pub(crate) fn refresh_state(&self, format: impl FnOnce() -> String) {
+        if self.records_state.load(Ordering::Relaxed)
+            && !self.is_state_current.swap(true, Ordering::Relaxed)
+        {
+            *self.state.write() = Some(format());
+        }
+    }
+}
+
+/// Metrics for a set of [spawned](crate::actor::spawn()) actors. Obtain an instance via
+/// [`SpawnBuilder::metrics`](crate::actor::SpawnBuilder::metrics).
+#[derive(Debug, Default)]
+pub struct RuntimeMetrics {
+    actors: RwLock<BTreeMap<Id, Arc<ActorMetrics>>>,
+    records_states: AtomicBool,
+}
+
+impl RuntimeMetrics {
+    /// Returns the metrics for a particular actor, if it has been spawned.
+    pub fn actor(&self, id: Id) -> Option<Arc<ActorMetrics>> {
+        self.actors.read().get(&id).cloned()
+    }
+
+    /// Returns the metrics for every spawned actor.
+    pub fn actors(&self) -> Vec<(Id, Arc<ActorMetrics>)> {
+        self.actors
+            .read()
+            .iter()
+            .map(|(id, metrics)| (*id, Arc::clone(metrics)))
+            .collect()
+    }
+
+    /// Starts maintaining [`ActorMetrics::state`] for every actor. States are not formatted by
+    /// default, as doing so after every transition is costly. Called by
+    /// [`ExplorerBuilder::cluster`](crate::checker::ExplorerBuilder::cluster). An actor's state
+    /// becomes available once the actor next wakes up.
+    pub fn record_states(&self) {
+        let actors = self.actors.read();
+        self.records_states.store(true, Ordering::Relaxed);
+        for metrics in actors.values() {
+            metrics.records_state.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Registers an actor, returning the counters that its runtime should update.
+    #[cfg_attr(not(feature = "spawn"), allow(dead_code))]
+    pub(crate) fn register(&self, id: Id) -> Arc<ActorMetrics> {
+        let metrics = Arc::clone(self.actors.write().entry(id).or_default());
+        if self.records_states.load(Ordering::Relaxed) {
+            metrics.records_state.store(true, Ordering::Relaxed);
+        }
+        metrics
+    }
+
+    /// Renders the metrics in the [Prometheus text exposition
+    /// format](https://prometheus.io/docs/instrumenting/exposition_formats/).
+    pub fn to_prometheus(&self) -> String {
+        let actors = self.actors();
+        let mut out = String::new();
+        let mut counter = |name: &str, help: &str, value: &dyn Fn(&ActorMetrics) -> u64| {
+            writeln!(out, "# HELP stateright_{} {}", name, help).unwrap();
+            writeln!(out, "# TYPE stateright_{} counter", name).unwrap();
+            for (id, metrics) in &actors {
+                writeln!(
+                    out,
+                    "stateright_{}{{actor=\"{}\"}} {}",
+                    name,
+                    SocketAddrV4::from(*id),
+                    value(metrics)
+                )
+                .unwrap();
+            }
+        };
+        counter(
+            "messages_sent_total",
+            "Messages sent by an actor.",
+            &ActorMetrics::messages_sent,
+        );
+        counter(
+            "messages_received_total",
+            "Messages delivered to an actor.",
+            &ActorMetrics::messages_received,
+        );
+        counter(
+            "timeouts_total",
+            "Timeouts handled by an actor.",
+            &ActorMetrics::timeouts,
+        );
+        counter(
+            "restarts_total",
+            "Restarts of an actor after crashing.",
+            &ActorMetrics::restarts,
+        );
+
+        // Dropped messages are further labeled by reason.
+        writeln!(
+            out,
+            "# HELP stateright_messages_dropped_total Messages to or from an actor that were dropped."
+        )
+        .unwrap();
+        writeln!(out, "# TYPE stateright_messages_dropped_total counter").unwrap();
+        for (id, metrics) in &actors {
+            for reason in DropReason::ALL {
+                writeln!(
+                    out,
+                    "stateright_messages_dropped_total{{actor=\"{}\",reason=\"{}\"}} {}",
+                    SocketAddrV4::from(*id),
+                    reason.label(),
+                    metrics.messages_dropped(reason)
+                )
+                .unwrap();
+            }
+        }
+        out
+    }
+}
+
+/// Serves `GET /metrics` on a background thread.
+#[cfg(feature = "spawn")]
+pub(crate) fn serve_metrics(metrics: Arc<RuntimeMetrics>, addresses: Vec<std::net::SocketAddr>) {
+    use tiny_http::{Header, Method, Response, StatusCode};
+
+    let server = tiny_http::Server::http(&addresses[..]).unwrap();
+    std::thread::spawn(move || loop {
+        let rq = match server.recv() {
+            Ok(rq) => rq,
+            Err(e) => {
+                log::warn!("Unable to receive metrics request. err={:?}", e);
+                continue;
+            }
+        };
+        let response = match (rq.method(), rq.url()) {
+            (Method::Get, "/metrics") => Response::from_string(metrics.to_prometheus())
+                .with_header(
+                    Header::from_bytes("Content-Type", "text/plain; version=0.0.4").unwrap(),
+                )
+                .boxed(),
+            _ => Response::empty(StatusCode(404)).boxed(),
+        };
+        let _ = rq.respond(response);
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn renders_prometheus_text_format() {
+        let metrics = RuntimeMetrics::default();
+        let id = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 3000));
+        let actor = metrics.register(id);
+        actor.on_send();
+        actor.on_send();
+        actor.on_receive();
+        actor.on_drop(DropReason::Partition);
+
+        let text = metrics.to_prometheus();
+        assert!(text.contains("# TYPE stateright_messages_sent_total counter\n"));
+        assert!(text.contains("stateright_messages_sent_total{actor=\"127.0.0.1:3000\"} 2\n"));
+        assert!(text.contains("stateright_messages_received_total{actor=\"127.0.0.1:3000\"} 1\n"));
+        assert!(text.contains("stateright_timeouts_total{actor=\"127.0.0.1:3000\"} 0\n"));
+        assert!(text.contains(
+            "stateright_messages_dropped_total{actor=\"127.0.0.1:3000\",reason=\"partition\"} 1\n"
+        ));
+        assert!(text.contains(
+            "stateright_messages_dropped_total{actor=\"127.0.0.1:3000\",reason=\"send\"} 0\n"
+        ));
+    }
+}
@@ -5,13 +5,14 @@ use crate::actor::{
 };
 use crate::{Expectation, Model, Path, Property};
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hash;
 use std::ops::Range;
 use std::sync::Arc;
 use std::time::Duration;
 
+use super::buffer_pool::BufferPool;
 use super::timers::Timers;
 
 /// Represents a system of [`Actor`]s that communicate over a network. `H` indicates the type of
@@ -32,10 +33,21 @@ where
     pub lossy_network: LossyNetwork,
     /// Maximum number of actors that can be contemporarily crashed
     pub max_crashes: usize,
+    /// Maximum number of times that crashed actors can recover during a behavior
+    pub max_recoveries: usize,
     pub properties: Vec<Property<ActorModel<A, C, H>>>,
     pub record_msg_in: fn(cfg: &C, history: &H, envelope: Envelope<&A::Msg>) -> Option<H>,
     pub record_msg_out: fn(cfg: &C, history: &H, envelope: Envelope<&A::Msg>) -> Option<H>,
     pub within_boundary: fn(cfg: &C, state: &ActorModelState<A, H>) -> bool,
+    /// Reused across steps that output more commands than [`Out`] holds inline.
+    command_buffers: BufferPool<Command<A::Msg, A::Timer>>,
+    /// Whether failed assertions are recorded in the state. See
+    /// [`ActorModel::check_assertions`].
+    records_assertions: bool,
+    /// Patterns of messages that must not be sent, by property name. See
+    /// [`ActorModel::forbid_send`].
+    #[allow(clippy::type_complexity)]
+    forbidden_sends: Vec<(&'static str, fn(&C, &H, Envelope<&A::Msg>) -> bool)>,
 }
 
 /// Indicates possible steps that an actor system can take as it evolves.
@@ -52,12 +64,15 @@ pub enum ActorModelAction<Msg, Timer> {
     /// An actor can by notified after a timeout.
     Timeout(Id, Timer),
     Crash(Id),
+    /// A crashed actor can recover its state via [`Actor::on_recover`]. See
+    /// [`ActorModel::max_recoveries`].
+    Recover(Id),
 }
 
 /// Indicates whether the network loses messages. Note that as long as invariants do not check
 /// the network state, losing a message is indistinguishable from an unlimited delay, so in
 /// many cases you can improve model checking performance by not modeling message loss.
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum LossyNetwork {
     Yes,
     No,
@@ -93,10 +108,14 @@ where
             init_network: Network::new_unordered_duplicating([]),
             lossy_network: LossyNetwork::No,
             max_crashes: 0,
+            max_recoveries: 0,
             properties: Default::default(),
             record_msg_in: |_, _, _| None,
             record_msg_out: |_, _, _| None,
             within_boundary: |_, _| true,
+            command_buffers: BufferPool::new(),
+            records_assertions: false,
+            forbidden_sends: Vec::new(),
         }
     }
 
@@ -132,22 +151,163 @@ where
         self
     }
 
+    /// Specifies the maximum number of times that crashed actors can recover during a behavior.
+    /// A recovering actor keeps the state it had when it crashed and runs
+    /// [`Actor::on_recover`], mirroring an actor [spawned](crate::actor::spawn()) with
+    /// [`SpawnBuilder::persist_states`](crate::actor::SpawnBuilder::persist_states). Crashed
+    /// actors never recover by default.
+    pub fn max_recoveries(mut self, max_recoveries: usize) -> Self {
+        self.max_recoveries = max_recoveries;
+        self
+    }
+
     /// Adds a [`Property`] to this model.
     #[allow(clippy::type_complexity)]
     pub fn property(
         mut self,
         expectation: Expectation,
-        name: &'static str,
+        name: impl Into<Cow<'static, str>>,
         condition: fn(&ActorModel<A, C, H>, &ActorModelState<A, H>) -> bool,
-    ) -> Self {
+    ) -> Self
+    where
+        A: 'static,
+        C: 'static,
+        H: 'static,
+    {
         self.properties.push(Property {
             expectation,
-            name,
-            condition,
+            name: name.into(),
+            condition: Arc::new(condition),
+            trigger: None,
+            assumption: None,
+            conjuncts: Vec::new(),
+            transition: None,
         });
         self
     }
 
+    /// Adds an `always` property named `"assertions"` that is violated once any actor fails an
+    /// assertion (see [`Out::assert`]). The discovery is the trace leading to the failure, and
+    /// the failed assertions are listed in [`ActorModelState::failed_assertions`]. Failed
+    /// assertions are otherwise ignored, so that they do not distinguish states.
+    pub fn check_assertions(mut self) -> Self
+    where
+        A: 'static,
+        C: 'static,
+        H: 'static,
+    {
+        self.records_assertions = true;
+        self.property(Expectation::Always, "assertions", |_, state| {
+            state.failed_assertions.is_empty()
+        })
+    }
+
+    /// Adds an `always` property that is violated once any actor sends a message matching a
+    /// forbidden pattern, such as a second `PutOk` for the same request ID. The pattern is
+    /// evaluated against each outgoing envelope as it is sent, along with the history as recorded
+    /// up to that point (including by [`ActorModel::record_msg_in`] for a delivery and by
+    /// [`ActorModel::record_msg_out`] for the messages sent before it), so the counterexample ends
+    /// with the action whose handler performed the offending send. Offending sends are listed in
+    /// [`ActorModelState::forbidden_sends`].
+    #[allow(clippy::type_complexity)]
+    pub fn forbid_send(
+        mut self,
+        name: &'static str,
+        pattern: fn(cfg: &C, history: &H, Envelope<&A::Msg>) -> bool,
+    ) -> Self
+    where
+        A: 'static,
+        C: 'static,
+        H: 'static,
+    {
+        self.forbidden_sends.push((name, pattern));
+        self.properties.push(Property::always(
+            name,
+            move |_: &ActorModel<A, C, H>, state: &ActorModelState<A, H>| {
+                !state.forbidden_sends.iter().any(|(_, n)| *n == name)
+            },
+        ));
+        self
+    }
+
+    /// Adds a [`Property`] for each actor registered so far, named after the actor's index (for
+    /// example `"bounded[2]"`), so that a discovery indicates which actor it concerns. Each
+    /// instance evaluates the condition against the corresponding actor's state.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use stateright::*;
+    /// # use stateright::actor::*;
+    /// let model = ActorModel::new((), ())
+    ///     .actors(vec![(), ()])
+    ///     .property_for_each_actor(Expectation::Always, "alive", |_, _id, _state| true);
+    /// let names: Vec<_> = model.properties().into_iter().map(|p| p.name).collect();
+    /// assert_eq!(names, vec!["alive[0]", "alive[1]"]);
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn property_for_each_actor(
+        mut self,
+        expectation: Expectation,
+        name: &'static str,
+        condition: fn(&ActorModel<A, C, H>, Id, &A::State) -> bool,
+    ) -> Self
+    where
+        A: 'static,
+        C: 'static,
+        H: 'static,
+    {
+        for index in 0..self.actors.len() {
+            let id = Id::from(index);
+            self.properties.push(Property {
+                expectation: expectation.clone(),
+                name: format!("{}[{}]", name, index).into(),
+                condition: Arc::new(move |model, state| {
+                    condition(model, id, &state.actor_states[index])
+                }),
+                trigger: None,
+                assumption: None,
+                conjuncts: Vec::new(),
+                transition: None,
+            });
+        }
+        self
+    }
+
+    /// Adds a [`Property`] for each pair of distinct actors registered so far, named after the
+    /// actors' indices (for example `"agreement[2,4]"`), so that a discovery indicates which
+    /// actors it concerns. Each unordered pair is instantiated once, with the lower index first.
+    #[allow(clippy::type_complexity)]
+    pub fn property_for_each_actor_pair(
+        mut self,
+        expectation: Expectation,
+        name: &'static str,
+        condition: fn(&ActorModel<A, C, H>, (Id, &A::State), (Id, &A::State)) -> bool,
+    ) -> Self
+    where
+        A: 'static,
+        C: 'static,
+        H: 'static,
+    {
+        for i in 0..self.actors.len() {
+            for j in (i + 1)..self.actors.len() {
+                self.properties.push(Property {
+                    expectation: expectation.clone(),
+                    name: format!("{}[{},{}]", name, i, j).into(),
+                    condition: Arc::new(move |model, state| {
+                        let (si, sj) = (&state.actor_states[i], &state.actor_states[j]);
+                        condition(model, (Id::from(i), si), (Id::from(j), sj))
+                    }),
+                    trigger: None,
+                    assumption: None,
+                    conjuncts: Vec::new(),
+                    transition: None,
+                });
+            }
+        }
+        self
+    }
+
     /// Defines whether/how an incoming message contributes to relevant history. Returning
     /// `Some(new_history)` updates the relevant history, while `None` does not.
     pub fn record_msg_in(
@@ -177,12 +337,41 @@ where
         self
     }
 
+    /// Indicates whether the actor system is quiescent, meaning that the only enabled actions are
+    /// environment faults (dropped messages, crashes, and recoveries). Deliveries and timeouts that would be
+    /// ignored, such as redeliveries of a duplicated message that do not change the recipient's
+    /// state, do not count as enabled. See [`Property::eventually_quiescent`].
+    pub fn is_quiescent(&self, state: &ActorModelState<A, H>) -> bool {
+        let mut actions = Vec::new();
+        self.actions(state, &mut actions);
+        actions.into_iter().all(|action| match action {
+            ActorModelAction::Drop(_)
+            | ActorModelAction::Crash(_)
+            | ActorModelAction::Recover(_) => true,
+            action @ (ActorModelAction::Deliver { .. } | ActorModelAction::Timeout(..)) => {
+                self.next_state(state, action).is_none()
+            }
+        })
+    }
+
     /// Updates the actor state, sends messages, and configures the timers.
-    fn process_commands(&self, id: Id, commands: Out<A>, state: &mut ActorModelState<A, H>) {
+    fn process_commands(&self, id: Id, mut commands: Out<A>, state: &mut ActorModelState<A, H>) {
         let index = usize::from(id);
-        for c in commands {
+        for c in commands.drain() {
             match c {
                 Command::Send(dst, msg) => {
+                    for (name, pattern) in &self.forbidden_sends {
+                        let env = Envelope {
+                            src: id,
+                            dst,
+                            msg: &msg,
+                        };
+                        if pattern(&self.cfg, &state.history, env)
+                            && !state.forbidden_sends.contains(&(id, name))
+                        {
+                            state.forbidden_sends.push((id, name));
+                        }
+                    }
                     if let Some(history) = (self.record_msg_out)(
                         &self.cfg,
                         &state.history,
@@ -208,6 +397,110 @@ where
                 }
             }
         }
+        if self.records_assertions {
+            let failed_assertions = commands.drain_failed_assertions();
+            state
+                .failed_assertions
+                .extend(failed_assertions.map(|label| (id, label)));
+        }
+        commands.release(&self.command_buffers);
+    }
+
+    /// Formats an actor state via [`Actor::format_state`], falling back to [`Debug`].
+    fn format_actor_state(&self, id: Id, actor_state: &A::State) -> String {
+        self.actors
+            .get(usize::from(id))
+            .and_then(|actor| actor.format_state(actor_state))
+            .unwrap_or_else(|| format!("{:#?}", actor_state))
+    }
+
+    /// Formats a message via the recipient's [`Actor::format_msg`], falling back to [`Debug`].
+    fn format_msg(&self, dst: Id, msg: &A::Msg) -> String {
+        self.actors
+            .get(usize::from(dst))
+            .and_then(|actor| actor.format_msg(msg))
+            .unwrap_or_else(|| format!("{:?}", msg))
+    }
+
+    /// Formats the network grouped by `(src → dst)` channel. Ordered channels list messages in
+    /// delivery order, while unordered channels list messages sorted by their representation
+    /// along with the number of copies in flight.
+    fn format_network(&self, network: &Network<A::Msg>) -> String {
+        use std::fmt::Write;
+
+        let (kind, mut channels) = match network {
+            Network::Ordered(map) => {
+                let channels: BTreeMap<_, _> = map
+                    .iter()
+                    .filter(|(_, msgs)| !msgs.is_empty())
+                    .map(|(&(src, dst), msgs)| {
+                        let msgs = msgs
+                            .iter()
+                            .enumerate()
+                            .map(|(i, msg)| format!("{}. {}", i + 1, self.format_msg(dst, msg)))
+                            .collect::<Vec<_>>();
+                        ((src, dst), msgs)
+                    })
+                    .collect();
+                ("ordered", channels)
+            }
+            Network::UnorderedDuplicating(set) => {
+                let mut channels = BTreeMap::<_, Vec<_>>::new();
+                for env in set.iter() {
+                    channels
+                        .entry((env.src, env.dst))
+                        .or_default()
+                        .push(self.format_msg(env.dst, &env.msg));
+                }
+                ("unordered_duplicating", channels)
+            }
+            Network::UnorderedNonDuplicating(multiset) => {
+                let mut channels = BTreeMap::<_, Vec<_>>::new();
+                for (env, &count) in multiset.iter() {
+                    let msg = self.format_msg(env.dst, &env.msg);
+                    channels
+                        .entry((env.src, env.dst))
+                        .or_default()
+                        .push(if count > 1 {
+                            format!("{} ×{}", msg, count)
+                        } else {
+                            msg
+                        });
+                }
+                ("unordered_nonduplicating", channels)
+            }
+        };
+        if !matches!(network, Network::Ordered(_)) {
+            // Unordered networks have no inherent order, so sort for a stable rendering.
+            channels.values_mut().for_each(|msgs| msgs.sort());
+        }
+
+        let mut out = String::new();
+        writeln!(out, "NETWORK ({}, {} in flight):", kind, network.len()).unwrap();
+        for ((src, dst), msgs) in channels {
+            writeln!(out, "  {:?} → {:?}:", src, dst).unwrap();
+            for msg in msgs {
+                writeln!(out, "    {}", msg.replace('\n', "\n    ")).unwrap();
+            }
+        }
+        out
+    }
+}
+
+impl<A, C, H> Property<ActorModel<A, C, H>>
+where
+    A: Actor,
+    H: Clone + Debug + Hash,
+{
+    /// An `eventually` property named `"quiescent"` that holds once no actions are enabled other
+    /// than environment faults, which is useful for checking protocols that should terminate.
+    /// Messages and timers may remain, provided delivering them and firing them would change
+    /// nothing, as for a duplicated message that the recipient ignores. Add it to
+    /// [`ActorModel::properties`] to check it. See [`ActorModel::is_quiescent`].
+    pub fn eventually_quiescent() -> Self {
+        Property::eventually("quiescent", |model: &ActorModel<A, C, H>, state| {
+            model.is_quiescent(state)
+        })
     }
 }
 
@@ -226,6 +519,9 @@ where
             timers_set: vec![Timers::new(); self.actors.len()],
             network: self.init_network.clone(),
             crashed: vec![false; self.actors.len()],
+            recoveries: 0,
+            failed_assertions: Vec::new(),
+            forbidden_sends: Vec::new(),
         };
 
         // init each actor
@@ -283,6 +579,16 @@ where
                 .filter_map(|(index, &crashed)| if !crashed { Some(index) } else { None })
                 .for_each(|index| actions.push(ActorModelAction::Crash(Id::from(index))));
         }
+
+        // option 5: actor recovery
+        if state.recoveries < self.max_recoveries {
+            state
+                .crashed
+                .iter()
+                .enumerate()
+                .filter_map(|(index, &crashed)| if crashed { Some(index) } else { None })
+                .for_each(|index| actions.push(ActorModelAction::Recover(Id::from(index))));
+        }
     }
 
     fn next_state(
@@ -312,9 +618,10 @@ where
                 let mut state = Cow::Borrowed(last_actor_state);
 
                 // Some operations are no-ops, so ignore those as well.
-                let mut out = Out::new();
+                let mut out = Out::with_buffer(self.command_buffers.take());
                 self.actors[index].on_msg(id, &mut state, src, msg.clone(), &mut out);
                 if is_no_op(&state, &out) && !matches!(self.init_network, Network::Ordered(_)) {
+                    out.release(&self.command_buffers);
                     return None;
                 }
                 let history = (self.record_msg_in)(
@@ -353,9 +660,10 @@ where
                 // Clone new state if necessary (otherwise early exit).
                 let index = usize::from(id);
                 let mut state = Cow::Borrowed(&*last_sys_state.actor_states[index]);
-                let mut out = Out::new();
+                let mut out = Out::with_buffer(self.command_buffers.take());
                 self.actors[index].on_timeout(id, &mut state, &timer, &mut out);
                 if is_no_op_with_timer(&state, &out, &timer) {
+                    out.release(&self.command_buffers);
                     return None;
                 }
                 let mut next_sys_state = last_sys_state.clone();
@@ -378,36 +686,87 @@ where
 
                 Some(next_sys_state)
             }
+            ActorModelAction::Recover(id) => {
+                let index = usize::from(id);
+                let mut out = Out::with_buffer(self.command_buffers.take());
+                self.actors[index].on_recover(id, &last_sys_state.actor_states[index], &mut out);
+
+                let mut next_sys_state = last_sys_state.clone();
+                next_sys_state.crashed[index] = false;
+                next_sys_state.recoveries += 1;
+                self.process_commands(id, out, &mut next_sys_state);
+                Some(next_sys_state)
+            }
         }
     }
 
     fn format_action(&self, action: &Self::Action) -> String {
         if let ActorModelAction::Deliver { src, dst, msg } = action {
-            format!("{:?} → {:?} → {:?}", src, msg, dst)
+            format!("{:?} → {} → {:?}", src, self.format_msg(*dst, msg), dst)
         } else {
             format!("{:?}", action)
         }
     }
 
+    /// Lists actor states using [`Actor::format_state`] and in-flight messages grouped by channel
+    /// using [`Actor::format_msg`], falling back to [`Debug`] for actors without those hooks.
+    fn format_state(&self, state: &Self::State) -> Option<String> {
+        use std::fmt::Write;
+
+        let indent = |s: String| s.replace('\n', "\n    ");
+        let mut out = String::new();
+        writeln!(out, "ACTOR STATES:").unwrap();
+        for (index, actor_state) in state.actor_states.iter().enumerate() {
+            let id = Id::from(index);
+            let crashed = if state.crashed.get(index) == Some(&true) {
+                " (crashed)"
+            } else {
+                ""
+            };
+            writeln!(
+                out,
+                "  {:?}{}: {}",
+                id,
+                crashed,
+                indent(self.format_actor_state(id, actor_state))
+            )
+            .unwrap();
+        }
+        writeln!(out).unwrap();
+        out.push_str(&self.format_network(&state.network));
+        writeln!(out).unwrap();
+        writeln!(out, "TIMERS: {:?}", state.timers_set).unwrap();
+        writeln!(out, "HISTORY: {:#?}", state.history).unwrap();
+        Some(out)
+    }
+
     fn format_step(&self, last_state: &Self::State, action: Self::Action) -> Option<String>
     where
         Self::State: Debug,
     {
         struct ActorStep<'a, A: Actor> {
+            actor: &'a A,
             last_state: &'a A::State,
             next_state: Option<A::State>,
             out: Out<A>,
         }
+        impl<'a, A: Actor> ActorStep<'a, A> {
+            fn format_state(&self, state: &A::State) -> String {
+                self.actor
+                    .format_state(state)
+                    .unwrap_or_else(|| format!("{:#?}", state))
+            }
+        }
         impl<'a, A: Actor> Display for ActorStep<'a, A> {
             fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
                 writeln!(f, "OUT: {:?}", self.out)?;
                 writeln!(f)?;
                 if let Some(next_state) = &self.next_state {
-                    writeln!(f, "NEXT_STATE: {:#?}", next_state)?;
+                    writeln!(f, "NEXT_STATE: {}", self.format_state(next_state))?;
                     writeln!(f)?;
-                    writeln!(f, "PREV_STATE: {:#?}", self.last_state)
+                    writeln!(f, "PREV_STATE: {}", self.format_state(self.last_state))
                 } else {
-                    writeln!(f, "UNCHANGED: {:#?}", self.last_state)
+                    writeln!(f, "UNCHANGED: {}", self.format_state(self.last_state))
                 }
             }
         }
@@ -426,6 +785,7 @@ where
                 Some(format!(
                     "{}",
                     ActorStep {
+                        actor: &self.actors[index],
                         last_state: last_actor_state,
                         next_state: match actor_state {
                             Cow::Borrowed(_) => None,
@@ -447,6 +807,7 @@ where
                 Some(format!(
                     "{}",
                     ActorStep {
+                        actor: &self.actors[index],
                         last_state: last_actor_state,
                         next_state: match actor_state {
                             Cow::Borrowed(_) => None,
@@ -462,6 +823,7 @@ where
                     format!(
                         "{}",
                         ActorStep {
+                            actor: &self.actors[index],
                             last_state: &**Cow::Borrowed(last_actor_state),
                             next_state: None,
                             out: Out::new() as Out<A>,
@@ -469,6 +831,22 @@ where
                     )
                 })
             }
+            ActorModelAction::Recover(id) => {
+                let index = usize::from(id);
+                last_state.actor_states.get(index).map(|last_actor_state| {
+                    let mut out = Out::new();
+                    self.actors[index].on_recover(id, last_actor_state, &mut out);
+                    format!(
+                        "{}",
+                        ActorStep {
+                            actor: &self.actors[index],
+                            last_state: &**last_actor_state,
+                            next_state: None,
+                            out,
+                        }
+                    )
+                })
+            }
         }
     }
 
@@ -540,7 +918,7 @@ where
             .unwrap();
         }
 
-        // Arrow for each delivery. Circle for other events.
+        // Arrow for each delivery. Self-arrow for each timeout. Circle for other events.
         let mut send_time = HashMap::new();
         for (time, (state, action)) in path.clone().into_iter().enumerate() {
             let time = time + 1; // action is for the next step
@@ -567,10 +945,12 @@ where
                 }
                 Some(ActorModelAction::Timeout(actor_id, timer)) => {
                     let (x, y) = plot(actor_id.into(), time);
+                    let (_, prev_y) = plot(actor_id.into(), time - 1);
+                    let y0 = (prev_y + y) / 2;
                     writeln!(
                         &mut svg,
-                        "<circle cx='{}' cy='{}' r='10' class='svg-event-shape' />",
-                        x, y
+                        "<path d='M {} {} C {} {}, {} {}, {} {}' marker-end='url(#arrow)' class='svg-event-loop' />",
+                        x, y0, x + 40, y0, x + 40, y, x, y
                     )
                     .unwrap();
 
@@ -596,6 +976,27 @@ where
                     )
                     .unwrap();
                 }
+                Some(ActorModelAction::Recover(actor_id)) => {
+                    let (x, y) = plot(actor_id.into(), time);
+                    writeln!(
+                        &mut svg,
+                        "<circle cx='{}' cy='{}' r='10' class='svg-event-shape' />",
+                        x, y
+                    )
+                    .unwrap();
+
+                    // Track sends to facilitate building arrows.
+                    let index = usize::from(actor_id);
+                    if let Some(actor_state) = state.actor_states.get(index) {
+                        let mut out = Out::new();
+                        self.actors[index].on_recover(actor_id, actor_state, &mut out);
+                        for command in out {
+                            if let Command::Send(dst, msg) = command {
+                                send_time.insert((actor_id, dst, msg), time);
+                            }
+                        }
+                    }
+                }
                 _ => {}
             }
         }
@@ -618,7 +1019,9 @@ where
                     writeln!(
                         &mut svg,
                         "<text x='{}' y='{}' class='svg-event-label'>Timeout({:?})</text>",
-                        x, y, timer
+                        x + 45, // clear of the self-arrow
+                        y,
+                        timer
                     )
                     .unwrap();
                 }
@@ -631,6 +1034,15 @@ where
                     )
                     .unwrap();
                 }
+                Some(ActorModelAction::Recover(id)) => {
+                    let (x, y) = plot(id.into(), time);
+                    writeln!(
+                        &mut svg,
+                        "<text x='{}' y='{}' class='svg-event-label'>Recover</text>",
+                        x, y
+                    )
+                    .unwrap();
+                }
                 _ => {}
             }
         }
@@ -670,6 +1082,9 @@ mod test {
                 network: Network::new_unordered_duplicating(envelopes),
                 timers_set,
                 crashed,
+                recoveries: 0,
+                failed_assertions: vec![],
+                forbidden_sends: vec![],
                 history: (0_u32, 0_u32), // constant as `maintains_history: false`
             }
         };
@@ -1163,6 +1578,449 @@ mod test {
             2
         );
     }
+
+    #[test]
+    fn recovers_crashed_actors_via_on_recover() {
+        struct TestActor;
+        impl Actor for TestActor {
+            type State = u8;
+            type Msg = ();
+            type Timer = ();
+            fn on_start(&self, _: Id, _: &mut Out<Self>) -> Self::State {
+                0
+            }
+            fn on_msg(
+                &self,
+                _: Id,
+                state: &mut Cow<Self::State>,
+                _: Id,
+                _: Self::Msg,
+                _: &mut Out<Self>,
+            ) {
+                *state.to_mut() = 1;
+            }
+            fn on_recover(&self, id: Id, _: &Self::State, o: &mut Out<Self>) {
+                o.send(id, ());
+            }
+        }
+
+        let model = || {
+            ActorModel::new((), ())
+                .actor(TestActor)
+                .max_crashes(1)
+                .property(Expectation::Sometimes, "recovered", |_, state| {
+                    *state.actor_states[0] == 1
+                })
+        };
+
+        // Crashed actors never recover by default.
+        let checker = model().checker().spawn_bfs().join();
+        assert_eq!(checker.unique_state_count(), 2);
+        checker.assert_no_discovery("recovered");
+
+        let checker = model().max_recoveries(1).checker().spawn_bfs().join();
+        assert_eq!(
+            checker.discovery("recovered").unwrap().into_actions(),
+            vec![
+                Crash(Id::from(0)),
+                Recover(Id::from(0)),
+                Deliver {
+                    src: Id::from(0),
+                    dst: Id::from(0),
+                    msg: ()
+                },
+            ]
+        );
+        let recovered = checker.discovery("recovered").unwrap().last_state().clone();
+        assert_eq!(recovered.recoveries, 1);
+        assert_eq!(recovered.crashed, vec![false]);
+    }
+
+    #[test]
+    fn renders_timeouts_as_self_arrows() {
+        struct TestActor;
+        impl Actor for TestActor {
+            type State = ();
+            type Msg = ();
+            type Timer = ();
+            fn on_start(&self, _: Id, o: &mut Out<Self>) {
+                o.set_timer((), model_timeout());
+            }
+        }
+
+        let model = ActorModel::new((), ()).actor(TestActor);
+        let init_state = model.init_states().remove(0);
+        let path = Path::from_actions(
+            &model,
+            init_state,
+            &[ActorModelAction::Timeout(Id::from(0), ())],
+        )
+        .unwrap();
+        let svg = model.as_svg(path).unwrap();
+        assert!(svg.contains(
+            "<path d='M 0 15 C 40 15, 40 30, 0 30' marker-end='url(#arrow)' class='svg-event-loop' />"
+        ));
+        assert!(svg.contains("<text x='45' y='30' class='svg-event-label'>Timeout(())</text>"));
+    }
+
+    #[test]
+    fn instantiates_properties_per_actor() {
+        let checker = PingPongCfg {
+            maintains_history: false,
+            max_nat: 3,
+        }
+        .into_model()
+        .property_for_each_actor(Expectation::Always, "below 2", |_, _, count| *count < 2)
+        .property_for_each_actor_pair(Expectation::Always, "in step", |_, (_, a), (_, b)| a == b)
+        .property_for_each_actor_pair(Expectation::Sometimes, "behind", |_, (_, a), (_, b)| a < b)
+        .checker()
+        .spawn_bfs()
+        .join();
+        let names: Vec<_> = checker
+            .model()
+            .properties()
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(
+            names[names.len() - 4..],
+            ["below 2[0]", "below 2[1]", "in step[0,1]", "behind[0,1]"]
+        );
+        assert_eq!(
+            checker
+                .discovery("below 2[0]")
+                .unwrap()
+                .last_state()
+                .actor_states[0],
+            Arc::new(2)
+        );
+        assert_eq!(
+            checker
+                .discovery("below 2[1]")
+                .unwrap()
+                .last_state()
+                .actor_states[1],
+            Arc::new(2)
+        );
+        checker.assert_any_discovery("in step[0,1]");
+        checker.assert_any_discovery("behind[0,1]");
+    }
+
+    #[test]
+    fn checks_quiescence() {
+        /// Counts down via messages to itself, optionally retrying via a timer forever.
+        struct Countdown {
+            retry: bool,
+        }
+        impl Actor for Countdown {
+            type State = u8;
+            type Msg = ();
+            type Timer = ();
+            fn on_start(&self, id: Id, o: &mut Out<Self>) -> Self::State {
+                o.send(id, ());
+                if self.retry {
+                    o.set_timer((), model_timeout());
+                }
+                2
+            }
+            fn on_msg(
+                &self,
+                id: Id,
+                state: &mut Cow<Self::State>,
+                _src: Id,
+                _msg: Self::Msg,
+                o: &mut Out<Self>,
+            ) {
+                if **state > 0 {
+                    *state.to_mut() -= 1;
+                    o.send(id, ());
+                }
+            }
+            fn on_timeout(
+                &self,
+                _id: Id,
+                state: &mut Cow<Self::State>,
+                _timer: &Self::Timer,
+                o: &mut Out<Self>,
+            ) {
+                *state.to_mut() += 1;
+                o.set_timer((), model_timeout());
+            }
+        }
+
+        let model = |retry| {
+            let mut model = ActorModel::new((), ())
+                .actor(Countdown { retry })
+                .init_network(Network::new_unordered_duplicating([]))
+                .lossy_network(LossyNetwork::Yes)
+                .within_boundary(|_, state| *state.actor_states[0] < 3);
+            model.properties.push(Property::eventually_quiescent());
+            model
+        };
+        let quiescent = model(false);
+        assert!(!quiescent.is_quiescent(&quiescent.init_states()[0]));
+        quiescent.checker().spawn_bfs().join().assert_properties();
+
+        // The pending timer prevents quiescence along a path that crosses the boundary.
+        let path = model(true)
+            .checker()
+            .spawn_bfs()
+            .join()
+            .discovery("quiescent")
+            .unwrap();
+        assert!(path.last_state().timers_set[0].iter().next().is_some());
+    }
+
+    #[test]
+    fn surfaces_failed_assertions_as_violations() {
+        struct Counter;
+        impl Actor for Counter {
+            type State = u8;
+            type Msg = ();
+            type Timer = ();
+            fn on_start(&self, id: Id, o: &mut Out<Self>) -> Self::State {
+                o.send(id, ());
+                0
+            }
+            fn on_msg(
+                &self,
+                id: Id,
+                state: &mut Cow<Self::State>,
+                _src: Id,
+                _msg: Self::Msg,
+                o: &mut Out<Self>,
+            ) {
+                *state.to_mut() += 1;
+                o.assert(**state < 3, "below 3");
+                o.send(id, ());
+            }
+        }
+
+        let checker = ActorModel::new((), ())
+            .actor(Counter)
+            .init_network(Network::new_ordered([]))
+            .check_assertions()
+            .checker()
+            .spawn_bfs()
+            .join();
+        let path = checker.discovery("assertions").unwrap();
+        assert_eq!(path.last_state().actor_states, vec![Arc::new(3)]);
+        assert_eq!(
+            path.last_state().failed_assertions,
+            vec![(Id::from(0), "below 3")]
+        );
+
+        // Failed assertions are ignored unless checked, so they do not distinguish states.
+        let (recorder, accessor) = StateRecorder::new_with_accessor();
+        ActorModel::new((), ())
+            .actor(Counter)
+            .init_network(Network::new_ordered([]))
+            .within_boundary(|_, state| *state.actor_states[0] < 5)
+            .property(Expectation::Always, "true", |_, _| true)
+            .checker()
+            .visitor(recorder)
+            .spawn_bfs()
+            .join();
+        let states = accessor();
+        assert_eq!(states.len(), 5);
+        assert!(states
+            .iter()
+            .all(|state| state.failed_assertions.is_empty()));
+    }
+
+    #[test]
+    fn forbids_send_patterns() {
+        #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+        enum Msg {
+            Put(u8),
+            PutOk(u8),
+        }
+        /// The client retries its request, and the server acknowledges every copy.
+        struct Node;
+        impl Actor for Node {
+            type State = ();
+            type Msg = Msg;
+            type Timer = ();
+            fn on_start(&self, id: Id, o: &mut Out<Self>) -> Self::State {
+                if id == Id::from(0) {
+                    o.send(Id::from(1), Msg::Put(7));
+                    o.send(Id::from(1), Msg::Put(7));
+                }
+            }
+            fn on_msg(
+                &self,
+                _id: Id,
+                _state: &mut Cow<Self::State>,
+                src: Id,
+                msg: Self::Msg,
+                o: &mut Out<Self>,
+            ) {
+                if let Msg::Put(req) = msg {
+                    o.send(src, Msg::PutOk(req));
+                }
+            }
+        }
+
+        let checker = ActorModel::new((), Vec::new())
+            .actors(vec![Node, Node])
+            .init_network(Network::new_ordered([]))
+            .record_msg_out(|_, acked, env| match env.msg {
+                Msg::PutOk(req) => Some([acked.as_slice(), &[*req]].concat()),
+                _ => None,
+            })
+            .forbid_send(
+                "acked once",
+                |_, acked, env| matches!(env.msg, Msg::PutOk(req) if acked.contains(req)),
+            )
+            .checker()
+            .spawn_bfs()
+            .join();
+        let path = checker.discovery("acked once").unwrap();
+        assert_eq!(path.last_state().history, vec![7, 7]);
+        assert_eq!(
+            path.last_state().forbidden_sends,
+            vec![(Id::from(1), "acked once")]
+        );
+        assert_eq!(
+            path.into_actions().last(),
+            Some(&ActorModelAction::Deliver {
+                src: Id::from(0),
+                dst: Id::from(1),
+                msg: Msg::Put(7),
+            })
+        );
+
+        // Patterns observe the history recorded upon receipt of the delivered message.
+        ActorModel::new((), Vec::new())
+            .actors(vec![Node, Node])
+            .init_network(Network::new_ordered([]))
+            .record_msg_in(|_, received, env| match env.msg {
+                Msg::Put(req) => Some([received.as_slice(), &[*req]].concat()),
+                _ => None,
+            })
+            .forbid_send(
+                "acks received",
+                |_, received, env| matches!(env.msg, Msg::PutOk(req) if !received.contains(req)),
+            )
+            .checker()
+            .spawn_bfs()
+            .join()
+            .assert_no_discovery("acks received");
+    }
+
+    #[test]
+    fn formats_states_and_messages_via_actor_hooks() {
+        struct Counter;
+        impl Actor for Counter {
+            type State = u8;
+            type Msg = u8;
+            type Timer = ();
+            fn on_start(&self, id: Id, o: &mut Out<Self>) -> Self::State {
+                o.send(id, 7);
+                0
+            }
+            fn on_msg(
+                &self,
+                _: Id,
+                state: &mut Cow<Self::State>,
+                _: Id,
+                msg: Self::Msg,
+                _: &mut Out<Self>,
+            ) {
+                *state.to_mut() = msg;
+            }
+            fn format_state(&self, state: &Self::State) -> Option<String> {
+                Some(format!("count={}", state))
+            }
+            fn format_msg(&self, msg: &Self::Msg) -> Option<String> {
+                Some(format!("set({})", msg))
+            }
+        }
+
+        let model = ActorModel::new((), ()).actor(Counter);
+        let init_state = model.init_states().remove(0);
+        assert_eq!(
+            model.format_state(&init_state).unwrap(),
+            "ACTOR STATES:\n  Id(0): count=0\n\nNETWORK (unordered_duplicating, 1 in flight):\n  Id(0) → Id(0):\n    set(7)\n\nTIMERS: [Timers({})]\nHISTORY: ()\n"
+        );
+        let deliver = ActorModelAction::Deliver {
+            src: Id::from(0),
+            dst: Id::from(0),
+            msg: 7,
+        };
+        assert_eq!(model.format_action(&deliver), "Id(0) → set(7) → Id(0)");
+        assert_eq!(
+            model.format_step(&init_state, deliver).unwrap(),
+            "OUT: []\n\nNEXT_STATE: count=7\n\nPREV_STATE: count=0\n"
+        );
+
+        // Actors that do not implement the hooks fall back to `Debug`.
+        let model = ActorModel::new((), ()).actor(());
+        assert_eq!(
+            model.format_state(&model.init_states()[0]).unwrap(),
+            "ACTOR STATES:\n  Id(0): ()\n\nNETWORK (unordered_duplicating, 0 in flight):\n\nTIMERS: [Timers({})]\nHISTORY: ()\n"
+        );
+    }
+
+    #[test]
+    fn formats_network_grouped_by_channel() {
+        struct Relay;
+        impl Actor for Relay {
+            type State = ();
+            type Msg = char;
+            type Timer = ();
+            fn on_start(&self, _: Id, _: &mut Out<Self>) -> Self::State {}
+        }
+
+        let model = ActorModel::new((), ()).actors(vec![Relay, Relay, Relay]);
+        let envelopes = vec![
+            Envelope {
+                src: Id::from(1),
+                dst: Id::from(0),
+                msg: 'b',
+            },
+            Envelope {
+                src: Id::from(0),
+                dst: Id::from(2),
+                msg: 'c',
+            },
+            Envelope {
+                src: Id::from(1),
+                dst: Id::from(0),
+                msg: 'a',
+            },
+            Envelope {
+                src: Id::from(1),
+                dst: Id::from(0),
+                msg: 'b',
+            },
+        ];
+
+        // Ordered channels number messages in delivery order.
+        let network = Network::new_ordered(envelopes.clone());
+        assert_eq!(
+            model.format_network(&network),
+            "NETWORK (ordered, 4 in flight):\n  \
+               Id(0) → Id(2):\n    1. 'c'\n  \
+               Id(1) → Id(0):\n    1. 'b'\n    2. 'a'\n    3. 'b'\n"
+        );
+
+        // Unordered channels count redundant copies.
+        let network = Network::new_unordered_nonduplicating(envelopes.clone());
+        assert_eq!(
+            model.format_network(&network),
+            "NETWORK (unordered_nonduplicating, 4 in flight):\n  \
+               Id(0) → Id(2):\n    'c'\n  \
+               Id(1) → Id(0):\n    'a'\n    'b' ×2\n"
+        );
+        let network = Network::new_unordered_duplicating(envelopes);
+        assert_eq!(
+            model.format_network(&network),
+            "NETWORK (unordered_duplicating, 3 in flight):\n  \
+               Id(0) → Id(2):\n    'c'\n  \
+               Id(1) → Id(0):\n    'a'\n    'b'\n"
+        );
+    }
 }
 
 #[cfg(test)]
@@ -14,7 +14,22 @@ pub struct ActorModelState<A: Actor, H = ()> {
     pub network: Network<A::Msg>,
     pub timers_set: Vec<Timers<A::Timer>>,
     pub crashed: Vec<bool>,
+    /// The number of times that crashed actors have recovered. See
+    /// [`ActorModel::max_recoveries`](crate::actor::ActorModel::max_recoveries).
+    pub recoveries: usize,
     pub history: H,
+    /// The assertions that actors have failed, in order, which are only recorded once
+    /// [`ActorModel::check_assertions`] is called. See [`Out::assert`].
+    ///
+    /// [`ActorModel::check_assertions`]: crate::actor::ActorModel::check_assertions
+    /// [`Out::assert`]: crate::actor::Out::assert
+    pub failed_assertions: Vec<(Id, &'static str)>,
+    /// The forbidden messages that actors have sent, by sender and the name of the property
+    /// forbidding them, which are only recorded for properties added by
+    /// [`ActorModel::forbid_send`].
+    ///
+    /// [`ActorModel::forbid_send`]: crate::actor::ActorModel::forbid_send
+    pub forbidden_sends: Vec<(Id, &'static str)>,
 }
 
 impl<A, H> serde::Serialize for ActorModelState<A, H>
@@ -27,11 +42,21 @@ where
 {
     fn serialize<Ser: serde::Serializer>(&self, ser: Ser) -> Result<Ser::Ok, Ser::Error> {
         use serde::ser::SerializeStruct;
-        let mut out = ser.serialize_struct("ActorModelState", 4)?;
+        let mut out = ser.serialize_struct("ActorModelState", 5)?;
         out.serialize_field("actor_states", &self.actor_states)?;
         out.serialize_field("network", &self.network)?;
         out.serialize_field("is_timer_set", &self.timers_set)?;
         out.serialize_field("history", &self.history)?;
+        if self.failed_assertions.is_empty() {
+            out.skip_field("failed_assertions")?;
+        } else {
+            out.serialize_field("failed_assertions", &self.failed_assertions)?;
+        }
+        if self.forbidden_sends.is_empty() {
+            out.skip_field("forbidden_sends")?;
+        } else {
+            out.serialize_field("forbidden_sends", &self.forbidden_sends)?;
+        }
         out.end()
     }
 }
@@ -50,6 +75,9 @@ where
             timers_set: self.timers_set.clone(),
             network: self.network.clone(),
             crashed: self.crashed.clone(),
+            recoveries: self.recoveries,
+            failed_assertions: self.failed_assertions.clone(),
+            forbidden_sends: self.forbidden_sends.clone(),
         }
     }
 }
@@ -67,6 +95,18 @@ where
         builder.field("history", &self.history);
         builder.field("is_timer_set", &self.timers_set);
         builder.field("network", &self.network);
+        if self.crashed.contains(&true) {
+            builder.field("crashed", &self.crashed);
+        }
+        if self.recoveries > 0 {
+            builder.field("recoveries", &self.recoveries);
+        }
+        if !self.failed_assertions.is_empty() {
+            builder.field("failed_assertions", &self.failed_assertions);
+        }
+        if !self.forbidden_sends.is_empty() {
+            builder.field("forbidden_sends", &self.forbidden_sends);
+        }
         builder.finish()
     }
 }
@@ -93,6 +133,22 @@ where
         self.history.hash(state);
         self.timers_set.hash(state);
         self.network.hash(state);
+        // Crashes and recoveries are only distinguished once they occur, so these leave the
+        // fingerprints of fault-free states unchanged.
+        if self.crashed.contains(&true) {
+            self.crashed.hash(state);
+        }
+        if self.recoveries > 0 {
+            self.recoveries.hash(state);
+        }
+        // Only recorded once assertions are checked or a forbidden message is sent, so these leave
+        // other fingerprints unchanged.
+        if !self.failed_assertions.is_empty() {
+            self.failed_assertions.hash(state);
+        }
+        if !self.forbidden_sends.is_empty() {
+            self.forbidden_sends.hash(state);
+        }
     }
 }
 
@@ -109,6 +165,10 @@ where
             && self.history.eq(&other.history)
             && self.timers_set.eq(&other.timers_set)
             && self.network.eq(&other.network)
+            && self.crashed.eq(&other.crashed)
+            && self.recoveries.eq(&other.recoveries)
+            && self.failed_assertions.eq(&other.failed_assertions)
+            && self.forbidden_sends.eq(&other.forbidden_sends)
     }
 }
 
@@ -126,7 +186,18 @@ where
             network: self.network.rewrite(&plan),
             timers_set: plan.reindex(&self.timers_set),
             crashed: plan.reindex(&self.crashed),
+            recoveries: self.recoveries,
             history: self.history.rewrite(&plan),
+            failed_assertions: self
+                .failed_assertions
+                .iter()
+                .map(|(id, label)| (id.rewrite(&plan), *label))
+                .collect(),
+            forbidden_sends: self
+                .forbidden_sends
+                .iter()
+                .map(|(id, name)| (id.rewrite(&plan), *name))
+                .collect(),
         }
     }
 }
@@ -164,6 +235,9 @@ mod test {
             ]),
             timers_set: vec![non_empty_timers.clone(), empty_timers.clone(), non_empty_timers.clone()],
             crashed: vec![false; 3],
+            recoveries: 0,
+            failed_assertions: vec![],
+            forbidden_sends: vec![],
             history: History {
                 send_sequence: vec![
                     // Id(0) sends two writes
@@ -202,6 +276,9 @@ mod test {
             ]),
             timers_set: vec![empty_timers, non_empty_timers.clone(), non_empty_timers.clone()],
             crashed: vec![false; 3],
+            recoveries: 0,
+            failed_assertions: vec![],
+            forbidden_sends: vec![],
             history: History {
                 send_sequence: vec![
                     // Id(2) sends two writes
@@ -0,0 +1,192 @@
+//! Runtime safety monitors, which evaluate `always` conditions over a single actor's state and
+//! the message it received.
+//!
+//! A [`Monitored`] actor reports each violated monitor as a failed [assertion](Out::assert), so
+//! the same conditions serve as a specification while model checking (via
+//! [`ActorModel::check_assertions`]) and as production monitors once
+//! [spawned](crate::actor::spawn()), where violations are logged or panic per the
+//! [`AssertionPolicy`].
+//!
+//! # Example
+//!
+//! ```
+//! # use stateright::*;
+//! # use stateright::actor::*;
+//! # use stateright::actor::monitor::Monitored;
+//! # use std::borrow::Cow;
+//! struct Counter;
+//! impl Actor for Counter {
+//!     type State = u8;
+//!     type Msg = ();
+//!     type Timer = ();
+//!     fn on_start(&self, id: Id, o: &mut Out<Self>) -> Self::State {
+//!         o.send(id, ());
+//!         0
+//!     }
+//!     fn on_msg(&self, id: Id, state: &mut Cow<Self::State>, _: Id, _: (), o: &mut Out<Self>) {
+//!         *state.to_mut() += 1;
+//!         o.send(id, ());
+//!     }
+//! }
+//!
+//! let actor = Monitored::new(Counter).always("below 3", |_id, count, _msg| *count < 3);
+//! let checker = ActorModel::new((), ())
+//!     .actor(actor)
+//!     .init_network(Network::new_ordered([]))
+//!     .check_assertions()
+//!     .checker()
+//!     .spawn_bfs()
+//!     .join();
+//! let path = checker.discovery("assertions").unwrap();
+//! assert_eq!(path.last_state().failed_assertions, vec![(Id::from(0), "below 3")]);
+//! ```
+
+use crate::actor::*;
+use std::borrow::Cow;
+
+/// The condition of a monitor, evaluated against an actor's ID, its state after handling an
+/// event, and the message that it received (or [`None`] on start or timeout).
+pub type MonitorCondition<A> = fn(Id, &<A as Actor>::State, Option<&<A as Actor>::Msg>) -> bool;
+
+/// Wraps an actor with named monitors that are evaluated after every event the actor handles.
+#[derive(Clone)]
+pub struct Monitored<A: Actor> {
+    pub wrapped_actor: A,
+    pub monitors: Vec<(&'static str, MonitorCondition<A>)>,
+}
+
+impl<A: Actor> Monitored<A> {
+    /// Wraps an actor without any monitors.
+    pub fn new(wrapped_actor: A) -> Self {
+        Self {
+            wrapped_actor,
+            monitors: Vec::new(),
+        }
+    }
+
+    /// Adds a monitor for a condition that should always hold, reporting a failed assertion
+    /// labeled by `name` whenever it does not.
+    pub fn always(mut self, name: &'static str, condition: MonitorCondition<A>) -> Self {
+        self.monitors.push((name, condition));
+        self
+    }
+
+    fn check(&self, id: Id, state: &A::State, msg: Option<&A::Msg>, o: &mut Out<Self>) {
+        for (name, condition) in &self.monitors {
+            o.assert(condition(id, state, msg), name);
+        }
+    }
+}
+
+impl<A: Actor> Actor for Monitored<A> {
+    type Msg = A::Msg;
+    type State = A::State;
+    type Timer = A::Timer;
+
+    fn on_start(&self, id: Id, o: &mut Out<Self>) -> Self::State {
+        let mut wrapped_out = Out::new();
+        let state = self.wrapped_actor.on_start(id, &mut wrapped_out);
+        o.append(&mut wrapped_out);
+        self.check(id, &state, None, o);
+        state
+    }
+
+    fn on_msg(
+        &self,
+        id: Id,
+        state: &mut Cow<Self::State>,
+        src: Id,
+        msg: Self::Msg,
+        o: &mut Out<Self>,
+    ) {
+        let mut wrapped_out = Out::new();
+        self.wrapped_actor
+            .on_msg(id, state, src, msg.clone(), &mut wrapped_out);
+        o.append(&mut wrapped_out);
+        self.check(id, state, Some(&msg), o);
+    }
+
+    fn on_timeout(
+        &self,
+        id: Id,
+        state: &mut Cow<Self::State>,
+        timer: &Self::Timer,
+        o: &mut Out<Self>,
+    ) {
+        let mut wrapped_out = Out::new();
+        self.wrapped_actor
+            .on_timeout(id, state, timer, &mut wrapped_out);
+        o.append(&mut wrapped_out);
+        self.check(id, state, None, o);
+    }
+
+    fn name(&self) -> String {
+        self.wrapped_actor.name()
+    }
+
+    fn format_state(&self, state: &Self::State) -> Option<String> {
+        self.wrapped_actor.format_state(state)
+    }
+
+    fn format_msg(&self, msg: &Self::Msg) -> Option<String> {
+        self.wrapped_actor.format_msg(msg)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Checker, Model};
+
+    /// Echoes every message back to its sender, recording the largest value seen.
+    #[derive(Clone)]
+    struct Echo;
+    impl Actor for Echo {
+        type Msg = u8;
+        type State = u8;
+        type Timer = ();
+
+        fn on_start(&self, id: Id, o: &mut Out<Self>) -> Self::State {
+            if id == Id::from(0) {
+                o.send(Id::from(1), 1);
+            }
+            0
+        }
+
+        fn on_msg(
+            &self,
+            _id: Id,
+            state: &mut Cow<Self::State>,
+            src: Id,
+            msg: Self::Msg,
+            o: &mut Out<Self>,
+        ) {
+            if msg > **state {
+                *state.to_mut() = msg;
+            }
+            if msg < 4 {
+                o.send(src, msg + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn reports_violated_monitors_as_failed_assertions() {
+        let actor = Monitored::new(Echo)
+            .always("small msg", |_, _, msg| msg.copied().unwrap_or(0) < 3)
+            .always("small state", |_, state, _| *state < 10);
+        let checker = ActorModel::new((), ())
+            .actors(vec![actor.clone(), actor])
+            .init_network(Network::new_ordered([]))
+            .check_assertions()
+            .checker()
+            .spawn_bfs()
+            .join();
+        let path = checker.discovery("assertions").unwrap();
+        assert_eq!(
+            path.last_state().failed_assertions,
+            vec![(Id::from(1), "small msg")]
+        );
+        assert_eq!(path.into_actions().len(), 3);
+    }
+}
@@ -19,6 +19,7 @@ use std::collections::{btree_map, hash_map, hash_set};
 use std::collections::{BTreeMap, VecDeque};
 use std::hash::Hash;
 use std::str::FromStr;
+use std::sync::Arc;
 
 /// Indicates the source and destination for a message.
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Serialize)]
@@ -43,16 +44,20 @@ impl<Msg> Envelope<&Msg> {
 }
 
 /// Represents a network of messages.
+///
+/// Messages are held behind an [`Arc`], so cloning a network (which happens for nearly every
+/// state that the checker generates) only copies pointers rather than message payloads. An `Arc`
+/// hashes, compares, and serializes the same as the message it points to.
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Serialize)]
 pub enum Network<Msg>
 where
     Msg: Eq + Hash,
 {
     /// Indicates that messages have no ordering (racing one another), and can be redelivered.
-    UnorderedDuplicating(HashableHashSet<Envelope<Msg>>),
+    UnorderedDuplicating(HashableHashSet<Envelope<Arc<Msg>>>),
 
     /// Indicates that messages have no ordering (racing one another), and will not be redelivered.
-    UnorderedNonDuplicating(HashableHashMap<Envelope<Msg>, usize>),
+    UnorderedNonDuplicating(HashableHashMap<Envelope<Arc<Msg>>, usize>),
 
     /// Indicates that directed message flows between pairs of actors are ordered. Does not
     /// indicate any ordering across different flows. Each direction for a pair of actors is a
@@ -64,7 +69,7 @@ where
     /// not restart. A later version of the module and checker will account for actor restarts.
     ///
     /// [`ordered_reliable_link`]: crate::actor::ordered_reliable_link
-    Ordered(BTreeMap<(Id, Id), VecDeque<Msg>>),
+    Ordered(BTreeMap<(Id, Id), VecDeque<Arc<Msg>>>),
 }
 
 impl<Msg> Network<Msg>
@@ -181,6 +186,7 @@ where
 
     /// Sends a message.
     pub(crate) fn send(&mut self, envelope: Envelope<Msg>) {
+        let envelope = shared(envelope);
         match self {
             Network::UnorderedDuplicating(set) => {
                 set.insert(envelope);
@@ -204,7 +210,7 @@ where
             Network::UnorderedDuplicating(_) => {
                 // This is a no-op as the message can be redelivered.
             }
-            Network::UnorderedNonDuplicating(multiset) => match multiset.entry(envelope) {
+            Network::UnorderedNonDuplicating(multiset) => match multiset.entry(shared(envelope)) {
                 hash_map::Entry::Occupied(mut entry) => {
                     let value = *entry.get();
                     assert!(value > 0);
@@ -233,7 +239,7 @@ where
                 let i = flow_entry
                     .get()
                     .iter()
-                    .position(|x| x == &envelope.msg)
+                    .position(|x| **x == envelope.msg)
                     .expect("message not found");
                 if flow_entry.get().len() > 1 {
                     flow_entry.into_mut().remove(i);
@@ -250,9 +256,9 @@ where
     {
         match self {
             Network::UnorderedDuplicating(set) => {
-                set.remove(&envelope);
+                set.remove(&shared(envelope));
             }
-            Network::UnorderedNonDuplicating(multiset) => match multiset.entry(envelope) {
+            Network::UnorderedNonDuplicating(multiset) => match multiset.entry(shared(envelope)) {
                 hash_map::Entry::Occupied(mut entry) => {
                     let value = *entry.get();
                     assert!(value > 0);
@@ -281,7 +287,7 @@ where
                 let i = flow_entry
                     .get()
                     .iter()
-                    .position(|x| x == &envelope.msg)
+                    .position(|x| **x == envelope.msg)
                     .expect("message not found");
                 if flow_entry.get().len() > 1 {
                     flow_entry.into_mut().remove(i);
@@ -293,6 +299,15 @@ where
     }
 }
 
+/// Converts an envelope to the representation stored by a network.
+fn shared<Msg>(envelope: Envelope<Msg>) -> Envelope<Arc<Msg>> {
+    Envelope {
+        src: envelope.src,
+        dst: envelope.dst,
+        msg: Arc::new(envelope.msg),
+    }
+}
+
 impl<Msg> FromStr for Network<Msg>
 where
     Msg: Eq + Hash,
@@ -324,16 +339,16 @@ where
 }
 
 pub enum NetworkIter<'a, Msg> {
-    UnorderedDuplicating(hash_set::Iter<'a, Envelope<Msg>>),
+    UnorderedDuplicating(hash_set::Iter<'a, Envelope<Arc<Msg>>>),
     UnorderedNonDuplicating(
         // active env/count to iterate over repeated sends
         Option<(Envelope<&'a Msg>, usize)>,
-        std::collections::hash_map::Iter<'a, Envelope<Msg>, usize>,
+        std::collections::hash_map::Iter<'a, Envelope<Arc<Msg>>, usize>,
     ),
     Ordered(
         // active channel/cursor to iterate over all messages of a channel
-        Option<(Id, Id, &'a VecDeque<Msg>, usize)>,
-        btree_map::Iter<'a, (Id, Id), VecDeque<Msg>>,
+        Option<(Id, Id, &'a VecDeque<Arc<Msg>>, usize)>,
+        btree_map::Iter<'a, (Id, Id), VecDeque<Arc<Msg>>>,
     ),
 }
 
@@ -344,7 +359,7 @@ impl<'a, Msg> Iterator for NetworkIter<'a, Msg> {
             NetworkIter::UnorderedDuplicating(it) => it.next().map(|env| Envelope {
                 src: env.src,
                 dst: env.dst,
-                msg: &env.msg,
+                msg: &*env.msg,
             }),
             NetworkIter::UnorderedNonDuplicating(active, it) => {
                 if let Some((env, count)) = active {
@@ -360,7 +375,7 @@ impl<'a, Msg> Iterator for NetworkIter<'a, Msg> {
                     let env = Envelope {
                         src: env.src,
                         dst: env.dst,
-                        msg: &env.msg,
+                        msg: &*env.msg,
                     };
                     if *count > 1 {
                         *active = Some((env, *count));
@@ -370,7 +385,7 @@ impl<'a, Msg> Iterator for NetworkIter<'a, Msg> {
             }
             NetworkIter::Ordered(active, it) => {
                 if let Some((src, dst, messages, index)) = active {
-                    let msg = messages.get(*index).unwrap(); // messages.len() > 1
+                    let msg = &**messages.get(*index).unwrap(); // messages.len() > 1
                     return Some(Envelope {
                         src: *src,
                         dst: *dst,
@@ -378,7 +393,7 @@ impl<'a, Msg> Iterator for NetworkIter<'a, Msg> {
                     });
                 }
                 it.next().map(|(&(src, dst), messages)| {
-                    let msg = messages.get(0).unwrap(); // messages.len() > 1
+                    let msg = &**messages.get(0).unwrap(); // messages.len() > 1
                     *active = Some((src, dst, messages, 0));
                     Envelope { src, dst, msg }
                 })
@@ -388,9 +403,9 @@ impl<'a, Msg> Iterator for NetworkIter<'a, Msg> {
 }
 
 pub enum NetworkDeliverableIter<'a, Msg> {
-    UnorderedDuplicating(hash_set::Iter<'a, Envelope<Msg>>),
-    UnorderedNonDuplicating(hash_map::Keys<'a, Envelope<Msg>, usize>),
-    Ordered(btree_map::Iter<'a, (Id, Id), VecDeque<Msg>>),
+    UnorderedDuplicating(hash_set::Iter<'a, Envelope<Arc<Msg>>>),
+    UnorderedNonDuplicating(hash_map::Keys<'a, Envelope<Arc<Msg>>, usize>),
+    Ordered(btree_map::Iter<'a, (Id, Id), VecDeque<Arc<Msg>>>),
 }
 
 impl<'a, Msg> Iterator for NetworkDeliverableIter<'a, Msg> {
@@ -400,15 +415,15 @@ impl<'a, Msg> Iterator for NetworkDeliverableIter<'a, Msg> {
             NetworkDeliverableIter::UnorderedDuplicating(it) => it.next().map(|env| Envelope {
                 src: env.src,
                 dst: env.dst,
-                msg: &env.msg,
+                msg: &*env.msg,
             }),
             NetworkDeliverableIter::UnorderedNonDuplicating(it) => it.next().map(|env| Envelope {
                 src: env.src,
                 dst: env.dst,
-                msg: &env.msg,
+                msg: &*env.msg,
             }),
             NetworkDeliverableIter::Ordered(it) => it.next().map(|(&(src, dst), messages)| {
-                let msg = messages.get(0).expect("empty channel");
+                let msg = &**messages.get(0).expect("empty channel");
                 Envelope { src, dst, msg }
             }),
         }
@@ -436,4 +451,27 @@ mod test {
             .collect()
         );
     }
+
+    #[test]
+    fn clones_share_messages() {
+        let envelope = Envelope {
+            src: Id::from(0),
+            dst: Id::from(1),
+            msg: "payload".to_string(),
+        };
+        for network in [
+            Network::new_ordered([envelope.clone()]),
+            Network::new_unordered_duplicating([envelope.clone()]),
+            Network::new_unordered_nonduplicating([envelope.clone()]),
+        ] {
+            let mut copy = network.clone();
+            let original_msg = network.iter_all().next().unwrap().msg;
+            let copied_msg = copy.iter_all().next().unwrap().msg;
+            assert!(std::ptr::eq(original_msg, copied_msg));
+
+            copy.on_drop(envelope.clone());
+            assert_eq!(copy.len(), 0);
+            assert_eq!(network.len(), 1);
+        }
+    }
 }
@@ -181,11 +181,14 @@ where
 
 fn process_output<A: Actor>(
     state: &mut StateWrapper<A::Msg, A::State>,
-    wrapped_out: Out<A>,
+    mut wrapped_out: Out<A>,
     o: &mut Out<ActorWrapper<A>>,
 ) where
     A::Msg: Hash,
 {
+    for label in wrapped_out.drain_failed_assertions() {
+        o.assert(false, label);
+    }
     for command in wrapped_out {
         match command {
             Command::CancelTimer(_) => {
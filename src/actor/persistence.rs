@@ -0,0 +1,153 @@
+//! Durable actor state for the runtime underlying [`spawn`](crate::actor::spawn()). See
+//! [`SpawnBuilder::persist_states`](crate::actor::SpawnBuilder::persist_states).
+
+use crate::actor::Id;
+use std::fmt::{Debug, Display, Formatter};
+use std::fs;
+use std::io::Write;
+use std::net::SocketAddrV4;
+use std::path::{Path, PathBuf};
+
+/// Why a snapshot could not be read or written.
+#[derive(Debug)]
+pub(crate) enum StoreError<E> {
+    /// The snapshot file could not be accessed.
+    Io(std::io::Error),
+    /// The state could not be converted to or from bytes.
+    Codec(E),
+}
+
+impl<E: Debug> Display for StoreError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoreError::Io(e) => write!(f, "{}", e),
+            StoreError::Codec(e) => write!(f, "{:?}", e),
+        }
+    }
+}
+
+/// Snapshots actor states to a directory, one file per actor.
+pub(crate) struct StateStore<State, E> {
+    dir: PathBuf,
+    serialize: fn(&State) -> Result<Vec<u8>, E>,
+    deserialize: fn(&[u8]) -> Result<State, E>,
+}
+
+impl<State, E: Debug> StateStore<State, E> {
+    pub(crate) fn new(
+        dir: PathBuf,
+        serialize: fn(&State) -> Result<Vec<u8>, E>,
+        deserialize: fn(&[u8]) -> Result<State, E>,
+    ) -> Self {
+        Self {
+            dir,
+            serialize,
+            deserialize,
+        }
+    }
+
+    /// The file holding the latest snapshot for an actor.
+    pub(crate) fn path(&self, id: Id) -> PathBuf {
+        let addr = SocketAddrV4::from(id);
+        self.dir
+            .join(format!("{}_{}.state", addr.ip(), addr.port()))
+    }
+
+    /// Returns the most recently saved state for an actor, or `None` if the actor has no
+    /// snapshot. A snapshot that exists but cannot be read or parsed is an error rather than
+    /// being ignored, as starting afresh would discard the actor's durable state.
+    pub(crate) fn load(&self, id: Id) -> Result<Option<State>, StoreError<E>> {
+        let bytes = match fs::read(self.path(id)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(StoreError::Io(e)),
+        };
+        (self.deserialize)(&bytes)
+            .map(Some)
+            .map_err(StoreError::Codec)
+    }
+
+    /// Durably replaces the saved state for an actor. The snapshot is written to a temporary
+    /// file that is then renamed, so a crash mid-write leaves the previous snapshot intact.
+    pub(crate) fn save(&self, id: Id, state: &State) -> Result<(), StoreError<E>> {
+        let bytes = (self.serialize)(state).map_err(StoreError::Codec)?;
+        write_atomically(&self.path(id), &bytes).map_err(StoreError::Io)
+    }
+}
+
+fn write_atomically(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let tmp_path = path.with_extension("state.tmp");
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)?;
+    sync_parent_dir(path)
+}
+
+/// Flushes the directory entry for a renamed file, without which the rename itself may be lost
+/// if the machine crashes.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> std::io::Result<()> {
+    match path.parent() {
+        Some(dir) if dir.as_os_str().is_empty() => fs::File::open(".")?.sync_all(),
+        Some(dir) => fs::File::open(dir)?.sync_all(),
+        None => Ok(()),
+    }
+}
+
+/// Directories cannot be opened for syncing on this platform.
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn saves_and_loads_snapshots() {
+        let dir =
+            std::env::temp_dir().join(format!("stateright-persistence-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let store = StateStore::new(
+            dir.clone(),
+            |s: &u32| Ok::<_, String>(s.to_string().into_bytes()),
+            |b| {
+                String::from_utf8_lossy(b)
+                    .parse::<u32>()
+                    .map_err(|e| e.to_string())
+            },
+        );
+        let id = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 3000));
+        assert_eq!(store.load(id).unwrap(), None);
+
+        store.save(id, &1).unwrap();
+        store.save(id, &2).unwrap();
+        assert_eq!(store.load(id).unwrap(), Some(2));
+        assert_eq!(store.path(id), dir.join("127.0.0.1_3000.state"));
+
+        // Corrupt snapshots are not mistaken for missing ones.
+        fs::write(store.path(id), b"corrupt").unwrap();
+        assert!(matches!(store.load(id), Err(StoreError::Codec(_))));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reports_save_failures() {
+        let dir = std::env::temp_dir().join(format!(
+            "stateright-persistence-missing-{}",
+            std::process::id()
+        ));
+        let id = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 3000));
+
+        let store = StateStore::new(dir.clone(), |_: &u32| Err("unserializable"), |_| Ok(0));
+        assert!(matches!(store.save(id, &1), Err(StoreError::Codec(_))));
+
+        // The directory does not exist.
+        let store = StateStore::new(dir, |_: &u32| Ok::<_, ()>(Vec::new()), |_| Ok(0));
+        assert!(matches!(store.save(id, &1), Err(StoreError::Io(_))));
+    }
+}
@@ -0,0 +1,621 @@
+//! Private module for exporting actor systems to Promela.
+
+use crate::actor::{Actor, ActorModel, Command, Id, LossyNetwork, Network, Out};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{Debug, Write};
+use std::hash::Hash;
+
+/// Promela encodes actor states and messages as `byte`s.
+const MAX_VALUES: usize = u8::MAX as usize + 1;
+
+impl<A, C, H> ActorModel<A, C, H>
+where
+    A: Actor,
+    A::State: Eq,
+    H: Clone + Debug + Hash,
+{
+    /// Exports the actor system as a [Promela](https://spinroot.com/spin/Man/promela.html) model
+    /// so that it can be cross-checked with SPIN. This is experimental and limited to systems
+    /// with finitely many actor states and messages, which are enumerated by running the actors'
+    /// handlers for every message that can reach each actor state.
+    ///
+    /// Each actor becomes a process whose state is `st[i]`, and each message delivery becomes an
+    /// atomic guarded command. Actor states and messages are numbered, with the numbering listed
+    /// in comments. Properties are not exported, but they can be added as `ltl` formulas over
+    /// `st`, and failed [assertions](crate::actor::Out::assert) become Promela assertions.
+    ///
+    /// `channel_capacity` bounds the number of in-flight messages per channel. Note that SPIN
+    /// blocks a send to a full channel, which interrupts atomic delivery.
+    ///
+    /// Returns an error if the system uses timers, crashes, or a lossy network, or if it has more
+    /// than 256 actors, states per actor, or distinct messages. The boundary and history are
+    /// ignored.
+    pub fn to_promela(&self, channel_capacity: usize) -> Result<String, String> {
+        if self.lossy_network == LossyNetwork::Yes {
+            return Err("Lossy networks cannot be exported to Promela.".to_string());
+        }
+        if self.max_crashes > 0 {
+            return Err("Crashes cannot be exported to Promela.".to_string());
+        }
+        if self.actors.len() > MAX_VALUES {
+            return Err(format!(
+                "At most {} actors can be exported to Promela.",
+                MAX_VALUES
+            ));
+        }
+        let table = TransitionTable::build(self)?;
+        Ok(table.render(self, channel_capacity))
+    }
+}
+
+/// Assigns sequential numbers to distinct values.
+struct Numbering<T> {
+    values: Vec<T>,
+    numbers: HashMap<T, usize>,
+    limit: usize,
+}
+
+impl<T: Clone + Eq + Hash> Numbering<T> {
+    fn new(limit: usize) -> Self {
+        Self {
+            values: Vec::new(),
+            numbers: HashMap::new(),
+            limit,
+        }
+    }
+
+    /// Returns the value's number and whether it is newly numbered.
+    fn number(&mut self, value: T, what: &str) -> Result<(usize, bool), String> {
+        if let Some(&number) = self.numbers.get(&value) {
+            return Ok((number, false));
+        }
+        let number = self.values.len();
+        if number == self.limit {
+            return Err(format!(
+                "More than {} {} cannot be exported to Promela.",
+                self.limit, what
+            ));
+        }
+        self.values.push(value.clone());
+        self.numbers.insert(value, number);
+        Ok((number, true))
+    }
+}
+
+/// The effects of an actor handler: sends as `(dst, msg)` and failed assertion labels.
+#[derive(Default)]
+struct Effects {
+    sends: Vec<(usize, usize)>,
+    assertions: Vec<&'static str>,
+}
+
+struct Delivery {
+    from: usize,
+    src: usize,
+    msg: usize,
+    to: usize,
+    effects: Effects,
+}
+
+struct ActorTable<State> {
+    states: Numbering<State>,
+    /// Messages that can reach the actor, as `(src, msg)`.
+    inbox: Numbering<(usize, usize)>,
+    deliveries: Vec<Delivery>,
+}
+
+struct TransitionTable<State, Msg> {
+    msgs: Numbering<Msg>,
+    actors: Vec<ActorTable<State>>,
+    /// Messages in flight initially, as `(src, dst, msg)` in send order.
+    init_sends: Vec<(usize, usize, usize)>,
+    init_assertions: Vec<(usize, &'static str)>,
+}
+
+impl<State, Msg> TransitionTable<State, Msg>
+where
+    State: Clone + Debug + Eq + Hash,
+    Msg: Clone + Debug + Eq + Hash,
+{
+    fn build<A, C, H>(model: &ActorModel<A, C, H>) -> Result<Self, String>
+    where
+        A: Actor<State = State, Msg = Msg>,
+        H: Clone + Debug + Hash,
+    {
+        let mut table = TransitionTable {
+            msgs: Numbering::new(MAX_VALUES),
+            actors: (0..model.actors.len())
+                .map(|_| ActorTable {
+                    states: Numbering::new(MAX_VALUES),
+                    inbox: Numbering::new(usize::MAX),
+                    deliveries: Vec::new(),
+                })
+                .collect(),
+            init_sends: Vec::new(),
+            init_assertions: Vec::new(),
+        };
+        // Pairs of actor state and inbox entry whose delivery has not been tabulated.
+        let mut pending = VecDeque::new();
+
+        for env in model.init_network.iter_all() {
+            let src = usize::from(env.src);
+            if let Some(msg) = table.enqueue(env.dst, src, env.msg.clone(), &mut pending)? {
+                table.init_sends.push((src, usize::from(env.dst), msg));
+            }
+        }
+        for (index, actor) in model.actors.iter().enumerate() {
+            let mut out = Out::new();
+            let state = actor.on_start(Id::from(index), &mut out);
+            let effects = table.effects(index, out, &mut pending)?;
+            table.init_sends.extend(
+                effects
+                    .sends
+                    .into_iter()
+                    .map(|(dst, msg)| (index, dst, msg)),
+            );
+            table
+                .init_assertions
+                .extend(effects.assertions.into_iter().map(|label| (index, label)));
+            table.number_state(index, state, &mut pending)?;
+        }
+
+        while let Some((index, from, entry)) = pending.pop_front() {
+            let actor_table = &table.actors[index];
+            let (src, msg) = actor_table.inbox.values[entry];
+            let mut state = Cow::Borrowed(&actor_table.states.values[from]);
+            let mut out = Out::new();
+            model.actors[index].on_msg(
+                Id::from(index),
+                &mut state,
+                Id::from(src),
+                table.msgs.values[msg].clone(),
+                &mut out,
+            );
+            let state = state.into_owned();
+            let effects = table.effects(index, out, &mut pending)?;
+            let to = table.number_state(index, state, &mut pending)?;
+            table.actors[index].deliveries.push(Delivery {
+                from,
+                src,
+                msg,
+                to,
+                effects,
+            });
+        }
+        Ok(table)
+    }
+
+    fn number_state(
+        &mut self,
+        index: usize,
+        state: State,
+        pending: &mut VecDeque<(usize, usize, usize)>,
+    ) -> Result<usize, String> {
+        let actor_table = &mut self.actors[index];
+        let (number, is_new) = actor_table
+            .states
+            .number(state, &format!("states of actor {}", index))?;
+        if is_new {
+            pending.extend((0..actor_table.inbox.values.len()).map(|entry| (index, number, entry)));
+        }
+        Ok(number)
+    }
+
+    /// Numbers a message sent to an actor, returning `None` if there is no such actor.
+    fn enqueue(
+        &mut self,
+        dst: Id,
+        src: usize,
+        msg: Msg,
+        pending: &mut VecDeque<(usize, usize, usize)>,
+    ) -> Result<Option<usize>, String> {
+        let dst = usize::from(dst);
+        if dst >= self.actors.len() {
+            return Ok(None);
+        }
+        let (msg, _) = self.msgs.number(msg, "distinct messages")?;
+        let actor_table = &mut self.actors[dst];
+        let (entry, is_new) = actor_table.inbox.number((src, msg), "distinct messages")?;
+        if is_new {
+            pending.extend((0..actor_table.states.values.len()).map(|from| (dst, from, entry)));
+        }
+        Ok(Some(msg))
+    }
+
+    fn effects<T>(
+        &mut self,
+        index: usize,
+        mut out: Out<impl Actor<Msg = Msg, Timer = T>>,
+        pending: &mut VecDeque<(usize, usize, usize)>,
+    ) -> Result<Effects, String> {
+        let mut effects = Effects::default();
+        effects.assertions.extend(out.drain_failed_assertions());
+        for command in out {
+            match command {
+                Command::Send(dst, msg) => {
+                    if let Some(msg) = self.enqueue(dst, index, msg, pending)? {
+                        effects.sends.push((usize::from(dst), msg));
+                    }
+                }
+                Command::SetTimer(..) => {
+                    return Err(format!(
+                        "Actor {} sets a timer, which cannot be exported to Promela.",
+                        index
+                    ));
+                }
+                // Timers are never set, so canceling one has no effect.
+                Command::CancelTimer(_) => {}
+            }
+        }
+        Ok(effects)
+    }
+
+    fn render<A, C, H>(&self, model: &ActorModel<A, C, H>, channel_capacity: usize) -> String
+    where
+        A: Actor<State = State, Msg = Msg>,
+        H: Clone + Debug + Hash,
+    {
+        let ordered = matches!(model.init_network, Network::Ordered(_));
+        let duplicating = matches!(model.init_network, Network::UnorderedDuplicating(_));
+        let network = match model.init_network {
+            Network::UnorderedDuplicating(_) => "unordered duplicating",
+            Network::UnorderedNonDuplicating(_) => "unordered nonduplicating",
+            Network::Ordered(_) => "ordered",
+        };
+        let channel = |src: usize, dst: usize| {
+            if ordered {
+                format!("net_{}_{}", src, dst)
+            } else {
+                format!("net_{}", dst)
+            }
+        };
+        let message = |src: usize, msg: usize| {
+            if ordered {
+                msg.to_string()
+            } else {
+                format!("{}, {}", src, msg)
+            }
+        };
+        let send = |out: &mut String, indent: &str, src: usize, dst: usize, msg: usize| {
+            let (channel, message) = (channel(src, dst), message(src, msg));
+            if duplicating {
+                writeln!(
+                    out,
+                    "{indent}if :: {channel}??[{message}] -> skip :: else -> {channel}!{message} fi;"
+                )
+                .unwrap();
+            } else {
+                writeln!(out, "{indent}{channel}!{message};").unwrap();
+            }
+        };
+        let assertion = |out: &mut String, indent: &str, index: usize, label: &str| {
+            writeln!(
+                out,
+                "{indent}printf(\"Actor {} failed assertion: {}\\n\");",
+                index,
+                label.replace('"', "'")
+            )
+            .unwrap();
+            writeln!(out, "{indent}assert(false);").unwrap();
+        };
+
+        let mut out = String::new();
+        writeln!(
+            out,
+            "/* Exported by Stateright from {} ({} network). */",
+            std::any::type_name::<ActorModel<A, C, H>>(),
+            network
+        )
+        .unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "/* Messages:").unwrap();
+        for (number, msg) in self.msgs.values.iter().enumerate() {
+            writeln!(out, " *   {}: {}", number, comment(msg)).unwrap();
+        }
+        writeln!(out, " */").unwrap();
+        writeln!(out).unwrap();
+
+        if ordered {
+            let mut channels = HashSet::new();
+            for (dst, actor_table) in self.actors.iter().enumerate() {
+                for &(src, _) in &actor_table.inbox.values {
+                    if channels.insert((src, dst)) {
+                        writeln!(
+                            out,
+                            "chan {} = [{}] of {{ byte }};",
+                            channel(src, dst),
+                            channel_capacity
+                        )
+                        .unwrap();
+                    }
+                }
+            }
+        } else {
+            for dst in 0..self.actors.len() {
+                writeln!(
+                    out,
+                    "chan {} = [{}] of {{ byte, byte }};",
+                    channel(0, dst),
+                    channel_capacity
+                )
+                .unwrap();
+            }
+        }
+        writeln!(out, "byte st[{}];", self.actors.len()).unwrap();
+
+        for (index, actor_table) in self.actors.iter().enumerate() {
+            writeln!(out).unwrap();
+            writeln!(out, "/* Actor {} states:", index).unwrap();
+            for (number, state) in actor_table.states.values.iter().enumerate() {
+                writeln!(out, " *   {}: {}", number, comment(state)).unwrap();
+            }
+            writeln!(out, " */").unwrap();
+            writeln!(out, "proctype Actor{}() {{", index).unwrap();
+            if actor_table.deliveries.is_empty() {
+                writeln!(out, "    skip").unwrap();
+                writeln!(out, "}}").unwrap();
+                continue;
+            }
+            writeln!(out, "    do").unwrap();
+            for delivery in &actor_table.deliveries {
+                let (channel, message) = (
+                    channel(delivery.src, index),
+                    message(delivery.src, delivery.msg),
+                );
+                let (poll, receive) = if ordered {
+                    (
+                        format!("{channel}?[{message}]"),
+                        format!("{channel}?{message}"),
+                    )
+                } else {
+                    (
+                        format!("{channel}??[{message}]"),
+                        format!("{channel}??{message}"),
+                    )
+                };
+                writeln!(
+                    out,
+                    "    :: atomic {{ st[{}] == {} && {} ->",
+                    index, delivery.from, poll
+                )
+                .unwrap();
+                if !duplicating {
+                    writeln!(out, "        {};", receive).unwrap();
+                }
+                writeln!(out, "        st[{}] = {};", index, delivery.to).unwrap();
+                for &(dst, msg) in &delivery.effects.sends {
+                    send(&mut out, "        ", index, dst, msg);
+                }
+                for label in &delivery.effects.assertions {
+                    assertion(&mut out, "        ", index, label);
+                }
+                writeln!(out, "    }}").unwrap();
+            }
+            writeln!(out, "    od").unwrap();
+            writeln!(out, "}}").unwrap();
+        }
+
+        writeln!(out).unwrap();
+        writeln!(out, "init {{").unwrap();
+        writeln!(out, "    atomic {{").unwrap();
+        for &(src, dst, msg) in &self.init_sends {
+            send(&mut out, "        ", src, dst, msg);
+        }
+        for &(index, label) in &self.init_assertions {
+            assertion(&mut out, "        ", index, label);
+        }
+        for index in 0..self.actors.len() {
+            // Each actor's initial state is numbered first.
+            writeln!(out, "        st[{}] = 0;", index).unwrap();
+        }
+        for index in 0..self.actors.len() {
+            writeln!(out, "        run Actor{}();", index).unwrap();
+        }
+        writeln!(out, "    }}").unwrap();
+        writeln!(out, "}}").unwrap();
+        out
+    }
+}
+
+/// Renders a value for a Promela comment, which cannot contain `*/`.
+fn comment(value: &impl Debug) -> String {
+    format!("{:?}", value).replace("*/", "* /")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::actor::*;
+    use std::borrow::Cow;
+
+    /// Replies to a `Ping` with a `Pong` until it has replied twice.
+    struct Ponger;
+    /// Pings the ponger upon starting and after each `Pong`.
+    struct Pinger(Id);
+
+    #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+    enum Msg {
+        Ping,
+        Pong,
+    }
+
+    #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+    enum State {
+        Pinger,
+        Ponger { replies: u8 },
+    }
+
+    enum PingPong {
+        Pinger(Pinger),
+        Ponger(Ponger),
+    }
+
+    impl Actor for PingPong {
+        type Msg = Msg;
+        type State = State;
+        type Timer = ();
+
+        fn on_start(&self, _id: Id, o: &mut Out<Self>) -> Self::State {
+            match self {
+                PingPong::Pinger(Pinger(ponger)) => {
+                    o.send(*ponger, Msg::Ping);
+                    State::Pinger
+                }
+                PingPong::Ponger(Ponger) => State::Ponger { replies: 0 },
+            }
+        }
+
+        fn on_msg(
+            &self,
+            _id: Id,
+            state: &mut Cow<Self::State>,
+            src: Id,
+            msg: Self::Msg,
+            o: &mut Out<Self>,
+        ) {
+            match (state.as_ref(), msg) {
+                (State::Pinger, Msg::Pong) => o.send(src, Msg::Ping),
+                (State::Ponger { replies }, Msg::Ping) if *replies < 2 => {
+                    o.send(src, Msg::Pong);
+                    *state.to_mut() = State::Ponger {
+                        replies: replies + 1,
+                    };
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn model() -> ActorModel<PingPong> {
+        ActorModel::new((), ())
+            .actor(PingPong::Pinger(Pinger(1.into())))
+            .actor(PingPong::Ponger(Ponger))
+            .init_network(Network::new_unordered_nonduplicating([]))
+    }
+
+    #[test]
+    fn exports_deliveries_as_guarded_commands() {
+        assert_eq!(
+            model().to_promela(4).unwrap(),
+            "\
+/* Exported by Stateright from stateright::actor::model::ActorModel<stateright::actor::promela::test::PingPong> (unordered nonduplicating network). */
+
+/* Messages:
+ *   0: Ping
+ *   1: Pong
+ */
+
+chan net_0 = [4] of { byte, byte };
+chan net_1 = [4] of { byte, byte };
+byte st[2];
+
+/* Actor 0 states:
+ *   0: Pinger
+ */
+proctype Actor0() {
+    do
+    :: atomic { st[0] == 0 && net_0??[1, 1] ->
+        net_0??1, 1;
+        st[0] = 0;
+        net_1!0, 0;
+    }
+    od
+}
+
+/* Actor 1 states:
+ *   0: Ponger { replies: 0 }
+ *   1: Ponger { replies: 1 }
+ *   2: Ponger { replies: 2 }
+ */
+proctype Actor1() {
+    do
+    :: atomic { st[1] == 0 && net_1??[0, 0] ->
+        net_1??0, 0;
+        st[1] = 1;
+        net_0!1, 1;
+    }
+    :: atomic { st[1] == 1 && net_1??[0, 0] ->
+        net_1??0, 0;
+        st[1] = 2;
+        net_0!1, 1;
+    }
+    :: atomic { st[1] == 2 && net_1??[0, 0] ->
+        net_1??0, 0;
+        st[1] = 2;
+    }
+    od
+}
+
+init {
+    atomic {
+        net_1!0, 0;
+        st[0] = 0;
+        st[1] = 0;
+        run Actor0();
+        run Actor1();
+    }
+}
+"
+        );
+    }
+
+    #[test]
+    fn exports_network_semantics() {
+        let promela = model()
+            .init_network(Network::new_ordered([]))
+            .to_promela(2)
+            .unwrap();
+        assert!(promela.contains("chan net_0_1 = [2] of { byte };\n"));
+        assert!(promela.contains("chan net_1_0 = [2] of { byte };\n"));
+        assert!(promela.contains(
+            "    :: atomic { st[1] == 0 && net_0_1?[0] ->\n        net_0_1?0;\n        st[1] = 1;\n        net_1_0!1;\n"
+        ));
+
+        let promela = model()
+            .init_network(Network::new_unordered_duplicating([]))
+            .to_promela(2)
+            .unwrap();
+        assert!(promela.contains(
+            "    :: atomic { st[1] == 0 && net_1??[0, 0] ->\n        st[1] = 1;\n        \
+             if :: net_0??[1, 1] -> skip :: else -> net_0!1, 1 fi;\n"
+        ));
+    }
+
+    #[test]
+    fn rejects_unsupported_semantics() {
+        assert_eq!(
+            model().max_crashes(1).to_promela(2),
+            Err("Crashes cannot be exported to Promela.".to_string())
+        );
+        assert_eq!(
+            model().lossy_network(LossyNetwork::Yes).to_promela(2),
+            Err("Lossy networks cannot be exported to Promela.".to_string())
+        );
+
+        struct Counter;
+        impl Actor for Counter {
+            type Msg = ();
+            type State = u32;
+            type Timer = ();
+            fn on_start(&self, id: Id, o: &mut Out<Self>) -> Self::State {
+                o.send(id, ());
+                0
+            }
+            fn on_msg(
+                &self,
+                id: Id,
+                state: &mut Cow<Self::State>,
+                _src: Id,
+                _msg: Self::Msg,
+                o: &mut Out<Self>,
+            ) {
+                o.send(id, ());
+                *state.to_mut() += 1;
+            }
+        }
+        assert_eq!(
+            ActorModel::new((), ()).actor(Counter).to_promela(2),
+            Err("More than 256 states of actor 0 cannot be exported to Promela.".to_string())
+        );
+    }
+}
@@ -0,0 +1,263 @@
+//! Reusable specifications of standard distributed-systems properties for an [`ActorModel`],
+//! parameterized by functions that project the relevant part of each actor's state.
+//!
+//! Add the resulting properties to [`ActorModel::properties`] to check them. The type of each
+//! projection's state parameter may need to be annotated, as it is not inferred from the model.
+//!
+//! # Example
+//!
+//! ```
+//! # use stateright::*;
+//! # use stateright::actor::*;
+//! # use stateright::actor::properties;
+//! # use std::borrow::Cow;
+//! /// Decides upon the first value it receives.
+//! struct Acceptor;
+//! impl Actor for Acceptor {
+//!     type State = Option<char>;
+//!     type Msg = char;
+//!     type Timer = ();
+//!     fn on_start(&self, _id: Id, _o: &mut Out<Self>) -> Self::State {
+//!         None
+//!     }
+//!     fn on_msg(&self, _id: Id, state: &mut Cow<Self::State>, _src: Id, msg: char, _o: &mut Out<Self>) {
+//!         if state.is_none() {
+//!             *state.to_mut() = Some(msg);
+//!         }
+//!     }
+//! }
+//!
+//! let mut model = ActorModel::new((), ())
+//!     .actors(vec![Acceptor, Acceptor])
+//!     .init_network(Network::new_unordered_nonduplicating([
+//!         Envelope { src: Id::from(0), dst: Id::from(0), msg: 'A' },
+//!         Envelope { src: Id::from(0), dst: Id::from(1), msg: 'B' },
+//!     ]));
+//! let decided: properties::Decision<Acceptor, char> = |_id, decided| decided.as_ref();
+//! model.properties.push(properties::agreement("agreement", decided));
+//! model.properties.push(properties::integrity("integrity", decided));
+//! let checker = model.checker().spawn_bfs().join();
+//! checker.assert_no_discovery("integrity");
+//! checker.assert_any_discovery("agreement");
+//! ```
+
+use crate::actor::{Actor, ActorModel, Id};
+use crate::Property;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// The value an actor has decided (or delivered, committed, etc), if any.
+pub type Decision<A, V> = for<'a> fn(Id, &'a <A as Actor>::State) -> Option<&'a V>;
+
+/// An `always` property that holds if all actors that have decided have decided the same value.
+pub fn agreement<A, C, H, V>(
+    name: &'static str,
+    decided: Decision<A, V>,
+) -> Property<ActorModel<A, C, H>>
+where
+    A: Actor + 'static,
+    C: 'static,
+    H: Clone + Debug + Hash + 'static,
+    V: PartialEq + 'static,
+{
+    Property::always(name, move |_: &ActorModel<A, C, H>, state| {
+        let mut decisions = state
+            .actor_states
+            .iter()
+            .enumerate()
+            .filter_map(|(index, actor_state)| decided(Id::from(index), actor_state));
+        match decisions.next() {
+            None => true,
+            Some(first) => decisions.all(|decision| decision == first),
+        }
+    })
+}
+
+/// An `always` property that holds if every decided value is valid, for instance because it was
+/// proposed by a client, as indicated by the model's configuration and recorded history.
+pub fn validity<A, C, H, V>(
+    name: &'static str,
+    decided: Decision<A, V>,
+    is_valid: fn(cfg: &C, history: &H, &V) -> bool,
+) -> Property<ActorModel<A, C, H>>
+where
+    A: Actor + 'static,
+    C: 'static,
+    H: Clone + Debug + Hash + 'static,
+    V: 'static,
+{
+    Property::always(name, move |model: &ActorModel<A, C, H>, state| {
+        state
+            .actor_states
+            .iter()
+            .enumerate()
+            .filter_map(|(index, actor_state)| decided(Id::from(index), actor_state))
+            .all(|decision| is_valid(&model.cfg, &state.history, decision))
+    })
+}
+
+/// A transition property that holds if no actor changes or retracts a decision once made. The
+/// counterexample ends with the step that changes the decision.
+pub fn integrity<A, C, H, V>(
+    name: &'static str,
+    decided: Decision<A, V>,
+) -> Property<ActorModel<A, C, H>>
+where
+    A: Actor + 'static,
+    C: 'static,
+    H: Clone + Debug + Hash + 'static,
+    V: PartialEq + 'static,
+{
+    Property::always_transition(
+        name,
+        move |_: &ActorModel<A, C, H>, state, _, next_state| {
+            state
+                .actor_states
+                .iter()
+                .zip(&next_state.actor_states)
+                .enumerate()
+                .all(|(index, (actor_state, next_actor_state))| {
+                    let id = Id::from(index);
+                    match decided(id, actor_state) {
+                        None => true,
+                        Some(decision) => decided(id, next_actor_state) == Some(decision),
+                    }
+                })
+        },
+    )
+}
+
+/// An `always` property that holds if no actor has delivered the same message twice, given the
+/// messages each actor has delivered.
+pub fn at_most_once_delivery<A, C, H, T>(
+    name: &'static str,
+    delivered: for<'a> fn(Id, &'a A::State) -> &'a [T],
+) -> Property<ActorModel<A, C, H>>
+where
+    A: Actor + 'static,
+    C: 'static,
+    H: Clone + Debug + Hash + 'static,
+    T: Eq + Hash + 'static,
+{
+    Property::always(name, move |_: &ActorModel<A, C, H>, state| {
+        state
+            .actor_states
+            .iter()
+            .enumerate()
+            .all(|(index, actor_state)| {
+                let mut seen = HashSet::new();
+                delivered(Id::from(index), actor_state)
+                    .iter()
+                    .all(|msg| seen.insert(msg))
+            })
+    })
+}
+
+/// A transition property that holds if no actor's sequence number (or term, epoch, clock, etc)
+/// ever decreases. The counterexample ends with the step that decreases it.
+pub fn monotonic_sequence_numbers<A, C, H, S>(
+    name: &'static str,
+    seq: fn(Id, &A::State) -> S,
+) -> Property<ActorModel<A, C, H>>
+where
+    A: Actor + 'static,
+    C: 'static,
+    H: Clone + Debug + Hash + 'static,
+    S: PartialOrd + 'static,
+{
+    Property::always_transition(
+        name,
+        move |_: &ActorModel<A, C, H>, state, _, next_state| {
+            state
+                .actor_states
+                .iter()
+                .zip(&next_state.actor_states)
+                .enumerate()
+                .all(|(index, (actor_state, next_actor_state))| {
+                    let id = Id::from(index);
+                    seq(id, actor_state) <= seq(id, next_actor_state)
+                })
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::actor::{Envelope, Network, Out};
+    use crate::{Checker, Model};
+    use std::borrow::Cow;
+
+    /// Adopts every value it receives, logging each one, which violates most properties.
+    struct Fickle;
+    impl Actor for Fickle {
+        type State = (Option<u8>, Vec<u8>);
+        type Msg = u8;
+        type Timer = ();
+        fn on_start(&self, _id: Id, _o: &mut Out<Self>) -> Self::State {
+            (None, Vec::new())
+        }
+        fn on_msg(
+            &self,
+            _id: Id,
+            state: &mut Cow<Self::State>,
+            _src: Id,
+            msg: Self::Msg,
+            _o: &mut Out<Self>,
+        ) {
+            let state = state.to_mut();
+            state.0 = Some(msg);
+            state.1.push(msg);
+        }
+    }
+
+    fn model(msgs: Vec<(usize, u8)>) -> ActorModel<Fickle, u8> {
+        let envelopes = msgs.into_iter().map(|(dst, msg)| Envelope {
+            src: Id::from(0),
+            dst: Id::from(dst),
+            msg,
+        });
+        let mut model = ActorModel::new(3, ())
+            .actors(vec![Fickle, Fickle])
+            .init_network(Network::new_unordered_duplicating(envelopes));
+        type State = (Option<u8>, Vec<u8>);
+        let decided: Decision<Fickle, u8> = |_, state| state.0.as_ref();
+        model.properties = vec![
+            agreement("agreement", decided),
+            validity("validity", decided, |max, _, decision| decision <= max),
+            integrity("integrity", decided),
+            at_most_once_delivery("at most once", |_, state: &State| &state.1),
+            monotonic_sequence_numbers("monotonic", |_, state: &State| state.0),
+        ];
+        model
+    }
+
+    #[test]
+    fn can_validate() {
+        model(vec![(0, 1), (1, 1)])
+            .within_boundary(|_, state| state.actor_states.iter().all(|s| s.1.len() < 2))
+            .checker()
+            .spawn_bfs()
+            .join()
+            .assert_properties();
+    }
+
+    #[test]
+    fn can_discover_counterexamples() {
+        let checker = model(vec![(0, 2), (0, 1), (1, 5)])
+            .checker()
+            .spawn_bfs()
+            .join();
+        let decisions = |name| {
+            let path = checker.discovery(name).unwrap();
+            let state = path.last_state();
+            state.actor_states.iter().map(|s| s.0).collect::<Vec<_>>()
+        };
+        assert_eq!(decisions("agreement"), vec![Some(2), Some(5)]);
+        assert_eq!(decisions("validity"), vec![None, Some(5)]);
+        assert_eq!(decisions("integrity"), vec![Some(1), None]);
+        assert_eq!(decisions("monotonic"), vec![Some(1), None]);
+        checker.assert_any_discovery("at most once");
+    }
+}
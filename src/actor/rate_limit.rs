@@ -0,0 +1,74 @@
+//! Outgoing rate limiting for the runtime underlying [`spawn`](crate::actor::spawn()). See
+//! [`SpawnBuilder::rate_limit`](crate::actor::SpawnBuilder::rate_limit).
+
+use crate::actor::Id;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Configures a token bucket per destination.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct RateLimit {
+    /// Tokens added to each bucket per second.
+    pub(crate) messages_per_second: f64,
+    /// Bucket capacity, which bounds how many messages can be sent in a burst.
+    pub(crate) burst: f64,
+}
+
+/// Tracks the token buckets for a single sender.
+pub(crate) struct RateLimiter {
+    limit: RateLimit,
+    buckets: HashMap<Id, (f64, Instant)>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Consumes a token for `dst` if one is available, returning `false` if the message should
+    /// be dropped instead.
+    pub(crate) fn try_acquire(&mut self, dst: Id, now: Instant) -> bool {
+        let limit = self.limit;
+        let (tokens, last_refill) = self.buckets.entry(dst).or_insert((limit.burst, now));
+        let elapsed = now.saturating_duration_since(*last_refill).as_secs_f64();
+        *tokens = (*tokens + elapsed * limit.messages_per_second).min(limit.burst);
+        *last_refill = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn limits_each_destination_independently() {
+        let mut limiter = RateLimiter::new(RateLimit {
+            messages_per_second: 10.0,
+            burst: 2.0,
+        });
+        let start = Instant::now();
+        assert!(limiter.try_acquire(Id::from(1), start));
+        assert!(limiter.try_acquire(Id::from(1), start));
+        assert!(!limiter.try_acquire(Id::from(1), start));
+        assert!(limiter.try_acquire(Id::from(2), start));
+
+        // Tokens refill over time, but never beyond the burst size.
+        let later = start + Duration::from_millis(100);
+        assert!(limiter.try_acquire(Id::from(1), later));
+        assert!(!limiter.try_acquire(Id::from(1), later));
+        let much_later = start + Duration::from_secs(60);
+        assert!(limiter.try_acquire(Id::from(1), much_later));
+        assert!(limiter.try_acquire(Id::from(1), much_later));
+        assert!(!limiter.try_acquire(Id::from(1), much_later));
+    }
+}
@@ -1,38 +1,23 @@
 //! Private module for selective re-export.
 
+use crate::actor::metrics::serve_metrics;
+use crate::actor::persistence::StateStore;
+use crate::actor::rate_limit::{RateLimit, RateLimiter};
 use crate::actor::*;
 use crossbeam_utils::thread;
+use parking_lot::Mutex;
 use std::collections::HashMap;
 use std::fmt::Debug;
-use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::future::Future;
+use std::net::{SocketAddr, SocketAddrV4, ToSocketAddrs, UdpSocket};
+use std::panic::AssertUnwindSafe;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
 use std::time::{Duration, Instant};
 
-impl From<Id> for SocketAddrV4 {
-    fn from(id: Id) -> Self {
-        let bytes = id.0.to_be_bytes();
-        let ip = Ipv4Addr::from([bytes[2], bytes[3], bytes[4], bytes[5]]);
-        let port = u16::from_be_bytes([bytes[6], bytes[7]]);
-        SocketAddrV4::new(ip, port)
-    }
-}
-
-impl From<SocketAddrV4> for Id {
-    fn from(addr: SocketAddrV4) -> Self {
-        let octets = addr.ip().octets();
-        let port_bytes = addr.port().to_be_bytes();
-        let mut result: [u8; 8] = [0; 8];
-        result[0] = 0;
-        result[1] = 0;
-        result[2] = octets[0];
-        result[3] = octets[1];
-        result[4] = octets[2];
-        result[5] = octets[3];
-        result[6] = port_bytes[0];
-        result[7] = port_bytes[1];
-        Id(u64::from_be_bytes(result))
-    }
-}
-
 /// 500 years in the future.
 fn practically_never() -> Instant {
     Instant::now() + Duration::from_secs(3600 * 24 * 365 * 500)
@@ -71,96 +56,741 @@ where
     A::Msg: Debug,
     A::State: Debug,
 {
-    thread::scope(|s| {
+    SpawnBuilder::new(serialize, deserialize)
+        .actors(actors)
+        .spawn()
+}
+
+/// A builder for running actors over UDP, for cases that need more control than [`spawn`]
+/// offers, such as exposing [`RuntimeMetrics`] over HTTP.
+///
+/// # Example
+///
+/// ```no_run
+/// use stateright::actor::{Id, SpawnBuilder};
+/// use std::net::{Ipv4Addr, SocketAddrV4};
+/// # mod serde_json {
+/// #     pub fn to_vec(_: &()) -> Result<Vec<u8>, ()> { Ok(vec![]) }
+/// #     pub fn from_slice(_: &[u8]) -> Result<(), ()> { Ok(()) }
+/// # }
+/// # let actor1 = ();
+/// # let actor2 = ();
+/// let id1 = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 3001));
+/// let id2 = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 3002));
+/// SpawnBuilder::new(serde_json::to_vec, |bytes| serde_json::from_slice(bytes))
+///     .actor(id1, actor1)
+///     .actor(id2, actor2)
+///     .serve_metrics("localhost:9100")
+///     .spawn();
+/// ```
+#[must_use = "This code constructs a builder. Consider calling spawn()."]
+pub struct SpawnBuilder<A: Actor, E> {
+    serialize: fn(&A::Msg) -> Result<Vec<u8>, E>,
+    deserialize: fn(&[u8]) -> Result<A::Msg, E>,
+    actors: Vec<(Id, A)>,
+    metrics: Arc<RuntimeMetrics>,
+    metrics_addresses: Option<Vec<SocketAddr>>,
+    store: Option<Arc<StateStore<A::State, E>>>,
+    rate_limit: Option<RateLimit>,
+    assertion_policy: AssertionPolicy,
+}
+
+/// How the runtime responds when an actor fails an assertion. See [`Out::assert`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AssertionPolicy {
+    /// Log the failure as an error and continue running the actor.
+    #[default]
+    Log,
+    /// Panic, which terminates the actor.
+    Panic,
+}
+
+impl<A: Actor, E> SpawnBuilder<A, E> {
+    /// Instantiates a builder that will use the specified functions to convert messages to and
+    /// from bytes.
+    pub fn new(
+        serialize: fn(&A::Msg) -> Result<Vec<u8>, E>,
+        deserialize: fn(&[u8]) -> Result<A::Msg, E>,
+    ) -> Self {
+        Self {
+            serialize,
+            deserialize,
+            actors: Vec::new(),
+            metrics: Default::default(),
+            metrics_addresses: None,
+            store: None,
+            rate_limit: None,
+            assertion_policy: AssertionPolicy::default(),
+        }
+    }
+
+    /// Adds another [`Actor`], which will bind to the socket address encoded by its [`Id`].
+    pub fn actor(mut self, id: impl Into<Id>, actor: A) -> Self {
+        self.actors.push((id.into(), actor));
+        self
+    }
+
+    /// Adds multiple [`Actor`]s.
+    pub fn actors(mut self, actors: impl IntoIterator<Item = (impl Into<Id>, A)>) -> Self {
         for (id, actor) in actors {
-            let id = id.into();
-            let addr = SocketAddrV4::from(id);
+            self.actors.push((id.into(), actor));
+        }
+        self
+    }
+
+    /// Serves [`RuntimeMetrics`] at `GET /metrics` in the [Prometheus text
+    /// format](https://prometheus.io/docs/instrumenting/exposition_formats/), so long-running
+    /// clusters can be graphed with standard tooling. Panics if the address cannot be resolved.
+    pub fn serve_metrics(self, addresses: impl ToSocketAddrs) -> Self {
+        Self {
+            metrics_addresses: Some(
+                addresses
+                    .to_socket_addrs()
+                    .expect("unable to resolve metrics address")
+                    .collect(),
+            ),
+            ..self
+        }
+    }
+
+    /// Durably snapshots each actor's state to a file in `dir` after every transition that
+    /// changes it, using the specified functions to convert states to and from bytes. Snapshots
+    /// are written before the transition's messages are sent.
+    ///
+    /// An actor with a snapshot resumes from that state when it (re)starts rather than calling
+    /// [`Actor::on_start`], and [`Actor::on_recover`] then sets its timers again. Use
+    /// [`ActorModel::max_recoveries`] to model check this recovery path. Panics if
+    /// the directory cannot be created. An actor whose snapshot exists but cannot be read or
+    /// parsed panics rather than starting afresh, and an actor that cannot write a snapshot
+    /// panics before sending the transition's messages.
+    pub fn persist_states(
+        self,
+        dir: impl Into<PathBuf>,
+        serialize_state: fn(&A::State) -> Result<Vec<u8>, E>,
+        deserialize_state: fn(&[u8]) -> Result<A::State, E>,
+    ) -> Self
+    where
+        E: Debug,
+    {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).expect("unable to create state directory");
+        Self {
+            store: Some(Arc::new(StateStore::new(
+                dir,
+                serialize_state,
+                deserialize_state,
+            ))),
+            ..self
+        }
+    }
+
+    /// Limits how quickly each actor sends messages to any single destination, so that
+    /// resend-heavy protocols cannot saturate a link. Each sender maintains a token bucket per
+    /// destination that holds up to `burst` messages and refills at `messages_per_second`.
+    /// Messages that exceed the limit are dropped and counted as [`DropReason::RateLimit`] in the
+    /// [`RuntimeMetrics`].
+    pub fn rate_limit(self, messages_per_second: f64, burst: u32) -> Self {
+        assert!(
+            messages_per_second > 0.0,
+            "messages_per_second must be positive"
+        );
+        assert!(burst > 0, "burst must be positive");
+        Self {
+            rate_limit: Some(RateLimit {
+                messages_per_second,
+                burst: burst as f64,
+            }),
+            ..self
+        }
+    }
+
+    /// Indicates how to respond when an actor fails an assertion. Failures are logged by default.
+    pub fn assertion_policy(self, assertion_policy: AssertionPolicy) -> Self {
+        Self {
+            assertion_policy,
+            ..self
+        }
+    }
+
+    /// Returns a handle to the metrics that the runtime maintains for the spawned actors.
+    pub fn metrics(&self) -> Arc<RuntimeMetrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Runs the actors, sending messages over UDP. Blocks the current thread.
+    pub fn spawn(self) -> Result<(), Box<dyn std::any::Any + Send + 'static>>
+    where
+        A: 'static + Send,
+        A::Msg: Debug,
+        A::State: Debug,
+        E: Debug + 'static,
+    {
+        if let Some(addresses) = self.metrics_addresses {
+            serve_metrics(Arc::clone(&self.metrics), addresses);
+        }
+        let serialize = self.serialize;
+        let deserialize = self.deserialize;
+        let metrics = self.metrics;
+        let store = self.store.as_deref();
+        let rate_limit = self.rate_limit;
+        let assertion_policy = self.assertion_policy;
+        thread::scope(|s| {
+            for (id, actor) in self.actors {
+                let metrics = metrics.register(id);
+
+                // note that panics are returned as `Err` when `join`ing
+                s.spawn(move |_| {
+                    run_actor(
+                        id,
+                        actor,
+                        serialize,
+                        deserialize,
+                        &(),
+                        &metrics,
+                        store,
+                        rate_limit,
+                        assertion_policy,
+                    )
+                });
+            }
+        })
+    }
+
+    /// Runs the actors, sending messages over UDP, without blocking the current thread. Returns
+    /// an [`ActorFuture`] per actor that resolves once the actor terminates, which allows actor
+    /// lifetimes to be composed with `select`, timeouts, and async test frameworks.
+    pub fn spawn_async(self) -> Vec<ActorFuture>
+    where
+        A: 'static + Send,
+        A::Msg: Debug,
+        A::State: Debug,
+        E: Debug + 'static,
+    {
+        if let Some(addresses) = self.metrics_addresses {
+            serve_metrics(Arc::clone(&self.metrics), addresses);
+        }
+        let serialize = self.serialize;
+        let deserialize = self.deserialize;
+        let rate_limit = self.rate_limit;
+        let assertion_policy = self.assertion_policy;
+        let mut futures = Vec::with_capacity(self.actors.len());
+        for (id, actor) in self.actors {
+            let metrics = self.metrics.register(id);
+            let shared = Arc::new(ActorFutureShared::default());
+            let store = self.store.clone();
+            futures.push(ActorFuture {
+                id,
+                shared: Arc::clone(&shared),
+            });
+            std::thread::spawn(move || {
+                let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                    run_actor(
+                        id,
+                        actor,
+                        serialize,
+                        deserialize,
+                        &*shared,
+                        &metrics,
+                        store.as_deref(),
+                        rate_limit,
+                        assertion_policy,
+                    )
+                }));
+                let waker = {
+                    let mut completion = shared.completion.lock();
+                    completion.result = Some(result);
+                    completion.is_terminated = true;
+                    completion.waker.take()
+                };
+                if let Some(waker) = waker {
+                    waker.wake();
+                }
+            });
+        }
+        futures
+    }
+}
+
+/// Runs actors without blocking the current thread. See [`SpawnBuilder::spawn_async`].
+///
+/// # Example
+///
+/// ```no_run
+/// use stateright::actor::{Id, spawn_async};
+/// use std::net::{Ipv4Addr, SocketAddrV4};
+/// # mod serde_json {
+/// #     pub fn to_vec(_: &()) -> Result<Vec<u8>, ()> { Ok(vec![]) }
+/// #     pub fn from_slice(_: &[u8]) -> Result<(), ()> { Ok(()) }
+/// # }
+/// # let actor1 = ();
+/// let id1 = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 3001));
+/// let futures = spawn_async(
+///     serde_json::to_vec,
+///     |bytes| serde_json::from_slice(bytes),
+///     vec![(id1, actor1)]);
+/// for f in &futures {
+///     f.stop();
+/// }
+/// # let _ = futures;
+/// ```
+pub fn spawn_async<A, E: Debug + 'static>(
+    serialize: fn(&A::Msg) -> Result<Vec<u8>, E>,
+    deserialize: fn(&[u8]) -> Result<A::Msg, E>,
+    actors: Vec<(impl Into<Id>, A)>,
+) -> Vec<ActorFuture>
+where
+    A: 'static + Send + Actor,
+    A::Msg: Debug,
+    A::State: Debug,
+{
+    SpawnBuilder::new(serialize, deserialize)
+        .actors(actors)
+        .spawn_async()
+}
+
+/// A [`Future`] that resolves when a spawned actor terminates, either because it was
+/// [stopped](ActorFuture::stop) (`Ok`) or because it panicked (`Err` with the panic payload).
+/// Dropping the future does not stop the actor.
+pub struct ActorFuture {
+    id: Id,
+    shared: Arc<ActorFutureShared>,
+}
+
+#[derive(Default)]
+struct ActorFutureShared {
+    is_stopped: AtomicBool,
+    completion: Mutex<ActorCompletion>,
+}
+
+#[derive(Default)]
+struct ActorCompletion {
+    result: Option<Result<(), Box<dyn std::any::Any + Send + 'static>>>,
+    waker: Option<Waker>,
+    // Remains set after `poll` takes the result.
+    is_terminated: bool,
+}
+
+impl ActorFuture {
+    /// The ID of the actor whose termination this future awaits.
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    /// Requests that the actor stop, after which the future resolves to `Ok(())`.
+    pub fn stop(&self) {
+        self.shared.is_stopped.store(true, Ordering::SeqCst);
+    }
+
+    /// Indicates whether the actor has terminated. Once it has, polling the future after it
+    /// resolves returns [`Poll::Pending`].
+    pub fn is_terminated(&self) -> bool {
+        self.shared.completion.lock().is_terminated
+    }
+}
+
+impl Future for ActorFuture {
+    type Output = Result<(), Box<dyn std::any::Any + Send + 'static>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut completion = self.shared.completion.lock();
+        match completion.result.take() {
+            Some(result) => Poll::Ready(result),
+            None if completion.is_terminated => Poll::Pending,
+            None => {
+                completion.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Debug for ActorFuture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ActorFuture")
+            .field("id", &self.id)
+            .field("is_terminated", &self.is_terminated())
+            .finish()
+    }
+}
+
+impl<Msg> RuntimeHooks<Msg> for ActorFutureShared {
+    fn poll_interval(&self) -> Option<Duration> {
+        Some(Duration::from_millis(10))
+    }
+
+    fn status(&self, _id: Id) -> RuntimeStatus {
+        if self.is_stopped.load(Ordering::SeqCst) {
+            RuntimeStatus::Stopped
+        } else {
+            RuntimeStatus::Running
+        }
+    }
+}
+
+/// Indicates how the runtime should treat an actor. See [`RuntimeHooks::status`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum RuntimeStatus {
+    /// The actor processes messages and timeouts.
+    Running,
+    /// The actor stops processing events, which queue up until it runs again.
+    Paused,
+    /// The actor loses its state and timers. Incoming messages are discarded. The actor restarts
+    /// via [`Actor::on_start`] once it is running again.
+    Crashed,
+    /// The actor thread exits.
+    Stopped,
+}
+
+/// Crate-internal extension points for the runtime underlying [`spawn`], which enable the
+/// [`harness`] to inject faults and observe message flows. `()` provides the default behavior.
+///
+/// [`harness`]: crate::actor::harness
+pub(crate) trait RuntimeHooks<Msg>: Sync {
+    /// Bounds how long the runtime blocks before consulting [`RuntimeHooks::status`] again.
+    fn poll_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Indicates how the runtime should treat the specified actor.
+    fn status(&self, _id: Id) -> RuntimeStatus {
+        RuntimeStatus::Running
+    }
+
+    /// Indicates whether messages can currently flow from `src` to `dst`.
+    fn is_linked(&self, _src: Id, _dst: Id) -> bool {
+        true
+    }
+
+    /// Called when an actor sends a message, even if the message is later lost.
+    fn on_send(&self, _envelope: Envelope<&Msg>) {}
+
+    /// Called when a message is delivered to an actor.
+    fn on_deliver(&self, _envelope: Envelope<&Msg>) {}
+}
+
+impl<Msg> RuntimeHooks<Msg> for () {}
+
+/// Runs a single actor on the current thread until [`RuntimeHooks::status`] indicates that it
+/// has stopped.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn run_actor<A, E, R>(
+    id: Id,
+    actor: A,
+    serialize: fn(&A::Msg) -> Result<Vec<u8>, E>,
+    deserialize: fn(&[u8]) -> Result<A::Msg, E>,
+    hooks: &R,
+    metrics: &ActorMetrics,
+    store: Option<&StateStore<A::State, E>>,
+    rate_limit: Option<RateLimit>,
+    assertion_policy: AssertionPolicy,
+) where
+    A: Actor,
+    A::Msg: Debug,
+    A::State: Debug,
+    E: Debug,
+    R: RuntimeHooks<A::Msg>,
+{
+    let addr = SocketAddrV4::from(id);
+    let socket = UdpSocket::bind(addr).unwrap(); // panic if unable to bind
+    let mut in_buf = [0; 65_535];
+    let mut next_interrupts = HashMap::new();
+    let mut rate_limiter = rate_limit.map(RateLimiter::new);
 
-            // note that panics are returned as `Err` when `join`ing
-            s.spawn(move |_| {
-                let socket = UdpSocket::bind(addr).unwrap(); // panic if unable to bind
-                let mut in_buf = [0; 65_535];
-                let mut next_interrupts = HashMap::new();
+    let mut out = Out::new();
+    let mut state = start_or_recover(id, &actor, store, &mut out);
+    metrics.on_state_change();
+    log::info!(
+        "Actor started. id={}, state={:?}, out={:?}",
+        addr,
+        state,
+        out
+    );
+    on_failed_assertions(id, &mut out, assertion_policy);
+    for c in out {
+        on_command::<A, E, R>(
+            id,
+            c,
+            serialize,
+            &socket,
+            &mut next_interrupts,
+            hooks,
+            metrics,
+            rate_limiter.as_mut(),
+        );
+    }
 
+    let mut is_crashed = false;
+    loop {
+        match hooks.status(id) {
+            RuntimeStatus::Running if is_crashed => {
+                is_crashed = false;
+                next_interrupts.clear();
+                metrics.on_restart();
                 let mut out = Out::new();
-                let mut state = Cow::Owned(actor.on_start(id, &mut out));
-                log::info!("Actor started. id={}, state={:?}, out={:?}", addr, state, out);
+                state = start_or_recover(id, &actor, store, &mut out);
+                metrics.on_state_change();
+                log::info!(
+                    "Actor restarted. id={}, state={:?}, out={:?}",
+                    addr,
+                    state,
+                    out
+                );
+                on_failed_assertions(id, &mut out, assertion_policy);
                 for c in out {
-                    on_command::<A, E>(addr, c, serialize, &socket, &mut next_interrupts);
+                    on_command::<A, E, R>(
+                        id,
+                        c,
+                        serialize,
+                        &socket,
+                        &mut next_interrupts,
+                        hooks,
+                        metrics,
+                        rate_limiter.as_mut(),
+                    );
                 }
+            }
+            RuntimeStatus::Running => {}
+            RuntimeStatus::Paused => {
+                std::thread::sleep(hooks.poll_interval().unwrap_or(Duration::from_millis(10)));
+                continue;
+            }
+            RuntimeStatus::Crashed => {
+                if !is_crashed {
+                    log::info!("Actor crashed. id={}", addr);
+                    is_crashed = true;
+                    next_interrupts.clear();
+                    metrics.on_crash();
+                }
+                // Discard anything sent to the crashed actor.
+                socket
+                    .set_read_timeout(Some(
+                        hooks.poll_interval().unwrap_or(Duration::from_millis(10)),
+                    ))
+                    .expect("set_read_timeout failed");
+                let _ = socket.recv_from(&mut in_buf);
+                continue;
+            }
+            RuntimeStatus::Stopped => {
+                log::info!("Actor stopped. id={}", addr);
+                return;
+            }
+        }
 
-                loop {
-                    // Apply an interrupt if present, otherwise wait for a message.
-                    let mut out = Out::new();
-                    let (min_timer, min_instant) = next_interrupts
-                        .iter()
-                        .min_by_key(|(_, instant)| *instant)
-                        .map(|(t, i)| (Some(t.clone()), *i))
-                        .unwrap_or_else(|| (None, practically_never()));
-                    if let Some(max_wait) = min_instant.checked_duration_since(Instant::now()) {
-                        socket.set_read_timeout(Some(max_wait)).expect("set_read_timeout failed");
-                        match socket.recv_from(&mut in_buf) {
-                            Err(e) => {
-                                // Timeout (`WouldBlock`) ignored since next iteration will apply interrupt.
-                                if e.kind() != std::io::ErrorKind::WouldBlock {
-                                    log::warn!("Unable to read socket. Ignoring. id={}, err={:?}", addr, e);
+        // Only pay to format the state if it is being inspected.
+        metrics.refresh_state(|| format_state(&actor, &state));
+
+        // Apply an interrupt if present, otherwise wait for a message.
+        let mut out = Out::new();
+        let mut next_state = Cow::Borrowed(&state);
+        let (min_timer, min_instant) = next_interrupts
+            .iter()
+            .min_by_key(|(_, instant)| *instant)
+            .map(|(t, i)| (Some(t.clone()), *i))
+            .unwrap_or_else(|| (None, practically_never()));
+        if let Some(max_wait) = min_instant.checked_duration_since(Instant::now()) {
+            let max_wait = match hooks.poll_interval() {
+                Some(poll_interval) if poll_interval < max_wait => poll_interval,
+                _ => max_wait,
+            };
+            socket
+                .set_read_timeout(Some(max_wait))
+                .expect("set_read_timeout failed");
+            match socket.recv_from(&mut in_buf) {
+                Err(e) => {
+                    // Timeout (`WouldBlock`) ignored since next iteration will apply interrupt.
+                    if e.kind() != std::io::ErrorKind::WouldBlock {
+                        log::warn!("Unable to read socket. Ignoring. id={}, err={:?}", addr, e);
+                    }
+                    continue;
+                }
+                Ok((count, src_addr)) => {
+                    match deserialize(&in_buf[..count]) {
+                        Ok(msg) => {
+                            if let SocketAddr::V4(src_addr) = src_addr {
+                                let src = Id::from(src_addr);
+                                if !hooks.is_linked(src, id) {
+                                    log::debug!("Received message across a partition. Ignoring. id={}, src={}, msg={:?}",
+                                                addr, src_addr, msg);
+                                    metrics.on_drop(DropReason::Partition);
+                                    continue;
                                 }
+                                log::info!(
+                                    "Received message. id={}, src={}, msg={}",
+                                    addr,
+                                    src_addr,
+                                    format!("{:?}", msg)
+                                );
+                                metrics.on_receive();
+                                hooks.on_deliver(Envelope {
+                                    src,
+                                    dst: id,
+                                    msg: &msg,
+                                });
+                                actor.on_msg(id, &mut next_state, src, msg, &mut out);
+                            } else {
+                                log::debug!(
+                                    "Received non-IPv4 message. Ignoring. id={}, src={}, msg={}",
+                                    addr,
+                                    src_addr,
+                                    format!("{:?}", msg)
+                                );
                                 continue;
-                            },
-                            Ok((count, src_addr)) => {
-                                match deserialize(&in_buf[..count]) {
-                                    Ok(msg) => {
-                                        if let SocketAddr::V4(src_addr) = src_addr {
-                                            log::info!("Received message. id={}, src={}, msg={}",
-                                                        addr, src_addr, format!("{:?}", msg));
-                                            actor.on_msg(id, &mut state, Id::from(src_addr), msg, &mut out);
-                                        } else {
-                                            log::debug!("Received non-IPv4 message. Ignoring. id={}, src={}, msg={}",
-                                                       addr, src_addr, format!("{:?}", msg));
-                                            continue;
-                                        }
-                                    },
-                                    Err(e) => {
-                                        log::debug!("Unable to parse message. Ignoring. id={}, src={}, buf={:?}, err={:?}",
-                                                   addr, src_addr, &in_buf[..count], e);
-                                        continue;
-                                    }
-                                }
-                            },
+                            }
+                        }
+                        Err(e) => {
+                            log::debug!("Unable to parse message. Ignoring. id={}, src={}, buf={:?}, err={:?}",
+                                       addr, src_addr, &in_buf[..count], e);
+                            metrics.on_drop(DropReason::Deserialize);
+                            continue;
                         }
-                    } else {
-                        let min_timer = min_timer.unwrap();
-                        next_interrupts.remove(&min_timer); // timer is no longer valid
-                        actor.on_timeout(id, &mut state, &min_timer, &mut out);
-                    }
-
-                    // Handle commands and update state.
-                    if !is_no_op(&state, &out) {
-                        log::debug!("Acted. id={}, state={:?}, out={:?}",
-                                    addr, state, out);
                     }
-                    for c in out { on_command::<A, E>(addr, c, serialize, &socket, &mut next_interrupts); }
                 }
-            });
+            }
+        } else {
+            let min_timer = min_timer.unwrap();
+            next_interrupts.remove(&min_timer); // timer is no longer valid
+            metrics.on_timeout();
+            actor.on_timeout(id, &mut next_state, &min_timer, &mut out);
+        }
+
+        // Handle commands and update state.
+        if !is_no_op(&next_state, &out) {
+            log::debug!("Acted. id={}, state={:?}, out={:?}", addr, next_state, out);
         }
-    })
+        if let Cow::Owned(next_state) = next_state {
+            if let Some(store) = store {
+                save_or_panic(id, store, &next_state);
+            }
+            metrics.on_state_change();
+            state = next_state;
+        }
+        on_failed_assertions(id, &mut out, assertion_policy);
+        for c in out {
+            on_command::<A, E, R>(
+                id,
+                c,
+                serialize,
+                &socket,
+                &mut next_interrupts,
+                hooks,
+                metrics,
+                rate_limiter.as_mut(),
+            );
+        }
+    }
+}
+
+/// Formats a state for [`ActorMetrics::state`].
+fn format_state<A>(actor: &A, state: &A::State) -> String
+where
+    A: Actor,
+    A::State: Debug,
+{
+    actor
+        .format_state(state)
+        .unwrap_or_else(|| format!("{:?}", state))
+}
+
+/// Resumes from the last persisted state if there is one, otherwise starts the actor afresh.
+/// Panics if a snapshot exists but cannot be loaded.
+fn start_or_recover<A, E>(
+    id: Id,
+    actor: &A,
+    store: Option<&StateStore<A::State, E>>,
+    o: &mut Out<A>,
+) -> A::State
+where
+    A: Actor,
+    E: Debug,
+{
+    let store = match store {
+        None => return actor.on_start(id, o),
+        Some(store) => store,
+    };
+    let loaded = store.load(id).unwrap_or_else(|e| {
+        panic!(
+            "Unable to load persisted state. Refusing to start actor. id={}, path={:?}, err={}",
+            SocketAddrV4::from(id),
+            store.path(id),
+            e
+        )
+    });
+    if let Some(state) = loaded {
+        log::info!(
+            "Actor recovered persisted state. id={}",
+            SocketAddrV4::from(id)
+        );
+        actor.on_recover(id, &state, o);
+        return state;
+    }
+    let state = actor.on_start(id, o);
+    save_or_panic(id, store, &state);
+    state
+}
+
+/// Snapshots a state before the transition's messages are sent. Panics if the snapshot cannot be
+/// written, which stops the actor, as sending would reveal a state that might not survive a
+/// restart.
+fn save_or_panic<State, E: Debug>(id: Id, store: &StateStore<State, E>, state: &State) {
+    if let Err(e) = store.save(id, state) {
+        panic!(
+            "Unable to persist state. Stopping actor. id={}, path={:?}, err={}",
+            SocketAddrV4::from(id),
+            store.path(id),
+            e
+        );
+    }
 }
 
 /// The effect to perform in response to spawned actor outputs.
-fn on_command<A, E>(
-    addr: SocketAddrV4,
+#[allow(clippy::too_many_arguments)]
+fn on_command<A, E, R>(
+    id: Id,
     command: Command<A::Msg, A::Timer>,
     serialize: fn(&A::Msg) -> Result<Vec<u8>, E>,
     socket: &UdpSocket,
     next_interrupts: &mut HashMap<A::Timer, Instant>,
+    hooks: &R,
+    metrics: &ActorMetrics,
+    rate_limiter: Option<&mut RateLimiter>,
 ) where
     A: Actor,
     A::Msg: Debug,
     E: Debug,
+    R: RuntimeHooks<A::Msg>,
 {
+    let addr = SocketAddrV4::from(id);
     match command {
         Command::Send(dst, msg) => {
             let dst_addr = SocketAddrV4::from(dst);
+            hooks.on_send(Envelope {
+                src: id,
+                dst,
+                msg: &msg,
+            });
+            metrics.on_send();
+            if !hooks.is_linked(id, dst) {
+                log::debug!(
+                    "Unable to send across a partition. Ignoring. src={}, dst={}, msg={:?}",
+                    addr,
+                    dst_addr,
+                    msg
+                );
+                metrics.on_drop(DropReason::Partition);
+                return;
+            }
+            if let Some(rate_limiter) = rate_limiter {
+                if !rate_limiter.try_acquire(dst, Instant::now()) {
+                    log::debug!(
+                        "Rate limit exceeded. Ignoring. src={}, dst={}, msg={:?}",
+                        addr,
+                        dst_addr,
+                        msg
+                    );
+                    metrics.on_drop(DropReason::RateLimit);
+                    return;
+                }
+            }
             match serialize(&msg) {
                 Err(e) => {
                     log::warn!(
@@ -170,6 +800,7 @@ fn on_command<A, E>(
                         msg,
                         e
                     );
+                    metrics.on_drop(DropReason::Serialize);
                 }
                 Ok(out_buf) => {
                     if let Err(e) = socket.send_to(&out_buf, dst_addr) {
@@ -180,6 +811,7 @@ fn on_command<A, E>(
                             msg,
                             e
                         );
+                        metrics.on_drop(DropReason::Send);
                     }
                 }
             }
@@ -205,10 +837,30 @@ fn on_command<A, E>(
     }
 }
 
+/// Logs or panics per the [`AssertionPolicy`] for each assertion that a spawned actor failed.
+fn on_failed_assertions<A: Actor>(id: Id, out: &mut Out<A>, assertion_policy: AssertionPolicy) {
+    let addr = SocketAddrV4::from(id);
+    for label in out.drain_failed_assertions() {
+        match assertion_policy {
+            AssertionPolicy::Log => {
+                log::error!("Assertion failed. id={}, label={}", addr, label);
+            }
+            AssertionPolicy::Panic => {
+                panic!("Assertion failed. id={}, label={}", addr, label);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::actor::*;
+    use std::future::Future;
     use std::net::{Ipv4Addr, SocketAddrV4};
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+    use std::time::Duration;
 
     #[test]
     fn can_encode_id() {
@@ -221,4 +873,203 @@ mod test {
         let addr = SocketAddrV4::new(Ipv4Addr::new(1, 2, 3, 4), 5);
         assert_eq!(SocketAddrV4::from(Id::from(addr)), addr);
     }
+
+    struct Idle {
+        should_panic: bool,
+    }
+
+    impl Actor for Idle {
+        type Msg = ();
+        type State = ();
+        type Timer = ();
+
+        fn on_start(&self, _id: Id, _o: &mut Out<Self>) -> Self::State {
+            if self.should_panic {
+                panic!("unable to start");
+            }
+        }
+    }
+
+    struct ThreadWaker(std::thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let waker = Arc::new(ThreadWaker(std::thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn spawn_async_resolves_on_termination() {
+        let id = |port| Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port));
+        let mut futures = spawn_async(
+            |_: &()| Ok::<_, ()>(Vec::new()),
+            |_| Ok(()),
+            vec![
+                (
+                    id(31_021),
+                    Idle {
+                        should_panic: false,
+                    },
+                ),
+                (id(31_022), Idle { should_panic: true }),
+            ],
+        );
+        let crashed = futures.pop().unwrap();
+        let stopped = futures.pop().unwrap();
+        assert_eq!(crashed.id(), id(31_022));
+        assert!(block_on(crashed).is_err());
+
+        assert!(!stopped.is_terminated());
+        stopped.stop();
+        assert!(block_on(stopped).is_ok());
+    }
+
+    #[test]
+    fn spawn_async_remains_terminated_after_resolving() {
+        let id = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 31_023));
+        let mut future = spawn_async(
+            |_: &()| Ok::<_, ()>(Vec::new()),
+            |_| Ok(()),
+            vec![(id, Idle { should_panic: true })],
+        )
+        .pop()
+        .unwrap();
+        assert!(block_on(&mut future).is_err());
+        assert!(future.is_terminated());
+
+        let waker = Arc::new(ThreadWaker(std::thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+        assert!(future.is_terminated());
+    }
+
+    struct Ticker;
+
+    impl Actor for Ticker {
+        type Msg = ();
+        type State = u32;
+        type Timer = ();
+
+        fn on_start(&self, _id: Id, o: &mut Out<Self>) -> Self::State {
+            o.set_timer((), Duration::ZERO..Duration::from_millis(1));
+            0
+        }
+
+        fn on_timeout(
+            &self,
+            _id: Id,
+            state: &mut Cow<Self::State>,
+            _timer: &Self::Timer,
+            _o: &mut Out<Self>,
+        ) {
+            *state.to_mut() += 1;
+        }
+    }
+
+    #[test]
+    fn persisted_state_survives_respawn() {
+        let dir = std::env::temp_dir().join(format!("stateright-spawn-{}", std::process::id()));
+        let id = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 31_031));
+        let snapshot = dir.join("127.0.0.1_31031.state");
+        let spawn = || {
+            SpawnBuilder::new(|_: &()| Ok::<_, String>(Vec::new()), |_| Ok(()))
+                .actor(id, Ticker)
+                .persist_states(
+                    &dir,
+                    |s| Ok(s.to_string().into_bytes()),
+                    |b| {
+                        String::from_utf8_lossy(b)
+                            .parse()
+                            .map_err(|e: std::num::ParseIntError| e.to_string())
+                    },
+                )
+                .spawn_async()
+                .pop()
+                .unwrap()
+        };
+
+        let future = spawn();
+        while std::fs::read(&snapshot).ok().as_deref() != Some(b"1") {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        future.stop();
+        assert!(block_on(future).is_ok());
+
+        // The restarted actor resumes from its snapshot instead of calling `on_start`, and
+        // `on_recover` sets the timer again.
+        std::fs::write(&snapshot, b"5").unwrap();
+        let future = spawn();
+        while std::fs::read(&snapshot).ok().as_deref() != Some(b"6") {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        future.stop();
+        assert!(block_on(future).is_ok());
+
+        // A corrupt snapshot stops the actor rather than silently discarding its state.
+        std::fs::write(&snapshot, b"corrupt").unwrap();
+        assert!(block_on(spawn()).is_err());
+        assert_eq!(std::fs::read(&snapshot).unwrap(), b"corrupt");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    struct Flooder(Id);
+
+    impl Actor for Flooder {
+        type Msg = ();
+        type State = ();
+        type Timer = ();
+
+        fn on_start(&self, _id: Id, o: &mut Out<Self>) -> Self::State {
+            for _ in 0..10 {
+                o.send(self.0, ());
+            }
+        }
+    }
+
+    #[test]
+    fn rate_limit_drops_excess_messages() {
+        let id = |port| Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port));
+        let builder = SpawnBuilder::new(|_: &()| Ok::<_, ()>(Vec::new()), |_| Ok(()))
+            .actor(id(31_041), Flooder(id(31_042)))
+            .rate_limit(1.0, 3);
+        let metrics = builder.metrics();
+        let future = builder.spawn_async().pop().unwrap();
+        while metrics.actor(id(31_041)).map(|m| m.messages_sent()) != Some(10) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        future.stop();
+        assert!(block_on(future).is_ok());
+
+        let metrics = metrics.actor(id(31_041)).unwrap();
+        assert_eq!(metrics.messages_dropped(DropReason::RateLimit), 7);
+        assert_eq!(metrics.messages_dropped(DropReason::Send), 0);
+    }
+
+    #[test]
+    fn metrics_expose_latest_state() {
+        let id = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 31_051));
+        let builder =
+            SpawnBuilder::new(|_: &()| Ok::<_, ()>(Vec::new()), |_| Ok(())).actor(id, Ticker);
+        let metrics = builder.metrics();
+        metrics.record_states();
+        let future = builder.spawn_async().pop().unwrap();
+        while metrics.actor(id).and_then(|m| m.state()) != Some("1".to_string()) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        future.stop();
+        assert!(block_on(future).is_ok());
+    }
 }
@@ -0,0 +1,187 @@
+//! Private module for rendering the communication topology of an actor system.
+
+use crate::actor::{Actor, ActorModel, ActorModelAction, Id};
+use crate::{fingerprint, Model, Path};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::fmt::{Debug, Write};
+use std::hash::Hash;
+
+impl<A, C, H> ActorModel<A, C, H>
+where
+    A: Actor,
+    H: Clone + Debug + Hash,
+{
+    /// Explores up to `max_states` states breadth-first and renders a
+    /// [Graphviz](https://graphviz.org/) DOT digraph with a node per actor and an edge per pair
+    /// of actors that communicate, labeled with the kinds of messages delivered between them.
+    /// Useful for documenting larger systems. See also [`ActorModel::topology_dot_for_path`].
+    ///
+    /// A message's kind is its variant name per its [`Debug`] representation, including the
+    /// names of nested variants, such as `Internal(Prepare)`.
+    pub fn topology_dot(&self, max_states: usize) -> String {
+        let mut edges = Edges::new();
+        let mut visited = HashSet::new();
+        let mut pending = VecDeque::new();
+        for state in self.init_states() {
+            if self.within_boundary(&state) && visited.insert(fingerprint(&state)) {
+                pending.push_back(state);
+            }
+        }
+        let mut actions = Vec::new();
+        while let Some(state) = pending.pop_front() {
+            self.actions(&state, &mut actions);
+            for action in actions.drain(..) {
+                if let ActorModelAction::Deliver { src, dst, msg } = &action {
+                    edges.insert(*src, *dst, msg);
+                }
+                if visited.len() >= max_states {
+                    continue;
+                }
+                if let Some(next_state) = self.next_state(&state, action) {
+                    if self.within_boundary(&next_state) && visited.insert(fingerprint(&next_state))
+                    {
+                        pending.push_back(next_state);
+                    }
+                }
+            }
+        }
+        self.render_topology(edges)
+    }
+
+    /// Renders the messages delivered along a recorded path, such as a counterexample, as with
+    /// [`ActorModel::topology_dot`].
+    pub fn topology_dot_for_path(
+        &self,
+        path: Path<<Self as Model>::State, <Self as Model>::Action>,
+    ) -> String {
+        let mut edges = Edges::new();
+        for action in path.into_actions() {
+            if let ActorModelAction::Deliver { src, dst, msg } = action {
+                edges.insert(src, dst, &msg);
+            }
+        }
+        self.render_topology(edges)
+    }
+
+    fn render_topology(&self, edges: Edges) -> String {
+        let mut out = String::new();
+        writeln!(out, "digraph topology {{").unwrap();
+        writeln!(out, "    node [shape=box, fontname=\"monospace\"];").unwrap();
+        writeln!(out, "    edge [fontname=\"monospace\"];").unwrap();
+        for (index, actor) in self.actors.iter().enumerate() {
+            let name = actor.name();
+            let label = if name.is_empty() {
+                index.to_string()
+            } else {
+                format!("{} {}", index, name)
+            };
+            writeln!(out, "    a{} [label=\"{}\"];", index, escape(&label)).unwrap();
+        }
+        for ((src, dst), kinds) in edges.0 {
+            let label: Vec<_> = kinds.iter().map(|kind| escape(kind)).collect();
+            writeln!(
+                out,
+                "    a{} -> a{} [label=\"{}\"];",
+                usize::from(src),
+                usize::from(dst),
+                label.join("\\n")
+            )
+            .unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+        out
+    }
+}
+
+/// The kinds of messages delivered between each pair of actors.
+struct Edges(BTreeMap<(Id, Id), BTreeSet<String>>);
+
+impl Edges {
+    fn new() -> Self {
+        Self(BTreeMap::new())
+    }
+
+    fn insert(&mut self, src: Id, dst: Id, msg: &impl Debug) {
+        let kind = msg_kind(&format!("{:?}", msg));
+        self.0.entry((src, dst)).or_default().insert(kind);
+    }
+}
+
+/// Extracts the variant name from the [`Debug`] representation of a message, recursing into the
+/// first field if it is also a variant, so that `Internal(Prepare { ballot: 1 })` becomes
+/// `Internal(Prepare)` while `Put(1, 'A')` becomes `Put`.
+fn msg_kind(debug: &str) -> String {
+    let name_len = debug
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+        .unwrap_or(debug.len());
+    let (name, rest) = debug.split_at(name_len);
+    if name.is_empty() {
+        return debug.to_string();
+    }
+    match rest.strip_prefix('(') {
+        Some(field) if field.starts_with(|c: char| c.is_uppercase()) => {
+            format!("{}({})", name, msg_kind(field))
+        }
+        _ => name.to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::actor::actor_test_util::ping_pong::*;
+    use crate::actor::ActorModelAction::Deliver;
+
+    #[test]
+    fn extracts_message_kinds() {
+        assert_eq!(msg_kind("Ping(1)"), "Ping");
+        assert_eq!(msg_kind("Put(1, 'A')"), "Put");
+        assert_eq!(
+            msg_kind("Internal(Prepare { ballot: (1, Id(2)) })"),
+            "Internal(Prepare)"
+        );
+        assert_eq!(msg_kind("Internal(Id(2))"), "Internal(Id)");
+        assert_eq!(msg_kind("Heartbeat"), "Heartbeat");
+        assert_eq!(msg_kind("Msg { seq: 3 }"), "Msg");
+        assert_eq!(msg_kind("(1, 2)"), "(1, 2)");
+    }
+
+    #[test]
+    fn renders_topology() {
+        let model = PingPongCfg {
+            maintains_history: false,
+            max_nat: 3,
+        }
+        .into_model();
+        let expected = "\
+digraph topology {
+    node [shape=box, fontname=\"monospace\"];
+    edge [fontname=\"monospace\"];
+    a0 [label=\"0\"];
+    a1 [label=\"1\"];
+    a0 -> a1 [label=\"Ping\"];
+    a1 -> a0 [label=\"Pong\"];
+}
+";
+        assert_eq!(model.topology_dot(100), expected);
+
+        let path = Path::from_actions(
+            &model,
+            model.init_states().remove(0),
+            &[Deliver {
+                src: 0.into(),
+                dst: 1.into(),
+                msg: PingPongMsg::Ping(0),
+            }],
+        )
+        .unwrap();
+        assert_eq!(
+            model.topology_dot_for_path(path),
+            expected.replace("    a1 -> a0 [label=\"Pong\"];\n", "")
+        );
+    }
+}
@@ -0,0 +1,460 @@
+//! Checks a system compositionally via assume-guarantee reasoning, for systems whose full state
+//! space is intractable but whose components interact through a narrow interface.
+//!
+//! An [`Assumption`] is an automaton over the events that an environment emits to a component.
+//! Rather than checking the product of the component and environment, a [`ComponentCheck`]
+//! verifies the component's properties while an environment that emits any events permitted by
+//! the assumption drives it, and a [`GuaranteeCheck`] separately verifies that the real
+//! environment only emits events that the assumption permits. If both hold, then the component's
+//! properties hold within the composed system.
+//!
+//! # Example
+//!
+//! ```
+//! use stateright::{Checker, Model, Property};
+//! use stateright::assume_guarantee::{Assumption, ComponentCheck, GuaranteeCheck};
+//!
+//! /// A buffer that is filled by its environment and drained by itself.
+//! struct Buffer;
+//! impl Model for Buffer {
+//!     type State = u8; // length
+//!     type Action = ();
+//!     fn init_states(&self) -> Vec<Self::State> {
+//!         vec![0]
+//!     }
+//!     fn actions(&self, len: &Self::State, actions: &mut Vec<Self::Action>) {
+//!         if *len > 0 {
+//!             actions.push(());
+//!         }
+//!     }
+//!     fn next_state(&self, len: &Self::State, _: Self::Action) -> Option<Self::State> {
+//!         Some(len - 1)
+//!     }
+//!     fn properties(&self) -> Vec<Property<Self>> {
+//!         vec![Property::always("within capacity", |_, len| *len <= 2)]
+//!     }
+//! }
+//!
+//! /// Fills the buffer a fixed number of times.
+//! struct Producer(u8);
+//! impl Model for Producer {
+//!     type State = u8; // items produced
+//!     type Action = ();
+//!     fn init_states(&self) -> Vec<Self::State> {
+//!         vec![0]
+//!     }
+//!     fn actions(&self, produced: &Self::State, actions: &mut Vec<Self::Action>) {
+//!         if *produced < self.0 {
+//!             actions.push(());
+//!         }
+//!     }
+//!     fn next_state(&self, produced: &Self::State, _: Self::Action) -> Option<Self::State> {
+//!         Some(produced + 1)
+//!     }
+//! }
+//!
+//! // The environment is assumed to produce at most 2 items.
+//! let assumption = Assumption::new(
+//!     "at most 2 puts",
+//!     0_u8,
+//!     |puts, events| if *puts < 2 { events.push("put") },
+//!     |puts, _| puts + 1,
+//! );
+//!
+//! ComponentCheck::new(Buffer, assumption.clone(), |_, len, _| Some(len + 1))
+//!     .checker()
+//!     .spawn_bfs()
+//!     .join()
+//!     .assert_properties();
+//! GuaranteeCheck::new(Producer(2), assumption.clone(), |_, _, _| Some("put"))
+//!     .checker()
+//!     .spawn_bfs()
+//!     .join()
+//!     .assert_properties();
+//! GuaranteeCheck::new(Producer(3), assumption, |_, _, _| Some("put"))
+//!     .checker()
+//!     .spawn_bfs()
+//!     .join()
+//!     .assert_any_discovery("at most 2 puts");
+//! ```
+
+use crate::{Condition, Model, Property, Trigger};
+use std::sync::Arc;
+
+/// A deterministic automaton over the events that an environment emits to a component,
+/// indicating which events are permitted after each sequence of events. See the [module
+/// documentation](self) for an example.
+#[derive(Clone)]
+pub struct Assumption<S, E> {
+    /// The name of the assumption, which is also the name of the property checked by a
+    /// [`GuaranteeCheck`].
+    pub name: &'static str,
+    /// The state of the automaton before any events.
+    pub init_state: S,
+    /// Lists the events that are permitted in a state.
+    pub events: fn(&S, &mut Vec<E>),
+    /// Converts a state and a permitted event to the resulting state.
+    pub next_state: fn(&S, &E) -> S,
+}
+
+impl<S, E> Assumption<S, E>
+where
+    E: PartialEq,
+{
+    /// Constructs an [`Assumption`].
+    pub fn new(
+        name: &'static str,
+        init_state: S,
+        events: fn(&S, &mut Vec<E>),
+        next_state: fn(&S, &E) -> S,
+    ) -> Self {
+        Self {
+            name,
+            init_state,
+            events,
+            next_state,
+        }
+    }
+
+    /// Indicates whether an event is permitted in a state.
+    pub fn permits(&self, state: &S, event: &E) -> bool {
+        let mut events = Vec::new();
+        (self.events)(state, &mut events);
+        events.contains(event)
+    }
+}
+
+/// An action of a [`ComponentCheck`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq, serde::Serialize)]
+pub enum ComponentAction<Action, Event> {
+    /// An action of the component.
+    Component(Action),
+    /// An event emitted by the assumed environment.
+    Environment(Event),
+}
+
+/// Checks the properties of a component model whose environment is only assumed to emit the
+/// events permitted by an [`Assumption`]. States pair the state of the component with the state
+/// of the assumption.
+pub struct ComponentCheck<M, S, E>
+where
+    M: Model,
+    M::State: Clone,
+    S: Clone,
+    E: Clone + PartialEq,
+{
+    component: M,
+    assumption: Assumption<S, E>,
+    #[allow(clippy::type_complexity)]
+    on_event: fn(&M, &M::State, &E) -> Option<M::State>,
+    properties: Vec<Property<ComponentCheck<M, S, E>>>,
+}
+
+impl<M, S, E> ComponentCheck<M, S, E>
+where
+    M: Model + 'static,
+    M::State: Clone,
+    S: Clone + 'static,
+    E: Clone + PartialEq + 'static,
+{
+    /// Instantiates the check, with `on_event` converting a state of the component and an event
+    /// from its environment to the resulting state ([`None`] indicates that the event does not
+    /// change the state). The component's [properties](Model::properties) are checked.
+    #[allow(clippy::type_complexity)]
+    pub fn new(
+        component: M,
+        assumption: Assumption<S, E>,
+        on_event: fn(&M, &M::State, &E) -> Option<M::State>,
+    ) -> Self {
+        let properties = component.properties().into_iter().map(lift).collect();
+        Self {
+            component,
+            assumption,
+            on_event,
+            properties,
+        }
+    }
+
+    /// The component being checked.
+    pub fn component(&self) -> &M {
+        &self.component
+    }
+}
+
+/// Converts a property of a component to a property of a [`ComponentCheck`]. Transition
+/// conditions only constrain the component's own actions.
+fn lift<M, S, E>(property: Property<M>) -> Property<ComponentCheck<M, S, E>>
+where
+    M: Model + 'static,
+    M::State: Clone,
+    S: Clone + 'static,
+    E: Clone + PartialEq + 'static,
+{
+    let lift_condition = |condition: Condition<M>| -> Condition<ComponentCheck<M, S, E>> {
+        Arc::new(move |check, (state, _)| condition(&check.component, state))
+    };
+    if let Some(transition) = property.transition {
+        let mut lifted = Property::always_transition(
+            property.name,
+            move |check: &ComponentCheck<M, S, E>, (state, _), action, (next_state, _)| match action
+            {
+                ComponentAction::Component(action) => {
+                    transition(&check.component, state, action, next_state)
+                }
+                ComponentAction::Environment(_) => true,
+            },
+        );
+        lifted.assumption = property.assumption;
+        return lifted;
+    }
+    Property {
+        expectation: property.expectation,
+        name: property.name,
+        condition: lift_condition(property.condition),
+        trigger: property.trigger.map(|trigger| Trigger {
+            condition: lift_condition(trigger.condition),
+            steps: trigger.steps,
+        }),
+        assumption: property.assumption,
+        conjuncts: property
+            .conjuncts
+            .into_iter()
+            .map(|(name, condition)| (name, lift_condition(condition)))
+            .collect(),
+        transition: None,
+    }
+}
+
+impl<M, S, E> Model for ComponentCheck<M, S, E>
+where
+    M: Model,
+    M::State: Clone,
+    S: Clone,
+    E: Clone + PartialEq,
+{
+    type State = (M::State, S);
+    type Action = ComponentAction<M::Action, E>;
+
+    fn init_states(&self) -> Vec<Self::State> {
+        self.component
+            .init_states()
+            .into_iter()
+            .map(|state| (state, self.assumption.init_state.clone()))
+            .collect()
+    }
+
+    fn actions(&self, state: &Self::State, actions: &mut Vec<Self::Action>) {
+        let (component_state, assumption_state) = state;
+        let mut component_actions = Vec::new();
+        self.component
+            .actions(component_state, &mut component_actions);
+        actions.extend(
+            component_actions
+                .into_iter()
+                .map(ComponentAction::Component),
+        );
+        let mut events = Vec::new();
+        (self.assumption.events)(assumption_state, &mut events);
+        actions.extend(events.into_iter().map(ComponentAction::Environment));
+    }
+
+    fn next_state(&self, last_state: &Self::State, action: Self::Action) -> Option<Self::State> {
+        let (component_state, assumption_state) = last_state;
+        match action {
+            ComponentAction::Component(action) => self
+                .component
+                .next_state(component_state, action)
+                .map(|state| (state, assumption_state.clone())),
+            ComponentAction::Environment(event) => {
+                let state = (self.on_event)(&self.component, component_state, &event)
+                    .unwrap_or_else(|| component_state.clone());
+                Some((
+                    state,
+                    (self.assumption.next_state)(assumption_state, &event),
+                ))
+            }
+        }
+    }
+
+    fn properties(&self) -> Vec<Property<Self>> {
+        self.properties.clone()
+    }
+
+    fn within_boundary(&self, state: &Self::State) -> bool {
+        self.component.within_boundary(&state.0)
+    }
+}
+
+/// Checks that an environment model only emits events permitted by an [`Assumption`], via an
+/// `always` property named after the assumption. States pair the state of the environment with
+/// the state of the assumption, which is [`None`] once the environment emits an event that the
+/// assumption does not permit.
+pub struct GuaranteeCheck<M, S, E>
+where
+    M: Model,
+{
+    environment: M,
+    assumption: Assumption<S, E>,
+    #[allow(clippy::type_complexity)]
+    event: fn(&M, &M::State, &M::Action) -> Option<E>,
+}
+
+impl<M, S, E> GuaranteeCheck<M, S, E>
+where
+    M: Model,
+{
+    /// Instantiates the check, with `event` indicating the event (if any) that the environment
+    /// emits when taking an action from a state.
+    #[allow(clippy::type_complexity)]
+    pub fn new(
+        environment: M,
+        assumption: Assumption<S, E>,
+        event: fn(&M, &M::State, &M::Action) -> Option<E>,
+    ) -> Self {
+        Self {
+            environment,
+            assumption,
+            event,
+        }
+    }
+
+    /// The environment being checked.
+    pub fn environment(&self) -> &M {
+        &self.environment
+    }
+}
+
+impl<M, S, E> Model for GuaranteeCheck<M, S, E>
+where
+    M: Model,
+    S: Clone,
+    E: PartialEq,
+{
+    type State = (M::State, Option<S>);
+    type Action = M::Action;
+
+    fn init_states(&self) -> Vec<Self::State> {
+        self.environment
+            .init_states()
+            .into_iter()
+            .map(|state| (state, Some(self.assumption.init_state.clone())))
+            .collect()
+    }
+
+    fn actions(&self, state: &Self::State, actions: &mut Vec<Self::Action>) {
+        // A violation is terminal.
+        if state.1.is_some() {
+            self.environment.actions(&state.0, actions);
+        }
+    }
+
+    fn next_state(&self, last_state: &Self::State, action: Self::Action) -> Option<Self::State> {
+        let (environment_state, assumption_state) = last_state;
+        let assumption_state = assumption_state.as_ref()?;
+        let event = (self.event)(&self.environment, environment_state, &action);
+        let state = self.environment.next_state(environment_state, action)?;
+        let assumption_state = match event {
+            None => Some(assumption_state.clone()),
+            Some(event) if self.assumption.permits(assumption_state, &event) => {
+                Some((self.assumption.next_state)(assumption_state, &event))
+            }
+            Some(_) => None,
+        };
+        Some((state, assumption_state))
+    }
+
+    fn properties(&self) -> Vec<Property<Self>> {
+        vec![Property::always(
+            self.assumption.name,
+            |_, (_, assumption_state): &Self::State| assumption_state.is_some(),
+        )]
+    }
+
+    fn within_boundary(&self, state: &Self::State) -> bool {
+        self.environment.within_boundary(&state.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::dgraph::DGraph;
+    use crate::Checker;
+
+    /// Permits each event at most once.
+    fn distinct_events() -> Assumption<Vec<u8>, u8> {
+        Assumption::new(
+            "distinct",
+            Vec::new(),
+            |seen, events| events.extend((1..=3).filter(|e| !seen.contains(e))),
+            |seen, event| [seen.as_slice(), &[*event]].concat(),
+        )
+    }
+
+    #[test]
+    fn checks_component_under_assumption() {
+        // The component adds each event to its state, which stays small if events are distinct.
+        let component =
+            DGraph::with_property(Property::always("small", |_, s| *s <= 6)).with_path(vec![0]);
+        ComponentCheck::new(component.clone(), distinct_events(), |_, s: &u8, e| {
+            Some(s + e)
+        })
+        .checker()
+        .spawn_bfs()
+        .join()
+        .assert_properties();
+
+        // A weaker assumption permits repeated events.
+        let any_events = Assumption::new(
+            "any",
+            0,
+            |count, events| {
+                if *count < 3 {
+                    events.extend(1..=3)
+                }
+            },
+            |count, _| count + 1,
+        );
+        let checker = ComponentCheck::new(component, any_events, |_, s: &u8, e| Some(s + e))
+            .checker()
+            .spawn_bfs()
+            .join();
+        assert_eq!(
+            checker.discovery("small").unwrap().into_actions(),
+            vec![
+                ComponentAction::Environment(1),
+                ComponentAction::Environment(3),
+                ComponentAction::Environment(3),
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_environment_guarantees_assumption() {
+        // The environment emits the value of each state it reaches.
+        let event = |_: &DGraph, _: &u8, next: &u8| Some(*next);
+        GuaranteeCheck::new(
+            DGraph::with_property(Property::always("true", |_, _| true)).with_path(vec![0, 1, 3]),
+            distinct_events(),
+            event,
+        )
+        .checker()
+        .spawn_bfs()
+        .join()
+        .assert_properties();
+
+        let checker = GuaranteeCheck::new(
+            DGraph::with_property(Property::always("true", |_, _| true))
+                .with_path(vec![0, 2, 1, 2]),
+            distinct_events(),
+            event,
+        )
+        .checker()
+        .spawn_bfs()
+        .join();
+        assert_eq!(
+            checker.discovery("distinct").unwrap().into_actions(),
+            vec![2, 1, 2]
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Generates a skeleton crate for a new Stateright project.
+//!
+//! ```sh
+//! cargo run --bin stateright-new -- my-system
+//! cd my-system && cargo test
+//! ```
+//!
+//! The generated crate contains an actor system with properties and `check`, `explore`, and
+//! `spawn` subcommands. Its source is `template.rs`, which is also compiled and tested as part of
+//! this binary so that the template cannot fall out of date with the library.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[cfg(all(test, feature = "explorer", feature = "spawn"))]
+#[allow(dead_code)]
+mod template;
+
+const TEMPLATE: &str = include_str!("template.rs");
+
+const GITIGNORE: &str = "/target\n";
+
+/// Returns the generated `Cargo.toml`, which depends on the version of Stateright that built this
+/// binary.
+fn cargo_toml(name: &str) -> String {
+    format!(
+        r#"[package]
+name = "{name}"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+env_logger = "0.10"
+serde = {{ version = "1.0", features = ["derive"] }}
+stateright = "{version}"
+"#,
+        name = name,
+        version = env!("CARGO_PKG_VERSION"),
+    )
+}
+
+/// Returns an error message if `name` is not a valid package name.
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("The project name cannot be empty.".to_owned());
+    }
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err(format!(
+            "The project name cannot start with a digit: {:?}",
+            name
+        ));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && *c != '-' && *c != '_')
+    {
+        return Err(format!(
+            "The project name contains an invalid character {:?}: {:?}",
+            c, name
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the files to generate, relative to the project directory.
+fn files(name: &str) -> Vec<(PathBuf, String)> {
+    vec![
+        (PathBuf::from("Cargo.toml"), cargo_toml(name)),
+        (PathBuf::from(".gitignore"), GITIGNORE.to_owned()),
+        (PathBuf::from("src").join("main.rs"), TEMPLATE.to_owned()),
+    ]
+}
+
+/// Writes the project into `dir`, which must not already exist.
+fn generate(dir: &Path, name: &str) -> io::Result<()> {
+    fs::create_dir_all(dir.parent().unwrap_or(Path::new(".")))?;
+    fs::create_dir(dir)?;
+    for (path, contents) in files(name) {
+        let path = dir.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let name = match args.as_slice() {
+        [name] if !name.starts_with('-') => name,
+        _ => {
+            println!("USAGE:");
+            println!("  stateright-new NAME");
+            println!();
+            println!("Creates a directory NAME containing a new Stateright project.");
+            std::process::exit(if args.is_empty() { 0 } else { 1 });
+        }
+    };
+    if let Err(err) = validate_name(name) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+    let dir = Path::new(name);
+    if let Err(err) = generate(dir, name) {
+        eprintln!("Unable to create {:?}: {}", dir, err);
+        std::process::exit(1);
+    }
+    println!("Created {:?}. Next steps:", dir);
+    println!("$ cd {}", name);
+    println!("$ cargo test");
+    println!("$ cargo run --release -- check");
+    println!("$ cargo run --release -- explore");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn validates_names() {
+        assert!(validate_name("my-system").is_ok());
+        assert!(validate_name("my_system2").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("2pc").is_err());
+        assert!(validate_name("my system").is_err());
+        assert!(validate_name("../escape").is_err());
+    }
+
+    #[test]
+    fn generates_project() {
+        let dir = std::env::temp_dir().join(format!("stateright-new-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        generate(&dir, "my-system").unwrap();
+
+        let cargo_toml = fs::read_to_string(dir.join("Cargo.toml")).unwrap();
+        assert!(cargo_toml.contains(r#"name = "my-system""#));
+        assert!(cargo_toml.contains(&format!(r#"stateright = "{}""#, env!("CARGO_PKG_VERSION"))));
+        assert_eq!(
+            fs::read_to_string(dir.join("src").join("main.rs")).unwrap(),
+            TEMPLATE
+        );
+
+        // Refuses to overwrite an existing project.
+        assert!(generate(&dir, "my-system").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,148 @@
+//! A starting point for a Stateright project, generated by `stateright-new`.
+//!
+//! Two actors play ping-pong: the client sends `Ping(0)`, and each actor replies to the message it
+//! expects with the next one in the sequence. Replace the actors, messages, and properties with
+//! your own system.
+//!
+//! The [`System`] impl wires the model up to the `stateright::cli` harness:
+//!
+//! - `cargo test` checks the model.
+//! - `cargo run --release -- check [MAX_NAT]` checks the model and reports progress.
+//! - `cargo run --release -- explore [MAX_NAT]` serves the Stateright Explorer web UI.
+//! - `cargo run --release -- spawn` runs the actors over UDP.
+
+use serde::{Deserialize, Serialize};
+use stateright::actor::{Actor, ActorModel, Id, Network, Out};
+use stateright::cli::{self, Parameters, System};
+use stateright::Expectation;
+use std::borrow::Cow;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+enum PingPongMsg {
+    Ping(u32),
+    Pong(u32),
+}
+
+#[derive(Clone)]
+enum PingPongActor {
+    /// Starts the exchange by pinging the server.
+    Client { server_id: Id },
+    /// Replies to pings.
+    Server,
+}
+
+impl Actor for PingPongActor {
+    type Msg = PingPongMsg;
+    /// The number of messages that the actor has received.
+    type State = u32;
+    type Timer = ();
+
+    fn on_start(&self, _id: Id, o: &mut Out<Self>) -> Self::State {
+        if let PingPongActor::Client { server_id } = self {
+            o.send(*server_id, PingPongMsg::Ping(0));
+        }
+        0
+    }
+
+    fn on_msg(
+        &self,
+        _id: Id,
+        state: &mut Cow<Self::State>,
+        src: Id,
+        msg: Self::Msg,
+        o: &mut Out<Self>,
+    ) {
+        match msg {
+            PingPongMsg::Ping(n) if n == **state => {
+                o.send(src, PingPongMsg::Pong(n));
+                *state.to_mut() += 1;
+            }
+            PingPongMsg::Pong(n) if n == **state => {
+                o.send(src, PingPongMsg::Ping(n + 1));
+                *state.to_mut() += 1;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Describes the system to check: its actors, network, and properties.
+#[derive(Clone)]
+struct PingPongModelCfg {
+    /// Bounds the state space by limiting how many messages each actor receives.
+    max_nat: u32,
+}
+
+impl PingPongModelCfg {
+    fn into_model(self) -> ActorModel<PingPongActor, Self> {
+        ActorModel::new(self.clone(), ())
+            .actors(self.actors(&Id::from))
+            .init_network(Network::new_unordered_nonduplicating([]))
+            .property(Expectation::Always, "delta within 1", |_, state| {
+                state.actor_states[0].abs_diff(*state.actor_states[1]) <= 1
+            })
+            .property(Expectation::Sometimes, "can reach max", |model, state| {
+                state
+                    .actor_states
+                    .iter()
+                    .any(|count| **count == model.cfg.max_nat)
+            })
+            .within_boundary(|cfg, state| state.actor_states.iter().all(|n| **n <= cfg.max_nat))
+    }
+}
+
+impl System for PingPongModelCfg {
+    type Model = ActorModel<PingPongActor, Self>;
+    type Actor = PingPongActor;
+
+    fn description(&self) -> String {
+        format!("ping-pong with up to {} messages", self.max_nat)
+    }
+
+    fn model(&self) -> Self::Model {
+        self.clone().into_model()
+    }
+
+    fn actors(&self, id: &dyn Fn(usize) -> Id) -> Vec<Self::Actor> {
+        vec![
+            PingPongActor::Client { server_id: id(1) },
+            PingPongActor::Server,
+        ]
+    }
+}
+
+fn factory(params: &mut Parameters) -> Result<PingPongModelCfg, String> {
+    Ok(PingPongModelCfg {
+        max_nat: params.next_or("MAX_NAT", 5)?,
+    })
+}
+
+#[cfg(test)]
+#[test]
+fn can_model_ping_pong() {
+    use stateright::{Checker, Model};
+
+    let checker = PingPongModelCfg { max_nat: 5 }
+        .into_model()
+        .checker()
+        .spawn_bfs()
+        .join();
+    checker.assert_properties();
+    assert_eq!(checker.unique_state_count(), 11);
+}
+
+#[cfg(test)]
+#[test]
+fn can_check_from_command_line() {
+    let mut out = Vec::new();
+    let args = ["check", "--threads", "1", "3"].map(String::from);
+    cli::run_with_args("ping-pong", args, factory, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("Done. states=7, unique=7"), "{}", out);
+}
+
+fn main() {
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info")); // `RUST_LOG=${LEVEL}` env variable to override
+
+    cli::run(factory);
+}
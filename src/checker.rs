@@ -2,18 +2,29 @@
 
 mod bfs;
 mod dfs;
+#[cfg(feature = "explorer")]
 mod explorer;
+mod incremental;
+mod metric;
 mod on_demand;
 mod path;
+mod profile;
 mod representative;
 mod rewrite;
 mod rewrite_plan;
 mod simulation;
+mod spill;
+mod tla;
+mod visited;
 mod visitor;
 
-use crate::report::{ReportData, ReportDiscovery, Reporter};
-use crate::{Expectation, Fingerprint, Model};
-use std::collections::{BTreeMap, HashMap};
+use crate::report::{ReportAssumption, ReportData, ReportDiscovery, Reporter};
+use crate::util::CompactState;
+use crate::{Expectation, Fingerprint, FingerprintHasher, Model, Property};
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
 use std::num::NonZeroUsize;
@@ -21,13 +32,25 @@ use std::sync::{Arc, Mutex};
 use std::thread::JoinHandle;
 use std::time::Instant;
 
+#[cfg(feature = "explorer")]
+pub use explorer::{ExplorerBuilder, ExplorerGroup, ExplorerResponse, LocalExplorer};
+pub use incremental::IncrementalChecker;
+pub use metric::MetricSummary;
+pub(crate) use metric::{Metric, Metrics};
 pub use path::*;
+pub use profile::{ActionProfile, FieldProfile, StateSpaceProfile};
 pub use representative::*;
 pub use rewrite::*;
 pub use rewrite_plan::*;
-pub use simulation::{Chooser, UniformChooser};
+pub use simulation::{Chooser, Estimate, UniformChooser};
+pub use tla::{TlaValue, TlcState, TlcTrace};
+use visited::VisitedMap;
 pub use visitor::*;
 
+/// Computes the fingerprint of what is observable about a state. See
+/// [`CheckerBuilder::observe`].
+pub(crate) type Observation<State> = Arc<dyn Fn(&State) -> Fingerprint + Send + Sync>;
+
 #[derive(Clone, Copy)]
 pub(crate) enum ControlFlow {
     CheckFingerprint(Fingerprint),
@@ -51,6 +74,28 @@ impl Display for DiscoveryClassification {
     }
 }
 
+/// Whether the assumption of a property holds, and hence whether the property is meaningful.
+/// See [`Property::assuming`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AssumptionStatus {
+    /// The assumption holds for the states checked, so the property is meaningful.
+    Holds,
+    /// The assumption is violated, so the property is vacuous.
+    Violated,
+    /// Checking has not determined whether the assumption holds.
+    Undetermined,
+}
+
+impl Display for AssumptionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssumptionStatus::Holds => write!(f, "holds"),
+            AssumptionStatus::Violated => write!(f, "is violated"),
+            AssumptionStatus::Undetermined => write!(f, "is undetermined"),
+        }
+    }
+}
+
 /// A [`Model`] [`Checker`] builder. Instantiable via the [`Model::checker`] method.
 ///
 /// # Example
@@ -63,26 +108,39 @@ impl Display for DiscoveryClassification {
               Consider calling spawn_bfs() or spawn_dfs()."]
 pub struct CheckerBuilder<M: Model> {
     model: M,
+    fingerprint_hasher: FingerprintHasher,
+    observation: Option<Observation<M::State>>,
+    probabilities: Vec<simulation::ProbabilityProperty<M>>,
+    metrics: Vec<Metric<M>>,
     #[allow(clippy::type_complexity)]
     symmetry: Option<fn(&M::State) -> M::State>,
+    spill: Option<spill::SpillOptions<M::State>>,
     target_state_count: Option<NonZeroUsize>,
     target_max_depth: Option<NonZeroUsize>,
     thread_count: usize,
+    track_terminal_states: bool,
     visitor: Option<Box<dyn CheckerVisitor<M> + Send + Sync>>,
 }
 impl<M: Model> CheckerBuilder<M> {
     pub(crate) fn new(model: M) -> Self {
         Self {
             model,
+            fingerprint_hasher: FingerprintHasher::default(),
+            observation: None,
+            probabilities: Vec::new(),
+            metrics: Vec::new(),
             target_state_count: None,
             target_max_depth: None,
             symmetry: None,
+            spill: None,
             thread_count: 1,
+            track_terminal_states: false,
             visitor: None,
         }
     }
 
     /// Starts a web service for interactively exploring a model ([demo](http://demo.stateright.rs:3000/)).
+    /// Requires the `explorer` feature, which is enabled by default.
     ///
     /// ![Stateright Explorer screenshot](https://raw.githubusercontent.com/stateright/stateright/master/explorer.png)
     ///
@@ -129,20 +187,84 @@ impl<M: Model> CheckerBuilder<M> {
     ///
     /// # API
     ///
+    /// The Explorer UI is backed by a JSON API that external tools can also use to drive
+    /// exploration. States are identified by their fingerprint, and paths
+    /// by the slash-separated fingerprints of their states, as produced by [`Path::encode`].
+    /// Endpoints that return JSON set `Content-Type: application/json`, and unknown paths or
+    /// fingerprints result in a 404 with a plain text explanation. Fields are only ever added.
+    ///
     /// - `GET /` returns a web browser UI as HTML.
-    /// - `GET /.status` returns information about the model checker status.
+    /// - `GET /.status` returns information about the model checker status:
+    ///   `{"done", "model", "state_count", "unique_state_count", "max_depth", "properties",
+    ///   "recent_path", "predicates", "breakpoints", "read_only", "cluster", "heatmap"}`, where
+    ///   each property is an `[expectation, name, discovery_path_or_null]` triple, and `heatmap`
+    ///   is `{"max_state_visits", "max_transition_visits"}` if visit counts are attached via
+    ///   [`ExplorerBuilder::heatmap`].
+    /// - `GET /.properties` returns each property as
+    ///   `{"expectation", "name", "discovery"}`, where `discovery` is the path to the
+    ///   example or counterexample found so far, if any.
     /// - `GET /.states` returns available initial states and fingerprints.
     /// - `GET /.states/{fingerprint1}/{fingerprint2}/...` follows the specified
-    ///    path of fingerprints and returns available actions with resulting
-    ///    states and fingerprints.
+    ///   path of fingerprints and returns available actions with resulting
+    ///   states and fingerprints, as well as whether each property's condition
+    ///   holds in each resulting state. Each element is
+    ///   `{"action", "outcome", "state", "fingerprint", "properties", "conditions", "clauses",
+    ///   "breakpoints", "svg"}`, omitting fields that do not apply (for instance, `state` is
+    ///   omitted if the action is ignored by the model). Only the first 100 elements of each
+    ///   collection within a state are included, with `elided` counting the rest; append
+    ///   `?collection_limit={n}` to adjust the bound, or `?collection_limit=0` to remove it.
+    ///   Similarly, if more than 50 successors share an action class (the leading identifier of
+    ///   the action's [`Debug`] representation, such as an enum variant), an evenly spaced sample
+    ///   of them is returned, followed by a `{"action", "omitted"}` placeholder naming the class
+    ///   and counting the successors omitted. `successor_limit` adjusts this bound. If visit
+    ///   counts are attached, each element also includes `"visits": {"state", "transition"}`.
     /// - `GET /.states/.../{invalid-fingerprint}` returns 404.
+    /// - `GET /.search/{predicate-index}?limit={n}` returns paths to up to `n` reachable states
+    ///   satisfying a predicate registered via [`ExplorerBuilder::predicate`]:
+    ///   `{"predicate", "matches", "explored_state_count", "complete"}`.
+    /// - `GET /.events` streams checking progress as
+    ///   [Server-Sent Events](https://html.spec.whatwg.org/multipage/server-sent-events.html):
+    ///   `status` events mirror `GET /.status`, and `discovery` events announce each newly found
+    ///   example or counterexample.
+    /// - `GET /.breakpoint/{fingerprint1}/{fingerprint2}/...` returns the shortest path
+    ///   continuing from the specified state (or from an initial state if none is specified)
+    ///   to a state at which a breakpoint registered via [`ExplorerBuilder::breakpoint`] fires:
+    ///   `{"breakpoint", "path"}`.
+    /// - `GET /.walk/{fingerprint1}/{fingerprint2}/...?steps={n}&seed={seed}` takes up to `n`
+    ///   (default 20) uniformly random steps from the specified state (or from a random initial
+    ///   state if none is specified), stopping early at a state without successors:
+    ///   `{"seed", "steps", "path"}`. The seed is chosen at random unless specified.
+    /// - `GET /.fingerprint/{fingerprint}` returns the shortest path to a reachable state with
+    ///   the specified fingerprint: `{"fingerprint", "path"}`.
+    /// - `GET /.path/{fingerprint1}/{fingerprint2}/...` exports the actions and states along a
+    ///   path: `{"path", "steps": [{"action", "state", "fingerprint"}]}`. The `path` field can
+    ///   be decoded via [`Path::from_encoded`]. Append `?format=tla` for a TLA+ sequence of
+    ///   `[action |-> ..., state |-> ...]` records (translated from each state's [`Debug`]
+    ///   representation) suitable for a TLC trace expression, or `?format=dot` for a
+    ///   [Graphviz](https://graphviz.org/) rendering.
+    /// - `GET /.cluster` describes each actor of a live cluster attached via
+    ///   [`ExplorerBuilder::cluster`]:
+    ///   `[{"id", "state", "messages_sent", "messages_received", "timeouts", "restarts"}]`.
+    ///   Returns 404 if no cluster is attached.
+    /// - `POST /.runtocompletion` checks the remaining state space in the background, or returns
+    ///   403 if the Explorer is [read-only](ExplorerBuilder::read_only).
+    ///
+    /// See [`CheckerBuilder::explorer`] for additional options.
+    #[cfg(feature = "explorer")]
     pub fn serve(self, addresses: impl std::net::ToSocketAddrs) -> std::sync::Arc<impl Checker<M>>
     where
         M: 'static + Model + Send + Sync,
         M::Action: Debug + Send + Sync,
         M::State: Debug + Hash + Send + Sync,
     {
-        explorer::serve(self, addresses)
+        self.explorer().serve(addresses)
+    }
+
+    /// Configures the Explorer web service before [serving](ExplorerBuilder::serve) it, for
+    /// example to register predicates that can be searched from the UI.
+    #[cfg(feature = "explorer")]
+    pub fn explorer(self) -> ExplorerBuilder<M> {
+        ExplorerBuilder::new(self)
     }
 
     /// Spawns a breadth-first search model checker. This traversal strategy uses more memory than
@@ -194,6 +316,37 @@ impl<M: Model> CheckerBuilder<M> {
         dfs::DfsChecker::spawn(self)
     }
 
+    /// Instantiates a breadth-first search model checker that runs on the current thread, checking
+    /// a bounded number of states per [`IncrementalChecker::step`]. This suits environments
+    /// without threads, such as WebAssembly in a browser, and callers that interleave checking
+    /// with other work, such as reporting progress.
+    ///
+    /// [`CheckerBuilder::threads`] is ignored. Panics if [symmetry
+    /// reduction](CheckerBuilder::symmetry) or [probabilities](CheckerBuilder::probability) are
+    /// requested, as this checker supports neither.
+    pub fn incremental_bfs(self) -> IncrementalChecker<M>
+    where
+        M::State: Hash,
+    {
+        IncrementalChecker::new(self)
+    }
+
+    /// Explores the state space breadth-first on the current thread, attributing unique states to
+    /// the actions that generated them and counting the distinct values of each state field. The
+    /// resulting [`StateSpaceProfile`] indicates what to abstract or bound when a model has too
+    /// many states to check.
+    ///
+    /// Properties are not evaluated. The boundary, targets, symmetry reduction, and fingerprint
+    /// hasher are honored, while other options such as [`CheckerBuilder::threads`] are ignored.
+    /// Combine this with [`CheckerBuilder::target_state_count`] to profile an unbounded model.
+    pub fn profile(self) -> StateSpaceProfile
+    where
+        M::State: Hash + serde::Serialize,
+        M::Action: Debug,
+    {
+        profile::profile(self)
+    }
+
     /// Spawns a simulation model checker. This repeatedly traverses the model from initial states
     /// to a terminal state. This aims to provide faster coverage of deep states for models that
     /// cannot practically be checked exhaustively.
@@ -211,6 +364,75 @@ impl<M: Model> CheckerBuilder<M> {
         simulation::SimulationChecker::spawn::<C>(self, seed, chooser)
     }
 
+    /// Evaluates properties over observable transitions only, where the observation of a state
+    /// is computed by the specified function. Properties are not evaluated for a state whose
+    /// observation matches that of its predecessor, so the properties should depend only on the
+    /// observation. This both reduces checking time and keeps properties robust to internal
+    /// bookkeeping steps, such as those introduced by wrapping an actor. Likewise the bound of a
+    /// [`Property::within`] counts observable transitions only.
+    pub fn observe<O>(self, observation: fn(&M::State) -> O) -> Self
+    where
+        M::State: 'static,
+        O: Hash + 'static,
+    {
+        Self {
+            observation: Some(Arc::new(move |state| {
+                crate::fingerprint(&observation(state))
+            })),
+            ..self
+        }
+    }
+
+    /// Requires that a state satisfying the specified condition be reached within `steps` steps
+    /// with at least the specified probability, such as "P(commit within 50 steps) >= 0.99".
+    /// Only the [simulation checker](CheckerBuilder::spawn_simulation) estimates these
+    /// probabilities, and it does so by simulating traces until reaching the
+    /// [target state count](CheckerBuilder::target_state_count) or, if none is set, until each
+    /// estimate's confidence interval either excludes the minimum or is narrower than 0.01. Traces
+    /// are chosen by the simulation's [`Chooser`], and a trace that is cut short (for instance by
+    /// revisiting a state) counts as not satisfying the condition. See [`Checker::estimates`].
+    pub fn probability(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        min_probability: f64,
+        steps: usize,
+        condition: impl Fn(&M, &M::State) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.probabilities.push(simulation::ProbabilityProperty {
+            name: name.into(),
+            min_probability,
+            steps,
+            condition: Arc::new(condition),
+        });
+        self
+    }
+
+    /// Tracks a numeric metric over the visited states, such as the number of messages in flight
+    /// or the length of a log. The checker summarizes each metric's minimum, maximum, and average
+    /// (see [`Checker::metrics`]), so a bounded check doubles as a worst-case analysis.
+    pub fn metric(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        measure: impl Fn(&M, &M::State) -> usize + Send + Sync + 'static,
+    ) -> Self {
+        self.metrics.push(Metric {
+            name: name.into(),
+            measure: Box::new(measure),
+        });
+        self
+    }
+
+    /// Records each terminal state, such as the possible outcomes of a protocol, so that they are
+    /// listed by [`Checker::terminal_states`] and in reports. Off by default, as the checker
+    /// otherwise keeps nothing per terminal state, whereas the DFS checker records the path to
+    /// each one.
+    pub fn track_terminal_states(self) -> Self {
+        Self {
+            track_terminal_states: true,
+            ..self
+        }
+    }
+
     /// Enables symmetry reduction. Requires the [model state] to implement [`Representative`].
     ///
     /// [model state]: crate::Model::State
@@ -240,6 +462,40 @@ impl<M: Model> CheckerBuilder<M> {
         }
     }
 
+    /// Sets the hash function with which states are fingerprinted. See [`FingerprintHasher`] for
+    /// the options.
+    ///
+    /// This only affects how the checker identifies states internally. Paths are still
+    /// [encoded](Path::encode) using the default hasher, and the [Explorer](Self::serve) always
+    /// uses the default hasher.
+    pub fn fingerprint_hasher(self, fingerprint_hasher: FingerprintHasher) -> Self {
+        Self {
+            fingerprint_hasher,
+            ..self
+        }
+    }
+
+    /// Allows the BFS checkers ([`spawn_bfs`](Self::spawn_bfs) and
+    /// [`incremental_bfs`](Self::incremental_bfs)) to spill pending states to files in `dir` once
+    /// a thread has more than `max_pending` states awaiting exploration, so that a frontier larger
+    /// than memory slows checking to the speed of the disk rather than exhausting memory. Requires
+    /// the [model state] to implement [`CompactState`], which serves as the file format. The files
+    /// are deleted once read or when checking ends.
+    ///
+    /// Spilled states are explored after those held in memory, so discoveries may be reached via
+    /// slightly longer paths than without spilling. Other checkers ignore this option.
+    ///
+    /// [model state]: crate::Model::State
+    pub fn spill_to_disk(self, dir: impl Into<std::path::PathBuf>, max_pending: usize) -> Self
+    where
+        M::State: CompactState,
+    {
+        Self {
+            spill: Some(spill::SpillOptions::new(dir.into(), max_pending)),
+            ..self
+        }
+    }
+
     /// Sets the maximum depth that the checker should aim to explore.
     pub fn target_max_depth(self, depth: usize) -> Self {
         Self {
@@ -297,7 +553,32 @@ pub trait Checker<M: Model> {
 
     /// Returns a map from property name to corresponding "discovery" (indicated
     /// by a [`Path`]).
-    fn discoveries(&self) -> HashMap<&'static str, Path<M::State, M::Action>>;
+    fn discoveries(&self) -> HashMap<Cow<'static, str>, Path<M::State, M::Action>>;
+
+    /// Returns an estimate for each property indicated via [`CheckerBuilder::probability`]. Only
+    /// the simulation checker produces estimates.
+    fn estimates(&self) -> Vec<Estimate> {
+        Vec::new()
+    }
+
+    /// Returns each reachable state within the boundary that has no successors within the
+    /// boundary, such as the possible outcomes of a protocol, ordered by fingerprint. States are
+    /// only considered once expanded, so those at the [target max
+    /// depth](CheckerBuilder::target_max_depth) are excluded, and the result is complete once
+    /// checking [is done](Self::is_done) unless checking ended early because every property has a
+    /// discovery.
+    ///
+    /// Returns `None` unless requested via [`CheckerBuilder::track_terminal_states`]. The
+    /// simulation checker does not enumerate terminal states.
+    fn terminal_states(&self) -> Option<Vec<M::State>> {
+        None
+    }
+
+    /// Returns a summary of each metric indicated via [`CheckerBuilder::metric`] over the states
+    /// visited so far.
+    fn metrics(&self) -> Vec<MetricSummary> {
+        Vec::new()
+    }
 
     /// Blocks the current thread until checking [`is_done`] or each thread evaluates
     /// a specified maximum number of states.
@@ -320,8 +601,16 @@ pub trait Checker<M: Model> {
     /// have been visited.
     fn is_done(&self) -> bool;
 
+    /// Indicates that checking stops short of some reachable states, because it reached the
+    /// [target state count](CheckerBuilder::target_state_count) or [target max
+    /// depth](CheckerBuilder::target_max_depth) or because it samples paths, so a property without
+    /// a discovery is not known to hold even once checking [is done](Self::is_done).
+    fn is_truncated(&self) -> bool {
+        false
+    }
+
     /// Looks up a discovery by property name. Panics if the property does not exist.
-    fn discovery(&self, name: &'static str) -> Option<Path<M::State, M::Action>> {
+    fn discovery(&self, name: &str) -> Option<Path<M::State, M::Action>> {
         self.discoveries().remove(name)
     }
 
@@ -357,6 +646,8 @@ pub trait Checker<M: Model> {
                 }
             });
 
+            // Checkers without threads of their own check here, while the status is reported.
+            slf.run_to_completion();
             for h in handles {
                 h.join().expect("Failed to join checker thread");
             }
@@ -374,8 +665,10 @@ pub trait Checker<M: Model> {
             let mut discoveries = BTreeMap::new();
             for (name, path) in slf.discoveries() {
                 let discovery = ReportDiscovery {
+                    formatted_path: path.format(slf.model()),
                     path,
-                    classification: slf.discovery_classification(name),
+                    classification: slf.discovery_classification(&name),
+                    violated_conjunct: slf.violated_conjunct(&name),
                 };
                 discoveries.insert(name, discovery);
             }
@@ -383,10 +676,66 @@ pub trait Checker<M: Model> {
                 .lock()
                 .unwrap()
                 .report_discoveries(discoveries);
+            let assumptions = assumptions(slf);
+            if !assumptions.is_empty() {
+                reporter_mutex2
+                    .lock()
+                    .unwrap()
+                    .report_assumptions(assumptions);
+            }
+            let estimates = slf.estimates();
+            if !estimates.is_empty() {
+                reporter_mutex2.lock().unwrap().report_estimates(estimates);
+            }
+            let metrics = slf.metrics();
+            if !metrics.is_empty() {
+                reporter_mutex2.lock().unwrap().report_metrics(metrics);
+            }
+            if let Some(terminal_states) = terminal_states(slf) {
+                reporter_mutex2
+                    .lock()
+                    .unwrap()
+                    .report_terminal_states(terminal_states);
+            }
         });
         self
     }
 
+    /// Blocks until checking is done, then writes a machine-readable JSON report for consumption
+    /// by continuous integration pipelines and dashboards. The report indicates the result of each
+    /// property (`"pass"`, `"fail"`, or `"unknown"` if checking is incomplete or
+    /// [truncated](Self::is_truncated)), each discovery as
+    /// a list of actions formatted via [`Model::format_action`] along with its fingerprint path,
+    /// and checking statistics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use stateright::*; let model = ();
+    /// let mut json = Vec::new();
+    /// model.checker().spawn_bfs().join_and_report_json(&mut json).unwrap();
+    /// let report: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    /// assert_eq!(report["stats"]["unique_states"], 1);
+    /// ```
+    fn join_and_report_json<W>(mut self, writer: &mut W) -> std::io::Result<Self>
+    where
+        M::Action: Debug,
+        M::State: Hash,
+        Self: Sized,
+        W: std::io::Write,
+    {
+        let method_start = Instant::now();
+        // Checkers without threads of their own check here.
+        self.run_to_completion();
+        for h in self.handles() {
+            h.join().expect("Failed to join checker thread");
+        }
+        let report = json_report(&self, method_start.elapsed());
+        serde_json::to_writer_pretty(&mut *writer, &report)?;
+        writeln!(writer)?;
+        Ok(self)
+    }
+
     /// Periodically emits a status message.
     fn report<R>(self, reporter: &mut R) -> Self
     where
@@ -397,6 +746,7 @@ pub trait Checker<M: Model> {
     {
         // Start with the checking status.
         let method_start = Instant::now();
+        self.run_to_completion();
         while !self.is_done() {
             reporter.report_checking(ReportData {
                 total_states: self.state_count(),
@@ -420,12 +770,29 @@ pub trait Checker<M: Model> {
         let mut discoveries = BTreeMap::new();
         for (name, path) in self.discoveries() {
             let discovery = ReportDiscovery {
+                formatted_path: path.format(self.model()),
                 path,
-                classification: self.discovery_classification(name),
+                classification: self.discovery_classification(&name),
+                violated_conjunct: self.violated_conjunct(&name),
             };
             discoveries.insert(name, discovery);
         }
         reporter.report_discoveries(discoveries);
+        let assumptions = assumptions(&self);
+        if !assumptions.is_empty() {
+            reporter.report_assumptions(assumptions);
+        }
+        let estimates = self.estimates();
+        if !estimates.is_empty() {
+            reporter.report_estimates(estimates);
+        }
+        let metrics = self.metrics();
+        if !metrics.is_empty() {
+            reporter.report_metrics(metrics);
+        }
+        if let Some(terminal_states) = terminal_states(&self) {
+            reporter.report_terminal_states(terminal_states);
+        }
 
         self
     }
@@ -435,15 +802,81 @@ pub trait Checker<M: Model> {
         let properties = self.model().properties();
         let property = properties.iter().find(|p| p.name == name).unwrap();
         match property.expectation {
-            Expectation::Always | Expectation::Eventually => {
-                DiscoveryClassification::Counterexample
-            }
+            Expectation::Always
+            | Expectation::Eventually
+            | Expectation::Within
+            | Expectation::LeadsTo
+            | Expectation::Terminal => DiscoveryClassification::Counterexample,
             Expectation::Sometimes => DiscoveryClassification::Example,
         }
     }
 
-    /// A helper that verifies examples exist for all `sometimes` properties and no counterexamples
-    /// exist for any `always`/`eventually` properties.
+    /// Names the first conjunct of a [`Property::all`] property violated by the last state of its
+    /// counterexample, or `None` if there is no counterexample or the property has no conjuncts.
+    /// Panics if the property does not exist.
+    fn violated_conjunct(&self, name: &str) -> Option<Cow<'static, str>> {
+        let property = self.model().property(name);
+        if property.conjuncts.is_empty() {
+            return None;
+        }
+        let path = self.discovery(name)?;
+        property.violated_conjunct(self.model(), path.last_state())
+    }
+
+    /// Indicates whether the assumption of a property holds, or `None` if the property has no
+    /// assumption. If the property has a discovery, the assumption is judged on the path of that
+    /// discovery where possible: an `always` assumption holds if it holds for every step, and a
+    /// `sometimes` or `eventually` assumption holds once it holds for some state. Otherwise an
+    /// `always`/`eventually`/`within`/`leads_to`/`terminal` assumption holds unless a
+    /// counterexample is found, whereas a `sometimes` assumption holds once an example is found,
+    /// and the status is undetermined until checking is done without being
+    /// [truncated](Self::is_truncated). Panics if either property does not exist.
+    fn assumption_status(&self, name: &str) -> Option<AssumptionStatus> {
+        let properties = self.model().properties();
+        let property = properties.iter().find(|p| p.name == name).unwrap();
+        let assumption = property.assumption.as_ref()?;
+        let assumed = properties
+            .iter()
+            .find(|p| p.name == *assumption)
+            .unwrap_or_else(|| panic!("Unknown assumption \"{}\" of \"{}\"", assumption, name));
+        if let Some(path) = self.discovery(name) {
+            match assumed.holds_on_path(self.model(), path) {
+                Some(true) => return Some(AssumptionStatus::Holds),
+                Some(false) => return Some(AssumptionStatus::Violated),
+                None => {}
+            }
+        }
+        let is_discovered = self.discoveries().contains_key(assumption);
+        let is_complete = self.is_done() && !self.is_truncated();
+        Some(match assumed.expectation {
+            Expectation::Always
+            | Expectation::Eventually
+            | Expectation::Within
+            | Expectation::LeadsTo
+            | Expectation::Terminal => {
+                if is_discovered {
+                    AssumptionStatus::Violated
+                } else if is_complete {
+                    AssumptionStatus::Holds
+                } else {
+                    AssumptionStatus::Undetermined
+                }
+            }
+            Expectation::Sometimes => {
+                if is_discovered {
+                    AssumptionStatus::Holds
+                } else if is_complete {
+                    AssumptionStatus::Violated
+                } else {
+                    AssumptionStatus::Undetermined
+                }
+            }
+        })
+    }
+
+    /// A helper that verifies examples exist for all `sometimes` properties, no counterexamples
+    /// exist for any `always`/`eventually`/`within`/`leads_to`/`terminal` properties, and
+    /// estimated probabilities meet their minimums.
     fn assert_properties(&self)
     where
         M::Action: Debug,
@@ -451,17 +884,28 @@ pub trait Checker<M: Model> {
     {
         for p in self.model().properties() {
             match p.expectation {
-                Expectation::Always => self.assert_no_discovery(p.name),
-                Expectation::Eventually => self.assert_no_discovery(p.name),
+                Expectation::Always => self.assert_no_discovery(&p.name),
+                Expectation::Eventually => self.assert_no_discovery(&p.name),
+                Expectation::Within => self.assert_no_discovery(&p.name),
+                Expectation::LeadsTo => self.assert_no_discovery(&p.name),
+                Expectation::Terminal => self.assert_no_discovery(&p.name),
                 Expectation::Sometimes => {
-                    self.assert_any_discovery(p.name);
+                    self.assert_any_discovery(&p.name);
                 }
             }
         }
+        for estimate in self.estimates() {
+            assert!(
+                estimate.is_satisfied(),
+                "Estimated probability for \"{}\" is too low. {}",
+                estimate.name,
+                estimate
+            );
+        }
     }
 
     /// Panics if a particular discovery is not found.
-    fn assert_any_discovery(&self, name: &'static str) -> Path<M::State, M::Action> {
+    fn assert_any_discovery(&self, name: &str) -> Path<M::State, M::Action> {
         if let Some(found) = self.discovery(name) {
             return found;
         }
@@ -474,18 +918,21 @@ pub trait Checker<M: Model> {
     }
 
     /// Panics if a particular discovery is found.
-    fn assert_no_discovery(&self, name: &'static str)
+    fn assert_no_discovery(&self, name: &str)
     where
         M::Action: Debug,
         M::State: Debug,
     {
         if let Some(found) = self.discovery(name) {
+            let last_state = found.last_state();
             panic!(
-                "Unexpected \"{}\" {} {}Last state: {:?}\n",
+                "Unexpected \"{}\" {} {}Last state: {}\n",
                 name,
                 self.discovery_classification(name),
-                found,
-                found.last_state()
+                found.format(self.model()),
+                self.model()
+                    .format_state(last_state)
+                    .unwrap_or_else(|| format!("{:?}", last_state))
             );
         }
         assert!(
@@ -497,7 +944,7 @@ pub trait Checker<M: Model> {
 
     /// Panics if the specified actions do not result in a discovery for the specified property
     /// name.
-    fn assert_discovery(&self, name: &'static str, actions: Vec<M::Action>)
+    fn assert_discovery(&self, name: &str, actions: Vec<M::Action>)
     where
         M::State: Debug + PartialEq,
         M::Action: Debug + PartialEq,
@@ -510,7 +957,17 @@ pub trait Checker<M: Model> {
                 let property = self.model().property(name);
                 match property.expectation {
                     Expectation::Always => {
-                        if !(property.condition)(self.model(), path.last_state()) {
+                        if let Some(transition) = &property.transition {
+                            let steps = path.into_vec();
+                            let is_violated = matches!(
+                                steps.windows(2).last(),
+                                Some([(state, Some(action)), (next_state, _)])
+                                    if !transition(self.model(), state, action, next_state)
+                            );
+                            if is_violated {
+                                return;
+                            }
+                        } else if !(property.condition)(self.model(), path.last_state()) {
                             return;
                         }
                     }
@@ -539,6 +996,67 @@ pub trait Checker<M: Model> {
                             return;
                         }
                     }
+                    Expectation::Within => {
+                        let states = path.into_states();
+                        let mut deadlines = Deadlines::default();
+                        let is_violated = states
+                            .iter()
+                            .any(|s| !deadlines.advance(0, &property, self.model(), s));
+                        let is_path_terminal = {
+                            let mut actions = Vec::new();
+                            self.model().actions(states.last().unwrap(), &mut actions);
+                            actions.is_empty()
+                        };
+                        if is_violated || (deadlines.is_pending(0) && is_path_terminal) {
+                            return;
+                        }
+                        additional_info.push("incorrect counterexample meets every deadline");
+                    }
+                    Expectation::LeadsTo => {
+                        let states = path.into_states();
+                        let mut deadlines = Deadlines::default();
+                        for (depth, state) in states.iter().enumerate() {
+                            deadlines.respond(0, &property, self.model(), state, depth + 1);
+                        }
+                        let next_states: Vec<_> = self
+                            .model()
+                            .next_steps(states.last().unwrap())
+                            .into_iter()
+                            .map(|(_, next_state)| next_state)
+                            .filter(|next_state| self.model().within_boundary(next_state))
+                            .collect();
+                        // A pending obligation is violated if the path terminates, or returns to
+                        // a state visited since the obligation began.
+                        let is_violated = deadlines.leads_to.first().is_some_and(|(_, since)| {
+                            next_states.is_empty()
+                                || next_states
+                                    .iter()
+                                    .any(|next_state| states[since - 1..].contains(next_state))
+                        });
+                        if is_violated {
+                            return;
+                        }
+                        additional_info.push("incorrect counterexample meets every obligation");
+                    }
+                    Expectation::Terminal => {
+                        let last_state = path.last_state();
+                        let is_satisfied = (property.condition)(self.model(), last_state);
+                        let is_path_terminal = self
+                            .model()
+                            .next_steps(last_state)
+                            .iter()
+                            .all(|(_, next_state)| !self.model().within_boundary(next_state));
+                        if !is_satisfied && is_path_terminal {
+                            return;
+                        }
+                        if is_satisfied {
+                            additional_info
+                                .push("incorrect counterexample satisfies terminal property");
+                        }
+                        if !is_path_terminal {
+                            additional_info.push("incorrect counterexample is nonterminal");
+                        }
+                    }
                 }
             }
         }
@@ -548,14 +1066,178 @@ pub trait Checker<M: Model> {
             format!(" ({})", additional_info.join("; "))
         };
         panic!(
-            "Invalid discovery for \"{}\"{}, but a valid one was found. found={:?}",
+            "Invalid discovery for \"{}\"{}, but a valid one was found. found=[{}]",
             name,
             additional_info,
-            found.into_actions()
+            found
+                .into_actions()
+                .iter()
+                .map(|action| self.model().format_action(action))
+                .collect::<Vec<_>>()
+                .join(", ")
         );
     }
 }
 
+/// Appends the violating step to a counterexample of a [`Property::always_transition`] property,
+/// as the checkers discover the state from which that step is taken.
+pub(crate) fn with_violating_step<M: Model>(
+    model: &M,
+    name: &str,
+    mut path: Path<M::State, M::Action>,
+) -> Path<M::State, M::Action> {
+    let property = model.property(name);
+    let transition = match &property.transition {
+        None => return path,
+        Some(transition) => transition,
+    };
+    let last_state = path.last_state();
+    let violation = model
+        .next_steps(last_state)
+        .into_iter()
+        .find(|(action, next_state)| {
+            model.within_boundary(next_state) && !transition(model, last_state, action, next_state)
+        });
+    if let Some((action, next_state)) = violation {
+        path.push(action, next_state);
+    }
+    path
+}
+
+/// Builds the report written by [`Checker::join_and_report_json`].
+fn json_report<M>(
+    checker: &(impl Checker<M> + ?Sized),
+    duration: std::time::Duration,
+) -> serde_json::Value
+where
+    M: Model,
+    M::Action: Debug,
+    M::State: Hash,
+{
+    let done = checker.is_done();
+    let is_complete = done && !checker.is_truncated();
+    let model = checker.model();
+    let properties: Vec<_> = model
+        .properties()
+        .iter()
+        .map(|p| {
+            let discovery = checker.discovery(&p.name);
+            let result = match (&p.expectation, &discovery, is_complete) {
+                (Expectation::Sometimes, Some(_), _) => "pass",
+                (Expectation::Sometimes, None, true) => "fail",
+                (_, Some(_), _) => "fail",
+                (_, None, true) => "pass",
+                (_, None, false) => "unknown",
+            };
+            let discovery = discovery.map(|path| {
+                let fingerprint_path = path.encode();
+                serde_json::json!({
+                    "classification": checker.discovery_classification(&p.name).to_string(),
+                    "actions": path
+                        .into_actions()
+                        .iter()
+                        .map(|action| model.format_action(action))
+                        .collect::<Vec<_>>(),
+                    "fingerprint_path": fingerprint_path,
+                    "violated_conjunct": checker.violated_conjunct(&p.name),
+                })
+            });
+            serde_json::json!({
+                "name": p.name,
+                "expectation": format!("{:?}", p.expectation).to_lowercase(),
+                "result": result,
+                "discovery": discovery,
+                "assumption": p.assumption.as_ref().map(|assumption| serde_json::json!({
+                    "name": assumption,
+                    "status": checker.assumption_status(&p.name).map(|status| match status {
+                        AssumptionStatus::Holds => "holds",
+                        AssumptionStatus::Violated => "violated",
+                        AssumptionStatus::Undetermined => "undetermined",
+                    }),
+                })),
+            })
+        })
+        .collect();
+    let estimates: Vec<_> = checker
+        .estimates()
+        .iter()
+        .map(|estimate| {
+            serde_json::json!({
+                "name": estimate.name,
+                "steps": estimate.steps,
+                "min_probability": estimate.min_probability,
+                "probability": estimate.probability(),
+                "trials": estimate.trials,
+                "hits": estimate.hits,
+                "satisfied": estimate.is_satisfied(),
+            })
+        })
+        .collect();
+    let metrics: Vec<_> = checker
+        .metrics()
+        .iter()
+        .map(|metric| {
+            serde_json::json!({
+                "name": metric.name,
+                "count": metric.count,
+                "min": metric.min,
+                "max": metric.max,
+                "mean": metric.mean,
+            })
+        })
+        .collect();
+    let mut stats = serde_json::json!({
+        "total_states": checker.state_count(),
+        "unique_states": checker.unique_state_count(),
+        "max_depth": checker.max_depth(),
+        "duration_secs": duration.as_secs_f64(),
+    });
+    if let Some(terminal_states) = checker.terminal_states() {
+        stats["terminal_states"] = terminal_states.len().into();
+    }
+    serde_json::json!({
+        "done": done,
+        "passed": properties.iter().all(|p| p["result"] == "pass")
+            && checker.estimates().iter().all(|e| e.is_satisfied()),
+        "properties": properties,
+        "estimates": estimates,
+        "metrics": metrics,
+        "stats": stats,
+    })
+}
+
+/// Formats the terminal states for reporting, if tracked.
+fn terminal_states<M>(checker: &(impl Checker<M> + ?Sized)) -> Option<Vec<String>>
+where
+    M: Model,
+    M::State: Debug,
+{
+    let model = checker.model();
+    let states = checker.terminal_states()?;
+    Some(
+        states
+            .iter()
+            .map(|s| model.format_state(s).unwrap_or_else(|| format!("{:?}", s)))
+            .collect(),
+    )
+}
+
+/// Collects the status of each property assumption for reporting.
+fn assumptions<M: Model>(checker: &(impl Checker<M> + ?Sized)) -> Vec<ReportAssumption> {
+    checker
+        .model()
+        .properties()
+        .into_iter()
+        .filter_map(|p| {
+            Some(ReportAssumption {
+                status: checker.assumption_status(&p.name)?,
+                name: p.name,
+                assumption: p.assumption?,
+            })
+        })
+        .collect()
+}
+
 // EventuallyBits tracks one bit per 'eventually' property being checked. Properties are assigned
 // bit-numbers just by counting the 'eventually' properties up from 0 in the properties list. If a
 // bit is present in a bitset, the property has _not_ been found on this path yet. Bits are removed
@@ -565,6 +1247,282 @@ pub trait Checker<M: Model> {
 // a counterexample to the property.
 type EventuallyBits = id_set::IdSet;
 
+// Deadlines track the pending obligations of `within` properties along a path: for each property
+// (by index) whose trigger held without a response, the number of further steps within which the
+// response must hold. Only the earliest unmet trigger matters, as a response discharges every
+// pending trigger. Like EventuallyBits, deadlines propagate to successors, and an obligation still
+// pending at the "end" of a path is also a discovery.
+//
+// A state reached with obligations pending is only explored again if some obligation has fewer
+// steps remaining than whenever the state was reached with the same obligations pending before,
+// as otherwise no deadline can pass that did not pass before. Checkers therefore keep the least
+// steps remaining per state and set of pending obligations (see `Deadlines::tighten`).
+//
+// The obligations of `leads_to` properties have no deadline, so instead each records the depth of
+// the path at which it began. Such an obligation is also a discovery if the path returns to a
+// state visited since that depth, as the path can then repeat the cycle without the response ever
+// holding (see `Deadlines::cycle`).
+#[derive(Clone, Debug, Default)]
+struct Deadlines {
+    within: Vec<(usize, usize)>,
+    leads_to: Vec<(usize, usize)>,
+}
+
+impl Deadlines {
+    /// Advances the obligation of the `within` property with index `i` upon reaching a state.
+    /// Returns `false` if the response did not hold in time.
+    fn advance<M: Model>(
+        &mut self,
+        i: usize,
+        property: &Property<M>,
+        model: &M,
+        state: &M::State,
+    ) -> bool {
+        let trigger = match &property.trigger {
+            None => return true,
+            Some(trigger) => trigger,
+        };
+        let pending = self.within.iter().position(|(j, _)| *j == i);
+        if (property.condition)(model, state) {
+            if let Some(index) = pending {
+                self.within.swap_remove(index);
+            }
+            return true;
+        }
+        match pending {
+            Some(index) => {
+                let remaining = &mut self.within[index].1;
+                *remaining -= 1;
+                *remaining > 0
+            }
+            None if (trigger.condition)(model, state) => {
+                self.within.push((i, trigger.steps));
+                trigger.steps > 0
+            }
+            None => true,
+        }
+    }
+
+    /// Advances the obligation of the `leads_to` property with index `i` upon reaching a state at
+    /// `depth` along a path.
+    fn respond<M: Model>(
+        &mut self,
+        i: usize,
+        property: &Property<M>,
+        model: &M,
+        state: &M::State,
+        depth: usize,
+    ) {
+        let trigger = match &property.trigger {
+            None => return,
+            Some(trigger) => trigger,
+        };
+        let pending = self.leads_to.iter().position(|(j, _)| *j == i);
+        if (property.condition)(model, state) {
+            if let Some(index) = pending {
+                self.leads_to.swap_remove(index);
+            }
+        } else if pending.is_none() && (trigger.condition)(model, state) {
+            self.leads_to.push((i, depth));
+        }
+    }
+
+    /// Indicates whether the `within` or `leads_to` property with index `i` has a pending
+    /// obligation.
+    fn is_pending(&self, i: usize) -> bool {
+        self.within
+            .iter()
+            .chain(&self.leads_to)
+            .any(|(j, _)| *j == i)
+    }
+
+    /// Returns the indices of the `leads_to` properties whose obligations remain pending around
+    /// the cycle that a path of `depth` states closes by continuing to the state with fingerprint
+    /// `next_fp`, if any. `path` yields the fingerprints of the states of the path from the last
+    /// to the first, and is only consumed back to the earliest pending obligation.
+    fn cycle(
+        &self,
+        depth: usize,
+        next_fp: Fingerprint,
+        path: impl IntoIterator<Item = Fingerprint>,
+    ) -> Vec<usize> {
+        let earliest = match self.leads_to.iter().map(|(_, since)| *since).min() {
+            None => return Vec::new(),
+            Some(earliest) => earliest,
+        };
+        let revisited = path
+            .into_iter()
+            .zip((earliest..=depth).rev())
+            .find(|(fp, _)| *fp == next_fp);
+        match revisited {
+            None => Vec::new(),
+            Some((_, revisited)) => self
+                .leads_to
+                .iter()
+                .filter(|(_, since)| *since <= revisited)
+                .map(|(i, _)| *i)
+                .collect(),
+        }
+    }
+
+    /// Records the steps remaining for these obligations upon reaching a state in `bounds`, which
+    /// holds the least steps remaining for each obligation keyed by the state and the pending
+    /// obligations. Returns `false` if no obligation has fewer steps remaining than recorded, in
+    /// which case the state need not be explored again. Each obligation belongs to a different
+    /// property and is discharged independently of the others, so tracking the least steps
+    /// remaining for each separately suffices. The pending `leads_to` obligations are also part of
+    /// the key, as they distinguish the nodes of a search that reach the state.
+    fn tighten(
+        &self,
+        fingerprint_hasher: FingerprintHasher,
+        state_fp: Fingerprint,
+        bounds: &DashMap<Fingerprint, Vec<usize>>,
+    ) -> bool {
+        if self.within.is_empty() {
+            return true;
+        }
+        let mut obligations = self.within.clone();
+        obligations.sort_unstable();
+        let (pending, remaining): (Vec<_>, Vec<_>) = obligations.into_iter().unzip();
+        let key = (state_fp, pending, self.leads_to_pending());
+        match bounds.entry(fingerprint_hasher.fingerprint(&key)) {
+            Entry::Vacant(entry) => {
+                entry.insert(remaining);
+                true
+            }
+            Entry::Occupied(mut entry) => {
+                let mut is_tighter = false;
+                for (bound, remaining) in entry.get_mut().iter_mut().zip(remaining) {
+                    if remaining < *bound {
+                        *bound = remaining;
+                        is_tighter = true;
+                    }
+                }
+                is_tighter
+            }
+        }
+    }
+
+    /// Identifies the node of a search that reaches a state with these obligations pending, so
+    /// that a state reached again with different obligations is explored again rather than
+    /// skipped, as whether a deadline passes depends on the obligations. A state without pending
+    /// obligations is identified by its fingerprint alone. The depth at which a `leads_to`
+    /// obligation began is not part of the node, so that a cycle closes upon reaching the node
+    /// again.
+    fn node_fingerprint(
+        &self,
+        fingerprint_hasher: FingerprintHasher,
+        state_fp: Fingerprint,
+    ) -> Fingerprint {
+        if self.within.is_empty() && self.leads_to.is_empty() {
+            return state_fp;
+        }
+        let mut obligations = self.within.clone();
+        obligations.sort_unstable();
+        fingerprint_hasher.fingerprint(&(state_fp, obligations, self.leads_to_pending()))
+    }
+
+    /// The indices of the `leads_to` properties with pending obligations, in order.
+    fn leads_to_pending(&self) -> Vec<usize> {
+        let mut pending: Vec<_> = self.leads_to.iter().map(|(i, _)| *i).collect();
+        pending.sort_unstable();
+        pending
+    }
+}
+
+// UniqueStates count the states that a search visits. A search visits a state once per set of
+// pending `within` and `leads_to` obligations with which it reaches the state (see
+// `Deadlines::node_fingerprint`), so the states are recorded separately from the nodes, but only if
+// the model has such properties, as otherwise each node is a state.
+struct UniqueStates(Option<VisitedMap<()>>);
+
+impl UniqueStates {
+    fn new<M: Model>(properties: &[Property<M>], thread_count: usize) -> Self {
+        let has_deadlines = properties
+            .iter()
+            .any(|p| matches!(p.expectation, Expectation::Within | Expectation::LeadsTo));
+        UniqueStates(has_deadlines.then(|| VisitedMap::new(thread_count)))
+    }
+
+    /// Records a state upon visiting a node of the search.
+    fn insert(&self, state_fp: Fingerprint) {
+        if let Some(states) = &self.0 {
+            states.insert(state_fp, ());
+        }
+    }
+
+    /// Counts the states, given the nodes of the search.
+    fn len<V>(&self, nodes: &VisitedMap<V>) -> usize {
+        match &self.0 {
+            None => nodes.len(),
+            Some(states) => states.len(),
+        }
+    }
+}
+
+// Transitions evaluate the conditions of pending `always_transition` properties for the steps that
+// a checker takes from a state, as it takes them. `Model::next_state` consumes each action, so the
+// actions are generated again for the conditions to observe, but only while such properties are
+// pending.
+struct Transitions<'a, M: Model> {
+    properties: Vec<&'a Property<M>>,
+    actions: VecDeque<M::Action>,
+}
+
+impl<'a, M: Model> Transitions<'a, M> {
+    /// Collects the `always_transition` properties for which `is_pending` holds, along with the
+    /// actions of a state if there are any.
+    fn new(
+        model: &M,
+        state: &M::State,
+        properties: &'a [Property<M>],
+        is_pending: impl Fn(&Property<M>) -> bool,
+    ) -> Self {
+        let properties: Vec<_> = properties
+            .iter()
+            .filter(|p| p.transition.is_some() && is_pending(p))
+            .collect();
+        let mut actions = Vec::new();
+        if !properties.is_empty() {
+            model.actions(state, &mut actions);
+        }
+        Transitions {
+            properties,
+            actions: actions.into(),
+        }
+    }
+
+    /// Takes the action at an index, as taken from the checker's own actions by
+    /// [`Vec::swap_remove`].
+    fn swap_remove(&mut self, index: usize) -> Option<M::Action> {
+        self.actions.swap_remove_back(index)
+    }
+
+    /// Takes the next action, for a checker that takes its own actions in order.
+    fn next_action(&mut self) -> Option<M::Action> {
+        self.actions.pop_front()
+    }
+
+    /// Returns the properties violated by a step within the boundary, if its action was taken.
+    fn violated<'b>(
+        &'b self,
+        model: &'b M,
+        state: &'b M::State,
+        action: &'b Option<M::Action>,
+        next_state: &'b M::State,
+    ) -> impl Iterator<Item = &'a Property<M>> + 'b {
+        let action = action
+            .as_ref()
+            .filter(|_| model.within_boundary(next_state));
+        self.properties.iter().copied().filter(move |p| {
+            let (Some(action), Some(transition)) = (action, &p.transition) else {
+                return false;
+            };
+            !transition(model, state, action, next_state)
+        })
+    }
+}
+
 #[cfg(test)]
 mod test_eventually_property_checker {
     use crate::test_util::dgraph::DGraph;
@@ -660,39 +1618,976 @@ mod test_eventually_property_checker {
 }
 
 #[cfg(test)]
-mod test_path {
-    use super::*;
-    use crate::fingerprint;
-    use crate::test_util::linear_equation_solver::LinearEquation;
-    use std::collections::VecDeque;
+mod test_within_property_checker {
+    use crate::test_util::dgraph::DGraph;
+    use crate::{Checker, Model, Property};
+
+    fn large_within_2() -> Property<DGraph> {
+        Property::within("large", 2, |_, s| s % 2 == 1, |_, s| *s >= 5)
+    }
 
     #[test]
-    fn can_build_path_from_fingerprints() {
-        let fp = |a: u8, b: u8| fingerprint(&(a, b));
-        let model = LinearEquation { a: 2, b: 10, c: 14 };
-        let fingerprints = VecDeque::from(vec![
-            fp(0, 0),
-            fp(0, 1),
-            fp(1, 1),
-            fp(2, 1), // final state
-        ]);
-        let path = Path::from_fingerprints(&model, fingerprints.clone());
-        assert_eq!(path.last_state(), &(2, 1));
+    fn can_validate() {
+        DGraph::with_property(large_within_2())
+            .with_path(vec![5]) // responds upon trigger
+            .with_path(vec![0, 4]) // never triggered
+            .with_path(vec![1, 2, 6]) // responds at the deadline
+            .with_path(vec![3, 8, 4]) // responds before the deadline
+            .check()
+            .assert_properties();
+    }
+
+    #[test]
+    fn can_discover_counterexample() {
         assert_eq!(
-            path.last_state(),
-            &Path::final_state(&model, fingerprints).unwrap()
+            DGraph::with_property(large_within_2())
+                .with_path(vec![1, 2, 4, 6])
+                .check()
+                .discovery("large")
+                .unwrap()
+                .into_states(),
+            vec![1, 2, 4]
+        );
+        // The earliest pending trigger determines the deadline.
+        assert_eq!(
+            DGraph::with_property(large_within_2())
+                .with_path(vec![1, 2, 3, 4, 5])
+                .checker()
+                .spawn_dfs()
+                .join()
+                .discovery("large")
+                .unwrap()
+                .into_states(),
+            vec![1, 2, 3]
+        );
+        // A path may terminate before the deadline.
+        assert_eq!(
+            DGraph::with_property(large_within_2())
+                .with_path(vec![0, 3])
+                .check()
+                .discovery("large")
+                .unwrap()
+                .into_states(),
+            vec![0, 3]
+        );
+        // A bound of zero requires an immediate response.
+        assert_eq!(
+            DGraph::with_property(Property::within(
+                "large",
+                0,
+                |_, s| s % 2 == 1,
+                |_, s| *s >= 5
+            ))
+            .with_path(vec![2, 3, 6])
+            .check()
+            .discovery("large")
+            .unwrap()
+            .into_states(),
+            vec![2, 3]
         );
     }
-}
 
-#[cfg(test)]
-mod test_report {
-    use super::*;
-    use crate::{report::WriteReporter, test_util::linear_equation_solver::LinearEquation};
+    #[test]
+    fn revisits_states_reached_with_different_deadlines() {
+        // State 2 is reached both with and without a pending deadline, in either order.
+        for paths in [
+            [vec![0, 2, 4, 6], vec![1, 2, 4, 6]],
+            [vec![1, 2, 4, 6], vec![0, 2, 4, 6]],
+        ] {
+            let model = DGraph::with_property(large_within_2())
+                .with_path(paths[0].clone())
+                .with_path(paths[1].clone());
+            assert_eq!(
+                model
+                    .clone()
+                    .checker()
+                    .spawn_bfs()
+                    .join()
+                    .discovery("large")
+                    .unwrap()
+                    .into_states(),
+                vec![1, 2, 4]
+            );
+            assert_eq!(
+                model
+                    .checker()
+                    .spawn_dfs()
+                    .join()
+                    .discovery("large")
+                    .unwrap()
+                    .into_states(),
+                vec![1, 2, 4]
+            );
+        }
+    }
 
     #[test]
-    fn report_includes_property_names_and_paths() {
-        // The assertions use `starts_with` to omit timing since it varies.
+    fn explores_cycles_while_obligations_are_pending() {
+        // State 1 triggers an obligation that remains pending around the cycle until the
+        // deadline passes.
+        let model = DGraph::with_property(Property::within(
+            "large",
+            4,
+            |_, s| s % 2 == 1,
+            |_, s| *s >= 5,
+        ))
+        .with_path(vec![1, 3, 1]);
+        let expected = vec![1, 3, 1, 3, 1];
+        let checker = model.clone().checker().spawn_bfs().join();
+        assert_eq!(checker.discovery("large").unwrap().into_states(), expected);
+        assert_eq!(checker.unique_state_count(), 2);
+        let checker = model.checker().spawn_dfs().join();
+        assert_eq!(checker.discovery("large").unwrap().into_states(), expected);
+        assert_eq!(checker.unique_state_count(), 2);
+
+        // A response on the cycle discharges the obligation each time around.
+        DGraph::with_property(large_within_2())
+            .with_path(vec![1, 2, 6, 1])
+            .check()
+            .assert_properties();
+    }
+
+    #[test]
+    fn skips_states_reached_with_more_steps_remaining() {
+        use crate::StateRecorder;
+
+        // State 4 is first reached with 1 step remaining, then with 2 steps remaining, which
+        // cannot pass a deadline that the first visit did not.
+        let (recorder, accessor) = StateRecorder::new_with_accessor();
+        DGraph::with_property(Property::within(
+            "large",
+            3,
+            |_, s| s % 2 == 1,
+            |_, s| *s >= 5,
+        ))
+        .with_path(vec![1, 2, 4, 8])
+        .with_path(vec![0, 10, 12, 3, 4, 8])
+        .checker()
+        .visitor(recorder)
+        .spawn_bfs()
+        .join()
+        .assert_properties();
+        assert_eq!(accessor().iter().filter(|s| **s == 4).count(), 1);
+    }
+}
+
+#[cfg(test)]
+mod test_leads_to_property_checker {
+    use crate::test_util::dgraph::DGraph;
+    use crate::{Checker, Model, Property, UniformChooser};
+
+    fn odd_leads_to_large() -> Property<DGraph> {
+        Property::leads_to("large", |_, s| s % 2 == 1, |_, s| *s >= 5)
+    }
+
+    #[test]
+    fn can_validate() {
+        DGraph::with_property(odd_leads_to_large())
+            .with_path(vec![5]) // responds upon trigger
+            .with_path(vec![0, 4]) // never triggered
+            .with_path(vec![1, 2, 4, 6]) // responds eventually
+            .with_path(vec![6, 3, 6]) // responds each time around the cycle
+            .check()
+            .assert_properties();
+    }
+
+    #[test]
+    fn can_discover_counterexample() {
+        // A path terminates while an obligation is pending.
+        let checker = DGraph::with_property(odd_leads_to_large())
+            .with_path(vec![1, 2, 6])
+            .with_path(vec![1, 2, 4])
+            .check();
+        assert_eq!(
+            checker.discovery("large").unwrap().into_states(),
+            vec![1, 2, 4]
+        );
+        checker.assert_discovery("large", vec![2, 4]);
+    }
+
+    #[test]
+    fn discovers_cycles_while_obligations_are_pending() {
+        // State 1 triggers an obligation that remains pending around the cycle.
+        let model = DGraph::with_property(odd_leads_to_large()).with_path(vec![1, 2, 4, 1]);
+        let expected = vec![1, 2, 4, 1];
+        let checker = model.clone().checker().spawn_bfs().join();
+        assert_eq!(checker.discovery("large").unwrap().into_states(), expected);
+        assert_eq!(checker.unique_state_count(), 3);
+        checker.assert_discovery("large", vec![2, 4, 1]);
+        let checker = model.clone().checker().spawn_dfs().join();
+        assert_eq!(checker.discovery("large").unwrap().into_states(), expected);
+        assert_eq!(checker.unique_state_count(), 3);
+        let checker = model.checker().spawn_simulation(0, UniformChooser).join();
+        assert_eq!(checker.discovery("large").unwrap().into_states(), expected);
+
+        // A trace that returns to a state visited before the obligation began has not shown
+        // that the obligation remains pending around a cycle.
+        assert_eq!(
+            DGraph::with_property(odd_leads_to_large())
+                .with_path(vec![6, 3, 6])
+                .checker()
+                .target_state_count(100)
+                .spawn_simulation(0, UniformChooser)
+                .join()
+                .discovery("large"),
+            None
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_transition_property_checker {
+    use crate::test_util::dgraph::DGraph;
+    use crate::{Checker, Model, Property};
+
+    fn no_decrease() -> Property<DGraph> {
+        Property::always_transition("no decrease", |_, s, _, next_s| s <= next_s)
+    }
+
+    #[test]
+    fn can_validate() {
+        DGraph::with_property(no_decrease())
+            .with_path(vec![1, 2, 4])
+            .with_path(vec![3, 3, 5])
+            .check()
+            .assert_properties();
+    }
+
+    #[test]
+    fn can_discover_counterexample() {
+        let model = DGraph::with_property(no_decrease())
+            .with_path(vec![1, 2, 4])
+            .with_path(vec![2, 3, 1]);
+        let assert_counterexample = |checker: &dyn Checker<DGraph>| {
+            let path = checker.discovery("no decrease").unwrap();
+            assert_eq!(path.clone().into_states(), vec![2, 3, 1]);
+            assert_eq!(path.into_actions(), vec![3, 1]);
+            checker.assert_discovery("no decrease", vec![3, 1]);
+        };
+        assert_counterexample(&model.clone().checker().spawn_bfs().join());
+        assert_counterexample(&model.checker().spawn_dfs().join());
+    }
+
+    #[test]
+    fn takes_each_step_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Counts up to 3, tallying the steps taken.
+        #[derive(Default)]
+        struct Counter(AtomicUsize);
+        impl Model for Counter {
+            type State = u8;
+            type Action = ();
+            fn init_states(&self) -> Vec<Self::State> {
+                vec![0]
+            }
+            fn actions(&self, count: &Self::State, actions: &mut Vec<Self::Action>) {
+                if *count < 3 {
+                    actions.push(());
+                }
+            }
+            fn next_state(&self, count: &Self::State, _: Self::Action) -> Option<Self::State> {
+                self.0.fetch_add(1, Ordering::Relaxed);
+                Some(count + 1)
+            }
+            fn properties(&self) -> Vec<Property<Self>> {
+                vec![Property::always_transition(
+                    "increments",
+                    |_, count, _, next_count| *next_count == count + 1,
+                )]
+            }
+        }
+
+        let checker = Counter::default().checker().spawn_bfs().join();
+        checker.assert_properties();
+        assert_eq!(checker.model().0.load(Ordering::Relaxed), 3);
+        let checker = Counter::default().checker().spawn_dfs().join();
+        checker.assert_properties();
+        assert_eq!(checker.model().0.load(Ordering::Relaxed), 3);
+    }
+}
+
+#[cfg(test)]
+mod test_terminal_property_checker {
+    use crate::test_util::dgraph::DGraph;
+    use crate::{Checker, Model, Property};
+
+    fn ends_odd() -> Property<DGraph> {
+        Property::terminal("ends odd", |_, s| s % 2 == 1)
+    }
+
+    #[test]
+    fn ignores_intermediate_states() {
+        DGraph::with_property(ends_odd())
+            .with_path(vec![1, 2, 3])
+            .with_path(vec![4, 5])
+            .check()
+            .assert_properties();
+    }
+
+    #[test]
+    fn can_discover_counterexample() {
+        let model = DGraph::with_property(ends_odd())
+            .with_path(vec![1, 3, 5])
+            .with_path(vec![1, 2, 4]);
+        let assert_counterexample = |checker: &dyn Checker<DGraph>| {
+            let path = checker.discovery("ends odd").unwrap();
+            assert_eq!(path.into_states(), vec![1, 2, 4]);
+            checker.assert_discovery("ends odd", vec![2, 4]);
+        };
+        assert_counterexample(&model.clone().checker().spawn_bfs().join());
+        assert_counterexample(&model.checker().spawn_dfs().join());
+    }
+}
+
+#[cfg(test)]
+mod test_terminal_states {
+    use crate::test_util::dgraph::DGraph;
+    use crate::{Checker, Model, Property};
+
+    /// 3 and 5 are terminal, whereas 1 and 2 form a loop.
+    fn model() -> DGraph {
+        DGraph::with_property(Property::always("true", |_, _| true))
+            .with_path(vec![1, 2, 1])
+            .with_path(vec![1, 2, 3])
+            .with_path(vec![4, 2])
+            .with_path(vec![4, 5])
+    }
+
+    fn sorted(checker: &dyn Checker<DGraph>) -> Vec<u8> {
+        let mut states = checker.terminal_states().unwrap();
+        states.sort();
+        states
+    }
+
+    #[test]
+    fn enumerates_terminal_states() {
+        let checker = || model().checker().track_terminal_states();
+        assert_eq!(sorted(&checker().spawn_bfs().join()), vec![3, 5]);
+        assert_eq!(sorted(&checker().spawn_dfs().join()), vec![3, 5]);
+        assert_eq!(sorted(&checker().incremental_bfs().join()), vec![3, 5]);
+        let on_demand = checker().spawn_on_demand();
+        on_demand.run_to_completion();
+        while !on_demand.is_done() {
+            std::thread::yield_now();
+        }
+        assert_eq!(sorted(&on_demand), vec![3, 5]);
+    }
+
+    #[test]
+    fn excludes_states_that_are_not_expanded() {
+        let checker = model()
+            .checker()
+            .track_terminal_states()
+            .target_max_depth(3)
+            .spawn_bfs()
+            .join();
+        assert_eq!(sorted(&checker), vec![5]);
+    }
+
+    #[test]
+    fn tracks_terminal_states_only_on_request() {
+        assert_eq!(model().checker().spawn_bfs().join().terminal_states(), None);
+        assert_eq!(model().checker().spawn_dfs().join().terminal_states(), None);
+        assert_eq!(
+            model().checker().incremental_bfs().join().terminal_states(),
+            None
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_property_memoization {
+    use crate::test_util::dgraph::DGraph;
+    use crate::{Checker, Property};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn evaluates_once_per_projection() {
+        let evaluations = Arc::new(AtomicUsize::new(0));
+        let property = {
+            let evaluations = Arc::clone(&evaluations);
+            Property::always("below 30", move |_, s: &u8| {
+                evaluations.fetch_add(1, Ordering::Relaxed);
+                s / 10 < 3
+            })
+            .memoize_by(|_, s| s / 10)
+        };
+        DGraph::with_property(property)
+            .with_path(vec![1, 2, 3, 11, 12])
+            .with_path(vec![4, 5, 25])
+            .check()
+            .assert_properties();
+        assert_eq!(evaluations.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn keeps_cache_when_properties_are_rebuilt() {
+        use crate::Model;
+
+        /// Counts up to 20, building its memoized property afresh on each call.
+        #[derive(Default)]
+        struct Counter(Arc<AtomicUsize>);
+        impl Model for Counter {
+            type State = u8;
+            type Action = ();
+            fn init_states(&self) -> Vec<Self::State> {
+                vec![0]
+            }
+            fn actions(&self, count: &Self::State, actions: &mut Vec<Self::Action>) {
+                if *count < 20 {
+                    actions.push(());
+                }
+            }
+            fn next_state(&self, count: &Self::State, _: Self::Action) -> Option<Self::State> {
+                Some(count + 1)
+            }
+            fn properties(&self) -> Vec<Property<Self>> {
+                let evaluations = Arc::clone(&self.0);
+                vec![Property::always("below 30", move |_, s: &u8| {
+                    evaluations.fetch_add(1, Ordering::Relaxed);
+                    s / 10 < 3
+                })
+                .memoize_by(|_, s| s / 10)]
+            }
+        }
+
+        let checker = Counter::default().checker().incremental_bfs();
+        while !checker.step(1) {}
+        checker.assert_properties();
+        assert_eq!(checker.model().0.load(Ordering::Relaxed), 3);
+    }
+}
+
+#[cfg(test)]
+mod test_property_boundary {
+    use crate::test_util::dgraph::DGraph;
+    use crate::{Checker, Property};
+
+    fn below_10(_: &DGraph, s: &u8) -> bool {
+        *s < 10
+    }
+
+    #[test]
+    fn only_evaluates_within_boundary() {
+        let small = || Property::always("small", |_, s| *s < 5).within_boundary(below_10);
+        DGraph::with_property(small())
+            .with_path(vec![1, 20, 3])
+            .check()
+            .assert_properties();
+        assert_eq!(
+            DGraph::with_property(small())
+                .with_path(vec![1, 20, 7])
+                .check()
+                .discovery("small")
+                .unwrap()
+                .into_states(),
+            vec![1, 20, 7]
+        );
+
+        let large = || Property::sometimes("large", |_, s| *s >= 5).within_boundary(below_10);
+        DGraph::with_property(large())
+            .with_path(vec![1, 20])
+            .check()
+            .assert_no_discovery("large");
+        DGraph::with_property(large())
+            .with_path(vec![1, 20, 6])
+            .check()
+            .assert_properties();
+
+        // Obligations are neither triggered nor pending outside the boundary.
+        let responsive = || {
+            Property::within("responsive", 1, |_, s| s % 2 == 1, |_, s| *s == 0)
+                .within_boundary(below_10)
+        };
+        DGraph::with_property(responsive())
+            .with_path(vec![11, 13, 15])
+            .with_path(vec![3, 20, 2])
+            .check()
+            .assert_properties();
+    }
+}
+
+#[cfg(test)]
+mod test_property_combinators {
+    use crate::test_util::dgraph::DGraph;
+    use crate::{Checker, Property};
+
+    fn even() -> Property<DGraph> {
+        Property::always("even", |_, s| s % 2 == 0)
+    }
+
+    fn small() -> Property<DGraph> {
+        Property::always("small", |_, s| *s < 5)
+    }
+
+    #[test]
+    fn names_combinations_after_operands() {
+        assert_eq!(even().and(small()).name, "(even && small)");
+        assert_eq!(even().or(small().not()).name, "(even || !(small))");
+        assert_eq!(even().implies(small()).not().name, "!(even => small)");
+    }
+
+    #[test]
+    fn can_discover_counterexamples() {
+        let checker = DGraph::with_property(even().and(small()))
+            .with_path(vec![0, 2, 6])
+            .check();
+        assert_eq!(
+            checker.discovery("(even && small)").unwrap().into_states(),
+            vec![0, 2, 6]
+        );
+        assert_eq!(
+            checker.violated_conjunct("(even && small)").as_deref(),
+            Some("small")
+        );
+
+        DGraph::with_property(even().or(small()))
+            .with_path(vec![1, 2, 6])
+            .check()
+            .assert_properties();
+        DGraph::with_property(small().implies(even()))
+            .with_path(vec![0, 2, 7])
+            .check()
+            .assert_properties();
+        assert_eq!(
+            DGraph::with_property(small().implies(even()))
+                .with_path(vec![0, 3])
+                .check()
+                .discovery("(small => even)")
+                .unwrap()
+                .into_states(),
+            vec![0, 3]
+        );
+        assert_eq!(
+            DGraph::with_property(even().not())
+                .with_path(vec![1, 2])
+                .check()
+                .discovery("!(even)")
+                .unwrap()
+                .into_states(),
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "as the expectations differ")]
+    fn rejects_mismatched_expectations() {
+        let _ = even().and(Property::eventually("small", |_, s| *s < 5));
+    }
+
+    #[test]
+    #[should_panic(expected = "as the assumptions differ")]
+    fn rejects_mismatched_assumptions() {
+        let _ = even().or(small().assuming("even"));
+    }
+}
+
+#[cfg(test)]
+mod test_ltl {
+    use crate::test_util::dgraph::DGraph;
+    use crate::{ltl, Checker};
+
+    fn odd(_: &DGraph, s: &u8) -> bool {
+        s % 2 == 1
+    }
+
+    fn large(_: &DGraph, s: &u8) -> bool {
+        *s >= 5
+    }
+
+    #[test]
+    fn can_check_state_formulas() {
+        let property = ltl!(always (large -> !odd));
+        let name = property.name.clone();
+        DGraph::with_property(property.clone())
+            .with_path(vec![1, 6])
+            .check()
+            .assert_properties();
+        assert_eq!(
+            DGraph::with_property(property)
+                .with_path(vec![1, 6, 7])
+                .check()
+                .discovery(&name)
+                .unwrap()
+                .into_states(),
+            vec![1, 6, 7]
+        );
+
+        let property = ltl!(sometimes(odd && (!large || odd)));
+        let name = property.name.clone();
+        assert_eq!(
+            DGraph::with_property(property)
+                .with_path(vec![2, 3])
+                .check()
+                .discovery(&name)
+                .unwrap()
+                .into_states(),
+            vec![2, 3]
+        );
+    }
+
+    #[test]
+    fn can_check_leads_to() {
+        let property = ltl!(always (odd -> eventually large));
+        let name = property.name.clone();
+        DGraph::with_property(property.clone())
+            .with_path(vec![1, 2, 4, 6])
+            .with_path(vec![0, 2])
+            .check()
+            .assert_properties();
+        assert_eq!(
+            DGraph::with_property(property.clone())
+                .with_path(vec![1, 6, 3, 4])
+                .check()
+                .discovery(&name)
+                .unwrap()
+                .into_states(),
+            vec![1, 6, 3, 4]
+        );
+        assert_eq!(
+            DGraph::with_property(property)
+                .with_path(vec![1, 2, 4, 1])
+                .check()
+                .discovery(&name)
+                .unwrap()
+                .into_states(),
+            vec![1, 2, 4, 1]
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_observation {
+    use crate::test_util::dgraph::DGraph;
+    use crate::{Checker, Model, Property, UniformChooser};
+
+    fn check_all(model: DGraph, name: &'static str) -> Vec<Option<Vec<u8>>> {
+        let tens = |s: &u8| s / 10;
+        let discovery =
+            |checker: &dyn Checker<DGraph>| checker.discovery(name).map(|path| path.into_states());
+        vec![
+            discovery(&model.clone().checker().observe(tens).spawn_bfs().join()),
+            discovery(&model.clone().checker().observe(tens).spawn_dfs().join()),
+            discovery(
+                &model
+                    .checker()
+                    .observe(tens)
+                    .target_state_count(10)
+                    .spawn_simulation(0, UniformChooser)
+                    .join(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn skips_stuttering_steps() {
+        let model = DGraph::with_property(Property::sometimes("odd", |_, s| s % 2 == 1))
+            .with_path(vec![10, 11, 20]);
+        assert_eq!(
+            model
+                .clone()
+                .check()
+                .discovery("odd")
+                .unwrap()
+                .into_states(),
+            vec![10, 11]
+        );
+        assert_eq!(check_all(model, "odd"), vec![None, None, None]);
+    }
+
+    #[test]
+    fn evaluates_observable_steps() {
+        let path = Some(vec![10, 11, 30]);
+        let model = DGraph::with_property(Property::always("small", |_, s| *s < 30))
+            .with_path(vec![10, 11, 30]);
+        assert_eq!(
+            check_all(model, "small"),
+            vec![path.clone(), path.clone(), path]
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_path {
+    use super::*;
+    use crate::fingerprint;
+    use crate::test_util::linear_equation_solver::LinearEquation;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn can_build_path_from_fingerprints() {
+        let fp = |a: u8, b: u8| fingerprint(&(a, b));
+        let model = LinearEquation { a: 2, b: 10, c: 14 };
+        let fingerprints = VecDeque::from(vec![
+            fp(0, 0),
+            fp(0, 1),
+            fp(1, 1),
+            fp(2, 1), // final state
+        ]);
+        let path = Path::from_fingerprints(&model, fingerprints.clone());
+        assert_eq!(path.last_state(), &(2, 1));
+        assert_eq!(
+            path.last_state(),
+            &Path::final_state(&model, fingerprints).unwrap()
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_fingerprint_hasher {
+    use crate::test_util::linear_equation_solver::*;
+    use crate::{Checker, FingerprintHasher, Model};
+
+    const HASHERS: [FingerprintHasher; 4] = [
+        FingerprintHasher::AHash,
+        FingerprintHasher::Fx,
+        FingerprintHasher::Xxh3,
+        FingerprintHasher::Sip,
+    ];
+
+    #[test]
+    fn enumerates_the_same_states_with_each_hasher() {
+        for hasher in HASHERS {
+            let checker = LinearEquation { a: 2, b: 4, c: 7 }
+                .checker()
+                .fingerprint_hasher(hasher)
+                .spawn_bfs()
+                .join();
+            checker.assert_no_discovery("solvable");
+            assert_eq!(checker.unique_state_count(), 256 * 256, "{:?}", hasher);
+        }
+    }
+
+    #[test]
+    fn reconstructs_discoveries_with_each_hasher() {
+        for hasher in HASHERS {
+            let bfs = LinearEquation { a: 2, b: 10, c: 14 }
+                .checker()
+                .fingerprint_hasher(hasher)
+                .spawn_bfs()
+                .join();
+            assert_eq!(bfs.unique_state_count(), 12, "{:?}", hasher);
+            assert_eq!(
+                bfs.discovery("solvable").unwrap().into_actions(),
+                vec![Guess::IncreaseX, Guess::IncreaseX, Guess::IncreaseY]
+            );
+            let dfs = LinearEquation { a: 2, b: 10, c: 14 }
+                .checker()
+                .fingerprint_hasher(hasher)
+                .spawn_dfs()
+                .join();
+            assert!(dfs.discovery("solvable").is_some(), "{:?}", hasher);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_report {
+    use super::*;
+    use crate::report::{ProgressReporter, ReportData, Reporter, WriteReporter};
+    use crate::test_util::linear_equation_solver::LinearEquation;
+    use std::time::Duration;
+
+    /// Counts crashes up to a maximum.
+    struct Crashes(u8);
+    impl Model for Crashes {
+        type State = u8;
+        type Action = ();
+        fn init_states(&self) -> Vec<Self::State> {
+            vec![0]
+        }
+        fn actions(&self, crashes: &Self::State, actions: &mut Vec<Self::Action>) {
+            if *crashes < self.0 {
+                actions.push(());
+            }
+        }
+        fn next_state(&self, crashes: &Self::State, _: Self::Action) -> Option<Self::State> {
+            Some(crashes + 1)
+        }
+        fn format_action(&self, _: &Self::Action) -> String {
+            "crash".to_string()
+        }
+        fn format_state(&self, crashes: &Self::State) -> Option<String> {
+            Some(format!("{} crashes", crashes))
+        }
+        fn properties(&self) -> Vec<Property<Self>> {
+            vec![
+                Property::always("at most 1 crash", |_, crashes| *crashes <= 1),
+                Property::always("agreement", |_, crashes| *crashes < 2)
+                    .assuming("at most 1 crash"),
+                Property::sometimes("crashed", |_, crashes| *crashes > 0),
+                Property::always("no crash", |_, crashes| *crashes == 0).assuming("crashed"),
+            ]
+        }
+    }
+
+    #[test]
+    fn report_includes_assumption_status() {
+        let checker = Crashes(1).checker().spawn_bfs().join();
+        assert_eq!(
+            checker.assumption_status("agreement"),
+            Some(AssumptionStatus::Holds)
+        );
+        assert_eq!(checker.assumption_status("at most 1 crash"), None);
+
+        let mut written: Vec<u8> = Vec::new();
+        Crashes(2)
+            .checker()
+            .spawn_bfs()
+            .report(&mut WriteReporter::new(&mut written));
+        let output = String::from_utf8(written).unwrap();
+        assert!(
+            output.contains(
+                "\
+                Assumption \"at most 1 crash\" of \"agreement\" is violated, so \"agreement\" is vacuous\n\
+                Assumption \"crashed\" of \"no crash\" holds\n"
+            ),
+            "Output did not include assumptions (see test). output={:?}`",
+            output
+        );
+    }
+
+    /// Branches from 0 to either 1 then 2, or 10 then 11.
+    struct Branches;
+    impl Model for Branches {
+        type State = u8;
+        type Action = u8;
+        fn init_states(&self) -> Vec<Self::State> {
+            vec![0]
+        }
+        fn actions(&self, state: &Self::State, actions: &mut Vec<Self::Action>) {
+            match state {
+                0 => actions.extend([1, 10]),
+                1 | 10 => actions.push(state + 1),
+                _ => {}
+            }
+        }
+        fn next_state(&self, _: &Self::State, action: Self::Action) -> Option<Self::State> {
+            Some(action)
+        }
+        fn properties(&self) -> Vec<Property<Self>> {
+            vec![
+                Property::always("not 11", |_, state| *state != 11),
+                Property::always("not 2", |_, state| *state != 2).assuming("not 11"),
+            ]
+        }
+    }
+
+    #[test]
+    fn assumption_status_is_judged_on_the_discovery_path() {
+        // "not 11" is violated, but not on the path of the "not 2" counterexample.
+        let checker = Branches.checker().spawn_bfs().join();
+        assert!(checker.discovery("not 11").is_some());
+        assert_eq!(
+            checker.assumption_status("not 2"),
+            Some(AssumptionStatus::Holds)
+        );
+    }
+
+    #[test]
+    fn assumption_status_is_undetermined_when_truncated() {
+        let checker = Branches.checker().target_max_depth(2).spawn_bfs().join();
+        assert!(checker.is_done());
+        assert!(checker.is_truncated());
+        assert_eq!(
+            checker.assumption_status("not 2"),
+            Some(AssumptionStatus::Undetermined)
+        );
+
+        let checker = Branches.checker().spawn_dfs().join();
+        assert!(!checker.is_truncated());
+    }
+
+    #[test]
+    fn report_formats_discoveries_via_model() {
+        let mut written: Vec<u8> = Vec::new();
+        Crashes(2)
+            .checker()
+            .spawn_bfs()
+            .report(&mut WriteReporter::new(&mut written));
+        let output = String::from_utf8(written).unwrap();
+        assert!(
+            output.contains(
+                "\
+                Discovered \"agreement\" counterexample Path[2]:\n\
+                - crash\n\
+                - crash\n"
+            ),
+            "Output did not format actions via the model (see test). output={:?}`",
+            output
+        );
+
+        let checker = Crashes(2).checker().spawn_bfs().join();
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            checker.assert_no_discovery("agreement")
+        }));
+        let message = panic.unwrap_err().downcast::<String>().unwrap();
+        assert!(message.ends_with("- crash\n- crash\nLast state: 2 crashes\n"));
+    }
+
+    /// Counts up to a maximum, requiring that counts remain small and even.
+    struct EvenCounter(u8);
+    impl Model for EvenCounter {
+        type State = u8;
+        type Action = ();
+        fn init_states(&self) -> Vec<Self::State> {
+            vec![0]
+        }
+        fn actions(&self, count: &Self::State, actions: &mut Vec<Self::Action>) {
+            if *count < self.0 {
+                actions.push(());
+            }
+        }
+        fn next_state(&self, count: &Self::State, _: Self::Action) -> Option<Self::State> {
+            Some(count + 2)
+        }
+        fn properties(&self) -> Vec<Property<Self>> {
+            vec![Property::all(
+                "well formed",
+                vec![
+                    Property::always("even", |_, count| count % 2 == 0),
+                    Property::always("small", |_, count| *count < 4),
+                ],
+            )]
+        }
+    }
+
+    #[test]
+    fn report_names_violated_conjunct() {
+        let checker = EvenCounter(2).checker().spawn_bfs().join();
+        assert_eq!(checker.discovery("well formed"), None);
+        assert_eq!(checker.violated_conjunct("well formed"), None);
+
+        let checker = EvenCounter(4).checker().spawn_bfs().join();
+        assert_eq!(
+            checker.violated_conjunct("well formed").as_deref(),
+            Some("small")
+        );
+
+        let mut written: Vec<u8> = Vec::new();
+        EvenCounter(4)
+            .checker()
+            .spawn_bfs()
+            .report(&mut WriteReporter::new(&mut written));
+        let output = String::from_utf8(written).unwrap();
+        assert!(
+            output.contains(
+                "\
+                Discovered \"well formed\" counterexample Path[2]:\n\
+                - ()\n\
+                - ()\n\
+                Violated conjunct: \"small\"\n"
+            ),
+            "Output did not name the violated conjunct (see test). output={:?}`",
+            output
+        );
+    }
+
+    #[test]
+    fn report_includes_property_names_and_paths() {
+        // The assertions use `starts_with` to omit timing since it varies.
 
         // BFS
         let mut written: Vec<u8> = Vec::new();
@@ -776,4 +2671,175 @@ mod test_report {
             output
         );
     }
+
+    #[test]
+    fn report_includes_terminal_states() {
+        let model =
+            crate::test_util::dgraph::DGraph::with_property(Property::always("true", |_, _| true))
+                .with_path(vec![1, 2, 3])
+                .with_path(vec![1, 4]);
+        let mut written = Vec::new();
+        model
+            .clone()
+            .checker()
+            .spawn_bfs()
+            .report(&mut WriteReporter::new(&mut written));
+        let output = String::from_utf8(written).unwrap();
+        assert!(!output.contains("Terminal states"), "{}", output);
+
+        let mut written = Vec::new();
+        let checker = model
+            .checker()
+            .track_terminal_states()
+            .spawn_bfs()
+            .report(&mut WriteReporter::new(&mut written));
+        let output = String::from_utf8(written).unwrap();
+        let expected: String = checker
+            .terminal_states()
+            .unwrap()
+            .iter()
+            .map(|s| format!("- {}\n", s))
+            .collect();
+        assert!(
+            output.ends_with(&format!("Terminal states: 2\n{}", expected)),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn report_json_includes_results_discoveries_and_stats() {
+        let mut written = Vec::new();
+        Crashes(2)
+            .checker()
+            .spawn_bfs()
+            .join_and_report_json(&mut written)
+            .unwrap();
+        let mut report: serde_json::Value = serde_json::from_slice(&written).unwrap();
+        assert!(report["stats"]["duration_secs"].is_f64());
+        report["stats"]["duration_secs"] = 0.into();
+        let discovery = |classification: &str, actions: &[&str], fingerprint_path: &str| {
+            serde_json::json!({
+                "classification": classification,
+                "actions": actions,
+                "fingerprint_path": fingerprint_path,
+                "violated_conjunct": null,
+            })
+        };
+        let two_crashes = discovery(
+            "counterexample",
+            &["crash", "crash"],
+            "7983701498971690546/345081625419309864/14588920123251910466",
+        );
+        assert_eq!(
+            report,
+            serde_json::json!({
+                "done": true,
+                "passed": false,
+                "properties": [
+                    {
+                        "name": "at most 1 crash",
+                        "expectation": "always",
+                        "result": "fail",
+                        "discovery": two_crashes,
+                        "assumption": null,
+                    },
+                    {
+                        "name": "agreement",
+                        "expectation": "always",
+                        "result": "fail",
+                        "discovery": two_crashes,
+                        "assumption": { "name": "at most 1 crash", "status": "violated" },
+                    },
+                    {
+                        "name": "crashed",
+                        "expectation": "sometimes",
+                        "result": "pass",
+                        "discovery": discovery(
+                            "example",
+                            &["crash"],
+                            "7983701498971690546/345081625419309864",
+                        ),
+                        "assumption": null,
+                    },
+                    {
+                        "name": "no crash",
+                        "expectation": "always",
+                        "result": "fail",
+                        "discovery": discovery(
+                            "counterexample",
+                            &["crash"],
+                            "7983701498971690546/345081625419309864",
+                        ),
+                        "assumption": { "name": "crashed", "status": "holds" },
+                    },
+                ],
+                "estimates": [],
+                "metrics": [],
+                "stats": {
+                    "total_states": 3,
+                    "unique_states": 3,
+                    "max_depth": 3,
+                    "duration_secs": 0,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn progress_reporter_shows_rate_and_eta() {
+        let data = |total_states, done| ReportData {
+            total_states,
+            unique_states: 100,
+            max_depth: 3,
+            duration: Duration::from_secs(2),
+            done,
+        };
+        let report = |reporter: &mut ProgressReporter<Vec<u8>>, total_states, done| {
+            Reporter::<LinearEquation>::report_checking(reporter, data(total_states, done));
+        };
+
+        let mut written = Vec::new();
+        let mut reporter = ProgressReporter::new(&mut written);
+        report(&mut reporter, 500, false);
+        report(&mut reporter, 600, true);
+        assert_eq!(
+            String::from_utf8(written).unwrap(),
+            "\
+            Checking. states=500, unique=100, depth=3, states/sec=250\n\
+            Done. states=600, unique=100, depth=3, states/sec=300, sec=2\n"
+        );
+
+        let mut written = Vec::new();
+        let mut reporter = ProgressReporter::new(&mut written)
+            .target_state_count(2_000)
+            .interactive(true);
+        report(&mut reporter, 500, false);
+        report(&mut reporter, 2_000, true);
+        assert_eq!(
+            String::from_utf8(written).unwrap(),
+            "\
+            \r\x1b[2KChecking. [#####...............]  25% states=500, unique=100, depth=3, \
+            states/sec=250, eta=6s\
+            \r\x1b[2KDone. [####################] 100% states=2000, unique=100, depth=3, \
+            states/sec=1000, sec=2\n"
+        );
+    }
+
+    #[test]
+    fn progress_reporter_can_be_quiet() {
+        let mut written = Vec::new();
+        LinearEquation { a: 2, b: 10, c: 14 }
+            .checker()
+            .spawn_bfs()
+            .join_and_report(&mut ProgressReporter::new(&mut written).quiet());
+        let output = String::from_utf8(written).unwrap();
+        assert!(output.starts_with("Done. states="), "{}", output);
+        assert_eq!(output.matches("states=").count(), 1, "{}", output);
+        assert!(
+            output.contains("Discovered \"solvable\" example"),
+            "{}",
+            output
+        );
+    }
 }
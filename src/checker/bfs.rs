@@ -1,13 +1,17 @@
 //! Private module for selective re-export.
 
-use crate::checker::{Checker, EventuallyBits, Expectation, Path};
+use crate::checker::spill::{SpillOptions, SpillQueue};
+use crate::checker::{
+    with_violating_step, Checker, Deadlines, EventuallyBits, Expectation, MetricSummary, Metrics,
+    Observation, Path, Transitions, UniqueStates, VisitedMap,
+};
 use crate::job_market::JobBroker;
-use crate::{fingerprint, CheckerBuilder, CheckerVisitor, Fingerprint, Model, Property};
-use dashmap::mapref::entry::Entry;
-use dashmap::DashMap;
-use nohash_hasher::NoHashHasher;
+use crate::util::CompactState;
+use crate::{CheckerBuilder, CheckerVisitor, Fingerprint, FingerprintHasher, Model, Property};
+use dashmap::{DashMap, DashSet};
+use std::borrow::Cow;
 use std::collections::{HashMap, VecDeque};
-use std::hash::{BuildHasherDefault, Hash};
+use std::hash::Hash;
 use std::num::NonZeroUsize;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
@@ -19,17 +23,32 @@ use std::thread::JoinHandle;
 pub(crate) struct BfsChecker<M: Model> {
     // Immutable state.
     model: Arc<M>,
+    fingerprint_hasher: FingerprintHasher,
+    target_state_count: Option<NonZeroUsize>,
+    target_max_depth: Option<NonZeroUsize>,
     handles: Vec<std::thread::JoinHandle<()>>,
 
     // Mutable state.
     job_broker: JobBroker<Job<M::State>>,
     state_count: Arc<AtomicUsize>,
     max_depth: Arc<AtomicUsize>,
-    generated:
-        Arc<DashMap<Fingerprint, Option<Fingerprint>, BuildHasherDefault<NoHashHasher<u64>>>>,
-    discoveries: Arc<DashMap<&'static str, Fingerprint>>,
+    generated: Arc<VisitedMap<Option<Fingerprint>>>,
+    node_states: Arc<DashMap<Fingerprint, Fingerprint>>,
+    unique_states: Arc<UniqueStates>,
+    discoveries: Arc<DashMap<Cow<'static, str>, Fingerprint>>,
+    terminals: Option<Arc<DashSet<Fingerprint>>>,
+    metrics: Arc<Metrics<M>>,
 }
-type Job<State> = (State, Fingerprint, EventuallyBits, NonZeroUsize);
+// The fingerprint of a job identifies its node of the search, which is only distinct from the
+// state's fingerprint while `within` obligations are pending. See `Deadlines::node_fingerprint`.
+pub(super) type Job<State> = (
+    State,
+    Fingerprint,
+    EventuallyBits,
+    Deadlines,
+    NonZeroUsize,
+    Option<Fingerprint>,
+);
 
 impl<M> BfsChecker<M>
 where
@@ -38,67 +57,66 @@ where
 {
     pub(crate) fn spawn(options: CheckerBuilder<M>) -> Self {
         let model = Arc::new(options.model);
+        let fingerprint_hasher = options.fingerprint_hasher;
         let target_state_count = options.target_state_count;
         let target_max_depth = options.target_max_depth;
         let thread_count = options.thread_count;
+        let spill_options = options.spill;
         let visitor = Arc::new(options.visitor);
-        let property_count = model.properties().len();
-
-        let init_states: Vec<_> = model
-            .init_states()
-            .into_iter()
-            .filter(|s| model.within_boundary(s))
-            .collect();
-        let state_count = Arc::new(AtomicUsize::new(init_states.len()));
+        let observation = options.observation;
+        let metrics = Arc::new(Metrics::new(options.metrics));
+        // The same properties are evaluated throughout, so that memoized conditions keep their
+        // caches. See `Property::memoize_by`.
+        let properties = Arc::new(model.properties());
+        let property_count = properties.len();
+
+        let generated = Arc::new(VisitedMap::new(thread_count));
+        let unique_states = Arc::new(UniqueStates::new(&properties, thread_count));
+        let pending = init_jobs(&*model, fingerprint_hasher, &generated, &unique_states);
+        let node_states = Arc::new(DashMap::default());
+        let deadline_bounds = Arc::new(DashMap::default());
+        let state_count = Arc::new(AtomicUsize::new(pending.len()));
         let max_depth = Arc::new(AtomicUsize::new(0));
-        let generated = Arc::new({
-            let generated = DashMap::default();
-            for s in &init_states {
-                generated.insert(fingerprint(s), None);
-            }
-            generated
-        });
-        let ebits = {
-            let mut ebits = EventuallyBits::new();
-            for (i, p) in model.properties().iter().enumerate() {
-                if let Property {
-                    expectation: Expectation::Eventually,
-                    ..
-                } = p
-                {
-                    ebits.insert(i);
-                }
-            }
-            ebits
-        };
-        let pending: VecDeque<_> = init_states
-            .into_iter()
-            .map(|s| {
-                let fp = fingerprint(&s);
-                (s, fp, ebits.clone(), NonZeroUsize::new(1).unwrap())
-            })
-            .collect();
         let discoveries = Arc::new(DashMap::default());
+        let terminals = options
+            .track_terminal_states
+            .then(|| Arc::new(DashSet::default()));
         let mut handles = Vec::new();
 
         let mut job_broker = JobBroker::new(thread_count);
         job_broker.push(pending);
         for t in 0..thread_count {
             let model = Arc::clone(&model);
+            let properties = Arc::clone(&properties);
             let visitor = Arc::clone(&visitor);
+            let observation = observation.clone();
+            let metrics = Arc::clone(&metrics);
             let mut job_broker = job_broker.clone();
             let state_count = Arc::clone(&state_count);
             let max_depth = Arc::clone(&max_depth);
             let generated = Arc::clone(&generated);
+            let node_states = Arc::clone(&node_states);
+            let unique_states = Arc::clone(&unique_states);
+            let deadline_bounds = Arc::clone(&deadline_bounds);
             let discoveries = Arc::clone(&discoveries);
+            let terminals = terminals.clone();
+            let spill_options = spill_options.clone();
             handles.push(
                 std::thread::Builder::new()
                     .name(format!("checker-{}", t))
                     .spawn(move || {
                         log::debug!("{}: Thread started.", t);
                         let mut pending = VecDeque::new();
+                        let mut spill = spill_options
+                            .as_ref()
+                            .map(|options| (options, SpillQueue::new(options.dir.clone())));
                         loop {
                             // Step 1: Do work.
+                            if let Some((options, spill)) = &mut spill {
+                                if pending.is_empty() && !spill.is_empty() {
+                                    unspill(options, spill, &mut pending);
+                                }
+                            }
                             if pending.is_empty() {
                                 pending = {
                                     let jobs = job_broker.pop();
@@ -114,13 +132,21 @@ where
                                     jobs
                                 };
                             }
-                            Self::check_block(
+                            check_block::<M>(
                                 &model,
+                                &properties,
+                                fingerprint_hasher,
                                 &state_count,
                                 &generated,
+                                &node_states,
+                                &deadline_bounds,
+                                &unique_states,
                                 &mut pending,
                                 &discoveries,
+                                terminals.as_deref(),
                                 &visitor,
+                                &observation,
+                                &metrics,
                                 1500,
                                 target_max_depth,
                                 &max_depth,
@@ -148,6 +174,13 @@ where
                             if pending.len() > 1 && thread_count > 1 {
                                 job_broker.split_and_push(&mut pending);
                             }
+
+                            // Step 3: Spill work that does not fit in memory.
+                            if let Some((options, spill)) = &mut spill {
+                                if pending.len() > options.max_pending {
+                                    spill_newest(options, spill, &mut pending);
+                                }
+                            }
                         }
                     })
                     .expect("Failed to spawn a thread"),
@@ -155,171 +188,306 @@ where
         }
         BfsChecker {
             model,
+            fingerprint_hasher,
+            target_state_count,
+            target_max_depth,
             handles,
             job_broker,
             state_count,
             max_depth,
             generated,
+            node_states,
+            unique_states,
             discoveries,
+            terminals,
+            metrics,
         }
     }
+}
 
-    #[allow(clippy::too_many_arguments)]
-    fn check_block(
-        model: &M,
-        state_count: &AtomicUsize,
-        generated: &DashMap<
-            Fingerprint,
-            Option<Fingerprint>,
-            BuildHasherDefault<NoHashHasher<u64>>,
-        >,
-        pending: &mut VecDeque<Job<M::State>>,
-        discoveries: &DashMap<&'static str, Fingerprint>,
-        visitor: &Option<Box<dyn CheckerVisitor<M> + Send + Sync>>,
-        mut max_count: usize,
-        target_max_depth: Option<NonZeroUsize>,
-        global_max_depth: &AtomicUsize,
-    ) {
-        let properties = model.properties();
-
-        let mut current_max_depth = global_max_depth.load(Ordering::Relaxed);
-        let mut actions = Vec::new();
-        loop {
-            // Done if reached max count.
-            if max_count == 0 {
-                return;
+/// Returns a job for each initial state within the boundary, recording each as generated.
+pub(super) fn init_jobs<M>(
+    model: &M,
+    fingerprint_hasher: FingerprintHasher,
+    generated: &VisitedMap<Option<Fingerprint>>,
+    unique_states: &UniqueStates,
+) -> VecDeque<Job<M::State>>
+where
+    M: Model,
+    M::State: Hash,
+{
+    let ebits = {
+        let mut ebits = EventuallyBits::new();
+        for (i, p) in model.properties().iter().enumerate() {
+            if let Property {
+                expectation: Expectation::Eventually,
+                ..
+            } = p
+            {
+                ebits.insert(i);
             }
-            max_count -= 1;
+        }
+        ebits
+    };
+    model
+        .init_states()
+        .into_iter()
+        .filter(|s| model.within_boundary(s))
+        .map(|s| {
+            let fp = fingerprint_hasher.fingerprint(&s);
+            generated.insert(fp, None);
+            unique_states.insert(fp);
+            (
+                s,
+                fp,
+                ebits.clone(),
+                Deadlines::default(),
+                NonZeroUsize::new(1).unwrap(),
+                None,
+            )
+        })
+        .collect()
+}
 
-            // Done if none pending.
-            let (state, state_fp, mut ebits, max_depth) = match pending.pop_back() {
+#[allow(clippy::too_many_arguments)]
+pub(super) fn check_block<M>(
+    model: &M,
+    properties: &[Property<M>],
+    fingerprint_hasher: FingerprintHasher,
+    state_count: &AtomicUsize,
+    generated: &VisitedMap<Option<Fingerprint>>,
+    node_states: &DashMap<Fingerprint, Fingerprint>,
+    deadline_bounds: &DashMap<Fingerprint, Vec<usize>>,
+    unique_states: &UniqueStates,
+    pending: &mut VecDeque<Job<M::State>>,
+    discoveries: &DashMap<Cow<'static, str>, Fingerprint>,
+    terminals: Option<&DashSet<Fingerprint>>,
+    visitor: &Option<Box<dyn CheckerVisitor<M> + Send + Sync>>,
+    observation: &Option<Observation<M::State>>,
+    metrics: &Metrics<M>,
+    mut max_count: usize,
+    target_max_depth: Option<NonZeroUsize>,
+    global_max_depth: &AtomicUsize,
+) where
+    M: Model,
+    M::State: Hash,
+{
+    let mut current_max_depth = global_max_depth.load(Ordering::Relaxed);
+    let mut actions = Vec::new();
+    loop {
+        // Done if reached max count.
+        if max_count == 0 {
+            return;
+        }
+        max_count -= 1;
+
+        // Done if none pending.
+        let (state, state_fp, mut ebits, mut deadlines, max_depth, prev_observation) =
+            match pending.pop_back() {
                 None => return,
                 Some(pair) => pair,
             };
 
-            if max_depth.get() > current_max_depth {
-                let _ = global_max_depth.compare_exchange(
-                    current_max_depth,
-                    max_depth.get(),
-                    Ordering::Relaxed,
-                    Ordering::Relaxed,
-                );
-                current_max_depth = max_depth.get();
-            }
+        if max_depth.get() > current_max_depth {
+            global_max_depth.fetch_max(max_depth.get(), Ordering::Relaxed);
+            current_max_depth = max_depth.get();
+        }
 
-            if let Some(target_max_depth) = target_max_depth {
-                if max_depth >= target_max_depth {
-                    log::trace!("Skipping state as past max depth {}", max_depth);
-                    continue;
-                }
+        if let Some(target_max_depth) = target_max_depth {
+            if max_depth >= target_max_depth {
+                log::trace!("Skipping state as past max depth {}", max_depth);
+                continue;
             }
+        }
 
-            if let Some(visitor) = visitor {
-                visitor.visit(model, reconstruct_path(model, generated, state_fp));
-            }
+        if let Some(visitor) = visitor {
+            visitor.visit(
+                model,
+                reconstruct_path(model, fingerprint_hasher, generated, node_states, state_fp),
+            );
+        }
+        metrics.record(model, &state);
 
-            // Done if discoveries found for all properties.
-            let mut is_awaiting_discoveries = false;
-            for (i, property) in properties.iter().enumerate() {
-                if discoveries.contains_key(property.name) {
-                    continue;
+        // Properties are only evaluated for observable transitions.
+        let state_observation = observation.as_ref().map(|observe| observe(&state));
+        let is_stuttering = state_observation.is_some() && state_observation == prev_observation;
+
+        // Done if discoveries found for all properties.
+        let mut is_awaiting_discoveries = false;
+        for (i, property) in properties.iter().enumerate() {
+            if discoveries.contains_key(&property.name) {
+                continue;
+            }
+            if is_stuttering {
+                // The observation is unchanged, so the property evaluates as it did for the
+                // predecessor.
+                is_awaiting_discoveries = true;
+                continue;
+            }
+            match property {
+                Property {
+                    expectation: Expectation::Always,
+                    condition: always,
+                    ..
+                } => {
+                    if property.transition.is_some() {
+                        // Evaluated for each step taken from the state.
+                        is_awaiting_discoveries = true;
+                    } else if !always(model, &state) {
+                        // Races other threads, but that's fine.
+                        discoveries.insert(property.name.clone(), state_fp);
+                    } else {
+                        is_awaiting_discoveries = true;
+                    }
                 }
-                match property {
-                    Property {
-                        expectation: Expectation::Always,
-                        condition: always,
-                        ..
-                    } => {
-                        if !always(model, &state) {
-                            // Races other threads, but that's fine.
-                            discoveries.insert(property.name, state_fp);
-                        } else {
-                            is_awaiting_discoveries = true;
-                        }
+                Property {
+                    expectation: Expectation::Sometimes,
+                    condition: sometimes,
+                    ..
+                } => {
+                    if sometimes(model, &state) {
+                        // Races other threads, but that's fine.
+                        discoveries.insert(property.name.clone(), state_fp);
+                    } else {
+                        is_awaiting_discoveries = true;
                     }
-                    Property {
-                        expectation: Expectation::Sometimes,
-                        condition: sometimes,
-                        ..
-                    } => {
-                        if sometimes(model, &state) {
-                            // Races other threads, but that's fine.
-                            discoveries.insert(property.name, state_fp);
-                        } else {
-                            is_awaiting_discoveries = true;
-                        }
+                }
+                Property {
+                    expectation: Expectation::Eventually,
+                    condition: eventually,
+                    ..
+                } => {
+                    // The checker early exits after finding discoveries for every property,
+                    // and "eventually" property discoveries are only identifid at terminal
+                    // states, so if we are here it means we are still awaiting a corresponding
+                    // discovery regardless of whether the eventually property is now satisfied
+                    // (i.e. it might be falsifiable via a different path).
+                    is_awaiting_discoveries = true;
+                    if eventually(model, &state) {
+                        ebits.remove(i);
                     }
-                    Property {
-                        expectation: Expectation::Eventually,
-                        condition: eventually,
-                        ..
-                    } => {
-                        // The checker early exits after finding discoveries for every property,
-                        // and "eventually" property discoveries are only identifid at terminal
-                        // states, so if we are here it means we are still awaiting a corresponding
-                        // discovery regardless of whether the eventually property is now satisfied
-                        // (i.e. it might be falsifiable via a different path).
+                }
+                Property {
+                    expectation: Expectation::Within,
+                    ..
+                } => {
+                    if !deadlines.advance(i, property, model, &state) {
+                        // Races other threads, but that's fine.
+                        discoveries.insert(property.name.clone(), state_fp);
+                    } else {
                         is_awaiting_discoveries = true;
-                        if eventually(model, &state) {
-                            ebits.remove(i);
-                        }
                     }
                 }
+                Property {
+                    expectation: Expectation::LeadsTo,
+                    ..
+                } => {
+                    // Only identified at terminal states and upon closing cycles.
+                    is_awaiting_discoveries = true;
+                    deadlines.respond(i, property, model, &state, max_depth.get());
+                }
+                Property {
+                    expectation: Expectation::Terminal,
+                    ..
+                } => {
+                    // Only evaluated once the state is known to be terminal.
+                    is_awaiting_discoveries = true;
+                }
             }
-            if !is_awaiting_discoveries {
-                return;
+        }
+        if !is_awaiting_discoveries {
+            return;
+        }
+
+        // Otherwise enqueue newly generated states (with related metadata).
+        let mut is_terminal = true;
+        let mut transitions = Transitions::new(model, &state, properties, |p| {
+            !is_stuttering && !discoveries.contains_key(&p.name)
+        });
+        model.actions(&state, &mut actions);
+        for action in actions.drain(..) {
+            let transition_action = transitions.next_action();
+            let next_state = match model.next_state(&state, action) {
+                None => continue,
+                Some(next_state) => next_state,
+            };
+            for property in transitions.violated(model, &state, &transition_action, &next_state) {
+                // Races other threads, but that's fine.
+                discoveries.insert(property.name.clone(), state_fp);
             }
 
-            // Otherwise enqueue newly generated states (with related metadata).
-            let mut is_terminal = true;
-            model.actions(&state, &mut actions);
-            let next_states = actions.drain(..).flat_map(|a| model.next_state(&state, a));
-            for next_state in next_states {
-                // Skip if outside boundary.
-                if !model.within_boundary(&next_state) {
-                    continue;
+            // Skip if outside boundary.
+            if !model.within_boundary(&next_state) {
+                continue;
+            }
+            state_count.fetch_add(1, Ordering::Relaxed);
+
+            // Skip if already generated.
+            //
+            // FIXME: we should really include ebits in the fingerprint here --
+            // it is possible to arrive at a DAG join with two different ebits
+            // values, and subsequently treat the fact that some eventually
+            // property held on the path leading to the first visit as meaning
+            // that it holds in the path leading to the second visit -- another
+            // possible false-negative.
+            let next_fingerprint = fingerprint_hasher.fingerprint(&next_state);
+            if !deadlines.tighten(fingerprint_hasher, next_fingerprint, deadline_bounds) {
+                let path = ancestors(generated, node_states, state_fp);
+                for i in deadlines.cycle(max_depth.get(), next_fingerprint, path) {
+                    // Races other threads, but that's fine.
+                    discoveries
+                        .entry(properties[i].name.clone())
+                        .or_insert(state_fp);
                 }
-                state_count.fetch_add(1, Ordering::Relaxed);
-
-                // Skip if already generated.
-                //
-                // FIXME: we should really include ebits in the fingerprint here --
-                // it is possible to arrive at a DAG join with two different ebits
-                // values, and subsequently treat the fact that some eventually
-                // property held on the path leading to the first visit as meaning
-                // that it holds in the path leading to the second visit -- another
-                // possible false-negative.
-                let next_fingerprint = fingerprint(&next_state);
-                if let Entry::Vacant(next_entry) = generated.entry(next_fingerprint) {
-                    next_entry.insert(Some(state_fp));
-                } else {
-                    // FIXME: arriving at an already-known state may be a loop (in which case it
-                    // could, in a fancier implementation, be considered a terminal state for
-                    // purposes of eventually-property checking) but it might also be a join in
-                    // a DAG, which makes it non-terminal. These cases can be disambiguated (at
-                    // some cost), but for now we just _don't_ treat them as terminal, and tell
-                    // users they need to explicitly ensure model path-acyclicality when they're
-                    // using eventually properties (using a boundary or empty actions or
-                    // whatever).
-                    is_terminal = false;
-                    continue;
+                is_terminal = false;
+                continue;
+            }
+            let next_node = deadlines.node_fingerprint(fingerprint_hasher, next_fingerprint);
+            if next_node != next_fingerprint {
+                node_states.insert(next_node, next_fingerprint);
+            }
+            if !generated.insert(next_node, Some(state_fp)) {
+                // FIXME: arriving at an already-known state may be a loop (in which case it
+                // could, in a fancier implementation, be considered a terminal state for
+                // purposes of eventually-property checking) but it might also be a join in
+                // a DAG, which makes it non-terminal. These cases can be disambiguated (at
+                // some cost), but for now we just _don't_ treat them as terminal, and tell
+                // users they need to explicitly ensure model path-acyclicality when they're
+                // using eventually properties (using a boundary or empty actions or
+                // whatever). Only `leads_to` properties look for a cycle along the path.
+                let path = ancestors(generated, node_states, state_fp);
+                for i in deadlines.cycle(max_depth.get(), next_fingerprint, path) {
+                    // Races other threads, but that's fine.
+                    discoveries
+                        .entry(properties[i].name.clone())
+                        .or_insert(state_fp);
                 }
-
-                // Otherwise further checking is applicable.
                 is_terminal = false;
-                pending.push_front((
-                    next_state,
-                    next_fingerprint,
-                    ebits.clone(),
-                    NonZeroUsize::new(max_depth.get() + 1).unwrap(),
-                ));
+                continue;
             }
-            if is_terminal {
-                for (i, property) in properties.iter().enumerate() {
-                    if ebits.contains(i) {
-                        // Races other threads, but that's fine.
-                        discoveries.insert(property.name, state_fp);
-                    }
+            unique_states.insert(next_fingerprint);
+
+            // Otherwise further checking is applicable.
+            is_terminal = false;
+            pending.push_front((
+                next_state,
+                next_node,
+                ebits.clone(),
+                deadlines.clone(),
+                NonZeroUsize::new(max_depth.get() + 1).unwrap(),
+                state_observation,
+            ));
+        }
+        if is_terminal {
+            if let Some(terminals) = terminals {
+                terminals.insert(state_fp);
+            }
+            for (i, property) in properties.iter().enumerate() {
+                let is_violated_terminal = property.expectation == Expectation::Terminal
+                    && !(property.condition)(model, &state);
+                if ebits.contains(i) || deadlines.is_pending(i) || is_violated_terminal {
+                    // Races other threads, but that's fine.
+                    discoveries.insert(property.name.clone(), state_fp);
                 }
             }
         }
@@ -340,25 +508,50 @@ where
     }
 
     fn unique_state_count(&self) -> usize {
-        self.generated.len()
+        self.unique_states.len(&self.generated)
     }
 
     fn max_depth(&self) -> usize {
         self.max_depth.load(Ordering::Relaxed)
     }
 
-    fn discoveries(&self) -> HashMap<&'static str, Path<M::State, M::Action>> {
+    fn discoveries(&self) -> HashMap<Cow<'static, str>, Path<M::State, M::Action>> {
         self.discoveries
             .iter()
             .map(|mapref| {
                 (
-                    <&'static str>::clone(mapref.key()),
-                    reconstruct_path(self.model(), &self.generated, *mapref.value()),
+                    mapref.key().clone(),
+                    with_violating_step(
+                        self.model(),
+                        mapref.key(),
+                        reconstruct_path(
+                            self.model(),
+                            self.fingerprint_hasher,
+                            &self.generated,
+                            &self.node_states,
+                            *mapref.value(),
+                        ),
+                    ),
                 )
             })
             .collect()
     }
 
+    fn terminal_states(&self) -> Option<Vec<M::State>> {
+        let terminals = self.terminals.as_ref()?;
+        Some(terminal_states(
+            self.model(),
+            self.fingerprint_hasher,
+            &self.generated,
+            &self.node_states,
+            terminals,
+        ))
+    }
+
+    fn metrics(&self) -> Vec<MetricSummary> {
+        self.metrics.summaries()
+    }
+
     fn handles(&mut self) -> Vec<JoinHandle<()>> {
         std::mem::take(&mut self.handles)
     }
@@ -366,11 +559,134 @@ where
     fn is_done(&self) -> bool {
         self.job_broker.is_closed() || self.discoveries.len() == self.model.properties().len()
     }
+
+    fn is_truncated(&self) -> bool {
+        self.target_state_count
+            .is_some_and(|target| target.get() <= self.state_count())
+            || self
+                .target_max_depth
+                .is_some_and(|target| target.get() <= self.max_depth())
+    }
+}
+
+/// Moves the most recently generated jobs to the spill queue, leaving half of the permitted
+/// number in memory.
+pub(crate) fn spill_newest<State>(
+    options: &SpillOptions<State>,
+    spill: &mut SpillQueue,
+    pending: &mut VecDeque<Job<State>>,
+) {
+    let count = pending.len() - options.max_pending / 2;
+    log::debug!("Spilling {} pending states. spilled={}", count, spill.len());
+    // New jobs are pushed to the front, so the oldest of these is spilled first.
+    for job in pending.drain(..count).rev() {
+        spill
+            .push_with(|buf| encode_job(options, &job, buf))
+            .expect("Failed to spill pending states");
+    }
+}
+
+/// Moves up to half of the permitted number of jobs from the spill queue to memory.
+pub(crate) fn unspill<State>(
+    options: &SpillOptions<State>,
+    spill: &mut SpillQueue,
+    pending: &mut VecDeque<Job<State>>,
+) {
+    for _ in 0..options.max_pending.div_ceil(2) {
+        match spill
+            .pop_with(|buf| decode_job(options, buf))
+            .expect("Failed to read spilled states")
+        {
+            // Jobs are popped from the back, so the oldest is pushed to the back.
+            Some(job) => pending.push_front(job),
+            None => break,
+        }
+    }
+}
+
+fn encode_job<State>(options: &SpillOptions<State>, job: &Job<State>, buf: &mut Vec<u8>) {
+    let (state, fp, ebits, deadlines, depth, observation) = job;
+    (options.encode)(state, buf);
+    fp.get().encode(buf);
+    ebits.iter().collect::<Vec<_>>().encode(buf);
+    deadlines.within.encode(buf);
+    deadlines.leads_to.encode(buf);
+    depth.get().encode(buf);
+    observation.map(Fingerprint::get).encode(buf);
+}
+
+fn decode_job<State>(options: &SpillOptions<State>, buf: &mut &[u8]) -> Option<Job<State>> {
+    let state = (options.decode)(buf)?;
+    let fp = Fingerprint::new(u64::decode(buf)?)?;
+    let mut ebits = EventuallyBits::new();
+    for i in Vec::<usize>::decode(buf)? {
+        ebits.insert(i);
+    }
+    let deadlines = Deadlines {
+        within: CompactState::decode(buf)?,
+        leads_to: CompactState::decode(buf)?,
+    };
+    let depth = NonZeroUsize::new(usize::decode(buf)?)?;
+    let observation = match Option::<u64>::decode(buf)? {
+        Some(observation) => Some(Fingerprint::new(observation)?),
+        None => None,
+    };
+    Some((state, fp, ebits, deadlines, depth, observation))
 }
 
-fn reconstruct_path<M>(
+/// Reconstructs the terminal states, ordered by fingerprint.
+pub(super) fn terminal_states<M>(
     model: &M,
-    generated: &DashMap<Fingerprint, Option<Fingerprint>, BuildHasherDefault<NoHashHasher<u64>>>,
+    fingerprint_hasher: FingerprintHasher,
+    generated: &VisitedMap<Option<Fingerprint>>,
+    node_states: &DashMap<Fingerprint, Fingerprint>,
+    terminals: &DashSet<Fingerprint>,
+) -> Vec<M::State>
+where
+    M: Model,
+    M::State: Hash,
+{
+    // A state may be terminal for several nodes that differ in their pending obligations.
+    let mut fingerprints: Vec<_> = terminals
+        .iter()
+        .map(|node| (state_fingerprint(node_states, *node), *node))
+        .collect();
+    fingerprints.sort_unstable();
+    fingerprints.dedup_by_key(|(state_fp, _)| *state_fp);
+    fingerprints
+        .into_iter()
+        .filter_map(|(_, node)| {
+            reconstruct_path(model, fingerprint_hasher, generated, node_states, node)
+                .into_states()
+                .pop()
+        })
+        .collect()
+}
+
+/// Returns the fingerprint of the state of a search node. See [`Job`].
+pub(super) fn state_fingerprint(
+    node_states: &DashMap<Fingerprint, Fingerprint>,
+    node: Fingerprint,
+) -> Fingerprint {
+    node_states.get(&node).map_or(node, |state_fp| *state_fp)
+}
+
+/// Yields the fingerprints of the states along the path to a node, from the node back to the
+/// initial state.
+pub(super) fn ancestors<'a>(
+    generated: &'a VisitedMap<Option<Fingerprint>>,
+    node_states: &'a DashMap<Fingerprint, Fingerprint>,
+    node: Fingerprint,
+) -> impl Iterator<Item = Fingerprint> + 'a {
+    std::iter::successors(Some(node), |node| generated.get(*node).flatten())
+        .map(|node| state_fingerprint(node_states, node))
+}
+
+pub(super) fn reconstruct_path<M>(
+    model: &M,
+    fingerprint_hasher: FingerprintHasher,
+    generated: &VisitedMap<Option<Fingerprint>>,
+    node_states: &DashMap<Fingerprint, Fingerprint>,
     fp: Fingerprint,
 ) -> Path<M::State, M::Action>
 where
@@ -384,19 +700,14 @@ where
 
     let mut fingerprints = VecDeque::new();
     let mut next_fp = fp;
-    while let Some(source) = generated.get(&next_fp) {
-        match *source {
-            Some(prev_fingerprint) => {
-                fingerprints.push_front(next_fp);
-                next_fp = prev_fingerprint;
-            }
-            None => {
-                fingerprints.push_front(next_fp);
-                break;
-            }
+    while let Some(source) = generated.get(next_fp) {
+        fingerprints.push_front(state_fingerprint(node_states, next_fp));
+        match source {
+            Some(prev_fingerprint) => next_fp = prev_fingerprint,
+            None => break,
         }
     }
-    Path::from_fingerprints(model, fingerprints)
+    Path::from_fingerprints_with_hasher(model, fingerprints, fingerprint_hasher)
 }
 
 #[cfg(test)]
@@ -466,6 +777,29 @@ mod test {
         );
     }
 
+    #[test]
+    fn can_spill_pending_states() {
+        let dir = std::env::temp_dir().join(format!("stateright-bfs-{}", std::process::id()));
+        for thread_count in [1, 2] {
+            let checker = LinearEquation { a: 2, b: 4, c: 7 }
+                .checker()
+                .spill_to_disk(&dir, 16)
+                .threads(thread_count)
+                .spawn_bfs()
+                .join();
+            checker.assert_no_discovery("solvable");
+            assert_eq!(checker.unique_state_count(), 256 * 256);
+
+            let checker = LinearEquation { a: 2, b: 10, c: 14 }
+                .checker()
+                .spill_to_disk(&dir, 16)
+                .threads(thread_count)
+                .spawn_bfs()
+                .join();
+            checker.assert_properties();
+        }
+    }
+
     // test that the checker shuts down all threads properly after a checker thread encounters a
     // panic in the model execution.
     #[test]
@@ -1,12 +1,15 @@
 //! Private module for selective re-export.
 
-use crate::checker::{Checker, EventuallyBits, Expectation, Path};
+use crate::checker::{
+    with_violating_step, Checker, Deadlines, EventuallyBits, Expectation, MetricSummary, Metrics,
+    Observation, Path, Transitions, UniqueStates, VisitedMap,
+};
 use crate::job_market::JobBroker;
-use crate::{fingerprint, CheckerBuilder, CheckerVisitor, Fingerprint, Model, Property};
-use dashmap::{DashMap, DashSet};
-use nohash_hasher::NoHashHasher;
+use crate::{CheckerBuilder, CheckerVisitor, Fingerprint, FingerprintHasher, Model, Property};
+use dashmap::DashMap;
+use std::borrow::Cow;
 use std::collections::{HashMap, VecDeque};
-use std::hash::{BuildHasherDefault, Hash};
+use std::hash::Hash;
 use std::num::NonZeroUsize;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
@@ -18,16 +21,29 @@ use std::thread::JoinHandle;
 pub(crate) struct DfsChecker<M: Model> {
     // Immutable state.
     model: Arc<M>,
+    fingerprint_hasher: FingerprintHasher,
+    target_state_count: Option<NonZeroUsize>,
+    target_max_depth: Option<NonZeroUsize>,
     handles: Vec<std::thread::JoinHandle<()>>,
 
     // Mutable state.
     job_broker: JobBroker<Job<M::State>>,
     state_count: Arc<AtomicUsize>,
     max_depth: Arc<AtomicUsize>,
-    generated: Arc<DashSet<Fingerprint, BuildHasherDefault<NoHashHasher<u64>>>>,
-    discoveries: Arc<DashMap<&'static str, Vec<Fingerprint>>>,
+    generated: Arc<VisitedMap<()>>,
+    unique_states: Arc<UniqueStates>,
+    discoveries: Arc<DashMap<Cow<'static, str>, Vec<Fingerprint>>>,
+    terminals: Option<Arc<DashMap<Fingerprint, Vec<Fingerprint>>>>,
+    metrics: Arc<Metrics<M>>,
 }
-type Job<State> = (State, Vec<Fingerprint>, EventuallyBits, NonZeroUsize);
+type Job<State> = (
+    State,
+    Vec<Fingerprint>,
+    EventuallyBits,
+    Deadlines,
+    NonZeroUsize,
+    Option<Fingerprint>,
+);
 
 impl<M> DfsChecker<M>
 where
@@ -36,12 +52,18 @@ where
 {
     pub(crate) fn spawn(options: CheckerBuilder<M>) -> Self {
         let model = Arc::new(options.model);
+        let fingerprint_hasher = options.fingerprint_hasher;
         let symmetry = options.symmetry;
         let target_state_count = options.target_state_count;
         let target_max_depth = options.target_max_depth;
         let thread_count = options.thread_count;
         let visitor = Arc::new(options.visitor);
-        let property_count = model.properties().len();
+        let observation = options.observation;
+        let metrics = Arc::new(Metrics::new(options.metrics));
+        // The same properties are evaluated throughout, so that memoized conditions keep their
+        // caches. See `Property::memoize_by`.
+        let properties = Arc::new(model.properties());
+        let property_count = properties.len();
 
         let init_states: Vec<_> = model
             .init_states()
@@ -50,14 +72,17 @@ where
             .collect();
         let state_count = Arc::new(AtomicUsize::new(init_states.len()));
         let max_depth = Arc::new(AtomicUsize::new(0));
+        let unique_states = Arc::new(UniqueStates::new(&properties, thread_count));
         let generated = Arc::new({
-            let generated = DashSet::default();
+            let generated = VisitedMap::new(thread_count);
             for s in &init_states {
-                if let Some(representative) = symmetry {
-                    generated.insert(fingerprint(&representative(s)));
+                let fp = if let Some(representative) = symmetry {
+                    fingerprint_hasher.fingerprint(&representative(s))
                 } else {
-                    generated.insert(fingerprint(s));
-                }
+                    fingerprint_hasher.fingerprint(s)
+                };
+                generated.insert(fp, ());
+                unique_states.insert(fp);
             }
             generated
         });
@@ -77,23 +102,40 @@ where
         let pending: VecDeque<_> = init_states
             .into_iter()
             .map(|s| {
-                let fp = fingerprint(&s);
-                (s, vec![fp], ebits.clone(), NonZeroUsize::new(1).unwrap())
+                let fp = fingerprint_hasher.fingerprint(&s);
+                (
+                    s,
+                    vec![fp],
+                    ebits.clone(),
+                    Deadlines::default(),
+                    NonZeroUsize::new(1).unwrap(),
+                    None,
+                )
             })
             .collect();
+        let deadline_bounds = Arc::new(DashMap::default());
         let discoveries = Arc::new(DashMap::default());
+        let terminals = options
+            .track_terminal_states
+            .then(|| Arc::new(DashMap::default()));
         let mut handles = Vec::new();
 
         let mut job_broker = JobBroker::new(thread_count);
         job_broker.push(pending);
         for t in 0..thread_count {
             let model = Arc::clone(&model);
+            let properties = Arc::clone(&properties);
             let visitor = Arc::clone(&visitor);
+            let observation = observation.clone();
+            let metrics = Arc::clone(&metrics);
             let mut job_broker = job_broker.clone();
             let state_count = Arc::clone(&state_count);
             let max_depth = Arc::clone(&max_depth);
             let generated = Arc::clone(&generated);
+            let deadline_bounds = Arc::clone(&deadline_bounds);
+            let unique_states = Arc::clone(&unique_states);
             let discoveries = Arc::clone(&discoveries);
+            let terminals = terminals.clone();
             handles.push(
                 std::thread::Builder::new()
                     .name(format!("checker-{}", t))
@@ -119,11 +161,18 @@ where
                             }
                             Self::check_block(
                                 &model,
+                                &properties,
+                                fingerprint_hasher,
                                 &state_count,
                                 &generated,
+                                &deadline_bounds,
+                                &unique_states,
                                 &mut pending,
                                 &discoveries,
+                                terminals.as_deref(),
                                 &visitor,
+                                &observation,
+                                &metrics,
                                 1500,
                                 target_max_depth,
                                 &max_depth,
@@ -159,12 +208,18 @@ where
         }
         DfsChecker {
             model,
+            fingerprint_hasher,
+            target_state_count,
+            target_max_depth,
             handles,
             job_broker,
             state_count,
             max_depth,
             generated,
+            unique_states,
             discoveries,
+            terminals,
+            metrics,
         }
     }
 
@@ -172,18 +227,23 @@ where
     #[allow(clippy::type_complexity)]
     fn check_block(
         model: &M,
+        properties: &[Property<M>],
+        fingerprint_hasher: FingerprintHasher,
         state_count: &AtomicUsize,
-        generated: &DashSet<Fingerprint, BuildHasherDefault<NoHashHasher<u64>>>,
+        generated: &VisitedMap<()>,
+        deadline_bounds: &DashMap<Fingerprint, Vec<usize>>,
+        unique_states: &UniqueStates,
         pending: &mut VecDeque<Job<M::State>>,
-        discoveries: &DashMap<&'static str, Vec<Fingerprint>>,
+        discoveries: &DashMap<Cow<'static, str>, Vec<Fingerprint>>,
+        terminals: Option<&DashMap<Fingerprint, Vec<Fingerprint>>>,
         visitor: &Option<Box<dyn CheckerVisitor<M> + Send + Sync>>,
+        observation: &Option<Observation<M::State>>,
+        metrics: &Metrics<M>,
         mut max_count: usize,
         target_max_depth: Option<NonZeroUsize>,
         global_max_depth: &AtomicUsize,
         symmetry: Option<fn(&M::State) -> M::State>,
     ) {
-        let properties = model.properties();
-
         let mut current_max_depth = global_max_depth.load(Ordering::Relaxed);
         let mut actions = Vec::new();
         loop {
@@ -194,18 +254,14 @@ where
             max_count -= 1;
 
             // Done if none pending.
-            let (state, fingerprints, mut ebits, max_depth) = match pending.pop_back() {
-                None => return,
-                Some(pair) => pair,
-            };
+            let (state, fingerprints, mut ebits, mut deadlines, max_depth, prev_observation) =
+                match pending.pop_back() {
+                    None => return,
+                    Some(pair) => pair,
+                };
 
             if max_depth.get() > current_max_depth {
-                let _ = global_max_depth.compare_exchange(
-                    current_max_depth,
-                    max_depth.get(),
-                    Ordering::Relaxed,
-                    Ordering::Relaxed,
-                );
+                global_max_depth.fetch_max(max_depth.get(), Ordering::Relaxed);
                 current_max_depth = max_depth.get();
             }
 
@@ -218,14 +274,30 @@ where
             if let Some(visitor) = visitor {
                 visitor.visit(
                     model,
-                    Path::from_fingerprints(model, VecDeque::from(fingerprints.clone())),
+                    Path::from_fingerprints_with_hasher(
+                        model,
+                        VecDeque::from(fingerprints.clone()),
+                        fingerprint_hasher,
+                    ),
                 );
             }
+            metrics.record(model, &state);
+
+            // Properties are only evaluated for observable transitions.
+            let state_observation = observation.as_ref().map(|observe| observe(&state));
+            let is_stuttering =
+                state_observation.is_some() && state_observation == prev_observation;
 
             // Done if discoveries found for all properties.
             let mut is_awaiting_discoveries = false;
             for (i, property) in properties.iter().enumerate() {
-                if discoveries.contains_key(property.name) {
+                if discoveries.contains_key(&property.name) {
+                    continue;
+                }
+                if is_stuttering {
+                    // The observation is unchanged, so the property evaluates as it did for the
+                    // predecessor.
+                    is_awaiting_discoveries = true;
                     continue;
                 }
                 match property {
@@ -234,9 +306,12 @@ where
                         condition: always,
                         ..
                     } => {
-                        if !always(model, &state) {
+                        if property.transition.is_some() {
+                            // Evaluated for each step taken from the state.
+                            is_awaiting_discoveries = true;
+                        } else if !always(model, &state) {
                             // Races other threads, but that's fine.
-                            discoveries.insert(property.name, fingerprints.clone());
+                            discoveries.insert(property.name.clone(), fingerprints.clone());
                         } else {
                             is_awaiting_discoveries = true;
                         }
@@ -248,7 +323,7 @@ where
                     } => {
                         if sometimes(model, &state) {
                             // Races other threads, but that's fine.
-                            discoveries.insert(property.name, fingerprints.clone());
+                            discoveries.insert(property.name.clone(), fingerprints.clone());
                         } else {
                             is_awaiting_discoveries = true;
                         }
@@ -268,6 +343,32 @@ where
                             ebits.remove(i);
                         }
                     }
+                    Property {
+                        expectation: Expectation::Within,
+                        ..
+                    } => {
+                        if !deadlines.advance(i, property, model, &state) {
+                            // Races other threads, but that's fine.
+                            discoveries.insert(property.name.clone(), fingerprints.clone());
+                        } else {
+                            is_awaiting_discoveries = true;
+                        }
+                    }
+                    Property {
+                        expectation: Expectation::LeadsTo,
+                        ..
+                    } => {
+                        // Only identified at terminal states and upon closing cycles.
+                        is_awaiting_discoveries = true;
+                        deadlines.respond(i, property, model, &state, max_depth.get());
+                    }
+                    Property {
+                        expectation: Expectation::Terminal,
+                        ..
+                    } => {
+                        // Only evaluated once the state is known to be terminal.
+                        is_awaiting_discoveries = true;
+                    }
                 }
             }
             if !is_awaiting_discoveries {
@@ -276,12 +377,21 @@ where
 
             // Otherwise enqueue newly generated states (with related metadata).
             let mut is_terminal = true;
+            let mut transitions = Transitions::new(model, &state, properties, |p| {
+                !is_stuttering && !discoveries.contains_key(&p.name)
+            });
             model.actions(&state, &mut actions);
             for action in actions.drain(..) {
+                let transition_action = transitions.next_action();
                 let next_state = match model.next_state(&state, action) {
                     None => continue,
                     Some(next_state) => next_state,
                 };
+                for property in transitions.violated(model, &state, &transition_action, &next_state)
+                {
+                    // Races other threads, but that's fine.
+                    discoveries.insert(property.name.clone(), fingerprints.clone());
+                }
 
                 // Skip if outside boundary.
                 if !model.within_boundary(&next_state) {
@@ -298,18 +408,41 @@ where
                 // that it holds in the path leading to the second visit -- another
                 // possible false-negative.
                 let next_fingerprint = if let Some(representative) = symmetry {
-                    let representative_fingerprint = fingerprint(&representative(&next_state));
-                    if !generated.insert(representative_fingerprint) {
+                    let representative_fingerprint =
+                        fingerprint_hasher.fingerprint(&representative(&next_state));
+                    if !deadlines.tighten(
+                        fingerprint_hasher,
+                        representative_fingerprint,
+                        deadline_bounds,
+                    ) || !generated.insert(
+                        deadlines.node_fingerprint(fingerprint_hasher, representative_fingerprint),
+                        (),
+                    ) {
+                        // The path holds the states as reached rather than their representatives.
+                        let next_fingerprint = fingerprint_hasher.fingerprint(&next_state);
+                        let path = fingerprints.iter().rev().copied();
+                        for i in deadlines.cycle(max_depth.get(), next_fingerprint, path) {
+                            // Races other threads, but that's fine.
+                            discoveries
+                                .entry(properties[i].name.clone())
+                                .or_insert_with(|| fingerprints.clone());
+                        }
                         is_terminal = false;
                         continue;
                     }
+                    unique_states.insert(representative_fingerprint);
                     // IMPORTANT: continue the path with the pre-canonicalized state/fingerprint to
                     // avoid jumping to another part of the state space for which there may not be
                     // a path extension from the previously collected path.
-                    fingerprint(&next_state)
+                    fingerprint_hasher.fingerprint(&next_state)
                 } else {
-                    let next_fingerprint = fingerprint(&next_state);
-                    if !generated.insert(next_fingerprint) {
+                    let next_fingerprint = fingerprint_hasher.fingerprint(&next_state);
+                    if !deadlines.tighten(fingerprint_hasher, next_fingerprint, deadline_bounds)
+                        || !generated.insert(
+                            deadlines.node_fingerprint(fingerprint_hasher, next_fingerprint),
+                            (),
+                        )
+                    {
                         // FIXME: arriving at an already-known state may be a loop (in which case it
                         // could, in a fancier implementation, be considered a terminal state for
                         // purposes of eventually-property checking) but it might also be a join in
@@ -317,10 +450,18 @@ where
                         // some cost), but for now we just _don't_ treat them as terminal, and tell
                         // users they need to explicitly ensure model path-acyclicality when they're
                         // using eventually properties (using a boundary or empty actions or
-                        // whatever).
+                        // whatever). Only `leads_to` properties look for a cycle along the path.
+                        let path = fingerprints.iter().rev().copied();
+                        for i in deadlines.cycle(max_depth.get(), next_fingerprint, path) {
+                            // Races other threads, but that's fine.
+                            discoveries
+                                .entry(properties[i].name.clone())
+                                .or_insert_with(|| fingerprints.clone());
+                        }
                         is_terminal = false;
                         continue;
                     }
+                    unique_states.insert(next_fingerprint);
                     next_fingerprint
                 };
 
@@ -335,14 +476,21 @@ where
                     next_state,
                     next_fingerprints,
                     ebits.clone(),
+                    deadlines.clone(),
                     NonZeroUsize::new(max_depth.get() + 1).unwrap(),
+                    state_observation,
                 ));
             }
             if is_terminal {
+                if let (Some(terminals), Some(&state_fp)) = (terminals, fingerprints.last()) {
+                    terminals.insert(state_fp, fingerprints.clone());
+                }
                 for (i, property) in properties.iter().enumerate() {
-                    if ebits.contains(i) {
+                    let is_violated_terminal = property.expectation == Expectation::Terminal
+                        && !(property.condition)(model, &state);
+                    if ebits.contains(i) || deadlines.is_pending(i) || is_violated_terminal {
                         // Races other threads, but that's fine.
-                        discoveries.insert(property.name, fingerprints.clone());
+                        discoveries.insert(property.name.clone(), fingerprints.clone());
                     }
                 }
             }
@@ -364,25 +512,61 @@ where
     }
 
     fn unique_state_count(&self) -> usize {
-        self.generated.len()
+        self.unique_states.len(&self.generated)
     }
 
     fn max_depth(&self) -> usize {
         self.max_depth.load(Ordering::Relaxed)
     }
 
-    fn discoveries(&self) -> HashMap<&'static str, Path<M::State, M::Action>> {
+    fn discoveries(&self) -> HashMap<Cow<'static, str>, Path<M::State, M::Action>> {
         self.discoveries
             .iter()
             .map(|mapref| {
                 (
-                    <&'static str>::clone(mapref.key()),
-                    Path::from_fingerprints(self.model(), VecDeque::from(mapref.value().clone())),
+                    mapref.key().clone(),
+                    with_violating_step(
+                        self.model(),
+                        mapref.key(),
+                        Path::from_fingerprints_with_hasher(
+                            self.model(),
+                            VecDeque::from(mapref.value().clone()),
+                            self.fingerprint_hasher,
+                        ),
+                    ),
                 )
             })
             .collect()
     }
 
+    fn terminal_states(&self) -> Option<Vec<M::State>> {
+        let mut terminals: Vec<_> = self
+            .terminals
+            .as_ref()?
+            .iter()
+            .map(|mapref| (*mapref.key(), mapref.value().clone()))
+            .collect();
+        terminals.sort_unstable_by_key(|(fp, _)| *fp);
+        Some(
+            terminals
+                .into_iter()
+                .filter_map(|(_, fingerprints)| {
+                    Path::from_fingerprints_with_hasher(
+                        self.model(),
+                        VecDeque::from(fingerprints),
+                        self.fingerprint_hasher,
+                    )
+                    .into_states()
+                    .pop()
+                })
+                .collect(),
+        )
+    }
+
+    fn metrics(&self) -> Vec<MetricSummary> {
+        self.metrics.summaries()
+    }
+
     fn handles(&mut self) -> Vec<JoinHandle<()>> {
         std::mem::take(&mut self.handles)
     }
@@ -390,6 +574,14 @@ where
     fn is_done(&self) -> bool {
         self.job_broker.is_closed() || self.discoveries.len() == self.model.properties().len()
     }
+
+    fn is_truncated(&self) -> bool {
+        self.target_state_count
+            .is_some_and(|target| target.get() <= self.state_count())
+            || self
+                .target_max_depth
+                .is_some_and(|target| target.get() <= self.max_depth())
+    }
 }
 
 #[cfg(test)]
@@ -1,17 +1,60 @@
+mod export;
+mod group;
+
+pub use group::ExplorerGroup;
+
+use crate::actor::RuntimeMetrics;
 use crate::*;
 use parking_lot::RwLock;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::ser::{SerializeStruct, Serializer};
 use serde::Serialize;
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::Write;
 use std::net::ToSocketAddrs;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread::{sleep, spawn};
-use std::time::Duration;
-use tiny_http::{Method, Response, ResponseBox, StatusCode};
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Method, Response, StatusCode};
 
 // (expectation, name, encoded path to discovery)
 type Property = (Expectation, String, Option<String>);
 
+// (name, condition)
+type Predicate<M> = (&'static str, fn(&M, &<M as Model>::State) -> bool);
+
+// (property name, conjunct name, whether the conjunct holds). See `Property::all`.
+type Clause = (String, String, bool);
+
+/// Bounds the number of states that a single predicate search visits, as searches block the web
+/// service.
+const SEARCH_STATE_LIMIT: usize = 1_000_000;
+
+/// The number of matches returned by a predicate search unless otherwise requested.
+const SEARCH_DEFAULT_LIMIT: usize = 10;
+
+/// The number of elements of each collection within a state that `/.states` returns unless
+/// otherwise requested, which keeps the Explorer responsive for models with large states.
+const STATE_COLLECTION_DEFAULT_LIMIT: usize = 100;
+
+/// The number of successors per action class that `/.states` returns unless otherwise requested,
+/// which keeps the Explorer usable for states with thousands of successors.
+const SUCCESSOR_DEFAULT_LIMIT: usize = 50;
+
+/// The number of steps that a random walk takes unless otherwise requested.
+const WALK_DEFAULT_STEPS: usize = 20;
+
+/// Bounds the number of steps that a single random walk takes, as walks block the web service.
+const WALK_STEP_LIMIT: usize = 10_000;
+
+/// How often checking progress is pushed to clients subscribed to `/.events`.
+const EVENT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often an otherwise idle event stream is written to, which detects disconnected clients.
+const EVENT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
 struct StatusView {
     done: bool,
@@ -21,6 +64,97 @@ struct StatusView {
     max_depth: usize,
     properties: Vec<Property>,
     recent_path: Option<String>,
+    predicates: Vec<String>,
+    breakpoints: Vec<String>,
+    read_only: bool,
+    /// Whether a live cluster is attached, in which case `/.cluster` describes it.
+    cluster: bool,
+    /// The highest visit counts, if visit counts are attached.
+    heatmap: Option<HeatmapView>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+struct HeatmapView {
+    max_state_visits: usize,
+    max_transition_visits: usize,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+struct VisitsView {
+    /// How often the state was visited.
+    state: usize,
+    /// How often the state was visited immediately after the previous state on the path.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transition: Option<usize>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+struct PropertyView {
+    expectation: Expectation,
+    name: String,
+    /// Encoded path to the example or counterexample, if one has been discovered.
+    discovery: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+struct SearchView {
+    predicate: String,
+    /// Encoded paths to matching states, shortest first.
+    matches: Vec<String>,
+    explored_state_count: usize,
+    /// Whether every reachable state was searched.
+    complete: bool,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+struct FingerprintView {
+    fingerprint: String,
+    /// Encoded shortest path to the state with the fingerprint.
+    path: String,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+struct WalkView {
+    /// Seed of the walk, as a string because JavaScript numbers cannot represent every `u64`.
+    seed: String,
+    /// The number of steps taken, which is fewer than requested if the walk reached a state
+    /// without successors.
+    steps: usize,
+    /// Encoded path to the state at which the walk ended.
+    path: String,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+struct ClusterActorView {
+    id: String,
+    /// Formatted state, or `None` if the actor has not started or has crashed.
+    state: Option<String>,
+    messages_sent: u64,
+    messages_received: u64,
+    timeouts: u64,
+    restarts: u64,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+struct BreakpointView {
+    breakpoint: String,
+    /// Encoded path to the state at which the breakpoint fired.
+    path: String,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+struct PathView {
+    /// Encoded fingerprints, as accepted by [`Path::from_encoded`].
+    path: String,
+    steps: Vec<PathStepView>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+struct PathStepView {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    action: Option<String>,
+    state: String,
+    fingerprint: String,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -28,8 +162,21 @@ struct StateView<State> {
     action: Option<String>,
     outcome: Option<String>,
     state: Option<State>,
+    /// Rendering of `state` via [`Model::format_state`], if provided.
+    formatted_state: Option<String>,
     properties: Vec<Property>,
+    /// Whether each property's condition holds in this state, in the order of `properties`.
+    conditions: Vec<bool>,
+    clauses: Vec<Clause>,
+    breakpoints: Vec<String>,
     svg: Option<String>,
+    /// Bounds the elements shown per collection within the rendered state, if any.
+    collection_limit: Option<usize>,
+    /// For a placeholder standing in for successors omitted by sampling, the number omitted. The
+    /// `action` is then the class of the omitted actions.
+    omitted: usize,
+    /// Visit counts, if attached via [`ExplorerBuilder::heatmap`].
+    visits: Option<VisitsView>,
 }
 
 impl<State> serde::Serialize for StateView<State>
@@ -45,15 +192,43 @@ where
             out.serialize_field("outcome", outcome)?;
         }
         if let Some(ref state) = self.state {
-            out.serialize_field("state", &format!("{:#?}", state))?;
+            let rendered = match self.formatted_state {
+                Some(ref formatted_state) => formatted_state.clone(),
+                None => format!("{:#?}", state),
+            };
+            match self.collection_limit {
+                Some(limit) => {
+                    let (rendered, elided) = paginate_collections(&rendered, limit);
+                    out.serialize_field("state", &rendered)?;
+                    if elided > 0 {
+                        out.serialize_field("elided", &elided)?;
+                    }
+                }
+                None => out.serialize_field("state", &rendered)?,
+            }
             out.serialize_field("fingerprint", &format!("{:?}", fingerprint(&state)))?;
         }
         if !self.properties.is_empty() {
             out.serialize_field("properties", &self.properties)?;
         }
+        if !self.conditions.is_empty() {
+            out.serialize_field("conditions", &self.conditions)?;
+        }
+        if !self.clauses.is_empty() {
+            out.serialize_field("clauses", &self.clauses)?;
+        }
+        if !self.breakpoints.is_empty() {
+            out.serialize_field("breakpoints", &self.breakpoints)?;
+        }
         if let Some(ref svg) = self.svg {
             out.serialize_field("svg", svg)?;
         }
+        if self.omitted > 0 {
+            out.serialize_field("omitted", &self.omitted)?;
+        }
+        if let Some(ref visits) = self.visits {
+            out.serialize_field("visits", visits)?;
+        }
         out.end()
     }
 }
@@ -76,45 +251,465 @@ impl<M: Model> CheckerVisitor<M> for Arc<RwLock<Snapshot<M::Action>>> {
     }
 }
 
-pub(crate) fn serve<M>(
+/// A [`CheckerBuilder`] extended with options for the Explorer web service. Instantiable via the
+/// [`CheckerBuilder::explorer`] method.
+///
+/// # Example
+///
+/// ```no_run
+/// # use stateright::*;
+/// # let model = ();
+/// model.checker()
+///     .explorer()
+///     .predicate("empty", |_, state| *state == ())
+///     .breakpoint("also empty", |_, state| *state == ())
+///     .serve("localhost:3000");
+/// ```
+#[must_use = "This code constructs a builder, not an Explorer. Consider calling serve()."]
+pub struct ExplorerBuilder<M: Model> {
     checker_builder: CheckerBuilder<M>,
-    addresses: impl ToSocketAddrs,
-) -> Arc<impl Checker<M>>
+    predicates: Vec<Predicate<M>>,
+    breakpoints: Vec<Predicate<M>>,
+    read_only: bool,
+    authorization: Option<String>,
+    cluster: Option<Arc<RuntimeMetrics>>,
+    heatmap: Option<Arc<VisitCounts>>,
+}
+
+impl<M: Model> ExplorerBuilder<M> {
+    pub(crate) fn new(checker_builder: CheckerBuilder<M>) -> Self {
+        Self {
+            checker_builder,
+            predicates: Vec::new(),
+            breakpoints: Vec::new(),
+            read_only: false,
+            authorization: None,
+            cluster: None,
+            heatmap: None,
+        }
+    }
+
+    /// Registers a named state predicate. The Explorer can search for reachable states that
+    /// satisfy the predicate, listing the shortest path to each match.
+    pub fn predicate(mut self, name: &'static str, condition: fn(&M, &M::State) -> bool) -> Self {
+        self.predicates.push((name, condition));
+        self
+    }
+
+    /// Registers a named breakpoint. States at which a breakpoint fires are highlighted, and the
+    /// Explorer can continue from the selected state to the nearest such state.
+    pub fn breakpoint(mut self, name: &'static str, condition: fn(&M, &M::State) -> bool) -> Self {
+        self.breakpoints.push((name, condition));
+        self
+    }
+
+    /// Checks the entire state space before serving, after which the Explorer only serves the
+    /// resulting fixed state graph and rejects every request other than a `GET` (such as a request
+    /// to run the checker). Useful for publishing a verified model for others to browse.
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Requires [HTTP basic authentication](https://datatracker.ietf.org/doc/html/rfc7617) for
+    /// every request. Credentials are sent in clear text, so the Explorer should be served behind
+    /// a TLS-terminating proxy if it is reachable from an untrusted network.
+    pub fn basic_auth(mut self, username: &str, password: &str) -> Self {
+        let credentials = format!("{}:{}", username, password);
+        self.authorization = Some(format!("Basic {}", base64(credentials.as_bytes())));
+        self
+    }
+
+    /// Attaches a running cluster of [spawned](crate::actor::spawn()) actors, whose current states
+    /// the Explorer displays next to the model, bridging model exploration and live debugging.
+    /// States are formatted as in [`ActorMetrics::state`], so actors wrapped in an
+    /// [`ordered_reliable_link::ActorWrapper`] also show the messages awaiting acknowledgement.
+    ///
+    /// [`ActorMetrics::state`]: crate::actor::ActorMetrics::state
+    /// [`ordered_reliable_link::ActorWrapper`]: crate::actor::ordered_reliable_link::ActorWrapper
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use stateright::*;
+    /// # use stateright::actor::*;
+    /// # let model = ();
+    /// # let builder: SpawnBuilder<(), ()> = SpawnBuilder::new(|_| Ok(vec![]), |_| Ok(()));
+    /// let metrics = builder.metrics();
+    /// builder.spawn_async();
+    /// model.checker().explorer().cluster(metrics).serve("localhost:3000");
+    /// ```
+    pub fn cluster(mut self, metrics: Arc<RuntimeMetrics>) -> Self {
+        metrics.record_states();
+        self.cluster = Some(metrics);
+        self
+    }
+
+    /// Attaches visit counts gathered while checking or simulating the model, which the Explorer
+    /// overlays as a heatmap on states and actions to highlight hot loops and rarely exercised
+    /// transitions.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use stateright::*;
+    /// # let model = ();
+    /// let (counter, counts) = VisitCounter::new_with_counts();
+    /// model.checker()
+    ///     .visitor(counter)
+    ///     .target_state_count(100_000)
+    ///     .spawn_simulation(0, UniformChooser)
+    ///     .join();
+    /// model.checker().explorer().heatmap(counts).serve("localhost:3000");
+    /// ```
+    pub fn heatmap(mut self, counts: Arc<VisitCounts>) -> Self {
+        self.heatmap = Some(counts);
+        self
+    }
+
+    /// Writes the actions and states along an encoded path, such as one copied from an Explorer
+    /// URL or returned by [`Path::encode`], without starting the web service. Useful for scripts
+    /// that turn stored fingerprints back into readable traces. Fails with
+    /// [`std::io::ErrorKind::InvalidInput`] if the path is unreachable via the model.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use stateright::*;
+    /// # let model = ();
+    /// let encoded = Path::from_actions(&model, (), &[]).unwrap().encode();
+    /// let mut output = Vec::new();
+    /// model.checker().explorer().resolve(&encoded, &mut output).unwrap();
+    /// assert!(String::from_utf8(output).unwrap().contains("Initial state"));
+    /// ```
+    pub fn resolve(self, encoded: &str, out: &mut impl Write) -> std::io::Result<()>
+    where
+        M::Action: Debug,
+        M::State: Debug + Hash,
+    {
+        let view = export_path(&self.checker_builder.model, encoded)
+            .map_err(|msg| std::io::Error::new(std::io::ErrorKind::InvalidInput, msg))?;
+        writeln!(out, "Path: {}", view.path)?;
+        for (i, step) in view.steps.iter().enumerate() {
+            match &step.action {
+                None => writeln!(out, "\n{}. Initial state [{}]", i, step.fingerprint)?,
+                Some(action) => writeln!(out, "\n{}. {} [{}]", i, action, step.fingerprint)?,
+            }
+            writeln!(out, "{}", step.state)?;
+        }
+        Ok(())
+    }
+
+    /// Starts the web service. See [`CheckerBuilder::serve`].
+    pub fn serve(self, addresses: impl ToSocketAddrs) -> Arc<impl Checker<M>>
+    where
+        M: 'static + Model + Send + Sync,
+        M::Action: Debug + Send + Sync,
+        M::State: Debug + Hash + Send + Sync,
+    {
+        serve_checker(self.spawn(), addresses)
+    }
+
+    /// Handles requests on the calling thread rather than serving them over HTTP, checking the
+    /// model incrementally via [`CheckerBuilder::incremental_bfs`]. Unlike [`Self::serve`], this
+    /// needs neither a server nor threads, so the Explorer can run entirely client-side, such as
+    /// in a static page that checks the model via WebAssembly (see the `stateright-wasm` crate).
+    pub fn local(self) -> LocalExplorer<M>
+    where
+        M::Action: Debug + Send + Sync + 'static,
+        M::State: Debug + Hash,
+    {
+        LocalExplorer {
+            data: Arc::new(self.build(CheckerBuilder::incremental_bfs)),
+            is_running: AtomicBool::new(false),
+        }
+    }
+
+    /// Starts the checker that backs the web service.
+    fn spawn(self) -> ExplorerData<M, impl Checker<M>>
+    where
+        M: 'static + Model + Send + Sync,
+        M::Action: Debug + Send + Sync,
+        M::State: Debug + Hash + Send + Sync,
+    {
+        let data = self.build(CheckerBuilder::spawn_on_demand);
+        let snapshot = Arc::clone(&data.snapshot);
+        spawn(move || loop {
+            sleep(Duration::from_secs(4));
+            snapshot.write().0 = true;
+        });
+        data
+    }
+
+    /// Starts the checker that backs the Explorer via `spawn_checker`.
+    fn build<C: Checker<M>>(
+        self,
+        spawn_checker: impl FnOnce(CheckerBuilder<M>) -> C,
+    ) -> ExplorerData<M, C>
+    where
+        M::Action: Send + Sync + 'static,
+    {
+        let snapshot = Arc::new(RwLock::new(Snapshot(true, None)));
+        // URLs identify states by their default fingerprints.
+        let mut checker = spawn_checker(
+            self.checker_builder
+                .fingerprint_hasher(FingerprintHasher::default())
+                .visitor(Arc::clone(&snapshot)),
+        );
+        if self.read_only {
+            log::info!("Checking the entire state space before serving a read-only Explorer.");
+            checker.run_to_completion();
+            checker = checker.join();
+        }
+        ExplorerData {
+            snapshot,
+            checker: Arc::new(checker),
+            predicates: self.predicates,
+            breakpoints: self.breakpoints,
+            read_only: self.read_only,
+            authorization: self.authorization,
+            cluster: self.cluster,
+            heatmap: self.heatmap,
+        }
+    }
+}
+
+/// An Explorer that handles requests on the calling thread, without a web server. Instantiate
+/// via [`ExplorerBuilder::local`].
+///
+/// The embedding page routes the Explorer UI's requests (see `explorerBackend` in `ui/app.js`)
+/// to [`LocalExplorer::handle`] and periodically calls [`LocalExplorer::step`], which checks
+/// more of the state space once the UI has asked to run to completion.
+///
+/// # Example
+///
+/// ```
+/// # use stateright::*;
+/// # let model = ();
+/// let explorer = model.checker().explorer().local();
+/// let response = explorer.handle("GET", "/.status");
+/// assert_eq!(response.status, 200);
+/// assert_eq!(response.content_type, Some("application/json"));
+///
+/// explorer.handle("POST", "/.runtocompletion");
+/// while explorer.step(1_000) {}
+/// assert!(explorer.checker().is_done());
+/// ```
+pub struct LocalExplorer<M: Model> {
+    data: Data<M, IncrementalChecker<M>>,
+    is_running: AtomicBool,
+}
+
+impl<M> LocalExplorer<M>
+where
+    M: Model,
+    M::Action: Debug,
+    M::State: Debug + Hash,
+{
+    /// Responds to a request from the Explorer UI, such as `GET /.states/...`. The UI files are
+    /// also served (e.g. `GET /app.js`), so a page can be bootstrapped from them.
+    pub fn handle(&self, method: &str, url: &str) -> ExplorerResponse {
+        let Ok(method) = method.parse::<Method>() else {
+            return ExplorerResponse::not_found(format!("Unsupported method: {}", method));
+        };
+        match (&method, url) {
+            // Running to completion at once would block the caller, so `step` does the work.
+            (Method::Post, "/.runtocompletion") if !self.data.read_only => {
+                self.is_running.store(true, Ordering::Relaxed);
+                ExplorerResponse {
+                    status: 200,
+                    content_type: None,
+                    body: Vec::new(),
+                }
+            }
+            (Method::Get, "/.status") => {
+                let response = handle(&method, url, &self.data);
+                // Sample the path to another state for the next status.
+                self.data.snapshot.write().0 = true;
+                response
+            }
+            _ => handle(&method, url, &self.data),
+        }
+    }
+
+    /// Checks up to `max_count` more states if the UI has asked to run to completion, then
+    /// returns whether more remain to be checked.
+    pub fn step(&self, max_count: usize) -> bool {
+        self.is_running.load(Ordering::Relaxed) && !self.data.checker.step(max_count)
+    }
+
+    /// The checker backing the Explorer.
+    pub fn checker(&self) -> &IncrementalChecker<M> {
+        &self.data.checker
+    }
+}
+
+fn serve_checker<M, C>(data: ExplorerData<M, C>, addresses: impl ToSocketAddrs) -> Arc<C>
 where
     M: 'static + Model + Send + Sync,
     M::Action: Debug + Send + Sync,
     M::State: Debug + Hash + Send + Sync,
+    C: 'static + Checker<M> + Send + Sync,
 {
-    let snapshot = Arc::new(RwLock::new(Snapshot(true, None)));
-    let snapshot_for_visitor = Arc::clone(&snapshot);
-    let snapshot_for_server = Arc::clone(&snapshot);
-    spawn(move || loop {
-        sleep(Duration::from_secs(4));
-        snapshot.write().0 = true;
-    });
-    let checker = checker_builder
-        .visitor(snapshot_for_visitor)
-        .spawn_on_demand();
-    serve_checker(checker, snapshot_for_server, addresses)
+    let checker = Arc::clone(&data.checker);
+    let service: Arc<dyn Service> = Arc::new(Arc::new(data));
+    serve_requests(addresses, move |rq| respond(rq, &service, None));
+    checker
 }
 
-fn serve_checker<M, C>(
-    checker: C,
-    snapshot: Arc<RwLock<Snapshot<M::Action>>>,
-    addresses: impl ToSocketAddrs,
-) -> Arc<impl Checker<M>>
+/// Hands each request to `respond` until the server fails.
+fn serve_requests(addresses: impl ToSocketAddrs, respond: impl Fn(tiny_http::Request)) {
+    let server = tiny_http::Server::http(addresses).unwrap();
+    loop {
+        respond(server.recv().unwrap());
+    }
+}
+
+/// An Explorer for one model, with the model's types erased so that several can share a server.
+trait Service: Send + Sync {
+    fn authorization(&self) -> &Option<String>;
+    fn handle(&self, method: &Method, url: &str) -> ExplorerResponse;
+    fn stream_events(&self, writer: Box<dyn Write + Send>) -> std::io::Result<()>;
+}
+
+impl<M, C> Service for Data<M, C>
 where
     M: 'static + Model + Send + Sync,
     M::Action: Debug + Send + Sync,
     M::State: Debug + Hash + Send + Sync,
     C: 'static + Checker<M> + Send + Sync,
 {
-    let checker = Arc::new(checker);
+    fn authorization(&self) -> &Option<String> {
+        &self.authorization
+    }
 
-    let server = tiny_http::Server::http(addresses).unwrap();
+    fn handle(&self, method: &Method, url: &str) -> ExplorerResponse {
+        handle(method, url, self)
+    }
+
+    fn stream_events(&self, writer: Box<dyn Write + Send>) -> std::io::Result<()> {
+        stream_events(writer, Arc::clone(self))
+    }
+}
+
+/// Responds to a request for a service. `url` overrides the request URL, such as when a prefix
+/// has been stripped to select the service.
+fn respond(rq: tiny_http::Request, service: &Arc<dyn Service>, url: Option<&str>) {
+    let authorization = rq
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .map(|h| h.value.as_str());
+    if !is_authorized(authorization, service.authorization()) {
+        let challenge = Header::from_bytes(
+            &b"WWW-Authenticate"[..],
+            &b"Basic realm=\"Stateright Explorer\""[..],
+        )
+        .unwrap();
+        let response = Response::empty(StatusCode(401)).with_header(challenge);
+        rq.respond(response).unwrap();
+        return;
+    }
+    let url = url.unwrap_or(rq.url()).to_string();
+    if rq.method() == &Method::Get && url == "/.events" {
+        // Streams are long-lived, so each is served by a dedicated thread.
+        let service = Arc::clone(service);
+        spawn(move || {
+            if let Err(err) = service.stream_events(rq.into_writer()) {
+                log::debug!("Event stream closed. err={:?}", err);
+            }
+        });
+        return;
+    }
+    let response = service.handle(rq.method(), &url);
+    respond_with(rq, response);
+}
+
+fn respond_with(rq: tiny_http::Request, response: ExplorerResponse) {
+    let mut http_response =
+        Response::from_data(response.body).with_status_code(StatusCode(response.status));
+    if let Some(content_type) = response.content_type {
+        let header = Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes()).unwrap();
+        http_response = http_response.with_header(header);
+    }
+    rq.respond(http_response).unwrap();
+}
+
+/// A response that is independent of the HTTP server, which allows the Explorer to be driven
+/// without one. See [`LocalExplorer::handle`].
+#[derive(Debug, PartialEq)]
+pub struct ExplorerResponse {
+    /// The HTTP status code.
+    pub status: u16,
+    /// The value of the `Content-Type` header, if any.
+    pub content_type: Option<&'static str>,
+    /// The response body.
+    pub body: Vec<u8>,
+}
+
+impl ExplorerResponse {
+    fn json(view: &impl Serialize) -> Self {
+        ExplorerResponse {
+            status: 200,
+            content_type: Some("application/json"),
+            body: serde_json::to_vec(view).unwrap(),
+        }
+    }
+
+    fn not_found(explanation: String) -> Self {
+        ExplorerResponse {
+            status: 404,
+            content_type: None,
+            body: explanation.into_bytes(),
+        }
+    }
+}
+
+fn is_authorized(authorization: Option<&str>, expected: &Option<String>) -> bool {
+    match expected {
+        None => true,
+        Some(expected) => {
+            authorization.is_some_and(|a| constant_time_eq(a.as_bytes(), expected.as_bytes()))
+        }
+    }
+}
+
+/// Compares byte strings in time that depends only on their lengths, so that response times do
+/// not reveal how much of a credential matched.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
 
-    let server = Arc::new(server);
+/// Encodes bytes as standard base64 with padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
 
+/// Routes a request other than `/.events`, which is streamed.
+fn handle<M, C>(method: &Method, url: &str, data: &Data<M, C>) -> ExplorerResponse
+where
+    M: Model,
+    M::Action: Debug,
+    M::State: Debug + Hash,
+    C: Checker<M>,
+{
     macro_rules! get_ui_file {
         ($filename:literal) => {{
             let data = if let Ok(content) = std::fs::read(concat!("./ui/", $filename)) {
@@ -124,59 +719,84 @@ where
                 log::info!("Explorer release mode. Loading {} from disk.", $filename);
                 include_bytes!(concat!("../../ui/", $filename)).to_vec()
             };
-            Response::from_data(data).boxed()
+            ExplorerResponse {
+                status: 200,
+                content_type: None,
+                body: data,
+            }
         }};
     }
 
-    let data = Arc::new((snapshot, Arc::clone(&checker)));
-    let web_handle = std::thread::spawn(move || loop {
-        let rq = server.recv().unwrap();
-        let response = match (rq.method(), rq.url()) {
-            (Method::Get, "/") => get_ui_file!("index.htm"),
-            (Method::Get, "/app.css") => get_ui_file!("app.css"),
-            (Method::Get, "/app.js") => get_ui_file!("app.js"),
-            (Method::Get, "/knockout-3.5.0.js") => get_ui_file!("knockout-3.5.0.js"),
-            (Method::Get, "/.status") => {
-                let view = status(Arc::clone(&data));
-                let status_json = serde_json::to_vec(&view).unwrap();
-                Response::from_data(status_json).boxed()
-            }
-            (Method::Post, "/.runtocompletion") => run_to_completion(Arc::clone(&data)),
-            (Method::Get, url) => {
-                if let Some(fingerprints) = url.strip_prefix("/.states") {
-                    match states(fingerprints, Arc::clone(&data)) {
-                        Ok(states) => {
-                            let states_json = serde_json::to_vec(&states).unwrap();
-                            Response::from_data(states_json).boxed()
-                        }
-                        Err(err) => Response::from_string(err)
-                            .with_status_code(StatusCode(404))
-                            .boxed(),
-                    }
-                } else {
-                    Response::empty(StatusCode(404)).boxed()
-                }
-            }
-            _ => Response::empty(StatusCode(404)).boxed(),
+    // Only reads are permitted once the state graph is fixed.
+    if data.read_only && method != &Method::Get {
+        return ExplorerResponse {
+            status: 403,
+            content_type: None,
+            body: b"The Explorer is read-only".to_vec(),
         };
-        rq.respond(response).unwrap();
-    });
-    web_handle.join().unwrap();
+    }
+    let data = Arc::clone(data);
+    let result = match (method, url) {
+        (Method::Get, "/") => return get_ui_file!("index.htm"),
+        (Method::Get, "/app.css") => return get_ui_file!("app.css"),
+        (Method::Get, "/app.js") => return get_ui_file!("app.js"),
+        (Method::Get, "/knockout-3.5.0.js") => return get_ui_file!("knockout-3.5.0.js"),
+        (Method::Get, "/.status") => return ExplorerResponse::json(&status(data)),
+        (Method::Get, "/.properties") => return ExplorerResponse::json(&properties(data)),
+        (Method::Get, "/.cluster") => cluster(data).map(|view| ExplorerResponse::json(&view)),
+        (Method::Post, "/.runtocompletion") => {
+            data.checker.run_to_completion();
+            return ExplorerResponse {
+                status: 200,
+                content_type: None,
+                body: Vec::new(),
+            };
+        }
+        (Method::Get, url) => {
+            if let Some(query) = url.strip_prefix("/.search/") {
+                search(query, data).map(|view| ExplorerResponse::json(&view))
+            } else if let Some(fingerprint) = url.strip_prefix("/.fingerprint/") {
+                find_fingerprint(fingerprint, data).map(|view| ExplorerResponse::json(&view))
+            } else if let Some(query) = url.strip_prefix("/.walk") {
+                random_walk(query, data).map(|view| ExplorerResponse::json(&view))
+            } else if let Some(encoded) = url.strip_prefix("/.breakpoint") {
+                continue_to_breakpoint(encoded, data).map(|view| ExplorerResponse::json(&view))
+            } else if let Some(query) = url.strip_prefix("/.path/") {
+                export_path_as(data.checker.model(), query)
+            } else if let Some(fingerprints) = url.strip_prefix("/.states") {
+                states(fingerprints, data).map(|states| ExplorerResponse::json(&states))
+            } else {
+                Err(String::new())
+            }
+        }
+        _ => Err(String::new()),
+    };
+    result.unwrap_or_else(ExplorerResponse::not_found)
+}
 
-    checker
+struct ExplorerData<M: Model, C> {
+    snapshot: Arc<RwLock<Snapshot<M::Action>>>,
+    checker: Arc<C>,
+    predicates: Vec<Predicate<M>>,
+    breakpoints: Vec<Predicate<M>>,
+    read_only: bool,
+    /// Expected value of the `Authorization` header, if authentication is required.
+    authorization: Option<String>,
+    cluster: Option<Arc<RuntimeMetrics>>,
+    heatmap: Option<Arc<VisitCounts>>,
 }
 
-type Data<Action, Checker> = Arc<(Arc<RwLock<Snapshot<Action>>>, Arc<Checker>)>;
+type Data<M, C> = Arc<ExplorerData<M, C>>;
 
-fn status<M, C>(data: Data<M::Action, C>) -> StatusView
+fn status<M, C>(data: Data<M, C>) -> StatusView
 where
     M: Model,
     M::Action: Debug,
     M::State: Hash,
     C: Checker<M>,
 {
-    let snapshot = &data.0;
-    let checker = &data.1;
+    let snapshot = &data.snapshot;
+    let checker = &data.checker;
 
     StatusView {
         model: std::any::type_name::<M>().to_string(),
@@ -186,50 +806,605 @@ where
         max_depth: checker.max_depth(),
         properties: get_properties(checker),
         recent_path: snapshot.read().1.as_ref().map(|p| format!("{:?}", p)),
+        predicates: data
+            .predicates
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect(),
+        breakpoints: data
+            .breakpoints
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect(),
+        read_only: data.read_only,
+        cluster: data.cluster.is_some(),
+        heatmap: data.heatmap.as_ref().map(|counts| {
+            let (max_state_visits, max_transition_visits) = counts.max();
+            HeatmapView {
+                max_state_visits,
+                max_transition_visits,
+            }
+        }),
+    }
+}
+
+/// Describes the current state of each actor in the attached cluster.
+fn cluster<M, C>(data: Data<M, C>) -> Result<Vec<ClusterActorView>, String>
+where
+    M: Model,
+{
+    let metrics = data
+        .cluster
+        .as_ref()
+        .ok_or_else(|| "No cluster is attached".to_string())?;
+    Ok(metrics
+        .actors()
+        .into_iter()
+        .map(|(id, metrics)| ClusterActorView {
+            id: std::net::SocketAddrV4::from(id).to_string(),
+            state: metrics.state(),
+            messages_sent: metrics.messages_sent(),
+            messages_received: metrics.messages_received(),
+            timeouts: metrics.timeouts(),
+            restarts: metrics.restarts(),
+        })
+        .collect())
+}
+
+/// Pushes [Server-Sent Events](https://html.spec.whatwg.org/multipage/server-sent-events.html)
+/// describing checking progress until checking completes: a `discovery` event for each newly
+/// found example or counterexample, and a `status` event whenever the [`StatusView`] changes.
+fn stream_events<M, C>(mut writer: impl Write, data: Data<M, C>) -> std::io::Result<()>
+where
+    M: Model,
+    M::Action: Debug,
+    M::State: Hash,
+    C: Checker<M>,
+{
+    write!(
+        writer,
+        "HTTP/1.1 200 OK\r\n\
+         Content-Type: text/event-stream\r\n\
+         Cache-Control: no-cache\r\n\
+         Connection: close\r\n\r\n"
+    )?;
+    writer.flush()?;
+
+    let mut last_view = None;
+    let mut last_write = Instant::now();
+    let mut discovered = HashSet::new();
+    loop {
+        let view = status(Arc::clone(&data));
+        for (expectation, name, path) in &view.properties {
+            if let Some(path) = path {
+                if discovered.insert(name.clone()) {
+                    let discovery = serde_json::json!({
+                        "expectation": expectation,
+                        "name": name,
+                        "path": path,
+                    });
+                    write!(writer, "event: discovery\ndata: {}\n\n", discovery)?;
+                }
+            }
+        }
+        if last_view.as_ref() != Some(&view) {
+            let status_json = serde_json::to_string(&view).unwrap();
+            write!(writer, "event: status\ndata: {}\n\n", status_json)?;
+            writer.flush()?;
+            last_write = Instant::now();
+        } else if last_write.elapsed() >= EVENT_HEARTBEAT_INTERVAL {
+            write!(writer, ": heartbeat\n\n")?;
+            writer.flush()?;
+            last_write = Instant::now();
+        }
+        if view.done {
+            return Ok(());
+        }
+        last_view = Some(view);
+        sleep(EVENT_INTERVAL);
+    }
+}
+
+fn get_properties<C, M>(checker: &Arc<C>) -> Vec<Property>
+where
+    M: Model,
+    M::State: Hash,
+    C: Checker<M>,
+{
+    checker
+        .model()
+        .properties()
+        .into_iter()
+        .map(|p| {
+            (
+                p.expectation,
+                p.name.to_string(),
+                checker.discovery(&p.name).map(|p| p.encode()),
+            )
+        })
+        .collect()
+}
+
+fn properties<M, C>(data: Data<M, C>) -> Vec<PropertyView>
+where
+    M: Model,
+    M::State: Hash,
+    C: Checker<M>,
+{
+    get_properties(&data.checker)
+        .into_iter()
+        .map(|(expectation, name, discovery)| PropertyView {
+            expectation,
+            name,
+            discovery,
+        })
+        .collect()
+}
+
+/// Searches breadth-first for reachable states satisfying a predicate. The query is of the form
+/// `{predicate index}` or `{predicate index}?limit={max matches}`.
+fn search<M, C>(query: &str, data: Data<M, C>) -> Result<SearchView, String>
+where
+    M: Model,
+    M::State: Hash,
+    C: Checker<M>,
+{
+    let (index, params) = query.split_once('?').unwrap_or((query, ""));
+    let (name, condition) = index
+        .trim_end_matches('/')
+        .parse::<usize>()
+        .ok()
+        .and_then(|i| data.predicates.get(i))
+        .ok_or_else(|| format!("Unable to find predicate {}", index))?;
+    let mut limit = SEARCH_DEFAULT_LIMIT;
+    for (key, value) in params.split('&').filter_map(|pair| pair.split_once('=')) {
+        if key == "limit" {
+            limit = value
+                .parse()
+                .map_err(|_| format!("Unable to parse limit {}", value))?;
+        }
+    }
+
+    let model = data.checker.model();
+    let (matches, explored_state_count, complete) =
+        find_paths(model, "", model.init_states(), limit, |state| {
+            condition(model, state)
+        });
+    Ok(SearchView {
+        predicate: name.to_string(),
+        matches,
+        explored_state_count,
+        complete,
+    })
+}
+
+/// Searches breadth-first beyond the end of an encoded path for the nearest state at which a
+/// breakpoint fires.
+fn continue_to_breakpoint<M, C>(encoded: &str, data: Data<M, C>) -> Result<BreakpointView, String>
+where
+    M: Model,
+    M::State: Hash,
+    C: Checker<M>,
+{
+    let model = data.checker.model();
+    let (prefix, starts) = if encoded.trim_matches('/').is_empty() {
+        // Nothing is selected yet, so continue from the initial states.
+        (String::new(), model.init_states())
+    } else {
+        let path = Path::from_encoded(model, encoded)
+            .ok_or_else(|| format!("Unable to find path {}", encoded))?;
+        let prefix = path.encode();
+        let (last_state, _) = path.into_vec().pop().unwrap();
+        (prefix, model.next_states(&last_state))
+    };
+    let mut fired = None;
+    let (matches, _, _) = find_paths(model, &prefix, starts, 1, |state| {
+        fired = data
+            .breakpoints
+            .iter()
+            .find(|(_, condition)| condition(model, state))
+            .map(|(name, _)| name.to_string());
+        fired.is_some()
+    });
+    match (fired, matches.into_iter().next()) {
+        (Some(breakpoint), Some(path)) => Ok(BreakpointView { breakpoint, path }),
+        _ => Err(format!("No breakpoint is reachable from {}", prefix)),
+    }
+}
+
+/// Takes uniformly random steps from the end of an encoded path, or from a random initial state if
+/// the path is empty, which quickly leads to deep states from which to start exploring. The query
+/// can specify the number of `steps` and the `seed`, which is otherwise chosen at random.
+fn random_walk<M, C>(query: &str, data: Data<M, C>) -> Result<WalkView, String>
+where
+    M: Model,
+    M::State: Hash,
+    C: Checker<M>,
+{
+    let (encoded, params) = query.split_once('?').unwrap_or((query, ""));
+    let mut steps = WALK_DEFAULT_STEPS;
+    let mut seed = None;
+    for (key, value) in params.split('&').filter_map(|pair| pair.split_once('=')) {
+        match key {
+            "steps" => {
+                steps = value
+                    .parse()
+                    .map_err(|_| format!("Unable to parse steps {}", value))?
+            }
+            "seed" => {
+                seed = Some(
+                    value
+                        .parse::<u64>()
+                        .map_err(|_| format!("Unable to parse seed {}", value))?,
+                )
+            }
+            _ => {}
+        }
+    }
+    let seed = seed.unwrap_or_else(rand::random);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let model = data.checker.model();
+    let (mut fingerprints, mut state) = if encoded.trim_matches('/').is_empty() {
+        let mut init_states = model.init_states();
+        if init_states.is_empty() {
+            return Err("The model has no initial states".to_string());
+        }
+        let state = init_states.swap_remove(rng.gen_range(0..init_states.len()));
+        (vec![fingerprint(&state)], state)
+    } else {
+        let path = Path::from_encoded(model, encoded)
+            .ok_or_else(|| format!("Unable to find path {}", encoded))?;
+        let mut states = path.into_states();
+        let fingerprints = states.iter().map(fingerprint).collect();
+        (fingerprints, states.pop().unwrap())
+    };
+    let mut taken = 0;
+    while taken < steps.min(WALK_STEP_LIMIT) {
+        let mut next_states: Vec<_> = model
+            .next_states(&state)
+            .into_iter()
+            .filter(|next_state| model.within_boundary(next_state))
+            .collect();
+        if next_states.is_empty() {
+            break;
+        }
+        state = next_states.swap_remove(rng.gen_range(0..next_states.len()));
+        fingerprints.push(fingerprint(&state));
+        taken += 1;
+    }
+    data.checker.check_fingerprint(fingerprint(&state));
+    Ok(WalkView {
+        seed: seed.to_string(),
+        steps: taken,
+        path: fingerprints
+            .iter()
+            .map(|fp| fp.to_string())
+            .collect::<Vec<_>>()
+            .join("/"),
+    })
+}
+
+/// Finds the shortest path to a reachable state with the specified fingerprint, such as one
+/// mentioned in a log.
+fn find_fingerprint<M, C>(
+    fingerprint_str: &str,
+    data: Data<M, C>,
+) -> Result<FingerprintView, String>
+where
+    M: Model,
+    M::State: Hash,
+    C: Checker<M>,
+{
+    let target = fingerprint_str
+        .trim()
+        .parse::<Fingerprint>()
+        .map_err(|_| format!("Unable to parse fingerprint {}", fingerprint_str))?;
+    let model = data.checker.model();
+    let (matches, explored_state_count, _) =
+        find_paths(model, "", model.init_states(), 1, |state| {
+            fingerprint(state) == target
+        });
+    match matches.into_iter().next() {
+        Some(path) => Ok(FingerprintView {
+            fingerprint: target.to_string(),
+            path,
+        }),
+        None => Err(format!(
+            "Unable to find a state with fingerprint {} after exploring {} states",
+            target, explored_state_count
+        )),
+    }
+}
+
+/// Searches breadth-first from `starts` for states satisfying `is_match`, stopping after `limit`
+/// matches. Returns the encoded path to each match (shortest first, and beginning with `prefix`),
+/// the number of states explored, and whether every reachable state was searched (which is never
+/// the case once the limit is reached).
+fn find_paths<M>(
+    model: &M,
+    prefix: &str,
+    starts: Vec<M::State>,
+    limit: usize,
+    mut is_match: impl FnMut(&M::State) -> bool,
+) -> (Vec<String>, usize, bool)
+where
+    M: Model,
+    M::State: Hash,
+{
+    let mut matches = Vec::new();
+    let mut explored_state_count = 0;
+    let mut complete = true;
+    let mut parents: HashMap<Fingerprint, Option<Fingerprint>> = HashMap::new();
+    let mut pending = VecDeque::new();
+    for state in starts {
+        if model.within_boundary(&state) && parents.insert(fingerprint(&state), None).is_none() {
+            pending.push_back(state);
+        }
+    }
+    while let Some(state) = pending.pop_front() {
+        let state_fp = fingerprint(&state);
+        explored_state_count += 1;
+        if is_match(&state) {
+            let mut fingerprints = vec![state_fp];
+            while let Some(Some(parent)) = parents.get(fingerprints.last().unwrap()) {
+                fingerprints.push(*parent);
+            }
+            fingerprints.reverse();
+            let mut encoded = prefix.to_string();
+            for fp in fingerprints {
+                if !encoded.is_empty() {
+                    encoded.push('/');
+                }
+                encoded.push_str(&fp.to_string());
+            }
+            matches.push(encoded);
+            if matches.len() >= limit {
+                complete = false;
+                break;
+            }
+        }
+        if parents.len() >= SEARCH_STATE_LIMIT {
+            complete = false;
+            continue;
+        }
+        for (_action, next_state) in model.next_steps(&state) {
+            if !model.within_boundary(&next_state) {
+                continue;
+            }
+            let next_fp = fingerprint(&next_state);
+            if let std::collections::hash_map::Entry::Vacant(e) = parents.entry(next_fp) {
+                e.insert(Some(state_fp));
+                pending.push_back(next_state);
+            }
+        }
+    }
+    (matches, explored_state_count, complete)
+}
+
+/// Classifies an action by the leading identifier of its [`Debug`] representation, which is the
+/// enum variant for typical actions (e.g. `Deliver`, `Drop`, or `Timeout` for an [`ActorModel`]).
+///
+/// [`ActorModel`]: crate::actor::ActorModel
+fn action_class<Action: Debug>(action: &Action) -> String {
+    let debug = format!("{:?}", action);
+    let end = debug
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(debug.len());
+    debug[..end].to_string()
+}
+
+/// Chooses which successors to return when a state has more than `limit` successors in some
+/// action class, spacing the chosen ones evenly within the class. Returns whether each
+/// successor was chosen, along with the number omitted from each class in order of first
+/// appearance.
+fn sample_successors(
+    classes: &[String],
+    limit: Option<usize>,
+) -> (Vec<bool>, Vec<(String, usize)>) {
+    let mut is_sampled = vec![true; classes.len()];
+    let mut omitted = Vec::new();
+    let limit = match limit {
+        None => return (is_sampled, omitted),
+        Some(limit) => limit,
+    };
+    let mut members: Vec<(&String, Vec<usize>)> = Vec::new();
+    for (i, class) in classes.iter().enumerate() {
+        match members.iter_mut().find(|(c, _)| *c == class) {
+            Some((_, indices)) => indices.push(i),
+            None => members.push((class, vec![i])),
+        }
+    }
+    for (class, indices) in members {
+        if indices.len() <= limit {
+            continue;
+        }
+        for &i in &indices {
+            is_sampled[i] = false;
+        }
+        for k in 0..limit {
+            is_sampled[indices[k * indices.len() / limit]] = true;
+        }
+        omitted.push((class.clone(), indices.len() - limit));
+    }
+    (is_sampled, omitted)
+}
+
+/// Elides all but the first `limit` elements of each collection in a state rendered via pretty
+/// [`Debug`] formatting (`{:#?}`), which places each element on its own line(s) indented one
+/// level deeper than the enclosing delimiters. Returns the resulting text and the number of
+/// elements elided. Text lacking such structure is returned unchanged.
+fn paginate_collections(text: &str, limit: usize) -> (String, usize) {
+    const INDENT: usize = 4;
+    let is_closer = |line: &str| line.starts_with(['}', ']', ')']);
+    let is_opener = |line: &str| line.ends_with(['{', '[', '(']);
+
+    let mut output = String::with_capacity(text.len());
+    let mut elided_total = 0;
+    // (indentation, element count) of each open collection
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    // (indentation, elided count) of the collection whose remaining elements are being elided
+    let mut eliding: Option<(usize, usize)> = None;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        if let Some((eliding_indent, elided)) = eliding.as_mut() {
+            if indent > *eliding_indent {
+                if indent == *eliding_indent + INDENT && !is_closer(trimmed) {
+                    *elided += 1;
+                }
+                continue;
+            }
+            output.push_str(&" ".repeat(*eliding_indent + INDENT));
+            output.push_str(&format!("... {} more\n", elided));
+            elided_total += *elided;
+            eliding = None;
+        }
+        if let Some((open_indent, count)) = open.last_mut() {
+            if indent == *open_indent && is_closer(trimmed) {
+                open.pop();
+            } else if indent == *open_indent + INDENT && !is_closer(trimmed) {
+                *count += 1;
+                if *count > limit {
+                    eliding = Some((*open_indent, 1));
+                    continue;
+                }
+            }
+        }
+        if is_opener(trimmed) {
+            open.push((indent, 0));
+        }
+        output.push_str(line);
+        output.push('\n');
+    }
+    if let Some((eliding_indent, elided)) = eliding {
+        output.push_str(&" ".repeat(eliding_indent + INDENT));
+        output.push_str(&format!("... {} more\n", elided));
+        elided_total += elided;
+    }
+    if !text.ends_with('\n') {
+        output.pop();
     }
+    (output, elided_total)
 }
 
-fn run_to_completion<M, C>(data: Data<M::Action, C>) -> ResponseBox
+/// Exports a path in the format requested via `?format=`: `json` (the default), `tla`, or `dot`.
+fn export_path_as<M>(model: &M, query: &str) -> Result<ExplorerResponse, String>
 where
     M: Model,
     M::Action: Debug,
-    M::State: Hash,
-    C: Checker<M>,
+    M::State: Debug + Hash,
 {
-    let checker = &data.1;
-    checker.run_to_completion();
-    Response::empty(StatusCode(200)).boxed()
+    let (encoded, params) = query.split_once('?').unwrap_or((query, ""));
+    let format = params
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == "format")
+        .map_or("json", |(_, value)| value);
+    let text = |content_type, body: String| ExplorerResponse {
+        status: 200,
+        content_type: Some(content_type),
+        body: body.into_bytes(),
+    };
+    let path = || {
+        Path::from_encoded(model, encoded).ok_or_else(|| format!("Unable to find path {}", encoded))
+    };
+    match format {
+        "json" => export_path(model, encoded).map(|view| ExplorerResponse::json(&view)),
+        "tla" => Ok(text("text/plain", export::tla_trace(model, path()?))),
+        "dot" => Ok(text("text/vnd.graphviz", export::dot_graph(model, path()?))),
+        _ => Err(format!("Unsupported format {}", format)),
+    }
 }
 
-fn get_properties<C, M>(checker: &Arc<C>) -> Vec<Property>
+/// Describes every step along an encoded path, for sharing or for consumption by tests.
+fn export_path<M>(model: &M, encoded: &str) -> Result<PathView, String>
 where
     M: Model,
-    M::State: Hash,
-    C: Checker<M>,
+    M::Action: Debug,
+    M::State: Debug + Hash,
 {
-    checker
-        .model()
-        .properties()
-        .into_iter()
-        .map(|p| {
-            (
-                p.expectation,
-                p.name.to_string(),
-                checker.discovery(p.name).map(|p| p.encode()),
-            )
-        })
-        .collect()
+    let path = Path::from_encoded(model, encoded)
+        .ok_or_else(|| format!("Unable to find path {}", encoded))?;
+    let encoded = path.encode();
+    let mut steps = Vec::new();
+    let mut prev_action = None;
+    for (state, action) in path.into_vec() {
+        steps.push(PathStepView {
+            action: prev_action.map(|a| model.format_action(&a)),
+            state: model
+                .format_state(&state)
+                .unwrap_or_else(|| format!("{:#?}", state)),
+            fingerprint: fingerprint(&state).to_string(),
+        });
+        prev_action = action;
+    }
+    Ok(PathView {
+        path: encoded,
+        steps,
+    })
 }
 
-fn states<M, C>(path: &str, data: Data<M::Action, C>) -> Result<Vec<StateView<M::State>>, String>
+fn states<M, C>(path: &str, data: Data<M, C>) -> Result<Vec<StateView<M::State>>, String>
 where
     M: Model,
     M::Action: Debug,
     M::State: Debug + Hash,
     C: Checker<M>,
 {
-    let checker = &data.1;
+    let checker = &data.checker;
     let model = &checker.model();
+    let conditions = |state: &M::State| -> Vec<bool> {
+        model
+            .properties()
+            .iter()
+            .map(|p| (p.condition)(model, state))
+            .collect()
+    };
+    let clauses = |state: &M::State| -> Vec<Clause> {
+        model
+            .properties()
+            .iter()
+            .flat_map(|p| {
+                p.conjuncts.iter().map(|(conjunct_name, condition)| {
+                    (
+                        p.name.to_string(),
+                        conjunct_name.to_string(),
+                        condition(model, state),
+                    )
+                })
+            })
+            .collect()
+    };
+    let breakpoints = |state: &M::State| -> Vec<String> {
+        data.breakpoints
+            .iter()
+            .filter(|(_, condition)| condition(model, state))
+            .map(|(name, _)| name.to_string())
+            .collect()
+    };
+
+    let visits = |src: Option<&M::State>, dst: Fingerprint| -> Option<VisitsView> {
+        data.heatmap.as_ref().map(|counts| VisitsView {
+            state: counts.state_by_fingerprint(dst),
+            transition: src.map(|src| counts.transition_by_fingerprint(fingerprint(src), dst)),
+        })
+    };
+
+    let (path, params) = path.split_once('?').unwrap_or((path, ""));
+    let mut collection_limit = Some(STATE_COLLECTION_DEFAULT_LIMIT);
+    let mut successor_limit = Some(SUCCESSOR_DEFAULT_LIMIT);
+    for (key, value) in params.split('&').filter_map(|pair| pair.split_once('=')) {
+        let limit = match key {
+            "collection_limit" => &mut collection_limit,
+            "successor_limit" => &mut successor_limit,
+            _ => continue,
+        };
+        let parsed = value
+            .parse::<usize>()
+            .map_err(|_| format!("Unable to parse {} {}", key.replace('_', " "), value))?;
+        *limit = if parsed == 0 { None } else { Some(parsed) };
+    }
 
     // extract fingerprints
     let mut fingerprints_str = path.to_string();
@@ -261,9 +1436,16 @@ where
             results.push(StateView {
                 action: None,
                 outcome: None,
+                conditions: conditions(&state),
+                clauses: clauses(&state),
+                breakpoints: breakpoints(&state),
+                formatted_state: model.format_state(&state),
                 state: Some(state),
                 properties: get_properties(checker),
                 svg,
+                collection_limit,
+                omitted: 0,
+                visits: visits(None, fingerprint),
             });
         }
     } else if let Some(last_state) = Path::final_state::<M>(model, fingerprints.clone()) {
@@ -275,7 +1457,14 @@ where
         model.actions(&last_state, &mut actions1);
         model.actions(&last_state, &mut actions2);
         model.actions(&last_state, &mut actions3);
-        for ((action, action2), action3) in actions1.into_iter().zip(actions2).zip(actions3) {
+        let classes: Vec<String> = actions1.iter().map(action_class).collect();
+        let (is_sampled, omitted) = sample_successors(&classes, successor_limit);
+        for (i, ((action, action2), action3)) in
+            actions1.into_iter().zip(actions2).zip(actions3).enumerate()
+        {
+            if !is_sampled[i] {
+                continue;
+            }
             let outcome = model.format_step(&last_state, action2);
             let state = model.next_state(&last_state, action3);
             log::debug!(
@@ -294,9 +1483,16 @@ where
                 results.push(StateView {
                     action: Some(model.format_action(&action)),
                     outcome,
+                    conditions: conditions(&state),
+                    clauses: clauses(&state),
+                    breakpoints: breakpoints(&state),
+                    formatted_state: model.format_state(&state),
                     state: Some(state),
                     properties: get_properties(checker),
                     svg,
+                    collection_limit,
+                    omitted: 0,
+                    visits: visits(Some(&last_state), fingerprint),
                 });
             } else {
                 // "Action ignored" case is still returned, as it may be useful for debugging.
@@ -304,11 +1500,34 @@ where
                     action: Some(model.format_action(&action)),
                     outcome: None,
                     state: None,
+                    formatted_state: None,
                     properties: get_properties(checker),
+                    conditions: Vec::new(),
+                    clauses: Vec::new(),
+                    breakpoints: Vec::new(),
                     svg: None,
+                    collection_limit,
+                    omitted: 0,
+                    visits: None,
                 });
             }
         }
+        for (class, count) in omitted {
+            results.push(StateView {
+                action: Some(class),
+                outcome: None,
+                state: None,
+                formatted_state: None,
+                properties: Vec::new(),
+                conditions: Vec::new(),
+                clauses: Vec::new(),
+                breakpoints: Vec::new(),
+                svg: None,
+                collection_limit,
+                omitted: count,
+                visits: None,
+            });
+        }
     } else {
         return Err(format!(
             "Unable to find state following fingerprints {}",
@@ -326,6 +1545,36 @@ mod test {
     use crate::test_util::binary_clock::*;
     use lazy_static::lazy_static;
 
+    /// Explorer data with the default options, which tests override as needed.
+    fn explorer_data<M: Model, C: Checker<M>>(checker: Arc<C>) -> ExplorerData<M, C> {
+        ExplorerData {
+            snapshot: Arc::new(RwLock::new(Snapshot(true, None))),
+            checker,
+            predicates: Vec::new(),
+            breakpoints: Vec::new(),
+            read_only: false,
+            authorization: None,
+            cluster: None,
+            heatmap: None,
+        }
+    }
+
+    #[test]
+    fn local_explorer_checks_once_asked_to_run_to_completion() {
+        let explorer = BinaryClock.checker().explorer().local();
+        assert_eq!(explorer.handle("GET", "/app.js").status, 200);
+        assert_eq!(explorer.handle("GET", "/.states").status, 200);
+        assert!(!explorer.step(1));
+        assert!(!explorer.checker().is_done());
+
+        assert_eq!(explorer.handle("POST", "/.runtocompletion").status, 200);
+        while explorer.step(1) {}
+        assert!(explorer.checker().is_done());
+        let status: serde_json::Value =
+            serde_json::from_slice(&explorer.handle("GET", "/.status").body).unwrap();
+        assert_eq!(status["done"], true);
+    }
+
     #[test]
     fn can_init() {
         let checker = Arc::new(BinaryClock.checker().spawn_bfs().join());
@@ -336,15 +1585,29 @@ mod test {
                     action: None,
                     outcome: None,
                     state: Some(0),
+                    formatted_state: None,
                     properties: vec![(Expectation::Always, "in [0, 1]".to_owned(), None)],
-                    svg: None
+                    conditions: vec![true],
+                    clauses: vec![],
+                    breakpoints: vec![],
+                    svg: None,
+                    collection_limit: Some(STATE_COLLECTION_DEFAULT_LIMIT),
+                    omitted: 0,
+                    visits: None,
                 },
                 StateView {
                     action: None,
                     outcome: None,
                     state: Some(1),
+                    formatted_state: None,
                     properties: vec![(Expectation::Always, "in [0, 1]".to_owned(), None)],
-                    svg: None
+                    conditions: vec![true],
+                    clauses: vec![],
+                    breakpoints: vec![],
+                    svg: None,
+                    collection_limit: Some(STATE_COLLECTION_DEFAULT_LIMIT),
+                    omitted: 0,
+                    visits: None,
                 },
             ]
         );
@@ -374,8 +1637,15 @@ mod test {
                 action: Some("GoHigh".to_string()),
                 outcome: Some("1".to_string()),
                 state: Some(1),
+                formatted_state: None,
                 properties: vec![(Expectation::Always, "in [0, 1]".to_owned(), None)],
+                conditions: vec![true],
+                clauses: vec![],
+                breakpoints: vec![],
                 svg: None,
+                collection_limit: Some(STATE_COLLECTION_DEFAULT_LIMIT),
+                omitted: 0,
+                visits: None,
             },]
         );
     }
@@ -427,10 +1697,14 @@ mod test {
                         history: (0, 1),
                         timers_set: vec![Timers::new(); 2],
                         crashed: vec![false; 2],
+                        recoveries: 0,
+                        failed_assertions: vec![],
+                        forbidden_sends: vec![],
                         network: Network::new_unordered_nonduplicating([
                             Envelope { src: Id::from(0), dst: Id::from(1), msg: Ping(0) },
                         ]),
                     }),
+                    formatted_state: Some("ACTOR STATES:\n  Id(0): 0\n  Id(1): 0\n\nNETWORK (unordered_nonduplicating, 1 in flight):\n  Id(0) → Id(1):\n    Ping(0)\n\nTIMERS: [Timers({}), Timers({})]\nHISTORY: (\n    0,\n    1,\n)\n".to_string()),
                     properties: vec![
                         (Expectation::Always, "delta within 1".into(), None),
                         (Expectation::Sometimes, "can reach max".into(), Some("9825351251631602636/3760012235735042049/7133060688412568841/11622042860899162315".into())),
@@ -439,7 +1713,13 @@ mod test {
                         (Expectation::Always, "#in <= #out".into(), None),
                         (Expectation::Eventually, "#out <= #in + 1".into(), None),
                     ],
+                    conditions: vec![true, false, false, false, true, true],
+                    clauses: vec![],
+                    breakpoints: vec![],
                     svg: Some("<svg version=\'1.1\' baseProfile=\'full\' width=\'500\' height=\'30\' viewbox=\'-20 -20 520 50\' xmlns=\'http://www.w3.org/2000/svg\'><defs><marker class=\'svg-event-shape\' id=\'arrow\' markerWidth=\'12\' markerHeight=\'10\' refX=\'12\' refY=\'5\' orient=\'auto\'><polygon points=\'0 0, 12 5, 0 10\' /></marker></defs><line x1=\'0\' y1=\'0\' x2=\'0\' y2=\'30\' class=\'svg-actor-timeline\' />\n<text x=\'0\' y=\'0\' class=\'svg-actor-label\'>0</text>\n<line x1=\'100\' y1=\'0\' x2=\'100\' y2=\'30\' class=\'svg-actor-timeline\' />\n<text x=\'100\' y=\'0\' class=\'svg-actor-label\'>1</text>\n</svg>\n".to_string()),
+                    collection_limit: Some(STATE_COLLECTION_DEFAULT_LIMIT),
+                    omitted: 0,
+                    visits: None,
                 },
             ]);
 
@@ -451,6 +1731,9 @@ mod test {
                     history: (0, 1),
                     timers_set: vec![Timers::new(); 2],
                     crashed: vec![false; 2],
+                    recoveries: 0,
+                    failed_assertions: vec![],
+                    forbidden_sends: vec![],
                     network: Network::new_unordered_nonduplicating([Envelope {
                         src: Id::from(0),
                         dst: Id::from(1),
@@ -472,8 +1755,12 @@ mod test {
                     history: (0, 1),
                     timers_set: vec![Timers::new(); 2],
                     crashed: vec![false; 2],
+                    recoveries: 0,
+                    failed_assertions: vec![],
+                    forbidden_sends: vec![],
                     network: Network::new_unordered_nonduplicating([]),
                 }),
+                formatted_state: Some("ACTOR STATES:\n  Id(0): 0\n  Id(1): 0\n\nNETWORK (unordered_nonduplicating, 0 in flight):\n\nTIMERS: [Timers({}), Timers({})]\nHISTORY: (\n    0,\n    1,\n)\n".to_string()),
                 properties: vec![
                     (Expectation::Always, "delta within 1".into(), None),
                     (Expectation::Sometimes, "can reach max".into(), Some("9825351251631602636/3760012235735042049/7133060688412568841/11622042860899162315".into())),
@@ -482,7 +1769,13 @@ mod test {
                     (Expectation::Always, "#in <= #out".into(), None),
                     (Expectation::Eventually, "#out <= #in + 1".into(), None),
                 ],
+                conditions: vec![true, false, false, false, true, true],
+                clauses: vec![],
+                breakpoints: vec![],
                 svg: Some("<svg version='1.1' baseProfile='full' width='500' height='60' viewbox='-20 -20 520 80' xmlns='http://www.w3.org/2000/svg'><defs><marker class='svg-event-shape' id='arrow' markerWidth='12' markerHeight='10' refX='12' refY='5' orient='auto'><polygon points='0 0, 12 5, 0 10' /></marker></defs><line x1='0' y1='0' x2='0' y2='60' class='svg-actor-timeline' />\n<text x='0' y='0' class='svg-actor-label'>0</text>\n<line x1='100' y1='0' x2='100' y2='60' class='svg-actor-timeline' />\n<text x='100' y='0' class='svg-actor-label'>1</text>\n</svg>\n".to_string()),
+                collection_limit: Some(STATE_COLLECTION_DEFAULT_LIMIT),
+                omitted: 0,
+                visits: None,
             });
         assert_eq!(
             states[1],
@@ -497,10 +1790,14 @@ mod test {
                     history: (1, 2),
                     timers_set: vec![Timers::new(); 2],
                     crashed: vec![false; 2],
+                    recoveries: 0,
+                    failed_assertions: vec![],
+                    forbidden_sends: vec![],
                     network: Network::new_unordered_nonduplicating([
                         Envelope { src: Id::from(1), dst: Id::from(0), msg: Pong(0) },
                     ]),
                 }),
+                formatted_state: Some("ACTOR STATES:\n  Id(0): 0\n  Id(1): 1\n\nNETWORK (unordered_nonduplicating, 1 in flight):\n  Id(1) → Id(0):\n    Pong(0)\n\nTIMERS: [Timers({}), Timers({})]\nHISTORY: (\n    1,\n    2,\n)\n".to_string()),
                 properties: vec![
                     (Expectation::Always, "delta within 1".into(), None),
                     (Expectation::Sometimes, "can reach max".into(), Some("9825351251631602636/3760012235735042049/7133060688412568841/11622042860899162315".into())),
@@ -509,10 +1806,609 @@ mod test {
                     (Expectation::Always, "#in <= #out".into(), None),
                     (Expectation::Eventually, "#out <= #in + 1".into(), None),
                 ],
+                conditions: vec![true, false, false, false, true, true],
+                clauses: vec![],
+                breakpoints: vec![],
                 svg: Some("<svg version='1.1' baseProfile='full' width='500' height='60' viewbox='-20 -20 520 80' xmlns='http://www.w3.org/2000/svg'><defs><marker class='svg-event-shape' id='arrow' markerWidth='12' markerHeight='10' refX='12' refY='5' orient='auto'><polygon points='0 0, 12 5, 0 10' /></marker></defs><line x1='0' y1='0' x2='0' y2='60' class='svg-actor-timeline' />\n<text x='0' y='0' class='svg-actor-label'>0</text>\n<line x1='100' y1='0' x2='100' y2='60' class='svg-actor-timeline' />\n<text x='100' y='0' class='svg-actor-label'>1</text>\n<line x1='0' x2='100' y1='0' y2='30' marker-end='url(#arrow)' class='svg-event-line' />\n<text x='100' y='30' class='svg-event-label'>Ping(0)</text>\n</svg>\n".to_string()),
+                collection_limit: Some(STATE_COLLECTION_DEFAULT_LIMIT),
+                omitted: 0,
+                visits: None,
             });
     }
 
+    #[test]
+    fn can_search() {
+        use crate::test_util::dgraph::DGraph;
+
+        let model = DGraph::with_property(crate::Property::always("unused", |_, _| true))
+            .with_path(vec![1, 2, 3, 4])
+            .with_path(vec![1, 5, 4])
+            .with_path(vec![6, 7]);
+        let checker = Arc::new(model.checker().spawn_bfs().join());
+        let predicates: Vec<Predicate<DGraph>> = vec![
+            ("is four", |_, s| *s == 4),
+            ("is odd", |_, s| *s % 2 == 1),
+            ("is zero", |_, s| *s == 0),
+        ];
+        let path = |states: &[u8]| {
+            states
+                .iter()
+                .map(|s| fingerprint(s).to_string())
+                .collect::<Vec<_>>()
+                .join("/")
+        };
+
+        // Only the shortest path to each match is returned.
+        assert_eq!(
+            get_search(Arc::clone(&checker), predicates.clone(), "0").unwrap(),
+            SearchView {
+                predicate: "is four".to_string(),
+                matches: vec![path(&[1, 5, 4])],
+                explored_state_count: 7,
+                complete: true,
+            }
+        );
+
+        // The search stops early when the limit is reached.
+        let view = get_search(Arc::clone(&checker), predicates.clone(), "1?limit=2").unwrap();
+        assert_eq!(view.matches, vec![path(&[1]), path(&[1, 5])]);
+        assert!(!view.complete);
+
+        let view = get_search(Arc::clone(&checker), predicates.clone(), "2").unwrap();
+        assert_eq!(view.matches, Vec::<String>::new());
+        assert!(view.complete);
+
+        assert_eq!(
+            get_search(Arc::clone(&checker), predicates.clone(), "3").unwrap_err(),
+            "Unable to find predicate 3"
+        );
+        assert_eq!(
+            get_search(Arc::clone(&checker), predicates, "0?limit=x").unwrap_err(),
+            "Unable to parse limit x"
+        );
+    }
+
+    #[test]
+    fn can_export_path() {
+        let encoded = format!("{}/{}", fingerprint(&0_i8), fingerprint(&1_i8));
+        assert_eq!(
+            export_path(&BinaryClock, &format!("{}/", encoded)).unwrap(),
+            PathView {
+                path: encoded,
+                steps: vec![
+                    PathStepView {
+                        action: None,
+                        state: "0".to_string(),
+                        fingerprint: fingerprint(&0_i8).to_string(),
+                    },
+                    PathStepView {
+                        action: Some("GoHigh".to_string()),
+                        state: "1".to_string(),
+                        fingerprint: fingerprint(&1_i8).to_string(),
+                    },
+                ],
+            }
+        );
+        assert_eq!(
+            export_path(&BinaryClock, "1/2").unwrap_err(),
+            "Unable to find path 1/2"
+        );
+    }
+
+    #[test]
+    fn can_resolve_path_without_serving() {
+        let encoded = format!("{}/{}", fingerprint(&0_i8), fingerprint(&1_i8));
+        let mut output = Vec::new();
+        BinaryClock
+            .checker()
+            .explorer()
+            .resolve(&encoded, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "Path: {}\n\n0. Initial state [{}]\n0\n\n1. GoHigh [{}]\n1\n",
+                encoded,
+                fingerprint(&0_i8),
+                fingerprint(&1_i8)
+            )
+        );
+
+        let error = BinaryClock
+            .checker()
+            .explorer()
+            .resolve("1/2", &mut Vec::new())
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(error.to_string(), "Unable to find path 1/2");
+    }
+
+    #[test]
+    fn describes_attached_cluster() {
+        use crate::actor::Id;
+        use std::net::{Ipv4Addr, SocketAddrV4};
+
+        let metrics = Arc::new(RuntimeMetrics::default());
+        let id = Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 3000));
+        let actor = metrics.register(id);
+        actor.on_state_change();
+        actor.refresh_state(|| unreachable!("states are not recorded"));
+        metrics.record_states();
+        actor.refresh_state(|| "Ready".to_string());
+        actor.refresh_state(|| unreachable!("state is unchanged"));
+        actor.on_send();
+        metrics.register(Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 3001)));
+
+        let checker = Arc::new(BinaryClock.checker().spawn_bfs().join());
+        let data = |cluster| {
+            Arc::new(ExplorerData {
+                cluster,
+                ..explorer_data(Arc::clone(&checker))
+            })
+        };
+        assert_eq!(handle(&Method::Get, "/.cluster", &data(None)).status, 404);
+
+        let data = data(Some(metrics));
+        assert!(status(Arc::clone(&data)).cluster);
+        assert_eq!(
+            cluster(data).unwrap(),
+            vec![
+                ClusterActorView {
+                    id: "127.0.0.1:3000".to_string(),
+                    state: Some("Ready".to_string()),
+                    messages_sent: 1,
+                    ..Default::default()
+                },
+                ClusterActorView {
+                    id: "127.0.0.1:3001".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn overlays_visit_counts() {
+        use BinaryClockAction::*;
+
+        let (counter, counts) = VisitCounter::new_with_counts();
+        for actions in [vec![], vec![GoHigh], vec![GoHigh, GoLow], vec![GoHigh]] {
+            let path = Path::from_actions(&BinaryClock, 0, &actions).unwrap();
+            counter.visit(&BinaryClock, path);
+        }
+        assert_eq!(counts.state(&0), 2);
+        assert_eq!(counts.transition(&0, &1), 2);
+
+        let data = Arc::new(ExplorerData {
+            heatmap: Some(counts),
+            ..explorer_data(Arc::new(BinaryClock.checker().spawn_bfs().join()))
+        });
+        assert_eq!(
+            status(Arc::clone(&data)).heatmap,
+            Some(HeatmapView {
+                max_state_visits: 2,
+                max_transition_visits: 2,
+            })
+        );
+        let visits = |path: &str| -> Vec<Option<VisitsView>> {
+            states(path, Arc::clone(&data))
+                .unwrap()
+                .into_iter()
+                .map(|view| view.visits)
+                .collect()
+        };
+        assert_eq!(
+            visits("/"),
+            vec![
+                Some(VisitsView {
+                    state: 2,
+                    transition: None,
+                }),
+                Some(VisitsView {
+                    state: 2,
+                    transition: None,
+                }),
+            ]
+        );
+        let fp = fingerprint(&1_i8);
+        assert_eq!(
+            visits(&format!("/{}", fp)),
+            vec![Some(VisitsView {
+                state: 2,
+                transition: Some(1),
+            })]
+        );
+    }
+
+    #[test]
+    fn paginates_large_collections() {
+        let text = format!("{:#?}", (vec![1, 2, 3], vec![vec![4, 5, 6]]));
+        assert_eq!(paginate_collections(&text, 3), (text.clone(), 0));
+        assert_eq!(
+            paginate_collections(&text, 2),
+            (
+                "(\n    [\n        1,\n        2,\n        ... 1 more\n    ],\n    [\n        [\n            4,\n            5,\n            ... 1 more\n        ],\n    ],\n)".to_string(),
+                2
+            )
+        );
+        assert_eq!(
+            paginate_collections(&text, 1),
+            (
+                "(\n    [\n        1,\n        ... 2 more\n    ],\n    ... 1 more\n)".to_string(),
+                3
+            )
+        );
+        assert_eq!(
+            paginate_collections("unstructured\ntext\n", 1),
+            ("unstructured\ntext\n".to_string(), 0)
+        );
+
+        // The limit is configurable per request, and 0 disables pagination.
+        let checker = Arc::new(BinaryClock.checker().spawn_bfs().join());
+        let data = Arc::new(explorer_data(checker));
+        let views = states("?collection_limit=1", Arc::clone(&data)).unwrap();
+        assert_eq!(views[0].collection_limit, Some(1));
+        let views = states("?collection_limit=0", Arc::clone(&data)).unwrap();
+        assert_eq!(views[0].collection_limit, None);
+        assert_eq!(
+            states("?collection_limit=many", data).unwrap_err(),
+            "Unable to parse collection limit many"
+        );
+    }
+
+    #[test]
+    fn samples_successors_per_action_class() {
+        let classes: Vec<String> = ["A", "B", "A", "A", "A", "B"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(
+            sample_successors(&classes, Some(2)),
+            (
+                vec![true, true, false, true, false, true],
+                vec![("A".to_string(), 2)]
+            )
+        );
+        assert_eq!(sample_successors(&classes, None), (vec![true; 6], vec![]));
+        assert_eq!(action_class(&Some(1)), "Some");
+        assert_eq!(action_class(&"label"), "");
+
+        struct Fanout;
+        impl Model for Fanout {
+            type State = u8;
+            type Action = Option<u8>;
+            fn init_states(&self) -> Vec<Self::State> {
+                vec![0]
+            }
+            fn actions(&self, state: &Self::State, actions: &mut Vec<Self::Action>) {
+                if *state == 0 {
+                    actions.extend((1..=10).map(Some));
+                    actions.push(None);
+                }
+            }
+            fn next_state(&self, _: &Self::State, action: Self::Action) -> Option<Self::State> {
+                action
+            }
+        }
+        let data = Arc::new(explorer_data(Arc::new(Fanout.checker().spawn_bfs().join())));
+        let path = format!("/{}", fingerprint(&0_u8));
+        let actions = |query: &str| -> Vec<(Option<String>, usize)> {
+            states(&format!("{}{}", path, query), Arc::clone(&data))
+                .unwrap()
+                .into_iter()
+                .map(|view| (view.action, view.omitted))
+                .collect()
+        };
+        assert_eq!(
+            actions("?successor_limit=3"),
+            vec![
+                (Some("Some(1)".to_string()), 0),
+                (Some("Some(4)".to_string()), 0),
+                (Some("Some(7)".to_string()), 0),
+                (Some("None".to_string()), 0),
+                (Some("Some".to_string()), 7),
+            ]
+        );
+        assert_eq!(actions("?successor_limit=0").len(), 11);
+        assert_eq!(actions("").len(), 11);
+    }
+
+    #[test]
+    fn can_find_fingerprint() {
+        let checker = Arc::new(BinaryClock.checker().spawn_bfs().join());
+        let data = Arc::new(explorer_data(checker));
+        // Both states are initial states, so each path has a single fingerprint.
+        assert_eq!(
+            find_fingerprint(&fingerprint(&1_i8).to_string(), Arc::clone(&data)).unwrap(),
+            FingerprintView {
+                fingerprint: fingerprint(&1_i8).to_string(),
+                path: fingerprint(&1_i8).to_string(),
+            }
+        );
+        assert_eq!(
+            find_fingerprint("abc", Arc::clone(&data)).unwrap_err(),
+            "Unable to parse fingerprint abc"
+        );
+        assert_eq!(
+            find_fingerprint("1", data).unwrap_err(),
+            "Unable to find a state with fingerprint 1 after exploring 2 states"
+        );
+    }
+
+    #[test]
+    fn takes_reproducible_random_walks() {
+        let checker = Arc::new(BinaryClock.checker().spawn_bfs().join());
+        let data = Arc::new(explorer_data(checker));
+        let (low, high) = (fingerprint(&0_i8), fingerprint(&1_i8));
+
+        let walk = random_walk("?steps=3&seed=7", Arc::clone(&data)).unwrap();
+        assert_eq!(walk.seed, "7");
+        assert_eq!(walk.steps, 3);
+        assert_eq!(walk.path.split('/').count(), 4);
+        assert_eq!(
+            walk,
+            random_walk("/?steps=3&seed=7", Arc::clone(&data)).unwrap()
+        );
+
+        // The clock alternates, so a walk from a given state is fully determined.
+        assert_eq!(
+            random_walk(&format!("/{}?steps=2", high), Arc::clone(&data))
+                .unwrap()
+                .path,
+            format!("{}/{}/{}", high, low, high)
+        );
+        assert_eq!(
+            random_walk("?steps=many", Arc::clone(&data)).unwrap_err(),
+            "Unable to parse steps many"
+        );
+        assert_eq!(handle(&Method::Get, "/.walk/1?steps=1", &data).status, 404);
+    }
+
+    #[test]
+    fn handles_requests_without_a_server() {
+        let checker = Arc::new(BinaryClock.checker().spawn_bfs().join());
+        let data = Arc::new(explorer_data(checker));
+
+        let response = handle(&Method::Get, "/.properties", &data);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.content_type, Some("application/json"));
+        assert_eq!(
+            String::from_utf8(response.body).unwrap(),
+            r#"[{"expectation":"Always","name":"in [0, 1]","discovery":null}]"#
+        );
+
+        let response = handle(&Method::Get, "/.states/1/2/3", &data);
+        assert_eq!(response.status, 404);
+        assert_eq!(
+            String::from_utf8(response.body).unwrap(),
+            "Unable to find state following fingerprints /1/2/3"
+        );
+        let encoded = format!("{}/{}", fingerprint(&0_i8), fingerprint(&1_i8));
+        let response = handle(
+            &Method::Get,
+            &format!("/.path/{}?format=dot", encoded),
+            &data,
+        );
+        assert_eq!(response.status, 200);
+        assert_eq!(response.content_type, Some("text/vnd.graphviz"));
+        assert!(String::from_utf8(response.body)
+            .unwrap()
+            .starts_with("digraph path {"));
+        let response = handle(
+            &Method::Get,
+            &format!("/.path/{}?format=tla", encoded),
+            &data,
+        );
+        assert_eq!(response.content_type, Some("text/plain"));
+        assert!(String::from_utf8(response.body)
+            .unwrap()
+            .ends_with("[action |-> \"GoHigh\", state |-> 1]\n>>\n"));
+        let response = handle(
+            &Method::Get,
+            &format!("/.path/{}?format=xml", encoded),
+            &data,
+        );
+        assert_eq!(response.status, 404);
+        assert_eq!(handle(&Method::Get, "/.unknown", &data).status, 404);
+        assert_eq!(handle(&Method::Post, "/.status", &data).status, 404);
+        assert_eq!(
+            handle(&Method::Post, "/.runtocompletion", &data).status,
+            200
+        );
+    }
+
+    #[test]
+    fn read_only_mode_rejects_checking_requests() {
+        let checker = Arc::new(BinaryClock.checker().spawn_bfs().join());
+        let data = Arc::new(ExplorerData {
+            read_only: true,
+            ..explorer_data(checker)
+        });
+        assert_eq!(
+            handle(&Method::Post, "/.runtocompletion", &data).status,
+            403
+        );
+        assert_eq!(handle(&Method::Put, "/.states", &data).status, 403);
+        assert_eq!(handle(&Method::Delete, "/.states", &data).status, 403);
+        assert_eq!(handle(&Method::Get, "/.states", &data).status, 200);
+        assert!(status(data).read_only);
+    }
+
+    #[test]
+    fn can_require_basic_auth() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(
+            base64(b"Aladdin:open sesame"),
+            "QWxhZGRpbjpvcGVuIHNlc2FtZQ=="
+        );
+
+        let expected = ExplorerBuilder::new(BinaryClock.checker())
+            .basic_auth("Aladdin", "open sesame")
+            .authorization;
+        assert!(is_authorized(
+            Some("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="),
+            &expected
+        ));
+        assert!(!is_authorized(Some("Basic Zm9v"), &expected));
+        assert!(!is_authorized(
+            Some("Basic QWxhZGRpbjpvcGVuIHNlc2FtZR=="),
+            &expected
+        ));
+        assert!(!is_authorized(None, &expected));
+        assert!(is_authorized(None, &None));
+    }
+
+    #[test]
+    fn json_api_is_stable() {
+        use serde_json::json;
+
+        let checker = Arc::new(BinaryClock.checker().spawn_bfs().join());
+        let data = Arc::new(ExplorerData {
+            predicates: vec![("is high", |_, s| *s == 1)],
+            ..explorer_data(checker)
+        });
+
+        let status = serde_json::to_value(status(Arc::clone(&data))).unwrap();
+        let mut keys: Vec<_> = status.as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![
+                "breakpoints",
+                "cluster",
+                "done",
+                "heatmap",
+                "max_depth",
+                "model",
+                "predicates",
+                "properties",
+                "read_only",
+                "recent_path",
+                "state_count",
+                "unique_state_count",
+            ]
+        );
+        assert_eq!(status["predicates"], json!(["is high"]));
+        assert_eq!(
+            serde_json::to_value(properties(Arc::clone(&data))).unwrap(),
+            json!([{"expectation": "Always", "name": "in [0, 1]", "discovery": null}])
+        );
+        assert_eq!(
+            serde_json::to_value(states(&format!("/{}", fingerprint(&0_i8)), data).unwrap())
+                .unwrap(),
+            json!([{
+                "action": "GoHigh",
+                "outcome": "1",
+                "state": "1",
+                "fingerprint": fingerprint(&1_i8).to_string(),
+                "properties": [["Always", "in [0, 1]", null]],
+                "conditions": [true],
+            }])
+        );
+    }
+
+    #[test]
+    fn explains_properties_via_clauses() {
+        use crate::test_util::dgraph::DGraph;
+
+        let model = DGraph::with_property(crate::Property::all(
+            "small and odd",
+            vec![
+                crate::Property::always("small", |_, s| *s < 3),
+                crate::Property::always("odd", |_, s| *s % 2 == 1),
+            ],
+        ))
+        .with_path(vec![1, 2, 3]);
+        let checker = Arc::new(model.checker().spawn_bfs().join());
+        let data = Arc::new(explorer_data(checker));
+        let states = states(&format!("/{}", fingerprint(&1_u8)), data).unwrap();
+        assert_eq!(states[0].conditions, vec![false]);
+        assert_eq!(
+            states[0].clauses,
+            vec![
+                ("small and odd".to_string(), "small".to_string(), true),
+                ("small and odd".to_string(), "odd".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_continue_to_breakpoint() {
+        use crate::test_util::dgraph::DGraph;
+
+        let model = DGraph::with_property(crate::Property::always("unused", |_, _| true))
+            .with_path(vec![1, 2, 3, 4])
+            .with_path(vec![1, 5, 4])
+            .with_path(vec![6, 7]);
+        let checker = Arc::new(model.checker().spawn_bfs().join());
+        let data = Arc::new(ExplorerData {
+            breakpoints: vec![("is four", |_, s| *s == 4)],
+            ..explorer_data(checker)
+        });
+        let path = |states: &[u8]| {
+            states
+                .iter()
+                .map(|s| fingerprint(s).to_string())
+                .collect::<Vec<_>>()
+                .join("/")
+        };
+
+        // Continues along the shortest path, excluding the starting state.
+        assert_eq!(
+            continue_to_breakpoint("", Arc::clone(&data)).unwrap().path,
+            path(&[1, 5, 4])
+        );
+        assert_eq!(
+            continue_to_breakpoint(&path(&[1]), Arc::clone(&data)).unwrap(),
+            BreakpointView {
+                breakpoint: "is four".to_string(),
+                path: path(&[1, 5, 4]),
+            }
+        );
+        assert_eq!(
+            continue_to_breakpoint(&path(&[1, 2]), Arc::clone(&data))
+                .unwrap()
+                .path,
+            path(&[1, 2, 3, 4])
+        );
+        assert_eq!(
+            continue_to_breakpoint(&path(&[1, 5, 4]), Arc::clone(&data)).unwrap_err(),
+            format!("No breakpoint is reachable from {}", path(&[1, 5, 4]))
+        );
+        assert!(continue_to_breakpoint(&path(&[6]), Arc::clone(&data)).is_err());
+
+        // Generated states indicate which breakpoints fired.
+        let states = states(&format!("/{}", path(&[1, 5])), data).unwrap();
+        assert_eq!(states.len(), 1);
+        assert_eq!(states[0].breakpoints, vec!["is four".to_string()]);
+    }
+
+    #[test]
+    fn streams_events_until_done() {
+        use crate::test_util::dgraph::DGraph;
+
+        let checker =
+            DGraph::with_property(crate::Property::sometimes("reaches 2", |_, s| *s == 2))
+                .with_path(vec![1, 2])
+                .checker()
+                .spawn_bfs()
+                .join();
+        let data = Arc::new(explorer_data(Arc::new(checker)));
+        let mut out = Vec::new();
+        stream_events(&mut out, data).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let path = format!("{}/{}", fingerprint(&1_u8), fingerprint(&2_u8));
+        assert!(out.starts_with("HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n"));
+        assert!(out.contains(&format!(
+            "event: discovery\ndata: {{\"expectation\":\"Sometimes\",\"name\":\"reaches 2\",\"path\":\"{}\"}}\n\n",
+            path
+        )));
+        assert!(out.contains("event: status\ndata: {\"done\":true,"));
+    }
+
     #[test]
     fn smoke_test_status() {
         use crate::actor::actor_test_util::ping_pong::PingPongCfg;
@@ -576,7 +2472,10 @@ mod test {
         C: Checker<M>,
     {
         let snapshot = Arc::new(RwLock::new(Snapshot(true, None)));
-        let data = Arc::new((snapshot, checker));
+        let data = Arc::new(ExplorerData {
+            snapshot,
+            ..explorer_data(checker)
+        });
         states(path_name, data)
     }
 
@@ -587,7 +2486,29 @@ mod test {
         M::State: Debug + Hash,
         C: Checker<M>,
     {
-        let data = Arc::new((snapshot, checker));
+        let data = Arc::new(ExplorerData {
+            snapshot,
+            ..explorer_data(checker)
+        });
         status(data)
     }
+
+    fn get_search<M, C>(
+        checker: Arc<C>,
+        predicates: Vec<Predicate<M>>,
+        query: &str,
+    ) -> Result<SearchView, String>
+    where
+        M: Model,
+        M::State: Hash,
+        C: Checker<M>,
+    {
+        let snapshot = Arc::new(RwLock::new(Snapshot(true, None)));
+        let data = Arc::new(ExplorerData {
+            snapshot,
+            predicates,
+            ..explorer_data(checker)
+        });
+        search(query, data)
+    }
 }
@@ -0,0 +1,152 @@
+//! Renders paths in formats understood by other tools, namely TLA+ and Graphviz.
+
+use crate::checker::tla::{debug_to_tla, tla_string};
+use crate::{fingerprint, Model, Path};
+use std::collections::HashSet;
+use std::fmt::{Debug, Write};
+use std::hash::Hash;
+
+/// Renders a path as a TLA+ sequence of `[action |-> ..., state |-> ...]` records, suitable for
+/// a TLC trace expression. States are translated as described by [`Path::to_tla_trace`].
+pub(super) fn tla_trace<M>(model: &M, path: Path<M::State, M::Action>) -> String
+where
+    M: Model,
+    M::Action: Debug,
+    M::State: Debug + Hash,
+{
+    let mut out = String::new();
+    writeln!(
+        out,
+        "\\* Trace of {} exported by Stateright. Path: {}",
+        std::any::type_name::<M>(),
+        path.encode()
+    )
+    .unwrap();
+    writeln!(out, "<<").unwrap();
+    let steps = path.into_vec();
+    let step_count = steps.len();
+    let mut prev_action = None;
+    for (i, (state, action)) in steps.into_iter().enumerate() {
+        let label = match prev_action.take() {
+            None => "Init".to_string(),
+            Some(action) => model.format_action(&action),
+        };
+        let debug = format!("{:?}", state);
+        writeln!(
+            out,
+            "    [action |-> {}, state |-> {}]{}",
+            tla_string(&label),
+            debug_to_tla(&debug).unwrap_or_else(|| tla_string(&debug)),
+            if i + 1 < step_count { "," } else { "" }
+        )
+        .unwrap();
+        prev_action = action;
+    }
+    writeln!(out, ">>").unwrap();
+    out
+}
+
+/// Renders a path as a [Graphviz](https://graphviz.org/) DOT digraph with a node per distinct
+/// state and an edge per action. Revisited states share a node, so cycles remain visible.
+pub(super) fn dot_graph<M>(model: &M, path: Path<M::State, M::Action>) -> String
+where
+    M: Model,
+    M::Action: Debug,
+    M::State: Debug + Hash,
+{
+    let mut out = String::new();
+    writeln!(out, "digraph path {{").unwrap();
+    writeln!(out, "    node [shape=box, fontname=\"monospace\"];").unwrap();
+    let mut declared = HashSet::new();
+    let mut prev: Option<(String, M::Action)> = None;
+    for (i, (state, action)) in path.into_vec().into_iter().enumerate() {
+        let node = format!("s{}", fingerprint(&state));
+        if declared.insert(node.clone()) {
+            let label = model
+                .format_state(&state)
+                .unwrap_or_else(|| format!("{:#?}", state));
+            let style = if i == 0 { ", style=bold" } else { "" };
+            writeln!(
+                out,
+                "    {} [label=\"{}\"{}];",
+                node,
+                dot_label(&label),
+                style
+            )
+            .unwrap();
+        }
+        if let Some((prev_node, prev_action)) = prev.take() {
+            writeln!(
+                out,
+                "    {} -> {} [label=\"{}\"];",
+                prev_node,
+                node,
+                dot_label(&model.format_action(&prev_action))
+            )
+            .unwrap();
+        }
+        prev = action.map(|action| (node, action));
+    }
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+/// Escapes text for a DOT label, left-justifying each line.
+fn dot_label(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\l"),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push_str("\\l");
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::binary_clock::BinaryClock;
+
+    #[test]
+    fn exports_tla_trace_and_dot_graph() {
+        let path = Path::from_actions(
+            &BinaryClock,
+            0,
+            &[
+                crate::test_util::binary_clock::BinaryClockAction::GoHigh,
+                crate::test_util::binary_clock::BinaryClockAction::GoLow,
+            ],
+        )
+        .unwrap();
+        let encoded = path.encode();
+        assert_eq!(
+            tla_trace(&BinaryClock, path.clone()),
+            format!(
+                "\\* Trace of stateright::test_util::binary_clock::BinaryClock exported by Stateright. Path: {}\n\
+                 <<\n    \
+                 [action |-> \"Init\", state |-> 0],\n    \
+                 [action |-> \"GoHigh\", state |-> 1],\n    \
+                 [action |-> \"GoLow\", state |-> 0]\n\
+                 >>\n",
+                encoded
+            )
+        );
+        let (s0, s1) = (fingerprint(&0_i8), fingerprint(&1_i8));
+        assert_eq!(
+            dot_graph(&BinaryClock, path),
+            format!(
+                "digraph path {{\n    \
+                 node [shape=box, fontname=\"monospace\"];\n    \
+                 s{s0} [label=\"0\\l\", style=bold];\n    \
+                 s{s1} [label=\"1\\l\"];\n    \
+                 s{s0} -> s{s1} [label=\"GoHigh\\l\"];\n    \
+                 s{s1} -> s{s0} [label=\"GoLow\\l\"];\n\
+                 }}\n"
+            )
+        );
+    }
+}
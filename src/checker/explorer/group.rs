@@ -0,0 +1,162 @@
+//! Serves the Explorers of several models from one web service.
+
+use super::{respond, respond_with, serve_requests, ExplorerBuilder, ExplorerResponse, Service};
+use crate::Model;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::net::ToSocketAddrs;
+use std::sync::Arc;
+use tiny_http::{Header, Response, StatusCode};
+
+/// Serves the Explorers of several named models from one web service, for instance so that a
+/// demo server can expose multiple examples on a single port. Each model's Explorer is served
+/// under `/{name}/`, `GET /.models` lists the names, and the UI offers a picker for switching
+/// between them.
+///
+/// # Example
+///
+/// ```no_run
+/// # use stateright::*;
+/// # let (model1, model2) = ((), ());
+/// ExplorerGroup::new()
+///     .model("first", model1.checker().explorer())
+///     .model("second", model2.checker().explorer().read_only())
+///     .serve("localhost:3000");
+/// ```
+#[derive(Default)]
+#[must_use = "This code constructs a builder, not an Explorer. Consider calling serve()."]
+pub struct ExplorerGroup {
+    #[allow(clippy::type_complexity)]
+    explorers: Vec<(String, Box<dyn FnOnce() -> Arc<dyn Service>>)>,
+}
+
+/// Where a request to an [`ExplorerGroup`] is directed.
+#[derive(Debug, PartialEq)]
+enum Route<'a> {
+    Models,
+    Redirect(String),
+    /// The index of an Explorer and the URL relative to its prefix.
+    Explorer(usize, &'a str),
+    NotFound,
+}
+
+impl ExplorerGroup {
+    /// Instantiates an empty group.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a named model, whose Explorer is configured via the specified builder. Panics if the
+    /// name is empty, contains `/`, starts with `.`, or is already taken.
+    pub fn model<M>(mut self, name: &str, explorer: ExplorerBuilder<M>) -> Self
+    where
+        M: 'static + Model + Send + Sync,
+        M::Action: Debug + Send + Sync,
+        M::State: Debug + Hash + Send + Sync,
+    {
+        assert!(
+            !name.is_empty() && !name.contains('/') && !name.starts_with('.'),
+            "Invalid model name {:?}",
+            name
+        );
+        assert!(
+            self.explorers.iter().all(|(existing, _)| existing != name),
+            "Duplicate model name {:?}",
+            name
+        );
+        self.explorers.push((
+            name.to_string(),
+            Box::new(move || Arc::new(Arc::new(explorer.spawn())) as Arc<dyn Service>),
+        ));
+        self
+    }
+
+    /// Starts checking every model and serves their Explorers. Panics if no model was added.
+    pub fn serve(self, addresses: impl ToSocketAddrs) {
+        assert!(!self.explorers.is_empty(), "No models to serve");
+        let (names, services): (Vec<_>, Vec<_>) = self
+            .explorers
+            .into_iter()
+            .map(|(name, spawn)| {
+                log::info!("Serving the Explorer for {} under /{}/.", name, name);
+                (name, spawn())
+            })
+            .unzip();
+        serve_requests(addresses, move |rq| {
+            let url = rq.url().to_string();
+            match route(&names, &url) {
+                Route::Models => respond_with(rq, ExplorerResponse::json(&names)),
+                Route::Redirect(location) => {
+                    let header = Header::from_bytes(&b"Location"[..], location.as_bytes()).unwrap();
+                    let response = Response::empty(StatusCode(302)).with_header(header);
+                    rq.respond(response).unwrap();
+                }
+                Route::Explorer(index, url) => respond(rq, &services[index], Some(url)),
+                Route::NotFound => respond_with(rq, ExplorerResponse::not_found(String::new())),
+            }
+        });
+    }
+
+    #[cfg(test)]
+    fn names(&self) -> Vec<&str> {
+        self.explorers
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
+
+/// Directs a request to the Explorer whose name prefixes the URL. Requests for the root are
+/// redirected to the first Explorer, and requests for a name without a trailing `/` are
+/// redirected so that the UI's relative URLs resolve under the prefix.
+fn route<'a>(names: &[String], url: &'a str) -> Route<'a> {
+    if url == "/.models" {
+        return Route::Models;
+    }
+    let rest = url.strip_prefix('/').unwrap_or(url);
+    if rest.is_empty() {
+        return Route::Redirect(format!("/{}/", names[0]));
+    }
+    let (name, relative) = match rest.find(['/', '?']) {
+        Some(i) if rest[i..].starts_with('/') => (&rest[..i], &rest[i..]),
+        Some(i) => (&rest[..i], ""),
+        None => (rest, ""),
+    };
+    match names.iter().position(|n| n == name) {
+        None => Route::NotFound,
+        Some(_) if relative.is_empty() => Route::Redirect(format!("/{}/", name)),
+        Some(index) => Route::Explorer(index, relative),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::binary_clock::*;
+
+    #[test]
+    fn routes_by_model_name() {
+        let names = vec!["2pc".to_string(), "paxos".to_string()];
+        assert_eq!(route(&names, "/.models"), Route::Models);
+        assert_eq!(route(&names, "/"), Route::Redirect("/2pc/".to_string()));
+        assert_eq!(
+            route(&names, "/paxos"),
+            Route::Redirect("/paxos/".to_string())
+        );
+        assert_eq!(route(&names, "/paxos/"), Route::Explorer(1, "/"));
+        assert_eq!(
+            route(&names, "/2pc/.states/123?successor_limit=0"),
+            Route::Explorer(0, "/.states/123?successor_limit=0")
+        );
+        assert_eq!(route(&names, "/raft/.status"), Route::NotFound);
+        assert_eq!(route(&names, "/.status"), Route::NotFound);
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate model name \"clock\"")]
+    fn rejects_duplicate_names() {
+        let group = ExplorerGroup::new().model("clock", BinaryClock.checker().explorer());
+        assert_eq!(group.names(), vec!["clock"]);
+        let _ = group.model("clock", BinaryClock.checker().explorer());
+    }
+}
@@ -0,0 +1,356 @@
+//! Private module for selective re-export.
+
+use crate::checker::bfs::{
+    check_block, init_jobs, reconstruct_path, spill_newest, terminal_states, unspill, Job,
+};
+use crate::checker::spill::{SpillOptions, SpillQueue};
+use crate::checker::{
+    with_violating_step, Checker, MetricSummary, Metrics, Observation, Path, UniqueStates,
+    VisitedMap,
+};
+use crate::{CheckerBuilder, CheckerVisitor, Fingerprint, FingerprintHasher, Model, Property};
+use dashmap::{DashMap, DashSet};
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+
+/// A breadth-first search [`Checker`] that runs on the calling thread, a bounded number of states
+/// per [`IncrementalChecker::step`]. Instantiate via [`CheckerBuilder::incremental_bfs`].
+///
+/// Unlike the other checkers, this one never spawns threads, so it can run where threads are
+/// unavailable (such as WebAssembly in a browser) and can interleave checking with other work
+/// (such as rendering progress). [`Checker::join`] steps until checking is done.
+pub struct IncrementalChecker<M: Model> {
+    model: M,
+    fingerprint_hasher: FingerprintHasher,
+    target_state_count: Option<NonZeroUsize>,
+    target_max_depth: Option<NonZeroUsize>,
+    visitor: Option<Box<dyn CheckerVisitor<M> + Send + Sync>>,
+    observation: Option<Observation<M::State>>,
+    metrics: Metrics<M>,
+    properties: Vec<Property<M>>,
+
+    pending: Mutex<VecDeque<Job<M::State>>>,
+    spill: Option<(SpillOptions<M::State>, Mutex<SpillQueue>)>,
+    state_count: AtomicUsize,
+    max_depth: AtomicUsize,
+    generated: VisitedMap<Option<Fingerprint>>,
+    node_states: DashMap<Fingerprint, Fingerprint>,
+    deadline_bounds: DashMap<Fingerprint, Vec<usize>>,
+    unique_states: UniqueStates,
+    discoveries: DashMap<Cow<'static, str>, Fingerprint>,
+    terminals: Option<DashSet<Fingerprint>>,
+}
+
+impl<M> IncrementalChecker<M>
+where
+    M: Model,
+    M::State: Hash,
+{
+    pub(crate) fn new(options: CheckerBuilder<M>) -> Self {
+        assert!(
+            options.symmetry.is_none(),
+            "The incremental checker does not support symmetry reduction. Use spawn_dfs instead."
+        );
+        assert!(
+            options.probabilities.is_empty(),
+            "The incremental checker does not estimate probabilities. Use spawn_simulation instead."
+        );
+        let model = options.model;
+        let properties = model.properties();
+        let generated = VisitedMap::new(1);
+        let unique_states = UniqueStates::new(&properties, 1);
+        let pending = init_jobs(
+            &model,
+            options.fingerprint_hasher,
+            &generated,
+            &unique_states,
+        );
+        IncrementalChecker {
+            fingerprint_hasher: options.fingerprint_hasher,
+            target_state_count: options.target_state_count,
+            target_max_depth: options.target_max_depth,
+            visitor: options.visitor,
+            observation: options.observation,
+            metrics: Metrics::new(options.metrics),
+            properties,
+            state_count: AtomicUsize::new(pending.len()),
+            pending: Mutex::new(pending),
+            spill: options.spill.map(|options| {
+                let spill = SpillQueue::new(options.dir.clone());
+                (options, Mutex::new(spill))
+            }),
+            max_depth: AtomicUsize::new(0),
+            generated,
+            node_states: DashMap::default(),
+            deadline_bounds: DashMap::default(),
+            unique_states,
+            discoveries: DashMap::default(),
+            terminals: options.track_terminal_states.then(DashSet::default),
+            model,
+        }
+    }
+
+    /// Checks up to `max_count` pending states, then returns whether checking
+    /// [is done](Checker::is_done).
+    pub fn step(&self, max_count: usize) -> bool {
+        if !self.is_done() {
+            let mut pending = self.pending.lock().unwrap();
+            if let Some((options, spill)) = &self.spill {
+                let mut spill = spill.lock().unwrap();
+                if pending.is_empty() && !spill.is_empty() {
+                    unspill(options, &mut spill, &mut pending);
+                }
+            }
+            check_block(
+                &self.model,
+                &self.properties,
+                self.fingerprint_hasher,
+                &self.state_count,
+                &self.generated,
+                &self.node_states,
+                &self.deadline_bounds,
+                &self.unique_states,
+                &mut pending,
+                &self.discoveries,
+                self.terminals.as_ref(),
+                &self.visitor,
+                &self.observation,
+                &self.metrics,
+                max_count,
+                self.target_max_depth,
+                &self.max_depth,
+            );
+            if let Some((options, spill)) = &self.spill {
+                if pending.len() > options.max_pending {
+                    spill_newest(options, &mut spill.lock().unwrap(), &mut pending);
+                }
+            }
+        }
+        self.is_done()
+    }
+}
+
+impl<M> Checker<M> for IncrementalChecker<M>
+where
+    M: Model,
+    M::State: Hash,
+{
+    fn model(&self) -> &M {
+        &self.model
+    }
+
+    fn run_to_completion(&self) {
+        while !self.step(1500) {}
+    }
+
+    fn state_count(&self) -> usize {
+        self.state_count.load(Ordering::Relaxed)
+    }
+
+    fn unique_state_count(&self) -> usize {
+        self.unique_states.len(&self.generated)
+    }
+
+    fn max_depth(&self) -> usize {
+        self.max_depth.load(Ordering::Relaxed)
+    }
+
+    fn discoveries(&self) -> HashMap<Cow<'static, str>, Path<M::State, M::Action>> {
+        self.discoveries
+            .iter()
+            .map(|mapref| {
+                (
+                    mapref.key().clone(),
+                    with_violating_step(
+                        self.model(),
+                        mapref.key(),
+                        reconstruct_path(
+                            self.model(),
+                            self.fingerprint_hasher,
+                            &self.generated,
+                            &self.node_states,
+                            *mapref.value(),
+                        ),
+                    ),
+                )
+            })
+            .collect()
+    }
+
+    fn terminal_states(&self) -> Option<Vec<M::State>> {
+        let terminals = self.terminals.as_ref()?;
+        Some(terminal_states(
+            self.model(),
+            self.fingerprint_hasher,
+            &self.generated,
+            &self.node_states,
+            terminals,
+        ))
+    }
+
+    fn metrics(&self) -> Vec<MetricSummary> {
+        self.metrics.summaries()
+    }
+
+    fn join(self) -> Self {
+        self.run_to_completion();
+        self
+    }
+
+    fn handles(&mut self) -> Vec<JoinHandle<()>> {
+        Vec::new()
+    }
+
+    fn is_done(&self) -> bool {
+        (self.pending.lock().unwrap().is_empty()
+            && self
+                .spill
+                .as_ref()
+                .is_none_or(|(_, spill)| spill.lock().unwrap().is_empty()))
+            || self.discoveries.len() == self.properties.len()
+            || self
+                .target_state_count
+                .is_some_and(|target| target.get() <= self.state_count())
+    }
+
+    fn is_truncated(&self) -> bool {
+        self.target_state_count
+            .is_some_and(|target| target.get() <= self.state_count())
+            || self
+                .target_max_depth
+                .is_some_and(|target| target.get() <= self.max_depth())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::linear_equation_solver::*;
+    use crate::Model;
+
+    #[test]
+    fn steps_until_done() {
+        let checker = LinearEquation { a: 2, b: 10, c: 14 }
+            .checker()
+            .incremental_bfs();
+        assert!(!checker.step(1));
+        assert_eq!(checker.unique_state_count(), 3);
+        assert!(!checker.step(1));
+        assert_eq!(checker.unique_state_count(), 5);
+        while !checker.step(10) {}
+        checker.assert_properties();
+        assert_eq!(
+            checker.discovery("solvable").unwrap().into_actions(),
+            vec![Guess::IncreaseX, Guess::IncreaseX, Guess::IncreaseY]
+        );
+    }
+
+    #[test]
+    fn matches_bfs_checker() {
+        let incremental = LinearEquation { a: 2, b: 4, c: 7 }
+            .checker()
+            .incremental_bfs()
+            .join();
+        let bfs = LinearEquation { a: 2, b: 4, c: 7 }
+            .checker()
+            .spawn_bfs()
+            .join();
+        assert!(incremental.is_done());
+        assert_eq!(incremental.unique_state_count(), bfs.unique_state_count());
+        assert_eq!(incremental.state_count(), bfs.state_count());
+        assert_eq!(incremental.max_depth(), bfs.max_depth());
+        assert!(incremental.discovery("solvable").is_none());
+    }
+
+    #[test]
+    fn honors_target_state_count() {
+        let checker = LinearEquation { a: 2, b: 4, c: 7 }
+            .checker()
+            .target_state_count(100)
+            .incremental_bfs()
+            .join();
+        assert!(checker.is_done());
+        assert!(checker.state_count() >= 100);
+        assert!(checker.unique_state_count() < 256 * 256);
+    }
+
+    #[test]
+    fn can_report() {
+        let mut written = Vec::new();
+        let checker = LinearEquation { a: 2, b: 10, c: 14 }
+            .checker()
+            .incremental_bfs()
+            .join_and_report(&mut crate::report::WriteReporter::new(&mut written));
+        assert!(checker.is_done());
+        let written = String::from_utf8(written).unwrap();
+        assert!(written.contains("Done."), "{}", written);
+        assert!(
+            written.contains("Discovered \"solvable\" example"),
+            "{}",
+            written
+        );
+    }
+
+    #[test]
+    fn can_spill_pending_states() {
+        let dir =
+            std::env::temp_dir().join(format!("stateright-incremental-{}", std::process::id()));
+        let checker = LinearEquation { a: 2, b: 4, c: 7 }
+            .checker()
+            .spill_to_disk(&dir, 16)
+            .incremental_bfs()
+            .join();
+        checker.assert_no_discovery("solvable");
+        assert_eq!(checker.unique_state_count(), 256 * 256);
+
+        let checker = LinearEquation { a: 2, b: 10, c: 14 }
+            .checker()
+            .spill_to_disk(&dir, 16)
+            .incremental_bfs()
+            .join();
+        checker.assert_properties();
+    }
+
+    #[test]
+    #[should_panic(expected = "does not support symmetry reduction")]
+    fn rejects_symmetry_reduction() {
+        let _ = LinearEquation { a: 2, b: 10, c: 14 }
+            .checker()
+            .symmetry_fn(|state| *state)
+            .incremental_bfs();
+    }
+
+    #[test]
+    #[should_panic(expected = "does not estimate probabilities")]
+    fn rejects_probabilities() {
+        let _ = LinearEquation { a: 2, b: 10, c: 14 }
+            .checker()
+            .probability("solved", 0.5, 10, |_, _| true)
+            .incremental_bfs();
+    }
+
+    #[test]
+    fn can_report_json() {
+        let mut written = Vec::new();
+        let checker = LinearEquation { a: 2, b: 4, c: 7 }
+            .checker()
+            .incremental_bfs()
+            .join_and_report_json(&mut written)
+            .unwrap();
+        assert!(checker.is_done());
+        let report: serde_json::Value = serde_json::from_slice(&written).unwrap();
+        assert_eq!(report["done"], true);
+        assert_eq!(
+            report["stats"]["unique_states"],
+            checker.unique_state_count()
+        );
+        assert_eq!(report["properties"][0]["name"], "solvable");
+        // Unsolvable, which is only known once checking completes.
+        assert_eq!(report["properties"][0]["result"], "fail");
+    }
+}
@@ -0,0 +1,141 @@
+//! Private module for selective re-export.
+
+use crate::Model;
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// A numeric measurement of a state, such as the number of messages in flight. See
+/// [`CheckerBuilder::metric`](crate::CheckerBuilder::metric).
+pub(crate) struct Metric<M: Model> {
+    pub name: Cow<'static, str>,
+    #[allow(clippy::type_complexity)]
+    pub measure: Box<dyn Fn(&M, &M::State) -> usize + Send + Sync>,
+}
+
+/// Accumulates metrics over the states visited by a checker. Safe for concurrent use.
+pub(crate) struct Metrics<M: Model> {
+    metrics: Vec<Metric<M>>,
+    stats: Vec<Stats>,
+}
+
+struct Stats {
+    count: AtomicUsize,
+    min: AtomicUsize,
+    max: AtomicUsize,
+    sum: AtomicU64,
+}
+
+/// A summary of a metric across the states visited by a checker. See
+/// [`Checker::metrics`](crate::Checker::metrics).
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricSummary {
+    /// The name of the metric.
+    pub name: Cow<'static, str>,
+    /// The number of states measured.
+    pub count: usize,
+    /// The minimum measurement, or `None` if no states were measured.
+    pub min: Option<usize>,
+    /// The maximum measurement, or `None` if no states were measured.
+    pub max: Option<usize>,
+    /// The mean measurement, or `None` if no states were measured.
+    pub mean: Option<f64>,
+}
+
+impl<M: Model> Metrics<M> {
+    pub fn new(metrics: Vec<Metric<M>>) -> Self {
+        let stats = metrics
+            .iter()
+            .map(|_| Stats {
+                count: AtomicUsize::new(0),
+                min: AtomicUsize::new(usize::MAX),
+                max: AtomicUsize::new(0),
+                sum: AtomicU64::new(0),
+            })
+            .collect();
+        Self { metrics, stats }
+    }
+
+    /// Measures a visited state.
+    pub fn record(&self, model: &M, state: &M::State) {
+        for (metric, stats) in self.metrics.iter().zip(&self.stats) {
+            let value = (metric.measure)(model, state);
+            stats.count.fetch_add(1, Ordering::Relaxed);
+            stats.min.fetch_min(value, Ordering::Relaxed);
+            stats.max.fetch_max(value, Ordering::Relaxed);
+            stats.sum.fetch_add(value as u64, Ordering::Relaxed);
+        }
+    }
+
+    pub fn summaries(&self) -> Vec<MetricSummary> {
+        self.metrics
+            .iter()
+            .zip(&self.stats)
+            .map(|(metric, stats)| {
+                let count = stats.count.load(Ordering::Relaxed);
+                let is_measured = count > 0;
+                MetricSummary {
+                    name: metric.name.clone(),
+                    count,
+                    min: Some(stats.min.load(Ordering::Relaxed)).filter(|_| is_measured),
+                    max: Some(stats.max.load(Ordering::Relaxed)).filter(|_| is_measured),
+                    mean: Some(stats.sum.load(Ordering::Relaxed) as f64 / count as f64)
+                        .filter(|_| is_measured),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Display for MetricSummary {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match (self.min, self.max, self.mean) {
+            (Some(min), Some(max), Some(mean)) => write!(
+                f,
+                "\"{}\" min={}, max={}, avg={:.2} over {} states",
+                self.name, min, max, mean, self.count
+            ),
+            _ => write!(f, "\"{}\" not measured", self.name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::linear_equation_solver::*;
+    use crate::{Checker, MetricSummary, Model};
+
+    #[test]
+    fn summarizes_metrics_over_visited_states() {
+        let builder = || {
+            LinearEquation { a: 2, b: 10, c: 14 }
+                .checker()
+                .metric("x + y", |_, (x, y)| (x + y) as usize)
+                .metric(String::from("y"), |_, (_, y)| *y as usize)
+                .target_max_depth(3)
+        };
+        // Visits (0, 0), (1, 0), and (0, 1).
+        let expected = vec![
+            MetricSummary {
+                name: "x + y".into(),
+                count: 3,
+                min: Some(0),
+                max: Some(1),
+                mean: Some(2.0 / 3.0),
+            },
+            MetricSummary {
+                name: "y".into(),
+                count: 3,
+                min: Some(0),
+                max: Some(1),
+                mean: Some(1.0 / 3.0),
+            },
+        ];
+        assert_eq!(builder().spawn_bfs().join().metrics(), expected);
+        assert_eq!(builder().spawn_dfs().join().metrics(), expected);
+        assert_eq!(
+            expected[0].to_string(),
+            "\"x + y\" min=0, max=1, avg=0.67 over 3 states"
+        );
+    }
+}
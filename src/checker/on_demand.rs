@@ -1,15 +1,18 @@
 //! Private module for selective re-export.
 
-use crate::checker::{Checker, EventuallyBits, Expectation, Path};
+use crate::checker::bfs::{ancestors, reconstruct_path, state_fingerprint};
+use crate::checker::{
+    with_violating_step, Checker, Deadlines, EventuallyBits, Expectation, MetricSummary, Metrics,
+    Observation, Path, Transitions, UniqueStates, VisitedMap,
+};
 use crate::job_market::JobBroker;
 use crate::{
-    fingerprint, CheckerBuilder, CheckerVisitor, ControlFlow, Fingerprint, Model, Property,
+    CheckerBuilder, CheckerVisitor, ControlFlow, Fingerprint, FingerprintHasher, Model, Property,
 };
-use dashmap::mapref::entry::Entry;
-use dashmap::DashMap;
-use nohash_hasher::NoHashHasher;
+use dashmap::{DashMap, DashSet};
+use std::borrow::Cow;
 use std::collections::{HashMap, VecDeque};
-use std::hash::{BuildHasherDefault, Hash};
+use std::hash::Hash;
 use std::num::NonZeroUsize;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
@@ -21,18 +24,30 @@ use std::thread::JoinHandle;
 pub(crate) struct OnDemandChecker<M: Model> {
     // Immutable state.
     model: Arc<M>,
+    fingerprint_hasher: FingerprintHasher,
+    target_state_count: Option<NonZeroUsize>,
     handles: Vec<std::thread::JoinHandle<()>>,
 
     // Mutable state.
     job_broker: JobBroker<Job<M::State>>,
     state_count: Arc<AtomicUsize>,
     max_depth: Arc<AtomicUsize>,
-    generated:
-        Arc<DashMap<Fingerprint, Option<Fingerprint>, BuildHasherDefault<NoHashHasher<u64>>>>,
-    discoveries: Arc<DashMap<&'static str, Fingerprint>>,
+    generated: Arc<VisitedMap<Option<Fingerprint>>>,
+    node_states: Arc<DashMap<Fingerprint, Fingerprint>>,
+    unique_states: Arc<UniqueStates>,
+    discoveries: Arc<DashMap<Cow<'static, str>, Fingerprint>>,
+    terminals: Option<Arc<DashSet<Fingerprint>>>,
+    metrics: Arc<Metrics<M>>,
     control_flow: std::sync::mpsc::SyncSender<ControlFlow>,
 }
-type Job<State> = (State, Fingerprint, EventuallyBits, NonZeroUsize);
+type Job<State> = (
+    State,
+    Fingerprint,
+    EventuallyBits,
+    Deadlines,
+    NonZeroUsize,
+    Option<Fingerprint>,
+);
 
 impl<M> OnDemandChecker<M>
 where
@@ -41,10 +56,16 @@ where
 {
     pub(crate) fn spawn(options: CheckerBuilder<M>) -> Self {
         let model = Arc::new(options.model);
+        let fingerprint_hasher = options.fingerprint_hasher;
         let target_state_count = options.target_state_count;
         let thread_count = options.thread_count;
         let visitor = Arc::new(options.visitor);
-        let property_count = model.properties().len();
+        let observation = options.observation;
+        let metrics = Arc::new(Metrics::new(options.metrics));
+        // The same properties are evaluated throughout, so that memoized conditions keep their
+        // caches. See `Property::memoize_by`.
+        let properties = Arc::new(model.properties());
+        let property_count = properties.len();
 
         let mut controlflow_channels = Vec::new();
         let (controlflow_to_check_sender, controlflow_to_check_receiver) =
@@ -57,10 +78,13 @@ where
             .collect();
         let state_count = Arc::new(AtomicUsize::new(init_states.len()));
         let max_depth = Arc::new(AtomicUsize::new(0));
+        let unique_states = Arc::new(UniqueStates::new(&properties, thread_count));
         let generated = Arc::new({
-            let generated = DashMap::default();
+            let generated = VisitedMap::new(thread_count);
             for s in &init_states {
-                generated.insert(fingerprint(s), None);
+                let fp = fingerprint_hasher.fingerprint(s);
+                generated.insert(fp, None);
+                unique_states.insert(fp);
             }
             generated
         });
@@ -80,23 +104,42 @@ where
         let pending: VecDeque<_> = init_states
             .into_iter()
             .map(|s| {
-                let fp = fingerprint(&s);
-                (s, fp, ebits.clone(), NonZeroUsize::new(1).unwrap())
+                let fp = fingerprint_hasher.fingerprint(&s);
+                (
+                    s,
+                    fp,
+                    ebits.clone(),
+                    Deadlines::default(),
+                    NonZeroUsize::new(1).unwrap(),
+                    None,
+                )
             })
             .collect();
+        let node_states = Arc::new(DashMap::default());
+        let deadline_bounds = Arc::new(DashMap::default());
         let discoveries = Arc::new(DashMap::default());
+        let terminals = options
+            .track_terminal_states
+            .then(|| Arc::new(DashSet::default()));
         let mut handles = Vec::new();
 
         let mut job_broker = JobBroker::new(thread_count);
         job_broker.push(pending);
         for t in 0..thread_count {
             let model = Arc::clone(&model);
+            let properties = Arc::clone(&properties);
             let visitor = Arc::clone(&visitor);
+            let observation = observation.clone();
+            let metrics = Arc::clone(&metrics);
             let mut job_broker = job_broker.clone();
             let state_count = Arc::clone(&state_count);
             let max_depth = Arc::clone(&max_depth);
             let generated = Arc::clone(&generated);
+            let node_states = Arc::clone(&node_states);
+            let unique_states = Arc::clone(&unique_states);
+            let deadline_bounds = Arc::clone(&deadline_bounds);
             let discoveries = Arc::clone(&discoveries);
+            let terminals = terminals.clone();
 
             let (controlflow_sender, controlflow_receiver) = std::sync::mpsc::channel();
             controlflow_channels.push(controlflow_sender);
@@ -126,7 +169,7 @@ where
                                 };
                                 log::debug!(
                                     "got new pending states: {:?}",
-                                    pending.iter().map(|(_, f, _, _)| f).collect::<Vec<_>>()
+                                    pending.iter().map(|(_, f, _, _, _, _)| f).collect::<Vec<_>>()
                                 );
                             }
 
@@ -140,14 +183,16 @@ where
                                                 log::debug!(
                                             "received fingerprint to check: {}, pending is {:?}",
                                             fingerprint,
-                                            pending.iter().map(|(_, f, _, _)| f).collect::<Vec<_>>()
+                                            pending.iter().map(|(_, f, _, _, _, _)| f).collect::<Vec<_>>()
                                         );
                                                 if pending.is_empty() {
                                                     break;
                                                 }
-                                                if let Some(index) = pending
-                                                    .iter()
-                                                    .position(|(_, f, _, _)| *f == fingerprint)
+                                                if let Some(index) =
+                                                    pending.iter().position(|(_, f, _, _, _, _)| {
+                                                        state_fingerprint(&node_states, *f)
+                                                            == fingerprint
+                                                    })
                                                 {
                                                     targetted_pending
                                                         .push_back(pending.remove(index).unwrap());
@@ -176,11 +221,19 @@ where
                             // Step 1: Do work.
                             Self::check_block(
                                 &model,
+                                &properties,
+                                fingerprint_hasher,
                                 &state_count,
                                 &generated,
+                                &node_states,
+                                &deadline_bounds,
+                                &unique_states,
                                 &mut targetted_pending,
                                 &discoveries,
+                                terminals.as_deref(),
                                 &visitor,
+                                &observation,
+                                &metrics,
                                 1500,
                                 &max_depth,
                             );
@@ -225,12 +278,18 @@ where
 
         OnDemandChecker {
             model,
+            fingerprint_hasher,
+            target_state_count,
             handles,
             job_broker,
             state_count,
             max_depth,
             generated,
+            node_states,
+            unique_states,
             discoveries,
+            terminals,
+            metrics,
             control_flow: controlflow_to_check_sender,
         }
     }
@@ -238,20 +297,22 @@ where
     #[allow(clippy::too_many_arguments)]
     fn check_block(
         model: &M,
+        properties: &[Property<M>],
+        fingerprint_hasher: FingerprintHasher,
         state_count: &AtomicUsize,
-        generated: &DashMap<
-            Fingerprint,
-            Option<Fingerprint>,
-            BuildHasherDefault<NoHashHasher<u64>>,
-        >,
+        generated: &VisitedMap<Option<Fingerprint>>,
+        node_states: &DashMap<Fingerprint, Fingerprint>,
+        deadline_bounds: &DashMap<Fingerprint, Vec<usize>>,
+        unique_states: &UniqueStates,
         pending: &mut VecDeque<Job<M::State>>,
-        discoveries: &DashMap<&'static str, Fingerprint>,
+        discoveries: &DashMap<Cow<'static, str>, Fingerprint>,
+        terminals: Option<&DashSet<Fingerprint>>,
         visitor: &Option<Box<dyn CheckerVisitor<M> + Send + Sync>>,
+        observation: &Option<Observation<M::State>>,
+        metrics: &Metrics<M>,
         max_count: usize,
         global_max_depth: &AtomicUsize,
     ) {
-        let properties = model.properties();
-
         let mut current_max_depth = global_max_depth.load(Ordering::Relaxed);
         let mut actions = Vec::new();
         let mut local_pending = pending
@@ -259,29 +320,40 @@ where
             .collect::<Vec<_>>();
         loop {
             // Done if none pending.
-            let (state, state_fp, mut ebits, max_depth) = match local_pending.pop() {
-                None => return,
-                Some(pair) => pair,
-            };
+            let (state, state_fp, mut ebits, mut deadlines, max_depth, prev_observation) =
+                match local_pending.pop() {
+                    None => return,
+                    Some(pair) => pair,
+                };
 
             if max_depth.get() > current_max_depth {
-                let _ = global_max_depth.compare_exchange(
-                    current_max_depth,
-                    max_depth.get(),
-                    Ordering::Relaxed,
-                    Ordering::Relaxed,
-                );
+                global_max_depth.fetch_max(max_depth.get(), Ordering::Relaxed);
                 current_max_depth = max_depth.get();
             }
 
             if let Some(visitor) = visitor {
-                visitor.visit(model, reconstruct_path(model, generated, state_fp));
+                visitor.visit(
+                    model,
+                    reconstruct_path(model, fingerprint_hasher, generated, node_states, state_fp),
+                );
             }
+            metrics.record(model, &state);
+
+            // Properties are only evaluated for observable transitions.
+            let state_observation = observation.as_ref().map(|observe| observe(&state));
+            let is_stuttering =
+                state_observation.is_some() && state_observation == prev_observation;
 
             // Done if discoveries found for all properties.
             let mut is_awaiting_discoveries = false;
             for (i, property) in properties.iter().enumerate() {
-                if discoveries.contains_key(property.name) {
+                if discoveries.contains_key(&property.name) {
+                    continue;
+                }
+                if is_stuttering {
+                    // The observation is unchanged, so the property evaluates as it did for the
+                    // predecessor.
+                    is_awaiting_discoveries = true;
                     continue;
                 }
                 match property {
@@ -290,9 +362,12 @@ where
                         condition: always,
                         ..
                     } => {
-                        if !always(model, &state) {
+                        if property.transition.is_some() {
+                            // Evaluated for each step taken from the state.
+                            is_awaiting_discoveries = true;
+                        } else if !always(model, &state) {
                             // Races other threads, but that's fine.
-                            discoveries.insert(property.name, state_fp);
+                            discoveries.insert(property.name.clone(), state_fp);
                         } else {
                             is_awaiting_discoveries = true;
                         }
@@ -304,7 +379,7 @@ where
                     } => {
                         if sometimes(model, &state) {
                             // Races other threads, but that's fine.
-                            discoveries.insert(property.name, state_fp);
+                            discoveries.insert(property.name.clone(), state_fp);
                         } else {
                             is_awaiting_discoveries = true;
                         }
@@ -324,6 +399,32 @@ where
                             ebits.remove(i);
                         }
                     }
+                    Property {
+                        expectation: Expectation::Within,
+                        ..
+                    } => {
+                        if !deadlines.advance(i, property, model, &state) {
+                            // Races other threads, but that's fine.
+                            discoveries.insert(property.name.clone(), state_fp);
+                        } else {
+                            is_awaiting_discoveries = true;
+                        }
+                    }
+                    Property {
+                        expectation: Expectation::LeadsTo,
+                        ..
+                    } => {
+                        // Only identified at terminal states and upon closing cycles.
+                        is_awaiting_discoveries = true;
+                        deadlines.respond(i, property, model, &state, max_depth.get());
+                    }
+                    Property {
+                        expectation: Expectation::Terminal,
+                        ..
+                    } => {
+                        // Only evaluated once the state is known to be terminal.
+                        is_awaiting_discoveries = true;
+                    }
                 }
             }
             if !is_awaiting_discoveries {
@@ -332,10 +433,23 @@ where
 
             // Otherwise enqueue newly generated states (with related metadata).
             let mut is_terminal = true;
+            let mut transitions = Transitions::new(model, &state, properties, |p| {
+                !is_stuttering && !discoveries.contains_key(&p.name)
+            });
             model.actions(&state, &mut actions);
-            let next_states = actions.drain(..).flat_map(|a| model.next_state(&state, a));
-            for next_state in next_states {
-                let next_fp = fingerprint(&next_state);
+            for action in actions.drain(..) {
+                let transition_action = transitions.next_action();
+                let next_state = match model.next_state(&state, action) {
+                    None => continue,
+                    Some(next_state) => next_state,
+                };
+                for property in transitions.violated(model, &state, &transition_action, &next_state)
+                {
+                    // Races other threads, but that's fine.
+                    discoveries.insert(property.name.clone(), state_fp);
+                }
+
+                let next_fp = fingerprint_hasher.fingerprint(&next_state);
                 log::debug!(
                     "checker generated state transition: {} -> {}",
                     state_fp,
@@ -355,9 +469,22 @@ where
                 // property held on the path leading to the first visit as meaning
                 // that it holds in the path leading to the second visit -- another
                 // possible false-negative.
-                if let Entry::Vacant(next_entry) = generated.entry(next_fp) {
-                    next_entry.insert(Some(state_fp));
-                } else {
+                if !deadlines.tighten(fingerprint_hasher, next_fp, deadline_bounds) {
+                    let path = ancestors(generated, node_states, state_fp);
+                    for i in deadlines.cycle(max_depth.get(), next_fp, path) {
+                        // Races other threads, but that's fine.
+                        discoveries
+                            .entry(properties[i].name.clone())
+                            .or_insert(state_fp);
+                    }
+                    is_terminal = false;
+                    continue;
+                }
+                let next_node = deadlines.node_fingerprint(fingerprint_hasher, next_fp);
+                if next_node != next_fp {
+                    node_states.insert(next_node, next_fp);
+                }
+                if !generated.insert(next_node, Some(state_fp)) {
                     // FIXME: arriving at an already-known state may be a loop (in which case it
                     // could, in a fancier implementation, be considered a terminal state for
                     // purposes of eventually-property checking) but it might also be a join in
@@ -365,25 +492,40 @@ where
                     // some cost), but for now we just _don't_ treat them as terminal, and tell
                     // users they need to explicitly ensure model path-acyclicality when they're
                     // using eventually properties (using a boundary or empty actions or
-                    // whatever).
+                    // whatever). Only `leads_to` properties look for a cycle along the path.
+                    let path = ancestors(generated, node_states, state_fp);
+                    for i in deadlines.cycle(max_depth.get(), next_fp, path) {
+                        // Races other threads, but that's fine.
+                        discoveries
+                            .entry(properties[i].name.clone())
+                            .or_insert(state_fp);
+                    }
                     is_terminal = false;
                     continue;
                 }
+                unique_states.insert(next_fp);
 
                 // Otherwise further checking is applicable.
                 is_terminal = false;
                 pending.push_front((
                     next_state,
-                    next_fp,
+                    next_node,
                     ebits.clone(),
+                    deadlines.clone(),
                     NonZeroUsize::new(max_depth.get() + 1).unwrap(),
+                    state_observation,
                 ));
             }
             if is_terminal {
+                if let Some(terminals) = terminals {
+                    terminals.insert(state_fp);
+                }
                 for (i, property) in properties.iter().enumerate() {
-                    if ebits.contains(i) {
+                    let is_violated_terminal = property.expectation == Expectation::Terminal
+                        && !(property.condition)(model, &state);
+                    if ebits.contains(i) || deadlines.is_pending(i) || is_violated_terminal {
                         // Races other threads, but that's fine.
-                        discoveries.insert(property.name, state_fp);
+                        discoveries.insert(property.name.clone(), state_fp);
                     }
                 }
             }
@@ -416,25 +558,50 @@ where
     }
 
     fn unique_state_count(&self) -> usize {
-        self.generated.len()
+        self.unique_states.len(&self.generated)
     }
 
     fn max_depth(&self) -> usize {
         self.max_depth.load(Ordering::Relaxed)
     }
 
-    fn discoveries(&self) -> HashMap<&'static str, Path<M::State, M::Action>> {
+    fn discoveries(&self) -> HashMap<Cow<'static, str>, Path<M::State, M::Action>> {
         self.discoveries
             .iter()
             .map(|mapref| {
                 (
-                    <&'static str>::clone(mapref.key()),
-                    reconstruct_path(self.model(), &self.generated, *mapref.value()),
+                    mapref.key().clone(),
+                    with_violating_step(
+                        self.model(),
+                        mapref.key(),
+                        reconstruct_path(
+                            self.model(),
+                            self.fingerprint_hasher,
+                            &self.generated,
+                            &self.node_states,
+                            *mapref.value(),
+                        ),
+                    ),
                 )
             })
             .collect()
     }
 
+    fn terminal_states(&self) -> Option<Vec<M::State>> {
+        let terminals = self.terminals.as_ref()?;
+        Some(crate::checker::bfs::terminal_states(
+            self.model(),
+            self.fingerprint_hasher,
+            &self.generated,
+            &self.node_states,
+            terminals,
+        ))
+    }
+
+    fn metrics(&self) -> Vec<MetricSummary> {
+        self.metrics.summaries()
+    }
+
     fn handles(&mut self) -> Vec<JoinHandle<()>> {
         std::mem::take(&mut self.handles)
     }
@@ -442,37 +609,11 @@ where
     fn is_done(&self) -> bool {
         self.job_broker.is_closed() || self.discoveries.len() == self.model.properties().len()
     }
-}
 
-fn reconstruct_path<M>(
-    model: &M,
-    generated: &DashMap<Fingerprint, Option<Fingerprint>, BuildHasherDefault<NoHashHasher<u64>>>,
-    fp: Fingerprint,
-) -> Path<M::State, M::Action>
-where
-    M: Model,
-    M::State: Hash,
-{
-    // First build a stack of digests representing the path (with the init digest at top of
-    // stack). Then unwind the stack of digests into a vector of states. The TLC model checker
-    // uses a similar technique, which is documented in the paper "Model Checking TLA+
-    // Specifications" by Yu, Manolios, and Lamport.
-
-    let mut fingerprints = VecDeque::new();
-    let mut next_fp = fp;
-    while let Some(source) = generated.get(&next_fp) {
-        match *source {
-            Some(prev_fingerprint) => {
-                fingerprints.push_front(next_fp);
-                next_fp = prev_fingerprint;
-            }
-            None => {
-                fingerprints.push_front(next_fp);
-                break;
-            }
-        }
+    fn is_truncated(&self) -> bool {
+        self.target_state_count
+            .is_some_and(|target| target.get() <= self.state_count())
     }
-    Path::from_fingerprints(model, fingerprints)
 }
 
 #[cfg(test)]
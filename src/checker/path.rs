@@ -1,6 +1,7 @@
 //! Private module for selective re-export.
 
-use crate::{fingerprint, Fingerprint, Model};
+use super::tla::{debug_to_tla, tla_string, TlcState, TlcTrace};
+use crate::{fingerprint, Fingerprint, FingerprintHasher, Model};
 use std::collections::VecDeque;
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hash;
@@ -17,7 +18,22 @@ pub struct Path<State, Action>(Vec<(State, Option<Action>)>);
 
 impl<State, Action> Path<State, Action> {
     /// Constructs a path from a model and a sequence of fingerprints.
-    pub(crate) fn from_fingerprints<M>(model: &M, mut fingerprints: VecDeque<Fingerprint>) -> Self
+    #[cfg_attr(not(feature = "explorer"), allow(dead_code))]
+    pub(crate) fn from_fingerprints<M>(model: &M, fingerprints: VecDeque<Fingerprint>) -> Self
+    where
+        M: Model<State = State, Action = Action>,
+        M::State: Hash,
+    {
+        Self::from_fingerprints_with_hasher(model, fingerprints, FingerprintHasher::default())
+    }
+
+    /// Constructs a path from a model and a sequence of fingerprints that were computed with a
+    /// particular hasher.
+    pub(crate) fn from_fingerprints_with_hasher<M>(
+        model: &M,
+        mut fingerprints: VecDeque<Fingerprint>,
+        hasher: FingerprintHasher,
+    ) -> Self
     where
         M: Model<State = State, Action = Action>,
         M::State: Hash,
@@ -31,7 +47,7 @@ impl<State, Action> Path<State, Action> {
         let mut last_state = model
             .init_states()
             .into_iter()
-            .find(|s| fingerprint(&s) == init_print)
+            .find(|s| hasher.fingerprint(&s) == init_print)
             .unwrap_or_else(|| {
                 panic!(
                     r#"
@@ -49,7 +65,7 @@ Available init fingerprints (none of which match): {:?}"#,
                     model
                         .init_states()
                         .into_iter()
-                        .map(|s| fingerprint(&s))
+                        .map(|s| hasher.fingerprint(&s))
                         .collect::<Vec<_>>()
                 );
             });
@@ -59,7 +75,7 @@ Available init fingerprints (none of which match): {:?}"#,
                 .next_steps(&last_state)
                 .into_iter()
                 .find_map(|(a, s)| {
-                    if fingerprint(&s) == next_fp {
+                    if hasher.fingerprint(&s) == next_fp {
                         Some((a, s))
                     } else {
                         None
@@ -84,7 +100,7 @@ Available next fingerprints (none of which match): {:?}"#,
                         model
                             .next_states(&last_state)
                             .into_iter()
-                            .map(|s| fingerprint(&s))
+                            .map(|s| hasher.fingerprint(&s))
                             .collect::<Vec<_>>()
                     );
                 });
@@ -96,6 +112,94 @@ Available next fingerprints (none of which match): {:?}"#,
         Path(output)
     }
 
+    /// Replays a behavior found by TLC against the corresponding model, so that a
+    /// counterexample to a TLA+ specification can be inspected with this crate's tooling. For
+    /// example, the result can be opened in the Explorer at `#/steps/{encoded}`, where
+    /// `{encoded}` is the output of [`Path::encode`].
+    ///
+    /// `to_state` maps each [`TlcState`] to the model's state type, returning `None` if the state
+    /// cannot be represented. Consecutive TLC states that map to the same state are treated as a
+    /// single step, which accommodates specifications that are more fine-grained than the model.
+    /// Returns an error describing the first state that cannot be mapped or reached.
+    ///
+    /// [`TlcState`]: crate::TlcState
+    pub fn from_tlc_trace<M>(
+        model: &M,
+        trace: &TlcTrace,
+        to_state: impl Fn(&TlcState) -> Option<State>,
+    ) -> Result<Self, String>
+    where
+        M: Model<State = State, Action = Action>,
+        M::State: Hash,
+    {
+        let mut output: Vec<(State, Option<Action>)> = Vec::new();
+        let mut last: Option<(State, Fingerprint)> = None;
+        for (i, tlc_state) in trace.states.iter().enumerate() {
+            let expected = to_state(tlc_state)
+                .ok_or_else(|| format!("TLC state {} cannot be mapped to a model state.", i + 1))?;
+            let expected_fp = fingerprint(&expected);
+            let Some((last_state, last_fp)) = last.take() else {
+                let state = model
+                    .init_states()
+                    .into_iter()
+                    .find(|s| fingerprint(s) == expected_fp)
+                    .ok_or_else(|| format!("TLC state {} is not an init state.", i + 1))?;
+                last = Some((state, expected_fp));
+                continue;
+            };
+            if last_fp == expected_fp {
+                last = Some((last_state, last_fp));
+                continue;
+            }
+            let (action, state) = model
+                .next_steps(&last_state)
+                .into_iter()
+                .find(|(_, s)| fingerprint(s) == expected_fp)
+                .ok_or_else(|| {
+                    format!(
+                        "TLC state {} is not reachable from the previous state in one step.",
+                        i + 1
+                    )
+                })?;
+            output.push((last_state, Some(action)));
+            last = Some((state, expected_fp));
+        }
+        let (last_state, _) = last.ok_or_else(|| "The TLC trace is empty.".to_string())?;
+        output.push((last_state, None));
+        Ok(Path(output))
+    }
+
+    /// Constructs a path from a model and an encoded sequence of fingerprints, such as one
+    /// returned by [`Path::encode`] or exported by the Explorer. Returns `None` if the encoding is
+    /// invalid or the path is unreachable via the model.
+    pub fn from_encoded<M>(model: &M, encoded: &str) -> Option<Self>
+    where
+        M: Model<State = State, Action = Action>,
+        M::State: Hash,
+    {
+        let mut fingerprints = encoded
+            .trim_matches('/')
+            .split('/')
+            .map(|fp| fp.parse::<Fingerprint>().ok());
+        let init_print = fingerprints.next()??;
+        let mut last_state = model
+            .init_states()
+            .into_iter()
+            .find(|s| fingerprint(s) == init_print)?;
+        let mut output = Vec::new();
+        for next_fp in fingerprints {
+            let next_fp = next_fp?;
+            let (action, next_state) = model
+                .next_steps(&last_state)
+                .into_iter()
+                .find(|(_a, s)| fingerprint(s) == next_fp)?;
+            output.push((last_state, Some(action)));
+            last_state = next_state;
+        }
+        output.push((last_state, None));
+        Some(Path(output))
+    }
+
     /// Constructs a path from a model, initial state, and a sequence of actions. Panics for inputs
     /// unreachable via the model.
     pub fn from_actions<'a, M>(
@@ -131,6 +235,7 @@ Available next fingerprints (none of which match): {:?}"#,
     }
 
     /// Determines the final state associated with a particular fingerprint path.
+    #[cfg_attr(not(feature = "explorer"), allow(dead_code))]
     pub(crate) fn final_state<M>(
         model: &M,
         mut fingerprints: VecDeque<Fingerprint>,
@@ -164,6 +269,12 @@ Available next fingerprints (none of which match): {:?}"#,
         Some(matching_state)
     }
 
+    /// Extends the path with a step from its last state.
+    pub(crate) fn push(&mut self, action: Action, state: State) {
+        self.0.last_mut().unwrap().1 = Some(action);
+        self.0.push((state, None));
+    }
+
     /// Extracts the last state.
     pub fn last_state(&self) -> &State {
         &self.0.last().unwrap().0
@@ -184,6 +295,24 @@ Available next fingerprints (none of which match): {:?}"#,
         self.into()
     }
 
+    /// Renders the path as with its [`Display`] implementation, but with actions converted via
+    /// [`Model::format_action`], so that a model can control how its counterexamples read in
+    /// [reports](crate::Checker::report) and failed assertions (for instance one line per message
+    /// delivery).
+    pub fn format<M>(&self, model: &M) -> String
+    where
+        M: Model<State = State, Action = Action>,
+        Action: Debug,
+    {
+        let mut out = format!("Path[{}]:\n", self.0.len() - 1);
+        for (_state, action) in &self.0 {
+            if let Some(action) = action {
+                out.push_str(&format!("- {}\n", model.format_action(action)));
+            }
+        }
+        out
+    }
+
     /// Encodes the path as a sequence of opaque "fingerprints" delimited by forward
     /// slash (`/`) characters.
     pub fn encode(&self) -> String
@@ -196,6 +325,82 @@ Available next fingerprints (none of which match): {:?}"#,
             .collect::<Vec<String>>()
             .join("/")
     }
+
+    /// Renders the path as a TLC-style behavior: a TLA+ sequence of state expressions, each
+    /// preceded by a comment naming the action that led to it. This allows a counterexample to
+    /// be cross-validated against a TLA+ specification of the same system, for instance by
+    /// checking it with a trace expression in TLC.
+    ///
+    /// States are translated from their [`Debug`] representations:
+    ///
+    /// - `Name { f: v, .. }` becomes the record `[f |-> v, ..]`.
+    /// - `Name(v, ..)` becomes the tuple `<<"Name", v, ..>>`, and a bare `Name` becomes `"Name"`.
+    /// - Tuples and lists become tuples, sets become sets, and maps become functions.
+    /// - Integers, booleans, strings, and characters map to their TLA+ counterparts. Other
+    ///   numbers become strings.
+    ///
+    /// A state whose representation cannot be translated is rendered as a string.
+    pub fn to_tla_trace(&self) -> String
+    where
+        State: Debug,
+        Action: Debug,
+    {
+        let mut out = "<<\n".to_string();
+        let mut prev_action = None;
+        for (i, (state, action)) in self.0.iter().enumerate() {
+            let label = match prev_action {
+                None => "Init".to_string(),
+                Some(action) => format!("{:?}", action),
+            };
+            let debug = format!("{:?}", state);
+            out.push_str(&format!(
+                "    \\* {}\n    {}{}\n",
+                label.replace('\n', " "),
+                debug_to_tla(&debug).unwrap_or_else(|| tla_string(&debug)),
+                if i + 1 < self.0.len() { "," } else { "" }
+            ));
+            prev_action = action.as_ref();
+        }
+        out.push_str(">>\n");
+        out
+    }
+
+    /// Emits the source of a Rust test named `name` that replays this path's actions against the
+    /// model constructed by the `model` expression (via [`Path::from_actions`]) and asserts that
+    /// the final state matches, so that an interesting discovery can be kept as a regression
+    /// test.
+    ///
+    /// States and actions are emitted via their [`Debug`] representations, so the test only
+    /// compiles if those are valid Rust expressions where it is placed (as is typical for
+    /// derived implementations with the relevant types imported).
+    pub fn to_test_case(&self, name: &str, model: &str) -> String
+    where
+        State: Debug,
+        Action: Debug,
+    {
+        let actions: Vec<String> = self
+            .0
+            .iter()
+            .filter_map(|(_s, a)| a.as_ref().map(|a| format!("        {:?},\n", a)))
+            .collect();
+        format!(
+            "#[test]\n\
+             fn {name}() {{\n\
+             \x20   let model = {model};\n\
+             \x20   let actions = vec![\n\
+             {actions}\
+             \x20   ];\n\
+             \x20   let path = stateright::Path::from_actions(&model, {init:?}, &actions)\n\
+             \x20       .expect(\"actions are not possible in the model\");\n\
+             \x20   assert_eq!(path.last_state(), &{last:?});\n\
+             }}\n",
+            name = name,
+            model = model,
+            actions = actions.concat(),
+            init = self.0[0].0,
+            last = self.last_state(),
+        )
+    }
 }
 
 impl<State, Action> From<Path<State, Action>> for Vec<(State, Option<Action>)> {
@@ -226,6 +431,128 @@ mod test {
     use std::iter::FromIterator;
     use std::panic::catch_unwind;
 
+    #[test]
+    fn can_decode_encoded_path() {
+        use crate::test_util::binary_clock::*;
+
+        let path = Path::from_actions(
+            &BinaryClock,
+            0,
+            &[BinaryClockAction::GoHigh, BinaryClockAction::GoLow],
+        )
+        .unwrap();
+        assert_eq!(
+            Path::from_encoded(&BinaryClock, &path.encode()),
+            Some(path.clone())
+        );
+        assert_eq!(
+            Path::from_encoded(&BinaryClock, &format!("/{}/", path.encode())),
+            Some(path)
+        );
+
+        assert_eq!(Path::from_encoded(&BinaryClock, ""), None);
+        assert_eq!(Path::from_encoded(&BinaryClock, "one/two"), None);
+        let unreachable = format!("{}/{}", fingerprint(&0_i8), fingerprint(&0_i8));
+        assert_eq!(Path::from_encoded(&BinaryClock, &unreachable), None);
+    }
+
+    #[test]
+    fn can_emit_test_case() {
+        use crate::test_util::binary_clock::*;
+
+        let path = Path::from_actions(
+            &BinaryClock,
+            0,
+            &[BinaryClockAction::GoHigh, BinaryClockAction::GoLow],
+        )
+        .unwrap();
+        assert_eq!(
+            path.to_test_case("goes_high_then_low", "BinaryClock"),
+            "\
+            #[test]\n\
+            fn goes_high_then_low() {\n\
+            \x20   let model = BinaryClock;\n\
+            \x20   let actions = vec![\n\
+            \x20       GoHigh,\n\
+            \x20       GoLow,\n\
+            \x20   ];\n\
+            \x20   let path = stateright::Path::from_actions(&model, 0, &actions)\n\
+            \x20       .expect(\"actions are not possible in the model\");\n\
+            \x20   assert_eq!(path.last_state(), &0);\n\
+            }\n"
+        );
+    }
+
+    #[test]
+    fn can_emit_tla_trace() {
+        use crate::test_util::binary_clock::*;
+
+        let path = Path::from_actions(
+            &BinaryClock,
+            0,
+            &[BinaryClockAction::GoHigh, BinaryClockAction::GoLow],
+        )
+        .unwrap();
+        assert_eq!(
+            path.to_tla_trace(),
+            "<<\n    \\* Init\n    0,\n    \\* GoHigh\n    1,\n    \\* GoLow\n    0\n>>\n"
+        );
+
+        let path: Path<_, ()> = Path(vec![((Some('a'), vec![(1, "b")]), None)]);
+        assert_eq!(
+            path.to_tla_trace(),
+            "<<\n    \\* Init\n    <<<<\"Some\", \"a\">>, <<<<1, \"b\">>>>>>\n>>\n"
+        );
+    }
+
+    #[test]
+    fn can_replay_tlc_trace() {
+        use crate::test_util::linear_equation_solver::*;
+        use crate::TlaValue;
+
+        let model = LinearEquation { a: 2, b: 10, c: 14 };
+        let to_state = |state: &TlcState| {
+            let x = state.get("x")?.as_int()?;
+            let y = state.get("y")?.as_int()?;
+            Some((u8::try_from(x).ok()?, u8::try_from(y).ok()?))
+        };
+        let trace = TlcTrace::parse(
+            "\
+            State 1: <Initial predicate>\n/\\ x = 0\n/\\ y = 0\n\n\
+            State 2: <IncreaseX line 8, col 14 to line 8, col 40 of module Linear>\n/\\ x = 1\n/\\ y = 0\n\n\
+            State 3: <Tick line 10, col 9 to line 10, col 30 of module Linear>\n/\\ x = 1\n/\\ y = 0\n\n\
+            State 4: <IncreaseY line 9, col 14 to line 9, col 40 of module Linear>\n/\\ x = 1\n/\\ y = 1\n",
+        )
+        .unwrap();
+        let path = Path::from_tlc_trace(&model, &trace, to_state).unwrap();
+        assert_eq!(path.last_state(), &(1, 1));
+        assert_eq!(
+            path.into_actions(),
+            vec![Guess::IncreaseX, Guess::IncreaseY]
+        );
+
+        let mut skipping = trace.clone();
+        skipping.states[1]
+            .variables
+            .insert("x".to_string(), TlaValue::Int(2));
+        assert_eq!(
+            Path::from_tlc_trace(&model, &skipping, to_state),
+            Err("TLC state 2 is not reachable from the previous state in one step.".to_string())
+        );
+        let mut unmappable = trace.clone();
+        unmappable.states[3].variables.remove("y");
+        assert_eq!(
+            Path::from_tlc_trace(&model, &unmappable, to_state),
+            Err("TLC state 4 cannot be mapped to a model state.".to_string())
+        );
+        let mut uninitialized = trace;
+        uninitialized.states.remove(0);
+        assert_eq!(
+            Path::from_tlc_trace(&model, &uninitialized, to_state),
+            Err("TLC state 1 is not an init state.".to_string())
+        );
+    }
+
     #[test]
     fn panics_if_unable_to_reconstruct_init_state() {
         let model: fn(Option<&_>, &mut Vec<_>) = |prev_state, next_states| {
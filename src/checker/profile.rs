@@ -0,0 +1,747 @@
+//! Private module for selective re-export.
+
+use crate::{CheckerBuilder, Fingerprint, Model};
+use nohash_hasher::NoHashHasher;
+use serde::ser::{self, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{BuildHasherDefault, Hash, Hasher};
+
+/// The number of actions and fields listed when a [`StateSpaceProfile`] is displayed.
+const DISPLAYED_CONTRIBUTORS: usize = 10;
+
+/// Attributes the growth of a model's state space to actions and to state fields, indicating
+/// what to abstract or bound when a model has too many states to check. See
+/// [`CheckerBuilder::profile`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct StateSpaceProfile {
+    /// The number of states generated, including repeats.
+    pub state_count: usize,
+    /// The number of unique states generated.
+    pub unique_state_count: usize,
+    /// The maximum depth of the states explored.
+    pub max_depth: usize,
+    /// Actions grouped by label, with those that generated the most unique states first.
+    pub actions: Vec<ActionProfile>,
+    /// State fields, with those that have the most distinct values first.
+    pub fields: Vec<FieldProfile>,
+}
+
+/// The states generated by actions that share a label. See [`StateSpaceProfile`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ActionProfile {
+    /// The [formatted action](Model::format_action) with any bracketed arguments removed, so
+    /// that for instance delivering any `Put` message between two actors is labeled
+    /// `Id → Put → Id`.
+    pub label: String,
+    /// The number of states generated by these actions, including repeats.
+    pub state_count: usize,
+    /// The number of states first generated by these actions.
+    pub unique_state_count: usize,
+}
+
+/// The values of a state field. See [`StateSpaceProfile`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FieldProfile {
+    /// The path to the field based on the state's [`Serialize`] implementation, such as
+    /// `actor_states[1].ballot`. Enum fields are qualified by the variant name, and the values of
+    /// maps are grouped under `[*]`.
+    pub path: String,
+    /// The number of distinct values of the field among the unique states. Sequences are compared
+    /// without regard to order.
+    pub distinct_value_count: usize,
+}
+
+impl Display for StateSpaceProfile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Profiled states={}, unique={}, depth={}",
+            self.state_count, self.unique_state_count, self.max_depth
+        )?;
+        writeln!(f, "Actions by unique states generated:")?;
+        for action in self.actions.iter().take(DISPLAYED_CONTRIBUTORS) {
+            writeln!(
+                f,
+                "  {:>10} {:>5.1}%  {} (states={})",
+                action.unique_state_count,
+                100.0 * action.unique_state_count as f64 / self.unique_state_count.max(1) as f64,
+                action.label,
+                action.state_count,
+            )?;
+        }
+        writeln!(f, "Fields by distinct values:")?;
+        for field in self.fields.iter().take(DISPLAYED_CONTRIBUTORS) {
+            writeln!(f, "  {:>10}  {}", field.distinct_value_count, field.path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Explores the state space breadth-first, honoring the builder's boundary, targets, symmetry
+/// reduction, and fingerprint hasher.
+pub(crate) fn profile<M>(options: CheckerBuilder<M>) -> StateSpaceProfile
+where
+    M: Model,
+    M::State: Hash + Serialize,
+    M::Action: Debug,
+{
+    let model = options.model;
+    let fingerprint = |state: &M::State| match options.symmetry {
+        Some(representative) => options
+            .fingerprint_hasher
+            .fingerprint(&representative(state)),
+        None => options.fingerprint_hasher.fingerprint(state),
+    };
+
+    let mut generated: HashSet<Fingerprint, BuildHasherDefault<NoHashHasher<u64>>> =
+        HashSet::default();
+    let mut fields = FieldValues::new();
+    let mut pending = VecDeque::new();
+    let mut state_count = 0;
+    for state in model.init_states() {
+        if !model.within_boundary(&state) {
+            continue;
+        }
+        state_count += 1;
+        if generated.insert(fingerprint(&state)) {
+            fields.record(&state);
+            pending.push_back((state, 0));
+        }
+    }
+
+    let mut actions_by_label = BTreeMap::<String, ActionProfile>::new();
+    let mut actions = Vec::new();
+    let mut max_depth = 0;
+    while let Some((state, depth)) = pending.pop_front() {
+        max_depth = max_depth.max(depth);
+        if let Some(target_state_count) = options.target_state_count {
+            if target_state_count.get() <= state_count {
+                break;
+            }
+        }
+        if let Some(target_max_depth) = options.target_max_depth {
+            if depth + 1 >= target_max_depth.get() {
+                continue;
+            }
+        }
+        model.actions(&state, &mut actions);
+        for action in actions.drain(..) {
+            let label = action_label(&model.format_action(&action));
+            let next_state = match model.next_state(&state, action) {
+                Some(next_state) if model.within_boundary(&next_state) => next_state,
+                _ => continue,
+            };
+            let profile = actions_by_label
+                .entry(label)
+                .or_insert_with_key(|label| ActionProfile {
+                    label: label.clone(),
+                    state_count: 0,
+                    unique_state_count: 0,
+                });
+            profile.state_count += 1;
+            state_count += 1;
+            if generated.insert(fingerprint(&next_state)) {
+                profile.unique_state_count += 1;
+                fields.record(&next_state);
+                pending.push_back((next_state, depth + 1));
+            }
+        }
+    }
+
+    let mut actions: Vec<_> = actions_by_label.into_values().collect();
+    actions.sort_by_key(|a| std::cmp::Reverse(a.unique_state_count));
+    StateSpaceProfile {
+        state_count,
+        unique_state_count: generated.len(),
+        max_depth,
+        actions,
+        fields: fields.into_profiles(),
+    }
+}
+
+/// Removes bracketed content from a formatted action, so that actions differing only in their
+/// arguments share a label.
+fn action_label(formatted: &str) -> String {
+    let mut nesting = 0_usize;
+    let mut label = String::new();
+    for c in formatted.chars() {
+        match c {
+            '(' | '[' | '{' => nesting += 1,
+            ')' | ']' | '}' => nesting = nesting.saturating_sub(1),
+            c if nesting == 0 => label.push(c),
+            _ => {}
+        }
+    }
+    label.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Tracks the hashes of the distinct values of each field path.
+struct FieldValues(HashMap<String, HashSet<u64, BuildHasherDefault<NoHashHasher<u64>>>>);
+
+impl FieldValues {
+    fn new() -> Self {
+        Self(HashMap::new())
+    }
+
+    fn record<T: Serialize>(&mut self, state: &T) {
+        // A state that fails to serialize still contributes the fields visited before the error.
+        let _ = state.serialize(FieldSerializer {
+            path: String::new(),
+            values: Some(self),
+            record_value: false,
+        });
+    }
+
+    fn into_profiles(self) -> Vec<FieldProfile> {
+        let mut profiles: Vec<_> = self
+            .0
+            .into_iter()
+            .map(|(path, values)| FieldProfile {
+                path,
+                distinct_value_count: values.len(),
+            })
+            .collect();
+        profiles.sort_by(|a, b| {
+            b.distinct_value_count
+                .cmp(&a.distinct_value_count)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        profiles
+    }
+}
+
+fn hash_of<T: Hash>(value: T) -> u64 {
+    let mut hasher = crate::stable::hasher();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[derive(Debug)]
+struct SerializeError(String);
+
+impl Display for SerializeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for SerializeError {}
+
+impl ser::Error for SerializeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        SerializeError(msg.to_string())
+    }
+}
+
+/// Computes the hash of a serialized value, recording the hash of each field along the way.
+/// Map keys are only hashed, so they are serialized without `values`.
+struct FieldSerializer<'a> {
+    path: String,
+    values: Option<&'a mut FieldValues>,
+    /// Whether the hash of the value itself is recorded, as opposed to only those of its fields.
+    record_value: bool,
+}
+
+impl<'a> FieldSerializer<'a> {
+    fn leaf(mut self, hash: u64) -> Result<u64, SerializeError> {
+        self.record(hash);
+        Ok(hash)
+    }
+
+    fn record(&mut self, hash: u64) {
+        if !self.record_value {
+            return;
+        }
+        if let Some(values) = &mut self.values {
+            match values.0.get_mut(&self.path) {
+                Some(hashes) => {
+                    hashes.insert(hash);
+                }
+                None => {
+                    values
+                        .0
+                        .insert(std::mem::take(&mut self.path), HashSet::from_iter([hash]));
+                }
+            }
+        }
+    }
+
+    fn field_path(&self, name: impl Display) -> String {
+        if self.path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", self.path, name)
+        }
+    }
+
+    fn compound(self, path: String, tag: u64, ordered: bool) -> Compound<'a> {
+        Compound {
+            parent: self,
+            path,
+            hash: tag,
+            ordered,
+            index: 0,
+            key: None,
+        }
+    }
+}
+
+/// Combines the hashes of the members of a sequence, tuple, map, or struct.
+struct Compound<'a> {
+    parent: FieldSerializer<'a>,
+    /// The path under which members are recorded.
+    path: String,
+    hash: u64,
+    ordered: bool,
+    index: usize,
+    key: Option<u64>,
+}
+
+impl<'a> Compound<'a> {
+    fn member<T: ?Sized + Serialize>(
+        &mut self,
+        path: String,
+        value: &T,
+    ) -> Result<(), SerializeError> {
+        let hash = value.serialize(FieldSerializer {
+            path,
+            values: self.parent.values.as_deref_mut(),
+            record_value: true,
+        })?;
+        self.combine(hash);
+        Ok(())
+    }
+
+    fn combine(&mut self, hash: u64) {
+        self.index += 1;
+        self.hash = if self.ordered {
+            hash_of((self.hash, hash))
+        } else {
+            self.hash.wrapping_add(hash_of(hash))
+        };
+    }
+
+    fn end(self) -> Result<u64, SerializeError> {
+        let hash = self.hash;
+        self.parent.leaf(hash)
+    }
+}
+
+macro_rules! leaf_methods {
+    ($($method:ident($type:ty)),* $(,)?) => {
+        $(
+            fn $method(self, v: $type) -> Result<u64, SerializeError> {
+                self.leaf(hash_of((stringify!($method), v)))
+            }
+        )*
+    };
+}
+
+impl<'a> ser::Serializer for FieldSerializer<'a> {
+    type Ok = u64;
+    type Error = SerializeError;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    leaf_methods!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+    );
+
+    fn serialize_f32(self, v: f32) -> Result<u64, SerializeError> {
+        self.leaf(hash_of(("f32", v.to_bits())))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<u64, SerializeError> {
+        self.leaf(hash_of(("f64", v.to_bits())))
+    }
+
+    fn serialize_none(self) -> Result<u64, SerializeError> {
+        self.leaf(hash_of("None"))
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<u64, SerializeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<u64, SerializeError> {
+        self.leaf(hash_of("()"))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<u64, SerializeError> {
+        self.leaf(hash_of(name))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<u64, SerializeError> {
+        self.leaf(hash_of(variant))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<u64, SerializeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        mut self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<u64, SerializeError> {
+        // The variant's value is recorded as the field itself, while any nested fields are
+        // qualified by the variant name.
+        let hash = value.serialize(FieldSerializer {
+            path: self.field_path(variant),
+            values: self.values.as_deref_mut(),
+            record_value: false,
+        })?;
+        self.leaf(hash_of((variant, hash)))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'a>, SerializeError> {
+        let path = self.path.clone();
+        Ok(self.compound(path, hash_of("seq"), false))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Compound<'a>, SerializeError> {
+        let path = self.path.clone();
+        Ok(self.compound(path, hash_of("tuple"), true))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, SerializeError> {
+        let path = self.path.clone();
+        Ok(self.compound(path, hash_of(name), true))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, SerializeError> {
+        let path = self.field_path(variant);
+        Ok(self.compound(path, hash_of(variant), true))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'a>, SerializeError> {
+        let path = self.path.clone();
+        Ok(self.compound(path, hash_of("map"), false))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, SerializeError> {
+        let path = self.path.clone();
+        Ok(self.compound(path, hash_of(name), true))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, SerializeError> {
+        let path = self.field_path(variant);
+        Ok(self.compound(path, hash_of(variant), true))
+    }
+}
+
+impl<'a> Compound<'a> {
+    fn index_path(&self) -> String {
+        format!("{}[{}]", self.path, self.index)
+    }
+
+    fn position_path(&self) -> String {
+        if self.path.is_empty() {
+            self.index.to_string()
+        } else {
+            format!("{}.{}", self.path, self.index)
+        }
+    }
+}
+
+impl<'a> ser::SerializeSeq for Compound<'a> {
+    type Ok = u64;
+    type Error = SerializeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.member(self.index_path(), value)
+    }
+
+    fn end(self) -> Result<u64, SerializeError> {
+        Compound::end(self)
+    }
+}
+
+impl<'a> ser::SerializeTuple for Compound<'a> {
+    type Ok = u64;
+    type Error = SerializeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.member(self.position_path(), value)
+    }
+
+    fn end(self) -> Result<u64, SerializeError> {
+        Compound::end(self)
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for Compound<'a> {
+    type Ok = u64;
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.member(self.position_path(), value)
+    }
+
+    fn end(self) -> Result<u64, SerializeError> {
+        Compound::end(self)
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for Compound<'a> {
+    type Ok = u64;
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.member(self.position_path(), value)
+    }
+
+    fn end(self) -> Result<u64, SerializeError> {
+        Compound::end(self)
+    }
+}
+
+impl<'a> ser::SerializeMap for Compound<'a> {
+    type Ok = u64;
+    type Error = SerializeError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Self::Error> {
+        self.key = Some(key.serialize(FieldSerializer {
+            path: String::new(),
+            values: None,
+            record_value: false,
+        })?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let key = self.key.take().unwrap_or_default();
+        let value = value.serialize(FieldSerializer {
+            path: format!("{}[*]", self.path),
+            values: self.parent.values.as_deref_mut(),
+            record_value: true,
+        })?;
+        self.combine(hash_of((key, value)));
+        Ok(())
+    }
+
+    fn end(self) -> Result<u64, SerializeError> {
+        Compound::end(self)
+    }
+}
+
+impl<'a> ser::SerializeStruct for Compound<'a> {
+    type Ok = u64;
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        let path = if self.path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", self.path, key)
+        };
+        self.member(path, value)
+    }
+
+    fn end(self) -> Result<u64, SerializeError> {
+        Compound::end(self)
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for Compound<'a> {
+    type Ok = u64;
+    type Error = SerializeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.member(format!("{}.{}", self.path, key), value)
+    }
+
+    fn end(self) -> Result<u64, SerializeError> {
+        Compound::end(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::linear_equation_solver::*;
+
+    #[test]
+    fn labels_actions_without_arguments() {
+        assert_eq!(action_label("IncreaseX"), "IncreaseX");
+        assert_eq!(action_label("Id(0) → Put(1, 'A') → Id(1)"), "Id → Put → Id");
+        assert_eq!(action_label("Timeout(Id(2), Election)"), "Timeout");
+        assert_eq!(
+            action_label("Deliver { src: Id(0), dst: Id(1), msg: Ping(1) }"),
+            "Deliver"
+        );
+    }
+
+    #[test]
+    fn records_distinct_values_per_field() {
+        #[derive(serde::Serialize)]
+        enum Phase {
+            Idle,
+            Decided((u8, u8)),
+            Voting { round: u8, votes: Vec<u8> },
+        }
+        #[derive(serde::Serialize)]
+        struct State {
+            phase: Phase,
+            peers: (char, char),
+            log: BTreeMap<u8, String>,
+        }
+
+        let mut fields = FieldValues::new();
+        for (phase, log) in [
+            (Phase::Idle, vec![]),
+            (
+                Phase::Voting {
+                    round: 1,
+                    votes: vec![1, 2],
+                },
+                vec![(1, "a")],
+            ),
+            (
+                Phase::Voting {
+                    round: 1,
+                    votes: vec![2, 1],
+                },
+                vec![(2, "a")],
+            ),
+            (
+                Phase::Voting {
+                    round: 2,
+                    votes: vec![3],
+                },
+                vec![(2, "b")],
+            ),
+            (Phase::Decided((2, 3)), vec![]),
+        ] {
+            fields.record(&State {
+                phase,
+                peers: ('a', 'b'),
+                log: log.into_iter().map(|(k, v)| (k, v.to_string())).collect(),
+            });
+        }
+        let counts: BTreeMap<_, _> = fields
+            .into_profiles()
+            .into_iter()
+            .map(|f| (f.path, f.distinct_value_count))
+            .collect();
+        assert_eq!(
+            counts,
+            BTreeMap::from_iter([
+                ("log".to_string(), 4),
+                ("log[*]".to_string(), 2),
+                ("peers".to_string(), 1),
+                ("peers.0".to_string(), 1),
+                ("peers.1".to_string(), 1),
+                ("phase".to_string(), 4), // vote order is ignored
+                ("phase.Decided.0".to_string(), 1),
+                ("phase.Decided.1".to_string(), 1),
+                ("phase.Voting.round".to_string(), 2),
+                ("phase.Voting.votes".to_string(), 2),
+                ("phase.Voting.votes[0]".to_string(), 3),
+                ("phase.Voting.votes[1]".to_string(), 2),
+            ])
+        );
+    }
+
+    #[test]
+    fn attributes_states_to_actions_and_fields() {
+        let profile = LinearEquation { a: 2, b: 4, c: 7 }.checker().profile();
+        assert_eq!(profile.unique_state_count, 256 * 256);
+        assert_eq!(profile.state_count, 1 + 2 * 256 * 256);
+        assert_eq!(profile.max_depth, 510);
+        assert_eq!(
+            profile.actions,
+            vec![
+                // Breadth-first search reaches each `(x, y)` with `y > 0` via `(x, y - 1)` first.
+                ActionProfile {
+                    label: "IncreaseY".to_string(),
+                    state_count: 256 * 256,
+                    unique_state_count: 256 * 255,
+                },
+                ActionProfile {
+                    label: "IncreaseX".to_string(),
+                    state_count: 256 * 256,
+                    unique_state_count: 255,
+                },
+            ]
+        );
+        assert_eq!(
+            profile.fields,
+            vec![
+                FieldProfile {
+                    path: "0".to_string(),
+                    distinct_value_count: 256,
+                },
+                FieldProfile {
+                    path: "1".to_string(),
+                    distinct_value_count: 256,
+                },
+            ]
+        );
+
+        let profile = LinearEquation { a: 2, b: 4, c: 7 }
+            .checker()
+            .target_max_depth(3)
+            .profile();
+        assert_eq!(profile.unique_state_count, 6); // (0,0) (1,0) (0,1) (2,0) (1,1) (0,2)
+        assert_eq!(profile.max_depth, 2);
+    }
+}
@@ -1,7 +1,7 @@
 //! Private module for selective re-export.
 
 use crate::actor::{Envelope, Id};
-use crate::util::{HashableHashMap, HashableHashSet};
+use crate::util::{HashableHashMap, HashableHashSet, PersistentHashMap, PersistentHashSet};
 use crate::RewritePlan;
 use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::hash::Hash;
@@ -114,6 +114,27 @@ where
             .collect()
     }
 }
+impl<R, V> Rewrite<R> for PersistentHashSet<V>
+where
+    V: Clone + Eq + Hash + Rewrite<R>,
+{
+    #[inline(always)]
+    fn rewrite<S>(&self, plan: &RewritePlan<R, S>) -> Self {
+        self.iter().map(|x| x.rewrite(plan)).collect()
+    }
+}
+impl<R, K, V> Rewrite<R> for PersistentHashMap<K, V>
+where
+    V: Clone + Hash + Rewrite<R>,
+    K: Clone + Eq + Hash + Rewrite<R>,
+{
+    #[inline(always)]
+    fn rewrite<S>(&self, plan: &RewritePlan<R, S>) -> Self {
+        self.iter()
+            .map(|(k, v)| (k.rewrite(plan), v.rewrite(plan)))
+            .collect()
+    }
+}
 impl<R, T> Rewrite<R> for Option<T>
 where
     T: Rewrite<R>,
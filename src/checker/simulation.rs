@@ -1,19 +1,26 @@
 //! Private module for selective re-export.
 
-use crate::checker::{Checker, Expectation, Path};
-use crate::{fingerprint, CheckerBuilder, CheckerVisitor, Fingerprint, Model, Property};
+use crate::checker::{
+    with_violating_step, Checker, Expectation, MetricSummary, Metrics, Observation, Path,
+    Transitions,
+};
+use crate::{
+    CheckerBuilder, CheckerVisitor, Condition, Fingerprint, FingerprintHasher, Model, Property,
+};
 use dashmap::DashMap;
 use rand::rngs::StdRng;
 use rand::Rng;
 use rand::SeedableRng;
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
 use std::hash::Hash;
 use std::num::NonZeroUsize;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::thread::JoinHandle;
 
-use super::EventuallyBits;
+use super::{Deadlines, EventuallyBits};
 
 /// Choose transitions in the model.
 ///
@@ -77,15 +84,107 @@ where
     }
 }
 
+/// A property requiring that a state satisfying a condition be reached within a bounded number of
+/// steps with at least some probability. See [`CheckerBuilder::probability`].
+pub(crate) struct ProbabilityProperty<M: Model> {
+    pub name: Cow<'static, str>,
+    pub min_probability: f64,
+    pub steps: usize,
+    pub condition: Condition<M>,
+}
+
+/// An estimate of the probability that a simulated trace reaches a state satisfying a condition
+/// within a bounded number of steps. See [`CheckerBuilder::probability`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Estimate {
+    /// The name of the property.
+    pub name: Cow<'static, str>,
+    /// The number of steps within which the condition must be satisfied.
+    pub steps: usize,
+    /// The minimum probability required by the property.
+    pub min_probability: f64,
+    /// The number of simulated traces.
+    pub trials: usize,
+    /// The number of traces that satisfied the condition within the step bound.
+    pub hits: usize,
+}
+
+impl Estimate {
+    /// The estimated probability, which is the fraction of traces that satisfied the condition.
+    pub fn probability(&self) -> f64 {
+        if self.trials == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / self.trials as f64
+    }
+
+    /// The 95% confidence interval for the probability (a Wilson score interval).
+    pub fn confidence_interval(&self) -> (f64, f64) {
+        if self.trials == 0 {
+            return (0.0, 1.0);
+        }
+        const Z: f64 = 1.96;
+        let n = self.trials as f64;
+        let p = self.probability();
+        let denominator = 1.0 + Z * Z / n;
+        let center = (p + Z * Z / (2.0 * n)) / denominator;
+        let margin = Z * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt() / denominator;
+        ((center - margin).max(0.0), (center + margin).min(1.0))
+    }
+
+    /// Indicates whether the estimated probability meets the minimum.
+    pub fn is_satisfied(&self) -> bool {
+        self.trials > 0 && self.probability() >= self.min_probability
+    }
+
+    /// Indicates whether further traces are unlikely to change whether the minimum is met, as the
+    /// confidence interval either excludes the minimum or is no wider than [`ESTIMATE_PRECISION`].
+    /// The latter bounds the number of traces needed (to about 40,000) even if the probability is
+    /// exactly the minimum.
+    fn is_decided(&self) -> bool {
+        let (low, high) = self.confidence_interval();
+        self.trials > 0
+            && (self.min_probability <= low
+                || high < self.min_probability
+                || high - low <= ESTIMATE_PRECISION)
+    }
+}
+
+/// The width of confidence interval at which the simulation checker stops estimating
+/// probabilities if no [target state count](CheckerBuilder::target_state_count) is set.
+const ESTIMATE_PRECISION: f64 = 0.01;
+
+impl Display for Estimate {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let (low, high) = self.confidence_interval();
+        write!(
+            f,
+            "P(\"{}\" within {} steps) = {:.4} (95% CI [{:.4}, {:.4}], trials={}), expected >= {}",
+            self.name,
+            self.steps,
+            self.probability(),
+            low,
+            high,
+            self.trials,
+            self.min_probability,
+        )
+    }
+}
+
 pub(crate) struct SimulationChecker<M: Model> {
     // Immutable state.
     model: Arc<M>,
+    fingerprint_hasher: FingerprintHasher,
     handles: Vec<std::thread::JoinHandle<()>>,
 
     // Mutable state.
     state_count: Arc<AtomicUsize>,
     max_depth: Arc<AtomicUsize>,
-    discoveries: Arc<DashMap<&'static str, Vec<Fingerprint>>>,
+    discoveries: Arc<DashMap<Cow<'static, str>, Vec<Fingerprint>>>,
+    metrics: Arc<Metrics<M>>,
+    probabilities: Arc<Vec<ProbabilityProperty<M>>>,
+    trace_count: Arc<AtomicUsize>,
+    hit_counts: Arc<Vec<AtomicUsize>>,
 }
 
 impl<M> SimulationChecker<M>
@@ -100,25 +199,41 @@ where
     /// reproducibility. For other threads and traces it is regenerated using a [`StdRng`].
     pub(crate) fn spawn<C: Chooser<M>>(options: CheckerBuilder<M>, seed: u64, chooser: C) -> Self {
         let model = Arc::new(options.model);
+        let fingerprint_hasher = options.fingerprint_hasher;
         let symmetry = options.symmetry;
         let target_state_count = options.target_state_count;
         let target_max_depth = options.target_max_depth;
         let visitor = Arc::new(options.visitor);
-        let property_count = model.properties().len();
+        let observation = options.observation;
+        let metrics = Arc::new(Metrics::new(options.metrics));
+        // The same properties are evaluated throughout, so that memoized conditions keep their
+        // caches. See `Property::memoize_by`.
+        let properties = Arc::new(model.properties());
+        let property_count = properties.len();
+        let probabilities = Arc::new(options.probabilities);
 
         let state_count = Arc::new(AtomicUsize::new(0));
         let max_depth = Arc::new(AtomicUsize::new(0));
         let discoveries = Arc::new(DashMap::default());
+        let trace_count = Arc::new(AtomicUsize::new(0));
+        let hit_counts: Arc<Vec<_>> =
+            Arc::new(probabilities.iter().map(|_| AtomicUsize::new(0)).collect());
         let mut handles = Vec::new();
 
         let mut thread_seed = seed;
 
         for t in 0..options.thread_count {
             let model = Arc::clone(&model);
+            let properties = Arc::clone(&properties);
             let visitor = Arc::clone(&visitor);
+            let observation = observation.clone();
+            let metrics = Arc::clone(&metrics);
             let state_count = Arc::clone(&state_count);
             let max_depth = Arc::clone(&max_depth);
             let discoveries = Arc::clone(&discoveries);
+            let probabilities = Arc::clone(&probabilities);
+            let trace_count = Arc::clone(&trace_count);
+            let hit_counts = Arc::clone(&hit_counts);
             let chooser = chooser.clone();
             handles.push(
                 std::thread::Builder::new()
@@ -131,20 +246,36 @@ where
                         loop {
                             Self::check_trace_from_initial::<C>(
                                 &model,
+                                &properties,
+                                fingerprint_hasher,
                                 seed,
                                 &chooser,
                                 &state_count,
                                 &discoveries,
+                                &probabilities,
+                                &trace_count,
+                                &hit_counts,
                                 &visitor,
+                                &observation,
+                                &metrics,
                                 target_max_depth,
                                 &max_depth,
                                 symmetry,
                             );
 
-                            // Check whether we have found everything.
+                            // Check whether we have found everything. Probabilities are estimated
+                            // until reaching the target state count, or if there is none, until
+                            // each estimate is decided.
                             // All threads should reach this check and have the same result,
                             // leading them all to shut down together.
-                            if discoveries.len() == property_count {
+                            let is_estimated = if target_state_count.is_some() {
+                                probabilities.is_empty()
+                            } else {
+                                estimates(&probabilities, &trace_count, &hit_counts)
+                                    .iter()
+                                    .all(Estimate::is_decided)
+                            };
+                            if discoveries.len() == property_count && is_estimated {
                                 log::debug!("{}: Discovery complete. Shutting down...", t,);
                                 return;
                             }
@@ -168,10 +299,15 @@ where
         }
         SimulationChecker {
             model,
+            fingerprint_hasher,
             handles,
             state_count,
             max_depth,
             discoveries,
+            metrics,
+            probabilities,
+            trace_count,
+            hit_counts,
         }
     }
 
@@ -179,17 +315,22 @@ where
     #[allow(clippy::type_complexity)]
     fn check_trace_from_initial<C: Chooser<M>>(
         model: &M,
+        properties: &[Property<M>],
+        fingerprint_hasher: FingerprintHasher,
         seed: u64,
         chooser: &C,
         state_count: &AtomicUsize,
-        discoveries: &DashMap<&'static str, Vec<Fingerprint>>,
+        discoveries: &DashMap<Cow<'static, str>, Vec<Fingerprint>>,
+        probabilities: &[ProbabilityProperty<M>],
+        trace_count: &AtomicUsize,
+        hit_counts: &[AtomicUsize],
         visitor: &Option<Box<dyn CheckerVisitor<M> + Send + Sync>>,
+        observation: &Option<Observation<M::State>>,
+        metrics: &Metrics<M>,
         target_max_depth: Option<NonZeroUsize>,
         global_max_depth: &AtomicUsize,
         symmetry: Option<fn(&M::State) -> M::State>,
     ) {
-        let properties = model.properties();
-
         let mut chooser_state = chooser.new_state(seed);
 
         let mut state = {
@@ -205,9 +346,14 @@ where
         let mut fingerprint_path = Vec::new();
         // The fingerprints we've seen in this run, for preventing cycles.
         let mut generated = HashSet::new();
+        // The observation of the previous state, for skipping stuttering steps.
+        let mut prev_observation = None;
+        // Which probability properties this trace has satisfied.
+        let mut reached = vec![false; probabilities.len()];
+        trace_count.fetch_add(1, Ordering::Relaxed);
         let mut ebits = {
             let mut ebits = EventuallyBits::new();
-            for (i, p) in model.properties().iter().enumerate() {
+            for (i, p) in properties.iter().enumerate() {
                 if let Property {
                     expectation: Expectation::Eventually,
                     ..
@@ -218,6 +364,8 @@ where
             }
             ebits
         };
+        let mut deadlines = Deadlines::default();
+        let mut is_terminal = false;
         'outer: loop {
             if fingerprint_path.len() > current_max_depth {
                 let _ = global_max_depth.compare_exchange(
@@ -247,16 +395,21 @@ where
             }
 
             // add the current fingerprint to the path
-            fingerprint_path.push(fingerprint(&state));
+            fingerprint_path.push(fingerprint_hasher.fingerprint(&state));
             // check that we haven't already seen this state
             let inserted = if let Some(representative) = symmetry {
-                generated.insert(fingerprint(&representative(&state)))
+                generated.insert(fingerprint_hasher.fingerprint(&representative(&state)))
             } else {
-                generated.insert(fingerprint(&state))
+                generated.insert(fingerprint_hasher.fingerprint(&state))
             };
             if !inserted {
-                // found a loop
+                // found a loop, which only violates the `leads_to` properties whose obligations
+                // remain pending around it
                 log::trace!("Found a loop");
+                let depth = fingerprint_path.len() - 1;
+                let path = fingerprint_path[..depth].iter().rev().copied();
+                let cycle = deadlines.cycle(depth, fingerprint_path[depth], path);
+                deadlines.leads_to.retain(|(i, _)| cycle.contains(i));
                 break;
             }
 
@@ -265,14 +418,31 @@ where
             if let Some(visitor) = visitor {
                 visitor.visit(
                     model,
-                    Path::from_fingerprints(model, VecDeque::from(fingerprint_path.clone())),
+                    Path::from_fingerprints_with_hasher(
+                        model,
+                        VecDeque::from(fingerprint_path.clone()),
+                        fingerprint_hasher,
+                    ),
                 );
             }
+            metrics.record(model, &state);
+
+            // Properties are only evaluated for observable transitions.
+            let state_observation = observation.as_ref().map(|observe| observe(&state));
+            let is_stuttering =
+                state_observation.is_some() && state_observation == prev_observation;
+            prev_observation = state_observation;
 
             // Done if discoveries found for all properties.
             let mut is_awaiting_discoveries = false;
             for (i, property) in properties.iter().enumerate() {
-                if discoveries.contains_key(property.name) {
+                if discoveries.contains_key(&property.name) {
+                    continue;
+                }
+                if is_stuttering {
+                    // The observation is unchanged, so the property evaluates as it did for the
+                    // predecessor.
+                    is_awaiting_discoveries = true;
                     continue;
                 }
                 match property {
@@ -281,9 +451,12 @@ where
                         condition: always,
                         ..
                     } => {
-                        if !always(model, &state) {
+                        if property.transition.is_some() {
+                            // Evaluated for each step taken from the state.
+                            is_awaiting_discoveries = true;
+                        } else if !always(model, &state) {
                             // Races other threads, but that's fine.
-                            discoveries.insert(property.name, fingerprint_path.clone());
+                            discoveries.insert(property.name.clone(), fingerprint_path.clone());
                         } else {
                             is_awaiting_discoveries = true;
                         }
@@ -295,7 +468,7 @@ where
                     } => {
                         if sometimes(model, &state) {
                             // Races other threads, but that's fine.
-                            discoveries.insert(property.name, fingerprint_path.clone());
+                            discoveries.insert(property.name.clone(), fingerprint_path.clone());
                         } else {
                             is_awaiting_discoveries = true;
                         }
@@ -315,14 +488,59 @@ where
                             ebits.remove(i);
                         }
                     }
+                    Property {
+                        expectation: Expectation::Within,
+                        ..
+                    } => {
+                        if !deadlines.advance(i, property, model, &state) {
+                            // Races other threads, but that's fine.
+                            discoveries.insert(property.name.clone(), fingerprint_path.clone());
+                        } else {
+                            is_awaiting_discoveries = true;
+                        }
+                    }
+                    Property {
+                        expectation: Expectation::LeadsTo,
+                        ..
+                    } => {
+                        // Only identified at terminal states and upon closing cycles.
+                        is_awaiting_discoveries = true;
+                        deadlines.respond(i, property, model, &state, fingerprint_path.len());
+                    }
+                    Property {
+                        expectation: Expectation::Terminal,
+                        ..
+                    } => {
+                        // Only evaluated once the state is known to be terminal.
+                        is_awaiting_discoveries = true;
+                    }
+                }
+            }
+
+            // Tally the probability properties that this trace satisfies within their bounds.
+            let depth = fingerprint_path.len() - 1;
+            let mut is_awaiting_estimates = false;
+            for (i, probability) in probabilities.iter().enumerate() {
+                if reached[i] || depth > probability.steps {
+                    continue;
+                }
+                if (probability.condition)(model, &state) {
+                    reached[i] = true;
+                    hit_counts[i].fetch_add(1, Ordering::Relaxed);
+                } else if depth < probability.steps {
+                    is_awaiting_estimates = true;
                 }
             }
-            if !is_awaiting_discoveries {
+
+            if !is_awaiting_discoveries && !is_awaiting_estimates {
                 log::trace!("Found all discoveries");
                 break;
             }
 
             // generate the possible next actions
+            let mut transitions = Transitions::new(model, &state, properties, |p| {
+                !is_stuttering && !discoveries.contains_key(&p.name)
+            });
             model.actions(&state, &mut actions);
 
             // generate the next state, repeatedly choosing an action until we get one or there are
@@ -332,12 +550,14 @@ where
                     // no actions to choose from
                     // break from the outer loop so that we still check eventually properties
                     log::trace!("No actions to choose from");
+                    is_terminal = true;
                     break 'outer;
                 }
 
                 // now pick one
                 let index = chooser.choose_action(&mut chooser_state, &state, &actions);
                 let action = actions.swap_remove(index);
+                let transition_action = transitions.swap_remove(index);
 
                 // take the chosen action
                 match model.next_state(&state, action) {
@@ -346,6 +566,12 @@ where
                         log::trace!("No next state");
                     }
                     Some(next_state) => {
+                        for property in
+                            transitions.violated(model, &state, &transition_action, &next_state)
+                        {
+                            // Races other threads, but that's fine.
+                            discoveries.insert(property.name.clone(), fingerprint_path.clone());
+                        }
                         // now clear the actions for the next round
                         actions.clear();
                         state = next_state;
@@ -354,11 +580,15 @@ where
                 };
             }
         }
-        // check the eventually properties
+        // check the eventually properties, and terminal properties if the trace ended for lack of
+        // successors
         for (i, property) in properties.iter().enumerate() {
-            if ebits.contains(i) {
+            let is_violated_terminal = is_terminal
+                && property.expectation == Expectation::Terminal
+                && !(property.condition)(model, &state);
+            if ebits.contains(i) || deadlines.is_pending(i) || is_violated_terminal {
                 // Races other threads, but that's fine.
-                discoveries.insert(property.name, fingerprint_path.clone());
+                discoveries.insert(property.name.clone(), fingerprint_path.clone());
             }
         }
     }
@@ -387,18 +617,34 @@ where
         self.max_depth.load(Ordering::Relaxed)
     }
 
-    fn discoveries(&self) -> HashMap<&'static str, Path<M::State, M::Action>> {
+    fn discoveries(&self) -> HashMap<Cow<'static, str>, Path<M::State, M::Action>> {
         self.discoveries
             .iter()
             .map(|mapref| {
                 (
-                    <&'static str>::clone(mapref.key()),
-                    Path::from_fingerprints(self.model(), VecDeque::from(mapref.value().clone())),
+                    mapref.key().clone(),
+                    with_violating_step(
+                        self.model(),
+                        mapref.key(),
+                        Path::from_fingerprints_with_hasher(
+                            self.model(),
+                            VecDeque::from(mapref.value().clone()),
+                            self.fingerprint_hasher,
+                        ),
+                    ),
                 )
             })
             .collect()
     }
 
+    fn estimates(&self) -> Vec<Estimate> {
+        estimates(&self.probabilities, &self.trace_count, &self.hit_counts)
+    }
+
+    fn metrics(&self) -> Vec<MetricSummary> {
+        self.metrics.summaries()
+    }
+
     fn handles(&mut self) -> Vec<JoinHandle<()>> {
         std::mem::take(&mut self.handles)
     }
@@ -406,6 +652,30 @@ where
     fn is_done(&self) -> bool {
         self.handles.iter().all(|h| h.is_finished())
     }
+
+    fn is_truncated(&self) -> bool {
+        // Simulation samples paths rather than visiting every reachable state.
+        true
+    }
+}
+
+fn estimates<M: Model>(
+    probabilities: &[ProbabilityProperty<M>],
+    trace_count: &AtomicUsize,
+    hit_counts: &[AtomicUsize],
+) -> Vec<Estimate> {
+    let trials = trace_count.load(Ordering::Relaxed);
+    probabilities
+        .iter()
+        .zip(hit_counts)
+        .map(|(probability, hits)| Estimate {
+            name: probability.name.clone(),
+            steps: probability.steps,
+            min_probability: probability.min_probability,
+            trials,
+            hits: hits.load(Ordering::Relaxed),
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -426,4 +696,62 @@ mod test {
             vec![Guess::IncreaseX, Guess::IncreaseY, Guess::IncreaseX],
         );
     }
+
+    #[test]
+    fn can_estimate_probabilities() {
+        let checker = LinearEquation { a: 2, b: 10, c: 14 }
+            .checker()
+            .probability("x increased", 0.4, 1, |_, (x, _)| *x > 0)
+            .probability(String::from("x and y increased"), 0.6, 2, |_, (x, y)| {
+                *x > 0 && *y > 0
+            })
+            .target_max_depth(5)
+            .target_state_count(10_000)
+            .spawn_simulation(0, UniformChooser)
+            .join();
+        let estimates = checker.estimates();
+        assert_eq!(estimates.len(), 2);
+        assert!(estimates[0].trials > 1_000);
+
+        // Exactly 1/2 and 1/2, respectively.
+        let (low, high) = estimates[0].confidence_interval();
+        assert!(low < 0.5 && 0.5 < high, "{}", estimates[0]);
+        assert!(estimates[0].is_satisfied());
+        let (low, high) = estimates[1].confidence_interval();
+        assert!(low < 0.5 && 0.5 < high, "{}", estimates[1]);
+        assert!(!estimates[1].is_satisfied());
+    }
+
+    #[test]
+    fn stops_estimating_probabilities_once_decided() {
+        let checker = LinearEquation { a: 2, b: 10, c: 14 }
+            .checker()
+            .probability("x increased", 0.4, 1, |_, (x, _)| *x > 0)
+            .probability("x and y increased", 0.5, 2, |_, (x, y)| *x > 0 && *y > 0)
+            .target_max_depth(5)
+            .spawn_simulation(0, UniformChooser)
+            .join();
+        // The second probability is exactly the minimum, so checking may only stop once the
+        // confidence interval is narrow enough.
+        let estimates = checker.estimates();
+        assert!(estimates.iter().all(Estimate::is_decided));
+        assert!(estimates[0].is_satisfied());
+    }
+
+    #[test]
+    fn formats_estimates() {
+        let estimate = Estimate {
+            name: "commits".into(),
+            steps: 50,
+            min_probability: 0.99,
+            trials: 1_000,
+            hits: 995,
+        };
+        assert!(estimate.is_satisfied());
+        assert_eq!(
+            estimate.to_string(),
+            "P(\"commits\" within 50 steps) = 0.9950 (95% CI [0.9883, 0.9979], trials=1000), \
+             expected >= 0.99"
+        );
+    }
 }
@@ -0,0 +1,225 @@
+//! Private module for spilling pending states to disk.
+
+use crate::util::CompactState;
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Pending states are written in segments of roughly this size, so that disk access is
+/// sequential and at most two segments per thread are held in memory.
+const SEGMENT_BYTES: usize = 16 << 20;
+
+static NEXT_QUEUE_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Configures a checker to spill pending states to disk. See [`CheckerBuilder::spill_to_disk`].
+///
+/// [`CheckerBuilder::spill_to_disk`]: crate::CheckerBuilder::spill_to_disk
+pub(crate) struct SpillOptions<State> {
+    pub(crate) dir: PathBuf,
+    pub(crate) max_pending: usize,
+    pub(crate) encode: fn(&State, &mut Vec<u8>),
+    pub(crate) decode: fn(&mut &[u8]) -> Option<State>,
+}
+
+impl<State> SpillOptions<State> {
+    pub(crate) fn new(dir: PathBuf, max_pending: usize) -> Self
+    where
+        State: CompactState,
+    {
+        Self {
+            dir,
+            max_pending: max_pending.max(1),
+            encode: State::encode,
+            decode: State::decode,
+        }
+    }
+}
+
+impl<State> Clone for SpillOptions<State> {
+    fn clone(&self) -> Self {
+        Self {
+            dir: self.dir.clone(),
+            max_pending: self.max_pending,
+            encode: self.encode,
+            decode: self.decode,
+        }
+    }
+}
+
+/// A first-in first-out queue of records that are buffered in memory and written to segment
+/// files once the buffer fills. Segment files are deleted once read or when the queue is dropped.
+pub(crate) struct SpillQueue {
+    dir: PathBuf,
+    id: usize,
+    next_segment: usize,
+    segments: VecDeque<PathBuf>,
+    write_buf: Vec<u8>,
+    read_buf: Vec<u8>,
+    read_pos: usize,
+    scratch: Vec<u8>,
+    len: usize,
+}
+
+impl SpillQueue {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            id: NEXT_QUEUE_ID.fetch_add(1, Ordering::Relaxed),
+            next_segment: 0,
+            segments: VecDeque::new(),
+            write_buf: Vec::new(),
+            read_buf: Vec::new(),
+            read_pos: 0,
+            scratch: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of records in the queue.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends the record written by `encode`.
+    pub(crate) fn push_with(&mut self, encode: impl FnOnce(&mut Vec<u8>)) -> io::Result<()> {
+        self.scratch.clear();
+        encode(&mut self.scratch);
+        self.scratch.len().encode(&mut self.write_buf);
+        self.write_buf.extend_from_slice(&self.scratch);
+        self.len += 1;
+        if self.write_buf.len() >= SEGMENT_BYTES {
+            fs::create_dir_all(&self.dir)?;
+            let path = self.dir.join(format!(
+                "stateright-{}-{}-{}.spill",
+                std::process::id(),
+                self.id,
+                self.next_segment
+            ));
+            fs::write(&path, &self.write_buf)?;
+            log::debug!(
+                "Spilled {} bytes to {}",
+                self.write_buf.len(),
+                path.display()
+            );
+            self.next_segment += 1;
+            self.segments.push_back(path);
+            self.write_buf.clear();
+        }
+        Ok(())
+    }
+
+    /// Removes the oldest record, decoding it with `decode`. Returns `Ok(None)` if the queue is
+    /// empty.
+    pub(crate) fn pop_with<T>(
+        &mut self,
+        decode: impl FnOnce(&mut &[u8]) -> Option<T>,
+    ) -> io::Result<Option<T>> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        if self.read_pos == self.read_buf.len() {
+            self.read_pos = 0;
+            self.read_buf = match self.segments.pop_front() {
+                Some(path) => {
+                    let bytes = fs::read(&path)?;
+                    fs::remove_file(&path)?;
+                    bytes
+                }
+                // Records that were never written to a segment are read directly.
+                None => std::mem::take(&mut self.write_buf),
+            };
+        }
+        let mut buf = &self.read_buf[self.read_pos..];
+        let record = usize::decode(&mut buf)
+            .and_then(|len| {
+                let record = buf.get(..len)?;
+                buf = &buf[len..];
+                Some(record)
+            })
+            .and_then(|mut record| {
+                let value = decode(&mut record)?;
+                record.is_empty().then_some(value)
+            })
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "corrupt spill record"))?;
+        self.read_pos = self.read_buf.len() - buf.len();
+        self.len -= 1;
+        Ok(Some(record))
+    }
+}
+
+impl Drop for SpillQueue {
+    fn drop(&mut self) {
+        for path in &self.segments {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn preserves_order_across_segments() {
+        let dir = std::env::temp_dir().join(format!("stateright-spill-{}", std::process::id()));
+        let mut queue = SpillQueue::new(dir.clone());
+        let record = vec![7_u8; 1 << 20];
+        let push = |queue: &mut SpillQueue, i: usize| {
+            queue
+                .push_with(|buf| {
+                    i.encode(buf);
+                    record.encode(buf);
+                })
+                .unwrap();
+        };
+        let pop = |queue: &mut SpillQueue| {
+            queue
+                .pop_with(<(usize, Vec<u8>)>::decode)
+                .unwrap()
+                .map(|(i, bytes)| {
+                    assert_eq!(bytes, record);
+                    i
+                })
+        };
+
+        for i in 0..20 {
+            push(&mut queue, i);
+        }
+        assert_eq!(queue.segments.len(), 1);
+        assert_eq!(pop(&mut queue), Some(0));
+        for i in 20..40 {
+            push(&mut queue, i);
+        }
+        // The first segment has been read into memory, and a second has been written.
+        assert_eq!(queue.segments.len(), 1);
+        let mut popped = Vec::new();
+        while let Some(i) = pop(&mut queue) {
+            popped.push(i);
+        }
+        assert_eq!(popped, (1..40).collect::<Vec<_>>());
+        assert!(queue.is_empty());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir(dir).unwrap();
+    }
+
+    #[test]
+    fn removes_segments_on_drop() {
+        let dir =
+            std::env::temp_dir().join(format!("stateright-spill-drop-{}", std::process::id()));
+        let mut queue = SpillQueue::new(dir.clone());
+        for _ in 0..20 {
+            queue.push_with(|buf| buf.resize(1 << 20, 1)).unwrap();
+        }
+        assert_eq!(queue.len(), 20);
+        assert!(fs::read_dir(&dir).unwrap().count() > 0);
+        drop(queue);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir(dir).unwrap();
+    }
+}
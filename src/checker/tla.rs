@@ -0,0 +1,649 @@
+//! Private module for translating values to and from TLA+ expressions.
+
+use std::collections::BTreeMap;
+
+/// Renders text as a TLA+ string literal.
+pub(crate) fn tla_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Translates the compact [`Debug`] representation of a value to a TLA+ expression, returning
+/// `None` if the representation is not understood:
+///
+/// - `Name { f: v, .. }` becomes the record `[f |-> v, ..]`.
+/// - `Name(v, ..)` becomes the tuple `<<"Name", v, ..>>`, and a bare `Name` becomes `"Name"`.
+/// - Tuples and lists become tuples, sets become sets, and maps become functions.
+/// - Integers, booleans, strings, and characters map to their TLA+ counterparts. Other numbers
+///   become strings.
+pub(crate) fn debug_to_tla(debug: &str) -> Option<String> {
+    let mut parser = DebugParser {
+        chars: debug.chars().collect(),
+        pos: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos == parser.chars.len() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+struct DebugParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl DebugParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(&predicate) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// Parses comma-separated items until `close`, which is consumed.
+    fn items<T>(
+        &mut self,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Option<T>,
+    ) -> Option<Vec<T>> {
+        let mut items = Vec::new();
+        if self.eat(close) {
+            return Some(items);
+        }
+        loop {
+            items.push(item(self)?);
+            if self.eat(close) {
+                return Some(items);
+            }
+            if !self.eat(',') {
+                return None;
+            }
+        }
+    }
+
+    fn value(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        match self.peek()? {
+            '"' => self.quoted('"').map(|s| format!("\"{}\"", s)),
+            '\'' => self
+                .quoted('\'')
+                .map(|s| format!("\"{}\"", s.replace("\\'", "'").replace('"', "\\\""))),
+            '[' | '(' => {
+                let close = if self.peek() == Some('[') { ']' } else { ')' };
+                self.pos += 1;
+                Some(format!(
+                    "<<{}>>",
+                    self.items(close, Self::value)?.join(", ")
+                ))
+            }
+            '{' => {
+                self.pos += 1;
+                self.set_or_map()
+            }
+            c if c == '-' || c.is_ascii_digit() => {
+                let number = self.take_while(|c| c.is_alphanumeric() || "-+._".contains(c));
+                let is_integer = number
+                    .strip_prefix('-')
+                    .unwrap_or(&number)
+                    .chars()
+                    .all(|c| c.is_ascii_digit());
+                Some(if is_integer {
+                    number
+                } else {
+                    tla_string(&number)
+                })
+            }
+            c if c.is_alphabetic() || c == '_' => self.named(),
+            _ => None,
+        }
+    }
+
+    /// Parses a string or character literal, returning its escaped contents.
+    fn quoted(&mut self, quote: char) -> Option<String> {
+        self.pos += 1;
+        let mut contents = String::new();
+        loop {
+            match self.peek()? {
+                '\\' => {
+                    contents.push('\\');
+                    self.pos += 1;
+                    contents.push(self.peek()?);
+                }
+                c if c == quote => {
+                    self.pos += 1;
+                    return Some(contents);
+                }
+                c => contents.push(c),
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn set_or_map(&mut self) -> Option<String> {
+        if self.eat('}') {
+            return Some("{}".to_string());
+        }
+        let first = self.value()?;
+        if !self.eat(':') {
+            let mut elements = vec![first];
+            if !self.eat('}') {
+                if !self.eat(',') {
+                    return None;
+                }
+                elements.extend(self.items('}', Self::value)?);
+            }
+            return Some(format!("{{{}}}", elements.join(", ")));
+        }
+        let mut entries = vec![format!("{} :> {}", first, self.value()?)];
+        if !self.eat('}') {
+            if !self.eat(',') {
+                return None;
+            }
+            entries.extend(self.items('}', |parser| {
+                let key = parser.value()?;
+                if !parser.eat(':') {
+                    return None;
+                }
+                Some(format!("{} :> {}", key, parser.value()?))
+            })?);
+        }
+        Some(format!("({})", entries.join(" @@ ")))
+    }
+
+    /// Parses a struct, tuple struct, enum variant, or boolean.
+    fn named(&mut self) -> Option<String> {
+        let name = self.take_while(|c| c.is_alphanumeric() || c == '_' || c == ':');
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                let fields = self.items('}', |parser| {
+                    parser.skip_whitespace();
+                    if parser.chars[parser.pos..].starts_with(&['.', '.']) {
+                        // Elided fields of a non-exhaustive struct.
+                        parser.pos += 2;
+                        return Some(None);
+                    }
+                    let field = parser.take_while(|c| c.is_alphanumeric() || c == '_');
+                    if field.is_empty() || !parser.eat(':') {
+                        return None;
+                    }
+                    Some(Some(format!("{} |-> {}", field, parser.value()?)))
+                })?;
+                let fields: Vec<_> = fields.into_iter().flatten().collect();
+                if fields.is_empty() {
+                    Some(tla_string(&name))
+                } else {
+                    Some(format!("[{}]", fields.join(", ")))
+                }
+            }
+            Some('(') => {
+                self.pos += 1;
+                let mut elements = vec![tla_string(&name)];
+                elements.extend(self.items(')', Self::value)?);
+                Some(format!("<<{}>>", elements.join(", ")))
+            }
+            _ => Some(match name.as_str() {
+                "true" => "TRUE".to_string(),
+                "false" => "FALSE".to_string(),
+                _ => tla_string(&name),
+            }),
+        }
+    }
+}
+
+/// A value parsed from a TLC error trace. See [`TlcTrace`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum TlaValue {
+    /// `TRUE` or `FALSE`.
+    Bool(bool),
+    /// An integer.
+    Int(i64),
+    /// A string.
+    String(String),
+    /// A model value or other bare identifier.
+    ModelValue(String),
+    /// A sequence or tuple, such as `<<1, 2>>`.
+    Seq(Vec<TlaValue>),
+    /// A set, such as `{1, 2}`, in the order printed by TLC.
+    Set(Vec<TlaValue>),
+    /// A record, such as `[a |-> 1, b |-> 2]`.
+    Record(BTreeMap<String, TlaValue>),
+    /// A function, such as `(1 :> "a" @@ 2 :> "b")`, in the order printed by TLC.
+    Function(Vec<(TlaValue, TlaValue)>),
+}
+
+impl TlaValue {
+    /// Returns the value of a boolean.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            TlaValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of an integer.
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            TlaValue::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Returns the contents of a string or the name of a model value.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            TlaValue::String(s) | TlaValue::ModelValue(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of a sequence or set.
+    pub fn as_slice(&self) -> Option<&[TlaValue]> {
+        match self {
+            TlaValue::Seq(elements) | TlaValue::Set(elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a record field.
+    pub fn field(&self, name: &str) -> Option<&TlaValue> {
+        match self {
+            TlaValue::Record(fields) => fields.get(name),
+            _ => None,
+        }
+    }
+
+    /// Applies a function, record, or sequence (indexed from 1) to an argument.
+    pub fn apply(&self, arg: &TlaValue) -> Option<&TlaValue> {
+        match (self, arg) {
+            (TlaValue::Function(pairs), _) => pairs.iter().find(|(k, _)| k == arg).map(|(_, v)| v),
+            (TlaValue::Record(fields), TlaValue::String(name)) => fields.get(name),
+            (TlaValue::Seq(elements), TlaValue::Int(i)) => {
+                elements.get(usize::try_from(*i).ok()?.checked_sub(1)?)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A state of a [`TlcTrace`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TlcState {
+    /// The description TLC printed for the step that reached this state, such as
+    /// `Initial predicate` or `Next line 12, col 5 to line 14, col 20 of module Clock`.
+    pub action: String,
+    /// The value of each variable.
+    pub variables: BTreeMap<String, TlaValue>,
+}
+
+impl TlcState {
+    /// Returns the value of a variable.
+    pub fn get(&self, variable: &str) -> Option<&TlaValue> {
+        self.variables.get(variable)
+    }
+}
+
+/// A behavior parsed from the error trace that TLC prints for a counterexample, which can be
+/// replayed against a corresponding model via [`Path::from_tlc_trace`].
+///
+/// [`Path::from_tlc_trace`]: crate::Path::from_tlc_trace
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TlcTrace {
+    /// The states of the behavior, starting with an initial state.
+    pub states: Vec<TlcState>,
+}
+
+impl TlcTrace {
+    /// Parses the `State N: <...>` blocks of TLC's output, each of which is a conjunction of
+    /// `variable = value` lines. Other output, such as the error description preceding the trace
+    /// and the statistics following it, is ignored, as are stuttering steps and the
+    /// `Back to state` marker that ends a liveness counterexample.
+    pub fn parse(output: &str) -> Result<Self, String> {
+        let mut states = Vec::new();
+        let mut block: Option<(usize, String, String)> = None;
+        let finish = |block: Option<(usize, String, String)>, states: &mut Vec<TlcState>| {
+            let Some((line, action, body)) = block else {
+                return Ok(());
+            };
+            let variables = TlaParser::new(&body)
+                .conjunction()
+                .ok_or_else(|| format!("Unable to parse the state starting on line {}.", line))?;
+            states.push(TlcState { action, variables });
+            Ok::<_, String>(())
+        };
+        for (i, line) in output.lines().enumerate() {
+            let trimmed = line.trim();
+            if let Some(header) = trimmed.strip_prefix("State ") {
+                finish(block.take(), &mut states)?;
+                let description = header
+                    .split_once(':')
+                    .map(|(_, description)| description.trim())
+                    .unwrap_or_default();
+                if let Some(action) = description
+                    .strip_prefix('<')
+                    .and_then(|d| d.strip_suffix('>'))
+                {
+                    block = Some((i + 1, action.to_string(), String::new()));
+                }
+            } else if trimmed.is_empty() || trimmed.starts_with("@!@!@") {
+                finish(block.take(), &mut states)?;
+            } else if let Some((_, _, body)) = &mut block {
+                body.push_str(line);
+                body.push('\n');
+            }
+        }
+        finish(block, &mut states)?;
+        if states.is_empty() {
+            return Err("No states found in the TLC output.".to_string());
+        }
+        Ok(TlcTrace { states })
+    }
+}
+
+struct TlaParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl TlaParser {
+    fn new(text: &str) -> Self {
+        TlaParser {
+            chars: text.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, expected: &str) -> bool {
+        self.skip_whitespace();
+        let expected: Vec<char> = expected.chars().collect();
+        if self.chars[self.pos..].starts_with(&expected) {
+            self.pos += expected.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn identifier(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.pos += 1;
+        }
+        (start < self.pos).then(|| self.chars[start..self.pos].iter().collect())
+    }
+
+    /// Parses `/\ x = 1 /\ y = 2` (or a lone `x = 1`) through the end of the input.
+    fn conjunction(&mut self) -> Option<BTreeMap<String, TlaValue>> {
+        let mut variables = BTreeMap::new();
+        loop {
+            let bulleted = self.eat("/\\");
+            let name = self.identifier()?;
+            if !self.eat("=") {
+                return None;
+            }
+            variables.insert(name, self.value()?);
+            self.skip_whitespace();
+            if self.peek().is_none() {
+                return Some(variables);
+            }
+            if !bulleted {
+                return None;
+            }
+        }
+    }
+
+    /// Parses comma-separated items until `close`, which is consumed.
+    fn items<T>(
+        &mut self,
+        close: &str,
+        mut item: impl FnMut(&mut Self) -> Option<T>,
+    ) -> Option<Vec<T>> {
+        let mut items = Vec::new();
+        if self.eat(close) {
+            return Some(items);
+        }
+        loop {
+            items.push(item(self)?);
+            if self.eat(close) {
+                return Some(items);
+            }
+            if !self.eat(",") {
+                return None;
+            }
+        }
+    }
+
+    fn value(&mut self) -> Option<TlaValue> {
+        self.skip_whitespace();
+        if self.eat("<<") {
+            return Some(TlaValue::Seq(self.items(">>", Self::value)?));
+        }
+        match self.peek()? {
+            '{' => {
+                self.pos += 1;
+                Some(TlaValue::Set(self.items("}", Self::value)?))
+            }
+            '[' => {
+                self.pos += 1;
+                let fields = self.items("]", |parser| {
+                    let name = parser.identifier()?;
+                    parser.eat("|->").then_some(())?;
+                    Some((name, parser.value()?))
+                })?;
+                Some(TlaValue::Record(fields.into_iter().collect()))
+            }
+            '(' => {
+                self.pos += 1;
+                let mut pairs = Vec::new();
+                loop {
+                    let key = self.value()?;
+                    self.eat(":>").then_some(())?;
+                    pairs.push((key, self.value()?));
+                    if self.eat(")") {
+                        return Some(TlaValue::Function(pairs));
+                    }
+                    self.eat("@@").then_some(())?;
+                }
+            }
+            '"' => {
+                self.pos += 1;
+                let mut contents = String::new();
+                loop {
+                    match self.peek()? {
+                        '\\' => {
+                            self.pos += 1;
+                            contents.push(match self.peek()? {
+                                'n' => '\n',
+                                't' => '\t',
+                                c => c,
+                            });
+                        }
+                        '"' => {
+                            self.pos += 1;
+                            return Some(TlaValue::String(contents));
+                        }
+                        c => contents.push(c),
+                    }
+                    self.pos += 1;
+                }
+            }
+            '-' => {
+                self.pos += 1;
+                self.identifier()?
+                    .parse::<i64>()
+                    .ok()
+                    .map(|i| TlaValue::Int(-i))
+            }
+            _ => {
+                let identifier = self.identifier()?;
+                Some(match identifier.as_str() {
+                    "TRUE" => TlaValue::Bool(true),
+                    "FALSE" => TlaValue::Bool(false),
+                    _ => match identifier.parse() {
+                        Ok(i) => TlaValue::Int(i),
+                        Err(_) => TlaValue::ModelValue(identifier),
+                    },
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    enum Msg {
+        Ping(u8, char),
+        Pong { seq: i32, text: &'static str },
+        Halt,
+    }
+
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct State {
+        ok: bool,
+        msgs: Vec<Msg>,
+        set: BTreeSet<u8>,
+        map: BTreeMap<&'static str, (u8, f32)>,
+        nothing: Option<u8>,
+    }
+
+    #[test]
+    fn translates_debug_to_tla() {
+        let state = State {
+            ok: true,
+            msgs: vec![
+                Msg::Ping(1, '"'),
+                Msg::Pong {
+                    seq: -2,
+                    text: "a \"b\"",
+                },
+                Msg::Halt,
+            ],
+            set: [3, 1].into_iter().collect(),
+            map: [("x", (1, 0.5))].into_iter().collect(),
+            nothing: None,
+        };
+        assert_eq!(
+            debug_to_tla(&format!("{:?}", state)).unwrap(),
+            "[ok |-> TRUE, \
+              msgs |-> <<<<\"Ping\", 1, \"\\\"\">>, [seq |-> -2, text |-> \"a \\\"b\\\"\"], \"Halt\">>, \
+              set |-> {1, 3}, \
+              map |-> (\"x\" :> <<1, \"0.5\">>), \
+              nothing |-> \"None\"]"
+        );
+        assert_eq!(debug_to_tla("{}").unwrap(), "{}");
+        assert_eq!(debug_to_tla("[]").unwrap(), "<<>>");
+        assert_eq!(debug_to_tla("Opaque { .. }").unwrap(), "\"Opaque\"");
+        assert_eq!(debug_to_tla("<opaque>"), None);
+        assert_eq!(debug_to_tla("[1, 2"), None);
+    }
+
+    #[test]
+    fn parses_tlc_trace() {
+        let output = r#"
+@!@!@STARTMSG 2110:1 @!@!@
+Invariant Safe is violated.
+@!@!@ENDMSG 2110 @!@!@
+Error: The behavior up to this point is:
+State 1: <Initial predicate>
+/\ msgs = {}
+/\ votes = (n1 :> -1 @@ n2 :> 0)
+/\ log = <<>>
+
+State 2: <Send line 12, col 9 to line 14, col 30 of module Vote>
+/\ msgs = { [type |-> "vote", src |-> n1, ok |-> TRUE],
+            [type |-> "say \"hi\"", src |-> n2, ok |-> FALSE] }
+/\ votes = (n1 :> -1 @@ n2 :> 0)
+/\ log = <<1, <<"a", 2>>>>
+
+State 3: Stuttering
+Back to state 1: <Send line 12, col 9 to line 14, col 30 of module Vote>
+
+4 states generated, 3 distinct states found, 0 states left on queue.
+"#;
+        let trace = TlcTrace::parse(output).unwrap();
+        assert_eq!(trace.states.len(), 2);
+        assert_eq!(trace.states[0].action, "Initial predicate");
+        assert_eq!(
+            trace.states[1].action,
+            "Send line 12, col 9 to line 14, col 30 of module Vote"
+        );
+        assert_eq!(trace.states[0].get("msgs"), Some(&TlaValue::Set(vec![])));
+        assert_eq!(trace.states[0].get("log"), Some(&TlaValue::Seq(vec![])));
+
+        let votes = trace.states[1].get("votes").unwrap();
+        let n1 = TlaValue::ModelValue("n1".to_string());
+        assert_eq!(votes.apply(&n1).and_then(TlaValue::as_int), Some(-1));
+        let msgs = trace.states[1].get("msgs").unwrap().as_slice().unwrap();
+        assert_eq!(msgs.len(), 2);
+        assert_eq!(msgs[0].field("src").and_then(TlaValue::as_str), Some("n1"));
+        assert_eq!(msgs[0].field("ok").and_then(TlaValue::as_bool), Some(true));
+        assert_eq!(
+            msgs[1].field("type").and_then(TlaValue::as_str),
+            Some("say \"hi\"")
+        );
+        assert_eq!(
+            trace.states[1].get("log"),
+            Some(&TlaValue::Seq(vec![
+                TlaValue::Int(1),
+                TlaValue::Seq(vec![TlaValue::String("a".to_string()), TlaValue::Int(2)]),
+            ]))
+        );
+
+        assert_eq!(
+            TlcTrace::parse("State 1: <Initial predicate>\nx = 1\n")
+                .unwrap()
+                .states[0]
+                .get("x"),
+            Some(&TlaValue::Int(1))
+        );
+        assert_eq!(
+            TlcTrace::parse("State 1: <Initial predicate>\n/\\ x = [a |-> ]\n"),
+            Err("Unable to parse the state starting on line 1.".to_string())
+        );
+        assert_eq!(
+            TlcTrace::parse("Model checking completed. No error has been found."),
+            Err("No states found in the TLC output.".to_string())
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! Private module for the set of states visited by a checker.
+
+use crate::Fingerprint;
+use nohash_hasher::NoHashHasher;
+use parking_lot::Mutex;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::hash::BuildHasherDefault;
+
+/// The number of shards per checker thread. More shards reduce the chance that two threads
+/// contend for the same lock at the cost of a larger fixed footprint.
+const SHARDS_PER_THREAD: usize = 16;
+
+type Shard<V> = HashMap<Fingerprint, V, BuildHasherDefault<NoHashHasher<u64>>>;
+
+/// Maps the fingerprint of each visited state to a value, such as the fingerprint of the state's
+/// predecessor. Use `()` for the value if only membership is needed.
+///
+/// The map is partitioned by fingerprint prefix into independently locked shards, so each state
+/// is routed to a shard by the leading bits of its fingerprint. Fingerprints are uniformly
+/// distributed, so threads that discover states concurrently rarely contend for a shard.
+pub(crate) struct VisitedMap<V> {
+    shards: Box<[Mutex<Shard<V>>]>,
+    prefix_shift: u32,
+}
+
+impl<V> VisitedMap<V> {
+    /// Constructs an empty map sized for the specified number of checker threads.
+    pub(crate) fn new(thread_count: usize) -> Self {
+        let shard_count = (thread_count.max(1) * SHARDS_PER_THREAD).next_power_of_two();
+        Self {
+            shards: (0..shard_count)
+                .map(|_| Mutex::new(Shard::default()))
+                .collect(),
+            prefix_shift: u64::BITS - shard_count.trailing_zeros(),
+        }
+    }
+
+    /// Inserts the value unless the fingerprint is already present, in which case the existing
+    /// value is retained. Returns `true` if the value was inserted.
+    pub(crate) fn insert(&self, fingerprint: Fingerprint, value: V) -> bool {
+        match self.shard(fingerprint).lock().entry(fingerprint) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                true
+            }
+        }
+    }
+
+    /// Returns the value for the fingerprint if the state has been visited.
+    pub(crate) fn get(&self, fingerprint: Fingerprint) -> Option<V>
+    where
+        V: Copy,
+    {
+        self.shard(fingerprint).lock().get(&fingerprint).copied()
+    }
+
+    /// Returns the number of visited states.
+    pub(crate) fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.lock().len()).sum()
+    }
+
+    fn shard(&self, fingerprint: Fingerprint) -> &Mutex<Shard<V>> {
+        let index = fingerprint
+            .get()
+            .checked_shr(self.prefix_shift)
+            .unwrap_or_default();
+        &self.shards[index as usize]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+
+    fn fp(n: u64) -> Fingerprint {
+        Fingerprint::new(n).unwrap()
+    }
+
+    #[test]
+    fn retains_first_inserted_value() {
+        let visited = VisitedMap::new(1);
+        assert!(visited.insert(fp(1), 'a'));
+        assert!(!visited.insert(fp(1), 'b'));
+        assert!(visited.insert(fp(u64::MAX), 'c'));
+        assert_eq!(visited.get(fp(1)), Some('a'));
+        assert_eq!(visited.get(fp(2)), None);
+        assert_eq!(visited.get(fp(u64::MAX)), Some('c'));
+        assert_eq!(visited.len(), 2);
+    }
+
+    #[test]
+    fn routes_by_fingerprint_prefix() {
+        let visited = VisitedMap::<()>::new(2);
+        assert_eq!(visited.shards.len(), 32);
+        let shard_index = |n| {
+            let shard = visited.shard(fp(n)) as *const _;
+            visited.shards.iter().position(|s| std::ptr::eq(s, shard))
+        };
+        assert_eq!(shard_index(1), Some(0));
+        assert_eq!(shard_index(u64::MAX >> 5), Some(0));
+        assert_eq!(shard_index(1 << 59), Some(1));
+        assert_eq!(shard_index(u64::MAX), Some(31));
+    }
+
+    #[test]
+    fn counts_concurrent_inserts() {
+        let visited = Arc::new(VisitedMap::new(4));
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let visited = Arc::clone(&visited);
+                std::thread::spawn(move || {
+                    // Pairs of threads insert the same fingerprints.
+                    (0..1_000)
+                        .filter(|i| visited.insert(crate::fingerprint(&(t / 2, i)), ()))
+                        .count()
+                })
+            })
+            .collect();
+        let inserted: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+        assert_eq!(inserted, 2_000);
+        assert_eq!(visited.len(), 2_000);
+    }
+}
@@ -1,5 +1,5 @@
-use crate::{Model, Path};
-use std::collections::HashSet;
+use crate::{fingerprint, Fingerprint, Model, Path};
+use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 use std::sync::{Arc, Mutex};
 
@@ -109,3 +109,95 @@ where
         (recorder, accessor)
     }
 }
+
+/// A [`CheckerVisitor`] that counts how often each state and each transition between two states
+/// is visited. Exhaustive checkers visit each unique state once, so the counts are most telling
+/// for [simulation](crate::CheckerBuilder::spawn_simulation), which revisits hot states
+/// repeatedly. The Explorer can overlay the counts as a
+/// [heatmap](crate::ExplorerBuilder::heatmap).
+///
+/// # Example
+///
+/// ```
+/// # use stateright::*; let model = ();
+/// let (counter, counts) = VisitCounter::new_with_counts();
+/// model.checker().visitor(counter).spawn_dfs().join();
+/// assert_eq!(counts.state(&()), 1);
+/// ```
+pub struct VisitCounter(Arc<VisitCounts>);
+impl<M> CheckerVisitor<M> for VisitCounter
+where
+    M: Model,
+    M::State: Hash,
+{
+    fn visit(&self, _: &M, path: Path<M::State, M::Action>) {
+        let states = path.into_states();
+        let mut fingerprints = states.iter().rev().take(2).map(fingerprint);
+        let dst = match fingerprints.next() {
+            Some(dst) => dst,
+            None => return,
+        };
+        *self.0.states.lock().unwrap().entry(dst).or_default() += 1;
+        if let Some(src) = fingerprints.next() {
+            *self
+                .0
+                .transitions
+                .lock()
+                .unwrap()
+                .entry((src, dst))
+                .or_default() += 1;
+        }
+    }
+}
+impl VisitCounter {
+    /// Instantiates a ([`VisitCounter`], [`VisitCounts`]) pair. The counts update as the checker
+    /// visits states.
+    pub fn new_with_counts() -> (Self, Arc<VisitCounts>) {
+        let counts = Arc::new(VisitCounts::default());
+        (Self(Arc::clone(&counts)), counts)
+    }
+}
+
+/// Visit counts recorded by a [`VisitCounter`].
+#[derive(Debug, Default)]
+pub struct VisitCounts {
+    states: Mutex<HashMap<Fingerprint, usize>>,
+    transitions: Mutex<HashMap<(Fingerprint, Fingerprint), usize>>,
+}
+impl VisitCounts {
+    /// The number of times a state was visited.
+    pub fn state<State: Hash>(&self, state: &State) -> usize {
+        self.state_by_fingerprint(fingerprint(state))
+    }
+
+    /// The number of times a state was visited immediately after another.
+    pub fn transition<State: Hash>(&self, src: &State, dst: &State) -> usize {
+        self.transition_by_fingerprint(fingerprint(src), fingerprint(dst))
+    }
+
+    pub(crate) fn state_by_fingerprint(&self, fingerprint: Fingerprint) -> usize {
+        self.states
+            .lock()
+            .unwrap()
+            .get(&fingerprint)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub(crate) fn transition_by_fingerprint(&self, src: Fingerprint, dst: Fingerprint) -> usize {
+        self.transitions
+            .lock()
+            .unwrap()
+            .get(&(src, dst))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// The highest (state, transition) visit counts, for scaling a heatmap.
+    #[cfg_attr(not(feature = "explorer"), allow(dead_code))]
+    pub(crate) fn max(&self) -> (usize, usize) {
+        let max_state = self.states.lock().unwrap().values().copied().max();
+        let max_transition = self.transitions.lock().unwrap().values().copied().max();
+        (max_state.unwrap_or(0), max_transition.unwrap_or(0))
+    }
+}
@@ -0,0 +1,627 @@
+//! A command line harness for checking, exploring, and spawning a system, so that a binary's
+//! `main` can be a single call to [`run`] rather than hand-rolled argument parsing.
+//!
+//! A [`System`] describes a model to check and optionally the actors to spawn on a real network.
+//! The caller supplies a factory that builds the system from positional [`Parameters`], and the
+//! harness provides the following subcommands:
+//!
+//! ```text
+//! check [--threads N] [--strategy bfs|dfs] [--target-state-count N] [--target-max-depth N] [--quiet true|false] [--format text|json] [PARAMETERS]
+//! explore [--threads N] [--address ADDRESS] [PARAMETERS]
+//! resolve FINGERPRINT_PATH [PARAMETERS]
+//! spawn [--port PORT] [PARAMETERS]
+//! ```
+//!
+//! Spawned actors listen on consecutive UDP ports of `127.0.0.1` starting from `--port`, and
+//! messages are serialized as JSON.
+//!
+//! # Configuration Files
+//!
+//! Every subcommand also accepts `--config FILE`, naming a TOML file that supplies defaults for
+//! options and parameters, so that experiment sweeps need neither recompilation nor long argument
+//! lists. The table named after the subcommand provides options (with underscores in place of
+//! dashes), and the `parameters` table provides parameters by name. Arguments on the command line
+//! take precedence.
+//!
+//! ```toml
+//! [check]
+//! threads = 8
+//! strategy = "dfs"
+//! target_state_count = 1_000_000
+//!
+//! [explore]
+//! address = "0.0.0.0:3000"
+//!
+//! [parameters]
+//! CLIENT_COUNT = 3
+//! NETWORK = "ordered"
+//! ```
+//!
+//! # Example
+//!
+//! ```no_run
+//! use stateright::actor::{ActorModel, Id, Network};
+//! use stateright::cli::{self, Parameters, System};
+//!
+//! struct Echo { count: usize, network: Network<()> }
+//!
+//! impl System for Echo {
+//!     type Model = ActorModel<(), (), ()>;
+//!     type Actor = ();
+//!
+//!     fn description(&self) -> String {
+//!         format!("{} echo actors", self.count)
+//!     }
+//!
+//!     fn model(&self) -> Self::Model {
+//!         ActorModel::new((), ())
+//!             .actors(self.actors(&Id::from))
+//!             .init_network(self.network.clone())
+//!     }
+//!
+//!     fn actors(&self, _id: &dyn Fn(usize) -> Id) -> Vec<Self::Actor> {
+//!         vec![(); self.count]
+//!     }
+//! }
+//!
+//! fn main() {
+//!     cli::run(|params: &mut Parameters| {
+//!         Ok(Echo {
+//!             count: params.next_or("COUNT", 2)?,
+//!             network: params.next_or("NETWORK", Network::new_ordered([]))?,
+//!         })
+//!     });
+//! }
+//! ```
+
+use crate::actor::{spawn, Actor, Id};
+use crate::report::ProgressReporter;
+use crate::{Checker, Model};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::io::{IsTerminal, Write};
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::str::FromStr;
+
+/// A system that can be checked, explored, and optionally spawned via [`run`].
+pub trait System {
+    /// The model to check or explore.
+    type Model: Model;
+
+    /// The type of actor to spawn. Use `()` if the system cannot be spawned.
+    type Actor: Actor;
+
+    /// Describes the system for status messages, such as "Paxos with 2 clients".
+    fn description(&self) -> String;
+
+    /// Builds the model to check or explore.
+    fn model(&self) -> Self::Model;
+
+    /// Returns the actors to spawn, given the [`Id`] of the actor at each index so that actors
+    /// can address one another. Defaults to no actors, in which case the `spawn` subcommand
+    /// reports an error.
+    fn actors(&self, _id: &dyn Fn(usize) -> Id) -> Vec<Self::Actor> {
+        Vec::new()
+    }
+}
+
+/// The positional parameters from which a [`System`] factory builds a system, such as a client
+/// count or [`Network`](crate::actor::Network).
+pub struct Parameters {
+    values: VecDeque<String>,
+    configured: BTreeMap<String, String>,
+    names: Vec<String>,
+}
+
+impl Parameters {
+    fn new(values: impl IntoIterator<Item = String>, configured: BTreeMap<String, String>) -> Self {
+        Parameters {
+            values: values.into_iter().collect(),
+            configured,
+            names: Vec::new(),
+        }
+    }
+
+    /// Parses the next positional parameter. If none remain, then parses the parameter of the
+    /// same `name` from the configuration file, if any, or returns `default` otherwise. The
+    /// `name` is also used for usage and error messages.
+    pub fn next_or<T>(&mut self, name: &str, default: T) -> Result<T, String>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.names.push(name.to_string());
+        let configured = self.configured.remove(name);
+        let value = match self.values.pop_front().or(configured) {
+            Some(value) => value,
+            None => return Ok(default),
+        };
+        value
+            .parse()
+            .map_err(|err| format!("Unable to parse {} {:?}: {}", name, value, err))
+    }
+}
+
+/// Runs the subcommand indicated by the process arguments, exiting with a usage message if they
+/// are invalid.
+pub fn run<S>(factory: impl Fn(&mut Parameters) -> Result<S, String>)
+where
+    S: System,
+    S::Model: Send + Sync + 'static,
+    <S::Model as Model>::Action: Debug + Send + Sync,
+    <S::Model as Model>::State: Debug + Hash + Send + Sync,
+    S::Actor: Send + 'static,
+    <S::Actor as Actor>::Msg: Debug + Serialize + DeserializeOwned,
+    <S::Actor as Actor>::State: Debug,
+{
+    let mut args = std::env::args();
+    let program = args
+        .next()
+        .and_then(|arg| {
+            std::path::Path::new(&arg)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .unwrap_or_else(|| "system".to_string());
+    let interactive = std::io::stdout().is_terminal();
+    if let Err(err) = execute(&program, args, factory, &mut std::io::stdout(), interactive) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+/// Runs the subcommand indicated by `args`, which excludes the program name, writing output to
+/// `out`. Returns a usage message if the arguments are invalid. See [`run`].
+pub fn run_with_args<S>(
+    program: &str,
+    args: impl IntoIterator<Item = String>,
+    factory: impl Fn(&mut Parameters) -> Result<S, String>,
+    out: &mut impl Write,
+) -> Result<(), String>
+where
+    S: System,
+    S::Model: Send + Sync + 'static,
+    <S::Model as Model>::Action: Debug + Send + Sync,
+    <S::Model as Model>::State: Debug + Hash + Send + Sync,
+    S::Actor: Send + 'static,
+    <S::Actor as Actor>::Msg: Debug + Serialize + DeserializeOwned,
+    <S::Actor as Actor>::State: Debug,
+{
+    execute(program, args, factory, out, false)
+}
+
+/// Runs a subcommand, rewriting the progress line in place if `interactive`.
+fn execute<S>(
+    program: &str,
+    args: impl IntoIterator<Item = String>,
+    factory: impl Fn(&mut Parameters) -> Result<S, String>,
+    out: &mut impl Write,
+    interactive: bool,
+) -> Result<(), String>
+where
+    S: System,
+    S::Model: Send + Sync + 'static,
+    <S::Model as Model>::Action: Debug + Send + Sync,
+    <S::Model as Model>::State: Debug + Hash + Send + Sync,
+    S::Actor: Send + 'static,
+    <S::Actor as Actor>::Msg: Debug + Serialize + DeserializeOwned,
+    <S::Actor as Actor>::State: Debug,
+{
+    let usage = |err: String| format!("{}\n\n{}", err, usage(program, &factory));
+    let mut args = Arguments::new(args).map_err(usage)?;
+    args.configure().map_err(usage)?;
+    let system = |args: Arguments| -> Result<S, String> {
+        let mut params = Parameters::new(args.positional, args.parameters);
+        let system = factory(&mut params)?;
+        if let Some(value) = params.values.pop_front() {
+            return Err(format!("Unexpected parameter: {}", value));
+        }
+        if let Some(name) = params.configured.keys().next() {
+            return Err(format!("Unknown parameter in configuration file: {}", name));
+        }
+        Ok(system)
+    };
+    let io_err = |err: std::io::Error| err.to_string();
+    match args.subcommand.as_deref() {
+        Some("check") => {
+            let threads = args.threads().map_err(usage)?;
+            let strategy = args.take("strategy").unwrap_or_else(|| "bfs".to_string());
+            if strategy != "bfs" && strategy != "dfs" {
+                return Err(usage(format!("Unknown strategy: {}", strategy)));
+            }
+            let target_state_count = args.parse("target-state-count").map_err(usage)?;
+            let target_max_depth = args.parse("target-max-depth").map_err(usage)?;
+            let quiet = args.parse("quiet").map_err(usage)?.unwrap_or(false);
+            let format = args.take("format").unwrap_or_else(|| "text".to_string());
+            if format != "text" && format != "json" {
+                return Err(usage(format!("Unknown format: {}", format)));
+            }
+            args.finish().map_err(usage)?;
+            let system = system(args).map_err(usage)?;
+            if format == "text" {
+                writeln!(out, "Model checking {}.", system.description()).map_err(io_err)?;
+            }
+            let mut builder = system.model().checker().threads(threads);
+            if let Some(count) = target_state_count {
+                builder = builder.target_state_count(count);
+            }
+            if let Some(depth) = target_max_depth {
+                builder = builder.target_max_depth(depth);
+            }
+            if format == "json" {
+                let result = if strategy == "bfs" {
+                    builder.spawn_bfs().join_and_report_json(out).map(|_| ())
+                } else {
+                    builder.spawn_dfs().join_and_report_json(out).map(|_| ())
+                };
+                return result.map_err(io_err);
+            }
+            let mut reporter = ProgressReporter::new(out).interactive(interactive);
+            if let Some(count) = target_state_count {
+                reporter = reporter.target_state_count(count);
+            }
+            if quiet {
+                reporter = reporter.quiet();
+            }
+            if strategy == "bfs" {
+                builder.spawn_bfs().report(&mut reporter);
+            } else {
+                builder.spawn_dfs().report(&mut reporter);
+            }
+            Ok(())
+        }
+        Some("explore") => {
+            let threads = args.threads().map_err(usage)?;
+            let address = args
+                .take("address")
+                .unwrap_or_else(|| "localhost:3000".to_string());
+            args.finish().map_err(usage)?;
+            let system = system(args).map_err(usage)?;
+            writeln!(
+                out,
+                "Exploring state space for {} on {}.",
+                system.description(),
+                address
+            )
+            .map_err(io_err)?;
+            system.model().checker().threads(threads).serve(address);
+            Ok(())
+        }
+        Some("resolve") => {
+            args.finish().map_err(usage)?;
+            if args.positional.is_empty() {
+                return Err(usage("Missing FINGERPRINT_PATH.".to_string()));
+            }
+            let path = args.positional.remove(0);
+            let system = system(args).map_err(usage)?;
+            system
+                .model()
+                .checker()
+                .explorer()
+                .resolve(&path, out)
+                .map_err(io_err)
+        }
+        Some("spawn") => {
+            let port: u16 = args.parse("port").map_err(usage)?.unwrap_or(3000);
+            args.finish().map_err(usage)?;
+            let system = system(args).map_err(usage)?;
+            let id = |index: usize| {
+                Id::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port + index as u16))
+            };
+            let actors = system.actors(&id);
+            if actors.is_empty() {
+                return Err(format!("{} cannot be spawned.", system.description()));
+            }
+            let ports = match actors.len() {
+                1 => format!("UDP port {}", port),
+                n => format!("UDP ports {} through {}", port, port as usize + n - 1),
+            };
+            writeln!(out, "Spawning {} on {}.", system.description(), ports).map_err(io_err)?;
+            let actors = actors.into_iter().enumerate().map(|(i, a)| (id(i), a));
+            spawn(
+                serde_json::to_vec,
+                |bytes| serde_json::from_slice(bytes),
+                actors.collect(),
+            )
+            .map_err(|err| format!("Actor failed: {:?}", err))
+        }
+        Some(subcommand) => Err(usage(format!("Unknown subcommand: {}", subcommand))),
+        None => Err(usage("Missing subcommand.".to_string())),
+    }
+}
+
+/// Describes the subcommands, naming the parameters that the factory reads.
+fn usage<S>(program: &str, factory: &impl Fn(&mut Parameters) -> Result<S, String>) -> String {
+    let mut params = Parameters::new([], BTreeMap::new());
+    let _ = factory(&mut params);
+    let params: String = params
+        .names
+        .iter()
+        .map(|name| format!(" [{}]", name))
+        .collect();
+    format!(
+        "USAGE:
+  {program} check [--threads N] [--strategy bfs|dfs] [--target-state-count N] [--target-max-depth N] [--quiet true|false] [--format text|json]{params}
+  {program} explore [--threads N] [--address ADDRESS]{params}
+  {program} resolve FINGERPRINT_PATH{params}
+  {program} spawn [--port PORT]{params}
+Each subcommand also accepts --config FILE, which names a TOML file of defaults.",
+        program = program,
+        params = params,
+    )
+}
+
+/// Command line arguments split into a subcommand, `--name value` options, and positional
+/// parameters, along with parameters from a configuration file.
+struct Arguments {
+    subcommand: Option<String>,
+    options: Vec<(String, String)>,
+    positional: Vec<String>,
+    parameters: BTreeMap<String, String>,
+}
+
+impl Arguments {
+    fn new(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut args = args.into_iter();
+        let subcommand = args.next();
+        let mut options = Vec::new();
+        let mut positional = Vec::new();
+        while let Some(arg) = args.next() {
+            let Some(option) = arg.strip_prefix("--") else {
+                positional.push(arg);
+                continue;
+            };
+            let (name, value) = match option.split_once('=') {
+                Some((name, value)) => (name.to_string(), value.to_string()),
+                None => match args.next() {
+                    Some(value) => (option.to_string(), value),
+                    None => return Err(format!("Missing value for --{}", option)),
+                },
+            };
+            options.push((name, value));
+        }
+        Ok(Arguments {
+            subcommand,
+            options,
+            positional,
+            parameters: BTreeMap::new(),
+        })
+    }
+
+    /// Reads the configuration file indicated by `--config`, if any, adding the options of the
+    /// table for the subcommand unless already specified.
+    fn configure(&mut self) -> Result<(), String> {
+        let Some(file) = self.take("config") else {
+            return Ok(());
+        };
+        let mut config: toml::Table = std::fs::read_to_string(&file)
+            .map_err(|err| err.to_string())
+            .and_then(|text| text.parse().map_err(|err: toml::de::Error| err.to_string()))
+            .map_err(|err| format!("Unable to read {}: {}", file, err.trim_end()))?;
+        let to_string = |table: &str, key: &str, value: toml::Value| match value {
+            toml::Value::String(value) => Ok(value),
+            toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => {
+                Ok(value.to_string())
+            }
+            _ => Err(format!("Unsupported value in {}: {}.{}", file, table, key)),
+        };
+        let table = |config: &mut toml::Table, name: &str| match config.remove(name) {
+            None => Ok(toml::Table::new()),
+            Some(toml::Value::Table(table)) => Ok(table),
+            Some(_) => Err(format!("Expected a table in {}: {}", file, name)),
+        };
+        if let Some(subcommand) = self.subcommand.clone() {
+            for (key, value) in table(&mut config, &subcommand)? {
+                let name = key.replace('_', "-");
+                if !self.options.iter().any(|(n, _)| *n == name) {
+                    let value = to_string(&subcommand, &key, value)?;
+                    self.options.push((name, value));
+                }
+            }
+        }
+        for (key, value) in table(&mut config, "parameters")? {
+            let value = to_string("parameters", &key, value)?;
+            self.parameters.insert(key, value);
+        }
+        for name in config.keys() {
+            if !["check", "explore", "resolve", "spawn"].contains(&name.as_str()) {
+                return Err(format!("Unknown table in {}: {}", file, name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the last value of an option.
+    fn take(&mut self, name: &str) -> Option<String> {
+        let mut value = None;
+        self.options.retain(|(n, v)| {
+            if n == name {
+                value = Some(v.clone());
+                false
+            } else {
+                true
+            }
+        });
+        value
+    }
+
+    fn parse<T>(&mut self, name: &str) -> Result<Option<T>, String>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.take(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|err| format!("Unable to parse --{} {:?}: {}", name, value, err))
+            })
+            .transpose()
+    }
+
+    fn threads(&mut self) -> Result<usize, String> {
+        Ok(self
+            .parse("threads")?
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get())))
+    }
+
+    /// Errors if any options were not recognized.
+    fn finish(&self) -> Result<(), String> {
+        match self.options.first() {
+            None => Ok(()),
+            Some((name, _)) => Err(format!("Unknown option: --{}", name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::actor::actor_test_util::ping_pong::*;
+    use crate::actor::{ActorModel, Network};
+
+    struct PingPong {
+        max_nat: u32,
+        network: Network<PingPongMsg>,
+    }
+
+    impl System for PingPong {
+        type Model = ActorModel<PingPongActor, PingPongCfg, PingPongHistory>;
+        type Actor = ();
+
+        fn description(&self) -> String {
+            format!("ping-pong up to {}", self.max_nat)
+        }
+
+        fn model(&self) -> Self::Model {
+            PingPongCfg {
+                maintains_history: false,
+                max_nat: self.max_nat,
+            }
+            .into_model()
+            .init_network(self.network.clone())
+        }
+    }
+
+    fn factory(params: &mut Parameters) -> Result<PingPong, String> {
+        Ok(PingPong {
+            max_nat: params.next_or("MAX_NAT", 5)?,
+            network: params.next_or("NETWORK", Network::new_unordered_duplicating([]))?,
+        })
+    }
+
+    fn run(args: &[&str]) -> Result<String, String> {
+        let mut out = Vec::new();
+        run_with_args(
+            "ping-pong",
+            args.iter().map(|arg| arg.to_string()),
+            factory,
+            &mut out,
+        )?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn can_check() {
+        let out = run(&["check", "--threads", "1", "3", "ordered"]).unwrap();
+        assert!(
+            out.starts_with("Model checking ping-pong up to 3.\n"),
+            "{}",
+            out
+        );
+        assert!(out.contains("Done. states=7, unique=7"), "{}", out);
+
+        let out = run(&["check", "--strategy=dfs", "--target-max-depth", "2"]).unwrap();
+        assert!(out.contains("depth=2,"), "{}", out);
+
+        let out = run(&["check", "--quiet", "true", "--target-state-count", "4"]).unwrap();
+        assert!(out.contains("\nDone. [####"), "{}", out);
+        assert!(!out.contains("Checking."), "{}", out);
+
+        let out = run(&["check", "--format", "json", "3"]).unwrap();
+        let report: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(report["stats"]["unique_states"], 7);
+    }
+
+    #[test]
+    fn reads_config_file() {
+        let path = std::env::temp_dir().join(format!("stateright-cli-{}.toml", std::process::id()));
+        let config = path.to_str().unwrap();
+        std::fs::write(
+            &path,
+            "[check]\nthreads = 1\nstrategy = \"dfs\"\ntarget_max_depth = 2\n\n\
+             [parameters]\nNETWORK = \"ordered\"\nMAX_NAT = 3\n",
+        )
+        .unwrap();
+        let out = run(&["check", "--config", config]).unwrap();
+        assert!(
+            out.starts_with("Model checking ping-pong up to 3.\n"),
+            "{}",
+            out
+        );
+        assert!(out.contains("depth=2,"), "{}", out);
+
+        // Command line arguments take precedence.
+        let out = run(&["check", "--config", config, "--target-max-depth=3", "4"]).unwrap();
+        assert!(
+            out.starts_with("Model checking ping-pong up to 4.\n"),
+            "{}",
+            out
+        );
+        assert!(out.contains("depth=3,"), "{}", out);
+
+        std::fs::write(&path, "[parameters]\nMAX_NAT = 3\nMAX_NATS = 4\n").unwrap();
+        assert!(run(&["check", "--config", config])
+            .unwrap_err()
+            .starts_with("Unknown parameter in configuration file: MAX_NATS\n"));
+        std::fs::write(&path, "[chek]\nthreads = 1\n").unwrap();
+        assert!(run(&["check", "--config", config])
+            .unwrap_err()
+            .starts_with(&format!("Unknown table in {}: chek\n", config)));
+        std::fs::write(&path, "[check]\nthread = 1\n").unwrap();
+        assert!(run(&["check", "--config", config])
+            .unwrap_err()
+            .starts_with("Unknown option: --thread\n"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reports_usage() {
+        let usage = "USAGE:
+  ping-pong check [--threads N] [--strategy bfs|dfs] [--target-state-count N] [--target-max-depth N] [--quiet true|false] [--format text|json] [MAX_NAT] [NETWORK]
+  ping-pong explore [--threads N] [--address ADDRESS] [MAX_NAT] [NETWORK]
+  ping-pong resolve FINGERPRINT_PATH [MAX_NAT] [NETWORK]
+  ping-pong spawn [--port PORT] [MAX_NAT] [NETWORK]
+Each subcommand also accepts --config FILE, which names a TOML file of defaults.";
+        assert_eq!(
+            run(&[]).unwrap_err(),
+            format!("Missing subcommand.\n\n{}", usage)
+        );
+        assert_eq!(
+            run(&["check", "--thread", "1"]).unwrap_err(),
+            format!("Unknown option: --thread\n\n{}", usage)
+        );
+        assert_eq!(
+            run(&["check", "--threads"]).unwrap_err(),
+            format!("Missing value for --threads\n\n{}", usage)
+        );
+        assert_eq!(
+            run(&["check", "x"]).unwrap_err(),
+            format!(
+                "Unable to parse MAX_NAT \"x\": invalid digit found in string\n\n{}",
+                usage
+            )
+        );
+        assert_eq!(
+            run(&["check", "1", "ordered", "2"]).unwrap_err(),
+            format!("Unexpected parameter: 2\n\n{}", usage)
+        );
+        assert_eq!(
+            run(&["spawn"]).unwrap_err(),
+            "ping-pong up to 5 cannot be spawned."
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! Property-based testing of a model across generated configurations, built on
+//! [proptest](https://docs.rs/proptest). Requires the `proptest` feature.
+//!
+//! A checker exhaustively explores one configuration of a system, such as three servers
+//! communicating over an unordered network. [`check_configs`] instead generates many
+//! configurations (actor counts, boundaries, network options, and so on) from a [`Strategy`],
+//! checks each, and shrinks any failure to the simplest configuration that still fails. This
+//! module also implements [`Arbitrary`] for the network options of an
+//! [`ActorModel`](crate::actor::ActorModel).
+//!
+//! # Example
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use proptest::test_runner::{Config, TestError};
+//! use stateright::*;
+//!
+//! /// Counts up to a limit.
+//! struct Counter { limit: u8 }
+//! impl Model for Counter {
+//!     type State = u8;
+//!     type Action = ();
+//!     fn init_states(&self) -> Vec<u8> { vec![0] }
+//!     fn actions(&self, state: &u8, actions: &mut Vec<()>) {
+//!         if *state < self.limit { actions.push(()); }
+//!     }
+//!     fn next_state(&self, state: &u8, _: ()) -> Option<u8> { Some(state + 1) }
+//!     fn properties(&self) -> Vec<Property<Self>> {
+//!         vec![Property::always("below 10", |_, state| *state < 10)]
+//!     }
+//! }
+//!
+//! let result = stateright::configs::check_configs(
+//!     Config::with_cases(64),
+//!     0..100_u8,
+//!     |&limit| Counter { limit }.checker());
+//! match result {
+//!     Err(TestError::Fail(_reason, limit)) => assert_eq!(limit, 10),
+//!     _ => panic!("expected a failing configuration"),
+//! }
+//! ```
+
+use crate::actor::{LossyNetwork, Network};
+use crate::{Checker, CheckerBuilder, Expectation, Model};
+use ::proptest::prelude::*;
+use ::proptest::test_runner::{Config, TestError, TestRunner};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// Generates whether a network loses messages, shrinking toward [`LossyNetwork::No`].
+impl Arbitrary for LossyNetwork {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        prop_oneof![Just(LossyNetwork::No), Just(LossyNetwork::Yes)].boxed()
+    }
+}
+
+/// Generates each kind of empty network, shrinking toward
+/// [`Network::new_unordered_duplicating`], which is the [`ActorModel`] default.
+///
+/// [`ActorModel`]: crate::actor::ActorModel
+impl<Msg> Arbitrary for Network<Msg>
+where
+    Msg: Clone + Debug + Eq + Hash + 'static,
+{
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        prop_oneof![
+            Just(Network::new_unordered_duplicating([])),
+            Just(Network::new_unordered_nonduplicating([])),
+            Just(Network::new_ordered([])),
+        ]
+        .boxed()
+    }
+}
+
+/// Checks the model built for each configuration generated by a [`Strategy`], using
+/// [`CheckerBuilder::spawn_bfs`]. The checker builder is supplied by the caller so that it can
+/// be customized, for instance with [`CheckerBuilder::target_max_depth`].
+///
+/// A configuration fails if an `always`, `eventually`, `within`, or `terminal` property has a
+/// counterexample or if a `sometimes` property has no example. The first failure is shrunk to
+/// the simplest configuration that still fails, which is returned in [`TestError::Fail`] along
+/// with a description of the failing properties.
+pub fn check_configs<S, M>(
+    config: Config,
+    strategy: S,
+    checker: impl Fn(&S::Value) -> CheckerBuilder<M>,
+) -> Result<(), TestError<S::Value>>
+where
+    S: Strategy,
+    M: Model + Send + Sync + 'static,
+    M::State: Hash + Send + Sync + 'static,
+    M::Action: Debug,
+{
+    TestRunner::new(config).run(&strategy, |value| {
+        let checker = checker(&value).spawn_bfs().join();
+        let failures = failures(&checker);
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(TestCaseError::fail(failures.join("\n")))
+        }
+    })
+}
+
+/// Describes each property that fails, as with [`Checker::assert_properties`].
+fn failures<M, C>(checker: &C) -> Vec<String>
+where
+    M: Model,
+    M::Action: Debug,
+    C: Checker<M>,
+{
+    let mut failures = Vec::new();
+    for p in checker.model().properties() {
+        match (p.expectation, checker.discovery(&p.name)) {
+            (Expectation::Sometimes, Some(_)) => {}
+            (Expectation::Sometimes, None) => {
+                if checker.is_done() {
+                    failures.push(format!("Example for \"{}\" not found.", p.name));
+                } else {
+                    failures.push(format!(
+                        "Example for \"{}\" not found, but model checking is incomplete.",
+                        p.name
+                    ));
+                }
+            }
+            (_, None) => {}
+            (_, Some(path)) => {
+                failures.push(format!(
+                    "Counterexample for \"{}\" found. {}",
+                    p.name,
+                    path.format(checker.model())
+                ));
+            }
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::linear_equation_solver::*;
+    use crate::Property;
+    use ::proptest::strategy::ValueTree;
+
+    /// Counts up to a limit.
+    struct Counter {
+        limit: u8,
+    }
+
+    impl Model for Counter {
+        type State = u8;
+        type Action = ();
+
+        fn init_states(&self) -> Vec<Self::State> {
+            vec![0]
+        }
+
+        fn actions(&self, state: &Self::State, actions: &mut Vec<Self::Action>) {
+            if *state < self.limit {
+                actions.push(());
+            }
+        }
+
+        fn next_state(&self, state: &Self::State, _action: Self::Action) -> Option<Self::State> {
+            Some(state + 1)
+        }
+
+        fn properties(&self) -> Vec<Property<Self>> {
+            vec![Property::always("below 3", |_, state| *state < 3)]
+        }
+    }
+
+    #[test]
+    fn shrinks_to_simplest_failing_config() {
+        let result = check_configs(Config::with_cases(32), 0..100_u8, |&limit| {
+            Counter { limit }.checker()
+        });
+        match result {
+            Err(TestError::Fail(reason, limit)) => {
+                assert_eq!(limit, 3);
+                assert_eq!(
+                    reason.message(),
+                    "Counterexample for \"below 3\" found. Path[3]:\n- ()\n- ()\n- ()\n"
+                );
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn reports_missing_examples() {
+        // With `a = b = 0` the equation is only solvable if `c = 0`.
+        let result = check_configs(Config::with_cases(8), 1..=255_u8, |&c| {
+            LinearEquation { a: 0, b: 0, c }.checker()
+        });
+        match result {
+            Err(TestError::Fail(reason, c)) => {
+                assert_eq!(c, 1);
+                assert_eq!(reason.message(), "Example for \"solvable\" not found.");
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn passes_if_all_configs_pass() {
+        assert_eq!(
+            check_configs(Config::with_cases(8), 1..=255_u8, |&a| {
+                LinearEquation { a, b: 1, c: 7 }.checker()
+            }),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn generates_network_options() {
+        let mut runner = TestRunner::deterministic();
+        let strategy = (any::<Network<u8>>(), any::<LossyNetwork>());
+        let mut networks = Vec::new();
+        let mut lossy_networks = Vec::new();
+        for _ in 0..64 {
+            let (network, lossy) = strategy.new_tree(&mut runner).unwrap().current();
+            if !networks.contains(&network) {
+                networks.push(network);
+            }
+            if !lossy_networks.contains(&lossy) {
+                lossy_networks.push(lossy);
+            }
+        }
+        assert_eq!(networks.len(), 3);
+        assert_eq!(lossy_networks.len(), 2);
+
+        // Shrinks toward the defaults.
+        let mut tree = strategy.new_tree(&mut runner).unwrap();
+        while tree.simplify() {}
+        assert_eq!(
+            tree.current(),
+            (Network::new_unordered_duplicating([]), LossyNetwork::No)
+        );
+    }
+}
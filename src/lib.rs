@@ -115,6 +115,17 @@
 //!     ]);
 //! ```
 //!
+//! # Cargo Features
+//!
+//! - `explorer` (default): the web-based Explorer served by [`CheckerBuilder::serve`].
+//! - `spawn` (default): the UDP runtime for [spawning][spawn] actors, along with the
+//!   `cli` module.
+//! - `proptest`: checking generated configurations via the `configs` module.
+//! - `schemars`: JSON Schemas for message and state types via `util::json_schema`.
+//!
+//! Disabling the default features yields a build with no networking dependencies, which
+//! is suitable for embedding the checker in other tools.
+//!
 //! # What to Read Next
 //!
 //! The [`actor`] and [`semantics`] submodules will be of particular interest to
@@ -131,19 +142,31 @@
 //! [`sometimes`]: Property::sometimes
 //! [spawn]: actor::spawn()
 
+// Allows code generated by `stateright-derive`, which refers to `::stateright`, to be used within
+// this crate.
+extern crate self as stateright;
+
 #[warn(anonymous_parameters)]
 #[warn(missing_docs)]
 mod checker;
 mod job_market;
 pub mod report;
+use std::borrow::Cow;
 use std::fmt::Debug;
 use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 
 #[cfg(test)]
 mod test_util;
 
 pub mod actor;
 pub use checker::*;
+pub mod assume_guarantee;
+#[cfg(all(feature = "explorer", feature = "spawn"))]
+pub mod cli;
+#[cfg(feature = "proptest")]
+pub mod configs;
+pub mod product;
 pub mod semantics;
 pub mod util;
 
@@ -170,7 +193,8 @@ pub trait Model: Sized {
     /// does not change the state.
     fn next_state(&self, last_state: &Self::State, action: Self::Action) -> Option<Self::State>;
 
-    /// Converts an action of this model to a more intuitive representation (e.g. for Explorer).
+    /// Converts an action of this model to a more intuitive representation (e.g. for Explorer,
+    /// [reports](Checker::report), and failed assertions).
     fn format_action(&self, action: &Self::Action) -> String
     where
         Self::Action: Debug,
@@ -178,6 +202,12 @@ pub trait Model: Sized {
         format!("{:?}", action)
     }
 
+    /// Converts a state of this model to a more intuitive representation (e.g. for Explorer and
+    /// failed assertions). [`None`] indicates that the [`Debug`] representation should be used.
+    fn format_state(&self, _state: &Self::State) -> Option<String> {
+        None
+    }
+
     /// Converts a step of this model to a more intuitive representation (e.g. for Explorer).
     fn format_step(&self, last_state: &Self::State, action: Self::Action) -> Option<String>
     where
@@ -227,11 +257,11 @@ pub trait Model: Sized {
     }
 
     /// Looks up a property by name. Panics if the property does not exist.
-    fn property(&self, name: &'static str) -> Property<Self> {
+    fn property(&self, name: &str) -> Property<Self> {
         if let Some(p) = self.properties().into_iter().find(|p| p.name == name) {
             p
         } else {
-            let available: Vec<_> = self.properties().iter().map(|p| p.name).collect();
+            let available: Vec<_> = self.properties().into_iter().map(|p| p.name).collect();
             panic!(
                 "Unknown property. requested={}, available={:?}",
                 name, available
@@ -244,11 +274,12 @@ pub trait Model: Sized {
         true
     }
 
-    /// Instantiates a [`CheckerBuilder`] for this model.
+    /// Instantiates a [`CheckerBuilder`] for this model. The multithreaded checkers additionally
+    /// require the model and its states to be [`Send`] and [`Sync`], unlike
+    /// [`CheckerBuilder::incremental_bfs`].
     fn checker(self) -> CheckerBuilder<Self>
     where
-        Self: Send + Sync + 'static,
-        Self::State: Hash + Send + Sync,
+        Self::State: Hash,
     {
         CheckerBuilder::new(self)
     }
@@ -258,21 +289,65 @@ pub trait Model: Sized {
 /// checker would find an example) or "an epoch *always* has at most one leader" (for which the
 /// model checker would find a counterexample) or "a proposal is *eventually* accepted" (for
 /// which the model checker would find a counterexample path leading from the initial state
-/// through to a terminal state).
+/// through to a terminal state) or "a leader is elected *within* 5 steps of an election
+/// starting" (for which the model checker would find a counterexample path along which the
+/// deadline passes).
+///
+/// Properties are built with constructors such as [`Property::always`] rather than struct
+/// literals, so that further kinds of properties can be added without breaking models.
 pub struct Property<M: Model> {
     pub expectation: Expectation,
-    pub name: &'static str,
-    pub condition: fn(&M, &M::State) -> bool,
+    pub name: Cow<'static, str>,
+    pub condition: Condition<M>,
+    /// The trigger of an [`Expectation::Within`] or [`Expectation::LeadsTo`] property. See
+    /// [`Property::within`] and [`Property::leads_to`].
+    pub(crate) trigger: Option<Trigger<M>>,
+    /// The name of another property that must hold for this one to be meaningful. See
+    /// [`Property::assuming`].
+    pub(crate) assumption: Option<Cow<'static, str>>,
+    /// The named conditions of an [`Expectation::Always`] property whose condition is their
+    /// conjunction. See [`Property::all`].
+    pub(crate) conjuncts: Vec<(Cow<'static, str>, Condition<M>)>,
+    /// The condition on steps of an [`Expectation::Always`] property that observes transitions.
+    /// See [`Property::always_transition`].
+    pub(crate) transition: Option<TransitionCondition<M>>,
 }
+
+/// Indicates when a [`Property::within`] or [`Property::leads_to`] obligation begins and how many
+/// steps it allows, which only bounds `within` obligations.
+pub(crate) struct Trigger<M: Model> {
+    pub(crate) condition: Condition<M>,
+    pub(crate) steps: usize,
+}
+
+/// The condition of a [`Property`]. A closure rather than a function pointer so that a property
+/// can be instantiated for each actor or value over which it is quantified.
+pub type Condition<M> = Arc<dyn Fn(&M, &<M as Model>::State) -> bool + Send + Sync>;
+
+/// The condition of a [`Property::always_transition`] property, which observes a previous state,
+/// an action, and the resulting state.
+pub type TransitionCondition<M> = Arc<
+    dyn Fn(&M, &<M as Model>::State, &<M as Model>::Action, &<M as Model>::State) -> bool
+        + Send
+        + Sync,
+>;
+
 impl<M: Model> Property<M> {
     /// An invariant that defines a [safety
     /// property](https://en.wikipedia.org/wiki/Safety_property). The model checker will try to
     /// discover a counterexample.
-    pub fn always(name: &'static str, condition: fn(&M, &M::State) -> bool) -> Property<M> {
+    pub fn always(
+        name: impl Into<Cow<'static, str>>,
+        condition: impl Fn(&M, &M::State) -> bool + Send + Sync + 'static,
+    ) -> Property<M> {
         Property {
             expectation: Expectation::Always,
-            name,
-            condition,
+            name: name.into(),
+            condition: Arc::new(condition),
+            trigger: None,
+            assumption: None,
+            conjuncts: Vec::new(),
+            transition: None,
         }
     }
 
@@ -286,21 +361,500 @@ impl<M: Model> Property<M> {
     /// ending in a cycle is not viewed as _terminating_ in that cycle, as the checker does not
     /// differentiate cycles from DAG joins, and so an `eventually` property that has not been met
     /// by the cycle-closing edge will ignored -- a false negative.
-    pub fn eventually(name: &'static str, condition: fn(&M, &M::State) -> bool) -> Property<M> {
+    pub fn eventually(
+        name: impl Into<Cow<'static, str>>,
+        condition: impl Fn(&M, &M::State) -> bool + Send + Sync + 'static,
+    ) -> Property<M> {
         Property {
             expectation: Expectation::Eventually,
-            name,
-            condition,
+            name: name.into(),
+            condition: Arc::new(condition),
+            trigger: None,
+            assumption: None,
+            conjuncts: Vec::new(),
+            transition: None,
+        }
+    }
+
+    /// An invariant over the steps of the model, observing each previous state, action, and
+    /// resulting state, so that specs such as "a node never sends `AckRecord` before receiving
+    /// `Record`" can be written directly against actions. The model checker will try to discover
+    /// a counterexample path whose last step violates the condition.
+    ///
+    /// The condition is evaluated for every step that remains within the
+    /// [boundary](Model::within_boundary) as the checker takes it. As [`Model::next_state`]
+    /// consumes each action, the checker generates each state's actions a second time for the
+    /// condition to observe, until a counterexample is found.
+    pub fn always_transition(
+        name: impl Into<Cow<'static, str>>,
+        condition: impl Fn(&M, &M::State, &M::Action, &M::State) -> bool + Send + Sync + 'static,
+    ) -> Property<M>
+    where
+        M: 'static,
+    {
+        let transition: TransitionCondition<M> = Arc::new(condition);
+        Property {
+            expectation: Expectation::Always,
+            name: name.into(),
+            condition: {
+                let transition = Arc::clone(&transition);
+                Arc::new(move |model, state| {
+                    model.next_steps(state).iter().all(|(action, next_state)| {
+                        !model.within_boundary(next_state)
+                            || transition(model, state, action, next_state)
+                    })
+                })
+            },
+            trigger: None,
+            assumption: None,
+            conjuncts: Vec::new(),
+            transition: Some(transition),
         }
     }
 
     /// Something that should be possible in the model. The model checker will try to discover an
     /// example.
-    pub fn sometimes(name: &'static str, condition: fn(&M, &M::State) -> bool) -> Property<M> {
+    pub fn sometimes(
+        name: impl Into<Cow<'static, str>>,
+        condition: impl Fn(&M, &M::State) -> bool + Send + Sync + 'static,
+    ) -> Property<M> {
         Property {
             expectation: Expectation::Sometimes,
+            name: name.into(),
+            condition: Arc::new(condition),
+            trigger: None,
+            assumption: None,
+            conjuncts: Vec::new(),
+            transition: None,
+        }
+    }
+
+    /// A postcondition that is only evaluated on terminal states: those with no successors, or
+    /// whose successors all lie outside the [boundary](Model::within_boundary). This is what
+    /// specs such as "all committed values are equal" usually want, and it avoids evaluating the
+    /// condition on intermediate states. The model checker will try to discover a counterexample
+    /// path ending in a terminal state that violates the condition.
+    ///
+    /// As with `eventually` properties, a path ending in a cycle is not viewed as terminating, so
+    /// these properties are only checked on acyclic paths.
+    pub fn terminal(
+        name: impl Into<Cow<'static, str>>,
+        condition: impl Fn(&M, &M::State) -> bool + Send + Sync + 'static,
+    ) -> Property<M> {
+        Property {
+            expectation: Expectation::Terminal,
+            name: name.into(),
+            condition: Arc::new(condition),
+            trigger: None,
+            assumption: None,
+            conjuncts: Vec::new(),
+            transition: None,
+        }
+    }
+
+    /// A bounded-response property: whenever `trigger` holds, `response` must hold in that state
+    /// or within `steps` subsequent steps along every path. The model checker will try to
+    /// discover a counterexample path along which the deadline passes, which includes a path
+    /// that terminates while an obligation is pending.
+    ///
+    /// Unlike `eventually` properties, a counterexample never depends on a path reaching a
+    /// terminal state unless the path is shorter than the bound, so these properties remain
+    /// checkable in models with cycles (subject to the same caveat about revisited states).
+    pub fn within(
+        name: impl Into<Cow<'static, str>>,
+        steps: usize,
+        trigger: impl Fn(&M, &M::State) -> bool + Send + Sync + 'static,
+        response: impl Fn(&M, &M::State) -> bool + Send + Sync + 'static,
+    ) -> Property<M> {
+        Property {
+            expectation: Expectation::Within,
+            name: name.into(),
+            condition: Arc::new(response),
+            trigger: Some(Trigger {
+                condition: Arc::new(trigger),
+                steps,
+            }),
+            assumption: None,
+            conjuncts: Vec::new(),
+            transition: None,
+        }
+    }
+
+    /// A response property: whenever `trigger` holds, `response` must eventually hold in that
+    /// state or a subsequent one along every path, as in the temporal formula
+    /// `always (trigger -> eventually response)`. The model checker will try to discover a
+    /// counterexample path that terminates while an obligation is pending, or that returns to a
+    /// state visited since the obligation began, as the path can then repeat that cycle without
+    /// the response ever holding.
+    ///
+    /// Like `eventually` properties, a cycle is only recognized when the search returns to a
+    /// state along the path it is exploring, not when it reaches a state that an earlier branch
+    /// of the search already explored.
+    pub fn leads_to(
+        name: impl Into<Cow<'static, str>>,
+        trigger: impl Fn(&M, &M::State) -> bool + Send + Sync + 'static,
+        response: impl Fn(&M, &M::State) -> bool + Send + Sync + 'static,
+    ) -> Property<M> {
+        Property {
+            expectation: Expectation::LeadsTo,
+            name: name.into(),
+            condition: Arc::new(response),
+            trigger: Some(Trigger {
+                condition: Arc::new(trigger),
+                steps: 0,
+            }),
+            assumption: None,
+            conjuncts: Vec::new(),
+            transition: None,
+        }
+    }
+
+    /// An invariant that is the conjunction of the conditions of several named `always`
+    /// properties. Checking is equivalent to an `always` property whose condition `&&`s the
+    /// conjuncts, but a counterexample also names the first conjunct it violates (see
+    /// [`Property::violated_conjunct`]), and the Explorer shows whether each conjunct holds in the
+    /// selected state.
+    ///
+    /// ```
+    /// # use stateright::*;
+    /// # struct Counter;
+    /// # impl Model for Counter {
+    /// #     type State = u8;
+    /// #     type Action = ();
+    /// #     fn init_states(&self) -> Vec<u8> { vec![0] }
+    /// #     fn actions(&self, _: &u8, _: &mut Vec<()>) {}
+    /// #     fn next_state(&self, _: &u8, _: ()) -> Option<u8> { None }
+    /// fn properties(&self) -> Vec<Property<Self>> {
+    ///     vec![Property::all(
+    ///         "well formed",
+    ///         vec![
+    ///             Property::always("small", |_, n| *n < 10),
+    ///             Property::always("even", |_, n| n % 2 == 0),
+    ///         ],
+    ///     )]
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// Panics if a conjunct is not an `always` property.
+    pub fn all(name: impl Into<Cow<'static, str>>, conjuncts: Vec<Property<M>>) -> Property<M>
+    where
+        M: 'static,
+    {
+        let name = name.into();
+        let conjuncts: Vec<_> = conjuncts
+            .into_iter()
+            .map(|conjunct| {
+                assert_eq!(
+                    conjunct.expectation,
+                    Expectation::Always,
+                    "Conjunct \"{}\" of \"{}\" is not an `always` property",
+                    conjunct.name,
+                    name
+                );
+                (conjunct.name, conjunct.condition)
+            })
+            .collect();
+        let conditions: Vec<_> = conjuncts.iter().map(|(_, c)| Arc::clone(c)).collect();
+        Property {
+            expectation: Expectation::Always,
             name,
+            condition: Arc::new(move |model, state| conditions.iter().all(|c| c(model, state))),
+            trigger: None,
+            assumption: None,
+            conjuncts,
+            transition: None,
+        }
+    }
+
+    /// Returns the name of the first conjunct of a [`Property::all`] property that does not hold
+    /// for a state, or `None` if they all hold or the property has no conjuncts.
+    pub fn violated_conjunct(&self, model: &M, state: &M::State) -> Option<Cow<'static, str>> {
+        self.conjuncts
+            .iter()
+            .find(|(_, condition)| !condition(model, state))
+            .map(|(name, _)| name.clone())
+    }
+
+    /// Indicates whether this property holds on a path, or `None` if the path does not settle it.
+    /// An `always` property must hold for every step, whereas a `sometimes` or `eventually`
+    /// property holds once it holds for some state. A path never settles a `within`, `leads_to`,
+    /// or `terminal` property, nor a violation of an `eventually` property, as those depend on how
+    /// the path continues.
+    pub(crate) fn holds_on_path(&self, model: &M, path: Path<M::State, M::Action>) -> Option<bool> {
+        let steps = path.into_vec();
+        match self.expectation {
+            Expectation::Always => Some(match &self.transition {
+                Some(transition) => steps.windows(2).all(|window| match window {
+                    [(state, Some(action)), (next_state, _)] => {
+                        transition(model, state, action, next_state)
+                    }
+                    _ => true,
+                }),
+                None => steps
+                    .iter()
+                    .all(|(state, _)| (self.condition)(model, state)),
+            }),
+            Expectation::Sometimes => Some(
+                steps
+                    .iter()
+                    .any(|(state, _)| (self.condition)(model, state)),
+            ),
+            Expectation::Eventually => steps
+                .iter()
+                .any(|(state, _)| (self.condition)(model, state))
+                .then_some(true),
+            Expectation::Within | Expectation::LeadsTo | Expectation::Terminal => None,
+        }
+    }
+
+    /// Combines this property with another whose condition must also hold, naming the result
+    /// after both (for example `"(a && b)"`). The result keeps the expectation and
+    /// [assumption](Property::assuming) of the operands, and for `always` properties a
+    /// counterexample [names](Property::violated_conjunct) the operand that it violates.
+    ///
+    /// Panics if either property is a [`Property::within`] or [`Property::leads_to`] property, or
+    /// if the properties differ in expectation or assumption.
+    pub fn and(self, other: Property<M>) -> Property<M>
+    where
+        M: 'static,
+    {
+        let conjuncts = if self.expectation == Expectation::Always {
+            let mut conjuncts = self.conjuncts_or_self();
+            conjuncts.extend(other.conjuncts_or_self());
+            conjuncts
+        } else {
+            Vec::new()
+        };
+        let (left, right) = (Arc::clone(&self.condition), Arc::clone(&other.condition));
+        Property {
+            conjuncts,
+            ..self.compose(
+                format!("({} && {})", self.name, other.name),
+                Some(&other),
+                move |model, state| left(model, state) && right(model, state),
+            )
+        }
+    }
+
+    /// Combines this property with another, either of whose conditions must hold. See
+    /// [`Property::and`].
+    pub fn or(self, other: Property<M>) -> Property<M>
+    where
+        M: 'static,
+    {
+        let (left, right) = (Arc::clone(&self.condition), Arc::clone(&other.condition));
+        self.compose(
+            format!("({} || {})", self.name, other.name),
+            Some(&other),
+            move |model, state| left(model, state) || right(model, state),
+        )
+    }
+
+    /// Combines this property with another whose condition must hold wherever this property's
+    /// condition holds. See [`Property::and`].
+    pub fn implies(self, other: Property<M>) -> Property<M>
+    where
+        M: 'static,
+    {
+        let (left, right) = (Arc::clone(&self.condition), Arc::clone(&other.condition));
+        self.compose(
+            format!("({} => {})", self.name, other.name),
+            Some(&other),
+            move |model, state| !left(model, state) || right(model, state),
+        )
+    }
+
+    /// Negates this property's condition, naming the result after it (for example `"!(a)"`). See
+    /// [`Property::and`].
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Property<M>
+    where
+        M: 'static,
+    {
+        let name = if self.name.starts_with('(') {
+            format!("!{}", self.name)
+        } else {
+            format!("!({})", self.name)
+        };
+        let condition = Arc::clone(&self.condition);
+        self.compose(name, None, move |model, state| !condition(model, state))
+    }
+
+    fn compose(
+        &self,
+        name: String,
+        other: Option<&Property<M>>,
+        condition: impl Fn(&M, &M::State) -> bool + Send + Sync + 'static,
+    ) -> Property<M> {
+        assert!(
+            self.trigger.is_none() && other.and_then(|other| other.trigger.as_ref()).is_none(),
+            "Unable to compose \"{}\" as `within` and `leads_to` properties cannot be composed",
+            name
+        );
+        if let Some(other) = other {
+            assert!(
+                self.expectation == other.expectation,
+                "Unable to compose \"{}\" as the expectations differ ({:?} and {:?})",
+                name,
+                self.expectation,
+                other.expectation
+            );
+            assert!(
+                self.assumption == other.assumption,
+                "Unable to compose \"{}\" as the assumptions differ ({:?} and {:?})",
+                name,
+                self.assumption,
+                other.assumption
+            );
+        }
+        Property {
+            expectation: self.expectation.clone(),
+            name: name.into(),
+            condition: Arc::new(condition),
+            trigger: None,
+            assumption: self.assumption.clone(),
+            conjuncts: Vec::new(),
+            transition: None,
+        }
+    }
+
+    fn conjuncts_or_self(&self) -> Vec<(Cow<'static, str>, Condition<M>)> {
+        if self.conjuncts.is_empty() {
+            vec![(self.name.clone(), Arc::clone(&self.condition))]
+        } else {
+            self.conjuncts
+                .iter()
+                .map(|(name, condition)| (name.clone(), Arc::clone(condition)))
+                .collect()
+        }
+    }
+
+    /// Restricts this property to states within a boundary, analogous to
+    /// [`Model::within_boundary`] but for this property alone, so that for instance "agreement"
+    /// can be checked only up to 3 writes while type invariants are checked everywhere. The model
+    /// is still explored up to its own boundary.
+    ///
+    /// Outside the boundary, `always`, `eventually`, and `terminal` conditions (including the
+    /// response of a `within` or `leads_to` property) are treated as holding, whereas `sometimes`
+    /// conditions and `within` and `leads_to` triggers are treated as not holding. An
+    /// `always_transition` condition is only evaluated for steps taken from states within the
+    /// boundary.
+    pub fn within_boundary(
+        self,
+        boundary: impl Fn(&M, &M::State) -> bool + Send + Sync + 'static,
+    ) -> Property<M>
+    where
+        M: 'static,
+    {
+        let boundary: Condition<M> = Arc::new(boundary);
+        let holds_outside = |condition: Condition<M>| -> Condition<M> {
+            let boundary = Arc::clone(&boundary);
+            Arc::new(move |model, state| !boundary(model, state) || condition(model, state))
+        };
+        let condition = match self.expectation {
+            Expectation::Sometimes => {
+                let (boundary, condition) = (Arc::clone(&boundary), self.condition);
+                Arc::new(move |model: &M, state: &M::State| {
+                    boundary(model, state) && condition(model, state)
+                }) as Condition<M>
+            }
+            _ => holds_outside(self.condition),
+        };
+        let trigger = self.trigger.map(|trigger| {
+            let (boundary, condition) = (Arc::clone(&boundary), trigger.condition);
+            Trigger {
+                condition: Arc::new(move |model, state| {
+                    boundary(model, state) && condition(model, state)
+                }),
+                steps: trigger.steps,
+            }
+        });
+        let conjuncts = self
+            .conjuncts
+            .into_iter()
+            .map(|(name, condition)| (name, holds_outside(condition)))
+            .collect();
+        let transition = self.transition.map(|transition| {
+            let boundary = Arc::clone(&boundary);
+            Arc::new(
+                move |model: &M, state: &M::State, action: &M::Action, next: &M::State| {
+                    !boundary(model, state) || transition(model, state, action, next)
+                },
+            ) as TransitionCondition<M>
+        });
+        Property {
+            condition,
+            trigger,
+            conjuncts,
+            transition,
+            ..self
+        }
+    }
+
+    /// Caches evaluations of this property's conditions keyed by a projection of the state, such
+    /// as just the sequence numbers, so that an expensive condition is only evaluated once per
+    /// distinct projected value. The conditions must depend only on the projection, or results
+    /// will be reused for states that they do not hold for.
+    ///
+    /// The cache is shared by clones of the property and lives as long as they do. A checker calls
+    /// [`Model::properties`] once and evaluates the same properties throughout, so the cache lasts
+    /// the whole run even if `properties` builds new ones on each call. The cache holds an entry per
+    /// distinct projected value, so the projection should have far fewer distinct values than the
+    /// model has states.
+    ///
+    /// Panics if this is an [`Property::always_transition`] property, as its condition depends on
+    /// the steps from a state rather than the state alone.
+    pub fn memoize_by<K>(
+        self,
+        projection: impl Fn(&M, &M::State) -> K + Send + Sync + 'static,
+    ) -> Property<M>
+    where
+        M: 'static,
+        K: Eq + Hash + Send + Sync + 'static,
+    {
+        assert!(
+            self.transition.is_none(),
+            "Unable to memoize \"{}\" as its condition observes transitions",
+            self.name
+        );
+        let projection = Arc::new(projection);
+        let memoize = |condition: Condition<M>| -> Condition<M> {
+            let projection = Arc::clone(&projection);
+            let cache = dashmap::DashMap::new();
+            Arc::new(move |model, state| {
+                let key = projection(model, state);
+                if let Some(holds) = cache.get(&key) {
+                    return *holds;
+                }
+                // Evaluated without holding a lock, so concurrent checker threads may race to
+                // evaluate the same projection, but that's fine.
+                let holds = condition(model, state);
+                cache.insert(key, holds);
+                holds
+            })
+        };
+        let condition = memoize(self.condition);
+        let trigger = self.trigger.map(|trigger| Trigger {
+            condition: memoize(trigger.condition),
+            steps: trigger.steps,
+        });
+        Property {
             condition,
+            trigger,
+            ..self
+        }
+    }
+
+    /// Declares that this property is only meaningful when the named property holds, for
+    /// instance "agreement" only under "at most f crashes". Checking is unaffected, but rather
+    /// than reporting a misleading pass, the [report](Checker::report) indicates whether the
+    /// assumption holds and hence whether this property is vacuous. See
+    /// [`Checker::assumption_status`].
+    pub fn assuming(self, assumption: impl Into<Cow<'static, str>>) -> Property<M> {
+        Property {
+            assumption: Some(assumption.into()),
+            ..self
         }
     }
 }
@@ -308,14 +862,146 @@ impl<M: Model> Clone for Property<M> {
     fn clone(&self) -> Self {
         Property {
             expectation: self.expectation.clone(),
-            name: self.name,
-            condition: self.condition,
+            name: self.name.clone(),
+            condition: Arc::clone(&self.condition),
+            trigger: self.trigger.as_ref().map(|trigger| Trigger {
+                condition: Arc::clone(&trigger.condition),
+                steps: trigger.steps,
+            }),
+            assumption: self.assumption.clone(),
+            conjuncts: self
+                .conjuncts
+                .iter()
+                .map(|(name, condition)| (name.clone(), Arc::clone(condition)))
+                .collect(),
+            transition: self.transition.as_ref().map(Arc::clone),
         }
     }
 }
 
-/// Indicates whether a property is always, eventually, or sometimes true.
+/// Expands into a `Vec` of individually named [`always`](Property::always) properties whose
+/// conditions share one pair of closure parameters, reducing boilerplate for models with many
+/// invariants. Each invariant is reported separately, so a counterexample names the one it
+/// violates.
+///
+/// ```
+/// # use stateright::*;
+/// # struct Counter;
+/// # impl Model for Counter {
+/// #     type State = u8;
+/// #     type Action = ();
+/// #     fn init_states(&self) -> Vec<u8> { vec![0] }
+/// #     fn actions(&self, _: &u8, _: &mut Vec<()>) {}
+/// #     fn next_state(&self, _: &u8, _: ()) -> Option<u8> { None }
+/// fn properties(&self) -> Vec<Property<Self>> {
+///     let mut properties = invariants! { |model, count|
+///         "small" => *count < 10,
+///         "even" => count % 2 == 0,
+///     };
+///     properties.push(Property::sometimes("nonzero", |_, count| *count > 0));
+///     properties
+/// }
+/// # }
+/// ```
+#[macro_export]
+macro_rules! invariants {
+    (|$model:ident, $state:ident| $($name:expr => $condition:expr),* $(,)?) => {
+        vec![$(
+            $crate::Property::always($name, |$model, $state| {
+                let _ = (&$model, &$state);
+                $condition
+            })
+        ),*]
+    };
+}
+
+/// Builds a [`Property`] from a temporal formula over named state predicates, which are
+/// functions (or closures) with the signature `Fn(&M, &M::State) -> bool`. The property is named
+/// after the formula itself.
+///
+/// The supported forms are `always f`, `eventually f`, `sometimes f`, and the "leads to" form
+/// `always (f -> eventually g)`, which is checked as a [`Property::leads_to`] property. State
+/// formulas combine predicates with `!`, `&&`, `||`, and `->`, and operands that are neither
+/// predicate names nor negated predicate names must be parenthesized.
+///
+/// ```
+/// # use stateright::*;
+/// # struct Store;
+/// # impl Model for Store {
+/// #     type State = (bool, bool);
+/// #     type Action = ();
+/// #     fn init_states(&self) -> Vec<Self::State> { vec![(false, false)] }
+/// #     fn actions(&self, _: &Self::State, _: &mut Vec<()>) {}
+/// #     fn next_state(&self, _: &Self::State, _: ()) -> Option<Self::State> { None }
+/// fn properties(&self) -> Vec<Property<Self>> {
+///     fn put_ok(_: &Store, state: &(bool, bool)) -> bool {
+///         state.0
+///     }
+///     fn get_consistent(_: &Store, state: &(bool, bool)) -> bool {
+///         state.1
+///     }
+///     vec![
+///         ltl!(always (put_ok -> eventually get_consistent)),
+///         ltl!(always (get_consistent -> put_ok)),
+///         ltl!(sometimes (put_ok && !get_consistent)),
+///     ]
+/// }
+/// # }
+/// ```
+#[macro_export]
+macro_rules! ltl {
+    (always ($p:tt -> eventually $q:tt)) => {
+        $crate::Property::leads_to(
+            stringify!(always ($p -> eventually $q)),
+            |model, state| $crate::ltl!(@state model state $p),
+            |model, state| $crate::ltl!(@state model state $q),
+        )
+    };
+    (always $($f:tt)+) => {
+        $crate::Property::always(stringify!(always $($f)+), |model, state| {
+            $crate::ltl!(@state model state $($f)+)
+        })
+    };
+    (eventually $($f:tt)+) => {
+        $crate::Property::eventually(stringify!(eventually $($f)+), |model, state| {
+            $crate::ltl!(@state model state $($f)+)
+        })
+    };
+    (sometimes $($f:tt)+) => {
+        $crate::Property::sometimes(stringify!(sometimes $($f)+), |model, state| {
+            $crate::ltl!(@state model state $($f)+)
+        })
+    };
+    (@state $model:ident $state:ident ($($f:tt)+)) => {
+        ($crate::ltl!(@state $model $state $($f)+))
+    };
+    (@state $model:ident $state:ident ! $p:tt) => {
+        !$crate::ltl!(@state $model $state $p)
+    };
+    (@state $model:ident $state:ident ! $p:tt $op:tt $($q:tt)+) => {
+        $crate::ltl!(@state $model $state (! $p) $op $($q)+)
+    };
+    (@state $model:ident $state:ident $p:tt $op:tt ! $q:tt) => {
+        $crate::ltl!(@state $model $state $p $op (! $q))
+    };
+    (@state $model:ident $state:ident $p:tt && $q:tt) => {
+        $crate::ltl!(@state $model $state $p) && $crate::ltl!(@state $model $state $q)
+    };
+    (@state $model:ident $state:ident $p:tt || $q:tt) => {
+        $crate::ltl!(@state $model $state $p) || $crate::ltl!(@state $model $state $q)
+    };
+    (@state $model:ident $state:ident $p:tt -> $q:tt) => {
+        !$crate::ltl!(@state $model $state $p) || $crate::ltl!(@state $model $state $q)
+    };
+    (@state $model:ident $state:ident $p:ident) => {
+        $p($model, $state)
+    };
+}
+
+/// Indicates whether a property is always, eventually, sometimes, or within a bounded number of
+/// steps true.
 #[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd, serde::Deserialize, serde::Serialize)]
+#[non_exhaustive]
 pub enum Expectation {
     /// The property is true for all reachable states.
     Always,
@@ -323,17 +1009,74 @@ pub enum Expectation {
     Eventually,
     /// The property is true for at least one reachable state.
     Sometimes,
+    /// The property is true within a bounded number of steps of its trigger for all behavior
+    /// paths. See [`Property::within`].
+    Within,
+    /// The property is true for all reachable terminal states. See [`Property::terminal`].
+    Terminal,
+    /// The property is eventually true after each time its trigger is true for all behavior
+    /// paths. See [`Property::leads_to`].
+    LeadsTo,
 }
 
 /// A state identifier. See [`fingerprint`].
 type Fingerprint = std::num::NonZeroU64;
 
-/// Converts a state to a [`Fingerprint`].
+/// Converts a state to a [`Fingerprint`] using the default [`FingerprintHasher`].
 #[inline]
 fn fingerprint<T: Hash>(value: &T) -> Fingerprint {
-    let mut hasher = stable::hasher();
-    value.hash(&mut hasher);
-    Fingerprint::new(hasher.finish()).expect("hasher returned zero, an invalid fingerprint")
+    FingerprintHasher::default().fingerprint(value)
+}
+
+/// The hash function with which a checker fingerprints states. See
+/// [`CheckerBuilder::fingerprint_hasher`].
+///
+/// Hashing states is a measurable fraction of checking time for models with large states. Every
+/// option is a 64-bit hash that is not designed to resist adversarial inputs, so any of them may
+/// map two distinct states to the same fingerprint, in which case the checker skips one of them.
+/// For typical models the likelihood of this is comparable across the options.
+///
+/// Fingerprints only identify states within a single run, except that [encoded paths](Path::encode)
+/// always use the default hasher. [`FingerprintHasher::Sip`] in particular is not stable across
+/// Rust releases.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum FingerprintHasher {
+    /// [aHash](https://github.com/tkaitchuck/aHash) with fixed keys. A fast general purpose hash
+    /// and the default.
+    #[default]
+    AHash,
+    /// The [FxHash](https://github.com/rust-lang/rustc-hash) used by the Rust compiler. The
+    /// fastest option for states consisting of many small integers, but its poor mixing makes
+    /// collisions more likely for states that differ only in a few bits.
+    Fx,
+    /// [XXH3](https://github.com/Cyan4973/xxHash), which is particularly fast for states that hash
+    /// long byte sequences, such as strings or [compact encodings](util::CompactState).
+    Xxh3,
+    /// The standard library's [`DefaultHasher`](std::collections::hash_map::DefaultHasher),
+    /// currently SipHash-1-3. The slowest option. Its algorithm is unspecified and may change
+    /// between Rust releases, so fingerprints computed with it must not be persisted.
+    Sip,
+}
+
+impl FingerprintHasher {
+    /// Converts a state to a [`Fingerprint`].
+    #[inline]
+    pub(crate) fn fingerprint<T: Hash>(self, value: &T) -> Fingerprint {
+        fn finish<H: Hasher>(mut hasher: H, value: &impl Hash) -> u64 {
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+        let hash = match self {
+            FingerprintHasher::AHash => finish(stable::hasher(), value),
+            FingerprintHasher::Fx => finish(rustc_hash::FxHasher::default(), value),
+            FingerprintHasher::Xxh3 => finish(xxhash_rust::xxh3::Xxh3Default::default(), value),
+            FingerprintHasher::Sip => {
+                finish(std::collections::hash_map::DefaultHasher::new(), value)
+            }
+        };
+        // Zero is reserved, so it is remapped rather than treated as an error.
+        Fingerprint::new(hash).unwrap_or(Fingerprint::MAX)
+    }
 }
 
 /// Implemented only for rustdoc. Do not take a dependency on this. It will likely be removed in a
@@ -0,0 +1,223 @@
+//! Checks hyperproperties, which relate two executions of a model rather than constraining each
+//! execution on its own, by model checking the product of the model with itself.
+//!
+//! For example, determinism requires that executions given the same input yield the same
+//! output, and noninterference requires that executions differing only in secret inputs are
+//! indistinguishable to an observer. Neither can be expressed as a [`Property`] of a single
+//! execution, but both can be expressed over the pairs of states of a [`SelfProduct`].
+//!
+//! # Example
+//!
+//! ```
+//! use stateright::{Checker, Expectation, Model};
+//! use stateright::product::SelfProduct;
+//!
+//! /// Doubles its input, but a bug sometimes adds one.
+//! struct Doubler;
+//! impl Model for Doubler {
+//!     type State = (u8, Option<u8>); // (input, output)
+//!     type Action = u8;
+//!     fn init_states(&self) -> Vec<Self::State> {
+//!         vec![(1, None), (2, None)]
+//!     }
+//!     fn actions(&self, state: &Self::State, actions: &mut Vec<Self::Action>) {
+//!         if state.1.is_none() {
+//!             actions.push(2 * state.0);
+//!             actions.push(2 * state.0 + 1);
+//!         }
+//!     }
+//!     fn next_state(&self, state: &Self::State, output: Self::Action) -> Option<Self::State> {
+//!         Some((state.0, Some(output)))
+//!     }
+//! }
+//!
+//! let checker = SelfProduct::new(Doubler)
+//!     .init_relation(|_, left, right| left.0 == right.0) // same input
+//!     .property(Expectation::Always, "deterministic", |_, left, right| {
+//!         left.1.is_none() || right.1.is_none() || left.1 == right.1
+//!     })
+//!     .checker()
+//!     .spawn_bfs()
+//!     .join();
+//! let (left, right) = checker.discovery("deterministic").unwrap().last_state().clone();
+//! assert_ne!(left.1, right.1);
+//! ```
+
+use crate::{Expectation, Model, Property};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// The product of a model with itself, whose states pair the states of two executions of the
+/// model. The executions take turns nondeterministically, so every combination of their
+/// progress is explored. See the [module documentation](self) for an example.
+pub struct SelfProduct<M>
+where
+    M: Model,
+    M::State: Clone,
+{
+    model: M,
+    #[allow(clippy::type_complexity)]
+    init_relation: fn(&M, &M::State, &M::State) -> bool,
+    properties: Vec<Property<SelfProduct<M>>>,
+}
+
+/// An action of one of the two executions in a [`SelfProduct`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq, serde::Serialize)]
+pub enum ProductAction<Action> {
+    /// An action of the left execution.
+    Left(Action),
+    /// An action of the right execution.
+    Right(Action),
+}
+
+impl<M> SelfProduct<M>
+where
+    M: Model,
+    M::State: Clone,
+{
+    /// Instantiates the product, pairing every initial state of the model with every initial
+    /// state.
+    pub fn new(model: M) -> Self {
+        Self {
+            model,
+            init_relation: |_, _, _| true,
+            properties: Vec::new(),
+        }
+    }
+
+    /// Only pairs initial states that satisfy the specified relation, such as "same inputs" for
+    /// determinism or "same public inputs" for noninterference.
+    #[allow(clippy::type_complexity)]
+    pub fn init_relation(self, init_relation: fn(&M, &M::State, &M::State) -> bool) -> Self {
+        Self {
+            init_relation,
+            ..self
+        }
+    }
+
+    /// Adds a [`Property`] over the states of the left and right executions.
+    #[allow(clippy::type_complexity)]
+    pub fn property(
+        mut self,
+        expectation: Expectation,
+        name: impl Into<Cow<'static, str>>,
+        condition: fn(&M, &M::State, &M::State) -> bool,
+    ) -> Self
+    where
+        M: 'static,
+        M::State: 'static,
+    {
+        self.properties.push(Property {
+            expectation,
+            name: name.into(),
+            condition: Arc::new(move |product: &SelfProduct<M>, (left, right)| {
+                condition(&product.model, left, right)
+            }),
+            trigger: None,
+            assumption: None,
+            conjuncts: Vec::new(),
+            transition: None,
+        });
+        self
+    }
+
+    /// The model whose executions are paired.
+    pub fn model(&self) -> &M {
+        &self.model
+    }
+}
+
+impl<M> Model for SelfProduct<M>
+where
+    M: Model,
+    M::State: Clone,
+{
+    type State = (M::State, M::State);
+    type Action = ProductAction<M::Action>;
+
+    fn init_states(&self) -> Vec<Self::State> {
+        let init_states = self.model.init_states();
+        let mut pairs = Vec::new();
+        for left in &init_states {
+            for right in &init_states {
+                if (self.init_relation)(&self.model, left, right) {
+                    pairs.push((left.clone(), right.clone()));
+                }
+            }
+        }
+        pairs
+    }
+
+    fn actions(&self, state: &Self::State, actions: &mut Vec<Self::Action>) {
+        let mut side_actions = Vec::new();
+        self.model.actions(&state.0, &mut side_actions);
+        actions.extend(side_actions.drain(..).map(ProductAction::Left));
+        self.model.actions(&state.1, &mut side_actions);
+        actions.extend(side_actions.drain(..).map(ProductAction::Right));
+    }
+
+    fn next_state(&self, last_state: &Self::State, action: Self::Action) -> Option<Self::State> {
+        let (left, right) = last_state;
+        match action {
+            ProductAction::Left(action) => self
+                .model
+                .next_state(left, action)
+                .map(|left| (left, right.clone())),
+            ProductAction::Right(action) => self
+                .model
+                .next_state(right, action)
+                .map(|right| (left.clone(), right)),
+        }
+    }
+
+    fn properties(&self) -> Vec<Property<Self>> {
+        self.properties.clone()
+    }
+
+    fn within_boundary(&self, state: &Self::State) -> bool {
+        self.model.within_boundary(&state.0) && self.model.within_boundary(&state.1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::linear_equation_solver::*;
+    use crate::Checker;
+
+    #[test]
+    fn pairs_related_initial_states() {
+        let product = SelfProduct::new(LinearEquation { a: 2, b: 10, c: 14 });
+        assert_eq!(product.init_states(), vec![((0, 0), (0, 0))]);
+        let product = product.init_relation(|_, left, right| left != right);
+        assert_eq!(product.init_states(), vec![]);
+    }
+
+    #[test]
+    fn checks_properties_over_pairs_of_executions() {
+        let checker = SelfProduct::new(LinearEquation { a: 2, b: 10, c: 14 })
+            .property(Expectation::Always, "in step", |_, left, right| {
+                left == right
+            })
+            .property(
+                Expectation::Sometimes,
+                "both progressed",
+                |_, left, right| left != &(0, 0) && right != &(0, 0),
+            )
+            .checker()
+            .target_max_depth(4)
+            .spawn_bfs()
+            .join();
+        assert_eq!(
+            checker.discovery("in step").unwrap().into_actions(),
+            vec![ProductAction::Left(Guess::IncreaseX)]
+        );
+        assert_eq!(
+            checker.discovery("both progressed").unwrap().into_actions(),
+            vec![
+                ProductAction::Left(Guess::IncreaseX),
+                ProductAction::Right(Guess::IncreaseX)
+            ]
+        );
+    }
+}
@@ -1,10 +1,11 @@
+use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::io::Write;
 use std::time::Duration;
 
-use crate::{DiscoveryClassification, Model, Path};
+use crate::{AssumptionStatus, DiscoveryClassification, Estimate, MetricSummary, Model, Path};
 
 /// The data sent during a report event.
 pub struct ReportData {
@@ -27,8 +28,22 @@ where
 {
     /// The path that led to the discovery.
     pub path: Path<M::State, M::Action>,
+    /// The path rendered via [`Path::format`], which honors [`Model::format_action`].
+    pub formatted_path: String,
     /// The classification of the path.
     pub classification: DiscoveryClassification,
+    /// The violated conjunct of a [`Property::all`](crate::Property::all) counterexample, if any.
+    pub violated_conjunct: Option<Cow<'static, str>>,
+}
+
+/// The status of a property's assumption, reported at the end of the checking.
+pub struct ReportAssumption {
+    /// The name of the property.
+    pub name: Cow<'static, str>,
+    /// The name of the property it assumes.
+    pub assumption: Cow<'static, str>,
+    /// Whether the assumption holds.
+    pub status: AssumptionStatus,
 }
 
 /// A reporter for progress during the model checking.
@@ -37,11 +52,24 @@ pub trait Reporter<M: Model> {
     fn report_checking(&mut self, data: ReportData);
 
     /// Report the discoveries at the end of the checking run.
-    fn report_discoveries(&mut self, discoveries: BTreeMap<&'static str, ReportDiscovery<M>>)
+    fn report_discoveries(&mut self, discoveries: BTreeMap<Cow<'static, str>, ReportDiscovery<M>>)
     where
         M::Action: Debug,
         M::State: Debug + Hash;
 
+    /// Report the status of property assumptions at the end of the checking run, if any.
+    fn report_assumptions(&mut self, _assumptions: Vec<ReportAssumption>) {}
+
+    /// Report the probability estimates at the end of the checking run, if any.
+    fn report_estimates(&mut self, _estimates: Vec<Estimate>) {}
+
+    /// Report the metric summaries at the end of the checking run, if any.
+    fn report_metrics(&mut self, _metrics: Vec<MetricSummary>) {}
+
+    /// Report the formatted [terminal states](crate::Checker::terminal_states) at the end of the
+    /// checking run, if [tracked](crate::CheckerBuilder::track_terminal_states).
+    fn report_terminal_states(&mut self, _states: Vec<String>) {}
+
     fn delay(&self) -> std::time::Duration {
         std::time::Duration::from_millis(1_000)
     }
@@ -81,7 +109,7 @@ where
         }
     }
 
-    fn report_discoveries(&mut self, discoveries: BTreeMap<&'static str, ReportDiscovery<M>>)
+    fn report_discoveries(&mut self, discoveries: BTreeMap<Cow<'static, str>, ReportDiscovery<M>>)
     where
         M::Action: Debug,
         M::State: Debug + Hash,
@@ -90,9 +118,217 @@ where
             let _ = write!(
                 self.writer,
                 "Discovered \"{}\" {} {}",
-                name, discovery.classification, discovery.path,
+                name, discovery.classification, discovery.formatted_path,
             );
+            if let Some(conjunct) = discovery.violated_conjunct {
+                let _ = writeln!(self.writer, "Violated conjunct: \"{}\"", conjunct);
+            }
             let _ = writeln!(self.writer, "Fingerprint path: {}", discovery.path.encode());
         }
     }
+
+    fn report_assumptions(&mut self, assumptions: Vec<ReportAssumption>) {
+        for assumption in assumptions {
+            let _ = write!(
+                self.writer,
+                "Assumption \"{}\" of \"{}\" {}",
+                assumption.assumption, assumption.name, assumption.status
+            );
+            let _ = match assumption.status {
+                AssumptionStatus::Holds => writeln!(self.writer),
+                AssumptionStatus::Violated => {
+                    writeln!(self.writer, ", so \"{}\" is vacuous", assumption.name)
+                }
+                AssumptionStatus::Undetermined => {
+                    writeln!(self.writer, ", so \"{}\" may be vacuous", assumption.name)
+                }
+            };
+        }
+    }
+
+    fn report_estimates(&mut self, estimates: Vec<Estimate>) {
+        for estimate in estimates {
+            let verdict = if estimate.is_satisfied() {
+                "satisfied"
+            } else {
+                "violated"
+            };
+            let _ = writeln!(self.writer, "Estimated {}: {}", estimate, verdict);
+        }
+    }
+
+    fn report_metrics(&mut self, metrics: Vec<MetricSummary>) {
+        for metric in metrics {
+            let _ = writeln!(self.writer, "Metric {}", metric);
+        }
+    }
+
+    fn report_terminal_states(&mut self, states: Vec<String>) {
+        let _ = writeln!(self.writer, "Terminal states: {}", states.len());
+        for state in states {
+            let _ = writeln!(self.writer, "- {}", state);
+        }
+    }
+}
+
+/// A reporter that renders progress as a single line, which is continually rewritten when
+/// [interactive](Self::interactive), showing the generated and unique state counts, states per
+/// second, and (given a [target state count](Self::target_state_count)) a progress bar with the
+/// estimated time remaining. Final results are written as with a [`WriteReporter`].
+///
+/// # Example
+///
+/// ```
+/// use std::io::IsTerminal;
+/// use stateright::report::ProgressReporter;
+/// # use stateright::*; let model = ();
+/// model.checker()
+///     .target_state_count(1_000_000)
+///     .spawn_bfs()
+///     .report(&mut ProgressReporter::new(&mut std::io::stderr())
+///         .target_state_count(1_000_000)
+///         .interactive(std::io::stderr().is_terminal()));
+/// ```
+pub struct ProgressReporter<'a, W> {
+    writer: &'a mut W,
+    target_state_count: Option<usize>,
+    interactive: bool,
+    quiet: bool,
+}
+
+impl<'a, W> ProgressReporter<'a, W> {
+    /// Creates a non-interactive reporter, which writes a line per progress update.
+    pub fn new(writer: &'a mut W) -> Self {
+        Self {
+            writer,
+            target_state_count: None,
+            interactive: false,
+            quiet: false,
+        }
+    }
+
+    /// Indicates the [target state count](crate::CheckerBuilder::target_state_count) of the
+    /// checker, against which progress and the time remaining are estimated.
+    pub fn target_state_count(self, count: usize) -> Self {
+        Self {
+            target_state_count: Some(count).filter(|count| *count > 0),
+            ..self
+        }
+    }
+
+    /// Indicates whether to rewrite the progress line in place, which is appropriate for a
+    /// terminal but not for a log.
+    pub fn interactive(self, interactive: bool) -> Self {
+        Self {
+            interactive,
+            ..self
+        }
+    }
+
+    /// Omits progress updates, only writing final results. Useful for continuous integration.
+    pub fn quiet(self) -> Self {
+        Self {
+            quiet: true,
+            ..self
+        }
+    }
+
+    fn progress(&self, data: &ReportData) -> String {
+        let secs = data.duration.as_secs_f64();
+        let rate = if secs > 0.0 {
+            (data.total_states as f64 / secs) as usize
+        } else {
+            0
+        };
+        let mut line = format!(
+            "states={}, unique={}, depth={}, states/sec={}",
+            data.total_states, data.unique_states, data.max_depth, rate,
+        );
+        if let Some(target) = self.target_state_count {
+            let fraction = (data.total_states as f64 / target as f64).min(1.0);
+            let filled = (fraction * 20.0) as usize;
+            line = format!(
+                "[{}{}] {:>3}% {}",
+                "#".repeat(filled),
+                ".".repeat(20 - filled),
+                (fraction * 100.0) as usize,
+                line
+            );
+            if !data.done && rate > 0 {
+                let remaining = target.saturating_sub(data.total_states) / rate;
+                line.push_str(&format!(", eta={}", format_duration(remaining as u64)));
+            }
+        }
+        line
+    }
+}
+
+impl<'a, M, W> Reporter<M> for ProgressReporter<'a, W>
+where
+    M: Model,
+    W: Write,
+{
+    fn report_checking(&mut self, data: ReportData) {
+        let progress = self.progress(&data);
+        // Clears the line written by the previous interactive update.
+        let clear = if self.interactive { "\r\x1b[2K" } else { "" };
+        if data.done {
+            let _ = writeln!(
+                self.writer,
+                "{}Done. {}, sec={}",
+                clear,
+                progress,
+                data.duration.as_secs()
+            );
+        } else if self.interactive && !self.quiet {
+            let _ = write!(self.writer, "{}Checking. {}", clear, progress);
+            let _ = self.writer.flush();
+        } else if !self.quiet {
+            let _ = writeln!(self.writer, "Checking. {}", progress);
+        }
+    }
+
+    fn report_discoveries(&mut self, discoveries: BTreeMap<Cow<'static, str>, ReportDiscovery<M>>)
+    where
+        M::Action: Debug,
+        M::State: Debug + Hash,
+    {
+        Reporter::<M>::report_discoveries(&mut WriteReporter::new(self.writer), discoveries);
+    }
+
+    fn report_assumptions(&mut self, assumptions: Vec<ReportAssumption>) {
+        Reporter::<M>::report_assumptions(&mut WriteReporter::new(self.writer), assumptions);
+    }
+
+    fn report_estimates(&mut self, estimates: Vec<Estimate>) {
+        Reporter::<M>::report_estimates(&mut WriteReporter::new(self.writer), estimates);
+    }
+
+    fn report_metrics(&mut self, metrics: Vec<MetricSummary>) {
+        Reporter::<M>::report_metrics(&mut WriteReporter::new(self.writer), metrics);
+    }
+
+    fn report_terminal_states(&mut self, states: Vec<String>) {
+        Reporter::<M>::report_terminal_states(&mut WriteReporter::new(self.writer), states);
+    }
+
+    fn delay(&self) -> Duration {
+        if self.interactive {
+            Duration::from_millis(250)
+        } else {
+            Duration::from_millis(1_000)
+        }
+    }
+}
+
+/// Formats a number of seconds, such as `1h02m03s`.
+fn format_duration(secs: u64) -> String {
+    let (hours, minutes, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{}h{:02}m{:02}s", hours, minutes, secs)
+    } else if minutes > 0 {
+        format!("{}m{:02}s", minutes, secs)
+    } else {
+        format!("{}s", secs)
+    }
 }
@@ -49,12 +49,15 @@
 mod consistency_tester;
 mod linearizability;
 mod sequential_consistency;
+mod serializability;
 
 pub use consistency_tester::ConsistencyTester;
 pub mod register;
 pub mod write_once_register;
 pub use linearizability::LinearizabilityTester;
 pub use sequential_consistency::SequentialConsistencyTester;
+pub use serializability::{SerializabilityTester, StrictSerializabilityTester};
+pub mod transactional_kv;
 pub mod vec;
 
 /// An implementation of this trait can serve as a sequential "reference object"
@@ -67,6 +70,8 @@ pub mod vec;
 ///
 /// - [`LinearizabilityTester`]
 /// - [`SequentialConsistencyTester`]
+/// - [`SerializabilityTester`] and [`StrictSerializabilityTester`] for transactional specs such as
+///   [`transactional_kv`]
 ///
 /// [consistency model]: https://en.wikipedia.org/wiki/Consistency_model
 /// [operational semantics]: https://en.wikipedia.org/wiki/Operational_semantics
@@ -6,12 +6,13 @@ use crate::semantics::SequentialSpec;
 /// a "reference sequential specification" [`SequentialSpec`]. The interface for doing so involves
 /// recording operation invocations and returns.
 ///
-/// Currently Stateright includes implementations in the form of a [`LinearizabilityTester`] and
-/// [`SequentialConsistencyTester`].
+/// Currently Stateright includes implementations in the form of a [`LinearizabilityTester`],
+/// [`SequentialConsistencyTester`], and [`SerializabilityTester`].
 ///
 /// [consistency]: https://en.wikipedia.org/wiki/Consistency_model
 /// [`LinearizabilityTester`]: crate::semantics::LinearizabilityTester
 /// [`SequentialConsistencyTester`]: crate::semantics::SequentialConsistencyTester
+/// [`SerializabilityTester`]: crate::semantics::SerializabilityTester
 pub trait ConsistencyTester<T, RefObj>
 where
     RefObj: SequentialSpec,
@@ -0,0 +1,303 @@
+//! Private module for selective re-export. See [`SerializabilityTester`].
+
+use crate::semantics::{ConsistencyTester, LinearizabilityTester, SequentialSpec};
+use std::collections::{btree_map, BTreeMap};
+use std::fmt::Debug;
+
+/// This tester captures a potentially concurrent history of transactions and validates that it
+/// adheres to a [`SequentialSpec`] based on the [serializability] consistency model. Each
+/// operation of the spec is interpreted as a transaction, as with
+/// [`TransactionalKv`](crate::semantics::transactional_kv::TransactionalKv). The model requires
+/// that transactions be applied atomically in some serial order, and in-flight transactions may or
+/// may not have taken effect.
+///
+/// # Serializability
+///
+/// Unlike with [`SequentialConsistencyTester`], the serial order need not respect the order of
+/// transactions within a thread, so a thread can fail to observe its own earlier writes. Use a
+/// [`StrictSerializabilityTester`] to also require that sequenced (non-concurrent) transactions
+/// are applied in order:
+///
+/// ```text
+///           -----------Time------------------------------>
+/// Thread 1: [write x invoked... and returns]
+/// Thread 2:                                 [read x invoked... and returns]
+/// ```
+///
+/// Under serializability, the read by Thread 2 may return the value of `x` prior to Thread 1's
+/// write, whereas under strict serializability it must not.
+///
+/// [serializability]: https://en.wikipedia.org/wiki/Serializability
+/// [`SequentialConsistencyTester`]: crate::semantics::SequentialConsistencyTester
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[allow(clippy::type_complexity)]
+pub struct SerializabilityTester<ThreadId, RefObj: SequentialSpec> {
+    init_ref_obj: RefObj,
+    history: Vec<(ThreadId, RefObj::Op, RefObj::Ret)>,
+    in_flight_by_thread: BTreeMap<ThreadId, RefObj::Op>,
+    is_valid_history: bool,
+}
+
+/// This tester validates that a history of transactions adheres to a [`SequentialSpec`] based on
+/// the [strict serializability] consistency model, which is [linearizability] where each
+/// operation is a transaction. See [`SerializabilityTester`].
+///
+/// [strict serializability]: https://jepsen.io/consistency/models/strict-serializable
+/// [linearizability]: https://en.wikipedia.org/wiki/Linearizability
+pub type StrictSerializabilityTester<ThreadId, RefObj> = LinearizabilityTester<ThreadId, RefObj>;
+
+#[allow(clippy::len_without_is_empty)] // no use case for an emptiness check
+impl<T: Ord, RefObj: SequentialSpec> SerializabilityTester<T, RefObj> {
+    /// Constructs a [`SerializabilityTester`].
+    pub fn new(init_ref_obj: RefObj) -> Self {
+        Self {
+            init_ref_obj,
+            history: Vec::new(),
+            in_flight_by_thread: Default::default(),
+            is_valid_history: true,
+        }
+    }
+
+    /// Indicates the aggregate number of transactions completed or in flight across all threads.
+    pub fn len(&self) -> usize {
+        self.history.len() + self.in_flight_by_thread.len()
+    }
+}
+
+impl<T, RefObj> ConsistencyTester<T, RefObj> for SerializabilityTester<T, RefObj>
+where
+    T: Copy + Debug + Ord,
+    RefObj: Clone + SequentialSpec,
+    RefObj::Op: Clone + Debug,
+    RefObj::Ret: Clone + Debug + PartialEq,
+{
+    /// Indicates that a thread invoked a transaction. Returns `Ok(...)` if the history is valid,
+    /// even if it is not serializable.
+    ///
+    /// See [`SerializabilityTester::serialized_history`].
+    fn on_invoke(&mut self, thread_id: T, op: RefObj::Op) -> Result<&mut Self, String> {
+        if !self.is_valid_history {
+            return Err("Earlier history was invalid.".to_string());
+        }
+        let in_flight_elem = self.in_flight_by_thread.entry(thread_id);
+        if let btree_map::Entry::Occupied(occupied_op_entry) = in_flight_elem {
+            self.is_valid_history = false;
+            return Err(format!(
+                "Thread already has an operation in flight. thread_id={:?}, op={:?}, history={:?}",
+                thread_id,
+                occupied_op_entry.get(),
+                self.history
+            ));
+        };
+        in_flight_elem.or_insert(op);
+        Ok(self)
+    }
+
+    /// Indicates that a thread's earlier transaction returned. Returns `Ok(...)` if the history
+    /// is valid, even if it is not serializable.
+    ///
+    /// See [`SerializabilityTester::serialized_history`].
+    fn on_return(&mut self, thread_id: T, ret: RefObj::Ret) -> Result<&mut Self, String> {
+        if !self.is_valid_history {
+            return Err("Earlier history was invalid.".to_string());
+        }
+        let op = match self.in_flight_by_thread.remove(&thread_id) {
+            None => {
+                self.is_valid_history = false;
+                return Err(format!(
+                    "There is no in-flight invocation for this thread ID. \
+                     thread_id={:?}, unexpected_return={:?}, history={:?}",
+                    thread_id, ret, self.history
+                ));
+            }
+            Some(op) => op,
+        };
+        self.history.push((thread_id, op, ret));
+        Ok(self)
+    }
+
+    /// Indicates whether the recorded history is serializable.
+    fn is_consistent(&self) -> bool {
+        self.serialized_history().is_some()
+    }
+}
+
+impl<T, RefObj> SerializabilityTester<T, RefObj>
+where
+    T: Copy + Debug + Ord,
+    RefObj: Clone + SequentialSpec,
+    RefObj::Op: Clone + Debug,
+    RefObj::Ret: Clone + Debug + PartialEq,
+{
+    /// Attempts to serialize the recorded transaction history into a total order that is
+    /// consistent with a reference object's operational semantics.
+    pub fn serialized_history(&self) -> Option<Vec<(RefObj::Op, RefObj::Ret)>> {
+        if !self.is_valid_history {
+            return None;
+        }
+        let remaining: Vec<_> = self
+            .history
+            .iter()
+            .map(|(_t, op, ret)| (op.clone(), ret.clone()))
+            .collect();
+        let in_flight: Vec<_> = self.in_flight_by_thread.values().cloned().collect();
+        Self::serialize(Vec::new(), &self.init_ref_obj, &remaining, &in_flight)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn serialize(
+        valid_history: Vec<(RefObj::Op, RefObj::Ret)>, // total order
+        ref_obj: &RefObj,
+        remaining: &[(RefObj::Op, RefObj::Ret)], // unordered
+        in_flight: &[RefObj::Op],
+    ) -> Option<Vec<(RefObj::Op, RefObj::Ret)>> {
+        // Return collected total order when there are no remaining completed transactions.
+        if remaining.is_empty() {
+            return Some(valid_history);
+        }
+
+        // Otherwise try each completed transaction next.
+        for (i, (op, ret)) in remaining.iter().enumerate() {
+            let mut ref_obj = ref_obj.clone();
+            if !ref_obj.is_valid_step(op, ret) {
+                continue;
+            }
+            let mut valid_history = valid_history.clone();
+            valid_history.push((op.clone(), ret.clone()));
+            let mut remaining = remaining.to_vec();
+            remaining.remove(i);
+            if let Some(valid_history) =
+                Self::serialize(valid_history, &ref_obj, &remaining, in_flight)
+            {
+                return Some(valid_history);
+            }
+        }
+
+        // Or an in-flight transaction that may have taken effect.
+        for (i, op) in in_flight.iter().enumerate() {
+            let mut ref_obj = ref_obj.clone();
+            let ret = ref_obj.invoke(op);
+            let mut valid_history = valid_history.clone();
+            valid_history.push((op.clone(), ret));
+            let mut in_flight = in_flight.to_vec();
+            in_flight.remove(i);
+            if let Some(valid_history) =
+                Self::serialize(valid_history, &ref_obj, remaining, &in_flight)
+            {
+                return Some(valid_history);
+            }
+        }
+        None
+    }
+}
+
+impl<T: Ord, RefObj> Default for SerializabilityTester<T, RefObj>
+where
+    RefObj: Default + SequentialSpec,
+{
+    fn default() -> Self {
+        Self::new(RefObj::default())
+    }
+}
+
+impl<T, RefObj> serde::Serialize for SerializabilityTester<T, RefObj>
+where
+    RefObj: serde::Serialize + SequentialSpec,
+    RefObj::Op: serde::Serialize,
+    RefObj::Ret: serde::Serialize,
+    T: Ord + serde::Serialize,
+{
+    fn serialize<Ser: serde::Serializer>(&self, ser: Ser) -> Result<Ser::Ok, Ser::Error> {
+        use serde::ser::SerializeStruct;
+        let mut out = ser.serialize_struct("SerializabilityTester", 4)?;
+        out.serialize_field("init_ref_obj", &self.init_ref_obj)?;
+        out.serialize_field("history", &self.history)?;
+        out.serialize_field("in_flight_by_thread", &self.in_flight_by_thread)?;
+        out.serialize_field("is_valid_history", &self.is_valid_history)?;
+        out.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::semantics::transactional_kv::*;
+
+    fn write(k: char, v: u8) -> (Vec<TxnOp<char, u8>>, Vec<TxnRet<u8>>) {
+        (vec![TxnOp::Write(k, v)], vec![TxnRet::WriteOk])
+    }
+
+    fn read2(
+        (k1, v1): (char, Option<u8>),
+        (k2, v2): (char, Option<u8>),
+    ) -> (Vec<TxnOp<char, u8>>, Vec<TxnRet<u8>>) {
+        (
+            vec![TxnOp::Read(k1), TxnOp::Read(k2)],
+            vec![TxnRet::ReadOk(v1), TxnRet::ReadOk(v2)],
+        )
+    }
+
+    #[test]
+    fn rejects_invalid_history() -> Result<(), String> {
+        let (op, _) = write('x', 1);
+        assert_eq!(
+            SerializabilityTester::new(TransactionalKv::default())
+                .on_invoke(99, op.clone())?
+                .on_invoke(99, op)
+                .map(|_| ()),
+            Err("Thread already has an operation in flight. \
+                 thread_id=99, op=[Write('x', 1)], history=[]"
+                .to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn identifies_serializable_history() -> Result<(), String> {
+        // A transaction that misses an earlier write is serializable but not strictly so.
+        let (w_op, w_ret) = write('x', 1);
+        let (r_op, r_ret) = read2(('x', None), ('y', None));
+        let mut tester = SerializabilityTester::new(TransactionalKv::default());
+        tester
+            .on_invret(0, w_op.clone(), w_ret.clone())?
+            .on_invret(1, r_op.clone(), r_ret.clone())?;
+        assert_eq!(
+            tester.serialized_history(),
+            Some(vec![
+                (r_op.clone(), r_ret.clone()),
+                (w_op.clone(), w_ret.clone())
+            ])
+        );
+        assert!(
+            !StrictSerializabilityTester::new(TransactionalKv::default())
+                .on_invret(0, w_op, w_ret)?
+                .on_invret(1, r_op, r_ret)?
+                .is_consistent()
+        );
+
+        // An in-flight transaction may have taken effect.
+        let (w_op, _) = write('x', 1);
+        let (r_op, r_ret) = read2(('x', Some(1)), ('y', None));
+        assert!(SerializabilityTester::new(TransactionalKv::default())
+            .on_invoke(0, w_op)?
+            .on_invret(1, r_op, r_ret)?
+            .is_consistent());
+        Ok(())
+    }
+
+    #[test]
+    fn identifies_unserializable_history() -> Result<(), String> {
+        // Each reader observes only one of two writes, so the writes are ordered differently.
+        let mut tester = SerializabilityTester::new(TransactionalKv::default());
+        let (op, ret) = write('x', 1);
+        tester.on_invret(0, op, ret)?;
+        let (op, ret) = write('y', 1);
+        tester.on_invret(1, op, ret)?;
+        let (op, ret) = read2(('x', Some(1)), ('y', None));
+        tester.on_invret(2, op, ret)?;
+        let (op, ret) = read2(('x', None), ('y', Some(1)));
+        tester.on_invret(3, op, ret)?;
+        assert_eq!(tester.serialized_history(), None);
+        Ok(())
+    }
+}
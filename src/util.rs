@@ -50,8 +50,15 @@
 //! #
 //! # let checker = MyModel.checker().spawn_bfs().join();
 //! ```
+//!
+//! Both copy every entry when cloned and hash every entry when fingerprinted. For large collections
+//! in states that are cloned on every transition, consider [`PersistentHashSet`] and
+//! [`PersistentHashMap`] instead, which share structure between clones and cache their hashes.
 
+mod compact;
 mod densenatmap;
+#[cfg(feature = "schemars")]
+mod json_schema;
 use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::collections::{hash_map::DefaultHasher, HashMap, HashSet};
@@ -59,9 +66,15 @@ use std::fmt::{self, Debug, Formatter};
 use std::hash::{BuildHasher, Hash, Hasher};
 use std::iter::FromIterator;
 use std::ops::{Deref, DerefMut};
+mod persistent;
 mod vector_clock;
 
+pub use compact::{hash_compact, CompactState};
 pub use densenatmap::DenseNatMap;
+#[cfg(feature = "schemars")]
+pub use json_schema::json_schema;
+pub use persistent::{PersistentHashMap, PersistentHashSet};
+pub use stateright_derive::CompactState;
 pub use vector_clock::*;
 
 // Reuse a buffer to avoid temporary allocations.
@@ -0,0 +1,609 @@
+//! Private module for selective re-export.
+
+use crate::util::{HashableHashMap, HashableHashSet, PersistentHashMap, PersistentHashSet};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::sync::Arc;
+
+/// A compact, canonical binary encoding of a state, which is usually derived via
+/// [`#[derive(CompactState)]`](macro@crate::util::CompactState).
+///
+/// Integers are encoded as [varints](https://en.wikipedia.org/wiki/LEB128) (signed integers
+/// after [zigzag](https://en.wikipedia.org/wiki/Variable-length_quantity#Zigzag_encoding)
+/// encoding), and the derive macro packs the `bool` fields of a struct or enum variant into bits,
+/// so a state with many small fields typically encodes to far fewer bytes than it occupies in
+/// memory. Collections are length-prefixed, and unordered collections are encoded in a canonical
+/// order, so equal states always have equal encodings and vice versa.
+///
+/// The encoding serves two purposes:
+///
+/// 1. **Fingerprinting.** The checker fingerprints states via [`Hash`], which for a derived
+///    implementation feeds every field to the hasher separately. Adding `#[compact_state(hash)]`
+///    alongside the derive instead implements `Hash` by feeding the encoded bytes to the hasher in
+///    one call (see [`hash_compact`]).
+/// 2. **Storage.** [`CompactState::to_compact_bytes`] and [`CompactState::from_compact_bytes`]
+///    convert a state to and from a byte buffer, such as when storing states outside of memory.
+///
+/// ```rust
+/// use stateright::util::CompactState;
+///
+/// #[derive(Clone, CompactState, Debug, Eq, PartialEq)]
+/// #[compact_state(hash)]
+/// struct ProcessState {
+///     round: u32,
+///     voted: bool,
+///     decided: bool,
+///     value: Option<char>,
+/// }
+///
+/// let state = ProcessState { round: 3, voted: true, decided: false, value: Some('A') };
+/// let bytes = state.to_compact_bytes();
+/// assert_eq!(bytes.len(), 4); // bools, round, option tag, value
+/// assert_eq!(ProcessState::from_compact_bytes(&bytes), Some(state));
+/// ```
+pub trait CompactState: Sized {
+    /// Appends the encoding of `self` to `buf`.
+    fn encode(&self, buf: &mut Vec<u8>);
+
+    /// Decodes a value from the front of `buf`, advancing `buf` past it. Returns `None` if `buf`
+    /// does not start with a valid encoding.
+    fn decode(buf: &mut &[u8]) -> Option<Self>;
+
+    /// Returns the encoding of `self`.
+    fn to_compact_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.encode(&mut buf);
+        buf
+    }
+
+    /// Decodes a value from `bytes`, which must contain exactly one encoded value.
+    fn from_compact_bytes(mut bytes: &[u8]) -> Option<Self> {
+        let value = Self::decode(&mut bytes)?;
+        bytes.is_empty().then_some(value)
+    }
+}
+
+// Reuse a buffer to avoid temporary allocations.
+thread_local!(static BUFFER: RefCell<Vec<u8>> = RefCell::new(Vec::with_capacity(256)));
+
+/// Feeds the [compact encoding](CompactState) of a value to a [`Hasher`] in a single call. This is
+/// the implementation of [`Hash`] generated by `#[compact_state(hash)]`.
+pub fn hash_compact<T: CompactState, H: Hasher>(value: &T, state: &mut H) {
+    BUFFER.with(|buffer| {
+        // The cached buffer might already be in use farther up the call stack, so the algorithm
+        // reverts to a fallback as needed.
+        let fallback = RefCell::new(Vec::new());
+
+        let mut buffer = buffer
+            .try_borrow_mut()
+            .unwrap_or_else(|_| fallback.borrow_mut());
+        buffer.clear();
+        value.encode(&mut buffer);
+        state.write(&buffer);
+    });
+}
+
+fn encode_varint(mut value: u128, buf: &mut Vec<u8>) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// Decodes a varint, rejecting non-canonical encodings and values that exceed `bits`.
+fn decode_varint(buf: &mut &[u8], bits: u32) -> Option<u128> {
+    let mut value = 0_u128;
+    let mut shift = 0;
+    loop {
+        let (&byte, rest) = buf.split_first()?;
+        *buf = rest;
+        if shift >= bits || (shift > 0 && byte == 0) {
+            return None;
+        }
+        let part = u128::from(byte & 0x7F);
+        if (part << shift) >> shift != part {
+            return None;
+        }
+        value |= part << shift;
+        if byte & 0x80 == 0 {
+            return (bits == 128 || value >> bits == 0).then_some(value);
+        }
+        shift += 7;
+    }
+}
+
+fn decode_byte(buf: &mut &[u8]) -> Option<u8> {
+    let (&byte, rest) = buf.split_first()?;
+    *buf = rest;
+    Some(byte)
+}
+
+impl CompactState for u8 {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(*self);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        decode_byte(buf)
+    }
+}
+
+impl CompactState for i8 {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(*self as u8);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        decode_byte(buf).map(|b| b as i8)
+    }
+}
+
+macro_rules! impl_unsigned {
+    ($($t:ty),*) => {$(
+        impl CompactState for $t {
+            fn encode(&self, buf: &mut Vec<u8>) {
+                encode_varint(*self as u128, buf);
+            }
+
+            fn decode(buf: &mut &[u8]) -> Option<Self> {
+                decode_varint(buf, <$t>::BITS).map(|v| v as $t)
+            }
+        }
+    )*};
+}
+impl_unsigned!(u16, u32, u64, u128, usize);
+
+macro_rules! impl_signed {
+    ($($t:ty),*) => {$(
+        impl CompactState for $t {
+            fn encode(&self, buf: &mut Vec<u8>) {
+                // Zigzag encoding keeps small negative numbers small.
+                let zigzag = (*self << 1) ^ (*self >> (<$t>::BITS - 1));
+                encode_varint(zigzag as u128 & (u128::MAX >> (128 - <$t>::BITS)), buf);
+            }
+
+            fn decode(buf: &mut &[u8]) -> Option<Self> {
+                let zigzag = decode_varint(buf, <$t>::BITS)?;
+                Some(((zigzag >> 1) as $t) ^ -((zigzag & 1) as $t))
+            }
+        }
+    )*};
+}
+impl_signed!(i16, i32, i64, i128, isize);
+
+impl CompactState for bool {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(*self as u8);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        match decode_byte(buf)? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl CompactState for char {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        encode_varint(u128::from(*self), buf);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        char::from_u32(decode_varint(buf, 32)? as u32)
+    }
+}
+
+impl CompactState for () {
+    fn encode(&self, _buf: &mut Vec<u8>) {}
+
+    fn decode(_buf: &mut &[u8]) -> Option<Self> {
+        Some(())
+    }
+}
+
+impl CompactState for String {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.len().encode(buf);
+        buf.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        let len = usize::decode(buf)?;
+        if buf.len() < len {
+            return None;
+        }
+        let (bytes, rest) = buf.split_at(len);
+        *buf = rest;
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+impl<T: CompactState> CompactState for Option<T> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            None => buf.push(0),
+            Some(value) => {
+                buf.push(1);
+                value.encode(buf);
+            }
+        }
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        match decode_byte(buf)? {
+            0 => Some(None),
+            1 => T::decode(buf).map(Some),
+            _ => None,
+        }
+    }
+}
+
+impl<T: CompactState> CompactState for Box<T> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        (**self).encode(buf);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        T::decode(buf).map(Box::new)
+    }
+}
+
+impl<T: CompactState> CompactState for Arc<T> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        (**self).encode(buf);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        T::decode(buf).map(Arc::new)
+    }
+}
+
+/// Decodes a length-prefixed sequence of values.
+fn decode_seq<T: CompactState, C: FromIterator<T>>(buf: &mut &[u8]) -> Option<C> {
+    let len = usize::decode(buf)?;
+    (0..len).map(|_| T::decode(buf)).collect()
+}
+
+impl<T: CompactState> CompactState for Vec<T> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.len().encode(buf);
+        for value in self {
+            value.encode(buf);
+        }
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        decode_seq(buf)
+    }
+}
+
+impl<T: CompactState> CompactState for VecDeque<T> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.len().encode(buf);
+        for value in self {
+            value.encode(buf);
+        }
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        decode_seq(buf)
+    }
+}
+
+impl<T: CompactState, const N: usize> CompactState for [T; N] {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        for value in self {
+            value.encode(buf);
+        }
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        let values: Vec<T> = (0..N).map(|_| T::decode(buf)).collect::<Option<_>>()?;
+        values.try_into().ok()
+    }
+}
+
+macro_rules! impl_tuple {
+    ($($t:ident),+) => {
+        impl<$($t: CompactState),+> CompactState for ($($t,)+) {
+            #[allow(non_snake_case)]
+            fn encode(&self, buf: &mut Vec<u8>) {
+                let ($($t,)+) = self;
+                $($t.encode(buf);)+
+            }
+
+            fn decode(buf: &mut &[u8]) -> Option<Self> {
+                Some(($($t::decode(buf)?,)+))
+            }
+        }
+    };
+}
+impl_tuple!(A);
+impl_tuple!(A, B);
+impl_tuple!(A, B, C);
+impl_tuple!(A, B, C, D);
+impl_tuple!(A, B, C, D, E);
+impl_tuple!(A, B, C, D, E, F);
+
+impl<T: CompactState + Ord> CompactState for BTreeSet<T> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.len().encode(buf);
+        for value in self {
+            value.encode(buf);
+        }
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        decode_seq(buf)
+    }
+}
+
+impl<K: CompactState + Ord, V: CompactState> CompactState for BTreeMap<K, V> {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.len().encode(buf);
+        for (key, value) in self {
+            key.encode(buf);
+            value.encode(buf);
+        }
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        decode_seq::<(K, V), _>(buf)
+    }
+}
+
+/// Encodes an unordered collection in a canonical order: sorted by the encodings of its entries.
+fn encode_unordered<T>(
+    len: usize,
+    entries: impl Iterator<Item = T>,
+    encode: impl Fn(T, &mut Vec<u8>),
+    buf: &mut Vec<u8>,
+) {
+    let mut encoded: Vec<Vec<u8>> = entries
+        .map(|entry| {
+            let mut entry_buf = Vec::new();
+            encode(entry, &mut entry_buf);
+            entry_buf
+        })
+        .collect();
+    encoded.sort_unstable();
+    len.encode(buf);
+    for entry_buf in encoded {
+        buf.extend_from_slice(&entry_buf);
+    }
+}
+
+impl<V, S> CompactState for HashableHashSet<V, S>
+where
+    V: CompactState + Eq + Hash,
+    S: BuildHasher + Default,
+{
+    fn encode(&self, buf: &mut Vec<u8>) {
+        encode_unordered(self.len(), self.iter(), V::encode, buf);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        decode_seq(buf)
+    }
+}
+
+impl<K, V, S> CompactState for HashableHashMap<K, V, S>
+where
+    K: CompactState + Eq + Hash,
+    V: CompactState,
+    S: BuildHasher + Default,
+{
+    fn encode(&self, buf: &mut Vec<u8>) {
+        encode_unordered(
+            self.len(),
+            self.iter(),
+            |(k, v), buf| {
+                k.encode(buf);
+                v.encode(buf);
+            },
+            buf,
+        );
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        decode_seq::<(K, V), _>(buf)
+    }
+}
+
+impl<V> CompactState for PersistentHashSet<V>
+where
+    V: CompactState + Clone + Eq + Hash,
+{
+    fn encode(&self, buf: &mut Vec<u8>) {
+        encode_unordered(self.len(), self.iter(), V::encode, buf);
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        decode_seq(buf)
+    }
+}
+
+impl<K, V> CompactState for PersistentHashMap<K, V>
+where
+    K: CompactState + Clone + Eq + Hash,
+    V: CompactState + Clone + Hash,
+{
+    fn encode(&self, buf: &mut Vec<u8>) {
+        encode_unordered(
+            self.len(),
+            self.iter(),
+            |(k, v), buf| {
+                k.encode(buf);
+                v.encode(buf);
+            },
+            buf,
+        );
+    }
+
+    fn decode(buf: &mut &[u8]) -> Option<Self> {
+        decode_seq::<(K, V), _>(buf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::actor::Id;
+    use crate::util::{CompactState, HashableHashMap, HashableHashSet};
+    use std::collections::BTreeMap;
+    use std::fmt::Debug;
+
+    fn round_trip<T: CompactState + Debug + PartialEq>(value: T) -> usize {
+        let bytes = value.to_compact_bytes();
+        assert_eq!(T::from_compact_bytes(&bytes), Some(value));
+        bytes.len()
+    }
+
+    #[test]
+    fn integers_are_varints() {
+        assert_eq!(round_trip(0_u64), 1);
+        assert_eq!(round_trip(127_u64), 1);
+        assert_eq!(round_trip(128_u64), 2);
+        assert_eq!(round_trip(u64::MAX), 10);
+        assert_eq!(round_trip(u128::MAX), 19);
+        assert_eq!(round_trip(-1_i32), 1);
+        assert_eq!(round_trip(63_i32), 1);
+        assert_eq!(round_trip(-64_i32), 1);
+        assert_eq!(round_trip(i64::MIN), 10);
+        assert_eq!(round_trip(i64::MAX), 10);
+        assert_eq!(round_trip(-128_i8), 1);
+        assert_eq!(round_trip(255_u8), 1);
+    }
+
+    #[test]
+    fn rejects_invalid_encodings() {
+        // Exceeds `u16::MAX`.
+        assert_eq!(u16::from_compact_bytes(&[0xFF, 0xFF, 0x7F]), None);
+        assert_eq!(u128::from_compact_bytes(&[0xFF; 19]), None);
+        // Non-canonical varint.
+        assert_eq!(u32::from_compact_bytes(&[0x80, 0x00]), None);
+        // Truncated.
+        assert_eq!(u32::from_compact_bytes(&[0x80]), None);
+        assert_eq!(String::from_compact_bytes(&[2, b'a']), None);
+        // Trailing bytes.
+        assert_eq!(u32::from_compact_bytes(&[1, 2]), None);
+        // Invalid tags.
+        assert_eq!(bool::from_compact_bytes(&[2]), None);
+        assert_eq!(Option::<u8>::from_compact_bytes(&[2, 0]), None);
+    }
+
+    #[test]
+    fn can_round_trip_collections() {
+        round_trip((Id::from(3), 'x', String::from("text"), ()));
+        round_trip(vec![Some(1_u8), None]);
+        round_trip([1_u16, 2, 3]);
+        round_trip(BTreeMap::from([(1_u8, vec![true]), (2, vec![])]));
+        round_trip(std::sync::Arc::new(Box::new(-5_isize)));
+        round_trip((0..100_u32).collect::<HashableHashSet<_>>());
+        round_trip(
+            (0..100_u32)
+                .map(|i| (i, i))
+                .collect::<HashableHashMap<_, _>>(),
+        );
+    }
+
+    #[test]
+    fn unordered_collections_are_canonical() {
+        let set1: HashableHashSet<_> = (0..100_u32).collect();
+        let set2: HashableHashSet<_> = (0..100_u32).rev().collect();
+        assert_eq!(set1.to_compact_bytes(), set2.to_compact_bytes());
+    }
+
+    mod derived {
+        use super::round_trip;
+        use crate::fingerprint;
+        use crate::util::CompactState;
+
+        #[derive(Clone, CompactState, Debug, Eq, Hash, PartialEq)]
+        struct Flags {
+            a: bool,
+            count: u32,
+            b: bool,
+            c: bool,
+        }
+
+        #[derive(Clone, CompactState, Debug, Eq, PartialEq)]
+        #[compact_state(hash)]
+        enum Msg<T> {
+            Empty,
+            Tuple(T, bool),
+            Struct { value: Option<T>, flags: Flags },
+        }
+
+        #[derive(Clone, CompactState, Debug, Eq, PartialEq)]
+        struct Unit;
+
+        #[derive(Clone, CompactState, Debug, Eq, PartialEq)]
+        struct ManyBools(bool, bool, bool, bool, bool, bool, bool, bool, bool);
+
+        #[test]
+        fn packs_bools() {
+            let flags = Flags {
+                a: true,
+                count: 1,
+                b: false,
+                c: true,
+            };
+            assert_eq!(flags.to_compact_bytes(), vec![0b101, 1]);
+            assert_eq!(round_trip(flags), 2);
+
+            assert_eq!(
+                round_trip(ManyBools(
+                    true, false, false, false, false, false, false, false, true
+                )),
+                2
+            );
+            // Unused bits must be zero.
+            assert_eq!(Flags::from_compact_bytes(&[0b1000, 1]), None);
+        }
+
+        #[test]
+        fn can_round_trip_enums() {
+            assert_eq!(round_trip(Msg::<u8>::Empty), 1);
+            assert_eq!(round_trip(Msg::Tuple('x', true)), 3);
+            round_trip(Msg::Struct {
+                value: Some(vec![1_u16, 2]),
+                flags: Flags {
+                    a: false,
+                    count: 300,
+                    b: true,
+                    c: false,
+                },
+            });
+            assert_eq!(Msg::<u8>::from_compact_bytes(&[3]), None);
+            assert_eq!(round_trip(Unit), 0);
+        }
+
+        #[test]
+        fn hash_distinguishes_states() {
+            assert_ne!(
+                fingerprint(&Msg::Tuple(1_u8, true)),
+                fingerprint(&Msg::Tuple(1_u8, false))
+            );
+            assert_ne!(
+                fingerprint(&Msg::<u8>::Empty),
+                fingerprint(&Msg::<u8>::Struct {
+                    value: None,
+                    flags: Flags {
+                        a: false,
+                        count: 0,
+                        b: false,
+                        c: false
+                    }
+                })
+            );
+            assert_eq!(
+                fingerprint(&Msg::Tuple(1_u8, true)),
+                fingerprint(&Msg::Tuple(1_u8, true))
+            );
+        }
+    }
+}
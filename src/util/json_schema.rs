@@ -0,0 +1,108 @@
+//! Private module for selective re-export.
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use std::collections::HashSet;
+use std::hash::{BuildHasher, Hash};
+
+/// Emits a [JSON Schema](https://json-schema.org/) describing how `serde_json` encodes a type, so
+/// that non-Rust clients exchanging messages with [spawned](crate::actor::spawn) actors over a
+/// JSON codec can validate messages and generate bindings. Requires the `schemars` feature.
+///
+/// The type must implement [`JsonSchema`], which is typically derived using the
+/// [`schemars`](https://docs.rs/schemars) crate. Stateright's own types that appear in messages
+/// and actor states, such as [`Id`](crate::actor::Id), implement it when the feature is enabled.
+///
+/// # Example
+///
+/// ```
+/// use schemars::JsonSchema;
+/// use stateright::actor::Id;
+/// use stateright::util::json_schema;
+///
+/// #[derive(JsonSchema)]
+/// enum Msg {
+///     Put(Id, char),
+///     Get(Id),
+/// }
+///
+/// let schema = json_schema::<Msg>();
+/// assert_eq!(schema["title"], "Msg");
+/// assert_eq!(schema["oneOf"].as_array().unwrap().len(), 2);
+/// ```
+pub fn json_schema<T: JsonSchema>() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(T)).expect("schemas serialize to JSON")
+}
+
+impl<V, S> JsonSchema for super::HashableHashSet<V, S>
+where
+    V: Eq + Hash + JsonSchema,
+    S: BuildHasher,
+{
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        HashSet::<V>::schema_name()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        HashSet::<V>::json_schema(gen)
+    }
+}
+
+impl<V: JsonSchema> JsonSchema for super::PersistentHashSet<V> {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        HashSet::<V>::schema_name()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        HashSet::<V>::json_schema(gen)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::actor::Id;
+    use crate::util::{HashableHashSet, VectorClock};
+    use serde_json::json;
+
+    #[derive(JsonSchema)]
+    #[allow(dead_code)]
+    enum Msg {
+        Ping,
+        Put(Id, char),
+        Batch {
+            ops: Vec<VectorClock>,
+            deadline: Option<u8>,
+        },
+    }
+
+    #[test]
+    fn describes_serde_json_encoding() {
+        let schema = json_schema::<Msg>();
+        let variants = schema["oneOf"].as_array().unwrap();
+        assert_eq!(variants[0]["enum"], json!(["Ping"]));
+        assert_eq!(
+            variants[1]["properties"]["Put"]["items"][0],
+            json!({ "$ref": "#/definitions/Id" })
+        );
+        assert_eq!(schema["definitions"]["Id"]["type"], "integer");
+        assert_eq!(
+            schema["definitions"]["VectorClock"]["items"]["type"],
+            "integer"
+        );
+
+        let schema = json_schema::<HashableHashSet<bool>>();
+        assert_eq!(schema["type"], "array");
+        assert_eq!(schema["uniqueItems"], true);
+        assert_eq!(schema["items"]["type"], "boolean");
+    }
+}
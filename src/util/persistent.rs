@@ -0,0 +1,713 @@
+//! Private module for selective re-export.
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt::{self, Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+/// Bits of the key's hash consumed by each level of the trie.
+const BITS: u32 = 5;
+const MASK: u64 = (1 << BITS) - 1;
+
+/// A persistent hash map that implements [`Hash`], intended as a replacement for
+/// [`HashableHashMap`](crate::util::HashableHashMap) in large model states.
+///
+/// The map is a [hash array mapped trie](https://en.wikipedia.org/wiki/Hash_array_mapped_trie)
+/// whose nodes are shared between clones, so cloning is `O(1)`, and an update only copies the
+/// nodes along the path to the updated entry (`O(log n)`) rather than the whole map. Each node
+/// also caches an order-independent digest of its entries, so hashing the map is `O(1)` as well,
+/// whereas `HashableHashMap` must hash and sort every entry each time.
+///
+/// Keys are placed in the trie using a stable hasher, so hashes do not vary between runs.
+///
+/// ```rust
+/// use stateright::util::PersistentHashMap;
+/// let mut before = PersistentHashMap::new();
+/// before.insert("key", 1);
+/// let mut after = before.clone(); // Cheap, regardless of size.
+/// after.insert("key", 2);
+/// assert_eq!(before.get("key"), Some(&1));
+/// assert_eq!(after.get("key"), Some(&2));
+/// ```
+pub struct PersistentHashMap<K, V> {
+    root: Arc<Node<K, V>>,
+}
+
+#[derive(Clone)]
+struct Node<K, V> {
+    /// The wrapping sum of the digests of the entries in this subtree.
+    digest: u64,
+    len: usize,
+    kind: NodeKind<K, V>,
+}
+
+#[derive(Clone)]
+enum NodeKind<K, V> {
+    /// Children are ordered by their index within the bitmap.
+    Branch {
+        bitmap: u32,
+        children: Vec<Arc<Node<K, V>>>,
+    },
+    /// Entries whose keys have the same hash.
+    Leaf { hash: u64, entries: Vec<(K, V)> },
+}
+
+fn key_hash<Q: Hash + ?Sized>(key: &Q) -> u64 {
+    let mut hasher = crate::stable::hasher();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn entry_digest<K: Hash, V: Hash>(key: &K, value: &V) -> u64 {
+    let mut hasher = crate::stable::hasher();
+    key.hash(&mut hasher);
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns the bit for `hash` within a branch at `shift`, and the position of the corresponding
+/// child.
+fn locate(bitmap: u32, hash: u64, shift: u32) -> (u32, usize) {
+    let bit = 1 << ((hash >> shift) & MASK);
+    (bit, (bitmap & (bit - 1)).count_ones() as usize)
+}
+
+impl<K, V> Node<K, V> {
+    fn empty() -> Self {
+        Node {
+            digest: 0,
+            len: 0,
+            kind: NodeKind::Branch {
+                bitmap: 0,
+                children: Vec::new(),
+            },
+        }
+    }
+
+    fn get<Q>(&self, hash: u64, shift: u32, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        match &self.kind {
+            NodeKind::Branch { bitmap, children } => {
+                let (bit, pos) = locate(*bitmap, hash, shift);
+                if bitmap & bit == 0 {
+                    return None;
+                }
+                children[pos].get(hash, shift + BITS, key)
+            }
+            NodeKind::Leaf {
+                hash: leaf_hash,
+                entries,
+            } => {
+                if *leaf_hash != hash {
+                    return None;
+                }
+                entries
+                    .iter()
+                    .find(|(k, _)| k.borrow() == key)
+                    .map(|(_, v)| v)
+            }
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash, V: Clone + Hash> Node<K, V> {
+    /// Inserts an entry, returning the replaced value and its digest.
+    fn insert(&mut self, hash: u64, shift: u32, key: K, value: V, digest: u64) -> Option<(V, u64)> {
+        if let NodeKind::Leaf {
+            hash: leaf_hash, ..
+        } = self.kind
+        {
+            if leaf_hash != hash {
+                // The hashes diverge at this level or a deeper one, so push the leaf down into a
+                // new branch.
+                let (bit, _) = locate(0, leaf_hash, shift);
+                let leaf = std::mem::replace(self, Node::empty());
+                *self = Node {
+                    digest: leaf.digest,
+                    len: leaf.len,
+                    kind: NodeKind::Branch {
+                        bitmap: bit,
+                        children: vec![Arc::new(leaf)],
+                    },
+                };
+            }
+        }
+        let replaced = match &mut self.kind {
+            NodeKind::Branch { bitmap, children } => {
+                let (bit, pos) = locate(*bitmap, hash, shift);
+                if *bitmap & bit == 0 {
+                    *bitmap |= bit;
+                    children.insert(
+                        pos,
+                        Arc::new(Node {
+                            digest,
+                            len: 1,
+                            kind: NodeKind::Leaf {
+                                hash,
+                                entries: vec![(key, value)],
+                            },
+                        }),
+                    );
+                    None
+                } else {
+                    Arc::make_mut(&mut children[pos]).insert(hash, shift + BITS, key, value, digest)
+                }
+            }
+            NodeKind::Leaf { entries, .. } => {
+                if let Some(entry) = entries.iter_mut().find(|(k, _)| *k == key) {
+                    let replaced = std::mem::replace(&mut entry.1, value);
+                    let replaced_digest = entry_digest(&entry.0, &replaced);
+                    Some((replaced, replaced_digest))
+                } else {
+                    entries.push((key, value));
+                    None
+                }
+            }
+        };
+        self.digest = self.digest.wrapping_add(digest);
+        match &replaced {
+            Some((_, replaced_digest)) => self.digest = self.digest.wrapping_sub(*replaced_digest),
+            None => self.len += 1,
+        }
+        replaced
+    }
+
+    /// Removes an entry that is known to be present, returning it.
+    fn remove<Q>(&mut self, hash: u64, shift: u32, key: &Q) -> (K, V)
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let (k, v) = match &mut self.kind {
+            NodeKind::Branch { bitmap, children } => {
+                let (bit, pos) = locate(*bitmap, hash, shift);
+                let removed = Arc::make_mut(&mut children[pos]).remove(hash, shift + BITS, key);
+                if children[pos].len == 0 {
+                    *bitmap &= !bit;
+                    children.remove(pos);
+                }
+                removed
+            }
+            NodeKind::Leaf { entries, .. } => {
+                let pos = entries
+                    .iter()
+                    .position(|(k, _)| k.borrow() == key)
+                    .expect("entry expected");
+                entries.swap_remove(pos)
+            }
+        };
+        self.digest = self.digest.wrapping_sub(entry_digest(&k, &v));
+        self.len -= 1;
+        (k, v)
+    }
+}
+
+impl<K, V> PersistentHashMap<K, V> {
+    #[inline]
+    pub fn new() -> Self {
+        PersistentHashMap {
+            root: Arc::new(Node::empty()),
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.root.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.root.len == 0
+    }
+
+    /// Returns the entries in an arbitrary but deterministic order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let NodeKind::Branch { children, .. } = &self.root.kind else {
+            unreachable!("the root is always a branch");
+        };
+        Iter {
+            stack: vec![children.iter()],
+            entries: [].iter(),
+            remaining: self.len(),
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.root.get(key_hash(key), 0, key)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+}
+
+impl<K: Clone + Eq + Hash, V: Clone + Hash> PersistentHashMap<K, V> {
+    /// Inserts an entry, returning the previous value for the key if any. Only the nodes along
+    /// the path to the entry are copied, and only if they are shared with another map.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let digest = entry_digest(&key, &value);
+        Arc::make_mut(&mut self.root)
+            .insert(key_hash(&key), 0, key, value, digest)
+            .map(|(v, _)| v)
+    }
+
+    /// Removes an entry, returning its value if present. The map is left untouched (and unshared
+    /// nodes are not copied) if the key is absent.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let hash = key_hash(key);
+        self.root.get(hash, 0, key)?;
+        Some(Arc::make_mut(&mut self.root).remove(hash, 0, key).1)
+    }
+}
+
+impl<K, V> Clone for PersistentHashMap<K, V> {
+    #[inline]
+    fn clone(&self) -> Self {
+        PersistentHashMap {
+            root: Arc::clone(&self.root),
+        }
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for PersistentHashMap<K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V> Default for PersistentHashMap<K, V> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Clone + Eq + Hash, V: Clone + Hash> Extend<(K, V)> for PersistentHashMap<K, V> {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash, V: Clone + Hash> FromIterator<(K, V)> for PersistentHashMap<K, V> {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut map = PersistentHashMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V> Hash for PersistentHashMap<K, V> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.root.len);
+        state.write_u64(self.root.digest);
+    }
+}
+
+impl<K: Eq + Hash, V: PartialEq> PartialEq for PersistentHashMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        if Arc::ptr_eq(&self.root, &other.root) {
+            return true;
+        }
+        self.root.len == other.root.len
+            && self.root.digest == other.root.digest
+            && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl<K: Eq + Hash, V: Eq> Eq for PersistentHashMap<K, V> {}
+
+impl<K: Eq + Hash, V: Eq> PartialOrd for PersistentHashMap<K, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Eq + Hash, V: Eq> Ord for PersistentHashMap<K, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.root.len, self.root.digest).cmp(&(other.root.len, other.root.digest))
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a PersistentHashMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    #[inline]
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<K, V> serde::Serialize for PersistentHashMap<K, V>
+where
+    K: serde::Serialize,
+    V: serde::Serialize,
+{
+    fn serialize<Ser: serde::Serializer>(&self, ser: Ser) -> Result<Ser::Ok, Ser::Error> {
+        ser.collect_map(self.iter())
+    }
+}
+
+/// An iterator over the entries of a [`PersistentHashMap`].
+pub struct Iter<'a, K, V> {
+    stack: Vec<std::slice::Iter<'a, Arc<Node<K, V>>>>,
+    entries: std::slice::Iter<'a, (K, V)>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((k, v)) = self.entries.next() {
+                self.remaining -= 1;
+                return Some((k, v));
+            }
+            let children = self.stack.last_mut()?;
+            match children.next() {
+                None => {
+                    self.stack.pop();
+                }
+                Some(child) => match &child.kind {
+                    NodeKind::Branch { children, .. } => self.stack.push(children.iter()),
+                    NodeKind::Leaf { entries, .. } => self.entries = entries.iter(),
+                },
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+/// A persistent hash set that implements [`Hash`], intended as a replacement for
+/// [`HashableHashSet`](crate::util::HashableHashSet) in large model states. See
+/// [`PersistentHashMap`] for details.
+///
+/// ```rust
+/// use stateright::util::PersistentHashSet;
+/// let before: PersistentHashSet<_> = (0..1_000).collect();
+/// let mut after = before.clone(); // Cheap, regardless of size.
+/// after.remove(&0);
+/// assert!(before.contains(&0));
+/// assert!(!after.contains(&0));
+/// ```
+pub struct PersistentHashSet<V>(PersistentHashMap<V, ()>);
+
+impl<V> PersistentHashSet<V> {
+    #[inline]
+    pub fn new() -> Self {
+        PersistentHashSet(PersistentHashMap::new())
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the values in an arbitrary but deterministic order.
+    pub fn iter(&self) -> SetIter<'_, V> {
+        SetIter(self.0.iter())
+    }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        V: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.0.contains_key(value)
+    }
+}
+
+impl<V: Clone + Eq + Hash> PersistentHashSet<V> {
+    /// Returns `true` if the value was not already present.
+    pub fn insert(&mut self, value: V) -> bool {
+        self.0.insert(value, ()).is_none()
+    }
+
+    /// Returns `true` if the value was present.
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        V: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.0.remove(value).is_some()
+    }
+}
+
+impl<V> Clone for PersistentHashSet<V> {
+    #[inline]
+    fn clone(&self) -> Self {
+        PersistentHashSet(self.0.clone())
+    }
+}
+
+impl<V: Debug> Debug for PersistentHashSet<V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<V> Default for PersistentHashSet<V> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: Clone + Eq + Hash> Extend<V> for PersistentHashSet<V> {
+    fn extend<T: IntoIterator<Item = V>>(&mut self, iter: T) {
+        for v in iter {
+            self.insert(v);
+        }
+    }
+}
+
+impl<V: Clone + Eq + Hash> FromIterator<V> for PersistentHashSet<V> {
+    fn from_iter<T: IntoIterator<Item = V>>(iter: T) -> Self {
+        let mut set = PersistentHashSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<V> Hash for PersistentHashSet<V> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl<V: Eq + Hash> PartialEq for PersistentHashSet<V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<V: Eq + Hash> Eq for PersistentHashSet<V> {}
+
+impl<V: Eq + Hash> PartialOrd for PersistentHashSet<V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<V: Eq + Hash> Ord for PersistentHashSet<V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl<'a, V> IntoIterator for &'a PersistentHashSet<V> {
+    type Item = &'a V;
+    type IntoIter = SetIter<'a, V>;
+
+    #[inline]
+    fn into_iter(self) -> SetIter<'a, V> {
+        self.iter()
+    }
+}
+
+impl<V: serde::Serialize> serde::Serialize for PersistentHashSet<V> {
+    fn serialize<Ser: serde::Serializer>(&self, ser: Ser) -> Result<Ser::Ok, Ser::Error> {
+        ser.collect_seq(self.iter())
+    }
+}
+
+impl<'de, V> serde::Deserialize<'de> for PersistentHashSet<V>
+where
+    V: Clone + Eq + Hash + serde::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Vec::<V>::deserialize(deserializer).map(|values| values.into_iter().collect())
+    }
+}
+
+/// An iterator over the values of a [`PersistentHashSet`].
+pub struct SetIter<'a, V>(Iter<'a, V, ()>);
+
+impl<'a, V> Iterator for SetIter<'a, V> {
+    type Item = &'a V;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(v, _)| v)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<V> ExactSizeIterator for SetIter<'_, V> {}
+
+#[cfg(test)]
+mod test {
+    use crate::fingerprint;
+    use crate::util::{PersistentHashMap, PersistentHashSet};
+    use std::collections::HashMap;
+
+    #[test]
+    fn behaves_like_hash_map() {
+        let mut expected = HashMap::new();
+        let mut actual = PersistentHashMap::new();
+        let mut rng = 7_u64;
+        for _ in 0..10_000 {
+            // A small linear congruential generator keeps the test deterministic.
+            rng = rng
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let key = (rng >> 33) % 500;
+            if (rng >> 40) & 3 == 0 {
+                assert_eq!(actual.remove(&key), expected.remove(&key));
+            } else {
+                assert_eq!(actual.insert(key, rng), expected.insert(key, rng));
+            }
+            assert_eq!(actual.len(), expected.len());
+        }
+        for (k, v) in &expected {
+            assert_eq!(actual.get(k), Some(v));
+        }
+        let mut entries: Vec<_> = actual.iter().map(|(k, v)| (*k, *v)).collect();
+        entries.sort();
+        let mut expected: Vec<_> = expected.into_iter().collect();
+        expected.sort();
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn handles_hash_collisions() {
+        #[derive(Clone, Debug, Eq, PartialEq)]
+        struct Colliding(u8);
+        impl std::hash::Hash for Colliding {
+            fn hash<H: std::hash::Hasher>(&self, _state: &mut H) {}
+        }
+
+        let mut map = PersistentHashMap::new();
+        for i in 0..10 {
+            map.insert(Colliding(i), i);
+        }
+        assert_eq!(map.insert(Colliding(3), 30), Some(3));
+        assert_eq!(map.remove(&Colliding(4)), Some(4));
+        assert_eq!(map.remove(&Colliding(4)), None);
+        assert_eq!(map.len(), 9);
+        assert_eq!(map.get(&Colliding(3)), Some(&30));
+        assert_eq!(map.get(&Colliding(9)), Some(&9));
+    }
+
+    #[test]
+    fn clones_are_independent() {
+        let original: PersistentHashMap<_, _> = (0..100).map(|i| (i, i)).collect();
+        let mut clone = original.clone();
+        clone.insert(0, 100);
+        clone.remove(&1);
+        clone.insert(100, 100);
+        assert_eq!(original.get(&0), Some(&0));
+        assert_eq!(original.get(&1), Some(&1));
+        assert_eq!(original.get(&100), None);
+        assert_eq!(original.len(), 100);
+        assert_eq!(clone.get(&0), Some(&100));
+        assert_eq!(clone.get(&1), None);
+        assert_eq!(clone.len(), 100);
+    }
+
+    #[test]
+    fn different_hash_if_items_differ() {
+        let map1: PersistentHashMap<_, _> = [("one", 1), ("two", 2)].into_iter().collect();
+        let map2: PersistentHashMap<_, _> = [("one", 2), ("two", 1)].into_iter().collect();
+        let map3: PersistentHashMap<_, _> = [("one", 1), ("three", 2)].into_iter().collect();
+        assert_ne!(fingerprint(&map1), fingerprint(&map2));
+        assert_ne!(fingerprint(&map1), fingerprint(&map3));
+        assert_ne!(map1, map2);
+        assert_ne!(map1, map3);
+    }
+
+    #[test]
+    fn history_is_irrelevant() {
+        let mut map1 = PersistentHashMap::new();
+        map1.insert("one", 1);
+        map1.insert("two", 2);
+        map1.insert("three", 3);
+
+        let mut map2 = PersistentHashMap::new();
+        map2.insert("four", 4);
+        map2.insert("three", 0);
+        map2.insert("two", 2);
+        map2.insert("one", 1);
+        map2.insert("three", 3);
+        map2.remove("four");
+
+        assert_eq!(map1, map2);
+        assert_eq!(fingerprint(&map1), fingerprint(&map2));
+
+        let set1: PersistentHashSet<_> = (0..100).collect();
+        let set2: PersistentHashSet<_> = (0..100).rev().collect();
+        assert_eq!(set1, set2);
+        assert_eq!(fingerprint(&set1), fingerprint(&set2));
+    }
+
+    #[test]
+    fn can_nest() {
+        let mut inner = PersistentHashMap::new();
+        inner.insert("key", PersistentHashSet::<u8>::new());
+        let mut map = PersistentHashMap::new();
+        map.insert("key", inner.clone());
+        let mut set = PersistentHashSet::new();
+        set.insert(map.clone());
+        assert!(set.contains(&map));
+        inner.insert("other", PersistentHashSet::new());
+        map.insert("key", inner);
+        assert!(!set.contains(&map));
+    }
+
+    #[test]
+    fn can_serialize() {
+        let map: PersistentHashMap<_, _> = [("key", 1)].into_iter().collect();
+        assert_eq!(serde_json::to_string(&map).unwrap(), r#"{"key":1}"#);
+
+        let set: PersistentHashSet<u8> = serde_json::from_str("[1, 2, 1]").unwrap();
+        assert_eq!(set.len(), 2);
+        assert_eq!(serde_json::to_string(&set).unwrap().len(), "[1,2]".len());
+    }
+}
@@ -7,6 +7,7 @@ use std::hash::{Hash, Hasher};
 /// A [vector clock](https://en.wikipedia.org/wiki/Vector_clock), which provides a partial causal
 /// order on events in a distributed sytem.
 #[derive(Clone, Debug, Default, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct VectorClock(Vec<u32>);
 
 impl VectorClock {
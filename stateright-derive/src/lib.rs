@@ -0,0 +1,225 @@
+//! Derive macros for [Stateright](https://docs.rs/stateright). These are re-exported by the
+//! `stateright` crate, so depend on that rather than on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Fields, Ident, Type};
+
+/// Implements `stateright::util::CompactState`. See the trait's documentation for the encoding.
+///
+/// The `bool` fields of a struct or enum variant are packed into bits, which precede the
+/// remaining fields in declaration order. Enum variants are identified by their index.
+///
+/// Adding `#[compact_state(hash)]` also implements [`Hash`](std::hash::Hash) in terms of the
+/// encoding.
+#[proc_macro_derive(CompactState, attributes(compact_state))]
+pub fn derive_compact_state(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(mut input: DeriveInput) -> syn::Result<TokenStream2> {
+    let mut hash = false;
+    for attr in &input.attrs {
+        if attr.path().is_ident("compact_state") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("hash") {
+                    hash = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported `compact_state` option"))
+                }
+            })?;
+        }
+    }
+
+    let (encode, decode) = match &input.data {
+        Data::Struct(data) => {
+            let fields = FieldList::new(&data.fields);
+            let pattern = fields.pattern(quote!(Self));
+            let encode = fields.encode();
+            let decode = fields.decode(quote!(Self));
+            (
+                quote! {
+                    let #pattern = self;
+                    #encode
+                },
+                quote! {
+                    #decode
+                },
+            )
+        }
+        Data::Enum(data) => {
+            let mut encode_arms = Vec::new();
+            let mut decode_arms = Vec::new();
+            for (index, variant) in data.variants.iter().enumerate() {
+                let variant_ident = &variant.ident;
+                let fields = FieldList::new(&variant.fields);
+                let pattern = fields.pattern(quote!(Self::#variant_ident));
+                let encode = fields.encode();
+                let decode = fields.decode(quote!(Self::#variant_ident));
+                encode_arms.push(quote! {
+                    #pattern => {
+                        ::stateright::util::CompactState::encode(&#index, buf);
+                        #encode
+                    }
+                });
+                decode_arms.push(quote! {
+                    #index => { #decode }
+                });
+            }
+            (
+                quote! {
+                    match self {
+                        #(#encode_arms)*
+                    }
+                },
+                quote! {
+                    match <usize as ::stateright::util::CompactState>::decode(buf)? {
+                        #(#decode_arms)*
+                        _ => ::std::option::Option::None,
+                    }
+                },
+            )
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new(
+                data.union_token.span,
+                "`CompactState` cannot be derived for unions",
+            ));
+        }
+    };
+
+    for param in input.generics.type_params_mut() {
+        param
+            .bounds
+            .push(parse_quote!(::stateright::util::CompactState));
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let hash_impl = hash.then(|| {
+        quote! {
+            impl #impl_generics ::std::hash::Hash for #ident #ty_generics #where_clause {
+                fn hash<__H: ::std::hash::Hasher>(&self, state: &mut __H) {
+                    ::stateright::util::hash_compact(self, state);
+                }
+            }
+        }
+    });
+    Ok(quote! {
+        impl #impl_generics ::stateright::util::CompactState for #ident #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn encode(&self, buf: &mut ::std::vec::Vec<u8>) {
+                #encode
+            }
+
+            #[allow(unused_variables)]
+            fn decode(buf: &mut &[u8]) -> ::std::option::Option<Self> {
+                #decode
+            }
+        }
+
+        #hash_impl
+    })
+}
+
+/// The fields of a struct or enum variant, each bound to a generated identifier.
+struct FieldList<'a> {
+    fields: &'a Fields,
+    bindings: Vec<(Ident, &'a Type)>,
+}
+
+impl<'a> FieldList<'a> {
+    fn new(fields: &'a Fields) -> Self {
+        let bindings = fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| (format_ident!("__field{}", i), &field.ty))
+            .collect();
+        FieldList { fields, bindings }
+    }
+
+    /// Returns a pattern or constructor for `path` in terms of the bindings.
+    fn pattern(&self, path: TokenStream2) -> TokenStream2 {
+        let bindings = self.bindings.iter().map(|(binding, _)| binding);
+        match self.fields {
+            Fields::Named(fields) => {
+                let names = fields.named.iter().map(|f| &f.ident);
+                quote!(#path { #(#names: #bindings),* })
+            }
+            Fields::Unnamed(_) => quote!(#path(#(#bindings),*)),
+            Fields::Unit => quote!(#path),
+        }
+    }
+
+    fn bools(&self) -> impl Iterator<Item = &Ident> {
+        self.bindings
+            .iter()
+            .filter(|(_, ty)| is_bool(ty))
+            .map(|(binding, _)| binding)
+    }
+
+    fn others(&self) -> impl Iterator<Item = &(Ident, &'a Type)> {
+        self.bindings.iter().filter(|(_, ty)| !is_bool(ty))
+    }
+
+    /// Encodes the bindings, which are references to the fields.
+    fn encode(&self) -> TokenStream2 {
+        let bools: Vec<_> = self.bools().collect();
+        let bytes = bools.chunks(8).map(|chunk| {
+            let bits = chunk.iter().enumerate().map(|(i, binding)| {
+                let i = i as u32;
+                quote!(((*#binding as u8) << #i))
+            });
+            quote!(buf.push(0 #(| #bits)*);)
+        });
+        let others = self
+            .others()
+            .map(|(binding, _)| quote!(::stateright::util::CompactState::encode(#binding, buf);));
+        quote! {
+            #(#bytes)*
+            #(#others)*
+        }
+    }
+
+    /// Decodes the bindings and evaluates to `Some` of the constructed value.
+    fn decode(&self, path: TokenStream2) -> TokenStream2 {
+        let bools: Vec<_> = self.bools().collect();
+        let bytes = bools.chunks(8).map(|chunk| {
+            let unused = (0xFF_u16 << chunk.len()) as u8;
+            let check_unused = (unused != 0).then(|| {
+                quote! {
+                    if __byte & #unused != 0 {
+                        return ::std::option::Option::None;
+                    }
+                }
+            });
+            let bits = chunk.iter().enumerate().map(|(i, binding)| {
+                let i = i as u32;
+                quote!(let #binding = __byte & (1 << #i) != 0;)
+            });
+            quote! {
+                let (&__byte, __rest) = buf.split_first()?;
+                *buf = __rest;
+                #check_unused
+                #(#bits)*
+            }
+        });
+        let others = self.others().map(|(binding, ty)| {
+            quote!(let #binding = <#ty as ::stateright::util::CompactState>::decode(buf)?;)
+        });
+        let constructor = self.pattern(path);
+        quote! {
+            #(#bytes)*
+            #(#others)*
+            ::std::option::Option::Some(#constructor)
+        }
+    }
+}
+
+fn is_bool(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("bool"))
+}
@@ -0,0 +1,643 @@
+//! Python bindings for [Stateright](stateright), so that models can be prototyped in Python
+//! while reusing the Rust model checker and Explorer.
+//!
+//! A Python model is any object with `init_states()`, `actions(state)`, and
+//! `next_state(state, action)` methods, plus optional `properties()` and
+//! `within_boundary(state)` methods. States and actions are arbitrary Python values, which must
+//! be hashable, and the checker fingerprints them by `repr`, so equal values need equal `repr`s
+//! (as with tuples, strings, numbers, and frozen dataclasses). `next_state` returns `None` to
+//! indicate that an action is disabled.
+//!
+//! ```python
+//! import stateright
+//!
+//! class Counter:
+//!     def init_states(self):
+//!         return [0]
+//!     def actions(self, state):
+//!         return ["inc", "dec"]
+//!     def next_state(self, state, action):
+//!         return state + 1 if action == "inc" else state - 1
+//!     def within_boundary(self, state):
+//!         return abs(state) <= 3
+//!     def properties(self):
+//!         return [
+//!             stateright.Property.always("bounded", lambda model, s: abs(s) <= 3),
+//!             stateright.Property.sometimes("reaches 3", lambda model, s: s == 3),
+//!         ]
+//!
+//! checker = stateright.checker(Counter()).spawn_bfs().join()
+//! checker.assert_properties()
+//! print(checker.discovery("reaches 3").actions)  # ['inc', 'inc', 'inc']
+//! ```
+//!
+//! Build the extension module with [maturin](https://www.maturin.rs/) (for example `maturin
+//! develop`), which reads `pyproject.toml` in this directory.
+
+// pyo3's generated wrappers for methods returning `PyResult` trip this lint.
+#![allow(clippy::useless_conversion)]
+
+use pyo3::exceptions::{PyAssertionError, PyRuntimeError};
+use pyo3::prelude::*;
+use pyo3::types::PyList;
+use std::fmt::{self, Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+use stateright::{Expectation, Model};
+
+/// A Python object serving as a state or action. Equality defers to Python, while hashing and the
+/// [`Debug`] representation use the object's `repr`.
+pub struct Value(Py<PyAny>);
+
+impl Clone for Value {
+    fn clone(&self) -> Self {
+        Python::with_gil(|py| Value(self.0.clone_ref(py)))
+    }
+}
+
+impl Debug for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let repr = Python::with_gil(|py| {
+            self.0
+                .bind(py)
+                .repr()
+                .map(|r| r.to_string())
+                .unwrap_or_else(|_| "<unrepresentable>".to_string())
+        });
+        f.write_str(&repr)
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        Python::with_gil(|py| self.0.bind(py).eq(other.0.bind(py)).unwrap_or(false))
+    }
+}
+
+impl Eq for Value {}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // The checker identifies states by their fingerprints, so Python's `hash` is too weak
+        // (for instance `hash(-1) == hash(-2)`). The `repr` is used instead.
+        format!("{:?}", self).hash(state)
+    }
+}
+
+/// A [`Model`] defined by the methods of a Python object.
+///
+/// Exceptions raised by the Python object cannot unwind through the checker, so the first one is
+/// retained and all states are treated as outside the boundary, which promptly ends checking.
+/// [`Checker::join`] then raises the exception.
+pub struct PyModel {
+    model: Py<PyAny>,
+    has_boundary: bool,
+    properties: Vec<(Expectation, String, Arc<Py<PyAny>>)>,
+    error: Mutex<Option<PyErr>>,
+}
+
+impl PyModel {
+    fn new(model: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let mut properties = Vec::new();
+        if model.hasattr("properties")? {
+            for property in model.call_method0("properties")?.iter()? {
+                let property = property?;
+                let property = property.downcast::<Property>()?.get();
+                properties.push((
+                    property.expectation.clone(),
+                    property.name.clone(),
+                    Arc::new(property.condition.clone_ref(model.py())),
+                ));
+            }
+        }
+        Ok(PyModel {
+            model: model.clone().unbind(),
+            has_boundary: model.hasattr("within_boundary")?,
+            properties,
+            error: Mutex::new(None),
+        })
+    }
+
+    /// Retains the first error, returning `None` in its place.
+    fn check<T>(&self, result: PyResult<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.error.lock().unwrap().get_or_insert(err);
+                None
+            }
+        }
+    }
+
+    fn failed(&self) -> bool {
+        self.error.lock().unwrap().is_some()
+    }
+
+    fn take_error(&self) -> Option<PyErr> {
+        self.error.lock().unwrap().take()
+    }
+
+    /// Rejects unhashable (typically mutable) values.
+    fn value(&self, value: Bound<'_, PyAny>) -> Option<Value> {
+        self.check(value.hash())?;
+        Some(Value(value.unbind()))
+    }
+
+    fn values(&self, values: PyResult<Bound<'_, PyAny>>) -> Vec<Value> {
+        let Some(values) = self.check(values.and_then(|v| v.iter())) else {
+            return Vec::new();
+        };
+        values
+            .filter_map(|v| self.check(v).and_then(|v| self.value(v)))
+            .collect()
+    }
+
+    fn holds(&self, condition: &Py<PyAny>, state: &Value) -> bool {
+        Python::with_gil(|py| {
+            let result = condition
+                .bind(py)
+                .call1((self.model.bind(py), state.0.bind(py)))
+                .and_then(|r| r.is_truthy());
+            self.check(result).unwrap_or(false)
+        })
+    }
+}
+
+impl Model for PyModel {
+    type State = Value;
+    type Action = Value;
+
+    fn init_states(&self) -> Vec<Self::State> {
+        Python::with_gil(|py| self.values(self.model.bind(py).call_method0("init_states")))
+    }
+
+    fn actions(&self, state: &Self::State, actions: &mut Vec<Self::Action>) {
+        Python::with_gil(|py| {
+            let result = self
+                .model
+                .bind(py)
+                .call_method1("actions", (state.0.bind(py),));
+            actions.extend(self.values(result));
+        })
+    }
+
+    fn next_state(&self, last_state: &Self::State, action: Self::Action) -> Option<Self::State> {
+        Python::with_gil(|py| {
+            let result = self
+                .model
+                .bind(py)
+                .call_method1("next_state", (last_state.0.bind(py), action.0.bind(py)));
+            let next_state = self.check(result)?;
+            if next_state.is_none() {
+                return None;
+            }
+            self.value(next_state)
+        })
+    }
+
+    fn properties(&self) -> Vec<stateright::Property<Self>> {
+        self.properties
+            .iter()
+            .map(|(expectation, name, condition)| {
+                let condition = Arc::clone(condition);
+                let condition =
+                    move |model: &PyModel, state: &Value| model.holds(&condition, state);
+                match expectation {
+                    Expectation::Eventually => {
+                        stateright::Property::eventually(name.clone(), condition)
+                    }
+                    Expectation::Sometimes => {
+                        stateright::Property::sometimes(name.clone(), condition)
+                    }
+                    _ => stateright::Property::always(name.clone(), condition),
+                }
+            })
+            .collect()
+    }
+
+    fn within_boundary(&self, state: &Self::State) -> bool {
+        if self.failed() {
+            return false;
+        }
+        if !self.has_boundary {
+            return true;
+        }
+        Python::with_gil(|py| {
+            let result = self
+                .model
+                .bind(py)
+                .call_method1("within_boundary", (state.0.bind(py),))
+                .and_then(|r| r.is_truthy());
+            self.check(result).unwrap_or(false)
+        })
+    }
+}
+
+/// A named condition that the checker evaluates against reachable states. The condition is
+/// called as `condition(model, state)` and returns a bool.
+#[pyclass(frozen, module = "stateright")]
+pub struct Property {
+    expectation: Expectation,
+    name: String,
+    condition: Py<PyAny>,
+}
+
+#[pymethods]
+impl Property {
+    /// A property that holds in every reachable state.
+    #[staticmethod]
+    fn always(name: String, condition: Py<PyAny>) -> Self {
+        Property {
+            expectation: Expectation::Always,
+            name,
+            condition,
+        }
+    }
+
+    /// A property that holds in some state along every path.
+    #[staticmethod]
+    fn eventually(name: String, condition: Py<PyAny>) -> Self {
+        Property {
+            expectation: Expectation::Eventually,
+            name,
+            condition,
+        }
+    }
+
+    /// A property that holds in at least one reachable state.
+    #[staticmethod]
+    fn sometimes(name: String, condition: Py<PyAny>) -> Self {
+        Property {
+            expectation: Expectation::Sometimes,
+            name,
+            condition,
+        }
+    }
+
+    /// The name of the property.
+    #[getter]
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn __repr__(&self) -> String {
+        let expectation = format!("{:?}", self.expectation).to_lowercase();
+        format!("Property.{}({:?})", expectation, self.name)
+    }
+}
+
+/// A sequence of states connected by actions, such as a counterexample.
+#[pyclass(frozen, module = "stateright")]
+pub struct Path {
+    states: Vec<Py<PyAny>>,
+    actions: Vec<Py<PyAny>>,
+    encoded: String,
+    formatted: String,
+}
+
+impl Path {
+    fn new(model: &PyModel, path: stateright::Path<Value, Value>) -> Self {
+        let encoded = path.encode();
+        let formatted = path.format(model);
+        let mut states = Vec::new();
+        let mut actions = Vec::new();
+        for (state, action) in path.into_vec() {
+            states.push(state.0);
+            actions.extend(action.map(|a| a.0));
+        }
+        Path {
+            states,
+            actions,
+            encoded,
+            formatted,
+        }
+    }
+}
+
+#[pymethods]
+impl Path {
+    /// The states along the path, starting with an initial state.
+    #[getter]
+    fn states<'py>(&self, py: Python<'py>) -> Bound<'py, PyList> {
+        PyList::new_bound(py, self.states.iter().map(|s| s.clone_ref(py)))
+    }
+
+    /// The actions along the path, one fewer than the states.
+    #[getter]
+    fn actions<'py>(&self, py: Python<'py>) -> Bound<'py, PyList> {
+        PyList::new_bound(py, self.actions.iter().map(|a| a.clone_ref(py)))
+    }
+
+    /// The fingerprint path, as used in Explorer URLs.
+    fn encode(&self) -> &str {
+        &self.encoded
+    }
+
+    fn __len__(&self) -> usize {
+        self.states.len()
+    }
+
+    fn __str__(&self) -> &str {
+        &self.formatted
+    }
+}
+
+/// Configures a model checker. Obtain an instance via `stateright.checker(model)`.
+#[pyclass(module = "stateright")]
+pub struct CheckerBuilder {
+    model: Py<PyAny>,
+    target_state_count: Option<usize>,
+    target_max_depth: Option<usize>,
+    threads: usize,
+}
+
+impl CheckerBuilder {
+    fn build(&self, py: Python<'_>) -> PyResult<stateright::CheckerBuilder<PyModel>> {
+        let mut builder = PyModel::new(self.model.bind(py))?
+            .checker()
+            .threads(self.threads);
+        if let Some(count) = self.target_state_count {
+            builder = builder.target_state_count(count);
+        }
+        if let Some(depth) = self.target_max_depth {
+            builder = builder.target_max_depth(depth);
+        }
+        Ok(builder)
+    }
+}
+
+#[pymethods]
+impl CheckerBuilder {
+    /// Stops checking after visiting approximately this many states.
+    fn target_state_count(mut slf: PyRefMut<'_, Self>, count: usize) -> PyRefMut<'_, Self> {
+        slf.target_state_count = Some(count);
+        slf
+    }
+
+    /// Stops checking paths longer than this depth.
+    fn target_max_depth(mut slf: PyRefMut<'_, Self>, depth: usize) -> PyRefMut<'_, Self> {
+        slf.target_max_depth = Some(depth);
+        slf
+    }
+
+    /// Sets the number of checker threads. Python callbacks still run one at a time.
+    fn threads(mut slf: PyRefMut<'_, Self>, count: usize) -> PyRefMut<'_, Self> {
+        slf.threads = count;
+        slf
+    }
+
+    /// Spawns a breadth-first search checker. Call `join()` to wait for it to finish.
+    fn spawn_bfs(&self, py: Python<'_>) -> PyResult<Checker> {
+        let builder = self.build(py)?;
+        let checker = py.allow_threads(|| builder.spawn_bfs());
+        Ok(Checker {
+            checker: Box::new(checker),
+        })
+    }
+
+    /// Spawns a depth-first search checker. Call `join()` to wait for it to finish.
+    fn spawn_dfs(&self, py: Python<'_>) -> PyResult<Checker> {
+        let builder = self.build(py)?;
+        let checker = py.allow_threads(|| builder.spawn_dfs());
+        Ok(Checker {
+            checker: Box::new(checker),
+        })
+    }
+
+    /// Serves the Explorer web UI at the specified address, such as `"localhost:3000"`. Blocks
+    /// the calling thread.
+    fn serve(&self, py: Python<'_>, address: String) -> PyResult<()> {
+        let builder = self.build(py)?;
+        py.allow_threads(|| {
+            builder.serve(address);
+        });
+        Ok(())
+    }
+}
+
+/// A running or finished model checker.
+#[pyclass(module = "stateright")]
+pub struct Checker {
+    checker: Box<dyn stateright::Checker<PyModel> + Send + Sync>,
+}
+
+impl Checker {
+    fn model(&self) -> &PyModel {
+        self.checker.model()
+    }
+}
+
+#[pymethods]
+impl Checker {
+    /// Blocks until checking completes, then returns this checker. Raises the first exception
+    /// thrown by the model, if any.
+    fn join<'py>(mut slf: PyRefMut<'py, Self>, py: Python<'_>) -> PyResult<PyRefMut<'py, Self>> {
+        let handles = slf.checker.handles();
+        let panicked = py.allow_threads(|| {
+            let mut panicked = false;
+            for handle in handles {
+                panicked |= handle.join().is_err();
+            }
+            panicked
+        });
+        if let Some(err) = slf.model().take_error() {
+            return Err(err);
+        }
+        if panicked {
+            return Err(PyRuntimeError::new_err("A checker thread panicked."));
+        }
+        Ok(slf)
+    }
+
+    /// Indicates whether checking has completed.
+    fn is_done(&self) -> bool {
+        self.checker.is_done()
+    }
+
+    /// The number of states visited, including repeat visits.
+    fn state_count(&self) -> usize {
+        self.checker.state_count()
+    }
+
+    /// The number of distinct states visited.
+    fn unique_state_count(&self) -> usize {
+        self.checker.unique_state_count()
+    }
+
+    /// The length of the longest path explored.
+    fn max_depth(&self) -> usize {
+        self.checker.max_depth()
+    }
+
+    /// Maps property names to their discovered examples or counterexamples.
+    fn discoveries(&self) -> Vec<(String, Path)> {
+        let mut discoveries: Vec<_> = self.checker.discoveries().into_iter().collect();
+        discoveries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        discoveries
+            .into_iter()
+            .map(|(name, path)| (name.into_owned(), Path::new(self.model(), path)))
+            .collect()
+    }
+
+    /// The example or counterexample for a property, or `None` if not discovered.
+    fn discovery(&self, name: &str) -> Option<Path> {
+        let path = self.checker.discoveries().remove(name)?;
+        Some(Path::new(self.model(), path))
+    }
+
+    /// Raises `AssertionError` if any property fails: an `always` or `eventually` property has
+    /// a counterexample, or a `sometimes` property lacks an example.
+    fn assert_properties(&self) -> PyResult<()> {
+        let mut discoveries = self.checker.discoveries();
+        for property in self.model().properties() {
+            let found = discoveries.remove(&property.name);
+            match (property.expectation, found) {
+                (Expectation::Sometimes, Some(_)) => {}
+                (Expectation::Sometimes, None) if self.checker.is_done() => {
+                    return Err(PyAssertionError::new_err(format!(
+                        "Discovery for \"{}\" not found.",
+                        property.name
+                    )));
+                }
+                (_, Some(path)) => {
+                    return Err(PyAssertionError::new_err(format!(
+                        "Unexpected \"{}\" {} {}",
+                        property.name,
+                        self.checker.discovery_classification(&property.name),
+                        path.format(self.model())
+                    )));
+                }
+                (_, None) if self.checker.is_done() => {}
+                (_, None) => {
+                    return Err(PyAssertionError::new_err(format!(
+                        "Discovery for \"{}\" not found, but model checking is incomplete.",
+                        property.name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns a `CheckerBuilder` for a Python model.
+#[pyfunction]
+fn checker(model: Py<PyAny>) -> CheckerBuilder {
+    CheckerBuilder {
+        model,
+        target_state_count: None,
+        target_max_depth: None,
+        threads: 1,
+    }
+}
+
+/// A model checker for implementing distributed systems.
+#[pymodule]
+#[pyo3(name = "stateright")]
+pub fn stateright_py(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(checker, module)?)?;
+    module.add_class::<CheckerBuilder>()?;
+    module.add_class::<Checker>()?;
+    module.add_class::<Path>()?;
+    module.add_class::<Property>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(code: &str) -> PyResult<()> {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::new_bound(py, "stateright")?;
+            stateright_py(&module)?;
+            py.import_bound("sys")?
+                .getattr("modules")?
+                .set_item("stateright", module)?;
+            py.run_bound(code, None, None)
+        })
+    }
+
+    const COUNTER: &str = r#"
+import stateright
+
+class Counter:
+    def init_states(self):
+        return [0]
+    def actions(self, state):
+        return ["inc", "dec"]
+    def next_state(self, state, action):
+        return state + 1 if action == "inc" else state - 1
+    def within_boundary(self, state):
+        return abs(state) <= 3
+    def properties(self):
+        return [
+            stateright.Property.always("bounded", lambda model, s: abs(s) <= 3),
+            stateright.Property.always("nonnegative", lambda model, s: s >= 0),
+            stateright.Property.sometimes("reaches 3", lambda model, s: s == 3),
+        ]
+"#;
+
+    #[test]
+    fn checks_python_model() {
+        run(&format!(
+            "{}{}",
+            COUNTER,
+            r#"
+checker = stateright.checker(Counter()).spawn_bfs().join()
+assert checker.is_done()
+assert checker.unique_state_count() == 7, checker.unique_state_count()
+assert checker.discovery("bounded") is None
+assert checker.discovery("reaches 3").actions == ["inc", "inc", "inc"]
+assert checker.discovery("reaches 3").states == [0, 1, 2, 3]
+assert checker.discovery("nonnegative").actions == ["dec"]
+assert [name for name, _ in checker.discoveries()] == ["nonnegative", "reaches 3"]
+try:
+    checker.assert_properties()
+    raise RuntimeError("expected a failure")
+except AssertionError as e:
+    assert 'Unexpected "nonnegative" counterexample' in str(e), str(e)
+"#
+        ))
+        .unwrap();
+    }
+
+    #[test]
+    fn honors_targets_and_disabled_actions() {
+        run(&format!(
+            "{}{}",
+            COUNTER,
+            r#"
+class OnlyUp(Counter):
+    def next_state(self, state, action):
+        return state + 1 if action == "inc" else None
+
+checker = stateright.checker(OnlyUp()).target_max_depth(3).spawn_dfs().join()
+assert checker.unique_state_count() == 3, checker.unique_state_count()
+assert checker.discovery("nonnegative") is None
+"#
+        ))
+        .unwrap();
+    }
+
+    #[test]
+    fn raises_model_exceptions_from_join() {
+        let err = run(&format!(
+            "{}{}",
+            COUNTER,
+            r#"
+class Broken(Counter):
+    def actions(self, state):
+        if state == 2:
+            raise ValueError("broken at 2")
+        return ["inc"]
+
+stateright.checker(Broken()).spawn_bfs().join()
+"#
+        ))
+        .unwrap_err();
+        assert!(err.to_string().contains("broken at 2"), "{}", err);
+    }
+}
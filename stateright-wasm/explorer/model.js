@@ -0,0 +1,18 @@
+// The model explored by `index.html`. Replace this file to share a different model. See the
+// `stateright-wasm` crate documentation for the shape of `definition` and `options`.
+
+export const definition = {
+    initStates: () => [{ x: 0, y: 0 }],
+    actions: () => ["IncreaseX", "IncreaseY"],
+    nextState: ({ x, y }, action) =>
+        action === "IncreaseX" ? { x: (x + 1) % 8, y } : { x, y: (y + 1) % 8 },
+    properties: [
+        {
+            name: "solvable",
+            expectation: "sometimes",
+            condition: ({ x, y }) => (2 * x + 3 * y) % 8 === 7,
+        },
+    ],
+};
+
+export const options = {};
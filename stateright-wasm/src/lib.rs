@@ -0,0 +1,381 @@
+//! WebAssembly bindings for [Stateright](stateright), so that models defined in JavaScript can be
+//! checked in a browser, for instance to power interactive documentation.
+//!
+//! Checking runs on the calling thread via [`stateright::IncrementalChecker`], a bounded number
+//! of states per call to `step`, so a page can poll progress between animation frames:
+//!
+//! ```js
+//! import init, { Checker } from "./pkg/stateright_wasm.js";
+//!
+//! await init();
+//! const checker = new Checker({
+//!   initStates: () => [0],
+//!   actions: (state) => ["inc", "dec"],
+//!   nextState: (state, action) => (action === "inc" ? state + 1 : state - 1),
+//!   withinBoundary: (state) => Math.abs(state) <= 3,
+//!   properties: [
+//!     { name: "bounded", expectation: "always", condition: (state) => Math.abs(state) <= 3 },
+//!     { name: "reaches 3", expectation: "sometimes", condition: (state) => state === 3 },
+//!   ],
+//! }, { targetMaxDepth: 10 });
+//! function tick() {
+//!   const done = checker.step(1000);
+//!   console.log(checker.progress()); // { done, stateCount, uniqueStateCount, maxDepth, discoveries }
+//!   if (done) console.log(checker.discovery("reaches 3").actions); // ["inc", "inc", "inc"]
+//!   else requestAnimationFrame(tick);
+//! }
+//! tick();
+//! ```
+//!
+//! States and actions must be JSON-serializable, and the checker identifies them by their
+//! `JSON.stringify` output. `nextState` returns `null` or `undefined` to indicate that an action
+//! is disabled, and `withinBoundary` and `properties` are optional. Build with
+//! [wasm-pack](https://rustwasm.github.io/wasm-pack/), for example `wasm-pack build --target web`.
+//!
+//! [`Explorer`] similarly runs the Stateright Explorer entirely client-side. `explorer/index.html`
+//! is a static page that loads the model exported by the adjacent `explorer/model.js`, so a model
+//! can be shared by publishing that directory alongside the `pkg` directory built by wasm-pack.
+
+use js_sys::{Array, Function, Object, Reflect, JSON};
+use std::cell::RefCell;
+use std::fmt::{self, Debug, Formatter};
+use std::num::NonZeroUsize;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use stateright::{
+    Checker as _, CheckerBuilder, Expectation, IncrementalChecker, LocalExplorer, Model, Path,
+    Property,
+};
+
+/// A state or action, represented by its JSON serialization.
+#[derive(Clone, Eq, Hash, PartialEq)]
+pub struct Json(String);
+
+impl Json {
+    fn parse(&self) -> JsValue {
+        JSON::parse(&self.0).unwrap_or(JsValue::UNDEFINED)
+    }
+}
+
+impl Debug for Json {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A [`Model`] defined by the functions of a JavaScript object.
+///
+/// Exceptions thrown by those functions cannot unwind through the checker, so the first one is
+/// retained and all states are treated as outside the boundary, which promptly ends checking.
+/// [`Checker::step`] then throws the exception.
+pub struct JsModel {
+    definition: Object,
+    init_states: Function,
+    actions: Function,
+    next_state: Function,
+    within_boundary: Option<Function>,
+    properties: Vec<(Expectation, String, Function)>,
+    error: RefCell<Option<JsValue>>,
+}
+
+impl JsModel {
+    fn new(definition: Object) -> Result<Self, JsValue> {
+        let mut properties = Vec::new();
+        let definitions = get(&definition, "properties")?;
+        if !definitions.is_undefined() {
+            for property in Array::from(&definitions).iter() {
+                let name = get(&property, "name")?
+                    .as_string()
+                    .ok_or_else(|| JsError::new("Property names must be strings."))?;
+                let expectation = match get(&property, "expectation")?.as_string().as_deref() {
+                    Some("always") => Expectation::Always,
+                    Some("eventually") => Expectation::Eventually,
+                    Some("sometimes") => Expectation::Sometimes,
+                    _ => {
+                        return Err(JsError::new(&format!(
+                            "Property \"{}\" must have an expectation of \"always\", \
+                             \"eventually\", or \"sometimes\".",
+                            name
+                        ))
+                        .into())
+                    }
+                };
+                let condition = function(&property, "condition")?;
+                properties.push((expectation, name, condition));
+            }
+        }
+        Ok(JsModel {
+            init_states: function(&definition, "initStates")?,
+            actions: function(&definition, "actions")?,
+            next_state: function(&definition, "nextState")?,
+            within_boundary: match get(&definition, "withinBoundary")? {
+                f if f.is_undefined() => None,
+                f => Some(f.dyn_into().map_err(|_| not_a_function("withinBoundary"))?),
+            },
+            definition,
+            properties,
+            error: RefCell::new(None),
+        })
+    }
+
+    /// Retains the first error, returning `None` in its place.
+    fn check<T>(&self, result: Result<T, JsValue>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.error.borrow_mut().get_or_insert(err);
+                None
+            }
+        }
+    }
+
+    fn value(&self, value: JsValue) -> Option<Json> {
+        let json = JSON::stringify(&value).ok().and_then(|s| s.as_string());
+        let json = json.ok_or_else(|| {
+            let message = format!("Not JSON-serializable: {:?}", value);
+            JsValue::from(JsError::new(&message))
+        });
+        self.check(json).map(Json)
+    }
+
+    fn values(&self, values: Result<JsValue, JsValue>) -> Vec<Json> {
+        let Some(values) = self.check(values) else {
+            return Vec::new();
+        };
+        Array::from(&values)
+            .iter()
+            .filter_map(|v| self.value(v))
+            .collect()
+    }
+
+    fn holds(&self, property: usize, state: &Json) -> bool {
+        let (_, _, condition) = &self.properties[property];
+        let result = condition.call1(&self.definition, &state.parse());
+        self.check(result).is_some_and(|r| r.is_truthy())
+    }
+}
+
+impl Model for JsModel {
+    type State = Json;
+    type Action = Json;
+
+    fn init_states(&self) -> Vec<Self::State> {
+        self.values(self.init_states.call0(&self.definition))
+    }
+
+    fn actions(&self, state: &Self::State, actions: &mut Vec<Self::Action>) {
+        actions.extend(self.values(self.actions.call1(&self.definition, &state.parse())));
+    }
+
+    fn next_state(&self, last_state: &Self::State, action: Self::Action) -> Option<Self::State> {
+        let result = self
+            .next_state
+            .call2(&self.definition, &last_state.parse(), &action.parse());
+        let next_state = self.check(result)?;
+        if next_state.is_null() || next_state.is_undefined() {
+            return None;
+        }
+        self.value(next_state)
+    }
+
+    fn properties(&self) -> Vec<Property<Self>> {
+        self.properties
+            .iter()
+            .enumerate()
+            .map(|(i, (expectation, name, _))| {
+                let condition = move |model: &JsModel, state: &Json| model.holds(i, state);
+                match expectation {
+                    Expectation::Eventually => Property::eventually(name.clone(), condition),
+                    Expectation::Sometimes => Property::sometimes(name.clone(), condition),
+                    _ => Property::always(name.clone(), condition),
+                }
+            })
+            .collect()
+    }
+
+    fn within_boundary(&self, state: &Self::State) -> bool {
+        if self.error.borrow().is_some() {
+            return false;
+        }
+        let Some(within_boundary) = &self.within_boundary else {
+            return true;
+        };
+        let result = within_boundary.call1(&self.definition, &state.parse());
+        self.check(result).is_some_and(|r| r.is_truthy())
+    }
+}
+
+/// A breadth-first search model checker for a model defined in JavaScript.
+#[wasm_bindgen]
+pub struct Checker {
+    checker: IncrementalChecker<JsModel>,
+}
+
+#[wasm_bindgen]
+impl Checker {
+    /// Starts checking a model. `definition` provides `initStates()`, `actions(state)`,
+    /// `nextState(state, action)`, and optionally `withinBoundary(state)` and `properties` (an
+    /// array of `{name, expectation, condition}`). `options` optionally provides
+    /// `targetStateCount` and `targetMaxDepth`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(definition: Object, options: Option<Object>) -> Result<Checker, JsValue> {
+        let checker = builder(definition, options)?.incremental_bfs();
+        if let Some(err) = checker.model().error.take() {
+            return Err(err);
+        }
+        Ok(Checker { checker })
+    }
+
+    /// Checks up to `maxCount` more states, then returns whether checking is done. Throws the
+    /// first exception thrown by the model, if any.
+    pub fn step(&self, max_count: usize) -> Result<bool, JsValue> {
+        let is_done = self.checker.step(max_count);
+        match self.checker.model().error.take() {
+            Some(err) => Err(err),
+            None => Ok(is_done),
+        }
+    }
+
+    /// Returns `{done, stateCount, uniqueStateCount, maxDepth, discoveries}`, where
+    /// `discoveries` lists the names of properties with an example or counterexample.
+    pub fn progress(&self) -> Object {
+        let mut discoveries: Vec<_> = self.checker.discoveries().into_keys().collect();
+        discoveries.sort_unstable();
+        object(&[
+            ("done", self.checker.is_done().into()),
+            ("stateCount", self.checker.state_count().into()),
+            ("uniqueStateCount", self.checker.unique_state_count().into()),
+            ("maxDepth", self.checker.max_depth().into()),
+            (
+                "discoveries",
+                discoveries
+                    .into_iter()
+                    .map(|name| JsValue::from(&*name))
+                    .collect::<Array>()
+                    .into(),
+            ),
+        ])
+    }
+
+    /// Returns the example or counterexample for a property as `{name, classification, actions,
+    /// states, fingerprintPath}`, or `undefined` if none has been discovered.
+    pub fn discovery(&self, name: &str) -> Option<Object> {
+        let (name, path) = self.checker.discoveries().remove_entry(name)?;
+        Some(self.path(&name, path))
+    }
+}
+
+impl Checker {
+    fn path(&self, name: &str, path: Path<Json, Json>) -> Object {
+        let fingerprint_path = path.encode();
+        let states = Array::new();
+        let actions = Array::new();
+        for (state, action) in path.into_vec() {
+            states.push(&state.parse());
+            if let Some(action) = action {
+                actions.push(&action.parse());
+            }
+        }
+        let classification = self.checker.discovery_classification(name).to_string();
+        object(&[
+            ("name", name.into()),
+            ("classification", classification.into()),
+            ("actions", actions.into()),
+            ("states", states.into()),
+            ("fingerprintPath", fingerprint_path.into()),
+        ])
+    }
+}
+
+/// The Stateright Explorer for a model defined in JavaScript, running without a server.
+#[wasm_bindgen]
+pub struct Explorer {
+    explorer: LocalExplorer<JsModel>,
+}
+
+#[wasm_bindgen]
+impl Explorer {
+    /// Starts exploring a model. Accepts the same arguments as [`Checker::new`].
+    #[wasm_bindgen(constructor)]
+    pub fn new(definition: Object, options: Option<Object>) -> Result<Explorer, JsValue> {
+        let explorer = builder(definition, options)?.explorer().local();
+        if let Some(err) = explorer.checker().model().error.take() {
+            return Err(err);
+        }
+        Ok(Explorer { explorer })
+    }
+
+    /// Answers a request from the Explorer UI with `{status, contentType, body}`, where `body`
+    /// is a `Uint8Array`. Throws the first exception thrown by the model, if any.
+    pub fn handle(&self, method: &str, url: &str) -> Result<Object, JsValue> {
+        let response = self.explorer.handle(method, url);
+        if let Some(err) = self.explorer.checker().model().error.take() {
+            return Err(err);
+        }
+        Ok(object(&[
+            ("status", response.status.into()),
+            ("contentType", response.content_type.into()),
+            ("body", response.body.into()),
+        ]))
+    }
+
+    /// Checks up to `maxCount` more states once the UI has asked to run to completion, then
+    /// returns whether more remain. Throws the first exception thrown by the model, if any.
+    pub fn step(&self, max_count: usize) -> Result<bool, JsValue> {
+        let is_running = self.explorer.step(max_count);
+        match self.explorer.checker().model().error.take() {
+            Some(err) => Err(err),
+            None => Ok(is_running),
+        }
+    }
+}
+
+fn builder(
+    definition: Object,
+    options: Option<Object>,
+) -> Result<CheckerBuilder<JsModel>, JsValue> {
+    let mut builder = JsModel::new(definition)?.checker();
+    if let Some(options) = options {
+        if let Some(count) = option(&options, "targetStateCount")? {
+            builder = builder.target_state_count(count.get());
+        }
+        if let Some(depth) = option(&options, "targetMaxDepth")? {
+            builder = builder.target_max_depth(depth.get());
+        }
+    }
+    Ok(builder)
+}
+
+fn get(target: &JsValue, key: &str) -> Result<JsValue, JsValue> {
+    Reflect::get(target, &key.into())
+}
+
+fn function(target: &JsValue, key: &str) -> Result<Function, JsValue> {
+    get(target, key)?
+        .dyn_into()
+        .map_err(|_| not_a_function(key))
+}
+
+fn not_a_function(key: &str) -> JsValue {
+    JsError::new(&format!("Expected {} to be a function.", key)).into()
+}
+
+fn option(options: &Object, key: &str) -> Result<Option<NonZeroUsize>, JsValue> {
+    let value = get(options, key)?;
+    if value.is_undefined() {
+        return Ok(None);
+    }
+    match value.as_f64() {
+        Some(n) if n >= 1.0 && n.fract() == 0.0 => Ok(NonZeroUsize::new(n as usize)),
+        _ => Err(JsError::new(&format!("Expected {} to be a positive integer.", key)).into()),
+    }
+}
+
+fn object(fields: &[(&str, JsValue)]) -> Object {
+    let object = Object::new();
+    for (key, value) in fields {
+        Reflect::set(&object, &(*key).into(), value).unwrap();
+    }
+    object
+}
@@ -1,5 +1,5 @@
 /// Represents the checker status. Reloads periodically until checking completes.
-function Status({done, state_count, unique_state_count, max_depth, model, properties, recent_path}) {
+function Status({done, state_count, unique_state_count, max_depth, model, properties, recent_path, predicates, breakpoints, read_only, cluster, heatmap}) {
     let status = this;
 
     status.stateCount = state_count.toLocaleString();
@@ -13,7 +13,21 @@ function Status({done, state_count, unique_state_count, max_depth, model, proper
             : recent_path.substring(0, 99 - 3) + '...';
     }
     status.properties = properties.map((p) => { return getProperty(p, done) });
+    status.counterexamples = status.properties
+        .filter(p => p.discoveryPath && p.expectation != 'Sometimes')
+        .map(p => ({
+            name: p.name,
+            path: p.discoveryPath,
+            label: `${p.expectation} ${p.name} (depth ${p.discoveryPath.split('/').length})`,
+        }));
     status.recentPath = recent_path;
+    status.predicates = (predicates || []).map((name, index) => ({name, index}));
+    status.breakpoints = breakpoints || [];
+    status.readOnly = !!read_only;
+    status.cluster = !!cluster;
+    status.heatmap = heatmap
+        ? {maxStateVisits: heatmap.max_state_visits, maxTransitionVisits: heatmap.max_transition_visits}
+        : null;
 }
 /// Placeholder status.
 Status.LOADING = new Status({
@@ -24,6 +38,8 @@ Status.LOADING = new Status({
     model: 'loading...',
     properties: [],
     recent_path: 'loading...',
+    predicates: [],
+    breakpoints: [],
 });
 
 function getProperty(p, done) {
@@ -39,6 +55,9 @@ function getProperty(p, done) {
                     case 'Always':     return '⚠️ Counterexample found: ';
                     case 'Sometimes':  return '✅ Example found: ';
                     case 'Eventually': return '⚠️ Counterexample found: ';
+                    case 'Within':     return '⚠️ Counterexample found: ';
+                    case 'LeadsTo':    return '⚠️ Counterexample found: ';
+                    case 'Terminal':   return '⚠️ Counterexample found: ';
                     default:
                         throw new Error(`Invalid expectation ${expectation}.`);
                 }
@@ -48,6 +67,9 @@ function getProperty(p, done) {
                     case 'Always':     return '✅ Safety holds: ';
                     case 'Sometimes':  return '⚠️ Example not found: ';
                     case 'Eventually': return '✅ Liveness holds: ';
+                    case 'Within':     return '✅ Response holds: ';
+                    case 'LeadsTo':    return '✅ Response holds: ';
+                    case 'Terminal':   return '✅ Postcondition holds: ';
                     default:
                         throw new Error(`Invalid expectation ${expectation}.`);
                 }
@@ -56,9 +78,12 @@ function getProperty(p, done) {
     };
 }
 
-function getPropertyForState(p, path) {
+/// `holds` indicates whether the property's condition is satisfied by the state, if known, and
+/// `clauses` lists the conjuncts of a `Property::all` property as `{name, holds}`.
+function getPropertyForState(p, path, holds, clauses) {
     let expectation = p[0];
     let discoveryPath = p[2];
+    let exampleFound = expectation == 'Sometimes' && !!discoveryPath;
     if (discoveryPath) {
         const dp = `/${discoveryPath}`
         console.log(dp, path)
@@ -81,6 +106,9 @@ function getPropertyForState(p, path) {
                     case 'Always': return [ '⚠️',' Counterexample found: ' ];
                     case 'Sometimes':  return [ '✅', ' Example found: ' ];
                     case 'Eventually': return [ '⚠️', ' Counterexample found: ' ];
+                    case 'Within':     return [ '⚠️', ' Counterexample found: ' ];
+                    case 'LeadsTo':    return [ '⚠️', ' Counterexample found: ' ];
+                    case 'Terminal':   return [ '⚠️', ' Counterexample found: ' ];
                     default:
                         throw new Error(`Invalid expectation ${expectation}.`);
                 }
@@ -90,40 +118,289 @@ function getPropertyForState(p, path) {
                 case 'Always':     return [ '✅', ' Safety holds: ' ];
                 case 'Sometimes':  return [ '⚠️', ' Example not found: ' ];
                 case 'Eventually': return [ '✅', ' Liveness holds: ' ];
+                case 'Within':     return [ '✅', ' Response holds: ' ];
+                case 'LeadsTo':    return [ '✅', ' Response holds: ' ];
+                case 'Terminal':   return [ '✅', ' Postcondition holds: ' ];
                 default:
                     throw new Error(`Invalid expectation ${expectation}.`);
             }
         }
     })()
 
+    let conditionSummary = (() => {
+        if (holds === undefined) { return ''; }
+        let inState = holds ? '✔ condition holds here' : '✘ condition does not hold here';
+        if (expectation != 'Sometimes') { return inState; }
+        return inState + (exampleFound ? ', example found' : ', no example found yet');
+    })();
+
     return {
         expectation,
         name: p[1],
         discoveryPath,
         summary,
         icon,
+        holds,
+        conditionSummary,
+        clauses: clauses || [],
     };
 }
 
+/// Computes a structured diff between the pretty-printed `Debug` representations of two states.
+/// Struct fields and map entries are matched by name and compared recursively, sequences of equal
+/// length are compared element by element, and otherwise (e.g. for sets) elements are reported as
+/// added or removed. Changes are grouped into hunks, each labeled with the path to the changed
+/// field (such as an actor state or the network), so changed actor states and added/removed
+/// envelopes stand out.
+function diffStates(before, after) {
+    let changes = [];
+    diffStateChildren([], parseStateTree(before), parseStateTree(after), '', changes);
+    let hunks = [];
+    for (let {path, kind, nodes} of changes) {
+        let context = path.join(' › ');
+        let hunk = hunks[hunks.length - 1];
+        if (!hunk || hunk.context !== context) {
+            hunk = {context, lines: []};
+            hunks.push(hunk);
+        }
+        let kinds = kind === 'changed' ? ['removed', 'added'] : [kind];
+        nodes.forEach((node, i) => {
+            let lines = stateNodeLines(node);
+            let indent = lines[0].length - lines[0].trimStart().length;
+            for (let line of lines) { hunk.lines.push({kind: kinds[i], text: line.slice(indent)}); }
+        });
+    }
+    return hunks;
+}
+
+/// Diffs the children of a node from `parseStateTree`, appending `{path, kind, nodes}` changes,
+/// where `kind` is `added`, `removed`, or `changed` (for which `nodes` is the before/after pair).
+/// `opener` is the bracket that opened the parent node.
+function diffStateChildren(path, before, after, opener, changes) {
+    let keysOf = (nodes) => nodes.map(stateNodeKey);
+    let [beforeKeys, afterKeys] = [keysOf(before), keysOf(after)];
+    let isKeyed = (keys) => keys.every(k => k !== null) && new Set(keys).size == keys.length;
+    if (isKeyed(beforeKeys) && isKeyed(afterKeys) && (before.length || after.length)) {
+        let afterByKey = new Map(after.map((node, i) => [afterKeys[i], node]));
+        before.forEach((node, i) => {
+            let other = afterByKey.get(beforeKeys[i]);
+            if (other === undefined) {
+                changes.push({path, kind: 'removed', nodes: [node]});
+            } else {
+                diffStateNodes(path.concat([beforeKeys[i]]), node, other, changes);
+            }
+        });
+        let beforeKeySet = new Set(beforeKeys);
+        after.forEach((node, i) => {
+            if (!beforeKeySet.has(afterKeys[i])) { changes.push({path, kind: 'added', nodes: [node]}); }
+        });
+    } else if (opener !== '{' && before.length == after.length) {
+        before.forEach((node, i) => {
+            let label = before.length == 1 ? [] : [`[${i}]`];
+            diffStateNodes(path.concat(label), node, after[i], changes);
+        });
+    } else {
+        // Unordered (or resized) collections are compared as multisets of elements.
+        let counts = new Map();
+        for (let node of after) {
+            let text = stateNodeText(node);
+            counts.set(text, (counts.get(text) || 0) + 1);
+        }
+        let removed = [];
+        for (let node of before) {
+            let text = stateNodeText(node);
+            if (counts.get(text)) {
+                counts.set(text, counts.get(text) - 1);
+            } else {
+                removed.push(node);
+            }
+        }
+        for (let node of removed) { changes.push({path, kind: 'removed', nodes: [node]}); }
+        for (let node of after) {
+            let text = stateNodeText(node);
+            if (counts.get(text)) {
+                counts.set(text, counts.get(text) - 1);
+                changes.push({path, kind: 'added', nodes: [node]});
+            }
+        }
+    }
+}
+
+/// Diffs two nodes from `parseStateTree`, descending into them if they share a type.
+function diffStateNodes(path, before, after, changes) {
+    if (stateNodeText(before) === stateNodeText(after)) { return; }
+    let opener = before.line.trimEnd().slice(-1);
+    if (before.children.length && after.children.length && before.line.trim() === after.line.trim()) {
+        let head = before.line.trim().slice(0, -1).trim();
+        let key = stateNodeKey(before);
+        if (key !== null) { head = head.slice(key.length + 1).trim(); }
+        diffStateChildren(head ? path.concat([head]) : path, before.children, after.children, opener,
+                          changes);
+    } else {
+        changes.push({path, kind: 'changed', nodes: [before, after]});
+    }
+}
+
+/// Returns the field name or map key labeling a node from `parseStateTree`, or `null` if none.
+function stateNodeKey(node) {
+    let match = /^([\w.]+(?:\([^()]*\))?|"(?:[^"\\]|\\.)*"): /.exec(node.line.trimStart());
+    return match && match[1];
+}
+
+/// Returns the lines spanned by a node from `parseStateTree`.
+function stateNodeLines(node) {
+    let lines = [node.line];
+    for (let child of node.children) { lines.push(...stateNodeLines(child)); }
+    if (node.closer) { lines.push(node.closer); }
+    return lines;
+}
+
+/// Returns the text of a node from `parseStateTree` with indentation and trailing commas removed,
+/// for comparing nodes.
+function stateNodeText(node) {
+    return stateNodeLines(node).map(line => line.trim().replace(/,$/, '')).join('\n');
+}
+
+/// Records every state fetched during the session (keyed by fingerprint) along with the
+/// transitions between them, which allows graphing the neighborhood of a state including its known
+/// predecessors.
+const StateGraph = {
+    nodes: {}, // fingerprint -> first step found with that fingerprint
+    edges: {}, // `${src} ${dst}` -> {src, dst, action}
+    version: ko.observable(0),
+    addSteps(prevStep, nextSteps) {
+        for (let nextStep of nextSteps) {
+            if (nextStep.isIgnored) { continue; }
+            StateGraph.nodes[nextStep.fingerprint] = StateGraph.nodes[nextStep.fingerprint] || nextStep;
+            if (prevStep !== Step.PRE_INIT) {
+                let key = `${prevStep.fingerprint} ${nextStep.fingerprint}`;
+                StateGraph.edges[key] = StateGraph.edges[key] || {
+                    src: prevStep.fingerprint,
+                    dst: nextStep.fingerprint,
+                    action: nextStep.action,
+                };
+            }
+        }
+        StateGraph.version(StateGraph.version() + 1);
+    },
+};
+
+/// Renders the neighborhood of the selected state as SVG: known predecessors to the left and
+/// successors to the right, plus the successors of any nodes that have been expanded.
+function renderStateGraph(selected, expanded) {
+    const nodeW = 180, nodeH = 30, colW = 280, rowH = 50;
+    const edges = Object.values(StateGraph.edges);
+    let layers = new Map([[selected, 0]]);
+    for (let {src, dst} of edges) {
+        if (dst == selected && !layers.has(src)) { layers.set(src, -1); }
+    }
+    let pending = [selected].concat([...layers.keys()].filter(fp => expanded.has(fp) && fp != selected));
+    while (pending.length > 0) {
+        let src = pending.shift();
+        for (let edge of edges) {
+            if (edge.src != src || layers.has(edge.dst)) { continue; }
+            layers.set(edge.dst, layers.get(src) + 1);
+            if (expanded.has(edge.dst)) { pending.push(edge.dst); }
+        }
+    }
+
+    let minLayer = Math.min(...layers.values());
+    let rowsPerLayer = {};
+    let positions = {};
+    for (let [fp, layer] of layers) {
+        let row = rowsPerLayer[layer] = (rowsPerLayer[layer] || 0) + 1;
+        positions[fp] = {x: (layer - minLayer) * colW, y: (row - 1) * rowH};
+    }
+    let width = (Math.max(...layers.values()) - minLayer) * colW + nodeW;
+    let height = Math.max(...Object.values(rowsPerLayer)) * rowH + nodeH; // room for back edges
+
+    const escape = (text) => String(text).replace(/[&<>'"]/g, (c) => `&#${c.charCodeAt(0)};`);
+    const truncate = (text, len) => text.length <= len ? text : text.substring(0, len - 3) + '...';
+    let svg = `<svg version='1.1' width='${width + 20}' height='${height + 20}' `
+        + `viewbox='-10 -10 ${width + 20} ${height + 20}' xmlns='http://www.w3.org/2000/svg'>`
+        + `<defs><marker class='graph-arrow' id='graph-arrow' markerWidth='12' markerHeight='10' `
+        + `refX='12' refY='5' orient='auto'><polygon points='0 0, 12 5, 0 10' /></marker></defs>`;
+    let labels = '';
+    for (let {src, dst, action} of edges) {
+        let from = positions[src], to = positions[dst];
+        if (!from || !to) { continue; }
+        let d;
+        if (to.x > from.x) {
+            d = `M ${from.x + nodeW} ${from.y + nodeH / 2} L ${to.x} ${to.y + nodeH / 2}`;
+        } else {
+            // Back edges and self-loops curve below the nodes.
+            d = `M ${from.x + nodeW / 2} ${from.y + nodeH} `
+                + `C ${from.x + nodeW / 2} ${from.y + nodeH + rowH / 2}, `
+                + `${to.x + nodeW / 2} ${to.y + nodeH + rowH / 2}, ${to.x + nodeW / 2} ${to.y + nodeH}`;
+        }
+        svg += `<path d='${d}' marker-end='url(#graph-arrow)' class='graph-edge' />`;
+        let labelX = (from.x + nodeW + to.x) / 2, labelY = (from.y + to.y + nodeH) / 2 - 4;
+        if (to.x <= from.x) { labelX = (from.x + to.x + nodeW) / 2; labelY = Math.max(from.y, to.y) + nodeH + rowH / 2; }
+        labels += `<text x='${labelX}' y='${labelY}' class='graph-edge-label'>`
+            + `<title>${escape(action)}</title>${escape(truncate(action, 30))}</text>`;
+    }
+    for (let fp of layers.keys()) {
+        let {x, y} = positions[fp];
+        let step = StateGraph.nodes[fp];
+        let state = step ? step.state.replace(/\s+/g, ' ') : fp;
+        let classes = 'graph-node' + (fp == selected ? ' is-selected' : '') + (expanded.has(fp) ? ' is-expanded' : '');
+        svg += `<g class='${classes}' data-fingerprint='${fp}'>`
+            + `<title>${escape(step ? step.state : fp)}</title>`
+            + `<rect x='${x}' y='${y}' width='${nodeW}' height='${nodeH}' rx='5' />`
+            + `<text x='${x + 5}' y='${y + nodeH / 2 + 4}'>${escape(truncate(state, 24))}</text></g>`;
+    }
+    return svg + labels + '</svg>';
+}
+
+/// Classifies an action label as formatted by `ActorModel`, also extracting the actor IDs that it
+/// mentions. Labels from other models are classified as `other`.
+function classifyAction(action) {
+    let kind = (() => {
+        if (action.startsWith('Drop(')) { return 'drop'; }
+        if (action.startsWith('Timeout(')) { return 'timeout'; }
+        if (action.startsWith('Crash(')) { return 'crash'; }
+        if (action.includes(' → ')) { return 'deliver'; }
+        return 'other';
+    })();
+    let actorIds = [...new Set([...action.matchAll(/Id\((\d+)\)/g)].map(m => m[1]))];
+    return {kind, actorIds};
+}
 
 /// Represents a model step. Only loads next steps on demand.
-function Step({action, outcome, state, fingerprint, properties, prevStep, svg}) {
+function Step({action, outcome, state, elided, fingerprint, properties, conditions, clauses, breakpoints, prevStep, svg, visits}) {
     let step = this;
 
     step.action = action || `Init ${i}`;
+    let {kind, actorIds} = classifyAction(step.action);
+    step.actionKind = kind;
+    step.actorIds = actorIds;
     step.outcome = outcome;
     step.state = state;
+    step.elided = elided || 0;
+    step.fullState = ko.observable(null);
     step.svg = svg;
     step.fingerprint = fingerprint;
+    step.breakpoints = breakpoints || [];
     step.prevStep = prevStep;
+    /// Visit counts as `{state, transition}` if the Explorer has a heatmap attached.
+    step.visits = visits || null;
 
     step.path = prevStep ? prevStep.path + '/' + fingerprint : '';
 
-    step.properties = properties.map((p) => { return getPropertyForState(p, step.path) });
+    step.properties = properties.map((p, i) => {
+        let propertyClauses = (clauses || [])
+            .filter(([propertyName]) => propertyName == p[1])
+            .map(([, name, holds]) => ({name, holds}));
+        return getPropertyForState(
+            p, step.path, conditions ? conditions[i] : undefined, propertyClauses);
+    });
     step.icons = step.properties.map((p) => { return p.icon }).join(' ')
 
     step.pathSteps = () => (prevStep ? prevStep.pathSteps() : []).concat([step]);
     step.nextSteps = ko.observableArray();
+    /// Classes of successors that the backend omitted by sampling, as `{actionClass, count}`.
+    step.omittedSuccessors = ko.observableArray();
+    step.isExpanded = false;
     step.computeOffsetTo = (dstStep) => {
         let offset = 0;
         for (let cursor = step; cursor; cursor = cursor.prevStep) {
@@ -141,20 +418,29 @@ function Step({action, outcome, state, fingerprint, properties, prevStep, svg})
         if (cached) { return cached; }
 
         console.log('Fetching next steps.', {path: step.path});
-        Step._NEXT_STEPS[step.path] = fetch(`/.states${step.path}`)
+        let query = step.isExpanded ? '?successor_limit=0' : '';
+        Step._NEXT_STEPS[step.path] = explorerFetch(`/.states${step.path}${query}`)
             .then(r => r.json())
             .then((nextSteps, err) => {
                 if (err) {
                     console.log(err);
                 }
                 console.log('Response received.', {path: step.path, nextSteps});
-                return nextSteps.map((nextStep, i) => new Step({
+                step.omittedSuccessors(nextSteps
+                    .filter(nextStep => nextStep.omitted)
+                    .map(nextStep => ({actionClass: nextStep.action, count: nextStep.omitted})));
+                return nextSteps.filter(nextStep => !nextStep.omitted).map((nextStep, i) => new Step({
                     action: nextStep.action || `Init ${i}`,
                     outcome: nextStep.outcome,
                     state: nextStep.state,
+                    elided: nextStep.elided,
                     svg: nextStep.svg,
                     fingerprint: nextStep.fingerprint,
                     properties: nextStep.properties,
+                    conditions: nextStep.conditions,
+                    clauses: nextStep.clauses,
+                    breakpoints: nextStep.breakpoints,
+                    visits: nextStep.visits,
                     prevStep: step,
                 }));
             })
@@ -163,11 +449,107 @@ function Step({action, outcome, state, fingerprint, properties, prevStep, svg})
                 Step._NEXT_STEPS[step.path] = undefined;
             });
         let nextSteps = await Step._NEXT_STEPS[step.path];
+        if (nextSteps) { StateGraph.addSteps(step, nextSteps); }
         step.nextSteps(nextSteps);
         return nextSteps;
     };
+    /// Loads every successor, including those omitted by sampling.
+    step.expandSuccessors = async () => {
+        step.isExpanded = true;
+        Step._NEXT_STEPS[step.path] = undefined;
+        return await step.fetchNextSteps();
+    };
+    /// Loads the state without eliding elements of large collections.
+    step.loadFullState = async () => {
+        let response = await explorerFetch(
+            `/.states${prevStep.path}?collection_limit=0&successor_limit=0`);
+        let siblings = await response.json();
+        let sibling = siblings.find(s => s.fingerprint == step.fingerprint);
+        if (sibling) { step.fullState(sibling.state); }
+    };
     step.isIgnored = 'undefined' === typeof step.state;
+    step.diffFromPrevStep = () => {
+        if (!prevStep || prevStep === Step.PRE_INIT || step.isIgnored) { return null; }
+        if (step._diff === undefined) { step._diff = diffStates(prevStep.state, step.state); }
+        return step._diff;
+    };
+}
+/// Extracts the actor states from a state formatted by `ActorModel`, as `[{id, text}]`, or `null`
+/// if the state is not formatted that way.
+function parseActorStates(text) {
+    let match = /^ACTOR STATES:\n([\s\S]*?)(?:\n\n|$)/.exec(text || '');
+    if (!match) { return null; }
+    let actors = [];
+    for (let line of match[1].split('\n')) {
+        let id = /^  (Id\([^)]*\))/.exec(line);
+        if (id) {
+            actors.push({id: id[1], lines: [line]});
+        } else if (actors.length > 0) {
+            actors[actors.length - 1].lines.push(line);
+        }
+    }
+    return actors.map(({id, lines}) => ({id, text: lines.join('\n')}));
+}
+
+/// Reduces a state formatted by `ActorModel` to its actor states, omitting the network, timers,
+/// and history. If `prevState` is specified, actors whose states are unchanged are also omitted.
+/// Other states are returned as is.
+function filterActorStates(state, prevState) {
+    let actors = parseActorStates(state);
+    if (!actors) { return state; }
+    let hiddenCount = 0;
+    if (prevState !== undefined) {
+        let prevActors = new Map((parseActorStates(prevState) || []).map(a => [a.id, a.text]));
+        let changed = actors.filter(a => prevActors.get(a.id) !== a.text);
+        hiddenCount = actors.length - changed.length;
+        actors = changed;
+    }
+    let lines = ['ACTOR STATES:'].concat(actors.map(a => a.text));
+    if (hiddenCount > 0) {
+        lines.push(`  (${hiddenCount} unchanged ${hiddenCount == 1 ? 'actor' : 'actors'} hidden)`);
+    }
+    return lines.join('\n');
+}
+
+/// Maps a visit count onto a hue from blue (never visited) to red (visited `max` times). Counts are
+/// scaled logarithmically so that a few hot loops do not wash out the rest of the state space.
+function heatColor(count, max) {
+    let ratio = max > 0 ? Math.log1p(count) / Math.log1p(max) : 0;
+    return `hsla(${Math.round(240 * (1 - ratio))}, 80%, 45%, 0.6)`;
+}
+
+/// Sections of a state spanning more lines than this are initially collapsed.
+const STATE_SECTION_COLLAPSE_LINES = 40;
+
+/// Parses a state pretty-printed via `Debug` into nested sections, one per struct, collection, or
+/// tuple spanning multiple lines, so that large sections can be collapsed. Each node is
+/// `{line, children, closer, isOpen}`, where `closer` is the line ending a section.
+function parseStateTree(text) {
+    let root = {children: []};
+    let stack = [{node: root, indent: -1, lineCount: 0}];
+    for (let line of (text || '').split('\n')) {
+        let trimmed = line.trimStart();
+        let indent = line.length - trimmed.length;
+        let top = stack[stack.length - 1];
+        if (stack.length > 1 && indent == top.indent && /^[\]})]/.test(trimmed)) {
+            top.node.closer = line;
+            top.node.isOpen = top.lineCount <= STATE_SECTION_COLLAPSE_LINES;
+            stack.pop();
+            stack[stack.length - 1].lineCount += top.lineCount + 1;
+            continue;
+        }
+        let node = {line, children: [], closer: null, isOpen: true};
+        top.node.children.push(node);
+        top.lineCount += 1;
+        if (/[\[{(]$/.test(trimmed)) {
+            stack.push({node, indent, lineCount: 0});
+        }
+    }
+    // Sections left unterminated (e.g. by custom formatting) remain expanded.
+    for (let {node} of stack.slice(1)) { node.closer = ''; }
+    return root.children;
 }
+
 /// Special step that points to the init steps.
 Step.PRE_INIT = new Step({
     action: 'Pre-init',
@@ -185,38 +567,476 @@ function App() {
     app.farthestStep = ko.observable(Step.PRE_INIT);
     app.isCompact = ko.observable(false);
     app.isCompleteState = ko.observable(false);
+    app.isDiff = ko.observable(false);
+    app.isActorsOnly = ko.observable(false);
+    app.isChangedActorsOnly = ko.observable(false);
+    app.hasActorStates = ko.pureComputed(() => parseActorStates(app.selectedStep().state) !== null);
+    /// Hides everything but the actor states if requested, optionally also hiding actors whose
+    /// states match the previous step.
+    app.filterState = (state, prevState) => {
+        if (!app.isActorsOnly()) { return state; }
+        return filterActorStates(state, app.isChangedActorsOnly() ? prevState : undefined);
+    };
+    app.displayedState = ko.pureComputed(() => {
+        let step = app.selectedStep();
+        let prevState = step.prevStep && step.prevStep.state;
+        let state = app.filterState(step.fullState() || step.state, prevState);
+        return app.isCompleteState() ? state : step.outcome || state;
+    });
+    app.displayedDiff = ko.pureComputed(() => {
+        let step = app.selectedStep();
+        let prevStep = step.prevStep;
+        if (!app.isActorsOnly() || !prevStep || prevStep === Step.PRE_INIT || step.isIgnored) {
+            return step.diffFromPrevStep();
+        }
+        return diffStates(filterActorStates(prevStep.state), filterActorStates(step.state));
+    });
+    app.displayedStateTree = ko.pureComputed(() => parseStateTree(app.displayedState()));
+    app.showSequenceDiagram = ko.observable(true);
+    app.showStateGraph = ko.observable(false);
+    app.expandedGraphNodes = ko.observable(new Set());
+    app.selectedStep.subscribe(() => app.expandedGraphNodes(new Set()));
+    app.stateGraphSvg = ko.pureComputed(() => {
+        StateGraph.version(); // re-render as states are discovered
+        let selected = app.selectedStep();
+        if (!app.showStateGraph() || selected === Step.PRE_INIT) { return ''; }
+        return renderStateGraph(selected.fingerprint, app.expandedGraphNodes());
+    });
+    /// Clicking a node expands it to show its successors. Double clicking navigates to it.
+    app.onStateGraphClick = async (data, ev) => {
+        let node = ev.target.closest('[data-fingerprint]');
+        let step = node && StateGraph.nodes[node.dataset.fingerprint];
+        if (!step) { return; }
+        let expanded = new Set(app.expandedGraphNodes());
+        expanded.add(step.fingerprint);
+        app.expandedGraphNodes(expanded);
+        await step.fetchNextSteps();
+    };
+    app.onStateGraphDblClick = (data, ev) => {
+        let node = ev.target.closest('[data-fingerprint]');
+        let step = node && StateGraph.nodes[node.dataset.fingerprint];
+        if (step) { window.location = `#/steps${step.path}`; }
+    };
     app.showPerStateProperties = ko.observable(false);
+    app.showHeatmap = ko.observable(true);
+    /// Colors a step by how often the checker visited it, preferring the count of the transition
+    /// leading to it. Rarely visited steps are cool, while frequently visited ones are hot.
+    app.heatStyle = (step) => {
+        let heatmap = app.status().heatmap;
+        if (!app.showHeatmap() || !heatmap || !step.visits) { return {}; }
+        let useTransition = step.visits.transition !== undefined;
+        let count = useTransition ? step.visits.transition : step.visits.state;
+        let max = useTransition ? heatmap.maxTransitionVisits : heatmap.maxStateVisits;
+        return {backgroundColor: heatColor(count, max)};
+    };
+    app.heatTitle = (step) => {
+        if (!step.visits) { return ''; }
+        let title = ` (state visited ${step.visits.state.toLocaleString()} times`;
+        if (step.visits.transition !== undefined) {
+            title += `, transition ${step.visits.transition.toLocaleString()} times`;
+        }
+        return title + ')';
+    };
     app.showCurrentStateProperties = ko.observable(false);
     app.isSameStateAsSelected = (step) => step.state == app.selectedStep().state;
+    app.stepBackward = () => {
+        let offset = app.farthestStep().computeOffsetTo(app.selectedStep());
+        offset = Math.min(offset + 1, app.farthestStep().pathSteps().length - 1);
+        window.location = app.farthestStep().computeUriWithOffset(offset);
+    };
+    app.stepForward = () => {
+        let offset = app.farthestStep().computeOffsetTo(app.selectedStep());
+        offset = Math.max(offset - 1, 0);
+        window.location = app.farthestStep().computeUriWithOffset(offset);
+    };
     app.onKeyDown = (data, ev) => {
         switch (ev.keyCode) {
             case 38: // up arrow
-            case 75: { // k (vim style)
-                let offset = app.farthestStep().computeOffsetTo(app.selectedStep());
-                offset = Math.min(offset + 1, app.farthestStep().pathSteps().length - 1);
-                window.location = app.farthestStep().computeUriWithOffset(offset);
+            case 75: // k (vim style)
+                app.stepBackward();
                 break;
-            }
             case 40: // down arrow
-            case 74: { // k (vim style)
-                let offset = app.farthestStep().computeOffsetTo(app.selectedStep());
-                offset = Math.max(offset - 1, 0);
-                window.location = app.farthestStep().computeUriWithOffset(offset);
+            case 74: // j (vim style)
+                app.stepForward();
                 break;
-            }
             default:
                 return true;
         }
     };
+
+    app.actionKindFilter = ko.observable('');
+    app.actorFilter = ko.observable('');
+    app.actionTextFilter = ko.observable('');
+    app.availableActionKinds = ko.pureComputed(() => [...new Set(
+        (app.selectedStep().nextSteps() || []).map(s => s.actionKind))].sort());
+    app.availableActorIds = ko.pureComputed(() => [...new Set(
+        (app.selectedStep().nextSteps() || []).flatMap(s => s.actorIds))]
+        .sort((a, b) => a - b));
+    app.filteredNextSteps = ko.pureComputed(() => {
+        let kind = app.actionKindFilter();
+        let actorId = app.actorFilter();
+        let text = app.actionTextFilter().toLowerCase();
+        return (app.selectedStep().nextSteps() || []).filter(s =>
+            (!kind || s.actionKind == kind)
+            && (!actorId || s.actorIds.includes(actorId))
+            && (!text || s.action.toLowerCase().includes(text)));
+    });
+
+    /// Position of the selected step within the loaded path, for the playback slider.
+    app.playbackIndex = ko.pureComputed({
+        read: () => app.farthestStep().pathSteps().indexOf(app.selectedStep()),
+        write: (index) => {
+            let lastIndex = app.farthestStep().pathSteps().length - 1;
+            window.location = app.farthestStep().computeUriWithOffset(lastIndex - parseInt(index));
+        },
+    });
+    app.playbackSpeeds = [
+        {label: '0.5×', ms: 2000},
+        {label: '1×', ms: 1000},
+        {label: '2×', ms: 500},
+        {label: '4×', ms: 250},
+    ];
+    app.playbackSpeed = ko.observable(1000);
+    app.isPlaying = ko.observable(false);
+    /// Advances through the loaded path until reaching its end or a step at which a breakpoint
+    /// fires.
+    app.togglePlayback = () => {
+        if (app.isPlaying()) {
+            app.isPlaying(false);
+            return;
+        }
+        let pathSteps = app.farthestStep().pathSteps();
+        if (app.selectedStep() === pathSteps[pathSteps.length - 1]) {
+            // Restart from the beginning rather than doing nothing.
+            app.playbackIndex(0);
+        }
+        app.isPlaying(true);
+        let tick = () => {
+            if (!app.isPlaying()) { return; }
+            let pathSteps = app.farthestStep().pathSteps();
+            let next = pathSteps[pathSteps.indexOf(app.selectedStep()) + 1];
+            if (!next) {
+                app.isPlaying(false);
+                return;
+            }
+            app.stepForward();
+            if (next.breakpoints.length > 0) {
+                app.isPlaying(false);
+                return;
+            }
+            setTimeout(tick, app.playbackSpeed());
+        };
+        setTimeout(tick, app.playbackSpeed());
+    };
     app.status = ko.observable(Status.LOADING);
+    app.searchPredicate = ko.observable();
+    app.searchResult = ko.observable(null);
+    app.search = async () => {
+        let predicate = app.status().predicates[app.searchPredicate()];
+        if (!predicate) { return; }
+        app.searchResult({summary: `Searching for "${predicate.name}"...`, matches: []});
+        console.log('Searching.', {predicate});
+        let response = await explorerFetch(`/.search/${predicate.index}?limit=20`);
+        if (!response.ok) {
+            app.searchResult({summary: await response.text(), matches: []});
+            return;
+        }
+        let {matches, explored_state_count, complete} = await response.json();
+        let summary = `${matches.length.toLocaleString()} match(es) after exploring `
+            + `${explored_state_count.toLocaleString()} states`
+            + (complete ? '.' : ' (more may exist).');
+        app.searchResult({
+            summary,
+            matches: matches.map((path, i) => ({
+                path,
+                label: `Match ${i + 1} (depth ${path.split('/').length})`,
+            })),
+        });
+    };
+
+    app.fingerprintQuery = ko.observable('');
+    app.fingerprintMessage = ko.observable(null);
+    /// Selects the state with the requested fingerprint via the shortest path to it.
+    app.goToFingerprint = async () => {
+        let fingerprint = app.fingerprintQuery().trim();
+        if (!fingerprint) { return; }
+        app.fingerprintMessage(`Searching for ${fingerprint}...`);
+        let response = await explorerFetch(`/.fingerprint/${encodeURIComponent(fingerprint)}`);
+        if (!response.ok) {
+            app.fingerprintMessage(await response.text());
+            return;
+        }
+        let {path} = await response.json();
+        app.fingerprintMessage(null);
+        window.location = `#/steps/${path}`;
+    };
+    app.copyFingerprint = () => {
+        navigator.clipboard.writeText(app.selectedStep().fingerprint);
+    };
+
+    app.breakpointMessage = ko.observable(null);
+    app.selectedStep.subscribe(() => app.breakpointMessage(null));
+    /// Explores from the farthest step until a breakpoint fires, then selects the resulting step.
+    app.continueToBreakpoint = async () => {
+        let path = app.farthestStep().path;
+        console.log('Continuing to breakpoint.', {path});
+        let response = await explorerFetch(`/.breakpoint${path}`);
+        if (!response.ok) {
+            app.breakpointMessage(await response.text());
+            return;
+        }
+        let {breakpoint, path: breakpointPath} = await response.json();
+        window.location = `#/steps/${breakpointPath}`;
+        console.log('Breakpoint fired.', {breakpoint, breakpointPath});
+    };
+
+    app.walkSteps = ko.observable(20);
+    app.walkSeed = ko.observable('');
+    app.walkMessage = ko.observable(null);
+    /// Takes random steps from the selected step, then selects the resulting step. The seed used
+    /// is displayed so that the walk can be reproduced.
+    app.takeRandomWalk = async () => {
+        let path = app.selectedStep().path;
+        let query = `?steps=${encodeURIComponent(app.walkSteps())}`;
+        if (app.walkSeed().trim()) { query += `&seed=${encodeURIComponent(app.walkSeed().trim())}`; }
+        console.log('Taking a random walk.', {path, query});
+        let response = await explorerFetch(`/.walk${path}${query}`);
+        if (!response.ok) {
+            app.walkMessage(await response.text());
+            return;
+        }
+        let {seed, steps, path: walkPath} = await response.json();
+        window.location = `#/steps/${walkPath}`;
+        app.walkMessage(`Took ${steps} random ${steps == 1 ? 'step' : 'steps'} with seed ${seed}.`);
+    };
+
+    /// Loads the first counterexample found unless the user has already selected a path.
+    app.hasAutoLoadedCounterexample = false;
+    app.status.subscribe((status) => {
+        let counterexample = status.counterexamples[0];
+        if (!counterexample || app.hasAutoLoadedCounterexample) { return; }
+        app.hasAutoLoadedCounterexample = true;
+        if (window.location.hash.replace(/^#\/steps\/?/, '')) { return; }
+        console.log('Loading counterexample.', counterexample);
+        window.location = `#/steps/${counterexample.path}`;
+    });
+
+    /// Paths recently loaded in this session, most recent first.
+    app.openedPaths = ko.observableArray();
+    app.farthestStep.subscribe((step) => {
+        if (!step.path) { return; }
+        let path = step.path.substring(1);
+        app.openedPaths.remove(p => p.path == path);
+        app.openedPaths.unshift({
+            path,
+            label: `${step.action} (depth ${step.pathSteps().length - 1})`,
+        });
+        app.openedPaths.splice(SESSION_MAX_OPENED_PATHS);
+    });
+    app.notes = ko.observable('');
+    /// Notes attached to individual states, keyed by fingerprint. Each is `{path, label, text}`,
+    /// where `path` leads to the state in which the note was written.
+    app.stateNotes = ko.observable({});
+    app.selectedStateNote = ko.pureComputed({
+        read: () => {
+            let note = app.stateNotes()[app.selectedStep().fingerprint];
+            return note ? note.text : '';
+        },
+        write: (text) => {
+            let step = app.selectedStep();
+            if (step === Step.PRE_INIT || step.isIgnored) { return; }
+            let stateNotes = Object.assign({}, app.stateNotes());
+            if (text.trim()) {
+                stateNotes[step.fingerprint] = {
+                    path: step.path.substring(1),
+                    label: `${step.action} (depth ${step.pathSteps().length - 1})`,
+                    text,
+                };
+            } else {
+                delete stateNotes[step.fingerprint];
+            }
+            app.stateNotes(stateNotes);
+        },
+    });
+    app.annotatedStates = ko.pureComputed(() => Object.entries(app.stateNotes())
+        .map(([fingerprint, note]) => Object.assign({fingerprint}, note)));
+    app.stateNoteFor = (step) => {
+        let note = app.stateNotes()[step.fingerprint];
+        return note ? note.text : null;
+    };
+    app.sessionMessage = ko.observable(null);
+    app.saveSession = () => {
+        let session = {
+            version: SESSION_VERSION,
+            model: app.status().model,
+            selectedPath: app.selectedStep().path.substring(1),
+            openedPaths: app.openedPaths(),
+            notes: app.notes(),
+            stateNotes: app.stateNotes(),
+            breakpoints: app.status().breakpoints,
+        };
+        let blob = new Blob([JSON.stringify(session, null, 2)], {type: 'application/json'});
+        let link = document.createElement('a');
+        link.href = URL.createObjectURL(blob);
+        link.download = 'stateright-session.json';
+        link.click();
+        URL.revokeObjectURL(link.href);
+    };
+    app.loadSession = async (data, ev) => {
+        let file = ev.target.files[0];
+        if (!file) { return; }
+        ev.target.value = ''; // allows loading the same file again
+        let session;
+        try {
+            session = JSON.parse(await file.text());
+        } catch (err) {
+            app.sessionMessage(`Unable to parse ${file.name}: ${err}`);
+            return;
+        }
+        if (session.version != SESSION_VERSION) {
+            app.sessionMessage(`Unsupported session version: ${session.version}`);
+            return;
+        }
+        console.log('Loading session.', session);
+        let warnings = [];
+        if (session.model != app.status().model) {
+            warnings.push(`Session was saved for model ${session.model}.`);
+        }
+        let missing = (session.breakpoints || [])
+            .filter(b => !app.status().breakpoints.includes(b));
+        if (missing.length > 0) {
+            warnings.push(`Breakpoints not registered by this Explorer: ${missing.join(', ')}.`);
+        }
+        app.openedPaths(session.openedPaths || []);
+        app.notes(session.notes || '');
+        app.stateNotes(session.stateNotes || {});
+        app.sessionMessage(warnings.length > 0 ? warnings.join(' ') : `Loaded ${file.name}.`);
+        window.location = `#/steps/${session.selectedPath || ''}`;
+    };
+
+    /// Aligns the steps of two paths by depth so that their states can be compared.
+    app.comparison = ko.observable(null);
+    app.compareWith = async (otherPath) => {
+        let path = app.farthestStep().path.substring(1);
+        if (!path || !otherPath) { return; }
+        console.log('Comparing paths.', {path, otherPath});
+        let [left, right] = await Promise.all([path, otherPath].map(async p => {
+            let response = await explorerFetch(`/.path/${p}`);
+            return response.ok ? response.json() : null;
+        }));
+        if (!left || !right) {
+            app.comparison({error: 'Unable to load both paths.', left: path, right: otherPath, rows: []});
+            return;
+        }
+        let rows = [];
+        for (let i = 0; i < Math.max(left.steps.length, right.steps.length); ++i) {
+            let l = left.steps[i];
+            let r = right.steps[i];
+            let isSame = l && r && l.fingerprint == r.fingerprint;
+            rows.push({
+                depth: i,
+                left: l,
+                right: r,
+                isSame,
+                diff: l && r && !isSame ? diffStates(l.state, r.state) : null,
+            });
+        }
+        let divergence = rows.findIndex(row => !row.isSame);
+        app.comparison({
+            error: null,
+            left: left.path,
+            right: right.path,
+            summary: divergence < 0
+                ? 'The paths are identical.'
+                : `The paths diverge at depth ${divergence}.`,
+            rows,
+        });
+    };
+
+    app.isLive = ko.observable(false);
+    app.recentDiscoveries = ko.observableArray();
+
+    // Live cluster, polled while one is attached.
+    app.clusterActors = ko.observableArray();
+    app.clusterError = ko.observable(null);
+    let isPollingCluster = false;
+    app.status.subscribe(status => {
+        if (status.cluster && !isPollingCluster) {
+            isPollingCluster = true;
+            refreshCluster();
+        }
+    });
+    async function refreshCluster() {
+        try {
+            let response = await explorerFetch('/.cluster');
+            if (!response.ok) { throw new Error(await response.text()); }
+            app.clusterActors(await response.json());
+            app.clusterError(null);
+        } catch (err) {
+            console.log('Unable to refresh cluster.', err);
+            app.clusterError(err.message);
+        }
+        setTimeout(refreshCluster, CLUSTER_REFRESH_INTERVAL_MS);
+    }
+
+    // Models served alongside this one, if several share the server.
+    app.models = ko.observableArray();
+    app.currentModel = ko.observable(EXPLORER_BASE.substring(1));
+    app.currentModel.subscribe(name => {
+        if (name && `/${name}` != EXPLORER_BASE) { window.location = `/${name}/`; }
+    });
+    if (EXPLORER_BASE && !window.explorerBackend) {
+        loadModels();
+    }
+    async function loadModels() {
+        try {
+            let response = await window.fetch('/.models');
+            if (response.ok) { app.models(await response.json()); }
+        } catch (err) {
+            console.log('Unable to list models.', err);
+        }
+    }
 
     window.onhashchange = prepareView;
     window.onhashchange();
-    refreshStatus();
+    if (window.EventSource && !window.explorerBackend) {
+        subscribeToEvents();
+    } else {
+        refreshStatus();
+    }
 
+    /// Receives progress as it happens, falling back to polling if the stream fails.
+    function subscribeToEvents() {
+        console.log('Subscribing to events.');
+        let events = new EventSource(explorerUrl('/.events'));
+        let isDone = false;
+        events.addEventListener('open', () => app.isLive(true));
+        events.addEventListener('status', (ev) => {
+            let json = JSON.parse(ev.data);
+            app.status(new Status(json));
+            if (json.done) {
+                isDone = true;
+                events.close();
+                app.isLive(false);
+            }
+        });
+        events.addEventListener('discovery', (ev) => {
+            let discovery = JSON.parse(ev.data);
+            console.log('Discovery.', discovery);
+            app.recentDiscoveries.unshift(getProperty(
+                [discovery.expectation, discovery.name, discovery.path], false));
+        });
+        events.addEventListener('error', () => {
+            if (isDone) { return; }
+            console.log('Event stream failed. Polling instead.');
+            events.close();
+            app.isLive(false);
+            setTimeout(refreshStatus, 5000);
+        });
+    }
     async function refreshStatus() {
         console.log('Refreshing status.');
-        let response = await fetch('/.status');
+        let response = await explorerFetch('/.status');
         let json = await response.json();
         console.log({json});
         app.status(new Status(json));
@@ -250,7 +1070,13 @@ function App() {
                         app.farthestStep(step);
                         break;
                     }
-                    step = nextSteps.find(step => step.fingerprint == nextFingerprint);
+                    let nextStep = nextSteps.find(step => step.fingerprint == nextFingerprint);
+                    if (!nextStep && step.omittedSuccessors().length > 0) {
+                        // The path continues via a successor omitted by sampling.
+                        nextSteps = await step.expandSuccessors();
+                        nextStep = nextSteps.find(step => step.fingerprint == nextFingerprint);
+                    }
+                    step = nextStep;
                 }
                 break;
             default: throw new Error(`Invalid view: '${view}'`);
@@ -272,9 +1098,41 @@ function App() {
     }
 }
 
+/// Issues a request to the Explorer backend. A page that embeds the Explorer without a server
+/// can assign `window.explorerBackend` a function with the same signature as `fetch` to handle
+/// requests itself, in which case progress is polled rather than streamed.
+function explorerFetch(url, options) {
+    if (window.explorerBackend) { return window.explorerBackend(url, options); }
+    return window.fetch(explorerUrl(url), options);
+}
+
+/// The path under which this Explorer is served, which is nonempty when several models share a
+/// server (for instance `/2pc` for the Explorer at `/2pc/`).
+const EXPLORER_BASE = window.location.pathname.replace(/\/[^/]*$/, '');
+
+/// Resolves a backend URL such as `/.status` relative to the path under which this Explorer is
+/// served.
+function explorerUrl(url) {
+    return EXPLORER_BASE + url;
+}
+
+/// How often the states of an attached live cluster are refreshed.
+const CLUSTER_REFRESH_INTERVAL_MS = 1000;
+
+/// Format version of saved sessions, incremented for incompatible changes.
+const SESSION_VERSION = 1;
+
+/// Bounds the paths remembered by a session.
+const SESSION_MAX_OPENED_PATHS = 20;
+
+/// Copies a link to the current view, which includes the selected path.
+async function copyPermalink() {
+    await navigator.clipboard.writeText(window.location.href);
+}
+
 async function runToCompletion() {
     console.log("continuing checker");
-    let response = await fetch('/.runtocompletion', {method:'POST'});
+    let response = await explorerFetch('/.runtocompletion', {method:'POST'});
     console.log(response);
 }
 